@@ -0,0 +1,43 @@
+//! An offscreen OpenGL context for benchmarks that draw.
+
+use std::os::raw::c_void;
+use gl;
+use glutin::{Context, ContextBuilder, GlRequest, Api, PossiblyCurrent};
+use glutin::dpi::PhysicalSize;
+use glutin::event_loop::EventLoop;
+use trdl::Window;
+
+pub struct HeadlessWindow {
+    context: Context<PossiblyCurrent>,
+    _event_loop: EventLoop<()>
+}
+
+impl Window for HeadlessWindow {
+    // the context is made current once and stays current
+    fn set_context(&self) {}
+
+    fn load_fn(&self, addr: &str) -> *const c_void {
+        self.context.get_proc_address(addr) as *const c_void
+    }
+}
+
+impl HeadlessWindow {
+    /// An OpenGL 4 context of the given size, None if the driver can't make one.
+    pub fn new(width: u32, height: u32) -> Option<Self> {
+        let event_loop = EventLoop::new();
+        let context = ContextBuilder::new()
+            .with_gl(GlRequest::Specific(Api::OpenGl, (4, 0)))
+            .build_headless(&event_loop, PhysicalSize::new(width, height))
+            .ok()?;
+        let context = unsafe { context.make_current() }.ok()?;
+        gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+        Some(HeadlessWindow { context, _event_loop: event_loop })
+    }
+
+    /// Wait for the GPU to finish everything submitted so far, so frame times include it.
+    pub fn finish(&self) {
+        unsafe {
+            gl::Finish();
+        }
+    }
+}
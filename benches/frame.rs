@@ -0,0 +1,89 @@
+//! Benchmarks of getting paths to the GPU: adding paths, and rebuilding and drawing a whole frame
+//! when every path changes, as in a particle effect drawn with paths. These need an OpenGL 4
+//! context and are skipped without one.
+
+#[macro_use]
+extern crate criterion;
+extern crate gl;
+extern crate glutin;
+extern crate trdl;
+
+mod common;
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use trdl::{Drawing, Path};
+use trdl::particles::{Emitter, EmitterConfig};
+use common::HeadlessWindow;
+
+const SIZE: u32 = 512;
+
+fn bench_add_path(c: &mut Criterion, window: &HeadlessWindow) {
+    let mut group = c.benchmark_group("add_path");
+    for &n in &[100, 1_000, 10_000] {
+        let paths: Vec<Path> = (0..n).map(|i| {
+            let center = ((i % 100) as f32 * 5f32, (i / 100) as f32 * 5f32);
+            Path::ellipse(center, 2f32, 2f32, 0f32).set_fill_color(1f32, 0f32, 0f32)
+        }).collect();
+        let mut drawing = Drawing::new(window, SIZE, SIZE, 1f32, 1f32, 1f32).unwrap();
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &paths, |b, paths| {
+            b.iter(|| {
+                drawing.clear_paths();
+                for path in paths {
+                    drawing.add_path(path.clone()).unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+// Every frame the particles move, so every path is replaced and the buffers are rebuilt.
+fn bench_particle_frame(c: &mut Criterion, window: &HeadlessWindow) {
+    let mut group = c.benchmark_group("particle_frame");
+    group.sample_size(20);
+    for &n in &[100, 1_000, 5_000] {
+        let config = EmitterConfig {
+            position: (SIZE as f32 / 2f32, SIZE as f32 / 2f32),
+            lifetime: (1000f32, 1000f32),
+            max_particles: n,
+            ..EmitterConfig::default()
+        };
+        let mut emitter = Emitter::new(config, 1);
+        emitter.set_emitting(false);
+        emitter.burst(n);
+        let mut drawing = Drawing::new(window, SIZE, SIZE, 0f32, 0f32, 0f32).unwrap();
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| {
+                emitter.update(1f32 / 60f32);
+                drawing.clear_paths();
+                for particle in emitter.particles() {
+                    let radius = particle.size / 2f32;
+                    let color = particle.color;
+                    drawing.add_path(Path::ellipse(particle.position, radius, radius, 0f32)
+                        .set_fill_color(color.red, color.green, color.blue)
+                        .set_opacity(color.alpha)).unwrap();
+                }
+                drawing.draw();
+                window.finish();
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_frames(c: &mut Criterion) {
+    let window = match HeadlessWindow::new(SIZE, SIZE) {
+        Some(window) => window,
+        None => {
+            eprintln!("no OpenGL 4 context, skipping the frame benchmarks");
+            return;
+        }
+    };
+    bench_add_path(c, &window);
+    bench_particle_frame(c, &window);
+}
+
+criterion_group!(benches, bench_frames);
+criterion_main!(benches);
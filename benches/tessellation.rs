@@ -0,0 +1,53 @@
+//! Benchmarks of polygon triangulation, which every closed path goes through when it is added.
+
+#[macro_use]
+extern crate criterion;
+extern crate trdl;
+
+use std::f32::consts::PI;
+use criterion::{BenchmarkId, Criterion, Throughput};
+use trdl::Mesh;
+
+// A regular polygon, every vertex is an ear.
+fn convex_polygon(n: usize) -> Vec<(f32, f32)> {
+    (0..n).map(|i| {
+        let angle = i as f32 * 2f32 * PI / n as f32;
+        (100f32 * angle.cos(), 100f32 * angle.sin())
+    }).collect()
+}
+
+// A star with every other vertex reflex, the ear tests have to check them all.
+fn star_polygon(n: usize) -> Vec<(f32, f32)> {
+    (0..n).map(|i| {
+        let angle = i as f32 * 2f32 * PI / n as f32;
+        let radius = if i % 2 == 0 { 100f32 } else { 80f32 };
+        (radius * angle.cos(), radius * angle.sin())
+    }).collect()
+}
+
+fn bench_triangulate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("triangulate/convex");
+    for &n in &[10, 100, 1_000, 10_000, 100_000] {
+        let polygon = convex_polygon(n);
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &polygon, |b, polygon| {
+            b.iter(|| Mesh::from_polygon(polygon).unwrap())
+        });
+    }
+    group.finish();
+
+    // ear clipping is quadratic in the number of reflex vertices, so this stops sooner
+    let mut group = c.benchmark_group("triangulate/star");
+    group.sample_size(10);
+    for &n in &[10, 100, 1_000, 10_000] {
+        let polygon = star_polygon(n);
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &polygon, |b, polygon| {
+            b.iter(|| Mesh::from_polygon(polygon).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_triangulate);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+/*
+ * C bindings for TRDL, see capi/src/lib.rs. Link against libtrdl_capi (cargo build in capi/).
+ *
+ * The application creates the window and OpenGL 4 context, then:
+ *
+ *     TrdlDrawing *drawing;
+ *     if (trdl_drawing_new(800, 600, 1, 1, 1, load_gl, NULL, window, &drawing) != TRDL_OK) {
+ *         fprintf(stderr, "%s\n", trdl_last_error_message());
+ *     }
+ *     TrdlPath *path = trdl_path_ellipse(400, 300, 100, 50, 0);
+ *     trdl_path_set_fill_color(path, 0.2f, 0.4f, 0.8f);
+ *     trdl_drawing_add_path(drawing, path, NULL);  // consumes path
+ *     ...every frame: trdl_drawing_draw(drawing); then swap buffers
+ *     trdl_drawing_free(drawing);
+ *
+ * Shaders are read from the shaders directory relative to the working directory.
+ */
+
+#ifndef TRDL_H
+#define TRDL_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum TrdlStatus {
+    TRDL_OK = 0,
+    TRDL_NULL_POINTER = 1,
+    TRDL_INVALID_ARGUMENT = 2,
+    TRDL_SHADER_ERROR = 3,
+    TRDL_GEOMETRY_ERROR = 4,
+    TRDL_LIMIT_EXCEEDED = 5,
+    TRDL_PANIC = 6
+} TrdlStatus;
+
+typedef struct TrdlDrawing TrdlDrawing;
+typedef struct TrdlPath TrdlPath;
+
+typedef const void *(*TrdlLoadFn)(const char *name, void *user_data);
+typedef void (*TrdlMakeCurrentFn)(void *user_data);
+
+const char *trdl_last_error_message(void);
+
+TrdlStatus trdl_drawing_new(uint32_t width, uint32_t height, float red, float green, float blue,
+                            TrdlLoadFn load_fn, TrdlMakeCurrentFn make_current, void *user_data,
+                            TrdlDrawing **out);
+void trdl_drawing_free(TrdlDrawing *drawing);
+TrdlStatus trdl_drawing_set_size(TrdlDrawing *drawing, uint32_t width, uint32_t height);
+TrdlStatus trdl_drawing_add_path(TrdlDrawing *drawing, TrdlPath *path, uint64_t *out_id);
+TrdlStatus trdl_drawing_update_path(TrdlDrawing *drawing, uint64_t id, TrdlPath *path);
+TrdlStatus trdl_drawing_remove_path(TrdlDrawing *drawing, uint64_t id);
+TrdlStatus trdl_drawing_clear_paths(TrdlDrawing *drawing);
+TrdlStatus trdl_drawing_draw(TrdlDrawing *drawing);
+TrdlStatus trdl_drawing_draw_embedded(TrdlDrawing *drawing);
+TrdlStatus trdl_drawing_invalidate_gl_state(TrdlDrawing *drawing);
+
+TrdlPath *trdl_path_new(float x, float y);
+TrdlPath *trdl_path_rectangle(float x, float y, float width, float height, float angle);
+TrdlPath *trdl_path_ellipse(float x, float y, float x_radius, float y_radius, float angle);
+TrdlPath *trdl_path_clone(const TrdlPath *path);
+void trdl_path_free(TrdlPath *path);
+TrdlStatus trdl_path_line_to(TrdlPath *path, float x, float y);
+TrdlStatus trdl_path_curve_to(TrdlPath *path, float x1, float y1, float x2, float y2, float x, float y);
+TrdlStatus trdl_path_arc_to(TrdlPath *path, float x_radius, float y_radius, float angle, float x, float y,
+                            int32_t large_arc, int32_t positive_sweep);
+TrdlStatus trdl_path_close(TrdlPath *path);
+TrdlStatus trdl_path_set_fill_color(TrdlPath *path, float red, float green, float blue);
+TrdlStatus trdl_path_set_stroke(TrdlPath *path, float red, float green, float blue, uint32_t thickness);
+TrdlStatus trdl_path_set_opacity(TrdlPath *path, float opacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
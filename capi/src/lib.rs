@@ -0,0 +1,462 @@
+//! C bindings for TRDL, for applications in C, C++ or other languages that create their own
+//! OpenGL 4 windows and want to draw into them. The matching header is include/trdl.h.
+//!
+//! Drawings and paths are opaque handles. Functions that can fail return a `TrdlStatus`, and
+//! `trdl_last_error_message` describes the last failure on the calling thread. Panics are caught
+//! at the boundary and reported as `TrdlStatus::Panic`.
+
+extern crate trdl;
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use trdl::{Drawing, ErrorKind, Path, PathId, TrdlError, Window};
+
+/// Result of a call, 0 is success.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrdlStatus {
+    Ok = 0,
+    /// A handle or output pointer was null.
+    NullPointer = 1,
+    /// An argument was out of range, or a path id is not in the drawing.
+    InvalidArgument = 2,
+    /// The shaders could not be read, compiled or linked.
+    ShaderError = 3,
+    /// A path could not be turned into triangles.
+    GeometryError = 4,
+    /// The drawing is full or over its GPU memory budget.
+    LimitExceeded = 5,
+    /// TRDL panicked, the handles involved should not be used again.
+    Panic = 6
+}
+
+/// Looks up an OpenGL function by name, e.g. `glfwGetProcAddress` wrapped to take user data.
+pub type TrdlLoadFn = extern "C" fn(name: *const c_char, user_data: *mut c_void) -> *const c_void;
+
+/// Makes the drawing's OpenGL context current, called before drawing. May be null if the
+/// context is always current.
+pub type TrdlMakeCurrentFn = Option<extern "C" fn(user_data: *mut c_void)>;
+
+/// A drawing handle.
+pub struct TrdlDrawing {
+    // the drawing borrows the window, so it is dropped first in trdl_drawing_free
+    drawing: Drawing<'static, CallbackWindow>,
+    window: *mut CallbackWindow
+}
+
+/// A path handle, built up with the trdl_path_ functions and handed to a drawing.
+pub struct TrdlPath {
+    // the Path builder methods take the path by value, it is taken out and put back
+    path: Option<Path>
+}
+
+// The window of a C application, reached through its callbacks.
+struct CallbackWindow {
+    load_fn: TrdlLoadFn,
+    make_current: TrdlMakeCurrentFn,
+    user_data: *mut c_void
+}
+
+impl Window for CallbackWindow {
+    fn set_context(&self) {
+        if let Some(make_current) = self.make_current {
+            make_current(self.user_data);
+        }
+    }
+
+    fn load_fn(&self, addr: &str) -> *const c_void {
+        match CString::new(addr) {
+            Ok(name) => (self.load_fn)(name.as_ptr(), self.user_data),
+            Err(_) => ptr::null()
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message).unwrap_or_else(|_| CString::new("invalid error message").unwrap());
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn status_of(err: &TrdlError) -> TrdlStatus {
+    match err.kind() {
+        ErrorKind::Gl | ErrorKind::Io => TrdlStatus::ShaderError,
+        ErrorKind::Geometry => TrdlStatus::GeometryError,
+        ErrorKind::Limit => TrdlStatus::LimitExceeded,
+        ErrorKind::InvalidInput => TrdlStatus::InvalidArgument
+    }
+}
+
+fn fail(status: TrdlStatus, message: &str) -> TrdlStatus {
+    set_last_error(message.to_string());
+    status
+}
+
+// Run the body of an exported function, turning errors and panics into status codes.
+fn guard<F>(body: F) -> TrdlStatus where F: FnOnce() -> Result<(), TrdlStatus> {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => TrdlStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => fail(TrdlStatus::Panic, "TRDL panicked")
+    }
+}
+
+fn report(err: TrdlError) -> TrdlStatus {
+    set_last_error(err.to_string());
+    status_of(&err)
+}
+
+unsafe fn drawing_mut<'d>(drawing: *mut TrdlDrawing) -> Result<&'d mut TrdlDrawing, TrdlStatus> {
+    drawing.as_mut().ok_or_else(|| fail(TrdlStatus::NullPointer, "drawing is null"))
+}
+
+// Replace the path in a handle with what f makes of it.
+unsafe fn edit_path<F>(path: *mut TrdlPath, f: F) -> TrdlStatus where F: FnOnce(Path) -> Path {
+    guard(|| {
+        let handle = path.as_mut().ok_or_else(|| fail(TrdlStatus::NullPointer, "path is null"))?;
+        let old = handle.path.take().ok_or_else(|| fail(TrdlStatus::Panic, "path was left empty by a panic"))?;
+        handle.path = Some(f(old));
+        Ok(())
+    })
+}
+
+fn new_path(path: Path) -> *mut TrdlPath {
+    Box::into_raw(Box::new(TrdlPath { path: Some(path) }))
+}
+
+// Take the path out of a handle and free the handle.
+unsafe fn take_path(path: *mut TrdlPath) -> Result<Path, TrdlStatus> {
+    if path.is_null() {
+        return Err(fail(TrdlStatus::NullPointer, "path is null"));
+    }
+    Box::from_raw(path).path.ok_or_else(|| fail(TrdlStatus::Panic, "path was left empty by a panic"))
+}
+
+/// The message of the last error on this thread, or null if there was none. The string stays
+/// valid until the next failing call on the thread.
+#[no_mangle]
+pub extern "C" fn trdl_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Create a drawing of width by height pixels with a background color. The context must be
+/// current, and the shaders directory must be in the working directory.
+///
+/// # Safety
+/// `out` must point to writable memory for a handle, and the callbacks must stay valid with the
+/// user data for the drawing's lifetime.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_new(width: u32, height: u32, red: f32, green: f32, blue: f32,
+                                          load_fn: Option<TrdlLoadFn>, make_current: TrdlMakeCurrentFn,
+                                          user_data: *mut c_void, out: *mut *mut TrdlDrawing) -> TrdlStatus {
+    guard(|| {
+        let out = out.as_mut().ok_or_else(|| fail(TrdlStatus::NullPointer, "out is null"))?;
+        *out = ptr::null_mut();
+        let load_fn = load_fn.ok_or_else(|| fail(TrdlStatus::NullPointer, "load_fn is null"))?;
+        let window = Box::into_raw(Box::new(CallbackWindow { load_fn, make_current, user_data }));
+        match Drawing::new(&*window, width, height, red, green, blue) {
+            Ok(drawing) => {
+                *out = Box::into_raw(Box::new(TrdlDrawing { drawing, window }));
+                Ok(())
+            }
+            Err(err) => {
+                drop(Box::from_raw(window));
+                Err(report(err))
+            }
+        }
+    })
+}
+
+/// Free a drawing and its OpenGL objects, with its context current. Null is ignored.
+///
+/// # Safety
+/// `drawing` must be null or a handle from `trdl_drawing_new` that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_free(drawing: *mut TrdlDrawing) {
+    if drawing.is_null() {
+        return;
+    }
+    let handle = Box::from_raw(drawing);
+    let window = handle.window;
+    drop(handle);
+    drop(Box::from_raw(window));
+}
+
+/// Resize the drawing to the window's new size in pixels.
+///
+/// # Safety
+/// `drawing` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_set_size(drawing: *mut TrdlDrawing, width: u32, height: u32) -> TrdlStatus {
+    guard(|| {
+        drawing_mut(drawing)?.drawing.set_size(width, height);
+        Ok(())
+    })
+}
+
+/// Add a path, writing its id to out_id if that is not null. The path handle is consumed, also
+/// when adding fails.
+///
+/// # Safety
+/// `drawing` must be a live drawing handle and `path` a live path handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_add_path(drawing: *mut TrdlDrawing, path: *mut TrdlPath,
+                                               out_id: *mut u64) -> TrdlStatus {
+    guard(|| {
+        let path = take_path(path)?;
+        let id = drawing_mut(drawing)?.drawing.add_path(path).map_err(report)?;
+        if let Some(out_id) = out_id.as_mut() {
+            *out_id = id.to_raw();
+        }
+        Ok(())
+    })
+}
+
+/// Replace the path with an id. The path handle is consumed, also when this fails.
+///
+/// # Safety
+/// `drawing` must be a live drawing handle and `path` a live path handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_update_path(drawing: *mut TrdlDrawing, id: u64, path: *mut TrdlPath) -> TrdlStatus {
+    guard(|| {
+        let path = take_path(path)?;
+        match drawing_mut(drawing)?.drawing.update_path(PathId::from_raw(id), path) {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err(fail(TrdlStatus::InvalidArgument, "no path with that id")),
+            Err(err) => Err(report(err))
+        }
+    })
+}
+
+/// Remove the path with an id.
+///
+/// # Safety
+/// `drawing` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_remove_path(drawing: *mut TrdlDrawing, id: u64) -> TrdlStatus {
+    guard(|| {
+        match drawing_mut(drawing)?.drawing.remove_path(PathId::from_raw(id)) {
+            Some(_) => Ok(()),
+            None => Err(fail(TrdlStatus::InvalidArgument, "no path with that id"))
+        }
+    })
+}
+
+/// Remove every path.
+///
+/// # Safety
+/// `drawing` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_clear_paths(drawing: *mut TrdlDrawing) -> TrdlStatus {
+    guard(|| {
+        drawing_mut(drawing)?.drawing.clear_paths();
+        Ok(())
+    })
+}
+
+/// Clear the window to the background and draw the paths. The caller swaps buffers.
+///
+/// # Safety
+/// `drawing` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_draw(drawing: *mut TrdlDrawing) -> TrdlStatus {
+    guard(|| {
+        drawing_mut(drawing)?.drawing.draw();
+        Ok(())
+    })
+}
+
+/// Draw the paths over what the application already drew this frame, keeping its OpenGL state.
+///
+/// # Safety
+/// `drawing` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_draw_embedded(drawing: *mut TrdlDrawing) -> TrdlStatus {
+    guard(|| {
+        drawing_mut(drawing)?.drawing.draw_embedded();
+        Ok(())
+    })
+}
+
+/// Tell the drawing its OpenGL context was lost, its objects are recreated on the next draw.
+///
+/// # Safety
+/// `drawing` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_drawing_invalidate_gl_state(drawing: *mut TrdlDrawing) -> TrdlStatus {
+    guard(|| {
+        drawing_mut(drawing)?.drawing.invalidate_gl_state();
+        Ok(())
+    })
+}
+
+/// Start a path at a point. Free it with `trdl_path_free` unless it is added to a drawing.
+#[no_mangle]
+pub extern "C" fn trdl_path_new(x: f32, y: f32) -> *mut TrdlPath {
+    new_path(Path::new((x, y)))
+}
+
+/// A closed rectangle path centered on (x, y), rotated by angle radians.
+#[no_mangle]
+pub extern "C" fn trdl_path_rectangle(x: f32, y: f32, width: f32, height: f32, angle: f32) -> *mut TrdlPath {
+    new_path(Path::rectangle((x, y), width, height, angle))
+}
+
+/// A closed ellipse path centered on (x, y), rotated by angle radians.
+#[no_mangle]
+pub extern "C" fn trdl_path_ellipse(x: f32, y: f32, x_radius: f32, y_radius: f32, angle: f32) -> *mut TrdlPath {
+    new_path(Path::ellipse((x, y), x_radius, y_radius, angle))
+}
+
+/// A copy of a path.
+///
+/// # Safety
+/// `path` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_clone(path: *const TrdlPath) -> *mut TrdlPath {
+    match path.as_ref().and_then(|handle| handle.path.clone()) {
+        Some(path) => new_path(path),
+        None => ptr::null_mut()
+    }
+}
+
+/// Free a path that was not added to a drawing. Null is ignored.
+///
+/// # Safety
+/// `path` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_free(path: *mut TrdlPath) {
+    if !path.is_null() {
+        drop(Box::from_raw(path));
+    }
+}
+
+/// Add a straight line to (x, y).
+///
+/// # Safety
+/// `path` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_line_to(path: *mut TrdlPath, x: f32, y: f32) -> TrdlStatus {
+    edit_path(path, |path| path.line_to((x, y)))
+}
+
+/// Add a cubic Bezier curve to (x, y).
+///
+/// # Safety
+/// `path` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_curve_to(path: *mut TrdlPath, x1: f32, y1: f32, x2: f32, y2: f32,
+                                            x: f32, y: f32) -> TrdlStatus {
+    edit_path(path, |path| path.curve_to((x1, y1), (x2, y2), (x, y)))
+}
+
+/// Add an elliptical arc to (x, y), as in SVG. Flags are 0 or 1.
+///
+/// # Safety
+/// `path` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_arc_to(path: *mut TrdlPath, x_radius: f32, y_radius: f32, angle: f32,
+                                          x: f32, y: f32, large_arc: i32, positive_sweep: i32) -> TrdlStatus {
+    edit_path(path, |path| path.arc_to(x_radius, y_radius, angle, (x, y), large_arc != 0, positive_sweep != 0))
+}
+
+/// Close the path with a line back to its start.
+///
+/// # Safety
+/// `path` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_close(path: *mut TrdlPath) -> TrdlStatus {
+    edit_path(path, |path| path.close_path())
+}
+
+/// Fill the path with a color, components from 0 to 1.
+///
+/// # Safety
+/// `path` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_set_fill_color(path: *mut TrdlPath, red: f32, green: f32, blue: f32) -> TrdlStatus {
+    edit_path(path, |path| path.set_fill_color(red, green, blue))
+}
+
+/// Stroke the path with a color and a thickness in pixels.
+///
+/// # Safety
+/// `path` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_set_stroke(path: *mut TrdlPath, red: f32, green: f32, blue: f32,
+                                              thickness: u32) -> TrdlStatus {
+    edit_path(path, |path| path.set_stroke(red, green, blue, thickness))
+}
+
+/// Set the opacity of the whole path, from 0 to 1.
+///
+/// # Safety
+/// `path` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn trdl_path_set_opacity(path: *mut TrdlPath, opacity: f32) -> TrdlStatus {
+    edit_path(path, |path| path.set_opacity(opacity))
+}
+
+// Lets tests read the last error message as a Rust string.
+#[cfg(test)]
+fn last_error() -> Option<String> {
+    use std::ffi::CStr;
+    let message = trdl_last_error_message();
+    if message.is_null() {
+        None
+    } else {
+        Some(unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_building() {
+        unsafe {
+            let path = trdl_path_new(0f32, 0f32);
+            assert_eq!(trdl_path_line_to(path, 10f32, 0f32), TrdlStatus::Ok);
+            assert_eq!(trdl_path_curve_to(path, 10f32, 5f32, 5f32, 10f32, 0f32, 10f32), TrdlStatus::Ok);
+            assert_eq!(trdl_path_close(path), TrdlStatus::Ok);
+            assert_eq!(trdl_path_set_fill_color(path, 1f32, 0f32, 0f32), TrdlStatus::Ok);
+            let copy = trdl_path_clone(path);
+            assert_eq!((*copy).path, (*path).path);
+            let expected = Path::new((0f32, 0f32)).line_to((10f32, 0f32))
+                .curve_to((10f32, 5f32), (5f32, 10f32), (0f32, 10f32))
+                .close_path()
+                .set_fill_color(1f32, 0f32, 0f32);
+            assert_eq!(take_path(path).unwrap(), expected);
+            trdl_path_free(copy);
+        }
+    }
+
+    #[test]
+    fn test_null_handles() {
+        unsafe {
+            assert_eq!(trdl_path_line_to(ptr::null_mut(), 1f32, 1f32), TrdlStatus::NullPointer);
+            assert_eq!(last_error().unwrap(), "path is null");
+            assert_eq!(trdl_drawing_draw(ptr::null_mut()), TrdlStatus::NullPointer);
+            let path = trdl_path_rectangle(0f32, 0f32, 1f32, 1f32, 0f32);
+            assert_eq!(trdl_drawing_add_path(ptr::null_mut(), path, ptr::null_mut()), TrdlStatus::NullPointer);
+            assert_eq!(trdl_drawing_new(1, 1, 0f32, 0f32, 0f32, None, None, ptr::null_mut(), ptr::null_mut()),
+                       TrdlStatus::NullPointer);
+            trdl_path_free(ptr::null_mut());
+            trdl_drawing_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_error_status() {
+        assert_eq!(status_of(&TrdlError::TooManyPaths), TrdlStatus::LimitExceeded);
+        assert_eq!(status_of(&TrdlError::NoVisibleGeometry), TrdlStatus::GeometryError);
+        assert_eq!(status_of(&TrdlError::CompileError(String::new())), TrdlStatus::ShaderError);
+    }
+}
@@ -0,0 +1,58 @@
+//! A bar chart and a line chart built from plain paths.
+//!
+//! cargo run --example charts --features glutin
+
+extern crate trdl;
+
+use trdl::{Drawing, LoopConfig, Path};
+use trdl::adapters::GlutinWindow;
+use trdl::input::{InputEvent, Key};
+
+const MONTHLY: [f32; 12] = [3.2, 4.1, 5.0, 4.4, 6.3, 7.8, 8.1, 7.2, 6.0, 5.1, 3.9, 3.0];
+const TREND: [f32; 12] = [2.0, 2.6, 3.9, 4.8, 5.2, 6.9, 7.5, 7.7, 6.8, 5.5, 4.0, 3.1];
+
+// Axes of a chart with its lower left corner at origin.
+fn axes(origin: (f32, f32), width: f32, height: f32) -> Path {
+    Path::new((origin.0, origin.1 + height))
+        .line_to(origin)
+        .line_to((origin.0 + width, origin.1))
+        .set_stroke(0.2f32, 0.2f32, 0.2f32, 2)
+}
+
+fn main() {
+    let (mut event_loop, window) = GlutinWindow::open("charts", 900, 500).unwrap();
+    let mut drawing = Drawing::new(&window, 900, 500, 1f32, 1f32, 1f32).unwrap();
+    let (width, height, scale) = (360f32, 320f32, 35f32);
+
+    // bar chart on the left
+    let origin = (60f32, 90f32);
+    drawing.add_path(axes(origin, width, height)).unwrap();
+    let bar_width = width / MONTHLY.len() as f32;
+    for (i, &value) in MONTHLY.iter().enumerate() {
+        let center = (origin.0 + (i as f32 + 0.5f32) * bar_width, origin.1 + value * scale / 2f32);
+        let shade = value / 10f32;
+        drawing.add_path(Path::rectangle(center, 0.7f32 * bar_width, value * scale, 0f32)
+            .set_fill_color(0.2f32, 0.4f32 + 0.5f32 * shade, 0.8f32)).unwrap();
+    }
+
+    // line chart with point markers on the right
+    let origin = (480f32, 90f32);
+    drawing.add_path(axes(origin, width, height)).unwrap();
+    let step = width / (TREND.len() - 1) as f32;
+    let points: Vec<(f32, f32)> = TREND.iter().enumerate()
+        .map(|(i, &value)| (origin.0 + i as f32 * step, origin.1 + value * scale))
+        .collect();
+    let line = points[1..].iter().fold(Path::new(points[0]), |path, &p| path.line_to(p));
+    drawing.add_path(line.set_stroke(0.8f32, 0.3f32, 0.1f32, 3)).unwrap();
+    for &p in &points {
+        drawing.add_path(Path::ellipse(p, 5f32, 5f32, 0f32)
+            .set_fill_color(1f32, 1f32, 1f32)
+            .set_stroke(0.8f32, 0.3f32, 0.1f32, 2)).unwrap();
+    }
+
+    window.run(&mut event_loop, &mut drawing, LoopConfig::default(), |frame, events| {
+        if events.iter().any(|e| matches!(*e, InputEvent::KeyDown { key: Key::Escape, .. })) {
+            frame.quit();
+        }
+    });
+}
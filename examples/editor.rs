@@ -0,0 +1,73 @@
+//! A small shape editor: click a shape to select it, drag it to move it, Delete removes it and
+//! Ctrl+Z / Ctrl+Y undo and redo. Every edit goes through a `DrawingJournal`.
+//!
+//! cargo run --example editor --features glutin
+
+extern crate trdl;
+
+use trdl::{Drawing, DrawingJournal, LoopConfig, Path, PathId, SelectionStyle, Transform2D};
+use trdl::adapters::GlutinWindow;
+use trdl::input::{InputEvent, Key, MouseButton};
+
+fn main() {
+    let (mut event_loop, window) = GlutinWindow::open("editor", 800, 600).unwrap();
+    let mut drawing = Drawing::new(&window, 800, 600, 0.95f32, 0.95f32, 0.95f32).unwrap();
+    let mut journal = DrawingJournal::new();
+    for i in 0..5 {
+        let center = (150f32 + 120f32 * i as f32, 300f32);
+        let path = if i % 2 == 0 {
+            Path::rectangle(center, 80f32, 60f32, 0.2f32 * i as f32)
+        } else {
+            Path::ellipse(center, 45f32, 30f32, 0f32)
+        };
+        let hue = i as f32 / 5f32;
+        journal.add_path(&mut drawing, path.set_fill_color(hue, 0.5f32, 1f32 - hue)
+            .set_stroke(0.1f32, 0.1f32, 0.1f32, 2)).unwrap();
+    }
+    // the journal should not undo the initial shapes
+    journal.clear();
+
+    let mut selected: Option<PathId> = None;
+    // where a drag started, the shape moves there on release
+    let mut drag_start: Option<(f32, f32)> = None;
+
+    window.run(&mut event_loop, &mut drawing, LoopConfig::default(), |frame, events| {
+        let drawing = frame.drawing();
+        for event in events {
+            match *event {
+                InputEvent::MouseDown { button: MouseButton::Left, position } => {
+                    let point = drawing.window_to_drawing(position);
+                    drawing.clear_selection();
+                    selected = drawing.pick(point);
+                    if let Some(id) = selected {
+                        drawing.set_selected(id, SelectionStyle::default());
+                        drag_start = Some(point);
+                    }
+                }
+                InputEvent::MouseUp { button: MouseButton::Left, position } => {
+                    if let (Some(id), Some(start)) = (selected, drag_start.take()) {
+                        let end = drawing.window_to_drawing(position);
+                        let moved = Transform2D::translation(end.0 - start.0, end.1 - start.1);
+                        journal.transform_path(drawing, id, &moved).unwrap();
+                    }
+                }
+                InputEvent::KeyDown { key: Key::Delete, .. } | InputEvent::KeyDown { key: Key::Backspace, .. } => {
+                    if let Some(id) = selected.take() {
+                        journal.remove_path(drawing, id);
+                    }
+                }
+                InputEvent::KeyDown { key: Key::Character(c), modifiers } if modifiers.control => {
+                    match c {
+                        'z' => { journal.undo(drawing).unwrap(); }
+                        'y' => { journal.redo(drawing).unwrap(); }
+                        _ => ()
+                    }
+                }
+                _ => ()
+            }
+        }
+        if events.iter().any(|e| matches!(*e, InputEvent::KeyDown { key: Key::Escape, .. })) {
+            frame.quit();
+        }
+    });
+}
@@ -0,0 +1,62 @@
+//! Layers: a background grid, shapes and labels-like markers on three layers. Keys 1 to 3 show or
+//! hide a layer, the mouse wheel fades the shapes layer.
+//!
+//! cargo run --example layers --features glutin
+
+extern crate trdl;
+
+use trdl::{Drawing, LoopConfig, Path};
+use trdl::adapters::GlutinWindow;
+use trdl::input::{InputEvent, Key};
+
+fn main() {
+    let (mut event_loop, window) = GlutinWindow::open("layers", 800, 600).unwrap();
+    let mut drawing = Drawing::new(&window, 800, 600, 1f32, 1f32, 1f32).unwrap();
+    let layers = [drawing.add_layer(), drawing.add_layer(), drawing.add_layer()];
+
+    // the grid never changes, a cached layer is only redrawn when it does
+    drawing.set_layer_cached(layers[0], true);
+    for i in 0..=16 {
+        let x = 50f32 * i as f32;
+        drawing.add_path_to_layer(layers[0], Path::new((x, 0f32)).line_to((x, 600f32))
+            .set_stroke(0.85f32, 0.85f32, 0.85f32, 1)).unwrap();
+    }
+    for i in 0..=12 {
+        let y = 50f32 * i as f32;
+        drawing.add_path_to_layer(layers[0], Path::new((0f32, y)).line_to((800f32, y))
+            .set_stroke(0.85f32, 0.85f32, 0.85f32, 1)).unwrap();
+    }
+
+    for i in 0..6 {
+        let center = (125f32 + 110f32 * i as f32, 300f32);
+        drawing.add_path_to_layer(layers[1], Path::ellipse(center, 70f32, 70f32, 0f32)
+            .set_fill_color(0.2f32 + 0.13f32 * i as f32, 0.4f32, 0.9f32 - 0.13f32 * i as f32)).unwrap();
+        drawing.add_path_to_layer(layers[2], Path::rectangle(center, 12f32, 12f32, 0.785f32)
+            .set_fill_color(0f32, 0f32, 0f32)).unwrap();
+    }
+
+    let mut visible = [true; 3];
+    let mut opacity = 1f32;
+    window.run(&mut event_loop, &mut drawing, LoopConfig::default(), |frame, events| {
+        let drawing = frame.drawing();
+        let mut quit = false;
+        for event in events {
+            match *event {
+                InputEvent::KeyDown { key: Key::Character(c @ '1'..='3'), .. } => {
+                    let i = c as usize - '1' as usize;
+                    visible[i] = !visible[i];
+                    drawing.set_layer_visible(layers[i], visible[i]);
+                }
+                InputEvent::MouseWheel { delta, .. } => {
+                    opacity = (opacity + 0.1f32 * delta).clamp(0f32, 1f32);
+                    drawing.set_layer_opacity(layers[1], opacity);
+                }
+                InputEvent::KeyDown { key: Key::Escape, .. } => quit = true,
+                _ => ()
+            }
+        }
+        if quit {
+            frame.quit();
+        }
+    });
+}
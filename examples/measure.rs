@@ -0,0 +1,59 @@
+//! Measuring paths: dots at equal distances along a curve with tangent ticks, and a marker that
+//! travels along it at constant speed.
+//!
+//! cargo run --example measure --features glutin
+
+extern crate trdl;
+
+use trdl::{Drawing, LoopConfig, Path, Transform2D};
+use trdl::adapters::GlutinWindow;
+use trdl::input::{InputEvent, Key};
+
+const SPEED: f32 = 150f32;
+
+fn main() {
+    let (mut event_loop, window) = GlutinWindow::open("measure", 800, 600).unwrap();
+    let mut drawing = Drawing::new(&window, 800, 600, 1f32, 1f32, 1f32).unwrap();
+
+    let curve = Path::new((80f32, 300f32))
+        .curve_to((200f32, 560f32), (320f32, 40f32), (440f32, 300f32))
+        .curve_to((520f32, 470f32), (640f32, 520f32), (720f32, 200f32));
+    let table = curve.arc_lengths();
+    let length = table.total_length();
+    drawing.add_path(curve.set_stroke(0.3f32, 0.3f32, 0.3f32, 2)).unwrap();
+
+    let spacing = 40f32;
+    for i in 0..=(length / spacing) as usize {
+        let point = table.point_at_length(i as f32 * spacing);
+        let (x, y) = point.position;
+        let (nx, ny) = point.normal;
+        drawing.add_path(Path::new((x - 10f32 * nx, y - 10f32 * ny)).line_to((x + 10f32 * nx, y + 10f32 * ny))
+            .set_stroke(0.2f32, 0.5f32, 0.8f32, 2)).unwrap();
+        drawing.add_path(Path::ellipse(point.position, 4f32, 4f32, 0f32)
+            .set_fill_color(0.2f32, 0.5f32, 0.8f32)).unwrap();
+    }
+
+    let marker = drawing.add_path(arrow(Transform2D::identity())).unwrap();
+    let mut distance = 0f32;
+
+    window.run(&mut event_loop, &mut drawing, LoopConfig::default(), |frame, events| {
+        if events.iter().any(|e| matches!(*e, InputEvent::KeyDown { key: Key::Escape, .. })) {
+            frame.quit();
+            return;
+        }
+        distance = (distance + SPEED * frame.dt()) % length;
+        let point = table.point_at_length(distance);
+        let transform = Transform2D::translation(point.position.0, point.position.1)
+            * Transform2D::rotation(point.tangent.1.atan2(point.tangent.0));
+        frame.drawing().update_path(marker, arrow(transform)).unwrap();
+    });
+}
+
+// An arrow head pointing along the x axis from the origin, moved by a transform.
+fn arrow(transform: Transform2D) -> Path {
+    Path::new(transform.apply((12f32, 0f32)))
+        .line_to(transform.apply((-8f32, 8f32)))
+        .line_to(transform.apply((-8f32, -8f32)))
+        .close_path()
+        .set_fill_color(0.9f32, 0.3f32, 0.1f32)
+}
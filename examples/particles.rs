@@ -0,0 +1,69 @@
+//! A fountain of particles following the mouse, click for a burst, T toggles trails.
+//!
+//! Particles are drawn after the drawing, so this example runs its own event loop with a
+//! `FrameTimer` instead of `GlutinWindow::run`.
+//!
+//! cargo run --example particles --features glutin
+
+extern crate glutin;
+extern crate trdl;
+
+use glutin::event::{Event, WindowEvent};
+use glutin::event_loop::ControlFlow;
+use glutin::platform::run_return::EventLoopExtRunReturn;
+use trdl::{Color, Drawing, FrameTimer, LoopConfig, Path};
+use trdl::adapters::GlutinWindow;
+use trdl::input::{InputEvent, Key};
+use trdl::particles::{Emitter, EmitterConfig, ParticleRenderer, ParticleShape};
+
+fn main() {
+    let (mut event_loop, window) = GlutinWindow::open("particles", 800, 600).unwrap();
+    let mut drawing = Drawing::new(&window, 800, 600, 0.05f32, 0.05f32, 0.1f32).unwrap();
+    drawing.add_path(Path::rectangle((400f32, 20f32), 800f32, 40f32, 0f32)
+        .set_fill_color(0.2f32, 0.2f32, 0.3f32)).unwrap();
+
+    let config = EmitterConfig {
+        position: (400f32, 40f32),
+        spawn_rate: 400f32,
+        lifetime: (1.5f32, 3f32),
+        direction: (1.3f32, 1.85f32),
+        speed: (250f32, 400f32),
+        gravity: (0f32, -300f32),
+        size: (3f32, 7f32),
+        color: (Color::rgb(0.3f32, 0.6f32, 1f32), Color::rgb(0.9f32, 0.95f32, 1f32)),
+        shape: ParticleShape::Circle,
+        ..EmitterConfig::default()
+    };
+    let mut emitter = Emitter::new(config, 1);
+    let mut renderer = ParticleRenderer::new().unwrap();
+    let mut timer = FrameTimer::new(LoopConfig::default());
+
+    event_loop.run_return(|event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+        match event {
+            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => *control_flow = ControlFlow::Exit,
+            Event::WindowEvent { ref event, .. } => {
+                window.handle_event(event, &mut drawing);
+                match window.input_event(event) {
+                    Some(InputEvent::MouseMove { position }) => emitter.config_mut().position = position,
+                    Some(InputEvent::MouseDown { .. }) => emitter.burst(500),
+                    Some(InputEvent::KeyDown { key: Key::Character('t'), .. }) => match drawing.trail() {
+                        Some(_) => drawing.clear_trail(),
+                        None => drawing.set_trail(0.15f32)
+                    },
+                    Some(InputEvent::KeyDown { key: Key::Escape, .. }) => *control_flow = ControlFlow::Exit,
+                    _ => ()
+                }
+            }
+            Event::MainEventsCleared => {
+                let timing = timer.begin_frame();
+                emitter.update(timing.dt);
+                drawing.draw();
+                renderer.draw(&drawing, &emitter);
+                window.swap_buffers().unwrap();
+                timer.end_frame();
+            }
+            _ => ()
+        }
+    });
+}
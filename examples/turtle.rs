@@ -0,0 +1,79 @@
+//! Turtle graphics on the canvas API: a Koch snowflake and a square spiral.
+//!
+//! cargo run --example turtle --features glutin
+
+extern crate trdl;
+
+use trdl::{Canvas, Color, Drawing, LoopConfig};
+use trdl::adapters::GlutinWindow;
+use trdl::input::{InputEvent, Key};
+
+// A pen that moves forward and turns, tracing a canvas path as it goes.
+struct Turtle {
+    position: (f32, f32),
+    heading: f32
+}
+
+impl Turtle {
+    fn new<W: trdl::Window>(canvas: &mut Canvas<'_, '_, W>, position: (f32, f32), heading: f32) -> Self {
+        canvas.begin_path();
+        canvas.move_to(position);
+        Turtle { position, heading }
+    }
+
+    fn forward<W: trdl::Window>(&mut self, canvas: &mut Canvas<'_, '_, W>, distance: f32) {
+        let (sin, cos) = self.heading.to_radians().sin_cos();
+        self.position = (self.position.0 + distance * cos, self.position.1 + distance * sin);
+        canvas.line_to(self.position);
+    }
+
+    fn turn(&mut self, degrees: f32) {
+        self.heading += degrees;
+    }
+}
+
+fn koch<W: trdl::Window>(turtle: &mut Turtle, canvas: &mut Canvas<'_, '_, W>, length: f32, depth: u32) {
+    if depth == 0 {
+        turtle.forward(canvas, length);
+        return;
+    }
+    for &angle in &[60f32, -120f32, 60f32, 0f32] {
+        koch(turtle, canvas, length / 3f32, depth - 1);
+        turtle.turn(angle);
+    }
+}
+
+fn main() {
+    let (mut event_loop, window) = GlutinWindow::open("turtle", 800, 600).unwrap();
+    let mut drawing = Drawing::new(&window, 800, 600, 1f32, 1f32, 1f32).unwrap();
+    {
+        let mut canvas = Canvas::new(&mut drawing);
+
+        canvas.set_fill_style(Color::rgb(0.8f32, 0.9f32, 1f32));
+        canvas.set_stroke_style(Color::rgb(0.1f32, 0.3f32, 0.6f32));
+        canvas.set_line_width(2f32);
+        let mut turtle = Turtle::new(&mut canvas, (60f32, 380f32), 0f32);
+        for _ in 0..3 {
+            koch(&mut turtle, &mut canvas, 360f32, 4);
+            turtle.turn(-120f32);
+        }
+        canvas.close_path();
+        canvas.fill().unwrap();
+        canvas.stroke().unwrap();
+
+        canvas.set_stroke_style(Color::rgb(0.7f32, 0.2f32, 0.2f32));
+        canvas.set_line_width(1f32);
+        let mut turtle = Turtle::new(&mut canvas, (600f32, 300f32), 0f32);
+        for i in 1..60 {
+            turtle.forward(&mut canvas, 3f32 * i as f32);
+            turtle.turn(91f32);
+        }
+        canvas.stroke().unwrap();
+    }
+
+    window.run(&mut event_loop, &mut drawing, LoopConfig::default(), |frame, events| {
+        if events.iter().any(|e| matches!(*e, InputEvent::KeyDown { key: Key::Escape, .. })) {
+            frame.quit();
+        }
+    });
+}
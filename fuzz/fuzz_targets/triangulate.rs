@@ -0,0 +1,22 @@
+//! Triangulate arbitrary polygons, run with `cargo fuzz run triangulate`. Any input may fail to
+//! triangulate, but it must not panic or hang, and a triangulation must only use the points it
+//! was given and have at most n - 2 triangles.
+
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate trdl;
+
+use trdl::Mesh;
+
+fuzz_target!(|data: &[u8]| {
+    // each point is two 16 bit coordinates, so inputs are small and repeat coordinates often
+    let points: Vec<(f32, f32)> = data.chunks_exact(4).map(|c| {
+        (i16::from_le_bytes([c[0], c[1]]) as f32, i16::from_le_bytes([c[2], c[3]]) as f32)
+    }).collect();
+    if let Ok(mesh) = Mesh::from_polygon(&points) {
+        assert!(mesh.indices.len() % 3 == 0);
+        assert!(mesh.triangle_count() + 2 <= points.len());
+        assert!(mesh.indices.iter().all(|&i| i < points.len()));
+    }
+});
@@ -0,0 +1,279 @@
+//! Python bindings for TRDL: build paths, add them to drawings and render the drawings offscreen
+//! to RGBA pixels or PNG images. Drawings show themselves as images in Jupyter notebooks.
+//!
+//! Rendering uses a hidden OpenGL 4 context, one per Python thread, shared by the thread's
+//! drawings. Build with `maturin develop` in this directory.
+
+extern crate gl;
+extern crate glutin;
+extern crate png;
+extern crate pyo3;
+extern crate trdl;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::panic;
+use std::os::raw::c_void;
+use std::path::PathBuf;
+use glutin::{Api, Context, ContextBuilder, GlRequest, PossiblyCurrent};
+use glutin::dpi::PhysicalSize;
+use glutin::event_loop::EventLoop;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use trdl::{Drawing, ImageData, Path, PathId, Window};
+
+pyo3::create_exception!(trdl, TrdlError, PyException, "An error from TRDL.");
+
+type Point = (f32, f32);
+type Rgb = (f32, f32, f32);
+
+// A hidden context that is made current once and stays current.
+struct Headless {
+    context: Context<PossiblyCurrent>
+}
+
+impl Window for Headless {
+    fn set_context(&self) {}
+
+    fn load_fn(&self, addr: &str) -> *const c_void {
+        self.context.get_proc_address(addr) as *const c_void
+    }
+}
+
+thread_local! {
+    // made on first use and kept until the process exits, drawings borrow it
+    static HEADLESS: RefCell<Option<&'static Headless>> = const { RefCell::new(None) };
+}
+
+#[cfg(unix)]
+fn event_loop() -> EventLoop<()> {
+    use glutin::event_loop::EventLoopBuilder;
+    use glutin::platform::unix::EventLoopBuilderExtUnix;
+    // Python may render from any thread
+    EventLoopBuilder::new().with_any_thread(true).build()
+}
+
+#[cfg(not(unix))]
+fn event_loop() -> EventLoop<()> {
+    EventLoop::new()
+}
+
+fn headless() -> PyResult<&'static Headless> {
+    HEADLESS.with(|headless| {
+        if let Some(window) = *headless.borrow() {
+            return Ok(window);
+        }
+        // winit panics when there is no display to connect to
+        let event_loop = panic::catch_unwind(event_loop)
+            .map_err(|_| TrdlError::new_err("could not connect to a display for an OpenGL context"))?;
+        let event_loop: &'static EventLoop<()> = Box::leak(Box::new(event_loop));
+        let context = ContextBuilder::new()
+            .with_gl(GlRequest::Specific(Api::OpenGl, (4, 0)))
+            .build_headless(event_loop, PhysicalSize::new(1, 1))
+            .map_err(|err| TrdlError::new_err(format!("could not create an OpenGL context: {}", err)))?;
+        let context = unsafe { context.make_current() }
+            .map_err(|(_, err)| TrdlError::new_err(format!("could not use the OpenGL context: {}", err)))?;
+        let window: &'static Headless = Box::leak(Box::new(Headless { context }));
+        *headless.borrow_mut() = Some(window);
+        Ok(window)
+    })
+}
+
+fn to_py_err(err: trdl::TrdlError) -> PyErr {
+    TrdlError::new_err(err.to_string())
+}
+
+fn encode_png(image: &ImageData) -> PyResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, image.width(), image.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(image.pixels()))
+            .map_err(|err| TrdlError::new_err(err.to_string()))?;
+    }
+    Ok(bytes)
+}
+
+/// A shape made of lines, curves and arcs. Paths are immutable, the building methods return a
+/// new path so calls can be chained:
+///
+///     Path((0, 0)).line_to((10, 0)).line_to((5, 8)).close_path().fill((1, 0, 0))
+#[pyclass(name = "Path", module = "trdl", frozen)]
+#[derive(Clone)]
+struct PyPath {
+    path: Path
+}
+
+#[pymethods]
+impl PyPath {
+    #[new]
+    fn new(start: Point) -> Self {
+        PyPath { path: Path::new(start) }
+    }
+
+    /// A closed rectangle centered on center, rotated by angle radians.
+    #[staticmethod]
+    #[pyo3(signature = (center, width, height, angle = 0.0))]
+    fn rectangle(center: Point, width: f32, height: f32, angle: f32) -> Self {
+        PyPath { path: Path::rectangle(center, width, height, angle) }
+    }
+
+    /// A closed ellipse centered on center, rotated by angle radians.
+    #[staticmethod]
+    #[pyo3(signature = (center, x_radius, y_radius, angle = 0.0))]
+    fn ellipse(center: Point, x_radius: f32, y_radius: f32, angle: f32) -> Self {
+        PyPath { path: Path::ellipse(center, x_radius, y_radius, angle) }
+    }
+
+    /// Add a straight line to point.
+    fn line_to(&self, point: Point) -> Self {
+        PyPath { path: self.path.clone().line_to(point) }
+    }
+
+    /// Add a cubic Bezier curve to end.
+    fn curve_to(&self, control_point_1: Point, control_point_2: Point, end: Point) -> Self {
+        PyPath { path: self.path.clone().curve_to(control_point_1, control_point_2, end) }
+    }
+
+    /// Add an elliptical arc to end, as in SVG.
+    #[pyo3(signature = (x_radius, y_radius, end, angle = 0.0, large_arc = false, positive_sweep = true))]
+    fn arc_to(&self, x_radius: f32, y_radius: f32, end: Point, angle: f32, large_arc: bool,
+              positive_sweep: bool) -> Self {
+        PyPath { path: self.path.clone().arc_to(x_radius, y_radius, angle, end, large_arc, positive_sweep) }
+    }
+
+    /// Add a smooth curve through points, tension from 0 (Catmull-Rom) to 1 (straight lines).
+    #[pyo3(signature = (points, tension = 0.0))]
+    fn spline_through(&self, points: Vec<Point>, tension: f32) -> Self {
+        PyPath { path: self.path.clone().spline_through(&points, tension) }
+    }
+
+    /// Close the path with a line back to its start.
+    fn close_path(&self) -> Self {
+        PyPath { path: self.path.clone().close_path() }
+    }
+
+    /// Fill with an (r, g, b) color, components from 0 to 1.
+    fn fill(&self, color: Rgb) -> Self {
+        PyPath { path: self.path.clone().set_fill_color(color.0, color.1, color.2) }
+    }
+
+    /// Stroke with an (r, g, b) color and a thickness in pixels.
+    #[pyo3(signature = (color, thickness = 1))]
+    fn stroke(&self, color: Rgb, thickness: u32) -> Self {
+        PyPath { path: self.path.clone().set_stroke(color.0, color.1, color.2, thickness) }
+    }
+
+    /// Set the opacity of the whole path, from 0 to 1.
+    fn opacity(&self, opacity: f32) -> Self {
+        PyPath { path: self.path.clone().set_opacity(opacity) }
+    }
+
+    /// The length of the path's outline.
+    fn length(&self) -> f32 {
+        self.path.length()
+    }
+
+    /// ((min_x, min_y), (max_x, max_y)) around the path's points.
+    fn bounds(&self) -> (Point, Point) {
+        self.path.bounds()
+    }
+
+    fn __repr__(&self) -> String {
+        let (min, max) = self.path.bounds();
+        format!("<trdl.Path from ({}, {}) to ({}, {})>", min.0, min.1, max.0, max.1)
+    }
+}
+
+/// A drawing of width by height pixels. The origin is at the bottom left with y up.
+#[pyclass(name = "Drawing", module = "trdl", unsendable)]
+struct PyDrawing {
+    drawing: Drawing<'static, Headless>,
+    size: (u32, u32)
+}
+
+#[pymethods]
+impl PyDrawing {
+    #[new]
+    #[pyo3(signature = (width, height, background = (1.0, 1.0, 1.0)))]
+    fn new(width: u32, height: u32, background: Rgb) -> PyResult<Self> {
+        let window = headless()?;
+        let drawing = Drawing::new(window, width, height, background.0, background.1, background.2)
+            .map_err(to_py_err)?;
+        Ok(PyDrawing { drawing, size: (width, height) })
+    }
+
+    #[getter]
+    fn width(&self) -> u32 {
+        self.size.0
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.size.1
+    }
+
+    /// Add a path, returns its id.
+    fn add_path(&mut self, path: &PyPath) -> PyResult<u64> {
+        self.drawing.add_path(path.path.clone()).map(PathId::to_raw).map_err(to_py_err)
+    }
+
+    /// Replace the path with an id, returns False if there is none.
+    fn update_path(&mut self, id: u64, path: &PyPath) -> PyResult<bool> {
+        self.drawing.update_path(PathId::from_raw(id), path.path.clone())
+            .map(|old| old.is_some())
+            .map_err(to_py_err)
+    }
+
+    /// Remove the path with an id, returns False if there is none.
+    fn remove_path(&mut self, id: u64) -> bool {
+        self.drawing.remove_path(PathId::from_raw(id)).is_some()
+    }
+
+    /// Remove every path.
+    fn clear(&mut self) {
+        self.drawing.clear_paths();
+    }
+
+    /// Render to RGBA bytes, 4 per pixel, rows from top to bottom.
+    fn render_rgba<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let image = self.drawing.render_to_image().map_err(to_py_err)?;
+        Ok(PyBytes::new(py, image.pixels()))
+    }
+
+    /// Render to PNG file contents.
+    #[pyo3(name = "to_png")]
+    fn png_bytes<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let image = self.drawing.render_to_image().map_err(to_py_err)?;
+        Ok(PyBytes::new(py, &encode_png(&image)?))
+    }
+
+    /// Render to a PNG file.
+    fn save_png(&mut self, file: PathBuf) -> PyResult<()> {
+        let image = self.drawing.render_to_image().map_err(to_py_err)?;
+        fs::write(file, encode_png(&image)?)?;
+        Ok(())
+    }
+
+    // shows the drawing in Jupyter
+    fn _repr_png_<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        self.png_bytes(py)
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "trdl")]
+fn trdl_python(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    // notebooks rarely run from the repository, use the shaders next to this crate
+    if env::var_os("TRDL_SHADER_DIR").is_none() {
+        env::set_var("TRDL_SHADER_DIR", concat!(env!("CARGO_MANIFEST_DIR"), "/../shaders"));
+    }
+    module.add_class::<PyPath>()?;
+    module.add_class::<PyDrawing>()?;
+    module.add("TrdlError", module.py().get_type::<TrdlError>())?;
+    Ok(())
+}
@@ -0,0 +1,42 @@
+"""Tests of the Python bindings, run with pytest after `maturin develop`. Rendering needs an
+OpenGL 4 capable display and is skipped without one."""
+
+import pytest
+import trdl
+
+
+def test_paths_are_immutable():
+    start = trdl.Path((0, 0))
+    triangle = start.line_to((10, 0)).line_to((5, 8)).close_path()
+    assert start.bounds() == ((0, 0), (0, 0))
+    assert triangle.bounds() == ((0, 0), (10, 8))
+
+
+def test_shapes():
+    rectangle = trdl.Path.rectangle((5, 5), 10, 4)
+    assert rectangle.bounds() == ((0, 3), (10, 7))
+    assert rectangle.length() == pytest.approx(28, rel=1e-4)
+
+
+@pytest.fixture
+def drawing():
+    try:
+        return trdl.Drawing(32, 16, background=(1, 1, 1))
+    except trdl.TrdlError as err:
+        pytest.skip(str(err))
+
+
+def test_render(drawing):
+    drawing.add_path(trdl.Path.rectangle((8, 8), 16, 16).fill((1, 0, 0)))
+    pixels = drawing.render_rgba()
+    assert len(pixels) == 32 * 16 * 4
+    # red on the left, the white background on the right
+    assert pixels[8 * 32 * 4 + 8 * 4:8 * 32 * 4 + 8 * 4 + 3] == b"\xff\x00\x00"
+    assert pixels[8 * 32 * 4 + 24 * 4:8 * 32 * 4 + 24 * 4 + 3] == b"\xff\xff\xff"
+    assert drawing.to_png().startswith(b"\x89PNG")
+
+
+def test_remove_path(drawing):
+    id = drawing.add_path(trdl.Path.ellipse((16, 8), 4, 4).fill((0, 0, 0)))
+    assert drawing.remove_path(id)
+    assert not drawing.remove_path(id)
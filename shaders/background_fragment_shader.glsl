@@ -0,0 +1,43 @@
+#version 400
+
+in vec2 v_tex_coord;
+layout ( location = 0 ) out vec4 frag_color;
+
+uniform int mode;
+uniform vec2 gradient_start;
+uniform vec2 gradient_end;
+uniform vec4 start_color;
+uniform vec4 end_color;
+uniform sampler2D image;
+uniform int srgb;
+// multiplies the alpha, below 1 to fade the previous frame for trails
+uniform float opacity;
+
+// Convert an sRGB encoded color to linear RGB, the sRGB framebuffer encodes it again on write.
+vec4 srgb_to_linear(vec4 color) {
+    vec3 low = color.rgb / 12.92;
+    vec3 high = pow((color.rgb + 0.055) / 1.055, vec3(2.4));
+    return vec4(mix(high, low, lessThanEqual(color.rgb, vec3(0.04045))), color.a);
+}
+
+// Mode 1 is a linear gradient in window coordinates, mode 2 samples the background image.
+void main() {
+    if (mode == 1) {
+        vec4 c0 = start_color;
+        vec4 c1 = end_color;
+        if (srgb > 0) {
+            c0 = srgb_to_linear(c0);
+            c1 = srgb_to_linear(c1);
+        }
+        vec2 dir = gradient_end - gradient_start;
+        float t = clamp(dot(gl_FragCoord.xy - gradient_start, dir) / max(dot(dir, dir), 1.0e-6), 0.0, 1.0);
+        frag_color = mix(c0, c1, t);
+    } else {
+        vec4 color = texture(image, v_tex_coord);
+        if (srgb > 0) {
+            color = srgb_to_linear(color);
+        }
+        frag_color = color;
+    }
+    frag_color.a *= opacity;
+}
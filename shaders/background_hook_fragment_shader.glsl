@@ -0,0 +1,33 @@
+#version 400
+
+in vec2 v_tex_coord;
+layout ( location = 0 ) out vec4 frag_color;
+
+// the size of the viewport in pixels
+uniform vec2 resolution;
+// the drawing's animation time in seconds and the index of the frame being drawn
+uniform float time;
+uniform int frame_index;
+uniform int srgb;
+// multiplies the alpha, below 1 to fade the previous frame for trails
+uniform float opacity;
+
+// The hook a background shader defines after this: the color at uv, from (0, 0) at the bottom
+// left of the viewport to (1, 1) at the top right, with straight alpha and sRGB encoded like the
+// colors of paths.
+vec4 background(vec2 uv);
+
+// Convert an sRGB encoded color to linear RGB, the sRGB framebuffer encodes it again on write.
+vec4 srgb_to_linear(vec4 color) {
+    vec3 low = color.rgb / 12.92;
+    vec3 high = pow((color.rgb + 0.055) / 1.055, vec3(2.4));
+    return vec4(mix(high, low, lessThanEqual(color.rgb, vec3(0.04045))), color.a);
+}
+
+void main() {
+    vec4 color = background(vec2(v_tex_coord.x, 1.0 - v_tex_coord.y));
+    if (srgb > 0) {
+        color = srgb_to_linear(color);
+    }
+    frag_color = vec4(color.rgb, color.a * opacity);
+}
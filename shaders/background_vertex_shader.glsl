@@ -0,0 +1,11 @@
+#version 400
+
+layout ( location = 0 ) in vec2 in_position;
+
+out vec2 v_tex_coord;
+
+// Pass through a fullscreen quad, texture coordinates have the top of the image at the top.
+void main() {
+    v_tex_coord = vec2((in_position.x + 1.0) / 2.0, (1.0 - in_position.y) / 2.0);
+    gl_Position = vec4(in_position, 0.0, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 400
+
+in vec2 v_tex_coord;
+layout ( location = 0 ) out vec4 frag_color;
+
+uniform sampler2D image;
+
+// Baked textures hold premultiplied colors, like layer textures.
+void main() {
+    frag_color = texture(image, v_tex_coord);
+}
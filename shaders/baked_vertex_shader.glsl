@@ -0,0 +1,14 @@
+#version 400
+
+in vec2 in_position;
+in vec2 in_tex_coord;
+
+out vec2 v_tex_coord;
+
+uniform mat4 projection;
+
+// A baked texture's quad, its corners in drawing coordinates.
+void main() {
+    v_tex_coord = in_tex_coord;
+    gl_Position = projection * vec4(in_position, 0.0, 1.0);
+}
@@ -0,0 +1,40 @@
+#version 400
+
+in vec2 v_local;
+flat in vec2 v_radii;
+flat in float v_thickness;
+flat in vec4 v_fill;
+flat in vec4 v_stroke;
+layout ( location = 0 ) out vec4 frag_color;
+
+uniform float pixel_size;
+uniform int srgb;
+
+// Convert an sRGB encoded color to linear RGB, the sRGB framebuffer encodes it again on write.
+vec4 srgb_to_linear(vec4 color) {
+    vec3 low = color.rgb / 12.92;
+    vec3 high = pow((color.rgb + 0.055) / 1.055, vec3(2.4));
+    return vec4(mix(high, low, lessThanEqual(color.rgb, vec3(0.04045))), color.a);
+}
+
+// A color premultiplied by its alpha and a coverage.
+vec4 premultiply(vec4 color, float coverage) {
+    vec4 linear = srgb == 1 ? srgb_to_linear(color) : color;
+    return vec4(linear.rgb * linear.a, linear.a) * coverage;
+}
+
+// Cover the ellipse from its implicit equation (x / rx)^2 + (y / ry)^2 = 1, divided by the length
+// of its gradient for the distance to the outline in pixels.
+void main() {
+    vec2 scaled = v_local / v_radii;
+    float f = dot(scaled, scaled) - 1.0;
+    vec2 gradient = 2.0 * v_local / (v_radii * v_radii);
+    float distance = f / max(length(gradient), 1e-6) / pixel_size;
+
+    vec4 fill = premultiply(v_fill, clamp(0.5 - distance, 0.0, 1.0));
+    vec4 stroke = premultiply(v_stroke, clamp(v_thickness / 2.0 + 0.5 - abs(distance), 0.0, 1.0));
+    frag_color = stroke + fill * (1.0 - stroke.a);
+    if (frag_color.a <= 0.0) {
+        discard;
+    }
+}
@@ -0,0 +1,33 @@
+#version 400
+
+in vec2 in_corner;
+in vec4 in_center_radii;
+in vec2 in_angle_thickness;
+in vec4 in_fill;
+in vec4 in_stroke;
+
+uniform mat4 projection;
+uniform float pixel_size;
+
+out vec2 v_local;
+flat out vec2 v_radii;
+flat out float v_thickness;
+flat out vec4 v_fill;
+flat out vec4 v_stroke;
+
+// Place a corner of the quad around an ellipse, with room for half the stroke and a pixel of
+// anti-aliasing outside it. One instance per ellipse.
+void main() {
+    float thickness = in_angle_thickness.y;
+    vec2 radii = in_center_radii.zw;
+    vec2 local = in_corner * (radii + vec2((thickness / 2.0 + 1.0) * pixel_size));
+    float c = cos(in_angle_thickness.x);
+    float s = sin(in_angle_thickness.x);
+    vec2 position = in_center_radii.xy + vec2(c * local.x - s * local.y, s * local.x + c * local.y);
+    v_local = local;
+    v_radii = radii;
+    v_thickness = thickness;
+    v_fill = in_fill;
+    v_stroke = in_stroke;
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+}
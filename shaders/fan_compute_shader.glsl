@@ -0,0 +1,40 @@
+#version 430
+
+layout ( local_size_x = 64 ) in;
+
+// a polygon: its points in the point buffer, where its triangles go and its color
+struct Shape {
+    uint first;
+    uint count;
+    uint first_triangle;
+    uint unused;
+    vec4 color;
+};
+
+struct Vertex {
+    vec4 position;
+    vec4 color;
+};
+
+layout ( std430, binding = 0 ) readonly buffer Points { vec2 points[]; };
+layout ( std430, binding = 1 ) readonly buffer Shapes { Shape shapes[]; };
+layout ( std430, binding = 2 ) writeonly buffer Triangles { Vertex vertices[]; };
+
+uniform uint shape_count;
+
+// Fan out one polygon per work group: triangle i joins the first point to points i + 1 and i + 2.
+void main() {
+    uint index = gl_WorkGroupID.y * gl_NumWorkGroups.x + gl_WorkGroupID.x;
+    if (index >= shape_count) {
+        return;
+    }
+    Shape shape = shapes[index];
+    for (uint i = gl_LocalInvocationID.x; i + 2u < shape.count; i += gl_WorkGroupSize.x) {
+        uint corners[3] = uint[3](shape.first, shape.first + i + 1u, shape.first + i + 2u);
+        uint base = 3u * (shape.first_triangle + i);
+        for (uint k = 0u; k < 3u; k++) {
+            vertices[base + k].position = vec4(points[corners[k]], 0.0, 1.0);
+            vertices[base + k].color = shape.color;
+        }
+    }
+}
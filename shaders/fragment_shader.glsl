@@ -1,9 +1,117 @@
 #version 400
 
-in vec3 g_color;
+in vec4 g_color;
+flat in int g_pattern;
+in vec2 g_position;
 layout ( location = 0 ) out vec4 frag_color;
 
+uniform int srgb;
+// whether g_color comes premultiplied and frag_color goes out premultiplied
+uniform bool premultiplied;
+// a record of three texels for each fill pattern: kind, spacing, angle and width, then the
+// origin, then the background color
+uniform samplerBuffer fill_patterns;
+// a slot for each gradient: the number of stops and the angle, then a position texel and a color
+// texel for each stop
+uniform samplerBuffer gradients;
+// the drawing's animation time in seconds and the number of the frame, for animated effects
+uniform float time;
+uniform int frame_index;
+
+const int HATCH = 1;
+const int CROSS_HATCH = 2;
+const int DOTS = 3;
+const int CHECKER = 4;
+const int GRADIENT = 5;
+// texels in a gradient's slot, for up to 16 stops
+const int SLOT_TEXELS = 33;
+
+// Convert an sRGB encoded color to linear RGB, the sRGB framebuffer encodes it again on write.
+vec3 srgb_to_linear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(high, low, lessThanEqual(color, vec3(0.04045)));
+}
+
+// The color of a gradient fill here. The gradient runs across the path's bounds (min in xy, max
+// in zw) at its angle, from the side of the box where it starts to the side where it ends.
+vec4 gradient_color(int gradient, vec4 bounds, float opacity) {
+    int slot = SLOT_TEXELS * gradient;
+    vec4 header = texelFetch(gradients, slot);
+    int count = int(header.x);
+    if (count == 0) {
+        return vec4(0.0);
+    }
+    vec2 direction = vec2(cos(header.y), sin(header.y));
+    vec2 half_size = (bounds.zw - bounds.xy) / 2.0;
+    float extent = abs(direction.x) * half_size.x + abs(direction.y) * half_size.y;
+    float t = extent > 0.0 ? 0.5 + dot(g_position - (bounds.xy + half_size), direction) / (2.0 * extent) : 0.0;
+
+    float previous = texelFetch(gradients, slot + 1).x;
+    vec4 color = texelFetch(gradients, slot + 2);
+    for (int i = 1; i < count; i++) {
+        float position = texelFetch(gradients, slot + 1 + 2 * i).x;
+        vec4 next = texelFetch(gradients, slot + 2 + 2 * i);
+        if (t >= position) {
+            previous = position;
+            color = next;
+        } else {
+            color = mix(color, next, clamp((t - previous) / max(position - previous, 0.000001), 0.0, 1.0));
+            break;
+        }
+    }
+    return vec4(color.rgb, color.a * opacity);
+}
+
+// The color of a patterned fill here: the ink where the pattern is drawn, the background between,
+// blended over the pixels on the pattern's edges. pixel is the size of a pixel in drawing units.
+vec4 pattern_color(vec4 ink, float pixel) {
+    int record = 3 * (g_pattern - 1);
+    vec4 params = texelFetch(fill_patterns, record);
+    vec2 origin = texelFetch(fill_patterns, record + 1).xy;
+    vec4 background = texelFetch(fill_patterns, record + 2);
+    int kind = int(params.x);
+    if (kind == GRADIENT) {
+        return gradient_color(int(params.y), texelFetch(fill_patterns, record + 1), background.a);
+    }
+    float spacing = max(params.y, 0.000001);
+    float angle = params.z;
+    float half_width = params.w / 2.0;
+
+    // the point in the pattern's own coordinates, the pattern turned back by its angle
+    vec2 d = g_position - origin;
+    vec2 p = vec2(cos(angle) * d.x + sin(angle) * d.y, cos(angle) * d.y - sin(angle) * d.x);
+    vec2 cell = p - spacing * round(p / spacing);
+    // the distance from the edge of the ink, negative on it
+    float distance;
+    if (kind == HATCH) {
+        distance = abs(cell.y) - half_width;
+    } else if (kind == CROSS_HATCH) {
+        distance = min(abs(cell.x), abs(cell.y)) - half_width;
+    } else if (kind == DOTS) {
+        distance = length(cell) - half_width;
+    } else {
+        vec2 square = floor(p / spacing);
+        float to_edge = min(abs(cell.x), abs(cell.y));
+        distance = mod(square.x + square.y, 2.0) < 0.5 ? -to_edge : to_edge;
+    }
+    float coverage = clamp(0.5 - distance / max(pixel, 0.000001), 0.0, 1.0);
+    // blend premultiplied so a transparent background does not darken the ink's edges
+    vec4 color = mix(vec4(background.rgb * background.a, background.a), vec4(ink.rgb * ink.a, ink.a), coverage);
+    return color.a > 0.0 ? vec4(color.rgb / color.a, color.a) : vec4(0.0);
+}
+
 // Set the fragment color.
 void main() {
-    frag_color = vec4(g_color, 1.0);
+    // derivatives are taken outside the branch, where all neighbouring fragments get to them
+    float pixel = max(length(dFdx(g_position)), length(dFdy(g_position)));
+    vec4 straight = g_color;
+    if (premultiplied) {
+        straight = g_color.a > 0.0 ? vec4(g_color.rgb / g_color.a, g_color.a) : vec4(0.0);
+    }
+    vec4 color = g_pattern > 0 ? pattern_color(straight, pixel) : straight;
+    if (srgb > 0) {
+        color.rgb = srgb_to_linear(color.rgb);
+    }
+    frag_color = premultiplied ? vec4(color.rgb * color.a, color.a) : color;
 }
@@ -2,22 +2,35 @@
 
 in vec3 te_edge[];
 in vec3 te_bary[];
-in vec3 te_color[];
+in vec4 te_color[];
 
 in vec2 te_tan_ab[];
 in vec2 te_tan_bc[];
 in vec2 te_tan_ca[];
 
+// bit 0 turns the fill on, bits 1 and 2 are the stroke alignment, and bit 3 marks a patch as the
+// join at a corner of the stroke, with the join's details in the bits above; from bit 10 up is
+// the number of the fill pattern record of a patterned path
 in int te_do_fill[];
 
-in vec3 te_stroke_color[];
+in vec4 te_stroke_color[];
 
-out vec3 g_color;
+out vec4 g_color;
+// the fill pattern record, 0 for flat colors, and the point in drawing coordinates for patterns
+flat out int g_pattern;
+out vec2 g_position;
 
 uniform vec2 window_size;
+// read to tell whether it mirrors the drawing, as it does with y down, and to take patterned
+// fills back to drawing coordinates
+uniform mat4 projection;
+// window pixels per drawing unit, for strokes with thicknesses in drawing units
+uniform float world_scale;
+// how far in front of its fill a path's stroke is drawn, half the depth step between paths
+uniform float stroke_depth_offset;
 
 layout(triangles) in;
-layout(triangle_strip, max_vertices = 12) out;
+layout(triangle_strip, max_vertices = 24) out;
 
 // Return true if a point is on the edge of a (non-tessellated original) triangle and that edge has its flag set.
 // A point is on an edge if a barycentric coordinate is zero.
@@ -28,29 +41,140 @@ int is_edge(vec3 bary1, vec3 bary2, vec3 edges) {
     else { return 0; }
 }
 
-// Emit an edge
+const int ALIGN_INSIDE = 1;
+const int ALIGN_OUTSIDE = 2;
+
+const int JOIN_FLAG = 8;
+const int INSIDE_LEFT_FLAG = 16;
+const int ROUND_SHARP_FLAG = 128;
+const int WORLD_STROKE_FLAG = 512;
+const int PATTERN_SHIFT = 10;
+const int JOIN_MITER = 0;
+const int JOIN_BEVEL = 1;
+const int JOIN_ROUND = 2;
+// the most triangles in a round join
+const int MAX_ROUND_STEPS = 8;
+
+// How far to move the stroke off the edge at a point, in half thicknesses along perp: towards the
+// inner point (the triangle's third vertex, on the path's inside) for inside strokes and away from
+// it for outside ones.
+float align_shift(int align, vec2 p, vec2 perp, vec2 inner) {
+    if (align != ALIGN_INSIDE && align != ALIGN_OUTSIDE) {
+        return 0.0;
+    }
+    float side = dot(perp, inner - p) < 0.0 ? -1.0 : 1.0;
+    return align == ALIGN_INSIDE ? side : -side;
+}
+
+// Emit a vertex in a flat color.
+void emit_vertex(vec2 position, float depth, vec4 color) {
+    g_color = color;
+    g_pattern = 0;
+    g_position = vec2(0.0);
+    gl_Position = vec4(position, depth, 1);
+    EmitVertex();
+}
+
+// Emit an edge, its stroke centered on the edge or moved to one side of it for aligned strokes,
+// colored color0 at p0 and color1 at p1
 // see http://prideout.net/blog/?p=54
-void make_edge(vec2 p0, vec2 p1, float depth, vec2 tan0, vec2 tan1, vec2 thickness, vec3 color) {
+void make_edge(vec2 p0, vec2 p1, vec2 inner, int align, float depth, vec2 tan0, vec2 tan1, vec2 thickness,
+               vec4 color0, vec4 color1) {
     tan0 = normalize(tan0);
     vec2 perp0 = vec2(-tan0.y, tan0.x) * thickness;
     tan1 = normalize(tan1);
     vec2 perp1 = vec2(-tan1.y, tan1.x) * thickness;
+    float shift0 = align_shift(align, p0, perp0, inner);
+    float shift1 = align_shift(align, p1, perp1, inner);
 
-    g_color = color;
-    gl_Position = vec4(p0 - perp0, depth, 1);
-    EmitVertex();
-    g_color = color;
-    gl_Position = vec4(p0 + perp0, depth, 1);
-    EmitVertex();
-    g_color = color;
-    gl_Position = vec4(p1 - perp1, depth, 1);
-    EmitVertex();
-    g_color = color;
-    gl_Position = vec4(p1 + perp1, depth, 1);
-    EmitVertex();
+    emit_vertex(p0 + (shift0 - 1.0) * perp0, depth, color0);
+    emit_vertex(p0 + (shift0 + 1.0) * perp0, depth, color0);
+    emit_vertex(p1 + (shift1 - 1.0) * perp1, depth, color1);
+    emit_vertex(p1 + (shift1 + 1.0) * perp1, depth, color1);
     EndPrimitive();
 }
 
+// Half of a stroke thickness in normalized device coordinates, along x and y.
+vec2 half_thickness(float thickness) {
+    if ((te_do_fill[0] & WORLD_STROKE_FLAG) != 0) {
+        thickness *= world_scale;
+    }
+    return vec2(thickness / window_size.x, thickness / window_size.y);
+}
+
+void emit_triangle(vec2 a, vec2 b, vec2 c, float depth, vec4 color) {
+    emit_vertex(a, depth, color);
+    emit_vertex(b, depth, color);
+    emit_vertex(c, depth, color);
+    EndPrimitive();
+}
+
+// The vertex of the patch at its k-th corner, the tessellator may hand them over in any order.
+vec2 patch_corner(int k) {
+    for (int i = 0; i < 2; i++) {
+        if (te_bary[i][k] > 0.5) {
+            return gl_in[i].gl_Position.xy;
+        }
+    }
+    return gl_in[2].gl_Position.xy;
+}
+
+// Emit the join at corner b between the stroke coming from a and the one going on to c. Offsets
+// are worked out for unit thickness and scaled by the stroke's half thickness like in make_edge,
+// so the join meets the ends of the edges.
+void make_join(vec2 a, vec2 b, vec2 c, float depth, vec2 thickness, float miter_limit, int flags,
+               vec4 color) {
+    vec2 u_in = normalize(b - a);
+    vec2 u_out = normalize(c - b);
+    float turn = u_in.x * u_out.y - u_in.y * u_out.x;
+    // the side of the corner the stroke leaves a gap on, as a sign on the left normals
+    float outer = turn > 0.0 ? -1.0 : 1.0;
+    // how many half thicknesses the stroke reaches to that side
+    float reach = 1.0;
+    int align = (flags >> 1) & 3;
+    if (align == ALIGN_INSIDE || align == ALIGN_OUTSIDE) {
+        float inside = (flags & INSIDE_LEFT_FLAG) != 0 ? 1.0 : -1.0;
+        if (determinant(mat2(projection)) < 0.0) {
+            inside = -inside;
+        }
+        float stroke_side = align == ALIGN_INSIDE ? inside : -inside;
+        if (stroke_side != outer) {
+            return;
+        }
+        reach = 2.0;
+    }
+    vec2 n_in = outer * vec2(-u_in.y, u_in.x);
+    vec2 n_out = outer * vec2(-u_out.y, u_out.x);
+    vec2 p_in = b + reach * n_in * thickness;
+    vec2 p_out = b + reach * n_out * thickness;
+
+    int join = (flags >> 5) & 3;
+    // the miter's length is 1 / sin of half the angle between the segments, in thicknesses
+    vec2 miter = (n_in + n_out) / max(1.0 + dot(n_in, n_out), 0.000001);
+    if (join == JOIN_MITER && length(miter) > miter_limit) {
+        join = (flags & ROUND_SHARP_FLAG) != 0 ? JOIN_ROUND : JOIN_BEVEL;
+    }
+    if (join == JOIN_ROUND) {
+        // an arc from n_in to n_out, turning towards the direction of travel
+        float angle = acos(clamp(dot(n_in, n_out), -1.0, 1.0));
+        float direction = n_in.x * u_in.y - n_in.y * u_in.x >= 0.0 ? 1.0 : -1.0;
+        int steps = int(clamp(ceil(angle / (3.14159265 / 8.0)), 1.0, float(MAX_ROUND_STEPS)));
+        vec2 previous = p_in;
+        for (int i = 1; i <= steps; i++) {
+            float t = direction * angle * float(i) / float(steps);
+            vec2 n = vec2(n_in.x * cos(t) - n_in.y * sin(t), n_in.x * sin(t) + n_in.y * cos(t));
+            vec2 next = i == steps ? p_out : b + reach * n * thickness;
+            emit_triangle(b, previous, next, depth, color);
+            previous = next;
+        }
+        return;
+    }
+    emit_triangle(b, p_in, p_out, depth, color);
+    if (join == JOIN_MITER) {
+        emit_triangle(p_in, b + reach * miter * thickness, p_out, depth, color);
+    }
+}
+
 // Emit interior triangles if shape is filled. Emit edges where appropriate, make the depth a little less so the edges
 // are drawn on top of the shape.
 void main() {
@@ -58,61 +182,73 @@ void main() {
     vec3 v1 = gl_in[1].gl_Position.xyz;
     vec3 v2 = gl_in[2].gl_Position.xyz;
 
-    if (te_do_fill[0] > 0) {
-        g_color = te_color[0];
-        gl_Position = vec4(v0, 1);
-        EmitVertex();
-
-        g_color = te_color[1];
-        gl_Position = vec4(v1, 1);
-        EmitVertex();
-
-        g_color = te_color[2];
-        gl_Position = vec4(v2, 1);
-        EmitVertex();
+    if ((te_do_fill[0] & JOIN_FLAG) != 0) {
+        vec2 thickness = half_thickness(te_edge[0].s);
+        make_join(patch_corner(0), patch_corner(1), patch_corner(2), v0.z - stroke_depth_offset, thickness,
+                  te_edge[0].t, te_do_fill[0], te_stroke_color[0]);
+        return;
+    }
 
+    if ((te_do_fill[0] & 1) != 0) {
+        int pattern = int(uint(te_do_fill[0]) >> PATTERN_SHIFT);
+        // patterns are drawn in drawing coordinates, back from normalized device coordinates
+        mat4 unproject = pattern != 0 ? inverse(projection) : mat4(1.0);
+        for (int i = 0; i < 3; i++) {
+            g_color = te_color[i];
+            g_pattern = pattern;
+            g_position = (unproject * vec4(gl_in[i].gl_Position.xy, 0, 1)).xy;
+            gl_Position = gl_in[i].gl_Position;
+            EmitVertex();
+        }
         EndPrimitive();
     }
 
-    float depth = v0.z - 1.0e-6;
-    vec3 stroke_color = te_stroke_color[0];
+    float depth = v0.z - stroke_depth_offset;
+    int align = (te_do_fill[0] >> 1) & 3;
 
     int edge = is_edge(te_bary[0], te_bary[1], te_edge[0]);
     if (edge == 1) {
-        vec2 stroke_thickness = vec2(te_edge[0].s / window_size.x, te_edge[0].s / window_size.y);
-        make_edge(v0.xy, v1.xy, depth, te_tan_bc[0], te_tan_bc[1], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[0].s);
+        make_edge(v0.xy, v1.xy, v2.xy, align, depth, te_tan_bc[0], te_tan_bc[1], stroke_thickness,
+                  te_stroke_color[0], te_stroke_color[1]);
     } else if (edge == 2) {
-        vec2 stroke_thickness = vec2(te_edge[0].t / window_size.x, te_edge[0].t / window_size.y);
-        make_edge(v0.xy, v1.xy, depth, te_tan_ca[0], te_tan_ca[1], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[0].t);
+        make_edge(v0.xy, v1.xy, v2.xy, align, depth, te_tan_ca[0], te_tan_ca[1], stroke_thickness,
+                  te_stroke_color[0], te_stroke_color[1]);
     } else if (edge == 3) {
-        vec2 stroke_thickness = vec2(te_edge[0].p / window_size.x, te_edge[0].p / window_size.y);
-        make_edge(v0.xy, v1.xy, depth, te_tan_ab[0], te_tan_ab[1], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[0].p);
+        make_edge(v0.xy, v1.xy, v2.xy, align, depth, te_tan_ab[0], te_tan_ab[1], stroke_thickness,
+                  te_stroke_color[0], te_stroke_color[1]);
     }
 
-    stroke_color = te_stroke_color[1];
     edge = is_edge(te_bary[1], te_bary[2], te_edge[1]);
     if (edge == 1) {
-        vec2 stroke_thickness = vec2(te_edge[1].s / window_size.x, te_edge[1].s / window_size.y);
-        make_edge(v1.xy, v2.xy, depth, te_tan_bc[1], te_tan_bc[2], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[1].s);
+        make_edge(v1.xy, v2.xy, v0.xy, align, depth, te_tan_bc[1], te_tan_bc[2], stroke_thickness,
+                  te_stroke_color[1], te_stroke_color[2]);
     } else if (edge == 2) {
-        vec2 stroke_thickness = vec2(te_edge[1].t / window_size.x, te_edge[1].t / window_size.y);
-        make_edge(v1.xy, v2.xy, depth, te_tan_ca[1], te_tan_ca[2], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[1].t);
+        make_edge(v1.xy, v2.xy, v0.xy, align, depth, te_tan_ca[1], te_tan_ca[2], stroke_thickness,
+                  te_stroke_color[1], te_stroke_color[2]);
     } else if (edge == 3) {
-        vec2 stroke_thickness = vec2(te_edge[1].p / window_size.x, te_edge[1].p / window_size.y);
-        make_edge(v1.xy, v2.xy, depth, te_tan_ab[1], te_tan_ab[2], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[1].p);
+        make_edge(v1.xy, v2.xy, v0.xy, align, depth, te_tan_ab[1], te_tan_ab[2], stroke_thickness,
+                  te_stroke_color[1], te_stroke_color[2]);
     }
 
-    stroke_color = te_stroke_color[2];
     edge = is_edge(te_bary[2], te_bary[0], te_edge[2]);
     if (edge == 1) {
-        vec2 stroke_thickness = vec2(te_edge[2].s / window_size.x, te_edge[2].s / window_size.y);
-        make_edge(v2.xy, v0.xy, depth, te_tan_bc[2], te_tan_bc[0], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[2].s);
+        make_edge(v2.xy, v0.xy, v1.xy, align, depth, te_tan_bc[2], te_tan_bc[0], stroke_thickness,
+                  te_stroke_color[2], te_stroke_color[0]);
     } else if (edge == 2) {
-        vec2 stroke_thickness = vec2(te_edge[2].t / window_size.x, te_edge[2].t / window_size.y);
-        make_edge(v2.xy, v0.xy, depth, te_tan_ca[2], te_tan_ca[0], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[2].t);
+        make_edge(v2.xy, v0.xy, v1.xy, align, depth, te_tan_ca[2], te_tan_ca[0], stroke_thickness,
+                  te_stroke_color[2], te_stroke_color[0]);
     } else if (edge == 3) {
-        vec2 stroke_thickness = vec2(te_edge[2].p / window_size.x, te_edge[2].p / window_size.y);
-        make_edge(v2.xy, v0.xy, depth, te_tan_ab[2], te_tan_ab[0], stroke_thickness, stroke_color);
+        vec2 stroke_thickness = half_thickness(te_edge[2].p);
+        make_edge(v2.xy, v0.xy, v1.xy, align, depth, te_tan_ab[2], te_tan_ab[0], stroke_thickness,
+                  te_stroke_color[2], te_stroke_color[0]);
     }
 }
 
@@ -0,0 +1,17 @@
+#version 430
+
+in vec4 v_color;
+layout ( location = 0 ) out vec4 frag_color;
+
+uniform int srgb;
+
+// Convert an sRGB encoded color to linear RGB, the sRGB framebuffer encodes it again on write.
+vec4 srgb_to_linear(vec4 color) {
+    vec3 low = color.rgb / 12.92;
+    vec3 high = pow((color.rgb + 0.055) / 1.055, vec3(2.4));
+    return vec4(mix(high, low, lessThanEqual(color.rgb, vec3(0.04045))), color.a);
+}
+
+void main() {
+    frag_color = srgb == 1 ? srgb_to_linear(v_color) : v_color;
+}
@@ -0,0 +1,14 @@
+#version 430
+
+layout ( location = 0 ) in vec4 in_position;
+layout ( location = 1 ) in vec4 in_color;
+
+uniform mat4 projection;
+
+out vec4 v_color;
+
+// Place a corner of a triangle the compute shader wrote.
+void main() {
+    v_color = in_color;
+    gl_Position = projection * in_position;
+}
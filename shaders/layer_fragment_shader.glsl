@@ -0,0 +1,12 @@
+#version 400
+
+in vec2 v_tex_coord;
+layout ( location = 0 ) out vec4 frag_color;
+
+uniform sampler2D layer;
+uniform float opacity;
+
+// The layer texture holds premultiplied colors, so fading it scales every component.
+void main() {
+    frag_color = texture(layer, v_tex_coord) * opacity;
+}
@@ -0,0 +1,12 @@
+#version 400
+
+in vec2 in_position;
+
+out vec2 v_tex_coord;
+
+// Pass through a fullscreen quad, the layer texture has its origin at the bottom left like the
+// window.
+void main() {
+    v_tex_coord = (in_position + 1.0) / 2.0;
+    gl_Position = vec4(in_position, 0.0, 1.0);
+}
@@ -0,0 +1,18 @@
+#version 400
+
+in vec2 in_corner;
+in vec3 in_center_size;
+in vec4 in_color;
+
+uniform mat4 projection;
+
+out vec2 v_corner;
+out vec4 v_color;
+
+// Place a corner of the particle quad, one instance per particle.
+void main() {
+    v_corner = in_corner;
+    v_color = in_color;
+    vec2 position = in_center_size.xy + in_corner * in_center_size.z * 0.5;
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,13 @@
+#version 400
+
+in vec4 g_color;
+layout ( location = 0 ) out uint frag_id;
+
+// For picking, the red channel holds the id of the path instead of a color. Fills and strokes
+// that are not drawn have alpha zero and leave the pixel alone.
+void main() {
+    if (g_color.a <= 0.0) {
+        discard;
+    }
+    frag_id = uint(round(g_color.r));
+}
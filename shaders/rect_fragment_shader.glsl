@@ -0,0 +1,41 @@
+#version 400
+
+in vec2 v_local;
+flat in vec2 v_half_size;
+flat in float v_radius;
+flat in float v_thickness;
+flat in vec4 v_fill;
+flat in vec4 v_stroke;
+layout ( location = 0 ) out vec4 frag_color;
+
+uniform float pixel_size;
+uniform int srgb;
+
+// Convert an sRGB encoded color to linear RGB, the sRGB framebuffer encodes it again on write.
+vec4 srgb_to_linear(vec4 color) {
+    vec3 low = color.rgb / 12.92;
+    vec3 high = pow((color.rgb + 0.055) / 1.055, vec3(2.4));
+    return vec4(mix(high, low, lessThanEqual(color.rgb, vec3(0.04045))), color.a);
+}
+
+// A color premultiplied by its alpha and a coverage.
+vec4 premultiply(vec4 color, float coverage) {
+    vec4 linear = srgb == 1 ? srgb_to_linear(color) : color;
+    return vec4(linear.rgb * linear.a, linear.a) * coverage;
+}
+
+// Cover the rectangle from its signed distance in pixels. Sharp corners measure the distance
+// along the axes so the stroke keeps square corners like a miter join, rounded ones measure it
+// to the corner arcs.
+void main() {
+    vec2 q = abs(v_local) - v_half_size + vec2(v_radius);
+    float d = v_radius > 0.0 ? length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - v_radius : max(q.x, q.y);
+    float distance = d / pixel_size;
+
+    vec4 fill = premultiply(v_fill, clamp(0.5 - distance, 0.0, 1.0));
+    vec4 stroke = premultiply(v_stroke, clamp(v_thickness / 2.0 + 0.5 - abs(distance), 0.0, 1.0));
+    frag_color = stroke + fill * (1.0 - stroke.a);
+    if (frag_color.a <= 0.0) {
+        discard;
+    }
+}
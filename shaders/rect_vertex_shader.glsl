@@ -0,0 +1,35 @@
+#version 400
+
+in vec2 in_corner;
+in vec4 in_center_half_size;
+in vec3 in_angle_radius_thickness;
+in vec4 in_fill;
+in vec4 in_stroke;
+
+uniform mat4 projection;
+uniform float pixel_size;
+
+out vec2 v_local;
+flat out vec2 v_half_size;
+flat out float v_radius;
+flat out float v_thickness;
+flat out vec4 v_fill;
+flat out vec4 v_stroke;
+
+// Place a corner of the quad around a rectangle, with room for half the stroke and a pixel of
+// anti-aliasing outside it. One instance per rectangle.
+void main() {
+    float thickness = in_angle_radius_thickness.z;
+    vec2 half_size = in_center_half_size.zw;
+    vec2 local = in_corner * (half_size + vec2((thickness / 2.0 + 1.0) * pixel_size));
+    float c = cos(in_angle_radius_thickness.x);
+    float s = sin(in_angle_radius_thickness.x);
+    vec2 position = in_center_half_size.xy + vec2(c * local.x - s * local.y, s * local.x + c * local.y);
+    v_local = local;
+    v_half_size = half_size;
+    v_radius = in_angle_radius_thickness.y;
+    v_thickness = thickness;
+    v_fill = in_fill;
+    v_stroke = in_stroke;
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,54 @@
+#version 400
+
+in vec2 v_uv;
+flat in float v_layer;
+layout ( location = 0 ) out vec4 frag_color;
+
+uniform sampler2DArray atlas;
+uniform float cell_size;
+uniform float spread;
+uniform vec4 color;
+uniform vec4 outline_color;
+uniform float outline_width;
+uniform vec4 shadow_color;
+uniform vec2 shadow_offset;
+uniform float shadow_softness;
+uniform int srgb;
+
+// Convert an sRGB encoded color to linear RGB, the sRGB framebuffer encodes it again on write.
+vec4 srgb_to_linear(vec4 color) {
+    vec3 low = color.rgb / 12.92;
+    vec3 high = pow((color.rgb + 0.055) / 1.055, vec3(2.4));
+    return vec4(mix(high, low, lessThanEqual(color.rgb, vec3(0.04045))), color.a);
+}
+
+// A color premultiplied by its alpha and a coverage.
+vec4 premultiply(vec4 color, float coverage) {
+    vec4 linear = srgb == 1 ? srgb_to_linear(color) : color;
+    return vec4(linear.rgb * linear.a, linear.a) * coverage;
+}
+
+// Distance to the edge in texels of the field, positive inside.
+float field_distance(vec2 uv) {
+    return (texture(atlas, vec3(uv, v_layer)).r - 0.5) * 2.0 * spread;
+}
+
+void main() {
+    vec2 texel = v_uv * cell_size;
+    float texels_per_pixel = max(0.5 * (length(dFdx(texel)) + length(dFdy(texel))), 1e-4);
+    // distances in pixels from here on
+    float edge_distance = field_distance(v_uv) / texels_per_pixel;
+
+    vec4 fill = premultiply(color, clamp(edge_distance + 0.5, 0.0, 1.0));
+    vec4 outline = outline_width > 0.0
+        ? premultiply(outline_color, clamp(edge_distance + outline_width + 0.5, 0.0, 1.0))
+        : vec4(0.0);
+    vec4 shape = fill + outline * (1.0 - fill.a);
+
+    vec2 shadow_uv = v_uv - shadow_offset.x * dFdx(v_uv) - shadow_offset.y * dFdy(v_uv);
+    float shadow_distance = field_distance(shadow_uv) / texels_per_pixel + outline_width;
+    vec4 shadow = premultiply(shadow_color, smoothstep(-shadow_softness - 0.5, shadow_softness + 0.5,
+                                                       shadow_distance));
+
+    frag_color = shape + shadow * (1.0 - shape.a);
+}
@@ -0,0 +1,18 @@
+#version 400
+
+in vec2 in_corner;
+in vec4 in_rect;
+in float in_layer;
+
+uniform mat4 projection;
+
+out vec2 v_uv;
+flat out float v_layer;
+
+// Place a corner of a distance field quad, one instance per quad. in_rect is the min and max
+// corners in drawing coordinates.
+void main() {
+    v_uv = in_corner;
+    v_layer = in_layer;
+    gl_Position = projection * vec4(mix(in_rect.xy, in_rect.zw, in_corner), 0.0, 1.0);
+}
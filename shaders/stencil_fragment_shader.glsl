@@ -0,0 +1,29 @@
+#version 400
+
+in vec2 v_curve;
+layout ( location = 0 ) out vec4 frag_color;
+
+uniform int cover;
+uniform vec4 color;
+uniform int srgb;
+
+// Convert an sRGB encoded color to linear RGB, the sRGB framebuffer encodes it again on write.
+vec4 srgb_to_linear(vec4 color) {
+    vec3 low = color.rgb / 12.92;
+    vec3 high = pow((color.rgb + 0.055) / 1.055, vec3(2.4));
+    return vec4(mix(high, low, lessThanEqual(color.rgb, vec3(0.04045))), color.a);
+}
+
+void main() {
+    if (cover == 0) {
+        // Loop-Blinn: a curve triangle has (u, v) = (0, 0), (1/2, 0) and (1, 1) at its corners,
+        // the quadratic curve is where u^2 = v and the part on the chord's side is kept. Other
+        // triangles have (0, 1) everywhere.
+        if (v_curve.x * v_curve.x - v_curve.y > 0.0) {
+            discard;
+        }
+        frag_color = vec4(0.0);
+    } else {
+        frag_color = srgb == 1 ? srgb_to_linear(color) : color;
+    }
+}
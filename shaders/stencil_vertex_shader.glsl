@@ -0,0 +1,14 @@
+#version 400
+
+in vec2 in_position;
+in vec2 in_curve;
+
+uniform mat4 projection;
+
+out vec2 v_curve;
+
+// Place a corner of a stencil triangle or of a path's cover quad.
+void main() {
+    v_curve = in_curve;
+    gl_Position = projection * vec4(in_position, 0.0, 1.0);
+}
@@ -5,19 +5,40 @@ layout(vertices = 3) out;
 in vec2 v_control_1[];
 in vec2 v_control_2[];
 in float v_edge[];
-in vec3 v_color[];
-in vec3 v_stroke_color[];
+in vec4 v_color[];
+in vec4 v_stroke_color[];
 in int v_do_fill[];
 
 out vec2 tc_control_1[];
 out vec2 tc_control_2[];
 out float tc_edge[];
-out vec3 tc_color[];
-out vec3 tc_stroke_color[];
+out vec4 tc_color[];
+out vec4 tc_stroke_color[];
 out int tc_do_fill[];
 
 uniform int outer_tess;
 uniform int inner_tess;
+uniform vec2 window_size;
+uniform float lod_tolerance;
+
+// the fill flag marking a patch as a stroke join
+const int JOIN_FLAG = 8;
+
+// Tessellation level for the edge from vertex i to the next one: enough straight pieces to stay
+// within lod_tolerance pixels of the curve (Wang's formula), capped at outer_tess. Both
+// triangles sharing an edge see the same control points, so they agree and leave no cracks.
+float edge_level(int i) {
+    if (lod_tolerance <= 0.0) {
+        return float(outer_tess);
+    }
+    vec2 scale = window_size / 2.0;
+    vec2 p0 = gl_in[i].gl_Position.xy * scale;
+    vec2 p1 = v_control_1[i] * scale;
+    vec2 p2 = v_control_2[i] * scale;
+    vec2 p3 = gl_in[(i + 1) % 3].gl_Position.xy * scale;
+    float m = max(length(p0 - 2.0 * p1 + p2), length(p1 - 2.0 * p2 + p3));
+    return clamp(ceil(sqrt(0.75 * m / lod_tolerance)), 1.0, float(outer_tess));
+}
 
 // Set the tessellation level and pass on needed information.
 void main() {
@@ -28,10 +49,17 @@ void main() {
     tc_stroke_color[gl_InvocationID] = v_stroke_color[gl_InvocationID];
     tc_do_fill[gl_InvocationID] = v_do_fill[gl_InvocationID];
     gl_out[gl_InvocationID].gl_Position = gl_in[gl_InvocationID].gl_Position;
-    if (gl_InvocationID == 0) {
+    if (gl_InvocationID == 0 && (v_do_fill[0] & JOIN_FLAG) != 0) {
+        // join patches are drawn from their corners alone
+        gl_TessLevelInner[0] = 1;
+        gl_TessLevelOuter[0] = 1;
+        gl_TessLevelOuter[1] = 1;
+        gl_TessLevelOuter[2] = 1;
+    } else if (gl_InvocationID == 0) {
         gl_TessLevelInner[0] = inner_tess;
-        gl_TessLevelOuter[0] = outer_tess;
-        gl_TessLevelOuter[1] = outer_tess;
-        gl_TessLevelOuter[2] = outer_tess;
+        // outer level k is for the edge opposite vertex k
+        gl_TessLevelOuter[0] = edge_level(1);
+        gl_TessLevelOuter[1] = edge_level(2);
+        gl_TessLevelOuter[2] = edge_level(0);
     }
 }
\ No newline at end of file
@@ -4,17 +4,17 @@ layout (triangles, equal_spacing, ccw) in;
 in vec2 tc_control_1[];
 in vec2 tc_control_2[];
 in float tc_edge[];
-in vec3 tc_color[];
-in vec3 tc_stroke_color[];
+in vec4 tc_color[];
+in vec4 tc_stroke_color[];
 in int  tc_do_fill[];
 
 out vec3 te_bary;
 out vec3 te_edge;
-out vec3 te_color;
+out vec4 te_color;
 out vec2 te_tan_ab;
 out vec2 te_tan_bc;
 out vec2 te_tan_ca;
-out vec3 te_stroke_color;
+out vec4 te_stroke_color;
 out int  te_do_fill;
 
 // Position tessellated triangles based on Bezier triangle equation. Calculate edge derivatives. Pass on needed info.
@@ -59,8 +59,10 @@ void main() {
     gl_Position = vec4(pos, gl_in[0].gl_Position.z, 1.0);
     te_bary = vec3(s, t, u);
     te_edge = vec3(tc_edge[0], tc_edge[1], tc_edge[2]);
-    te_color = tc_color[0];
-    te_stroke_color = tc_stroke_color[0];
+    // blended across the patch for fills with vertex colors, the same at every corner otherwise
+    te_color = s * tc_color[0] + t * tc_color[1] + u * tc_color[2];
+    // blended along the edges for strokes colored along the path, the same at every corner otherwise
+    te_stroke_color = s * tc_stroke_color[0] + t * tc_stroke_color[1] + u * tc_stroke_color[2];
     te_do_fill = tc_do_fill[0];
 }
 
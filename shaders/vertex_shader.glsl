@@ -1,29 +1,50 @@
 #version 400
 
-in vec3 in_position;
-in vec2 in_control_1;
-in vec2 in_control_2;
-in float in_edge;
-in vec3 in_color;
-in vec3 in_stroke_color;
-in int in_do_fill;
+// fixed locations in the order of the vertex buffers, the picking program shares them
+layout ( location = 0 ) in vec3 in_position;
+layout ( location = 1 ) in vec2 in_control_1;
+layout ( location = 2 ) in vec2 in_control_2;
+layout ( location = 4 ) in float in_edge;
+layout ( location = 3 ) in vec4 in_color;
+layout ( location = 5 ) in vec4 in_stroke_color;
+layout ( location = 6 ) in int in_do_fill;
 
 out vec2 v_control_1;
 out vec2 v_control_2;
 out float v_edge;
-out vec3 v_color;
-out vec3 v_stroke_color;
+out vec4 v_color;
+out vec4 v_stroke_color;
 out int v_do_fill;
 
 uniform mat4 projection;
+uniform vec2 window_size;
+// whether points are snapped to the pixel grid
+uniform bool crisp;
+// whether colors are multiplied by their alpha, so they blend across patches premultiplied
+uniform bool premultiplied;
+
+// the fill flag of paths with a stroke an odd number of pixels thick
+const int ODD_STROKE_FLAG = 256;
+
+// In crisp mode move a projected point to the nearest pixel corner, or to the nearest pixel center
+// on paths with odd stroke thicknesses so their strokes cover whole pixels.
+vec2 snap(vec2 p) {
+    if (!crisp) {
+        return p;
+    }
+    vec2 pixel = (p * 0.5 + 0.5) * window_size;
+    pixel = (in_do_fill & ODD_STROKE_FLAG) != 0 ? floor(pixel) + 0.5 : round(pixel);
+    return pixel / window_size * 2.0 - 1.0;
+}
 
 // Apply the projection matrix and pass on needed info.
 void main() {
-    gl_Position = projection * vec4(in_position, 1);
-    v_control_1 = (projection * vec4(in_control_1, 0, 1)).xy;
-    v_control_2 = (projection * vec4(in_control_2, 0, 1)).xy;
+    vec4 position = projection * vec4(in_position, 1);
+    gl_Position = vec4(snap(position.xy), position.zw);
+    v_control_1 = snap((projection * vec4(in_control_1, 0, 1)).xy);
+    v_control_2 = snap((projection * vec4(in_control_2, 0, 1)).xy);
     v_edge = in_edge;
-    v_color = in_color;
-    v_stroke_color = in_stroke_color;
+    v_color = premultiplied ? vec4(in_color.rgb * in_color.a, in_color.a) : in_color;
+    v_stroke_color = premultiplied ? vec4(in_stroke_color.rgb * in_stroke_color.a, in_stroke_color.a) : in_stroke_color;
     v_do_fill = in_do_fill;
 }
\ No newline at end of file
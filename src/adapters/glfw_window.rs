@@ -0,0 +1,128 @@
+use std::cell::Cell;
+use std::os::raw::c_void;
+use glfw::{Action, Context, PWindow, WindowEvent};
+use super::super::gl2d::drawing::{Drawing, Window};
+use super::super::input::{InputEvent, Key, Modifiers, MouseButton};
+
+/// A GLFW window usable as a TRDL window.
+pub struct GlfwWindow {
+    window: PWindow,
+    // the last cursor position, in framebuffer pixels with the origin at the bottom left
+    cursor: Cell<(f32, f32)>
+}
+
+impl GlfwWindow {
+    /// Constructor, takes a window created with an OpenGL 4 context. Enable framebuffer size and
+    /// content scale polling on it for `handle_event` to see resizes, and cursor, mouse button,
+    /// scroll and key polling for `input_event`.
+    pub fn new(window: PWindow) -> Self {
+        GlfwWindow { window, cursor: Cell::new((0f32, 0f32)) }
+    }
+
+    /// The wrapped window.
+    pub fn window(&self) -> &PWindow {
+        &self.window
+    }
+
+    /// The wrapped window, mutably, for polling and swapping buffers.
+    pub fn window_mut(&mut self) -> &mut PWindow {
+        &mut self.window
+    }
+
+    /// Resize the drawing when the framebuffer is resized or the content scale changes. Other
+    /// events are ignored.
+    pub fn handle_event(&self, event: &WindowEvent, drawing: &mut Drawing<'_, Self>) {
+        let (width, height) = match *event {
+            WindowEvent::FramebufferSize(width, height) => (width, height),
+            WindowEvent::ContentScale(_, _) => self.window.get_framebuffer_size(),
+            _ => return
+        };
+        drawing.set_size(width.max(0) as u32, height.max(0) as u32);
+    }
+
+    /// Convert a GLFW event to a TRDL input event, for `Drawing::handle_event`.
+    pub fn input_event(&self, event: &WindowEvent) -> Option<InputEvent> {
+        match *event {
+            WindowEvent::CursorPos(x, y) => {
+                // GLFW reports the cursor in screen coordinates, which differ from framebuffer
+                // pixels on high density displays
+                let (width, height) = self.window.get_size();
+                let (fb_width, fb_height) = self.window.get_framebuffer_size();
+                let scale_x = fb_width as f32 / width.max(1) as f32;
+                let scale_y = fb_height as f32 / height.max(1) as f32;
+                let position = (x as f32 * scale_x, fb_height as f32 - y as f32 * scale_y);
+                self.cursor.set(position);
+                Some(InputEvent::MouseMove { position })
+            }
+            WindowEvent::MouseButton(button, action, _) => {
+                let button = match button {
+                    glfw::MouseButtonLeft => MouseButton::Left,
+                    glfw::MouseButtonRight => MouseButton::Right,
+                    glfw::MouseButtonMiddle => MouseButton::Middle,
+                    other => MouseButton::Other(other as u16)
+                };
+                let position = self.cursor.get();
+                match action {
+                    Action::Press => Some(InputEvent::MouseDown { button, position }),
+                    Action::Release => Some(InputEvent::MouseUp { button, position }),
+                    Action::Repeat => None
+                }
+            }
+            WindowEvent::Scroll(_, y) => {
+                Some(InputEvent::MouseWheel { delta: y as f32, position: self.cursor.get() })
+            }
+            WindowEvent::Key(key, _, action, mods) => {
+                let key = match key {
+                    glfw::Key::Space => Key::Character(' '),
+                    glfw::Key::Escape => Key::Escape,
+                    glfw::Key::Enter | glfw::Key::KpEnter => Key::Enter,
+                    glfw::Key::Tab => Key::Tab,
+                    glfw::Key::Backspace => Key::Backspace,
+                    glfw::Key::Delete => Key::Delete,
+                    glfw::Key::Left => Key::Left,
+                    glfw::Key::Right => Key::Right,
+                    glfw::Key::Up => Key::Up,
+                    glfw::Key::Down => Key::Down,
+                    glfw::Key::Home => Key::Home,
+                    glfw::Key::End => Key::End,
+                    glfw::Key::PageUp => Key::PageUp,
+                    glfw::Key::PageDown => Key::PageDown,
+                    glfw::Key::LeftShift | glfw::Key::RightShift => Key::Shift,
+                    glfw::Key::LeftControl | glfw::Key::RightControl => Key::Control,
+                    glfw::Key::LeftAlt | glfw::Key::RightAlt => Key::Alt,
+                    // the layout dependent name is the character for text keys
+                    other => match other.get_name().and_then(|name| name.chars().next()) {
+                        Some(c) => Key::Character(c),
+                        None => Key::Other
+                    }
+                };
+                let modifiers = Modifiers {
+                    shift: mods.contains(glfw::Modifiers::Shift),
+                    control: mods.contains(glfw::Modifiers::Control),
+                    alt: mods.contains(glfw::Modifiers::Alt),
+                    logo: mods.contains(glfw::Modifiers::Super)
+                };
+                match action {
+                    Action::Press | Action::Repeat => Some(InputEvent::KeyDown { key, modifiers }),
+                    Action::Release => Some(InputEvent::KeyUp { key, modifiers })
+                }
+            }
+            _ => None
+        }
+    }
+}
+
+impl Window for GlfwWindow {
+    fn set_context(&self) {
+        glfw::make_context_current(Some(&*self.window as &dyn Context));
+    }
+
+    fn load_fn(&self, addr: &str) -> *const c_void {
+        self.window.glfw.get_proc_address_raw(addr) as *const c_void
+    }
+
+    fn swap_buffers(&self) {
+        // Context::swap_buffers wants the window mutably, the raw call does not
+        unsafe { glfw::ffi::glfwSwapBuffers(self.window.window_ptr()) };
+    }
+}
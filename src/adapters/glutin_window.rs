@@ -0,0 +1,196 @@
+use std::cell::{Cell, Ref, RefCell};
+use std::os::raw::c_void;
+use glutin::{Api, ContextBuilder, ContextWrapper, CreationError, GlRequest, PossiblyCurrent, WindowedContext};
+use glutin::dpi::PhysicalSize;
+use glutin::event::{self, ElementState, Event, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+use glutin::event_loop::{ControlFlow, EventLoop};
+use glutin::platform::run_return::EventLoopExtRunReturn;
+use glutin::window::{Window as GlutinWinitWindow, WindowBuilder};
+use super::super::gl2d::drawing::{Drawing, Window};
+use super::super::frame::{self, FrameContext, FrameTimer, LoopConfig};
+use super::super::input::{InputEvent, Key, Modifiers, MouseButton, PIXELS_PER_LINE};
+
+/// A glutin windowed context usable as a TRDL window.
+pub struct GlutinWindow {
+    // glutin consumes the context to make it current, so it lives in an Option we can take from
+    context: RefCell<Option<WindowedContext<PossiblyCurrent>>>,
+    // glutin only reports these in their own events, input_event needs them for the others
+    cursor: Cell<(f32, f32)>,
+    modifiers: Cell<Modifiers>
+}
+
+impl GlutinWindow {
+    /// Constructor, takes a context made with `glutin::ContextBuilder::build_windowed`.
+    pub fn new(context: WindowedContext<PossiblyCurrent>) -> Self {
+        GlutinWindow {
+            context: RefCell::new(Some(context)),
+            cursor: Cell::new((0f32, 0f32)),
+            modifiers: Cell::new(Modifiers::default())
+        }
+    }
+
+    /// Open a window with an OpenGL 4 context and vsync, along with the event loop that drives
+    /// it. Pass both to `run`.
+    pub fn open(title: &str, width: u32, height: u32) -> Result<(EventLoop<()>, Self), CreationError> {
+        let event_loop = EventLoop::new();
+        let window = WindowBuilder::new()
+            .with_title(title)
+            .with_inner_size(PhysicalSize::new(width, height));
+        let context = ContextBuilder::new()
+            .with_gl(GlRequest::Specific(Api::OpenGl, (4, 0)))
+            .with_vsync(true)
+            .build_windowed(window, &event_loop)?;
+        let context = unsafe { context.make_current() }
+            .map_err(|(_, err)| CreationError::OsError(err.to_string()))?;
+        Ok((event_loop, GlutinWindow::new(context)))
+    }
+
+    /// Run frames until the window is closed or the closure calls `quit`. Resizes are handled,
+    /// the other window events are converted to input events and handed to the closure with the
+    /// frame they arrived before. After the closure the drawing is drawn and the buffers swapped.
+    pub fn run<F>(&self, event_loop: &mut EventLoop<()>, drawing: &mut Drawing<'_, Self>, config: LoopConfig,
+                  mut frame: F)
+            where F: FnMut(&mut FrameContext<'_, '_, Self>, &[InputEvent]) {
+        let mut timer = FrameTimer::new(config);
+        let mut events = Vec::new();
+        event_loop.run_return(|event, _, control_flow| {
+            *control_flow = ControlFlow::Poll;
+            match event {
+                Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+                    *control_flow = ControlFlow::Exit;
+                }
+                Event::WindowEvent { ref event, .. } => {
+                    self.handle_event(event, drawing);
+                    events.extend(self.input_event(event));
+                }
+                Event::MainEventsCleared => {
+                    if frame::run_frame(&mut timer, drawing, |context| frame(context, &events)) {
+                        let _ = self.swap_buffers();
+                        timer.end_frame();
+                    } else {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    events.clear();
+                }
+                _ => ()
+            }
+        });
+    }
+
+    /// The wrapped context.
+    pub fn context(&self) -> Ref<'_, ContextWrapper<PossiblyCurrent, GlutinWinitWindow>> {
+        Ref::map(self.context.borrow(), |context| context.as_ref().unwrap())
+    }
+
+    /// Show the drawn frame.
+    pub fn swap_buffers(&self) -> Result<(), glutin::ContextError> {
+        self.context().swap_buffers()
+    }
+
+    /// Resize the context and the drawing when the window is resized or moved to a monitor with a
+    /// different scale factor. Other events are ignored.
+    pub fn handle_event(&self, event: &WindowEvent, drawing: &mut Drawing<'_, Self>) {
+        let size = match *event {
+            WindowEvent::Resized(size) => size,
+            WindowEvent::ScaleFactorChanged { ref new_inner_size, .. } => **new_inner_size,
+            _ => return
+        };
+        self.context().resize(size);
+        drawing.set_size(size.width, size.height);
+    }
+
+    /// Convert a glutin event to a TRDL input event, for `Drawing::handle_event`. Text keys are
+    /// reported from `ReceivedCharacter`, so they have no `KeyUp`.
+    pub fn input_event(&self, event: &WindowEvent) -> Option<InputEvent> {
+        match *event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let height = self.context().window().inner_size().height as f32;
+                let position = (position.x as f32, height - position.y as f32);
+                self.cursor.set(position);
+                Some(InputEvent::MouseMove { position })
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    event::MouseButton::Left => MouseButton::Left,
+                    event::MouseButton::Right => MouseButton::Right,
+                    event::MouseButton::Middle => MouseButton::Middle,
+                    event::MouseButton::Other(n) => MouseButton::Other(n)
+                };
+                let position = self.cursor.get();
+                Some(match state {
+                    ElementState::Pressed => InputEvent::MouseDown { button, position },
+                    ElementState::Released => InputEvent::MouseUp { button, position }
+                })
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / PIXELS_PER_LINE
+                };
+                Some(InputEvent::MouseWheel { delta, position: self.cursor.get() })
+            }
+            WindowEvent::ModifiersChanged(state) => {
+                self.modifiers.set(Modifiers {
+                    shift: state.shift(),
+                    control: state.ctrl(),
+                    alt: state.alt(),
+                    logo: state.logo()
+                });
+                None
+            }
+            WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+                Some(InputEvent::KeyDown { key: Key::Character(c), modifiers: self.modifiers.get() })
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                let key = match input.virtual_keycode {
+                    Some(VirtualKeyCode::Escape) => Key::Escape,
+                    Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::NumpadEnter) => Key::Enter,
+                    Some(VirtualKeyCode::Tab) => Key::Tab,
+                    Some(VirtualKeyCode::Back) => Key::Backspace,
+                    Some(VirtualKeyCode::Delete) => Key::Delete,
+                    Some(VirtualKeyCode::Left) => Key::Left,
+                    Some(VirtualKeyCode::Right) => Key::Right,
+                    Some(VirtualKeyCode::Up) => Key::Up,
+                    Some(VirtualKeyCode::Down) => Key::Down,
+                    Some(VirtualKeyCode::Home) => Key::Home,
+                    Some(VirtualKeyCode::End) => Key::End,
+                    Some(VirtualKeyCode::PageUp) => Key::PageUp,
+                    Some(VirtualKeyCode::PageDown) => Key::PageDown,
+                    Some(VirtualKeyCode::LShift) | Some(VirtualKeyCode::RShift) => Key::Shift,
+                    Some(VirtualKeyCode::LControl) | Some(VirtualKeyCode::RControl) => Key::Control,
+                    Some(VirtualKeyCode::LAlt) | Some(VirtualKeyCode::RAlt) => Key::Alt,
+                    // text keys come through ReceivedCharacter
+                    _ => return None
+                };
+                let modifiers = self.modifiers.get();
+                Some(match input.state {
+                    ElementState::Pressed => InputEvent::KeyDown { key, modifiers },
+                    ElementState::Released => InputEvent::KeyUp { key, modifiers }
+                })
+            }
+            _ => None
+        }
+    }
+}
+
+impl Window for GlutinWindow {
+    fn set_context(&self) {
+        let mut slot = self.context.borrow_mut();
+        if let Some(context) = slot.take() {
+            let context = match unsafe { context.make_current() } {
+                Ok(context) => context,
+                Err((context, _)) => context
+            };
+            *slot = Some(context);
+        }
+    }
+
+    fn load_fn(&self, addr: &str) -> *const c_void {
+        self.context().get_proc_address(addr) as *const c_void
+    }
+
+    fn swap_buffers(&self) {
+        // a lost context shows up on the next draw
+        let _ = self.context().swap_buffers();
+    }
+}
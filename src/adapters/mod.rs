@@ -0,0 +1,21 @@
+//! Ready made `Window` implementations for common windowing libraries. Each one is behind a cargo
+//! feature of the same name (`glutin`, `winit`, `glfw`, `sdl2`) and also forwards resize and
+//! scale factor events to the drawing.
+
+#[cfg(feature = "glutin")]
+mod glutin_window;
+#[cfg(feature = "winit")]
+mod winit_window;
+#[cfg(feature = "glfw")]
+mod glfw_window;
+#[cfg(feature = "sdl2")]
+mod sdl2_window;
+
+#[cfg(feature = "glutin")]
+pub use self::glutin_window::GlutinWindow;
+#[cfg(feature = "winit")]
+pub use self::winit_window::WinitWindow;
+#[cfg(feature = "glfw")]
+pub use self::glfw_window::GlfwWindow;
+#[cfg(feature = "sdl2")]
+pub use self::sdl2_window::Sdl2Window;
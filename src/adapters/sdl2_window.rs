@@ -0,0 +1,150 @@
+use std::os::raw::c_void;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse;
+use sdl2::video::{GLContext, Window as Sdl2BaseWindow};
+use super::super::gl2d::drawing::{Drawing, Window};
+use super::super::input::{InputEvent, Key, Modifiers, MouseButton};
+
+/// An SDL2 window and its OpenGL context usable as a TRDL window.
+pub struct Sdl2Window {
+    window: Sdl2BaseWindow,
+    context: GLContext
+}
+
+impl Sdl2Window {
+    /// Constructor, takes a window built with `.opengl()` and a context created from it.
+    pub fn new(window: Sdl2BaseWindow, context: GLContext) -> Self {
+        Sdl2Window { window, context }
+    }
+
+    /// The wrapped window.
+    pub fn window(&self) -> &Sdl2BaseWindow {
+        &self.window
+    }
+
+    /// Show the drawn frame.
+    pub fn swap_buffers(&self) {
+        self.window.gl_swap_window();
+    }
+
+    /// Resize the drawing when the window is resized or moved to a display with a different pixel
+    /// density. Other events are ignored.
+    pub fn handle_event(&self, event: &Event, drawing: &mut Drawing<'_, Self>) {
+        if let Event::Window { window_id, ref win_event, .. } = *event {
+            if window_id != self.window.id() {
+                return;
+            }
+            match *win_event {
+                WindowEvent::SizeChanged(_, _) | WindowEvent::Resized(_, _) |
+                WindowEvent::DisplayChanged(_) => {
+                    let (width, height) = self.window.drawable_size();
+                    drawing.set_size(width, height);
+                }
+                _ => ()
+            }
+        }
+    }
+
+    /// Convert an SDL event for this window to a TRDL input event, for `Drawing::handle_event`.
+    pub fn input_event(&self, event: &Event) -> Option<InputEvent> {
+        match *event {
+            Event::MouseMotion { window_id, x, y, .. } if window_id == self.window.id() => {
+                Some(InputEvent::MouseMove { position: self.to_pixels(x, y) })
+            }
+            Event::MouseButtonDown { window_id, mouse_btn, x, y, .. }
+                    if window_id == self.window.id() => {
+                Some(InputEvent::MouseDown { button: Self::button(mouse_btn),
+                                             position: self.to_pixels(x, y) })
+            }
+            Event::MouseButtonUp { window_id, mouse_btn, x, y, .. }
+                    if window_id == self.window.id() => {
+                Some(InputEvent::MouseUp { button: Self::button(mouse_btn),
+                                           position: self.to_pixels(x, y) })
+            }
+            Event::MouseWheel { window_id, precise_y, mouse_x, mouse_y, .. }
+                    if window_id == self.window.id() => {
+                Some(InputEvent::MouseWheel { delta: precise_y,
+                                              position: self.to_pixels(mouse_x, mouse_y) })
+            }
+            Event::KeyDown { window_id, keycode: Some(keycode), keymod, .. }
+                    if window_id == self.window.id() => {
+                Some(InputEvent::KeyDown { key: Self::key(keycode), modifiers: Self::modifiers(keymod) })
+            }
+            Event::KeyUp { window_id, keycode: Some(keycode), keymod, .. }
+                    if window_id == self.window.id() => {
+                Some(InputEvent::KeyUp { key: Self::key(keycode), modifiers: Self::modifiers(keymod) })
+            }
+            _ => None
+        }
+    }
+
+    // SDL mouse positions are in window coordinates from the top left, which differ from
+    // drawable pixels on high density displays
+    fn to_pixels(&self, x: i32, y: i32) -> (f32, f32) {
+        let (width, height) = self.window.size();
+        let (pixel_width, pixel_height) = self.window.drawable_size();
+        let scale_x = pixel_width as f32 / width.max(1) as f32;
+        let scale_y = pixel_height as f32 / height.max(1) as f32;
+        (x as f32 * scale_x, pixel_height as f32 - y as f32 * scale_y)
+    }
+
+    fn button(button: mouse::MouseButton) -> MouseButton {
+        match button {
+            mouse::MouseButton::Left => MouseButton::Left,
+            mouse::MouseButton::Right => MouseButton::Right,
+            mouse::MouseButton::Middle => MouseButton::Middle,
+            other => MouseButton::Other(other as u16)
+        }
+    }
+
+    fn key(keycode: Keycode) -> Key {
+        match keycode {
+            Keycode::ESCAPE => Key::Escape,
+            Keycode::RETURN | Keycode::KP_ENTER => Key::Enter,
+            Keycode::TAB => Key::Tab,
+            Keycode::BACKSPACE => Key::Backspace,
+            Keycode::DELETE => Key::Delete,
+            Keycode::LEFT => Key::Left,
+            Keycode::RIGHT => Key::Right,
+            Keycode::UP => Key::Up,
+            Keycode::DOWN => Key::Down,
+            Keycode::HOME => Key::Home,
+            Keycode::END => Key::End,
+            Keycode::PAGEUP => Key::PageUp,
+            Keycode::PAGEDOWN => Key::PageDown,
+            Keycode::LSHIFT | Keycode::RSHIFT => Key::Shift,
+            Keycode::LCTRL | Keycode::RCTRL => Key::Control,
+            Keycode::LALT | Keycode::RALT => Key::Alt,
+            // printable keys have their character as the key code
+            other => match ::std::char::from_u32(other.into_i32() as u32) {
+                Some(c) if !c.is_control() => Key::Character(c),
+                _ => Key::Other
+            }
+        }
+    }
+
+    fn modifiers(keymod: Mod) -> Modifiers {
+        Modifiers {
+            shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            control: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+            alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+            logo: keymod.intersects(Mod::LGUIMOD | Mod::RGUIMOD)
+        }
+    }
+}
+
+impl Window for Sdl2Window {
+    fn set_context(&self) {
+        // a failure leaves the previous context current, which the draw calls will show
+        let _ = self.window.gl_make_current(&self.context);
+    }
+
+    fn load_fn(&self, addr: &str) -> *const c_void {
+        self.window.subsystem().gl_get_proc_address(addr) as *const c_void
+    }
+
+    fn swap_buffers(&self) {
+        self.window.gl_swap_window();
+    }
+}
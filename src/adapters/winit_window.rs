@@ -0,0 +1,150 @@
+use std::cell::Cell;
+use std::os::raw::c_void;
+use winit::event::{self, ElementState, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{self, NamedKey};
+use winit::window::Window as WinitBaseWindow;
+use super::super::gl2d::drawing::{Drawing, Window};
+use super::super::input::{InputEvent, Key, Modifiers, MouseButton, PIXELS_PER_LINE};
+
+/// A winit window usable as a TRDL window. winit does not create OpenGL contexts, so the
+/// functions to make the context current and to load OpenGL functions come from whichever
+/// library created it (e.g. glutin with glutin-winit).
+pub struct WinitWindow {
+    window: WinitBaseWindow,
+    make_current: Box<dyn Fn()>,
+    load_fn: Box<dyn Fn(&str) -> *const c_void>,
+    swap_buffers: Option<Box<dyn Fn()>>,
+    // winit only reports these in their own events, input_event needs them for the others
+    cursor: Cell<(f32, f32)>,
+    modifiers: Cell<Modifiers>
+}
+
+impl WinitWindow {
+    /// Constructor, takes the window, a function that makes its OpenGL context current and a
+    /// function that looks up OpenGL functions by name.
+    pub fn new<F, G>(window: WinitBaseWindow, make_current: F, load_fn: G) -> Self
+            where F: Fn() + 'static, G: Fn(&str) -> *const c_void + 'static {
+        WinitWindow {
+            window,
+            make_current: Box::new(make_current),
+            load_fn: Box::new(load_fn),
+            swap_buffers: None,
+            cursor: Cell::new((0f32, 0f32)),
+            modifiers: Cell::new(Modifiers::default())
+        }
+    }
+
+    /// Set the function that presents a drawn frame, for `run_loop`.
+    pub fn with_swap_buffers<F>(mut self, swap_buffers: F) -> Self where F: Fn() + 'static {
+        self.swap_buffers = Some(Box::new(swap_buffers));
+        self
+    }
+
+    /// The wrapped window.
+    pub fn window(&self) -> &WinitBaseWindow {
+        &self.window
+    }
+
+    /// Resize the drawing when the window is resized or moved to a monitor with a different scale
+    /// factor. Other events are ignored. The OpenGL surface has to be resized by its owner.
+    pub fn handle_event(&self, event: &WindowEvent, drawing: &mut Drawing<'_, Self>) {
+        let size = match *event {
+            WindowEvent::Resized(size) => size,
+            WindowEvent::ScaleFactorChanged { .. } => self.window.inner_size(),
+            _ => return
+        };
+        drawing.set_size(size.width, size.height);
+    }
+
+    /// Convert a winit event to a TRDL input event, for `Drawing::handle_event`.
+    pub fn input_event(&self, event: &WindowEvent) -> Option<InputEvent> {
+        match *event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let height = self.window.inner_size().height as f32;
+                let position = (position.x as f32, height - position.y as f32);
+                self.cursor.set(position);
+                Some(InputEvent::MouseMove { position })
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    event::MouseButton::Left => MouseButton::Left,
+                    event::MouseButton::Right => MouseButton::Right,
+                    event::MouseButton::Middle => MouseButton::Middle,
+                    event::MouseButton::Back => MouseButton::Other(3),
+                    event::MouseButton::Forward => MouseButton::Other(4),
+                    event::MouseButton::Other(n) => MouseButton::Other(n)
+                };
+                let position = self.cursor.get();
+                Some(match state {
+                    ElementState::Pressed => InputEvent::MouseDown { button, position },
+                    ElementState::Released => InputEvent::MouseUp { button, position }
+                })
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / PIXELS_PER_LINE
+                };
+                Some(InputEvent::MouseWheel { delta, position: self.cursor.get() })
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                let state = modifiers.state();
+                self.modifiers.set(Modifiers {
+                    shift: state.shift_key(),
+                    control: state.control_key(),
+                    alt: state.alt_key(),
+                    logo: state.super_key()
+                });
+                None
+            }
+            WindowEvent::KeyboardInput { ref event, .. } => {
+                let key = match event.logical_key {
+                    keyboard::Key::Character(ref text) => match text.chars().next() {
+                        Some(c) => Key::Character(c),
+                        None => return None
+                    },
+                    keyboard::Key::Named(NamedKey::Space) => Key::Character(' '),
+                    keyboard::Key::Named(NamedKey::Escape) => Key::Escape,
+                    keyboard::Key::Named(NamedKey::Enter) => Key::Enter,
+                    keyboard::Key::Named(NamedKey::Tab) => Key::Tab,
+                    keyboard::Key::Named(NamedKey::Backspace) => Key::Backspace,
+                    keyboard::Key::Named(NamedKey::Delete) => Key::Delete,
+                    keyboard::Key::Named(NamedKey::ArrowLeft) => Key::Left,
+                    keyboard::Key::Named(NamedKey::ArrowRight) => Key::Right,
+                    keyboard::Key::Named(NamedKey::ArrowUp) => Key::Up,
+                    keyboard::Key::Named(NamedKey::ArrowDown) => Key::Down,
+                    keyboard::Key::Named(NamedKey::Home) => Key::Home,
+                    keyboard::Key::Named(NamedKey::End) => Key::End,
+                    keyboard::Key::Named(NamedKey::PageUp) => Key::PageUp,
+                    keyboard::Key::Named(NamedKey::PageDown) => Key::PageDown,
+                    keyboard::Key::Named(NamedKey::Shift) => Key::Shift,
+                    keyboard::Key::Named(NamedKey::Control) => Key::Control,
+                    keyboard::Key::Named(NamedKey::Alt) => Key::Alt,
+                    _ => Key::Other
+                };
+                let modifiers = self.modifiers.get();
+                Some(match event.state {
+                    ElementState::Pressed => InputEvent::KeyDown { key, modifiers },
+                    ElementState::Released => InputEvent::KeyUp { key, modifiers }
+                })
+            }
+            _ => None
+        }
+    }
+}
+
+impl Window for WinitWindow {
+    fn set_context(&self) {
+        (self.make_current)();
+    }
+
+    fn load_fn(&self, addr: &str) -> *const c_void {
+        (self.load_fn)(addr)
+    }
+
+    fn swap_buffers(&self) {
+        if let Some(ref swap_buffers) = self.swap_buffers {
+            swap_buffers();
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Angles that know their unit.
+
+use std::f32::consts::PI;
+use std::ops::{Add, Neg, Sub};
+
+/// An angle, counter clockwise. Functions that take one accept an `Angle` or a plain f32 in
+/// radians, so `Angle::degrees(90f32)` and `std::f32::consts::FRAC_PI_2` mean the same.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Angle {
+    radians: f32
+}
+
+impl Angle {
+    /// An angle in radians.
+    pub fn radians(radians: f32) -> Self {
+        Angle { radians }
+    }
+
+    /// An angle in degrees.
+    pub fn degrees(degrees: f32) -> Self {
+        Angle { radians: degrees.to_radians() }
+    }
+
+    /// An angle in whole turns, 1 being 360 degrees.
+    pub fn turns(turns: f32) -> Self {
+        Angle { radians: turns * 2f32 * PI }
+    }
+
+    /// The angle in radians.
+    pub fn to_radians(self) -> f32 {
+        self.radians
+    }
+
+    /// The angle in degrees.
+    pub fn to_degrees(self) -> f32 {
+        self.radians.to_degrees()
+    }
+}
+
+impl From<f32> for Angle {
+    /// Plain numbers are radians.
+    fn from(radians: f32) -> Angle {
+        Angle { radians }
+    }
+}
+
+impl Add for Angle {
+    type Output = Angle;
+
+    fn add(self, other: Angle) -> Angle {
+        Angle { radians: self.radians + other.radians }
+    }
+}
+
+impl Sub for Angle {
+    type Output = Angle;
+
+    fn sub(self, other: Angle) -> Angle {
+        Angle { radians: self.radians - other.radians }
+    }
+}
+
+impl Neg for Angle {
+    type Output = Angle;
+
+    fn neg(self) -> Angle {
+        Angle { radians: -self.radians }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Angle;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_units() {
+        assert!((Angle::degrees(90f32).to_radians() - PI / 2f32).abs() < 1e-6);
+        assert!((Angle::turns(0.5f32).to_degrees() - 180f32).abs() < 1e-4);
+        assert_eq!(Angle::from(1f32), Angle::radians(1f32));
+        assert_eq!(Angle::radians(1f32) - Angle::radians(0.25f32), -Angle::radians(-0.75f32));
+    }
+}
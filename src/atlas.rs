@@ -0,0 +1,163 @@
+//! Packing many small images into a few big ones, so icons and glyphs can share textures and be
+//! drawn without binding a texture for each. Images are packed on shelves: rows as tall as the
+//! tallest image on them, filled left to right.
+
+use super::image::ImageData;
+use super::TrdlError;
+
+/// Where an image was packed in a `TextureAtlas`, in texels from the top left of its page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRegion {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32
+}
+
+impl AtlasRegion {
+    /// The texture coordinates of the region's top left and bottom right corners on a page
+    /// page_size texels across.
+    pub fn tex_coords(&self, page_size: u32) -> ((f32, f32), (f32, f32)) {
+        let size = page_size as f32;
+        ((self.x as f32 / size, self.y as f32 / size),
+         ((self.x + self.width) as f32 / size, (self.y + self.height) as f32 / size))
+    }
+}
+
+// A row of a page, from y down height texels, filled up to x.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32
+}
+
+// A square page and its shelves, top to bottom.
+#[derive(Debug, Clone)]
+struct Page {
+    pixels: Vec<u8>,
+    shelves: Vec<Shelf>,
+    // texels covered by images
+    used: u64
+}
+
+/// Square RGBA pages with many images packed into each, see `add`. Upload each page as one
+/// texture, again after `add` changed it.
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    page_size: u32,
+    padding: u32,
+    pages: Vec<Page>
+}
+
+impl TextureAtlas {
+    /// An empty atlas of page_size by page_size pages, with padding transparent texels between
+    /// images so filtering does not bleed one into the next.
+    pub fn new(page_size: u32, padding: u32) -> Self {
+        TextureAtlas { page_size: page_size.max(1), padding, pages: Vec::new() }
+    }
+
+    /// Width and height of each page in texels.
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// The number of pages.
+    pub fn num_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// A page as an image, rows top to bottom. Panics if there is no such page.
+    pub fn page(&self, page: usize) -> ImageData {
+        ImageData::new(self.page_size, self.page_size, self.pages[page].pixels.clone())
+            .expect("pages are page_size square")
+    }
+
+    /// The fraction of the pages' area covered by images, from 0 to 1.
+    pub fn occupancy(&self) -> f32 {
+        if self.pages.is_empty() {
+            return 0f32;
+        }
+        let used: u64 = self.pages.iter().map(|page| page.used).sum();
+        (used as f64 / (self.pages.len() as f64 * self.page_size as f64 * self.page_size as f64)) as f32
+    }
+
+    /// Copy an image into the atlas: onto the lowest shelf it fits on, a new shelf below the
+    /// others or a new page. Fails with `InvalidImageSize` for images bigger than a page.
+    pub fn add(&mut self, image: &ImageData) -> Result<AtlasRegion, TrdlError> {
+        let (width, height) = (image.width(), image.height());
+        if width > self.page_size || height > self.page_size {
+            return Err(TrdlError::InvalidImageSize);
+        }
+        let found = (0..self.pages.len()).find_map(|page| self.place(page, width, height).map(|spot| (page, spot)));
+        let (page, (x, y)) = match found {
+            Some(found) => found,
+            None => {
+                let size = self.page_size as usize;
+                self.pages.push(Page { pixels: vec![0u8; 4 * size * size], shelves: Vec::new(), used: 0 });
+                let page = self.pages.len() - 1;
+                (page, self.place(page, width, height).expect("an empty page fits any image"))
+            }
+        };
+        let entry = &mut self.pages[page];
+        let row_bytes = 4 * width as usize;
+        for row in 0..height as usize {
+            let start = 4 * ((y as usize + row) * self.page_size as usize + x as usize);
+            entry.pixels[start..start + row_bytes].copy_from_slice(&image.pixels()[row * row_bytes..(row + 1) * row_bytes]);
+        }
+        entry.used += width as u64 * height as u64;
+        Ok(AtlasRegion { page, x, y, width, height })
+    }
+
+    // Find room for a width by height image on a page and take it, returning its top left. Of
+    // the shelves it fits on the one wasting the least height is used.
+    fn place(&mut self, page: usize, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (size, padding) = (self.page_size, self.padding);
+        let shelves = &mut self.pages[page].shelves;
+        let best = shelves.iter_mut()
+            .filter(|shelf| shelf.height >= height && shelf.x + width <= size)
+            .min_by_key(|shelf| shelf.height - height);
+        if let Some(shelf) = best {
+            let spot = (shelf.x, shelf.y);
+            shelf.x = (shelf.x + width + padding).min(size);
+            return Some(spot);
+        }
+        let top = shelves.last().map_or(0, |shelf| (shelf.y + shelf.height + padding).min(size));
+        if top + height > size {
+            return None;
+        }
+        shelves.push(Shelf { y: top, height, x: (width + padding).min(size) });
+        Some((0, top))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AtlasRegion, TextureAtlas};
+    use super::super::image::ImageData;
+
+    fn image(width: u32, height: u32, value: u8) -> ImageData {
+        ImageData::new(width, height, vec![value; 4 * (width * height) as usize]).unwrap()
+    }
+
+    #[test]
+    fn test_shelf_packing() {
+        let mut atlas = TextureAtlas::new(16, 1);
+        assert_eq!(atlas.occupancy(), 0f32);
+        assert_eq!(atlas.add(&image(4, 4, 1)).unwrap(), AtlasRegion { page: 0, x: 0, y: 0, width: 4, height: 4 });
+        // next to the first on its shelf, then on a new shelf when taller
+        assert_eq!(atlas.add(&image(4, 3, 2)).unwrap(), AtlasRegion { page: 0, x: 5, y: 0, width: 4, height: 3 });
+        assert_eq!(atlas.add(&image(8, 6, 3)).unwrap(), AtlasRegion { page: 0, x: 0, y: 5, width: 8, height: 6 });
+        assert_eq!(atlas.occupancy(), (16 + 12 + 48) as f32 / 256f32);
+        let page = atlas.page(0);
+        assert_eq!(&page.pixels()[4 * 5..4 * 6], &[2u8; 4]);
+        assert_eq!(&page.pixels()[4 * (5 * 16)..4 * (5 * 16 + 1)], &[3u8; 4]);
+        // a full page starts another
+        assert_eq!(atlas.add(&image(16, 8, 4)).unwrap().page, 1);
+        assert_eq!(atlas.num_pages(), 2);
+        assert!(atlas.add(&image(17, 1, 0)).is_err());
+        assert_eq!(AtlasRegion { page: 0, x: 4, y: 8, width: 4, height: 8 }.tex_coords(16),
+                   ((0.25f32, 0.5f32), (0.5f32, 1f32)));
+    }
+}
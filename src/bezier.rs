@@ -0,0 +1,560 @@
+//! Module for evaluating cubic Bezier curves.
+
+/// Evaluate a cubic Bezier curve at t (0 is p0, 1 is p3).
+pub fn point_at(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32),
+                t: f32) -> (f32, f32) {
+    let mt = 1f32 - t;
+    let a = mt * mt * mt;
+    let b = 3f32 * mt * mt * t;
+    let c = 3f32 * mt * t * t;
+    let d = t * t * t;
+    (a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+     a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1)
+}
+
+/// First derivative of a cubic Bezier curve at t.
+pub fn derivative_at(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32),
+                     t: f32) -> (f32, f32) {
+    let mt = 1f32 - t;
+    let a = 3f32 * mt * mt;
+    let b = 6f32 * mt * t;
+    let c = 3f32 * t * t;
+    (a * (p1.0 - p0.0) + b * (p2.0 - p1.0) + c * (p3.0 - p2.0),
+     a * (p1.1 - p0.1) + b * (p2.1 - p1.1) + c * (p3.1 - p2.1))
+}
+
+/// One segment of a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    Line((f32, f32), (f32, f32)),
+    Cubic((f32, f32), (f32, f32), (f32, f32), (f32, f32))
+}
+
+impl Segment {
+    /// The point at t, from 0 at the start to 1 at the end.
+    pub fn point_at(&self, t: f32) -> (f32, f32) {
+        match *self {
+            Segment::Line(a, b) => (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t),
+            Segment::Cubic(p0, p1, p2, p3) => point_at(p0, p1, p2, p3, t)
+        }
+    }
+
+    /// The segment as a cubic Bezier curve, lines become straight curves.
+    pub fn to_cubic(self) -> [(f32, f32); 4] {
+        match self {
+            Segment::Line(a, b) => [a, self.point_at(1f32 / 3f32), self.point_at(2f32 / 3f32), b],
+            Segment::Cubic(p0, p1, p2, p3) => [p0, p1, p2, p3]
+        }
+    }
+
+    /// The part of the segment from t0 to t1, as a segment of the same kind.
+    pub fn between(&self, t0: f32, t1: f32) -> Segment {
+        match *self {
+            Segment::Line(..) => Segment::Line(self.point_at(t0), self.point_at(t1)),
+            Segment::Cubic(p0, p1, p2, p3) => {
+                let (head, _) = split_at(&[p0, p1, p2, p3], t1);
+                let (_, part) = split_at(&head, if t1 > 0f32 { t0 / t1 } else { 0f32 });
+                Segment::Cubic(part[0], part[1], part[2], part[3])
+            }
+        }
+    }
+
+    /// The derivative at t.
+    pub fn derivative_at(&self, t: f32) -> (f32, f32) {
+        match *self {
+            Segment::Line(a, b) => (b.0 - a.0, b.1 - a.1),
+            Segment::Cubic(p0, p1, p2, p3) => derivative_at(p0, p1, p2, p3, t)
+        }
+    }
+
+    /// The point of the segment closest to p and its t.
+    pub fn nearest_point(&self, p: (f32, f32)) -> ((f32, f32), f32) {
+        let distance_sq = |t: f32| {
+            let q = self.point_at(t);
+            (q.0 - p.0) * (q.0 - p.0) + (q.1 - p.1) * (q.1 - p.1)
+        };
+        let t = match *self {
+            Segment::Line(a, b) => {
+                let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+                let length_sq = dx * dx + dy * dy;
+                if length_sq > 0f32 {
+                    (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0f32, 1f32)
+                } else {
+                    0f32
+                }
+            }
+            Segment::Cubic(..) => {
+                // sample to find the right stretch of the curve, then narrow it down
+                let samples = 16;
+                let best = (0..=samples).min_by(|&i, &j| {
+                    distance_sq(i as f32 / samples as f32).total_cmp(&distance_sq(j as f32 / samples as f32))
+                }).unwrap();
+                let step = 1f32 / samples as f32;
+                let (mut low, mut high) = ((best as f32 * step - step).max(0f32), (best as f32 * step + step).min(1f32));
+                for _ in 0..30 {
+                    let (m1, m2) = (low + (high - low) / 3f32, high - (high - low) / 3f32);
+                    if distance_sq(m1) < distance_sq(m2) {
+                        high = m2;
+                    } else {
+                        low = m1;
+                    }
+                }
+                (low + high) / 2f32
+            }
+        };
+        (self.point_at(t), t)
+    }
+
+    /// The smallest axis aligned box containing the segment, as (min, max). Curves are bounded
+    /// by their end points and the points where they turn back along x or y, not their control
+    /// points.
+    pub fn bounds(&self) -> ((f32, f32), (f32, f32)) {
+        let (start, end) = (self.point_at(0f32), self.point_at(1f32));
+        let mut min = (start.0.min(end.0), start.1.min(end.1));
+        let mut max = (start.0.max(end.0), start.1.max(end.1));
+        if let Segment::Cubic(p0, p1, p2, p3) = *self {
+            let turns = [extrema(p0.0, p1.0, p2.0, p3.0), extrema(p0.1, p1.1, p2.1, p3.1)];
+            for &t in turns.iter().flatten().flatten() {
+                let p = self.point_at(t);
+                min = (min.0.min(p.0), min.1.min(p.1));
+                max = (max.0.max(p.0), max.1.max(p.1));
+            }
+        }
+        (min, max)
+    }
+
+    /// How many times the segment crosses the ray from p towards +x, counting upward crossings
+    /// (y increasing) as +1 and downward ones as -1. Summed over a closed outline this is the
+    /// winding number of p. Crossings are half open in y, so outlines passing through the ray at a
+    /// vertex count once.
+    pub fn crossings(&self, p: (f32, f32)) -> i32 {
+        let mut stops = vec![0f32, 1f32];
+        if let Segment::Cubic(p0, p1, p2, p3) = *self {
+            stops.extend(extrema(p0.1, p1.1, p2.1, p3.1).iter().flatten());
+            stops.sort_by(|a, b| a.total_cmp(b));
+        }
+        // y is monotone between stops
+        let mut count = 0;
+        for piece in stops.windows(2) {
+            let (mut low, mut high) = (piece[0], piece[1]);
+            let (y0, y1) = (self.point_at(low).1, self.point_at(high).1);
+            if (y0 <= p.1) == (y1 <= p.1) {
+                continue;
+            }
+            let rising = y1 > y0;
+            for _ in 0..40 {
+                let mid = (low + high) / 2f32;
+                if (self.point_at(mid).1 <= p.1) == rising {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            if self.point_at((low + high) / 2f32).0 > p.0 {
+                count += if rising { 1 } else { -1 };
+            }
+        }
+        count
+    }
+
+    /// The unit direction of travel at t. Where the derivative vanishes (a control point on an
+    /// end point) the direction is taken from a nearby point instead, (1, 0) if the whole
+    /// segment is a single point.
+    pub fn direction_at(&self, t: f32) -> (f32, f32) {
+        let d = self.derivative_at(t);
+        let length = d.0.hypot(d.1);
+        if length > 1e-6 {
+            return (d.0 / length, d.1 / length);
+        }
+        let (a, b) = if t < 0.5f32 {
+            (self.point_at(t), self.point_at(t + 1e-3))
+        } else {
+            (self.point_at(t - 1e-3), self.point_at(t))
+        };
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = dx.hypot(dy);
+        if length > 0f32 { (dx / length, dy / length) } else { (1f32, 0f32) }
+    }
+}
+
+// a pair of curves is never split more than this many times deep while intersecting them
+const MAX_INTERSECTION_DEPTH: u32 = 40;
+
+/// Points where two segments cross, found by splitting both in halves wherever their bounding
+/// boxes overlap until each piece is nearly straight. Points already in `points` are not added
+/// again, so crossings at the shared ends of neighbouring segments are reported once. Overlapping
+/// collinear parts are not reported.
+pub fn intersections(a: &Segment, b: &Segment, points: &mut Vec<(f32, f32)>) {
+    let (a, b) = (a.to_cubic(), b.to_cubic());
+    let (min, max) = bounds_union(&a, &b);
+    let size = (max.0 - min.0).max(max.1 - min.1);
+    let tolerance = (size * 1e-5f32).max(f32::MIN_POSITIVE);
+    let start = points.len();
+    intersect_recursive(a, b, tolerance, MAX_INTERSECTION_DEPTH, points);
+    // a crossing on the split point of a curve is found from both halves
+    let mut i = start;
+    while i < points.len() {
+        let p = points[i];
+        if points[..i].iter().any(|q| (p.0 - q.0).abs() <= size * 1e-4f32 &&
+                                          (p.1 - q.1).abs() <= size * 1e-4f32) {
+            points.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+type Cubic = [(f32, f32); 4];
+
+fn bounds(c: &Cubic) -> ((f32, f32), (f32, f32)) {
+    c.iter().fold(((f32::INFINITY, f32::INFINITY), (f32::NEG_INFINITY, f32::NEG_INFINITY)),
+                  |(min, max), p| ((min.0.min(p.0), min.1.min(p.1)), (max.0.max(p.0), max.1.max(p.1))))
+}
+
+// Where one coordinate of a cubic has zero derivative strictly between its ends, the roots of
+// the quadratic a t^2 + b t + c.
+fn extrema(p0: f32, p1: f32, p2: f32, p3: f32) -> [Option<f32>; 2] {
+    let a = -p0 + 3f32 * p1 - 3f32 * p2 + p3;
+    let b = 2f32 * (p0 - 2f32 * p1 + p2);
+    let c = p1 - p0;
+    let inside = |t: f32| if t > 0f32 && t < 1f32 { Some(t) } else { None };
+    if a.abs() < 1e-12 {
+        // the derivative is linear
+        return [if b != 0f32 { inside(-c / b) } else { None }, None];
+    }
+    let discriminant = b * b - 4f32 * a * c;
+    if discriminant < 0f32 {
+        return [None, None];
+    }
+    let root = discriminant.sqrt();
+    [inside((-b + root) / (2f32 * a)), inside((-b - root) / (2f32 * a))]
+}
+
+fn bounds_union(a: &Cubic, b: &Cubic) -> ((f32, f32), (f32, f32)) {
+    let (a_min, a_max) = bounds(a);
+    let (b_min, b_max) = bounds(b);
+    ((a_min.0.min(b_min.0), a_min.1.min(b_min.1)), (a_max.0.max(b_max.0), a_max.1.max(b_max.1)))
+}
+
+// Split a curve in two at t with de Casteljau's algorithm.
+fn split_at(c: &Cubic, t: f32) -> (Cubic, Cubic) {
+    let lerp = |a: (f32, f32), b: (f32, f32)| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+    let (p01, p12, p23) = (lerp(c[0], c[1]), lerp(c[1], c[2]), lerp(c[2], c[3]));
+    let (p012, p123) = (lerp(p01, p12), lerp(p12, p23));
+    let point = lerp(p012, p123);
+    ([c[0], p01, p012, point], [point, p123, p23, c[3]])
+}
+
+fn split(c: &Cubic) -> (Cubic, Cubic) {
+    let mid = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2f32, (a.1 + b.1) / 2f32);
+    let (p01, p12, p23) = (mid(c[0], c[1]), mid(c[1], c[2]), mid(c[2], c[3]));
+    let (p012, p123) = (mid(p01, p12), mid(p12, p23));
+    let centre = mid(p012, p123);
+    ([c[0], p01, p012, centre], [centre, p123, p23, c[3]])
+}
+
+fn is_flat(c: &Cubic, tolerance: f32) -> bool {
+    distance_to_segment(c[1], c[0], c[3]) <= tolerance && distance_to_segment(c[2], c[0], c[3]) <= tolerance
+}
+
+fn intersect_recursive(a: Cubic, b: Cubic, tolerance: f32, depth: u32, points: &mut Vec<(f32, f32)>) {
+    let (a_min, a_max) = bounds(&a);
+    let (b_min, b_max) = bounds(&b);
+    if a_max.0 < b_min.0 - tolerance || b_max.0 < a_min.0 - tolerance ||
+       a_max.1 < b_min.1 - tolerance || b_max.1 < a_min.1 - tolerance {
+        return;
+    }
+    let (a_flat, b_flat) = (is_flat(&a, tolerance), is_flat(&b, tolerance));
+    if (a_flat && b_flat) || depth == 0 {
+        if let Some(p) = line_intersection(a[0], a[3], b[0], b[3]) {
+            points.push(p);
+        }
+        return;
+    }
+    // split whichever is less straight, or both if neither is
+    if a_flat {
+        let (b0, b1) = split(&b);
+        intersect_recursive(a, b0, tolerance, depth - 1, points);
+        intersect_recursive(a, b1, tolerance, depth - 1, points);
+    } else if b_flat {
+        let (a0, a1) = split(&a);
+        intersect_recursive(a0, b, tolerance, depth - 1, points);
+        intersect_recursive(a1, b, tolerance, depth - 1, points);
+    } else {
+        let (a0, a1) = split(&a);
+        let (b0, b1) = split(&b);
+        intersect_recursive(a0, b0, tolerance, depth - 1, points);
+        intersect_recursive(a0, b1, tolerance, depth - 1, points);
+        intersect_recursive(a1, b0, tolerance, depth - 1, points);
+        intersect_recursive(a1, b1, tolerance, depth - 1, points);
+    }
+}
+
+/// Where the line segments a0-a1 and b0-b1 cross, if they do. Ends count as crossing.
+pub fn line_intersection(a0: (f32, f32), a1: (f32, f32), b0: (f32, f32), b1: (f32, f32)) -> Option<(f32, f32)> {
+    let (dax, day) = (a1.0 - a0.0, a1.1 - a0.1);
+    let (dbx, dby) = (b1.0 - b0.0, b1.1 - b0.1);
+    let denominator = dax * dby - day * dbx;
+    if denominator == 0f32 {
+        return None;
+    }
+    let (ox, oy) = (b0.0 - a0.0, b0.1 - a0.1);
+    let t = (ox * dby - oy * dbx) / denominator;
+    let u = (ox * day - oy * dax) / denominator;
+    // a little slack so crossings exactly at a split point are not lost to rounding
+    let slack = 1e-4f32;
+    if t < -slack || t > 1f32 + slack || u < -slack || u > 1f32 + slack {
+        return None;
+    }
+    Some((a0.0 + t * dax, a0.1 + t * day))
+}
+
+/// Approximate a cubic Bezier curve by a polyline of `steps` segments. The first point is left
+/// out so consecutive curves can be chained, the last point is p3.
+pub fn flatten_uniform(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32),
+                       steps: usize, points: &mut Vec<(f32, f32)>) {
+    for i in 1..(steps + 1) {
+        points.push(point_at(p0, p1, p2, p3, i as f32 / steps as f32));
+    }
+}
+
+/// Approximate a cubic Bezier curve by a polyline that stays within `tolerance` of it, splitting
+/// it in half until the control points are that close to the chord. Like `flatten_uniform` the
+/// first point is left out.
+pub fn flatten_adaptive(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32),
+                        tolerance: f32, points: &mut Vec<(f32, f32)>) {
+    flatten_recursive([p0, p1, p2, p3], tolerance, MAX_SUBDIVISIONS, points);
+}
+
+// a curve is never split more than this many times deep, limiting one curve to 2^16 pieces
+const MAX_SUBDIVISIONS: u32 = 16;
+
+fn flatten_recursive(p: [(f32, f32); 4], tolerance: f32, depth: u32, points: &mut Vec<(f32, f32)>) {
+    // the curve lies in the hull of its control points, so it is within the larger of these
+    // distances of the chord
+    let flat = distance_to_segment(p[1], p[0], p[3]) <= tolerance &&
+               distance_to_segment(p[2], p[0], p[3]) <= tolerance;
+    if flat || depth == 0 {
+        points.push(p[3]);
+        return;
+    }
+    let mid = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2f32, (a.1 + b.1) / 2f32);
+    let (p01, p12, p23) = (mid(p[0], p[1]), mid(p[1], p[2]), mid(p[2], p[3]));
+    let (p012, p123) = (mid(p01, p12), mid(p12, p23));
+    let centre = mid(p012, p123);
+    flatten_recursive([p[0], p01, p012, centre], tolerance, depth - 1, points);
+    flatten_recursive([centre, p123, p23, p[3]], tolerance, depth - 1, points);
+}
+
+/// Split a cubic Bezier curve in half until no control point of a piece is further from the
+/// piece's chord than `flatness` times the chord's length. Pushes each piece with the t it starts
+/// at on the whole curve, in order.
+pub fn split_until_flat(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32),
+                        flatness: f32, pieces: &mut Vec<(f32, [(f32, f32); 4])>) {
+    split_flat_recursive([p0, p1, p2, p3], flatness, MAX_BEND_SPLITS, (0f32, 1f32), pieces);
+}
+
+// a curve is split into at most 2^5 pieces to make it flat
+const MAX_BEND_SPLITS: u32 = 5;
+
+fn split_flat_recursive(c: Cubic, flatness: f32, depth: u32, range: (f32, f32),
+                        pieces: &mut Vec<(f32, Cubic)>) {
+    let chord = (c[3].0 - c[0].0).hypot(c[3].1 - c[0].1);
+    let bend = distance_to_segment(c[1], c[0], c[3]).max(distance_to_segment(c[2], c[0], c[3]));
+    if bend <= flatness * chord || depth == 0 {
+        pieces.push((range.0, c));
+        return;
+    }
+    let (first, second) = split(&c);
+    let middle = (range.0 + range.1) / 2f32;
+    split_flat_recursive(first, flatness, depth - 1, (range.0, middle), pieces);
+    split_flat_recursive(second, flatness, depth - 1, (middle, range.1), pieces);
+}
+
+/// Control points of the cubic Bezier curves of a cardinal spline through `points`, one pair per
+/// gap between neighbouring points. A tension of 0 gives a Catmull-Rom spline, 1 gives straight
+/// lines. The ends use the end points themselves as their missing neighbours.
+pub fn cardinal_control_points(points: &[(f32, f32)], tension: f32) -> Vec<((f32, f32), (f32, f32))> {
+    let n = points.len();
+    let scale = (1f32 - tension) / 6f32;
+    // tangent at point i, divided by 3 as needed for Bezier control points
+    let tangent = |i: usize| {
+        let before = points[i.saturating_sub(1)];
+        let after = points[(i + 1).min(n - 1)];
+        ((after.0 - before.0) * scale, (after.1 - before.1) * scale)
+    };
+    (0..n.saturating_sub(1)).map(|i| {
+        let (p1, p2) = (points[i], points[i + 1]);
+        let (t1, t2) = (tangent(i), tangent(i + 1));
+        ((p1.0 + t1.0, p1.1 + t1.1), (p2.0 - t2.0, p2.1 - t2.1))
+    }).collect()
+}
+
+/// Distance from a point to the line segment a-b.
+pub fn distance_to_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let dx = b.0 - a.0;
+    let dy = b.1 - a.1;
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq > 0f32 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0f32, 1f32)
+    } else {
+        0f32
+    };
+    let x = a.0 + t * dx - p.0;
+    let y = a.1 + t * dy - p.1;
+    (x * x + y * y).sqrt()
+}
+
+/// The area of a closed polygon, positive if it winds counter clockwise.
+pub fn polygon_area(points: &[(f32, f32)]) -> f32 {
+    (0..points.len()).map(|i| {
+        let (p, q) = (points[i], points[(i + 1) % points.len()]);
+        p.0 * q.1 - q.0 * p.1
+    }).sum::<f32>() / 2f32
+}
+
+/// Even-odd test of a point against a closed polygon.
+#[cfg(test)]
+pub fn polygon_contains(polygon: &[(f32, f32)], p: (f32, f32)) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    let mut j = n.wrapping_sub(1);
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[j];
+        if (a.1 > p.1) != (b.1 > p.1) &&
+           p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_bounds() {
+        // a bump reaching 7.5 of the way to its control points' height of 10
+        let bump = Segment::Cubic((0f32, 0f32), (0f32, 10f32), (10f32, 10f32), (10f32, 0f32));
+        assert_eq!(bump.bounds(), ((0f32, 0f32), (10f32, 7.5f32)));
+        // an s curve overshooting its ends on x
+        let s = Segment::Cubic((0f32, 0f32), (20f32, 0f32), (-10f32, 10f32), (10f32, 10f32));
+        let ((min_x, _), (max_x, _)) = s.bounds();
+        let samples: Vec<f32> = (0..=1000).map(|i| s.point_at(i as f32 / 1000f32).0).collect();
+        assert!((min_x - samples.iter().cloned().fold(f32::MAX, f32::min)).abs() < 1e-3);
+        assert!((max_x - samples.iter().cloned().fold(f32::MIN, f32::max)).abs() < 1e-3);
+        assert_eq!(Segment::Line((3f32, 1f32), (1f32, 2f32)).bounds(), ((1f32, 1f32), (3f32, 2f32)));
+    }
+
+    #[test]
+    fn test_crossings() {
+        let bump = Segment::Cubic((0f32, 0f32), (0f32, 10f32), (10f32, 10f32), (10f32, 0f32));
+        // the bump goes up then down through y = 5 right of x = -1, and never reaches y = 8
+        assert_eq!(bump.crossings((-1f32, 5f32)), 0);
+        assert_eq!(bump.crossings((5f32, 5f32)), -1);
+        assert_eq!(bump.crossings((5f32, 8f32)), 0);
+        assert_eq!(Segment::Line((1f32, 0f32), (1f32, 2f32)).crossings((0f32, 1f32)), 1);
+        assert_eq!(Segment::Line((1f32, 2f32), (1f32, 0f32)).crossings((0f32, 1f32)), -1);
+    }
+
+    #[test]
+    fn test_cardinal_spline_passes_through_points() {
+        let points = [(0f32, 0f32), (10f32, 5f32), (20f32, 0f32), (30f32, 10f32)];
+        let controls = cardinal_control_points(&points, 0f32);
+        assert_eq!(controls.len(), 3);
+        // the tangent at (10, 5) is parallel to (20, 0) - (0, 0)
+        assert!((controls[0].1.0 - (10f32 - 20f32 / 6f32)).abs() < 1e-5 && controls[0].1.1 == 5f32);
+        assert!((controls[1].0.0 - (10f32 + 20f32 / 6f32)).abs() < 1e-5 && controls[1].0.1 == 5f32);
+        // full tension puts the control points on the points
+        let straight = cardinal_control_points(&points, 1f32);
+        assert_eq!(straight[1], (points[1], points[2]));
+    }
+
+    #[test]
+    fn test_point_at_ends() {
+        let p0 = (0f32, 0f32);
+        let p3 = (3f32, 1f32);
+        assert_eq!(point_at(p0, (1f32, 2f32), (2f32, 2f32), p3, 0f32), p0);
+        assert_eq!(point_at(p0, (1f32, 2f32), (2f32, 2f32), p3, 1f32), p3);
+    }
+
+    #[test]
+    fn test_flatten_uniform() {
+        let mut points = Vec::new();
+        flatten_uniform((0f32, 0f32), (1f32, 0f32), (2f32, 0f32), (3f32, 0f32), 3, &mut points);
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[2], (3f32, 0f32));
+        assert!((points[0].0 - 1f32).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_flatten_adaptive() {
+        let (p0, p1, p2, p3) = ((0f32, 0f32), (0f32, 50f32), (100f32, 50f32), (100f32, 0f32));
+        let mut coarse = vec![p0];
+        flatten_adaptive(p0, p1, p2, p3, 1f32, &mut coarse);
+        let mut fine = vec![p0];
+        flatten_adaptive(p0, p1, p2, p3, 0.01f32, &mut fine);
+        assert!(fine.len() > coarse.len());
+        assert_eq!(*coarse.last().unwrap(), p3);
+        // every point of the curve is within the tolerance of the polyline
+        for i in 0..=100 {
+            let p = point_at(p0, p1, p2, p3, i as f32 / 100f32);
+            let distance = coarse.windows(2)
+                .map(|w| distance_to_segment(p, w[0], w[1]))
+                .fold(f32::INFINITY, f32::min);
+            assert!(distance <= 1f32);
+        }
+        // a straight curve needs no splitting
+        let mut line = vec![p0];
+        flatten_adaptive(p0, (1f32, 0f32), (2f32, 0f32), (3f32, 0f32), 0.1f32, &mut line);
+        assert_eq!(line, vec![p0, (3f32, 0f32)]);
+    }
+
+    #[test]
+    fn test_intersections() {
+        let line = Segment::Line((0f32, 5f32), (100f32, 5f32));
+        // an arch from (0, 0) up to a height of 37.5 and back down to (100, 0)
+        let arch = Segment::Cubic((0f32, 0f32), (0f32, 50f32), (100f32, 50f32), (100f32, 0f32));
+        let mut points = Vec::new();
+        intersections(&line, &arch, &mut points);
+        assert_eq!(points.len(), 2);
+        for p in &points {
+            assert!((p.1 - 5f32).abs() < 1e-2);
+        }
+        let mut points = Vec::new();
+        intersections(&Segment::Line((0f32, 40f32), (100f32, 40f32)), &arch, &mut points);
+        assert!(points.is_empty());
+        assert_eq!(line_intersection((0f32, 0f32), (2f32, 2f32), (0f32, 2f32), (2f32, 0f32)),
+                   Some((1f32, 1f32)));
+    }
+
+    #[test]
+    fn test_distance_to_segment() {
+        assert_eq!(distance_to_segment((1f32, 1f32), (0f32, 0f32), (2f32, 0f32)), 1f32);
+        assert_eq!(distance_to_segment((3f32, 0f32), (0f32, 0f32), (2f32, 0f32)), 1f32);
+        assert_eq!(distance_to_segment((0f32, 2f32), (0f32, 0f32), (0f32, 0f32)), 2f32);
+    }
+
+    #[test]
+    fn test_polygon_contains() {
+        let square = [(0f32, 0f32), (2f32, 0f32), (2f32, 2f32), (0f32, 2f32)];
+        assert!(polygon_contains(&square, (1f32, 1f32)));
+        assert!(!polygon_contains(&square, (3f32, 1f32)));
+        assert!(!polygon_contains(&square, (1f32, -1f32)));
+    }
+
+    #[test]
+    fn test_between() {
+        let curve = Segment::Cubic((0f32, 0f32), (0f32, 10f32), (10f32, 10f32), (10f32, 0f32));
+        let part = curve.between(0.25f32, 0.75f32);
+        for &t in &[0f32, 0.5f32, 1f32] {
+            let (a, b) = (part.point_at(t), curve.point_at(0.25f32 + t / 2f32));
+            assert!((a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4);
+        }
+        let line = Segment::Line((0f32, 0f32), (4f32, 0f32));
+        assert_eq!(line.between(0.5f32, 1f32), Segment::Line((2f32, 0f32), (4f32, 0f32)));
+    }
+}
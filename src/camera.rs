@@ -0,0 +1,272 @@
+//! The camera maps drawing coordinates to window pixels, for panning and zooming.
+
+/// Maps a drawing point p to the view point p * zoom + pan. The drawing's coordinate system
+/// places view points in the window, by default they are window pixels from the bottom left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub pan: (f32, f32),
+    pub zoom: f32
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new()
+    }
+}
+
+impl Camera {
+    /// Constructor, the identity camera: drawing coordinates are window pixels.
+    pub fn new() -> Self {
+        Camera { pan: (0f32, 0f32), zoom: 1f32 }
+    }
+
+    /// Convert a point from drawing coordinates to window pixels.
+    pub fn to_window(&self, point: (f32, f32)) -> (f32, f32) {
+        (point.0 * self.zoom + self.pan.0, point.1 * self.zoom + self.pan.1)
+    }
+
+    /// Convert a point from window pixels to drawing coordinates.
+    pub fn to_drawing(&self, point: (f32, f32)) -> (f32, f32) {
+        ((point.0 - self.pan.0) / self.zoom, (point.1 - self.pan.1) / self.zoom)
+    }
+
+    /// Move the view by a distance in window pixels.
+    pub fn pan_by(&mut self, dx: f32, dy: f32) {
+        self.pan = (self.pan.0 + dx, self.pan.1 + dy);
+    }
+
+    /// Multiply the zoom by factor, keeping the drawing point under the window point `anchor`
+    /// in place.
+    pub fn zoom_about(&mut self, anchor: (f32, f32), factor: f32) {
+        let fixed = self.to_drawing(anchor);
+        self.zoom *= factor;
+        self.pan = (anchor.0 - fixed.0 * self.zoom, anchor.1 - fixed.1 * self.zoom);
+    }
+}
+
+/// How drawing coordinates are laid out in the view before the camera pans and zooms it, see
+/// `Drawing::set_projection`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Drawing x and y are the view's x and y, the default.
+    Orthographic2D,
+    /// The drawing is a ground plane seen from above at a slant, as in isometric game maps and
+    /// diagrams: x runs to the upper right and y to the upper left, both at `angle` radians from
+    /// the horizontal. Use π/6 for true isometric and atan(1/2) for 2:1 pixel art.
+    Isometric { angle: f32 }
+}
+
+impl Projection {
+    // The projection as a 2x2 matrix [a, b, c, d] mapping (x, y) to (a x + b y, c x + d y).
+    fn matrix(self) -> [f32; 4] {
+        match self {
+            Projection::Orthographic2D => [1f32, 0f32, 0f32, 1f32],
+            Projection::Isometric { angle } => {
+                let (sin, cos) = angle.sin_cos();
+                [cos, -cos, sin, sin]
+            }
+        }
+    }
+
+    // Whether the projection can be undone, isometric angles must be strictly between 0 and a
+    // quarter turn.
+    pub(crate) fn is_valid(self) -> bool {
+        let [a, b, c, d] = self.matrix();
+        (a * d - b * c).abs() > 1e-6f32
+    }
+
+    // The columns of the matrix, the view vectors of the drawing's x and y axes.
+    pub(crate) fn axes(self) -> ((f32, f32), (f32, f32)) {
+        let [a, b, c, d] = self.matrix();
+        ((a, c), (b, d))
+    }
+
+    // Lay a drawing point out in the view.
+    pub(crate) fn apply(self, point: (f32, f32)) -> (f32, f32) {
+        let [a, b, c, d] = self.matrix();
+        (a * point.0 + b * point.1, c * point.0 + d * point.1)
+    }
+
+    // The drawing point laid out at a view point.
+    pub(crate) fn invert(self, point: (f32, f32)) -> (f32, f32) {
+        let [a, b, c, d] = self.matrix();
+        let det = a * d - b * c;
+        ((d * point.0 - b * point.1) / det, (a * point.1 - c * point.0) / det)
+    }
+
+    // The least a length in the drawing is scaled by in the view, the smallest singular value of
+    // the matrix.
+    pub(crate) fn min_scale(self) -> f32 {
+        let [a, b, c, d] = self.matrix();
+        let (p, q) = (a * a + b * b + c * c + d * d, a * d - b * c);
+        let root = (p * p - 4f32 * q * q).max(0f32).sqrt();
+        ((p - root) / 2f32).max(0f32).sqrt()
+    }
+}
+
+/// Where the origin of the view is in the window, see `Drawing::set_coordinate_system`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    BottomLeft,
+    TopLeft
+}
+
+/// Which way y grows in the view, see `Drawing::set_coordinate_system`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YAxis {
+    Up,
+    Down
+}
+
+/// How a logical size is fitted into the window, see `Drawing::set_logical_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Scale evenly until the whole logical area is in the window, centered, with bars along the
+    /// sides or the top and bottom where the window's shape differs.
+    Fit,
+    /// Scale evenly until the logical area covers the whole window, centered, cutting off its
+    /// sides or its top and bottom where the window's shape differs.
+    Fill,
+    /// Scale x and y each to the window's width and height, distorting the view.
+    Stretch
+}
+
+// A view of a fixed size in logical units, fitted into the window whatever its size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LogicalSize {
+    pub width: f32,
+    pub height: f32,
+    pub mode: ScaleMode
+}
+
+// Places view points in the window: the origin, the direction of y, and window pixels per
+// logical unit, or the logical size fitted into the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CoordinateSystem {
+    pub origin: Origin,
+    pub y_axis: YAxis,
+    pub scale: f32,
+    pub logical: Option<LogicalSize>
+}
+
+impl CoordinateSystem {
+    // Window pixels from the bottom left, y up.
+    pub fn new() -> Self {
+        CoordinateSystem { origin: Origin::BottomLeft, y_axis: YAxis::Up, scale: 1f32, logical: None }
+    }
+
+    // 1 if y grows up the window, -1 if down.
+    pub fn y_sign(self) -> f32 {
+        if self.y_axis == YAxis::Up { 1f32 } else { -1f32 }
+    }
+
+    // Window pixels per view unit along x and y, in a window of a size in pixels.
+    pub fn scales(self, window: (f32, f32)) -> (f32, f32) {
+        match self.logical {
+            Some(logical) => {
+                let (x, y) = (window.0 / logical.width, window.1 / logical.height);
+                match logical.mode {
+                    ScaleMode::Fit => (x.min(y), x.min(y)),
+                    ScaleMode::Fill => (x.max(y), x.max(y)),
+                    ScaleMode::Stretch => (x, y)
+                }
+            }
+            None => (self.scale, self.scale)
+        }
+    }
+
+    // The part of the window the view covers, (min, max) in window pixels from the bottom left.
+    // It is the whole window unless a logical size is fitted into it, and runs past the window's
+    // edges when one fills it.
+    pub fn area(self, window: (f32, f32)) -> ((f32, f32), (f32, f32)) {
+        match self.logical {
+            Some(logical) => {
+                let (x, y) = self.scales(window);
+                let (width, height) = (logical.width * x, logical.height * y);
+                let min = ((window.0 - width) / 2f32, (window.1 - height) / 2f32);
+                (min, (min.0 + width, min.1 + height))
+            }
+            None => ((0f32, 0f32), window)
+        }
+    }
+
+    // The window position of the origin.
+    fn origin(self, window: (f32, f32)) -> (f32, f32) {
+        let (min, max) = self.area(window);
+        if self.origin == Origin::TopLeft { (min.0, max.1) } else { min }
+    }
+
+    // Convert a view point to window pixels, in a window of a size in pixels.
+    pub fn to_window(self, point: (f32, f32), window: (f32, f32)) -> (f32, f32) {
+        let ((x, y), origin) = (self.scales(window), self.origin(window));
+        (origin.0 + point.0 * x, origin.1 + self.y_sign() * point.1 * y)
+    }
+
+    // Convert a point in window pixels to the view.
+    pub fn to_view(self, point: (f32, f32), window: (f32, f32)) -> (f32, f32) {
+        let ((x, y), origin) = (self.scales(window), self.origin(window));
+        ((point.0 - origin.0) / x, (point.1 - origin.1) * self.y_sign() / y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Camera, CoordinateSystem, LogicalSize, Origin, Projection, ScaleMode, YAxis};
+
+    #[test]
+    fn test_round_trip() {
+        let camera = Camera { pan: (10f32, -5f32), zoom: 2f32 };
+        let p = camera.to_window((3f32, 4f32));
+        assert_eq!(p, (16f32, 3f32));
+        assert_eq!(camera.to_drawing(p), (3f32, 4f32));
+    }
+
+    #[test]
+    fn test_zoom_about_keeps_anchor() {
+        let mut camera = Camera::new();
+        camera.pan_by(7f32, 3f32);
+        let anchor = (100f32, 50f32);
+        let before = camera.to_drawing(anchor);
+        camera.zoom_about(anchor, 1.5f32);
+        let after = camera.to_drawing(anchor);
+        assert!((before.0 - after.0).abs() < 1e-4 && (before.1 - after.1).abs() < 1e-4);
+        assert_eq!(camera.zoom, 1.5f32);
+    }
+
+    #[test]
+    fn test_coordinate_system() {
+        let system = CoordinateSystem { origin: Origin::TopLeft, y_axis: YAxis::Down, scale: 2f32, logical: None };
+        assert_eq!(system.to_window((10f32, 5f32), (100f32, 100f32)), (20f32, 90f32));
+        assert_eq!(system.to_view((20f32, 90f32), (100f32, 100f32)), (10f32, 5f32));
+        assert_eq!(CoordinateSystem::new().to_window((10f32, 5f32), (100f32, 100f32)), (10f32, 5f32));
+    }
+
+    #[test]
+    fn test_logical_size() {
+        let logical = |mode| CoordinateSystem {
+            logical: Some(LogicalSize { width: 200f32, height: 100f32, mode }), ..CoordinateSystem::new()
+        };
+        // a square window: bars above and below when fitted, the sides cut off when filled
+        let window = (400f32, 400f32);
+        assert_eq!(logical(ScaleMode::Fit).area(window), ((0f32, 100f32), (400f32, 300f32)));
+        assert_eq!(logical(ScaleMode::Fit).to_window((200f32, 100f32), window), (400f32, 300f32));
+        assert_eq!(logical(ScaleMode::Fill).area(window), ((-200f32, 0f32), (600f32, 400f32)));
+        assert_eq!(logical(ScaleMode::Stretch).scales(window), (2f32, 4f32));
+        let top_left = CoordinateSystem { origin: Origin::TopLeft, y_axis: YAxis::Down, ..logical(ScaleMode::Fit) };
+        assert_eq!(top_left.to_window((0f32, 0f32), window), (0f32, 300f32));
+        assert_eq!(top_left.to_view((400f32, 100f32), window), (200f32, 100f32));
+    }
+
+    #[test]
+    fn test_isometric() {
+        let iso = Projection::Isometric { angle: 0.5f32.atan() };
+        // one step along x goes twice as far right as up
+        let p = iso.apply((1f32, 0f32));
+        assert!((p.0 - 2f32 * p.1).abs() < 1e-6);
+        let q = iso.invert(iso.apply((3f32, -2f32)));
+        assert!((q.0 - 3f32).abs() < 1e-5 && (q.1 + 2f32).abs() < 1e-5);
+        assert!((Projection::Isometric { angle: std::f32::consts::FRAC_PI_6 }.min_scale() - 0.5f32.sqrt()).abs() < 1e-5);
+        assert_eq!(Projection::Orthographic2D.min_scale(), 1f32);
+        assert!(!Projection::Isometric { angle: 0f32 }.is_valid());
+    }
+}
@@ -0,0 +1,353 @@
+//! An immediate mode layer in the style of the HTML canvas 2D context. Paths are built with
+//! `begin_path`, `move_to`, `line_to` and friends, then added to the drawing by `fill` or
+//! `stroke` with the current style and transform.
+
+use std::f32::consts::PI;
+use super::gl2d::drawing::{Drawing, Path, PathId, Window};
+use super::angle::Angle;
+use super::color::Color;
+use super::transform::Transform2D;
+use super::TrdlError;
+
+// One piece of a subpath, points are already transformed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment {
+    Line((f32, f32)),
+    Curve((f32, f32), (f32, f32), (f32, f32))
+}
+
+// A connected run of segments, started by move_to.
+#[derive(Debug, Clone, PartialEq)]
+struct Subpath {
+    start: (f32, f32),
+    segments: Vec<Segment>,
+    is_closed: bool
+}
+
+impl Subpath {
+    fn new(start: (f32, f32)) -> Self {
+        Subpath { start, segments: Vec::new(), is_closed: false }
+    }
+
+    fn end(&self) -> (f32, f32) {
+        match self.segments.last() {
+            Some(&Segment::Line(p)) | Some(&Segment::Curve(_, _, p)) => p,
+            None => self.start
+        }
+    }
+
+    // Build a path, closing it if asked to. Returns None when there are too few points for the
+    // path to show up.
+    fn to_path(&self, close: bool) -> Option<Path> {
+        let min_segments = if close { 2 } else { 1 };
+        if self.segments.len() < min_segments {
+            return None;
+        }
+        let mut path = Path::new(self.start);
+        for segment in &self.segments {
+            path = match *segment {
+                Segment::Line(p) => path.line_to(p),
+                Segment::Curve(cp1, cp2, p) => path.curve_to(cp1, cp2, p)
+            };
+        }
+        if close {
+            path = path.close_path();
+        }
+        Some(path)
+    }
+}
+
+// The part of the canvas state that save and restore keep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CanvasState {
+    transform: Transform2D,
+    fill_color: Color,
+    stroke_color: Color,
+    line_width: f32
+}
+
+/// Immediate mode drawing on top of a `Drawing`.
+pub struct Canvas<'d, 'a: 'd, W: Window + 'a> {
+    drawing: &'d mut Drawing<'a, W>,
+    subpaths: Vec<Subpath>,
+    state: CanvasState,
+    saved: Vec<CanvasState>
+}
+
+impl<'d, 'a: 'd, W: Window + 'a> Canvas<'d, 'a, W> {
+    /// Constructor, the style starts as black 1 pixel wide strokes and black fill, with no
+    /// transform.
+    pub fn new(drawing: &'d mut Drawing<'a, W>) -> Self {
+        Canvas {
+            drawing,
+            subpaths: Vec::new(),
+            state: CanvasState {
+                transform: Transform2D::identity(),
+                fill_color: Color::rgb(0f32, 0f32, 0f32),
+                stroke_color: Color::rgb(0f32, 0f32, 0f32),
+                line_width: 1f32
+            },
+            saved: Vec::new()
+        }
+    }
+
+    /// The drawing the canvas adds paths to.
+    pub fn drawing(&mut self) -> &mut Drawing<'a, W> {
+        self.drawing
+    }
+
+    /// Push the transform and style on a stack.
+    pub fn save(&mut self) {
+        self.saved.push(self.state);
+    }
+
+    /// Pop the transform and style saved last, does nothing if there is nothing saved.
+    pub fn restore(&mut self) {
+        if let Some(state) = self.saved.pop() {
+            self.state = state;
+        }
+    }
+
+    /// Set the color used by `fill`.
+    pub fn set_fill_style(&mut self, color: Color) {
+        self.state.fill_color = color;
+    }
+
+    /// Set the color used by `stroke`.
+    pub fn set_stroke_style(&mut self, color: Color) {
+        self.state.stroke_color = color;
+    }
+
+    /// Set the stroke width, it is scaled by the transform when stroking.
+    pub fn set_line_width(&mut self, width: f32) {
+        self.state.line_width = width;
+    }
+
+    /// The current transform.
+    pub fn transform(&self) -> Transform2D {
+        self.state.transform
+    }
+
+    /// Replace the current transform.
+    pub fn set_transform(&mut self, transform: Transform2D) {
+        self.state.transform = transform;
+    }
+
+    /// Go back to no transform.
+    pub fn reset_transform(&mut self) {
+        self.state.transform = Transform2D::identity();
+    }
+
+    /// Apply a transform before the current one.
+    pub fn apply_transform(&mut self, transform: Transform2D) {
+        self.state.transform = self.state.transform * transform;
+    }
+
+    /// Move what is drawn next by (dx, dy).
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        self.apply_transform(Transform2D::translation(dx, dy));
+    }
+
+    /// Rotate what is drawn next counter clockwise by angle (radians if a plain f32).
+    pub fn rotate(&mut self, angle: impl Into<Angle>) {
+        self.apply_transform(Transform2D::rotation(angle));
+    }
+
+    /// Scale what is drawn next.
+    pub fn scale(&mut self, sx: f32, sy: f32) {
+        self.apply_transform(Transform2D::scaling(sx, sy));
+    }
+
+    /// Throw away the current path and start a new one.
+    pub fn begin_path(&mut self) {
+        self.subpaths.clear();
+    }
+
+    /// Start a new subpath at a point.
+    pub fn move_to(&mut self, point: (f32, f32)) {
+        let point = self.state.transform.apply(point);
+        self.subpaths.push(Subpath::new(point));
+    }
+
+    /// Add a straight line to the current subpath, or start one if there is none.
+    pub fn line_to(&mut self, point: (f32, f32)) {
+        let point = self.state.transform.apply(point);
+        self.push_segment(point, Segment::Line(point));
+    }
+
+    /// Add a cubic Bezier curve to the current subpath, or start one if there is none.
+    pub fn bezier_curve_to(&mut self, control_point_1: (f32, f32), control_point_2: (f32, f32),
+                           end_point: (f32, f32)) {
+        let transform = self.state.transform;
+        let cp1 = transform.apply(control_point_1);
+        self.push_segment(cp1, Segment::Curve(cp1, transform.apply(control_point_2),
+                                              transform.apply(end_point)));
+    }
+
+    /// Add a circular arc. It is joined to the current subpath with a straight line, angles are
+    /// counter clockwise from the x axis, radians if plain f32s.
+    pub fn arc(&mut self, center: (f32, f32), radius: f32, start_angle: impl Into<Angle>, end_angle: impl Into<Angle>,
+               counter_clockwise: bool) {
+        let (start_angle, end_angle) = (start_angle.into().to_radians(), end_angle.into().to_radians());
+        let sweep = arc_sweep(start_angle, end_angle, counter_clockwise);
+        let start = (center.0 + radius * start_angle.cos(), center.1 + radius * start_angle.sin());
+        self.line_to(start);
+        for (cp1, cp2, end) in arc_segments(center, radius, start_angle, sweep) {
+            self.bezier_curve_to(cp1, cp2, end);
+        }
+    }
+
+    /// Add a closed rectangle subpath with a corner at (x, y).
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.move_to((x, y));
+        self.line_to((x + width, y));
+        self.line_to((x + width, y + height));
+        self.line_to((x, y + height));
+        self.close_path();
+    }
+
+    /// Close the current subpath with a straight line back to its start, the next segment
+    /// starts a new subpath there.
+    pub fn close_path(&mut self) {
+        let start = match self.subpaths.last_mut() {
+            Some(subpath) => {
+                subpath.is_closed = true;
+                subpath.start
+            }
+            None => return
+        };
+        self.subpaths.push(Subpath::new(start));
+    }
+
+    /// Fill the current path with the fill style, every subpath is closed first. Returns the
+    /// added paths, subpaths with fewer than 3 points are skipped.
+    pub fn fill(&mut self) -> Result<Vec<PathId>, TrdlError> {
+        let color = self.state.fill_color;
+        let paths: Vec<Path> = self.subpaths.iter()
+            .filter_map(|subpath| subpath.to_path(true))
+            .map(|path| path.set_fill_color(color.red, color.green, color.blue))
+            .collect();
+        self.add_paths(paths)
+    }
+
+    /// Stroke the current path with the stroke style. Returns the added paths.
+    pub fn stroke(&mut self) -> Result<Vec<PathId>, TrdlError> {
+        let color = self.state.stroke_color;
+        let thickness = self.stroke_thickness();
+        let paths: Vec<Path> = self.subpaths.iter()
+            .filter_map(|subpath| subpath.to_path(subpath.is_closed))
+            .map(|path| path.set_stroke(color.red, color.green, color.blue, thickness))
+            .collect();
+        self.add_paths(paths)
+    }
+
+    /// Fill a rectangle without touching the current path.
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32) -> Result<Vec<PathId>, TrdlError> {
+        let subpaths = self.take_rect(x, y, width, height);
+        let result = self.fill();
+        self.subpaths = subpaths;
+        result
+    }
+
+    /// Stroke a rectangle without touching the current path.
+    pub fn stroke_rect(&mut self, x: f32, y: f32, width: f32, height: f32) -> Result<Vec<PathId>, TrdlError> {
+        let subpaths = self.take_rect(x, y, width, height);
+        let result = self.stroke();
+        self.subpaths = subpaths;
+        result
+    }
+
+    // Make the current path just the rectangle, returning the old path.
+    fn take_rect(&mut self, x: f32, y: f32, width: f32, height: f32) -> Vec<Subpath> {
+        let subpaths = ::std::mem::take(&mut self.subpaths);
+        self.rect(x, y, width, height);
+        subpaths
+    }
+
+    fn push_segment(&mut self, start_if_new: (f32, f32), segment: Segment) {
+        let needs_start = self.subpaths.last().is_none_or(|subpath| subpath.is_closed);
+        if needs_start {
+            self.subpaths.push(Subpath::new(start_if_new));
+        }
+        let subpath = self.subpaths.last_mut().unwrap();
+        // a line to where the subpath already is adds nothing
+        if segment == Segment::Line(subpath.end()) {
+            return;
+        }
+        subpath.segments.push(segment);
+    }
+
+    fn stroke_thickness(&self) -> u32 {
+        let width = self.state.line_width * self.state.transform.scale_factor();
+        width.round().max(1f32) as u32
+    }
+
+    fn add_paths(&mut self, paths: Vec<Path>) -> Result<Vec<PathId>, TrdlError> {
+        paths.into_iter().map(|path| self.drawing.add_path(path)).collect()
+    }
+}
+
+// The signed sweep from start to end angle in the given direction, at most a full turn.
+fn arc_sweep(start_angle: f32, end_angle: f32, counter_clockwise: bool) -> f32 {
+    let turn = 2f32 * PI;
+    let delta = end_angle - start_angle;
+    if counter_clockwise {
+        if delta >= turn { turn } else { delta.rem_euclid(turn) }
+    } else if -delta >= turn {
+        -turn
+    } else {
+        -(-delta).rem_euclid(turn)
+    }
+}
+
+// The two control points and the end point of a cubic Bezier curve.
+type CubicPiece = ((f32, f32), (f32, f32), (f32, f32));
+
+// Split a circular arc into cubic Bezier curves of at most a quarter turn each.
+fn arc_segments(center: (f32, f32), radius: f32, start_angle: f32, sweep: f32) -> Vec<CubicPiece> {
+    let count = (sweep.abs() / (PI / 2f32)).ceil() as usize;
+    let step = if count == 0 { 0f32 } else { sweep / count as f32 };
+    let k = 4f32 / 3f32 * (step / 4f32).tan() * radius;
+    let point = |angle: f32| (center.0 + radius * angle.cos(), center.1 + radius * angle.sin());
+    (0..count).map(|i| {
+        let a0 = start_angle + step * i as f32;
+        let a1 = a0 + step;
+        let p0 = point(a0);
+        let p1 = point(a1);
+        let cp1 = (p0.0 - k * a0.sin(), p0.1 + k * a0.cos());
+        let cp2 = (p1.0 + k * a1.sin(), p1.1 - k * a1.cos());
+        (cp1, cp2, p1)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arc_segments, arc_sweep, Segment, Subpath};
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_arc_sweep() {
+        assert!((arc_sweep(0f32, PI / 2f32, true) - PI / 2f32).abs() < 1e-6);
+        assert!((arc_sweep(0f32, PI / 2f32, false) + 3f32 * PI / 2f32).abs() < 1e-5);
+        assert!((arc_sweep(0f32, 4f32 * PI, true) - 2f32 * PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_arc_segments_on_circle() {
+        let segments = arc_segments((1f32, 2f32), 3f32, 0.2f32, 2f32 * PI);
+        assert_eq!(segments.len(), 4);
+        let end = segments[3].2;
+        assert!((end.0 - (1f32 + 3f32 * 0.2f32.cos())).abs() < 1e-4);
+        assert!((end.1 - (2f32 + 3f32 * 0.2f32.sin())).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_subpath_needs_enough_points() {
+        let mut subpath = Subpath::new((0f32, 0f32));
+        subpath.segments.push(Segment::Line((1f32, 0f32)));
+        assert!(subpath.to_path(false).is_some());
+        assert!(subpath.to_path(true).is_none());
+        subpath.segments.push(Segment::Line((1f32, 1f32)));
+        assert!(subpath.to_path(true).is_some());
+    }
+}
@@ -0,0 +1,259 @@
+//! Colors used for fills, strokes and backgrounds.
+
+/// A color with red, green, blue and alpha components, each in the range 0 to 1. Colors are sRGB
+/// encoded, as in SVG and most image formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub alpha: f32
+}
+
+impl Color {
+    /// Constructor for an opaque color.
+    pub fn rgb(red: f32, green: f32, blue: f32) -> Self {
+        Color { red, green, blue, alpha: 1f32 }
+    }
+
+    /// Constructor for a color with an alpha (opacity) value.
+    pub fn rgba(red: f32, green: f32, blue: f32, alpha: f32) -> Self {
+        Color { red, green, blue, alpha }
+    }
+
+    /// Convert the color components from sRGB to linear RGB, alpha is unchanged.
+    pub fn to_linear(&self) -> Self {
+        Color { red: srgb_to_linear(self.red), green: srgb_to_linear(self.green),
+                blue: srgb_to_linear(self.blue), alpha: self.alpha }
+    }
+
+    /// The color with red, green and blue multiplied by alpha, for premultiplied alpha blending.
+    pub fn premultiplied(&self) -> Self {
+        Color { red: self.red * self.alpha, green: self.green * self.alpha, blue: self.blue * self.alpha,
+                alpha: self.alpha }
+    }
+
+    /// Undo `premultiplied`. Fully transparent colors come back black.
+    pub fn unpremultiplied(&self) -> Self {
+        if self.alpha <= 0f32 {
+            return Color::rgba(0f32, 0f32, 0f32, 0f32);
+        }
+        Color { red: self.red / self.alpha, green: self.green / self.alpha, blue: self.blue / self.alpha,
+                alpha: self.alpha }
+    }
+
+    /// The color as an array of red, green, blue and alpha.
+    pub fn to_array(&self) -> [f32; 4] {
+        [self.red, self.green, self.blue, self.alpha]
+    }
+
+    /// The color t of the way from this one to other, from 0 for this one to 1 for other, blended
+    /// in space. Alpha is always blended as it is.
+    pub fn mix(&self, other: &Color, t: f32, space: ColorSpace) -> Color {
+        let (a, mut b) = (space.components(self), space.components(other));
+        if space == ColorSpace::Hsl {
+            // grays have no hue, they take the other color's so only lightness changes
+            if a[1] <= 0f32 {
+                return space.color([b[0], a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t],
+                                   self.alpha + (other.alpha - self.alpha) * t);
+            }
+            if b[1] <= 0f32 {
+                b[0] = a[0];
+            }
+            // the hue goes the short way round
+            b[0] = a[0] + ((b[0] - a[0] + 0.5f32).rem_euclid(1f32) - 0.5f32);
+        }
+        let mix = |x: f32, y: f32| x + (y - x) * t;
+        space.color([mix(a[0], b[0]), mix(a[1], b[1]), mix(a[2], b[2])], mix(self.alpha, other.alpha))
+    }
+}
+
+/// The color space colors are blended in, see `Path::set_gradient_space` and
+/// `Animation::set_color_space`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// The sRGB encoded components as they are, the default. Midpoints between saturated colors
+    /// come out dark and muddy.
+    #[default]
+    Srgb,
+    /// Linear light, how light itself mixes. Midpoints come out brighter than in sRGB.
+    LinearRgb,
+    /// OKLab, which is even to the eye: lightness changes steadily and midpoints keep their
+    /// color.
+    Oklab,
+    /// Hue, saturation and lightness, with the hue going the short way round the color wheel.
+    Hsl
+}
+
+impl ColorSpace {
+    // The color's components in the space, without alpha. Hues are fractions of a turn.
+    fn components(self, color: &Color) -> [f32; 3] {
+        match self {
+            ColorSpace::Srgb => [color.red, color.green, color.blue],
+            ColorSpace::LinearRgb => {
+                let linear = color.to_linear();
+                [linear.red, linear.green, linear.blue]
+            }
+            ColorSpace::Oklab => {
+                let linear = color.to_linear();
+                let (r, g, b) = (linear.red, linear.green, linear.blue);
+                let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+                let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+                let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+                [0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+                 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+                 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s]
+            }
+            ColorSpace::Hsl => {
+                let (r, g, b) = (color.red, color.green, color.blue);
+                let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+                let lightness = (max + min) / 2f32;
+                let chroma = max - min;
+                if chroma <= 0f32 {
+                    return [0f32, 0f32, lightness];
+                }
+                let saturation = chroma / (1f32 - (2f32 * lightness - 1f32).abs());
+                let sector = if max == r {
+                    ((g - b) / chroma).rem_euclid(6f32)
+                } else if max == g {
+                    (b - r) / chroma + 2f32
+                } else {
+                    (r - g) / chroma + 4f32
+                };
+                [sector / 6f32, saturation, lightness]
+            }
+        }
+    }
+
+    // The color with these components in the space, clamped to what sRGB can show.
+    fn color(self, v: [f32; 3], alpha: f32) -> Color {
+        let [red, green, blue] = match self {
+            ColorSpace::Srgb => v,
+            ColorSpace::LinearRgb => [linear_to_srgb(v[0]), linear_to_srgb(v[1]), linear_to_srgb(v[2])],
+            ColorSpace::Oklab => {
+                let l = (v[0] + 0.396_337_78 * v[1] + 0.215_803_76 * v[2]).powi(3);
+                let m = (v[0] - 0.105_561_346 * v[1] - 0.063_854_17 * v[2]).powi(3);
+                let s = (v[0] - 0.089_484_18 * v[1] - 1.291_485_5 * v[2]).powi(3);
+                [linear_to_srgb(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+                 linear_to_srgb(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+                 linear_to_srgb(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s)]
+            }
+            ColorSpace::Hsl => {
+                let (hue, saturation, lightness) = (v[0].rem_euclid(1f32) * 6f32, v[1], v[2]);
+                let chroma = (1f32 - (2f32 * lightness - 1f32).abs()) * saturation;
+                let x = chroma * (1f32 - (hue % 2f32 - 1f32).abs());
+                let (r, g, b) = match hue as u32 {
+                    0 => (chroma, x, 0f32),
+                    1 => (x, chroma, 0f32),
+                    2 => (0f32, chroma, x),
+                    3 => (0f32, x, chroma),
+                    4 => (x, 0f32, chroma),
+                    _ => (chroma, 0f32, x)
+                };
+                let m = lightness - chroma / 2f32;
+                [r + m, g + m, b + m]
+            }
+        };
+        let clamp = |c: f32| if c.is_nan() { 0f32 } else { c.clamp(0f32, 1f32) };
+        Color::rgba(clamp(red), clamp(green), clamp(blue), alpha)
+    }
+}
+
+// The color at t of a gradient with stops (position, color) in order of position. Colors are
+// blended between stops and the end colors extended beyond them.
+pub(crate) fn gradient_at(stops: &[(f32, Color)], t: f32) -> Color {
+    gradient_in(stops, t, ColorSpace::Srgb)
+}
+
+// The color at t of a gradient, blended in a color space.
+pub(crate) fn gradient_in(stops: &[(f32, Color)], t: f32, space: ColorSpace) -> Color {
+    let next = stops.partition_point(|&(position, _)| position <= t);
+    if next == 0 {
+        return stops.first().map_or(Color::rgba(0f32, 0f32, 0f32, 0f32), |&(_, color)| color);
+    }
+    if next == stops.len() {
+        return stops[next - 1].1;
+    }
+    let ((p0, a), (p1, b)) = (stops[next - 1], stops[next]);
+    let f = if p1 > p0 { (t - p0) / (p1 - p0) } else { 0f32 };
+    a.mix(&b, f, space)
+}
+
+// Convert an sRGB encoded color component to linear RGB.
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Convert a linear RGB color component to sRGB.
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1f32 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gradient_at, gradient_in, srgb_to_linear};
+    use super::{Color, ColorSpace};
+
+    #[test]
+    fn test_srgb_to_linear() {
+        assert_eq!(srgb_to_linear(0f32), 0f32);
+        assert!((srgb_to_linear(1f32) - 1f32).abs() < 1e-6);
+        // mid grey in sRGB is about 21.4% in linear light
+        assert!((srgb_to_linear(0.5f32) - 0.214).abs() < 1e-3);
+        // the linear segment near black
+        assert!((srgb_to_linear(0.04f32) - 0.04 / 12.92).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_to_linear_keeps_alpha() {
+        let color = Color::rgba(1f32, 0.5f32, 0f32, 0.25f32).to_linear();
+        assert_eq!(color.alpha, 0.25f32);
+        assert_eq!(color.blue, 0f32);
+    }
+
+    #[test]
+    fn test_premultiplied() {
+        let color = Color::rgba(1f32, 0.5f32, 0f32, 0.5f32);
+        assert_eq!(color.premultiplied(), Color::rgba(0.5f32, 0.25f32, 0f32, 0.5f32));
+        assert_eq!(color.premultiplied().unpremultiplied(), color);
+        assert_eq!(Color::rgba(1f32, 1f32, 1f32, 0f32).unpremultiplied(), Color::rgba(0f32, 0f32, 0f32, 0f32));
+    }
+
+    #[test]
+    fn test_gradient_at() {
+        let stops = [(0.5f32, Color::rgb(1f32, 0f32, 0f32)), (1f32, Color::rgba(0f32, 0f32, 1f32, 0f32))];
+        assert_eq!(gradient_at(&stops, 0f32), Color::rgb(1f32, 0f32, 0f32));
+        assert_eq!(gradient_at(&stops, 0.75f32), Color::rgba(0.5f32, 0f32, 0.5f32, 0.5f32));
+        assert_eq!(gradient_at(&stops, 2f32), Color::rgba(0f32, 0f32, 1f32, 0f32));
+    }
+
+    #[test]
+    fn test_mix_in_color_spaces() {
+        let (red, green) = (Color::rgb(1f32, 0f32, 0f32), Color::rgb(0f32, 1f32, 0f32));
+        let close = |a: Color, b: Color| (a.red - b.red).abs() < 1e-3 && (a.green - b.green).abs() < 1e-3 &&
+                                         (a.blue - b.blue).abs() < 1e-3 && (a.alpha - b.alpha).abs() < 1e-3;
+        for &space in &[ColorSpace::Srgb, ColorSpace::LinearRgb, ColorSpace::Oklab, ColorSpace::Hsl] {
+            assert!(close(red.mix(&green, 0f32, space), red), "{:?}", space);
+            assert!(close(red.mix(&green, 1f32, space), green), "{:?}", space);
+        }
+        // the midpoint is a dull olive in sRGB, brighter in linear light and a full yellow in HSL
+        assert!(close(red.mix(&green, 0.5f32, ColorSpace::Srgb), Color::rgb(0.5f32, 0.5f32, 0f32)));
+        assert!(close(red.mix(&green, 0.5f32, ColorSpace::LinearRgb), Color::rgb(0.735f32, 0.735f32, 0f32)));
+        assert!(close(red.mix(&green, 0.5f32, ColorSpace::Hsl), Color::rgb(1f32, 1f32, 0f32)));
+        let oklab = red.mix(&green, 0.5f32, ColorSpace::Oklab);
+        assert!(oklab.red > 0.7f32 && oklab.green > 0.5f32 && oklab.blue < 0.1f32);
+        // the hue goes the short way round, from red back through magenta to blue
+        let blue = Color::rgba(0f32, 0f32, 1f32, 0f32);
+        assert!(close(red.mix(&blue, 0.5f32, ColorSpace::Hsl), Color::rgba(1f32, 0f32, 1f32, 0.5f32)));
+        let stops = [(0f32, red), (1f32, green)];
+        assert_eq!(gradient_in(&stops, 0.5f32, ColorSpace::Hsl), red.mix(&green, 0.5f32, ColorSpace::Hsl));
+    }
+}
@@ -0,0 +1,512 @@
+//! TrueType and OpenType fonts: glyph outlines as contours and as paths ready to fill, with the
+//! holes of glyphs like "o" joined to their outlines so each filled part is one simple path.
+//!
+//! Glyphs are measured in ems with y up, so a glyph drawn at size s is scaled by s. Each glyph
+//! is read and tessellated once and then shared from the font's cache.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path as FilePath;
+use std::rc::Rc;
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+use super::bezier::polygon_area;
+use super::gl2d::drawing::Path;
+use super::mesh::Mesh;
+use super::transform::Transform2D;
+use super::TrdlError;
+
+// glyph meshes follow curves to within this fraction of an em
+const MESH_TOLERANCE: f32 = 1f32 / 512f32;
+// points per curve when testing which contours are inside which
+const NESTING_STEPS: usize = 4;
+
+/// A piece of a glyph contour, ending at the point given last.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContourSegment {
+    Line((f32, f32)),
+    /// A quadratic Bezier curve with one control point, as in TrueType fonts.
+    Quadratic((f32, f32), (f32, f32)),
+    /// A cubic Bezier curve with two control points, as in CFF (OpenType) fonts.
+    Cubic((f32, f32), (f32, f32), (f32, f32))
+}
+
+/// A closed loop of a glyph outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    pub start: (f32, f32),
+    pub segments: Vec<ContourSegment>
+}
+
+/// A glyph's outline, paths and tessellation, in ems.
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    id: u16,
+    advance: f32,
+    contours: Vec<Contour>,
+    paths: Vec<Path>,
+    meshes: Vec<Mesh>
+}
+
+impl Glyph {
+    /// The glyph's index in its font.
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// How far to move right after the glyph.
+    pub fn advance(&self) -> f32 {
+        self.advance
+    }
+
+    /// The outline as the font stores it. Empty for glyphs like the space.
+    pub fn contours(&self) -> &[Contour] {
+        &self.contours
+    }
+
+    /// Counter clockwise closed paths, one per filled part of the glyph, with its holes joined
+    /// to it by a cut. Fill them to draw the glyph.
+    pub fn paths(&self) -> &[Path] {
+        &self.paths
+    }
+
+    /// Triangles covering each of `paths`, for renderers that do not handle curves.
+    pub fn meshes(&self) -> &[Mesh] {
+        &self.meshes
+    }
+}
+
+/// A parsed TrueType or OpenType font.
+pub struct Font {
+    data: Vec<u8>,
+    units_per_em: f32,
+    ascender: f32,
+    descender: f32,
+    line_gap: f32,
+    glyphs: RefCell<HashMap<u16, Option<Rc<Glyph>>>>
+}
+
+impl Font {
+    /// Parse a font from the contents of a .ttf or .otf file.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Font, TrdlError> {
+        let (units_per_em, ascender, descender, line_gap) = {
+            let face = Face::parse(&data, 0)?;
+            (face.units_per_em() as f32, face.ascender() as f32, face.descender() as f32, face.line_gap() as f32)
+        };
+        Ok(Font {
+            data,
+            units_per_em,
+            ascender: ascender / units_per_em,
+            descender: descender / units_per_em,
+            line_gap: line_gap / units_per_em,
+            glyphs: RefCell::new(HashMap::new())
+        })
+    }
+
+    /// Read and parse a font file.
+    pub fn open<P: AsRef<FilePath>>(file: P) -> Result<Font, TrdlError> {
+        Font::from_bytes(fs::read(file)?)
+    }
+
+    /// How far above the baseline the font reaches, in ems.
+    pub fn ascender(&self) -> f32 {
+        self.ascender
+    }
+
+    /// How far below the baseline the font reaches, negative, in ems.
+    pub fn descender(&self) -> f32 {
+        self.descender
+    }
+
+    /// Distance from one baseline to the next, in ems.
+    pub fn line_height(&self) -> f32 {
+        self.ascender - self.descender + self.line_gap
+    }
+
+    // The face, parsing again is cheap and saves holding a self-referencing struct.
+    fn face(&self) -> Face<'_> {
+        Face::parse(&self.data, 0).expect("font was parsed when it was loaded")
+    }
+
+    /// The glyph a character is drawn with, if the font has one.
+    pub fn glyph_id(&self, character: char) -> Option<u16> {
+        self.face().glyph_index(character).map(|id| id.0)
+    }
+
+    /// A glyph by index, None if the font has no such glyph or its outline could not be
+    /// tessellated.
+    pub fn glyph(&self, id: u16) -> Option<Rc<Glyph>> {
+        if let Some(glyph) = self.glyphs.borrow().get(&id) {
+            return glyph.clone();
+        }
+        let glyph = self.load_glyph(id).map(Rc::new);
+        self.glyphs.borrow_mut().insert(id, glyph.clone());
+        glyph
+    }
+
+    /// The glyph a character is drawn with.
+    pub fn glyph_for(&self, character: char) -> Option<Rc<Glyph>> {
+        self.glyph_id(character).and_then(|id| self.glyph(id))
+    }
+
+    /// How many glyphs are cached.
+    pub fn cached_glyphs(&self) -> usize {
+        self.glyphs.borrow().len()
+    }
+
+    fn load_glyph(&self, id: u16) -> Option<Glyph> {
+        let face = self.face();
+        let advance = face.glyph_hor_advance(GlyphId(id))? as f32 / self.units_per_em;
+        let mut builder = ContourBuilder { scale: 1f32 / self.units_per_em, contours: Vec::new() };
+        // glyphs without outlines, like the space, have none
+        face.outline_glyph(GlyphId(id), &mut builder);
+        let contours: Vec<Contour> = builder.contours.into_iter().filter(|c| !c.segments.is_empty()).collect();
+        let paths = glyph_paths(&contours);
+        let meshes = paths.iter().map(|path| path.fill_mesh(MESH_TOLERANCE)).collect::<Result<_, _>>().ok()?;
+        Some(Glyph { id, advance, contours, paths, meshes })
+    }
+
+    /// Lay out a line of text and return the paths of its glyphs, unstyled, with the start of
+    /// the baseline at origin and the text size (one em) in drawing units. Newlines start a line
+    /// below. Characters the font has no glyph for are skipped.
+    pub fn text_paths(&self, text: &str, origin: (f32, f32), size: f32) -> Vec<Path> {
+        let mut paths = Vec::new();
+        let mut pen = origin;
+        for character in text.chars() {
+            if character == '\n' {
+                pen = (origin.0, pen.1 - self.line_height() * size);
+                continue;
+            }
+            if let Some(glyph) = self.glyph_for(character) {
+                let transform = Transform2D::translation(pen.0, pen.1) * Transform2D::scaling(size, size);
+                paths.extend(glyph.paths().iter().map(|path| path.transformed(&transform)));
+                pen.0 += glyph.advance() * size;
+            }
+        }
+        paths
+    }
+
+    /// The width of a line of text at a size.
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        text.chars().filter_map(|c| self.glyph_for(c)).map(|glyph| glyph.advance() * size).sum()
+    }
+}
+
+// Collects ttf-parser's outline callbacks into contours, scaled to ems.
+struct ContourBuilder {
+    scale: f32,
+    contours: Vec<Contour>
+}
+
+impl ContourBuilder {
+    fn push(&mut self, segment: ContourSegment) {
+        if let Some(contour) = self.contours.last_mut() {
+            contour.segments.push(segment);
+        }
+    }
+}
+
+impl OutlineBuilder for ContourBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.contours.push(Contour { start: (x * self.scale, y * self.scale), segments: Vec::new() });
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let s = self.scale;
+        self.push(ContourSegment::Line((x * s, y * s)));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let s = self.scale;
+        self.push(ContourSegment::Quadratic((x1 * s, y1 * s), (x * s, y * s)));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let s = self.scale;
+        self.push(ContourSegment::Cubic((x1 * s, y1 * s), (x2 * s, y2 * s), (x * s, y * s)));
+    }
+
+    fn close(&mut self) {}
+}
+
+// The two control points of a cubic curve.
+type Controls = ((f32, f32), (f32, f32));
+
+// A closed loop of points where curves[i] holds the control points of a cubic curve from
+// points[i] to the next point, or None for a straight line.
+#[derive(Debug, Clone)]
+struct Ring {
+    points: Vec<(f32, f32)>,
+    curves: Vec<Option<Controls>>
+}
+
+impl Ring {
+    fn from_contour(contour: &Contour) -> Ring {
+        let mut points = vec![contour.start];
+        let mut curves = Vec::new();
+        for segment in &contour.segments {
+            let from = points[points.len() - 1];
+            let (curve, end) = match *segment {
+                ContourSegment::Line(end) => (None, end),
+                ContourSegment::Quadratic(q, end) => {
+                    let toward = |p: (f32, f32)| (p.0 + 2f32 / 3f32 * (q.0 - p.0), p.1 + 2f32 / 3f32 * (q.1 - p.1));
+                    (Some((toward(from), toward(end))), end)
+                }
+                ContourSegment::Cubic(c1, c2, end) => (Some((c1, c2)), end)
+            };
+            curves.push(curve);
+            points.push(end);
+        }
+        // the contour ends where it started, the closing segment joins them
+        if points.len() > 1 && points[points.len() - 1] == points[0] {
+            points.pop();
+        } else {
+            curves.push(None);
+        }
+        Ring { points, curves }
+    }
+
+    // Points along the ring, curves sampled, for area and containment tests.
+    fn outline(&self) -> Vec<(f32, f32)> {
+        let n = self.points.len();
+        let mut outline = Vec::with_capacity(n * NESTING_STEPS);
+        for i in 0..n {
+            let (p0, p3) = (self.points[i], self.points[(i + 1) % n]);
+            outline.push(p0);
+            if let Some((p1, p2)) = self.curves[i] {
+                for step in 1..NESTING_STEPS {
+                    let t = step as f32 / NESTING_STEPS as f32;
+                    let mt = 1f32 - t;
+                    let (a, b, c, d) = (mt * mt * mt, 3f32 * mt * mt * t, 3f32 * mt * t * t, t * t * t);
+                    outline.push((a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0, a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1));
+                }
+            }
+        }
+        outline
+    }
+
+    fn reversed(&self) -> Ring {
+        let n = self.points.len();
+        // point i of the reversed ring is point n-1-i, its segment is the old one into that point
+        let points = (0..n).map(|i| self.points[n - 1 - i]).collect();
+        let curves = (0..n).map(|i| self.curves[(2 * n - 2 - i) % n].map(|(c1, c2)| (c2, c1))).collect();
+        Ring { points, curves }
+    }
+
+    fn to_path(&self) -> Path {
+        let n = self.points.len();
+        let segment = |path: Path, i: usize| match self.curves[i] {
+            Some((c1, c2)) => path.curve_to(c1, c2, self.points[(i + 1) % n]),
+            None => path.line_to(self.points[(i + 1) % n])
+        };
+        let path = (0..n - 1).fold(Path::new(self.points[0]), segment);
+        match self.curves[n - 1] {
+            // ending on the start point makes close_path keep the curve as the closing segment
+            Some(_) => segment(path, n - 1).close_path(),
+            None => path.close_path()
+        }
+    }
+}
+
+// Even-odd test of whether a point is inside a polygon.
+fn contains(polygon: &[(f32, f32)], point: (f32, f32)) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.1 > point.1) != (b.1 > point.1) && point.0 < a.0 + (point.1 - a.1) / (b.1 - a.1) * (b.0 - a.0) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Join a hole (wound clockwise) into an outline (counter clockwise) with a cut from the hole's
+// rightmost point to a point of the outline it can see, as in Eberly's "Triangulation by Ear
+// Clipping".
+fn bridge(outer: &Ring, hole: &Ring) -> Ring {
+    let m = (0..hole.points.len()).max_by(|&a, &b| hole.points[a].0.total_cmp(&hole.points[b].0)).unwrap();
+    let mp = hole.points[m];
+    let n = outer.points.len();
+
+    // the closest outline edge to the right of the hole's rightmost point
+    let mut best: Option<(f32, usize)> = None;
+    for i in 0..n {
+        let (a, b) = (outer.points[i], outer.points[(i + 1) % n]);
+        if (a.1 > mp.1) == (b.1 > mp.1) {
+            continue;
+        }
+        let x = a.0 + (mp.1 - a.1) / (b.1 - a.1) * (b.0 - a.0);
+        if x >= mp.0 && best.is_none_or(|(best_x, _)| x < best_x) {
+            best = Some((x, i));
+        }
+    }
+    let p = match best {
+        Some((x, i)) => {
+            // the end of the edge further right, unless another point is in the way
+            let candidate = if outer.points[i].0 > outer.points[(i + 1) % n].0 { i } else { (i + 1) % n };
+            let hit = (x, mp.1);
+            let angle = |j: usize| {
+                let q = outer.points[j];
+                (q.1 - mp.1).abs().atan2(q.0 - mp.0)
+            };
+            if outer.points[candidate].1 == mp.1 {
+                // the ray goes through the point, nothing can be in the way
+                candidate
+            } else {
+                (0..n).filter(|&j| j != candidate && in_triangle(outer.points[j], mp, hit, outer.points[candidate]))
+                    .min_by(|&a, &b| angle(a).total_cmp(&angle(b)))
+                    .unwrap_or(candidate)
+            }
+        }
+        // only for odd outlines, the nearest point will do
+        None => {
+            let distance = |j: usize| (outer.points[j].0 - mp.0).hypot(outer.points[j].1 - mp.1);
+            (0..n).min_by(|&a, &b| distance(a).total_cmp(&distance(b))).unwrap()
+        }
+    };
+    // a point an earlier cut goes to is in the outline twice, use the copy facing the hole
+    let p = (0..n).filter(|&j| outer.points[j] == outer.points[p])
+        .find(|&j| faces(outer, j, mp))
+        .unwrap_or(p);
+    splice(outer, p, hole, m)
+}
+
+// Whether a point is inside the corner of a counter clockwise outline at its point j.
+fn faces(outline: &Ring, j: usize, point: (f32, f32)) -> bool {
+    let n = outline.points.len();
+    let (a, p, b) = (outline.points[(j + n - 1) % n], outline.points[j], outline.points[(j + 1) % n]);
+    let left = |u: (f32, f32), v: (f32, f32)| (v.0 - u.0) * (point.1 - u.1) - (v.1 - u.1) * (point.0 - u.0) >= 0f32;
+    if (p.0 - a.0) * (b.1 - p.1) - (p.1 - a.1) * (b.0 - p.0) >= 0f32 {
+        left(a, p) && left(p, b)
+    } else {
+        left(a, p) || left(p, b)
+    }
+}
+
+// An outline that goes around outer to its point p, across to hole's point m, around the hole and
+// back the same way.
+fn splice(outer: &Ring, p: usize, hole: &Ring, m: usize) -> Ring {
+    let n = outer.points.len();
+    let h = hole.points.len();
+    let mut points = Vec::with_capacity(n + h + 2);
+    let mut curves = Vec::with_capacity(n + h + 2);
+    for i in 0..=p {
+        points.push(outer.points[i]);
+        curves.push(if i < p { outer.curves[i] } else { None });
+    }
+    for k in 0..=h {
+        let j = (m + k) % h;
+        points.push(hole.points[j]);
+        curves.push(if k < h { hole.curves[j] } else { None });
+    }
+    for i in p..n {
+        points.push(outer.points[i]);
+        curves.push(outer.curves[i]);
+    }
+    Ring { points, curves }
+}
+
+// Whether a point is inside or on the edge of a triangle, either winding.
+fn in_triangle(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> bool {
+    let side = |u: (f32, f32), v: (f32, f32)| (v.0 - u.0) * (p.1 - u.1) - (v.1 - u.1) * (p.0 - u.0);
+    let (d1, d2, d3) = (side(a, b), side(b, c), side(c, a));
+    !((d1 < 0f32 || d2 < 0f32 || d3 < 0f32) && (d1 > 0f32 || d2 > 0f32 || d3 > 0f32))
+}
+
+// Turn contours into fillable paths. A contour is a hole when it winds the other way from the
+// smallest contour around it, otherwise it is an outline of its own, like an accent overlapping
+// its letter. TrueType and CFF wind outlines in opposite directions, so only relative winding
+// counts and the results are oriented afterwards.
+fn glyph_paths(contours: &[Contour]) -> Vec<Path> {
+    let rings: Vec<Ring> = contours.iter().map(Ring::from_contour).filter(|r| r.points.len() >= 2).collect();
+    let outlines: Vec<Vec<(f32, f32)>> = rings.iter().map(Ring::outline).collect();
+    let areas: Vec<f32> = outlines.iter().map(|o| polygon_area(o)).collect();
+    let parents: Vec<Option<usize>> = (0..rings.len()).map(|i| {
+        (0..rings.len())
+            .filter(|&j| j != i && areas[j].abs() > areas[i].abs() && contains(&outlines[j], outlines[i][0]))
+            .min_by(|&a, &b| areas[a].abs().total_cmp(&areas[b].abs()))
+    }).collect();
+    let is_hole = |i: usize| parents[i].is_some_and(|p| (areas[i] > 0f32) != (areas[p] > 0f32));
+
+    let mut paths = Vec::new();
+    for i in 0..rings.len() {
+        if is_hole(i) || areas[i] == 0f32 {
+            continue;
+        }
+        let mut outline = if areas[i] < 0f32 { rings[i].reversed() } else { rings[i].clone() };
+        let mut holes: Vec<usize> = (0..rings.len()).filter(|&j| parents[j] == Some(i) && is_hole(j)).collect();
+        // rightmost hole first, so later cuts do not cross earlier ones
+        let right = |j: usize| rings[j].points.iter().fold(f32::MIN, |x, p| x.max(p.0));
+        holes.sort_by(|&a, &b| right(b).total_cmp(&right(a)));
+        for j in holes {
+            let hole = if areas[j] > 0f32 { rings[j].reversed() } else { rings[j].clone() };
+            outline = bridge(&outline, &hole);
+        }
+        paths.push(outline.to_path());
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glyph_paths, Contour, ContourSegment, Font, Ring};
+
+    fn square(center: (f32, f32), half: f32, clockwise: bool) -> Contour {
+        let mut corners = [(center.0 - half, center.1 - half), (center.0 + half, center.1 - half),
+                               (center.0 + half, center.1 + half), (center.0 - half, center.1 + half)];
+        if clockwise {
+            corners.reverse();
+        }
+        Contour { start: corners[0], segments: corners[1..].iter().chain(&corners[..1]).map(|&p| ContourSegment::Line(p)).collect() }
+    }
+
+    #[test]
+    fn test_holes_are_bridged() {
+        // an "o" wound the TrueType way: clockwise outline, counter clockwise hole, and a dot
+        let contours = [square((0f32, 0f32), 2f32, true), square((0f32, 0f32), 1f32, false),
+                        square((5f32, 0f32), 0.5f32, true)];
+        let paths = glyph_paths(&contours);
+        assert_eq!(paths.len(), 2);
+        let mesh = paths[0].fill_mesh(0.01f32).unwrap();
+        let area = |t: [(f32, f32); 3]| ((t[1].0 - t[0].0) * (t[2].1 - t[0].1) - (t[2].0 - t[0].0) * (t[1].1 - t[0].1)) / 2f32;
+        // 16 minus the hole's 4, every triangle counter clockwise
+        assert!((mesh.triangles().map(area).sum::<f32>() - 12f32).abs() < 1e-4);
+        assert!(mesh.triangles().all(|t| area(t) >= 0f32));
+        assert_eq!(paths[1].fill_mesh(0.01f32).unwrap().triangle_count(), 2);
+    }
+
+    #[test]
+    fn test_holes_cut_to_the_same_point() {
+        // a "θ", both holes are cut to the outline's top right corner
+        let polygon = |points: &[(f32, f32)]| Contour {
+            start: points[0],
+            segments: points[1..].iter().chain(&points[..1]).map(|&p| ContourSegment::Line(p)).collect()
+        };
+        let contours = [polygon(&[(-2f32, -2f32), (2f32, -2f32), (2f32, 2f32), (-2f32, 2f32)]),
+                        polygon(&[(-1f32, 0.2f32), (-1f32, 1.5f32), (1f32, 1.5f32), (1f32, 0.2f32)]),
+                        polygon(&[(-1f32, -1.5f32), (-1f32, -0.2f32), (1f32, -0.2f32), (1f32, -1.5f32)])];
+        let paths = glyph_paths(&contours);
+        assert_eq!(paths.len(), 1);
+        let mesh = paths[0].fill_mesh(0.01f32).unwrap();
+        assert!((mesh.area() - (16f32 - 2f32 * 2.6f32)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_quadratic_to_cubic_and_reverse() {
+        let contour = Contour { start: (0f32, 0f32), segments: vec![
+            ContourSegment::Quadratic((3f32, 3f32), (6f32, 0f32)),
+            ContourSegment::Line((0f32, 0f32))
+        ] };
+        let ring = Ring::from_contour(&contour);
+        assert_eq!(ring.points, vec![(0f32, 0f32), (6f32, 0f32)]);
+        assert_eq!(ring.curves, vec![Some(((2f32, 2f32), (4f32, 2f32))), None]);
+        let reversed = ring.reversed();
+        assert_eq!(reversed.points, vec![(6f32, 0f32), (0f32, 0f32)]);
+        assert_eq!(reversed.curves, vec![Some(((4f32, 2f32), (2f32, 2f32))), None]);
+    }
+
+    #[test]
+    fn test_invalid_font() {
+        assert!(Font::from_bytes(vec![0u8; 16]).is_err());
+    }
+}
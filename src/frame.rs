@@ -0,0 +1,258 @@
+//! Frame timing: delta time, fixed timestep updates and frame rate limiting, plus a run loop built
+//! on them for windowing libraries where the application owns the loop (GLFW, SDL2).
+
+use std::thread;
+use std::time::{Duration, Instant};
+use super::gl2d::drawing::{Drawing, Window};
+
+// a frame that takes longer than this counts as this long, so a breakpoint or a dragged window
+// does not cause a burst of fixed updates
+const MAX_FRAME_TIME: f32 = 0.25f32;
+// how often the frames per second estimate is refreshed
+const FPS_INTERVAL: f32 = 0.5f32;
+
+/// Timing settings for a frame loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoopConfig {
+    /// Length of a fixed update step in seconds, see `FrameContext::fixed_steps`.
+    pub fixed_timestep: f32,
+    /// Sleep at the end of frames to stay at or under this many frames per second. With vsync
+    /// on, swapping buffers already waits and the limit only matters if it is lower than the
+    /// refresh rate.
+    pub max_fps: Option<f32>
+}
+
+impl Default for LoopConfig {
+    fn default() -> Self {
+        LoopConfig { fixed_timestep: 1f32 / 60f32, max_fps: Some(60f32) }
+    }
+}
+
+/// Timing for one frame, produced by `FrameTimer::begin_frame`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTiming {
+    /// Seconds since the previous frame began.
+    pub dt: f32,
+    /// Number of fixed updates to run this frame.
+    pub fixed_steps: u32,
+    /// How far between the last fixed update and the next one this frame is, from 0 to 1, for
+    /// interpolating what is drawn.
+    pub alpha: f32,
+    /// Frame number, starting at 0.
+    pub frame: u64
+}
+
+/// Measures frames, for loops the application drives itself (e.g. winit's event loop).
+#[derive(Debug, Clone)]
+pub struct FrameTimer {
+    config: LoopConfig,
+    last_frame: Option<Instant>,
+    frame_start: Option<Instant>,
+    accumulator: f32,
+    frame: u64,
+    fps: f32,
+    fps_frames: u32,
+    fps_time: f32
+}
+
+impl FrameTimer {
+    /// Constructor.
+    pub fn new(config: LoopConfig) -> Self {
+        FrameTimer {
+            config,
+            last_frame: None,
+            frame_start: None,
+            accumulator: 0f32,
+            frame: 0,
+            fps: 0f32,
+            fps_frames: 0,
+            fps_time: 0f32
+        }
+    }
+
+    /// Average frames per second over the last half second or so, 0 until then.
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    /// Call at the start of every frame.
+    pub fn begin_frame(&mut self) -> FrameTiming {
+        self.begin_frame_at(Instant::now())
+    }
+
+    /// Call at the end of every frame, sleeps to keep under the frame rate limit.
+    pub fn end_frame(&mut self) {
+        if let (Some(max_fps), Some(start)) = (self.config.max_fps, self.frame_start) {
+            let budget = Duration::from_secs_f32(1f32 / max_fps);
+            let elapsed = start.elapsed();
+            if elapsed < budget {
+                thread::sleep(budget - elapsed);
+            }
+        }
+    }
+
+    fn begin_frame_at(&mut self, now: Instant) -> FrameTiming {
+        let dt = match self.last_frame {
+            Some(last) => now.duration_since(last).as_secs_f32(),
+            None => 0f32
+        };
+        if self.last_frame.is_some() {
+            self.fps_frames += 1;
+            self.fps_time += dt;
+            if self.fps_time >= FPS_INTERVAL {
+                self.fps = self.fps_frames as f32 / self.fps_time;
+                self.fps_frames = 0;
+                self.fps_time = 0f32;
+            }
+        }
+        self.last_frame = Some(now);
+        self.frame_start = Some(now);
+
+        let step = self.config.fixed_timestep;
+        self.accumulator += dt.min(MAX_FRAME_TIME);
+        let mut fixed_steps = 0;
+        while self.accumulator >= step {
+            self.accumulator -= step;
+            fixed_steps += 1;
+        }
+        let timing = FrameTiming { dt, fixed_steps, alpha: self.accumulator / step, frame: self.frame };
+        self.frame += 1;
+        timing
+    }
+}
+
+/// What the run loop closure gets every frame.
+pub struct FrameContext<'f, 'a: 'f, W: Window + 'a> {
+    drawing: &'f mut Drawing<'a, W>,
+    timing: FrameTiming,
+    fixed_dt: f32,
+    fps: f32,
+    quit: bool
+}
+
+impl<'f, 'a: 'f, W: Window + 'a> FrameContext<'f, 'a, W> {
+    /// The drawing, to change paths before it is drawn.
+    pub fn drawing(&mut self) -> &mut Drawing<'a, W> {
+        self.drawing
+    }
+
+    /// Seconds since the previous frame.
+    pub fn dt(&self) -> f32 {
+        self.timing.dt
+    }
+
+    /// How many fixed updates of `fixed_dt` seconds to run this frame.
+    pub fn fixed_steps(&self) -> u32 {
+        self.timing.fixed_steps
+    }
+
+    /// Length of a fixed update in seconds.
+    pub fn fixed_dt(&self) -> f32 {
+        self.fixed_dt
+    }
+
+    /// Fraction of a fixed step left over after this frame's fixed updates.
+    pub fn alpha(&self) -> f32 {
+        self.timing.alpha
+    }
+
+    /// Frame number, starting at 0.
+    pub fn frame(&self) -> u64 {
+        self.timing.frame
+    }
+
+    /// The drawing's animation time in seconds, see `Drawing::time`.
+    pub fn time(&self) -> f32 {
+        self.drawing.time()
+    }
+
+    /// Average frames per second.
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    /// Stop the loop after this frame, it is not drawn.
+    pub fn quit(&mut self) {
+        self.quit = true;
+    }
+}
+
+/// Run frames with the default `LoopConfig` until the closure calls `quit`. Each frame the
+/// closure runs (poll window events there), then the drawing is drawn and the window's buffers
+/// swapped.
+pub fn run_loop<W, F>(window: &W, drawing: &mut Drawing<'_, W>, frame: F)
+        where W: Window, F: FnMut(&mut FrameContext<'_, '_, W>) {
+    run_loop_with(window, drawing, LoopConfig::default(), frame)
+}
+
+/// `run_loop` with explicit timing settings.
+pub fn run_loop_with<W, F>(window: &W, drawing: &mut Drawing<'_, W>, config: LoopConfig, mut frame: F)
+        where W: Window, F: FnMut(&mut FrameContext<'_, '_, W>) {
+    let mut timer = FrameTimer::new(config);
+    while run_frame(&mut timer, drawing, &mut frame) {
+        window.swap_buffers();
+        timer.end_frame();
+    }
+}
+
+// Begin a frame, let the closure update the drawing and draw it. Returns false without drawing if
+// the closure quit. The caller swaps buffers and ends the frame.
+pub(crate) fn run_frame<W, F>(timer: &mut FrameTimer, drawing: &mut Drawing<'_, W>, frame: F) -> bool
+        where W: Window, F: FnOnce(&mut FrameContext<'_, '_, W>) {
+    let timing = timer.begin_frame();
+    drawing.make_current();
+    let quit = {
+        let mut context = FrameContext {
+            drawing: &mut *drawing,
+            timing,
+            fixed_dt: timer.config.fixed_timestep,
+            fps: timer.fps(),
+            quit: false
+        };
+        frame(&mut context);
+        context.quit
+    };
+    if !quit {
+        drawing.draw();
+    }
+    !quit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameTimer, LoopConfig};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_fixed_steps() {
+        let mut timer = FrameTimer::new(LoopConfig { fixed_timestep: 0.01f32, max_fps: None });
+        let start = Instant::now();
+        let first = timer.begin_frame_at(start);
+        assert_eq!((first.dt, first.fixed_steps, first.frame), (0f32, 0, 0));
+        let second = timer.begin_frame_at(start + Duration::from_millis(25));
+        assert_eq!(second.fixed_steps, 2);
+        assert!((second.alpha - 0.5f32).abs() < 1e-3);
+        let third = timer.begin_frame_at(start + Duration::from_millis(30));
+        assert_eq!(third.fixed_steps, 1);
+    }
+
+    #[test]
+    fn test_long_frames_are_clamped() {
+        let mut timer = FrameTimer::new(LoopConfig { fixed_timestep: 0.05f32, max_fps: None });
+        let start = Instant::now();
+        timer.begin_frame_at(start);
+        let timing = timer.begin_frame_at(start + Duration::from_secs(10));
+        assert_eq!(timing.dt, 10f32);
+        assert_eq!(timing.fixed_steps, 5);
+    }
+
+    #[test]
+    fn test_fps() {
+        let mut timer = FrameTimer::new(LoopConfig::default());
+        let start = Instant::now();
+        for i in 0..=30 {
+            timer.begin_frame_at(start + Duration::from_millis(20 * i));
+        }
+        assert!((timer.fps() - 50f32).abs() < 1f32);
+    }
+}
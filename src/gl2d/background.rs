@@ -0,0 +1,394 @@
+extern crate gl;
+
+use std::mem;
+use std::ffi::CString;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::texture::Texture;
+use super::super::color::Color;
+use super::super::image::ImageData;
+use super::super::TrdlError;
+
+/// What is drawn behind all the paths of a drawing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Background {
+    /// A single color, the alpha is used as the clear alpha.
+    Solid(Color),
+    /// A linear gradient from start_color at start to end_color at end, in window coordinates.
+    /// Beyond either end the gradient color is extended.
+    LinearGradient { start: (f32, f32), end: (f32, f32), start_color: Color, end_color: Color },
+    /// An image stretched over the whole window.
+    Image(ImageData),
+    /// Clear to transparent black, for compositing the drawing over other content.
+    Transparent,
+    /// A fragment shader of your own drawn over the whole window, for procedural skies, noise
+    /// and other backgrounds that change from frame to frame.
+    Shader(BackgroundShader)
+}
+
+/// A value for a uniform of a `BackgroundShader`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformValue {
+    Int(i32),
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4])
+}
+
+impl UniformValue {
+    // Set the uniform at location of the program in use.
+    unsafe fn apply(self, location: GLint) {
+        match self {
+            UniformValue::Int(value) => gl::Uniform1i(location, value),
+            UniformValue::Float(value) => gl::Uniform1f(location, value),
+            UniformValue::Vec2(value) => gl::Uniform2fv(location, 1, value.as_ptr()),
+            UniformValue::Vec3(value) => gl::Uniform3fv(location, 1, value.as_ptr()),
+            UniformValue::Vec4(value) => gl::Uniform4fv(location, 1, value.as_ptr())
+        }
+    }
+}
+
+impl From<i32> for UniformValue {
+    fn from(value: i32) -> Self {
+        UniformValue::Int(value)
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(value: f32) -> Self {
+        UniformValue::Float(value)
+    }
+}
+
+impl From<(f32, f32)> for UniformValue {
+    fn from(value: (f32, f32)) -> Self {
+        UniformValue::Vec2([value.0, value.1])
+    }
+}
+
+/// Colors are passed as a vec4, sRGB encoded like the colors the hook returns.
+impl From<Color> for UniformValue {
+    fn from(color: Color) -> Self {
+        UniformValue::Vec4(color.to_array())
+    }
+}
+
+/// A background drawn by a fragment shader, see `Background::Shader`. The source defines the
+/// hook `vec4 background(vec2 uv)`, returning the color at uv, which runs from (0, 0) at the
+/// bottom left of the window to (1, 1) at the top right. Colors are straight alpha and sRGB
+/// encoded like those of paths. Besides the uniforms set here the hook can read
+/// `vec2 resolution` (the window size in pixels), `float time` and `int frame_index` (see
+/// `Drawing::time`), which the drawing declares and sets itself. The source must not have a
+/// `#version` line or a `main` function of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackgroundShader {
+    source: String,
+    uniforms: Vec<(String, UniformValue)>
+}
+
+impl BackgroundShader {
+    /// Constructor, from the GLSL source of the hook. It is compiled by `Drawing::set_background`.
+    pub fn new(source: &str) -> Self {
+        BackgroundShader { source: source.to_string(), uniforms: Vec::new() }
+    }
+
+    /// The GLSL source of the hook.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Set a uniform the hook declares, kept until set again. Names with a nul character are
+    /// ignored, and so are uniforms the compiled hook does not use.
+    pub fn set_uniform(&mut self, name: &str, value: impl Into<UniformValue>) {
+        if name.contains('\0') {
+            return;
+        }
+        let value = value.into();
+        match self.uniforms.iter_mut().find(|uniform| uniform.0 == name) {
+            Some(uniform) => uniform.1 = value,
+            None => self.uniforms.push((name.to_string(), value))
+        }
+    }
+
+    /// The value of a uniform set with `set_uniform`.
+    pub fn uniform(&self, name: &str) -> Option<UniformValue> {
+        self.uniforms.iter().find(|uniform| uniform.0 == name).map(|uniform| uniform.1)
+    }
+}
+
+impl Background {
+    /// The color the framebuffer is cleared to before anything is drawn.
+    pub fn clear_color(&self) -> Color {
+        match *self {
+            Background::Solid(color) => color,
+            Background::LinearGradient { .. } | Background::Image(_) | Background::Shader(_) => Color::rgb(0f32, 0f32, 0f32),
+            Background::Transparent => Color::rgba(0f32, 0f32, 0f32, 0f32)
+        }
+    }
+
+    /// Whether a fullscreen quad has to be drawn, as opposed to just clearing.
+    pub fn needs_quad(&self) -> bool {
+        match *self {
+            Background::LinearGradient { .. } | Background::Image(_) | Background::Shader(_) => true,
+            Background::Solid(_) | Background::Transparent => false
+        }
+    }
+}
+
+/// What a draw does with the frame that is already there before drawing the paths.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ClearMode {
+    /// Clear to the drawing's background and draw it, what `Drawing::draw` does.
+    #[default]
+    Background,
+    /// Clear to a color instead of the background.
+    Color(Color),
+    /// Clear nothing, keeping the colors and depths of what was drawn before, for passes drawn
+    /// into a frame another pass started. Paths behind what is already there stay hidden.
+    KeepPrevious,
+    /// Clear only the depth buffer and draw over the previous frame's colors, for trails and
+    /// other accumulation effects.
+    DepthOnly,
+    /// Clear the depth buffer and draw the background over the previous frame with this opacity,
+    /// so what was drawn before fades out over a few frames instead of vanishing. What
+    /// `Drawing::draw` does with trails on, see `Drawing::set_trail`.
+    Fade(f32)
+}
+
+/// Draws gradient, image and shader backgrounds as a fullscreen quad.
+pub struct BackgroundRenderer {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    position_vbo: GLuint,
+    texture: Option<Texture>,
+    // the hook source of the last background shader set and its program
+    hook: Option<(String, shader::ShaderProgram)>,
+    // the animation time and frame index handed to background shaders
+    time: f32,
+    frame_index: u64,
+
+    mode_uniform: GLint,
+    gradient_start_uniform: GLint,
+    gradient_end_uniform: GLint,
+    start_color_uniform: GLint,
+    end_color_uniform: GLint,
+    image_uniform: GLint,
+    srgb_uniform: GLint,
+    opacity_uniform: GLint
+}
+
+impl BackgroundRenderer {
+    /// Constructor, compiles the background shaders and creates the quad.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/background_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/background_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        let quad: [GLfloat; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+        unsafe {
+            let c_str = CString::new("in_position").unwrap();
+            let in_position = gl::GetAttribLocation(program_id, c_str.as_ptr());
+
+            let mut position_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut position_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, position_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (quad.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           quad.as_ptr() as *const c_void,
+                           gl::STATIC_DRAW);
+
+            let mut vao_handle = 0 as GLuint;
+            gl::GenVertexArrays(1, &mut vao_handle);
+            gl::BindVertexArray(vao_handle);
+            gl::EnableVertexAttribArray(in_position as GLuint);
+            gl::VertexAttribPointer(in_position as GLuint, 2, gl::FLOAT,
+                                    gl::FALSE as GLboolean, 0, ptr::null());
+
+            Ok(BackgroundRenderer {
+                mode_uniform: shader::uniform_location(program_id, "mode"),
+                gradient_start_uniform: shader::uniform_location(program_id, "gradient_start"),
+                gradient_end_uniform: shader::uniform_location(program_id, "gradient_end"),
+                start_color_uniform: shader::uniform_location(program_id, "start_color"),
+                end_color_uniform: shader::uniform_location(program_id, "end_color"),
+                image_uniform: shader::uniform_location(program_id, "image"),
+                srgb_uniform: shader::uniform_location(program_id, "srgb"),
+                opacity_uniform: shader::uniform_location(program_id, "opacity"),
+
+                shader_program: program,
+                vao_handle,
+                position_vbo,
+                texture: None,
+                hook: None,
+                time: 0f32,
+                frame_index: 0
+            })
+        }
+    }
+
+    /// Upload the image used by image backgrounds, replacing any previous one.
+    pub fn set_image(&mut self, image: &ImageData) {
+        self.texture = Some(Texture::new(image));
+    }
+
+    /// Compile a background shader's hook, replacing any previous one. Does nothing if the hook
+    /// is the one compiled already, so changing uniforms is cheap.
+    pub fn set_shader(&mut self, background: &BackgroundShader) -> Result<(), TrdlError> {
+        if self.hook.as_ref().is_some_and(|hook| hook.0 == background.source()) {
+            return Ok(());
+        }
+        let vertex_shader_code = shader::read_file("shaders/background_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/background_hook_fragment_shader.glsl")? + "\n" +
+                                   background.source();
+        let mut builder = shader::ShaderProgramBuilder::new();
+        builder.set_vertex_shader(&vertex_shader_code);
+        builder.set_fragment_shader(&fragment_shader_code);
+        let program = builder.build_shader_program()?;
+        self.hook = Some((background.source().to_string(), program));
+        Ok(())
+    }
+
+    /// Set the animation time in seconds and the frame index background shaders see next.
+    pub fn set_time(&mut self, time: f32, frame_index: u64) {
+        self.time = time;
+        self.frame_index = frame_index;
+    }
+
+    /// Draw the background behind everything, without touching the depth buffer. Leaves the
+    /// background program bound.
+    pub fn draw(&self, background: &Background, srgb: bool) {
+        if background.needs_quad() {
+            self.draw_quad(background, 1f32, srgb);
+        }
+    }
+
+    /// Blend the background over what is there with an opacity, solid backgrounds included,
+    /// without touching the depth buffer. Leaves the background program bound and blending on.
+    pub fn fade(&self, background: &Background, opacity: f32, srgb: bool) {
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+        self.draw_quad(background, opacity, srgb);
+    }
+
+    fn draw_quad(&self, background: &Background, opacity: f32, srgb: bool) {
+        if let Background::Shader(ref shader) = *background {
+            if !self.use_hook(shader, opacity, srgb) {
+                return;
+            }
+            self.draw_vertices();
+            return;
+        }
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::Uniform1i(self.srgb_uniform, srgb as GLint);
+            gl::Uniform1f(self.opacity_uniform, opacity);
+            match *background {
+                Background::LinearGradient { start, end, start_color, end_color } => {
+                    gl::Uniform1i(self.mode_uniform, 1);
+                    gl::Uniform2f(self.gradient_start_uniform, start.0, start.1);
+                    gl::Uniform2f(self.gradient_end_uniform, end.0, end.1);
+                    gl::Uniform4fv(self.start_color_uniform, 1, start_color.to_array().as_ptr());
+                    gl::Uniform4fv(self.end_color_uniform, 1, end_color.to_array().as_ptr());
+                }
+                Background::Image(_) => {
+                    match self.texture {
+                        Some(ref texture) => texture.bind(0),
+                        None => return
+                    }
+                    gl::Uniform1i(self.mode_uniform, 2);
+                    gl::Uniform1i(self.image_uniform, 0);
+                }
+                Background::Shader(_) => (),
+                Background::Solid(_) | Background::Transparent => {
+                    // a gradient from the clear color to itself
+                    let color = background.clear_color().to_array();
+                    gl::Uniform1i(self.mode_uniform, 1);
+                    gl::Uniform4fv(self.start_color_uniform, 1, color.as_ptr());
+                    gl::Uniform4fv(self.end_color_uniform, 1, color.as_ptr());
+                }
+            }
+        }
+        self.draw_vertices();
+    }
+
+    // Use the program of a background shader's hook and set its uniforms. False if the hook
+    // was not compiled with set_shader.
+    fn use_hook(&self, background: &BackgroundShader, opacity: f32, srgb: bool) -> bool {
+        let program = match self.hook {
+            Some((ref source, ref program)) if source == background.source() => program.get_program_id(),
+            _ => return false
+        };
+        unsafe {
+            gl::UseProgram(program);
+            let mut viewport = [0 as GLint; 4];
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            gl::Uniform2f(shader::uniform_location(program, "resolution"), viewport[2] as GLfloat, viewport[3] as GLfloat);
+            gl::Uniform1f(shader::uniform_location(program, "time"), self.time);
+            gl::Uniform1i(shader::uniform_location(program, "frame_index"), self.frame_index as GLint);
+            gl::Uniform1i(shader::uniform_location(program, "srgb"), srgb as GLint);
+            gl::Uniform1f(shader::uniform_location(program, "opacity"), opacity);
+            for &(ref name, value) in &background.uniforms {
+                let location = shader::uniform_location(program, name);
+                if location >= 0 {
+                    value.apply(location);
+                }
+            }
+        }
+        true
+    }
+
+    // Draw the quad with the program in use, without testing or writing depth, so everything
+    // drawn after it shows in front.
+    fn draw_vertices(&self) {
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            gl::BindVertexArray(self.vao_handle);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl::DepthMask(gl::TRUE);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+impl Drop for BackgroundRenderer {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.position_vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Background, BackgroundShader, UniformValue};
+    use super::super::super::color::Color;
+
+    #[test]
+    fn test_background_shader() {
+        let mut shader = BackgroundShader::new("vec4 background(vec2 uv) { return vec4(uv, tint, 1.0); }");
+        shader.set_uniform("tint", 0.5f32);
+        shader.set_uniform("horizon", Color::rgb(1f32, 0f32, 0f32));
+        shader.set_uniform("tint", 0.25f32);
+        shader.set_uniform("bad\0name", 1);
+        assert_eq!(shader.uniform("tint"), Some(UniformValue::Float(0.25f32)));
+        assert_eq!(shader.uniform("horizon"), Some(UniformValue::Vec4([1f32, 0f32, 0f32, 1f32])));
+        assert_eq!(shader.uniforms.len(), 2);
+        let background = Background::Shader(shader);
+        assert!(background.needs_quad());
+        assert_eq!(background.clear_color(), Color::rgb(0f32, 0f32, 0f32));
+    }
+}
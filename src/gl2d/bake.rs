@@ -0,0 +1,209 @@
+//! Baked artwork: a group of paths drawn once into a texture and from then on drawn as a single
+//! quad, for complex static artwork that is expensive to tessellate every frame.
+
+use std::mem;
+use std::ffi::CString;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::drawing::Path;
+use super::layer::LayerCache;
+use super::super::camera::Camera;
+use super::super::image::ImageData;
+use super::super::transform::Transform2D;
+use super::super::TrdlError;
+
+// floats per quad corner: position x, y, then texture coordinates
+const CORNER_FLOATS: usize = 4;
+// size of the checkerboard shown while a texture loads, and of its squares
+#[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+const PLACEHOLDER_SIZE: u32 = 64;
+#[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+const PLACEHOLDER_SQUARE: u32 = 8;
+
+// a rectangle in drawing coordinates, (min, max)
+type Area = ((f32, f32), (f32, f32));
+
+/// Identifies a baked group of paths in a drawing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureHandle {
+    // the namespace of the drawing that baked it
+    pub(crate) namespace: usize,
+    pub(crate) index: usize
+}
+
+// A baked group: the paths are kept so the texture can be baked again when it is lost with the
+// OpenGL context. Textures made from images keep the image instead.
+pub(crate) struct Baked {
+    pub paths: Vec<Path>,
+    pub picture: Option<ImageData>,
+    pub resolution: u32,
+    pub transform: Transform2D,
+    // the drawing area the texture covers
+    pub area: Area,
+    pub srgb: bool,
+    pub image: Option<LayerCache>
+}
+
+// The texture size, camera and covered area for baking paths with bounds (min, max) at a
+// resolution for their longer side, with margin texels around them for strokes and
+// anti-aliasing.
+pub(crate) fn bake_frame(bounds: Area, resolution: u32, margin: u32) -> ((GLsizei, GLsizei), Camera, Area) {
+    let (min, max) = bounds;
+    let longer = (max.0 - min.0).max(max.1 - min.1);
+    let zoom = if longer > 0f32 { resolution as f32 / longer } else { 1f32 };
+    let size = (((max.0 - min.0) * zoom).ceil() as GLsizei + 2 * margin as GLsizei,
+                ((max.1 - min.1) * zoom).ceil() as GLsizei + 2 * margin as GLsizei);
+    let offset = margin as f32 / zoom;
+    let area = ((min.0 - offset, min.1 - offset),
+                (min.0 - offset + size.0 as f32 / zoom, min.1 - offset + size.1 as f32 / zoom));
+    let camera = Camera { zoom, pan: (-area.0 .0 * zoom, -area.0 .1 * zoom) };
+    (size, camera, area)
+}
+
+// The gray checkerboard shown in place of a texture that is still loading.
+#[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+pub(crate) fn placeholder() -> ImageData {
+    let pixels = (0..PLACEHOLDER_SIZE * PLACEHOLDER_SIZE).flat_map(|i| {
+        let (x, y) = (i % PLACEHOLDER_SIZE / PLACEHOLDER_SQUARE, i / PLACEHOLDER_SIZE / PLACEHOLDER_SQUARE);
+        let value = if (x + y) % 2 == 0 { 204u8 } else { 153u8 };
+        [value, value, value, 255u8]
+    }).collect();
+    ImageData::new(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, pixels).expect("the placeholder is square")
+}
+
+// Draws baked textures as quads with the drawing's camera.
+pub(crate) struct BakedRenderer {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    corner_vbo: GLuint,
+    projection_uniform: GLint,
+    image_uniform: GLint
+}
+
+impl BakedRenderer {
+    // Constructor, compiles the baked quad shaders.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/baked_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/baked_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        unsafe {
+            let attribute = |name: &str| {
+                let c_str = CString::new(name).unwrap();
+                gl::GetAttribLocation(program_id, c_str.as_ptr()) as GLuint
+            };
+            let in_position = attribute("in_position");
+            let in_tex_coord = attribute("in_tex_coord");
+
+            let mut vao_handle = 0 as GLuint;
+            gl::GenVertexArrays(1, &mut vao_handle);
+            gl::BindVertexArray(vao_handle);
+
+            let mut corner_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut corner_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, corner_vbo);
+            let stride = (CORNER_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+            gl::EnableVertexAttribArray(in_position);
+            gl::VertexAttribPointer(in_position, 2, gl::FLOAT, gl::FALSE as GLboolean, stride, ptr::null());
+            gl::EnableVertexAttribArray(in_tex_coord);
+            gl::VertexAttribPointer(in_tex_coord, 2, gl::FLOAT, gl::FALSE as GLboolean, stride,
+                                    (2 * mem::size_of::<GLfloat>()) as *const c_void);
+
+            Ok(BakedRenderer {
+                projection_uniform: shader::uniform_location(program_id, "projection"),
+                image_uniform: shader::uniform_location(program_id, "image"),
+                shader_program: program,
+                vao_handle,
+                corner_vbo
+            })
+        }
+    }
+
+    // Blend a baked texture over the framebuffer, without touching the depth buffer so paths
+    // drawn afterwards go on top. Leaves blending off.
+    pub(crate) fn draw(&self, baked: &Baked, projection: &[GLfloat; 16]) {
+        let image = match baked.image {
+            Some(ref image) => image,
+            None => return
+        };
+        let ((x0, y0), (x1, y1)) = baked.area;
+        let mut corners: Vec<GLfloat> = Vec::with_capacity(4 * CORNER_FLOATS);
+        for &(x, y, u, v) in &[(x0, y0, 0f32, 0f32), (x1, y0, 1f32, 0f32), (x0, y1, 0f32, 1f32), (x1, y1, 1f32, 1f32)] {
+            let (x, y) = baked.transform.apply((x, y));
+            corners.extend_from_slice(&[x, y, u, v]);
+        }
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean, projection.as_ptr());
+            gl::ActiveTexture(gl::TEXTURE0);
+            image.bind_texture();
+            gl::Uniform1i(self.image_uniform, 0);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            gl::Enable(gl::BLEND);
+            // baked textures hold premultiplied colors, like layer caches
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+            gl::BindVertexArray(self.vao_handle);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.corner_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (corners.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           corners.as_ptr() as *const c_void,
+                           gl::STREAM_DRAW);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl::Disable(gl::BLEND);
+            gl::DepthMask(gl::TRUE);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+impl Drop for BakedRenderer {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.corner_vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bake_frame;
+
+    #[test]
+    fn test_bake_frame() {
+        // 200 by 100 units baked at 400 texels, with 2 texels around
+        let (size, camera, area) = bake_frame(((10f32, 20f32), (210f32, 120f32)), 400, 2);
+        assert_eq!(size, (404, 204));
+        assert_eq!(camera.zoom, 2f32);
+        assert_eq!(area, ((9f32, 19f32), (211f32, 121f32)));
+        // the covered area fills the texture exactly
+        assert_eq!(camera.to_window(area.0), (0f32, 0f32));
+        assert_eq!(camera.to_window(area.1), (404f32, 204f32));
+    }
+
+    #[test]
+    #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+    fn test_placeholder() {
+        let image = super::placeholder();
+        assert_eq!((image.width(), image.height()), (64, 64));
+        // squares of 8 pixels alternate along rows and columns
+        let pixel = |x: usize, y: usize| &image.pixels()[4 * (y * 64 + x)..4 * (y * 64 + x) + 4];
+        assert_eq!(pixel(0, 0), pixel(7, 7));
+        assert_ne!(pixel(7, 0), pixel(8, 0));
+        assert_ne!(pixel(0, 7), pixel(0, 8));
+        assert_eq!(pixel(8, 8), pixel(0, 0));
+        assert!(image.pixels().chunks(4).all(|pixel| pixel[3] == 255));
+    }
+}
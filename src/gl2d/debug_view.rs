@@ -0,0 +1,214 @@
+//! Debug views of how paths were cut up for the GPU: the outlines of their patches, the
+//! diagonals the triangulation cut fills along, and how finely each patch is tessellated, and
+//! the bounding boxes of paths labeled with their ids. Drawn as extra paths above the drawing,
+//! like selection highlights.
+
+use std::collections::HashMap;
+use super::drawing::Path;
+
+// colors of the patch outlines and of the diagonals
+const WIREFRAME_COLOR: [f32; 3] = [0f32, 0.6f32, 0.2f32];
+const DIAGONAL_COLOR: [f32; 3] = [0.9f32, 0f32, 0.6f32];
+// tessellation levels are shown from this color at level 1 to the other at the most
+const COARSE_COLOR: [f32; 3] = [0.2f32, 0.4f32, 1f32];
+const FINE_COLOR: [f32; 3] = [1f32, 0.2f32, 0f32];
+const TESS_LEVEL_OPACITY: f32 = 0.5f32;
+// color of bounding boxes and their labels
+const BOUNDS_COLOR: [f32; 3] = [1f32, 0.5f32, 0f32];
+// size of a label digit and the room after it, in window pixels
+const DIGIT_WIDTH: f32 = 4f32;
+const DIGIT_HEIGHT: f32 = 8f32;
+const DIGIT_SPACING: f32 = 2f32;
+// the ends of the seven segments of a digit, in digit widths and heights from its lower left
+// corner: top, top right, bottom right, bottom, bottom left, top left and middle
+const SEGMENTS: [((f32, f32), (f32, f32)); 7] = [((0f32, 1f32), (1f32, 1f32)), ((1f32, 1f32), (1f32, 0.5f32)),
+                                               ((1f32, 0.5f32), (1f32, 0f32)), ((0f32, 0f32), (1f32, 0f32)),
+                                               ((0f32, 0f32), (0f32, 0.5f32)), ((0f32, 0.5f32), (0f32, 1f32)),
+                                               ((0f32, 0.5f32), (1f32, 0.5f32))];
+// the segments lit for each digit, one bit per segment in the order above
+const DIGIT_SEGMENTS: [u8; 10] = [0b0111111, 0b0000110, 0b1011011, 0b1001111, 0b1100110, 0b1101101, 0b1111101,
+                                  0b0000111, 0b1111111, 0b1101111];
+
+/// What `Drawing::set_debug_view` draws over the paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugView {
+    /// Nothing, the default.
+    #[default]
+    Off,
+    /// The outline of every patch the paths were cut into, curved edges included, so the
+    /// triangles of fills and the pieces of strokes can be seen.
+    Wireframe,
+    /// The diagonals the triangulation cut each fill along, the patch edges inside the path.
+    Diagonals,
+    /// Each patch tinted by how many pieces its most finely tessellated edge is cut into, blue
+    /// for one up to red for the most the drawing allows.
+    TessLevels
+}
+
+// A patch of a path's geometry: its corners, the control points of the edge from each corner to
+// the next, and whether it is the join at a corner of a stroke.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Patch {
+    pub corners: [(f32, f32); 3],
+    pub controls: [((f32, f32), (f32, f32)); 3],
+    pub is_join: bool
+}
+
+// How finely the shaders tessellate patches, see the tessellation control shader.
+pub(crate) struct Tessellation<'t> {
+    // drawing coordinates to window pixels
+    pub to_window: &'t dyn Fn((f32, f32)) -> (f32, f32),
+    pub lod_tolerance: Option<f32>,
+    pub max_level: u32
+}
+
+impl<'t> Tessellation<'t> {
+    // The level of the patch's edge from corner i to the next: enough pieces to stay within the
+    // tolerance of the curve by Wang's formula, capped at the most allowed.
+    fn edge_level(&self, patch: &Patch, i: usize) -> u32 {
+        let tolerance = match self.lod_tolerance {
+            Some(tolerance) if tolerance > 0f32 => tolerance,
+            _ => return self.max_level
+        };
+        let p0 = (self.to_window)(patch.corners[i]);
+        let p1 = (self.to_window)(patch.controls[i].0);
+        let p2 = (self.to_window)(patch.controls[i].1);
+        let p3 = (self.to_window)(patch.corners[(i + 1) % 3]);
+        let second = |a: (f32, f32), b: (f32, f32), c: (f32, f32)| (a.0 - 2f32 * b.0 + c.0).hypot(a.1 - 2f32 * b.1 + c.1);
+        let m = second(p0, p1, p2).max(second(p1, p2, p3));
+        ((0.75f32 * m / tolerance).sqrt().ceil() as u32).clamp(1, self.max_level)
+    }
+
+    // The level of the patch's most finely tessellated edge, joins are drawn whole.
+    fn patch_level(&self, patch: &Patch) -> u32 {
+        if patch.is_join {
+            return 1;
+        }
+        (0..3).map(|i| self.edge_level(patch, i)).max().unwrap_or(1)
+    }
+}
+
+// A pixel thick line in a color.
+fn stroked(path: Path, color: [f32; 3]) -> Path {
+    path.clear_fill_color().set_stroke(color[0], color[1], color[2], 1)
+}
+
+// The paths outlining a path's bounds, with its id written in seven segment digits just above
+// the top left corner of the box as seen in the window.
+pub(crate) fn bounds_paths(id: u64, bounds: ((f32, f32), (f32, f32)), to_window: &dyn Fn((f32, f32)) -> (f32, f32),
+                           to_drawing: &dyn Fn((f32, f32)) -> (f32, f32)) -> Vec<Path> {
+    let (min, max) = bounds;
+    let corners = [min, (max.0, min.1), max, (min.0, max.1)];
+    let outline = corners.iter().skip(1).fold(Path::new(min), |path, &corner| path.line_to(corner)).line_to(min);
+    let mut paths = vec![stroked(outline, BOUNDS_COLOR)];
+    let window: Vec<(f32, f32)> = corners.iter().map(|&corner| to_window(corner)).collect();
+    let left = window.iter().fold(f32::INFINITY, |left, p| left.min(p.0));
+    let top = window.iter().fold(f32::NEG_INFINITY, |top, p| top.max(p.1)) + DIGIT_SPACING;
+    for (k, digit) in id.to_string().bytes().map(|digit| (digit - b'0') as usize).enumerate() {
+        let x = left + k as f32 * (DIGIT_WIDTH + DIGIT_SPACING);
+        let point = |p: (f32, f32)| to_drawing((x + p.0 * DIGIT_WIDTH, top + p.1 * DIGIT_HEIGHT));
+        for (i, &(a, b)) in SEGMENTS.iter().enumerate() {
+            if DIGIT_SEGMENTS[digit] & (1 << i) != 0 {
+                paths.push(stroked(Path::new(point(a)).line_to(point(b)), BOUNDS_COLOR));
+            }
+        }
+    }
+    paths
+}
+
+// The paths showing a debug view of one path's patches.
+pub(crate) fn debug_paths(view: DebugView, patches: &[Patch], tessellation: &Tessellation) -> Vec<Path> {
+    match view {
+        DebugView::Off => Vec::new(),
+        DebugView::Wireframe => patches.iter().filter(|patch| !patch.is_join).map(|patch| {
+            let path = (0..3).fold(Path::new(patch.corners[0]), |path, i| {
+                let (cp1, cp2) = patch.controls[i];
+                path.curve_to(cp1, cp2, patch.corners[(i + 1) % 3])
+            });
+            stroked(path, WIREFRAME_COLOR)
+        }).collect(),
+        DebugView::Diagonals => {
+            // edges two patches share are inside the path, the outline's edges have one patch
+            let mut edges: HashMap<[u32; 4], usize> = HashMap::new();
+            for patch in patches.iter().filter(|patch| !patch.is_join) {
+                for i in 0..3 {
+                    let (a, b) = (patch.corners[i], patch.corners[(i + 1) % 3]);
+                    let (a, b) = ([a.0.to_bits(), a.1.to_bits()], [b.0.to_bits(), b.1.to_bits()]);
+                    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+                    *edges.entry([a[0], a[1], b[0], b[1]]).or_insert(0) += 1;
+                }
+            }
+            let mut diagonals: Vec<[u32; 4]> = edges.into_iter().filter(|&(_, count)| count > 1).map(|(edge, _)| edge)
+                .collect();
+            diagonals.sort();
+            diagonals.into_iter().map(|edge| {
+                let [x0, y0, x1, y1] = edge.map(f32::from_bits);
+                stroked(Path::new((x0, y0)).line_to((x1, y1)), DIAGONAL_COLOR)
+            }).collect()
+        }
+        DebugView::TessLevels => patches.iter().map(|patch| {
+            let level = tessellation.patch_level(patch);
+            let t = if tessellation.max_level > 1 {
+                (level - 1) as f32 / (tessellation.max_level - 1) as f32
+            } else {
+                0f32
+            };
+            let mix = |i: usize| COARSE_COLOR[i] + (FINE_COLOR[i] - COARSE_COLOR[i]) * t;
+            let [a, b, c] = patch.corners;
+            let corners = if (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0) < 0f32 { [a, c, b] } else { [a, b, c] };
+            Path::new(corners[0]).line_to(corners[1]).line_to(corners[2]).close_path()
+                .set_fill_color(mix(0), mix(1), mix(2)).set_opacity(TESS_LEVEL_OPACITY)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bounds_paths, debug_paths, DebugView, Patch, Tessellation};
+
+    // A patch with straight edges, the control points a third of the way along them.
+    fn straight(corners: [(f32, f32); 3]) -> Patch {
+        let third = |a: (f32, f32), b: (f32, f32), t: f32| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+        let control = |i: usize| {
+            let (a, b) = (corners[i], corners[(i + 1) % 3]);
+            (third(a, b, 1f32 / 3f32), third(a, b, 2f32 / 3f32))
+        };
+        Patch { corners, controls: [control(0), control(1), control(2)], is_join: false }
+    }
+
+    #[test]
+    fn test_debug_paths() {
+        // a square cut along its diagonal, 10 pixels a unit
+        let patches = [straight([(0f32, 0f32), (1f32, 0f32), (1f32, 1f32)]),
+                       straight([(0f32, 0f32), (1f32, 1f32), (0f32, 1f32)])];
+        let to_window = |p: (f32, f32)| (p.0 * 10f32, p.1 * 10f32);
+        let tessellation = Tessellation { to_window: &to_window, lod_tolerance: Some(0.25f32), max_level: 32 };
+        assert!(debug_paths(DebugView::Off, &patches, &tessellation).is_empty());
+        assert_eq!(debug_paths(DebugView::Wireframe, &patches, &tessellation).len(), 2);
+        let diagonals = debug_paths(DebugView::Diagonals, &patches, &tessellation);
+        assert_eq!(diagonals.len(), 1);
+        assert_eq!(diagonals[0].vertices(), &[(0f32, 0f32), (1f32, 1f32)][..]);
+
+        // straight edges need one piece, a bulging one more
+        assert_eq!(tessellation.patch_level(&patches[0]), 1);
+        let mut curved = patches[0];
+        curved.controls[0] = ((0.3f32, -1f32), (0.7f32, -1f32));
+        assert!(tessellation.patch_level(&curved) > 1);
+        let levels = debug_paths(DebugView::TessLevels, &[patches[0], curved], &tessellation);
+        assert_eq!(levels[0].fill(), Some([0.2f32, 0.4f32, 1f32]));
+        assert_ne!(levels[1].fill(), levels[0].fill());
+        let fixed = Tessellation { lod_tolerance: None, ..tessellation };
+        assert_eq!(fixed.patch_level(&patches[0]), 32);
+    }
+
+    #[test]
+    fn test_bounds_paths() {
+        let (to_window, to_drawing) = (|p: (f32, f32)| p, |p: (f32, f32)| p);
+        // the box, then 2 segments for the 1 and 6 for the 0
+        let paths = bounds_paths(10, ((0f32, 0f32), (20f32, 10f32)), &to_window, &to_drawing);
+        assert_eq!(paths.len(), 1 + 2 + 6);
+        assert_eq!(paths[0].vertices().len(), 5);
+        // the 1 is on the right of its cell, just above the box
+        assert_eq!(paths[1].vertices(), &[(4f32, 20f32), (4f32, 16f32)][..]);
+    }
+}
@@ -1,16 +1,48 @@
 extern crate gl;
 
+use std::any::Any;
 use std::mem;
 use std::ffi::CString;
-use std::ptr;
-use std::io::prelude::*;
-use std::fs::File;
 use std::collections::hash_map::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::os::raw::c_void;
 use std::f32;
+use std::time::Instant;
+#[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+#[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+use std::thread;
 use gl::types::*;
 use super::shader;
-use super::super::triangulation::triangulate;
+use super::background::{Background, BackgroundRenderer, ClearMode, UniformValue};
+use super::bake::{self, Baked, BakedRenderer, TextureHandle};
+use super::gl_state::{DepthCopy, SavedGlState};
+use super::gl_api::{Gl, GlApi};
+#[cfg(feature = "software")]
+use super::software::{RasterView, SoftwareRasterizer};
+use super::selection::{self, SelectionStyle};
+use super::overlay::{self, Overlay, OverlayView};
+use super::debug_view::{self, DebugView, Patch, Tessellation};
+use super::layer::{CacheKey, Layer, LayerCache, LayerCompositor, LayerId};
+use super::pick::{self, PickKey, PickPass};
+use super::tiles::{TileIndex, TileRange};
+use super::rtree::RTree;
+use super::stroke_style::{StrokePiece, StrokeStyle};
+use super::fill_pattern::{self, FillPattern, PatternBuffer};
+use super::gradient::{self, Fill, Gradient, GradientId};
+use super::resource::{self, Resource};
+use super::super::angle::Angle;
+use super::super::color::{self, Color, ColorSpace};
+use super::super::theme::{Theme, ThemeVariant};
+use super::super::image::ImageData;
+use super::super::camera::{Camera, CoordinateSystem, LogicalSize, Origin, Projection, ScaleMode, YAxis};
+use super::super::transform::Transform2D;
+use super::super::input::{InputEvent, MouseButton, PathEvent};
+use super::super::bezier;
+use super::super::nurbs;
+use super::super::measure::{ArcLengthTable, PathPoint};
+use super::super::mesh::Mesh;
+use super::super::triangulation::{self, insert_points, triangulate, triangulate_exact};
 use super::super::TrdlError;
 
 macro_rules! gl {
@@ -22,38 +54,153 @@ const ONE:   GLfloat = gl!(1);
 const TWO:   GLfloat = gl!(2);
 const THREE: GLfloat = gl!(3);
 
-const MAX_DEPTH : f32 = 5e5f32;
-const TOL: f32 = 1e-5f32;
+// Each path gets two depth levels, its fill and its stroke just above it. This keeps a level at
+// least a few steps of a 24 bit depth buffer apart.
+const MAX_PATHS: usize = 1 << 21;
+// angles closer than this, in radians, are the same
+const ANGLE_TOL: f32 = 1e-5f32;
+// lengths this small compared to the coordinates around them are taken as zero, see
+// length_tolerance
+const RELATIVE_TOL: f32 = 1e-6f32;
+// freehand samples closer than this to the previous one are dropped
+const INK_MIN_SPACING: f32 = 0.5f32;
+// zoom factor for one line of scroll wheel movement
+const ZOOM_STEP: f32 = 1.1f32;
+// curves are split into at most this many pieces by default
+const DEFAULT_MAX_TESSELLATION: u32 = 32;
+// the OpenGL minimum for the largest tessellation level
+const MAX_TESSELLATION: u32 = 64;
+// curves are tessellated to stay within this many pixels of the true curve by default
+const DEFAULT_LOD_TOLERANCE: f32 = 0.25f32;
+// the vertex arrays are shrunk when they have this many floats more than twice what they hold
+const SHRINK_SLACK: usize = 1 << 16;
+// smallest tolerance `Path::flatten` works to, finer ones are rounded up to it
+const MIN_FLATTEN_TOLERANCE: f32 = 1e-4f32;
+// the bit of a vertex's fill flags that turns the fill on, the stroke alignment is in the bits
+// above it
+pub(crate) const FILL_FLAG: GLint = 1;
+// fill flags of a patch that draws the join at a corner of the stroke rather than a part of the
+// path, whether the path's inside is to the left of the corner, and whether to round miters
+// beyond the limit; the kind of join is in bits 5 and 6
+pub(crate) const JOIN_FLAG: GLint = 8;
+pub(crate) const INSIDE_LEFT_FLAG: GLint = 16;
+pub(crate) const ROUND_SHARP_FLAG: GLint = 128;
+// fill flag of paths with a stroke an odd number of pixels thick, crisp mode puts their points on
+// pixel centers
+const ODD_STROKE_FLAG: GLint = 256;
+// fill flag of paths with stroke thicknesses in drawing units
+pub(crate) const WORLD_STROKE_FLAG: GLint = 512;
+// the texture unit the fill pattern records are bound to
+const PATTERN_UNIT: GLuint = 1;
+// the texture unit the gradients' slots are bound to
+const GRADIENT_UNIT: GLuint = 2;
+// the miter limit SVG uses by default
+const DEFAULT_MITER_LIMIT: f32 = 4f32;
+// corners where the direction turns by less than this (as 1 - cos of the angle) get no join
+const SMOOTH_CORNER: f32 = 1e-4f32;
+// fills split curves until their control points are at most this fraction of the chord away from
+// it, so the curved edges of the triangles stay clear of the other triangles
+const FILL_CURVE_FLATNESS: f32 = 0.2f32;
 
 /// Users of the library must provide a window with these functions, they are provided by winit,
 /// glutin, GLFW-rs
 pub trait Window {
     fn set_context(&self);
     fn load_fn(&self, addr: &str) -> *const c_void;
+    /// Show the drawn frame, used by `run_loop`. Does nothing unless implemented.
+    fn swap_buffers(&self) {}
 }
 
 /// All shapes in TRDL are paths, which are built by adding lines curves and arcs.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Path {
     vertices: Vec<(f32, f32)>,
     control_point_1s: Vec<Option<(f32, f32)>>,
     control_point_2s: Vec<Option<(f32, f32)>>,
     fill_color: Option<[f32; 3]>,
+    fill_pattern: Option<(FillPattern, Color)>,
+    // a gradient of the drawing filling the path in place of the fill color
+    fill_gradient: Option<GradientId>,
+    // fill colors at the vertices and at points inside the path, for mesh gradients
+    vertex_colors: Vec<Color>,
+    color_points: Vec<((f32, f32), Color)>,
     stroke: Option<([f32; 3], u32)>,
-    is_closed: bool
+    stroke_align: StrokeAlign,
+    stroke_units: StrokeUnits,
+    line_join: Option<LineJoin>,
+    miter_limit: f32,
+    round_sharp_corners: bool,
+    stroke_style: StrokeStyle,
+    gradient_space: ColorSpace,
+    opacity: f32,
+    // multiply the opacity for the fill and the stroke alone, as SVG's fill-opacity and
+    // stroke-opacity
+    fill_opacity: f32,
+    stroke_opacity: f32,
+    is_closed: bool,
+    // the first call that went wrong while building the path and why, see build
+    problem: Option<(&'static str, &'static str)>
+}
+
+// The length below which distances between the points are lost in rounding, a fraction of their
+// largest coordinate. Fixed tolerances are too coarse for shapes in normalized coordinates and
+// too fine for ones thousands of pixels from the origin.
+fn length_tolerance<I: IntoIterator<Item = (f32, f32)>>(points: I) -> f32 {
+    points.into_iter().fold(0f32, |largest, p| largest.max(p.0.abs()).max(p.1.abs())) * RELATIVE_TOL
+}
+
+// The center, radii, start angle and sweep angle of an elliptical arc.
+type ArcParams = ((f32, f32), (f32, f32), f32, f32);
+
+// Whether a point is fit to build a path with.
+fn is_finite_point(p: (f32, f32)) -> bool {
+    p.0.is_finite() && p.1.is_finite()
 }
 
 impl Path {
     /// Constructor, takes the first point in the path as input.
     pub fn new(start: (f32, f32)) -> Self {
         let mut path = Path { vertices: Vec::new(), control_point_1s: Vec::new(),
-            control_point_2s: Vec::new(), fill_color: None, stroke: None, is_closed: false };
+            control_point_2s: Vec::new(), fill_color: None, fill_pattern: None, fill_gradient: None,
+            vertex_colors: Vec::new(),
+            color_points: Vec::new(), stroke: None,
+            stroke_align: StrokeAlign::Center, stroke_units: StrokeUnits::Pixels, line_join: None,
+            miter_limit: DEFAULT_MITER_LIMIT, round_sharp_corners: false, stroke_style: StrokeStyle::Solid,
+            gradient_space: ColorSpace::Srgb, opacity: 1f32, fill_opacity: 1f32,
+            stroke_opacity: 1f32, is_closed: false, problem: None };
         path.vertices.push(start);
+        if !is_finite_point(start) {
+            path = path.fail("new", "the start point is not finite");
+        }
         path
     }
 
+    // Remember what went wrong, unless something already did.
+    fn fail(mut self, call: &'static str, reason: &'static str) -> Self {
+        self.problem = self.problem.or(Some((call, reason)));
+        self
+    }
+
+    /// Finish building the path, checking that every call made sense. The builder methods can't
+    /// fail, so they note the first call that went wrong, such as a point that is NaN or a line
+    /// added after `close_path`, and this returns it as `InvalidPath`. Adding an invalid path to
+    /// a drawing fails the same way.
+    pub fn build(self) -> Result<Self, TrdlError> {
+        match self.problem {
+            Some((call, reason)) => Err(TrdlError::InvalidPath(call, reason)),
+            None => Ok(self)
+        }
+    }
+
     /// Add a straight line segment from the current point to end_point, which becomes the current
     /// point.
     pub fn line_to(mut self, end_point: (f32, f32)) -> Self {
+        if self.is_closed {
+            self = self.fail("line_to", "the path is already closed");
+        }
+        if !is_finite_point(end_point) {
+            self = self.fail("line_to", "the end point is not finite");
+        }
         self.control_point_1s.push(None);
         self.control_point_2s.push(None);
         self.vertices.push(end_point);
@@ -65,6 +212,12 @@ impl Path {
     /// can be considered control_point_0, and the end_point control_point_3.
     pub fn curve_to(mut self, control_point_1: (f32, f32), control_point_2: (f32, f32),
                     end_point: (f32, f32),) -> Self {
+        if self.is_closed {
+            self = self.fail("curve_to", "the path is already closed");
+        }
+        if !is_finite_point(control_point_1) || !is_finite_point(control_point_2) || !is_finite_point(end_point) {
+            self = self.fail("curve_to", "a point is not finite");
+        }
         self.control_point_1s.push(Some(control_point_1));
         self.control_point_2s.push(Some(control_point_2));
         self.vertices.push(end_point);
@@ -75,59 +228,143 @@ impl Path {
     /// point. The arc is defined by x_radius and y_radius, angle, which describe the whole ellipse
     /// of which the arc is a part. It is also described by is_positive_sweep which determine if the
     /// arc curves clockwise or counter clockwise and is_large_arc which determines if the arc takes
-    /// the short path or long path to the end point.
+    /// the short path or long path to the end point. Unlike in SVG, an end point on the current
+    /// point makes a whole ellipse, see `ellipse_from_current`.
     /// See https://www.w3.org/TR/SVG/implnote.html#ArcImplementationNotes
-    pub fn arc_to(mut self, x_radius: f32, y_radius: f32, angle: f32, end_point: (f32, f32),
+    pub fn arc_to(mut self, x_radius: f32, y_radius: f32, angle: impl Into<Angle>, end_point: (f32, f32),
               is_large_arc: bool, is_positive_sweep: bool) -> Self {
-        if let Ok((center, start_angle, sweep_angle)) =
+        let angle = angle.into().to_radians();
+        if self.is_closed {
+            self = self.fail("arc_to", "the path is already closed");
+        }
+        if !(x_radius.is_finite() && y_radius.is_finite() && angle.is_finite() && is_finite_point(end_point)) {
+            self = self.fail("arc_to", "a radius, the angle or the end point is not finite");
+        }
+        let start = self.vertices[self.vertices.len() - 1];
+        let tolerance = length_tolerance([start, end_point]);
+        if (end_point.0 - start.0).hypot(end_point.1 - start.1) <= tolerance &&
+                x_radius.abs() > tolerance && y_radius.abs() > tolerance {
+            return self.ellipse_from_current(x_radius.abs(), y_radius.abs(), angle, is_positive_sweep);
+        }
+        if let Ok((center, (x_radius, y_radius), start_angle, sweep_angle)) =
             self.get_ellipse_params(x_radius, y_radius, angle, end_point,
                                     is_large_arc, is_positive_sweep) {
-            // approximate a circular arc (radius = x_radius) with Bezier splines
-
-            // break it into quarter-circle arcs
-            let mut num_arcs = (sweep_angle.abs() / f32::consts::FRAC_PI_2).floor() as usize;
-            // and 1 less than 90 degree arc
-             let remainder = sweep_angle.abs() - f32::consts::FRAC_PI_2 * (num_arcs as f32);
-            let mut points = Vec::new();
-            if num_arcs > 0 {
-                points.append(&mut Self::quarter_circle(x_radius, num_arcs, sweep_angle >= TOL));
-            }
-            if remainder.abs() > TOL {
-                points.append(&mut Self::less_than_quarter_circle(x_radius, remainder, num_arcs,
-                                                                  sweep_angle >= TOL));
-                num_arcs += 1;
-            }
-            // now make the circular arc start at the right place
-            Self::rotate_points(&mut points, start_angle);
-            // now make it into an ellipse
-            let scale = y_radius / x_radius;
-            for p in &mut points {
-                *p = (p.0, p.1 * scale);
-            }
-            // rotate the ellipse
-            Self::rotate_points(&mut points, angle);
-            // center it in the correct location
-            for p in &mut points {
-                let x = p.0;
-                let y = p.1;
-                *p = (x + center.0, y + center.1);
-            }
-            // add the curves
-            for i in 0..num_arcs {
-                let k = (i * 3) as usize;
-                self = self.curve_to(points[k], points[k + 1], points[k + 2]);
-            }
+            self = self.elliptical_arc(center, x_radius, y_radius, angle, start_angle, sweep_angle);
         } else {
             self = self.line_to(end_point);
         }
         self
     }
 
+    /// Add a whole ellipse that starts and ends at the current point, at the end of its x radius.
+    /// The ellipse is turned by angle and goes round counter clockwise if is_positive_sweep.
+    pub fn ellipse_from_current(mut self, x_radius: f32, y_radius: f32, angle: impl Into<Angle>,
+                                is_positive_sweep: bool) -> Self {
+        let angle = angle.into().to_radians();
+        if self.is_closed {
+            self = self.fail("ellipse_from_current", "the path is already closed");
+        }
+        if !(x_radius > 0f32 && y_radius > 0f32 && x_radius.is_finite() && y_radius.is_finite() && angle.is_finite()) {
+            return self.fail("ellipse_from_current", "a radius is not positive or not finite, or the angle not finite");
+        }
+        let start = self.vertices[self.vertices.len() - 1];
+        let center = (start.0 - x_radius * angle.cos(), start.1 - x_radius * angle.sin());
+        let sweep = if is_positive_sweep { 2f32 * f32::consts::PI } else { -2f32 * f32::consts::PI };
+        let mut path = self.elliptical_arc(center, x_radius, y_radius, angle, 0f32, sweep);
+        // end exactly where it started, so closing the path adds no sliver of a line
+        let last = path.vertices.len() - 1;
+        path.vertices[last] = start;
+        path
+    }
+
+    // Add the arc of the ellipse around center from start_angle, turning by sweep_angle. The
+    // angles are of the circle of radius x_radius that is squashed into the ellipse, before it is
+    // rotated by angle. The current point must be the start of the arc.
+    fn elliptical_arc(mut self, center: (f32, f32), x_radius: f32, y_radius: f32, angle: f32,
+                      start_angle: f32, sweep_angle: f32) -> Self {
+        // approximate a circular arc (radius = x_radius) with Bezier splines
+
+        // break it into quarter-circle arcs
+        let mut num_arcs = (sweep_angle.abs() / f32::consts::FRAC_PI_2).floor() as usize;
+        // and 1 less than 90 degree arc
+        let remainder = sweep_angle.abs() - f32::consts::FRAC_PI_2 * (num_arcs as f32);
+        let mut points = Vec::new();
+        if num_arcs > 0 {
+            points.append(&mut Self::quarter_circle(x_radius, num_arcs, sweep_angle >= ANGLE_TOL));
+        }
+        if remainder.abs() > ANGLE_TOL {
+            points.append(&mut Self::less_than_quarter_circle(x_radius, remainder, num_arcs,
+                                                              sweep_angle >= ANGLE_TOL));
+            num_arcs += 1;
+        }
+        // now make the circular arc start at the right place
+        Self::rotate_points(&mut points, start_angle);
+        // now make it into an ellipse
+        let scale = y_radius / x_radius;
+        for p in &mut points {
+            *p = (p.0, p.1 * scale);
+        }
+        // rotate the ellipse
+        Self::rotate_points(&mut points, angle);
+        // center it in the correct location
+        for p in &mut points {
+            let x = p.0;
+            let y = p.1;
+            *p = (x + center.0, y + center.1);
+        }
+        // add the curves
+        for i in 0..num_arcs {
+            let k = i * 3;
+            self = self.curve_to(points[k], points[k + 1], points[k + 2]);
+        }
+        self
+    }
+
+    /// Add a smooth curve from the current point through each of points in turn, the last of which
+    /// becomes the current point. The curve is a cardinal spline: tension 0 gives a Catmull-Rom
+    /// spline, higher tension pulls the curve tighter around the points until at 1 the points are
+    /// joined by straight lines.
+    pub fn spline_through(mut self, points: &[(f32, f32)], tension: f32) -> Self {
+        if !tension.is_finite() || !points.iter().all(|&p| is_finite_point(p)) {
+            self = self.fail("spline_through", "a point or the tension is not finite");
+        }
+        let mut knots = Vec::with_capacity(points.len() + 1);
+        knots.push(self.vertices[self.vertices.len() - 1]);
+        knots.extend_from_slice(points);
+        self.curves_through(&knots, tension)
+    }
+
+    /// Add a B-spline or NURBS curve, converted to cubic Bezier curves. Its degree is
+    /// knots.len() - control_points.len() - 1 and can be 1, 2 or 3; use weights of 1 for a plain
+    /// B-spline. Rational curves are approximated closely, non-rational ones exactly. If the curve
+    /// does not start at the current point (clamped knots start at the first control point) a
+    /// straight line joins them.
+    pub fn nurbs_to(self, control_points: &[(f32, f32)], knots: &[f32], weights: &[f32]) ->
+            Result<Self, TrdlError> {
+        let cubics = nurbs::to_cubics(control_points, knots, weights)?;
+        let mut path = self;
+        if path.vertices[path.vertices.len() - 1] != cubics[0][0] {
+            path = path.line_to(cubics[0][0]);
+        }
+        for c in cubics {
+            path = path.curve_to(c[1], c[2], c[3]);
+        }
+        Ok(path)
+    }
+
     /// Makes a polygon closed so it can be filled with color. If the last point is not the same as
     /// the first point, they are connected with a straight line.
     pub fn close_path(mut self) -> Self {
+        if self.is_closed {
+            return self.fail("close_path", "the path is already closed");
+        }
+        let straight = self.control_point_1s.iter().all(|cp| cp.is_none());
+        let first_repeated = self.vertices.len() > 1 && self.vertices[0] == self.vertices[self.vertices.len()-1];
+        if straight && self.vertices.len() - first_repeated as usize <= 2 {
+            self = self.fail("close_path", "a closed path of straight lines needs at least 3 points");
+        }
         self.is_closed = true;
-        if self.vertices[0] == self.vertices[self.vertices.len()-1] {
+        if first_repeated {
             self.vertices.pop();
         } else {
             self.control_point_1s.push(None);
@@ -136,15 +373,376 @@ impl Path {
         self
     }
 
-    /// Sets the fill color for closed shapes.
+    /// Round the corners where two straight segments meet with circular arcs of the given
+    /// radius. Call once all the points are added, and the path closed if it is to be. Corners
+    /// next to curves and the ends of open paths are left sharp, and the radius shrinks where the
+    /// edges are too short for it so that neighbouring corners don't overlap.
+    pub fn fillet_corners(self, radius: f32) -> Self {
+        self.cut_corners(radius, true)
+    }
+
+    /// Cut the corners where two straight segments meet with a straight line, starting `size`
+    /// along each edge from the corner. Like `fillet_corners` it leaves corners next to curves
+    /// alone and shortens the cut on short edges.
+    pub fn chamfer_corners(self, size: f32) -> Self {
+        self.cut_corners(size, false)
+    }
+
+    // Replace the corners between straight segments with an arc (fillet) of radius size, or with
+    // a line size along each edge.
+    fn cut_corners(self, size: f32, fillet: bool) -> Self {
+        let n = self.vertices.len();
+        if !(size > 0f32 && size.is_finite()) || n < 3 {
+            return self;
+        }
+        let segment_count = self.control_point_1s.len();
+        let tolerance = length_tolerance(self.vertices.iter().cloned());
+        let is_line = |i: usize| self.control_point_1s[i].is_none();
+        let mut path = Path { vertices: Vec::new(), control_point_1s: Vec::new(), control_point_2s: Vec::new(),
+                              vertex_colors: Vec::new(), ..self.clone() };
+        for i in 0..n {
+            let outgoing = if i < segment_count { Some(i) } else { None };
+            let incoming = if i > 0 { Some(i - 1) } else if self.is_closed { Some(segment_count - 1) } else { None };
+            let color = self.vertex_colors.get(i);
+            let push = |path: &mut Path, point: (f32, f32), controls: Option<((f32, f32), (f32, f32))>| {
+                // an edge used up by the corners on both its ends is dropped
+                if path.vertices.last() == Some(&point) && path.control_point_1s.last() == Some(&None) {
+                    path.vertices.pop();
+                    path.control_point_1s.pop();
+                    path.control_point_2s.pop();
+                    path.vertex_colors.truncate(path.vertices.len());
+                }
+                path.vertices.push(point);
+                path.control_point_1s.push(controls.map(|c| c.0));
+                path.control_point_2s.push(controls.map(|c| c.1));
+                if let Some(&color) = color {
+                    path.vertex_colors.push(color);
+                }
+            };
+            let original = |path: &mut Path| match outgoing {
+                Some(s) => push(path, self.vertices[i], self.control_point_1s[s].zip(self.control_point_2s[s])),
+                None => {
+                    path.vertices.push(self.vertices[i]);
+                    if let Some(&color) = color {
+                        path.vertex_colors.push(color);
+                    }
+                }
+            };
+            let (s_in, s_out) = match (incoming, outgoing) {
+                (Some(s_in), Some(s_out)) if is_line(s_in) && is_line(s_out) => (s_in, s_out),
+                _ => {
+                    original(&mut path);
+                    continue;
+                }
+            };
+            let v = self.vertices[i];
+            let previous = self.vertices[s_in];
+            let next = self.vertices[(s_out + 1) % n];
+            let (a, b) = ((previous.0 - v.0).hypot(previous.1 - v.1), (next.0 - v.0).hypot(next.1 - v.1));
+            if a <= tolerance || b <= tolerance {
+                original(&mut path);
+                continue;
+            }
+            // unit vectors from the corner along each edge and the angle between them
+            let u1 = ((previous.0 - v.0) / a, (previous.1 - v.1) / a);
+            let u2 = ((next.0 - v.0) / b, (next.1 - v.1) / b);
+            let angle = (u1.0 * u2.0 + u1.1 * u2.1).clamp(-1f32, 1f32).acos();
+            if angle >= f32::consts::PI - ANGLE_TOL || angle <= ANGLE_TOL {
+                // straight on or doubling back, there is no corner to cut
+                original(&mut path);
+                continue;
+            }
+            let half_tan = (angle / 2f32).tan();
+            let wanted = if fillet { size / half_tan } else { size };
+            let distance = wanted.min(a / 2f32).min(b / 2f32);
+            let start = (v.0 + u1.0 * distance, v.1 + u1.1 * distance);
+            let end = (v.0 + u2.0 * distance, v.1 + u2.1 * distance);
+            let controls = if fillet {
+                // a cubic close to the circular arc turning through the angle the edges turn by
+                let radius = distance * half_tan;
+                let handle = 4f32 / 3f32 * ((f32::consts::PI - angle) / 4f32).tan() * radius;
+                Some(((start.0 - u1.0 * handle, start.1 - u1.1 * handle),
+                      (end.0 - u2.0 * handle, end.1 - u2.1 * handle)))
+            } else {
+                None
+            };
+            push(&mut path, start, controls);
+            push(&mut path, end, None);
+        }
+        if path.is_closed && path.vertices.len() > 1 && path.vertices[0] == path.vertices[path.vertices.len() - 1] &&
+                path.control_point_1s.last() == Some(&None) {
+            path.vertices.pop();
+            path.control_point_1s.pop();
+            path.control_point_2s.pop();
+            path.vertex_colors.truncate(path.vertices.len());
+        }
+        path
+    }
+
+    /// Insert a segment before segment i, where segment i goes from vertex i to the next vertex.
+    /// The segment has to start at vertex i, its end becomes vertex i + 1 and the old segment i
+    /// goes on from there. On an open path i can be the number of segments, to add at the end.
+    pub fn insert_segment(mut self, i: usize, segment: bezier::Segment) -> Self {
+        let segment_count = self.control_point_1s.len();
+        if i > segment_count || (self.is_closed && i == segment_count) {
+            return self.fail("insert_segment", "there is no segment at that index");
+        }
+        let (start, controls, end) = match segment {
+            bezier::Segment::Line(start, end) => (start, None, end),
+            bezier::Segment::Cubic(start, cp1, cp2, end) => (start, Some((cp1, cp2)), end)
+        };
+        if !is_finite_point(end) || !controls.is_none_or(|(cp1, cp2)| is_finite_point(cp1) && is_finite_point(cp2)) {
+            return self.fail("insert_segment", "a point is not finite");
+        }
+        let v = self.vertices[i];
+        if (start.0 - v.0).hypot(start.1 - v.1) > length_tolerance([start, v].iter().cloned()) {
+            return self.fail("insert_segment", "the segment does not start at vertex i");
+        }
+        if let Some(color) = self.color_between(i, 0.5f32) {
+            self.vertex_colors.insert(i + 1, color);
+        }
+        self.vertices.insert(i + 1, end);
+        self.control_point_1s.insert(i, controls.map(|c| c.0));
+        self.control_point_2s.insert(i, controls.map(|c| c.1));
+        self
+    }
+
+    /// Remove segment i. Its end vertex goes with it, so the segment after it starts where
+    /// segment i started. An open path keeps at least one segment and a closed one two, or three
+    /// if they are all straight.
+    pub fn remove_segment(mut self, i: usize) -> Self {
+        let n = self.vertices.len();
+        let segment_count = self.control_point_1s.len();
+        if i >= segment_count {
+            return self.fail("remove_segment", "there is no segment at that index");
+        }
+        let straight = self.control_point_1s.iter().enumerate().all(|(j, cp)| j == i || cp.is_none());
+        if n < 3 || (self.is_closed && straight && n < 4) {
+            return self.fail("remove_segment", "the path would have too few segments left");
+        }
+        let end = (i + 1) % n;
+        self.vertices.remove(end);
+        if end < self.vertex_colors.len() {
+            self.vertex_colors.remove(end);
+        }
+        self.control_point_1s.remove(i);
+        self.control_point_2s.remove(i);
+        if end == 0 {
+            // the closing segment went, the one that started at the first vertex now closes the path
+            self.control_point_1s.rotate_left(1);
+            self.control_point_2s.rotate_left(1);
+        }
+        self
+    }
+
+    /// Move vertex i to point. The control points on either side of it move along, so curves
+    /// keep their shape near the vertex.
+    pub fn set_vertex(mut self, i: usize, point: (f32, f32)) -> Self {
+        let n = self.vertices.len();
+        if i >= n {
+            return self.fail("set_vertex", "there is no vertex at that index");
+        }
+        if !is_finite_point(point) {
+            return self.fail("set_vertex", "the point is not finite");
+        }
+        let offset = (point.0 - self.vertices[i].0, point.1 - self.vertices[i].1);
+        let moved = |cp: &mut Option<(f32, f32)>| if let Some(p) = cp.as_mut() {
+            *p = (p.0 + offset.0, p.1 + offset.1);
+        };
+        if i < self.control_point_1s.len() {
+            moved(&mut self.control_point_1s[i]);
+        }
+        if i > 0 {
+            moved(&mut self.control_point_2s[i - 1]);
+        } else if self.is_closed {
+            moved(&mut self.control_point_2s[n - 1]);
+        }
+        self.vertices[i] = point;
+        self
+    }
+
+    /// Cut the path in two at t, where t goes from 0 at the start to 1 at the end as in
+    /// `point_at`. Both pieces are open and styled like the path. A closed path is cut open at its
+    /// first vertex, so the second piece ends with the closing segment. At t = 0 or 1 one of the
+    /// pieces has no length.
+    pub fn split_at(&self, t: f32) -> (Path, Path) {
+        let segments = self.segment_list();
+        let n = self.vertices.len();
+        // the colors at the ends of the segments, the closing segment ends at the first vertex
+        let colors: Vec<Color> = (0..=segments.len()).map_while(|j| self.vertex_colors.get(j % n).cloned())
+            .collect();
+        if segments.is_empty() {
+            return (self.piece(self.vertices[0], &[], colors.clone()), self.piece(self.vertices[0], &[], colors));
+        }
+        let scaled = t.clamp(0f32, 1f32) * segments.len() as f32;
+        let i = (scaled.floor() as usize).min(segments.len() - 1);
+        let local = scaled - i as f32;
+        let (head, tail) = (segments[i].between(0f32, local), segments[i].between(local, 1f32));
+        let cut = head.point_at(1f32);
+        let mut before: Vec<bezier::Segment> = segments[..i].to_vec();
+        before.push(head);
+        let mut after = vec![tail];
+        after.extend_from_slice(&segments[i + 1..]);
+        let (before_colors, after_colors) = if colors.len() > i + 1 {
+            let color = color::gradient_at(&[(0f32, colors[i]), (1f32, colors[i + 1])], local);
+            let mut before_colors = colors[..=i].to_vec();
+            before_colors.push(color);
+            let mut after_colors = vec![color];
+            after_colors.extend_from_slice(&colors[i + 1..]);
+            (before_colors, after_colors)
+        } else {
+            (colors, Vec::new())
+        };
+        (self.piece(self.vertices[0], &before, before_colors), self.piece(cut, &after, after_colors))
+    }
+
+    // An open path through the segments from start, styled like this one.
+    fn piece(&self, start: (f32, f32), segments: &[bezier::Segment], vertex_colors: Vec<Color>) -> Path {
+        let mut path = Path { vertices: vec![start], control_point_1s: Vec::new(), control_point_2s: Vec::new(),
+                              vertex_colors, is_closed: false, ..self.clone() };
+        for &segment in segments {
+            let (controls, end) = match segment {
+                bezier::Segment::Line(_, end) => (None, end),
+                bezier::Segment::Cubic(_, cp1, cp2, end) => (Some((cp1, cp2)), end)
+            };
+            path.vertices.push(end);
+            path.control_point_1s.push(controls.map(|c| c.0));
+            path.control_point_2s.push(controls.map(|c| c.1));
+        }
+        path
+    }
+
+    // The vertex color at t along segment i, if both its ends have one.
+    fn color_between(&self, i: usize, t: f32) -> Option<Color> {
+        let next = if self.is_closed { (i + 1) % self.vertices.len() } else { i + 1 };
+        match (self.vertex_colors.get(i), self.vertex_colors.get(next)) {
+            (Some(&a), Some(&b)) => Some(color::gradient_at(&[(0f32, a), (1f32, b)], t)),
+            _ => None
+        }
+    }
+
+    /// The same path traced the other way, with the segments in reverse order and each one's
+    /// control points swapped. A closed path keeps its first vertex, and its winding flips, so a
+    /// fill needs it turned back. Vertex colors are kept if every vertex has one.
+    pub fn reversed(&self) -> Path {
+        let mut order: Vec<usize> = (0..self.vertices.len()).rev().collect();
+        if self.is_closed {
+            order.rotate_right(1);
+        }
+        let vertex_colors = if self.vertex_colors.len() == self.vertices.len() {
+            order.iter().map(|&i| self.vertex_colors[i]).collect()
+        } else {
+            Vec::new()
+        };
+        Path {
+            vertices: order.iter().map(|&i| self.vertices[i]).collect(),
+            control_point_1s: self.control_point_2s.iter().rev().cloned().collect(),
+            control_point_2s: self.control_point_1s.iter().rev().cloned().collect(),
+            vertex_colors,
+            ..self.clone()
+        }
+    }
+
+    /// Continue the path with the segments of other, which has to start where this one ends.
+    /// Both paths have to be open. The style stays this path's.
+    pub fn append(mut self, other: &Path) -> Self {
+        if self.is_closed || other.is_closed {
+            return self.fail("append", "both paths have to be open");
+        }
+        let (end, start) = (self.vertices[self.vertices.len() - 1], other.vertices[0]);
+        if (start.0 - end.0).hypot(start.1 - end.1) > length_tolerance([start, end].iter().cloned()) {
+            return self.fail("append", "the other path does not start where this one ends");
+        }
+        if let Some((call, reason)) = other.problem {
+            self = self.fail(call, reason);
+        }
+        // vertex colors carry on only if this path's reach its end
+        if self.vertex_colors.len() == self.vertices.len() {
+            self.vertex_colors.extend(other.vertex_colors.iter().skip(1));
+        }
+        self.vertices.extend(other.vertices.iter().skip(1));
+        self.control_point_1s.extend(other.control_point_1s.iter().cloned());
+        self.control_point_2s.extend(other.control_point_2s.iter().cloned());
+        self
+    }
+
+    /// Like `append`, but if other starts somewhere else a straight line goes there first.
+    pub fn connect(mut self, other: &Path) -> Self {
+        if self.is_closed || other.is_closed {
+            return self.fail("connect", "both paths have to be open");
+        }
+        let (end, start) = (self.vertices[self.vertices.len() - 1], other.vertices[0]);
+        if (start.0 - end.0).hypot(start.1 - end.1) > length_tolerance([start, end].iter().cloned()) {
+            self = self.line_to(start);
+        }
+        self.append(other)
+    }
+
+    /// Sets the fill color for closed shapes, in place of any gradient.
     pub fn set_fill_color(mut self, red: f32, green: f32, blue: f32) -> Self {
         self.fill_color = Some([red as GLfloat, green as GLfloat, blue as GLfloat]);
+        self.fill_gradient = None;
         self
     }
 
-    /// Removes the fill color if previously set, shape will be drawn unfilled.
+    /// Removes the fill color or gradient if previously set, shape will be drawn unfilled.
     pub fn clear_fill_color(mut self) -> Self {
         self.fill_color = None;
+        self.fill_gradient = None;
+        self
+    }
+
+    /// Fill the path with a flat color, whose alpha becomes the fill opacity, or with a gradient
+    /// made with `Drawing::create_gradient`. A gradient runs across the path's bounding box, and
+    /// changing it with `Drawing::update_gradient` changes every path filled with it. A gradient
+    /// replaces any fill pattern.
+    pub fn set_fill(self, fill: Fill) -> Self {
+        match fill {
+            Fill::Color(color) => self.set_fill_color(color.red, color.green, color.blue).set_fill_opacity(color.alpha),
+            Fill::Gradient(gradient) => {
+                let mut path = self.clear_fill_pattern();
+                // the gradient gives the colors, the fill color only turns the fill on
+                path.fill_color = path.fill_color.or(Some([1f32; 3]));
+                path.fill_gradient = Some(gradient);
+                path
+            }
+        }
+    }
+
+    /// The gradient the path is filled with, if any.
+    pub fn fill_gradient(&self) -> Option<GradientId> {
+        self.fill_gradient
+    }
+
+    /// Fill with a procedural pattern, drawn in the fill color over the background color, which
+    /// may be transparent. The pattern starts at the path's first point and moves with it. Only
+    /// paths with a fill color are filled, and the pattern replaces any gradient.
+    pub fn set_fill_pattern(mut self, pattern: FillPattern, background: Color) -> Self {
+        self.fill_pattern = Some((pattern, background));
+        self.fill_gradient = None;
+        self
+    }
+
+    /// Removes the fill pattern if previously set, the fill will be a flat color.
+    pub fn clear_fill_pattern(mut self) -> Self {
+        self.fill_pattern = None;
+        self
+    }
+
+    /// Give the fill a color at each vertex, in the order they were added, blended across the
+    /// fill's triangles for mesh gradient style artwork. Vertices past the end of colors keep the
+    /// fill color, and only paths with a fill color are filled.
+    pub fn set_vertex_colors(mut self, colors: &[Color]) -> Self {
+        self.vertex_colors = colors.to_vec();
+        self
+    }
+
+    /// Add a point inside a closed path with a fill color of its own. The fill's triangles are
+    /// split at the point so its color blends out to the vertices around it. Building the path's
+    /// geometry fails with `TrdlError::PointOutsidePolygon` if the point is outside the polygon
+    /// of the path's vertices.
+    pub fn add_color_point(mut self, point: (f32, f32), color: Color) -> Self {
+        self.color_points.push((point, color));
         self
     }
 
@@ -160,8 +758,116 @@ impl Path {
         self
     }
 
-    /// Create a rectangle path.
-    pub fn rectangle(center: (f32, f32), width: f32, height: f32, angle: f32) -> Self {
+    /// Set which side of the outline the stroke of a closed path is drawn on, centered on it by
+    /// default. Open paths are always stroked centered.
+    pub fn set_stroke_align(mut self, align: StrokeAlign) -> Self {
+        self.stroke_align = align;
+        self
+    }
+
+    /// Set whether the stroke thickness is in window pixels, staying the same on screen at any
+    /// zoom (the default), or in drawing units, growing and shrinking with the camera.
+    pub fn set_stroke_units(mut self, units: StrokeUnits) -> Self {
+        self.stroke_units = units;
+        self
+    }
+
+    /// Set how the stroke is drawn around corners where segments meet. Without a join (the
+    /// default) the stroke of each segment ends square at the corner.
+    pub fn set_line_join(mut self, join: LineJoin) -> Self {
+        self.line_join = Some(join);
+        self
+    }
+
+    /// Set the longest a miter join can be, as a multiple of the stroke thickness, like SVG's
+    /// `stroke-miterlimit`. Longer miters at sharper corners are drawn as bevels. The default is 4,
+    /// limits below 1 are taken as 1.
+    pub fn set_miter_limit(mut self, limit: f32) -> Self {
+        self.miter_limit = limit.max(1f32);
+        self
+    }
+
+    /// Draw miters beyond the miter limit as round joins rather than bevels.
+    pub fn set_round_sharp_corners(mut self, enabled: bool) -> Self {
+        self.round_sharp_corners = enabled;
+        self
+    }
+
+    /// Color the stroke with a gradient along the path or a repeating pattern in place of the
+    /// stroke color. The thickness is still the one set with `set_stroke`, without a stroke the
+    /// style is not drawn.
+    pub fn set_stroke_style(mut self, style: StrokeStyle) -> Self {
+        self.stroke_style = style;
+        self
+    }
+
+    /// How the stroke is colored along the path.
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.stroke_style
+    }
+
+    /// Set the color space a gradient stroke style blends its stops in, sRGB by default. Blending
+    /// in OKLab or HSL keeps the colors between stops from going muddy.
+    pub fn set_gradient_space(mut self, space: ColorSpace) -> Self {
+        self.gradient_space = space;
+        self
+    }
+
+    /// The color space gradient strokes blend in.
+    pub fn gradient_space(&self) -> ColorSpace {
+        self.gradient_space
+    }
+
+    /// Set the opacity of the whole path, from 0 (invisible) to 1 (opaque, the default).
+    /// Translucent paths are blended over the paths below them.
+    pub fn set_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0f32, 1f32);
+        self
+    }
+
+    /// Set the opacity of the fill alone, from 0 to 1 (the default), like SVG's `fill-opacity`.
+    /// It multiplies the opacity of the whole path.
+    pub fn set_fill_opacity(mut self, opacity: f32) -> Self {
+        self.fill_opacity = opacity.clamp(0f32, 1f32);
+        self
+    }
+
+    /// Set the opacity of the stroke alone, from 0 to 1 (the default), like SVG's
+    /// `stroke-opacity`, so an outline can fade while the fill stays. It multiplies the opacity
+    /// of the whole path.
+    pub fn set_stroke_opacity(mut self, opacity: f32) -> Self {
+        self.stroke_opacity = opacity.clamp(0f32, 1f32);
+        self
+    }
+
+    // The alpha the fill's colors are multiplied by.
+    fn fill_alpha(&self) -> f32 {
+        self.opacity * self.fill_opacity
+    }
+
+    // The alpha the stroke's colors are multiplied by.
+    fn stroke_alpha(&self) -> f32 {
+        self.opacity * self.stroke_opacity
+    }
+
+    // Whether the path hides everything below it.
+    fn is_opaque(&self) -> bool {
+        let stroke_colors: &[(f32, Color)] = match self.stroke_style {
+            StrokeStyle::Solid => &[],
+            StrokeStyle::Gradient(ref colors) | StrokeStyle::Pattern(ref colors) => colors
+        };
+        // gaps in a pattern are left for what is below to show through
+        let background = self.fill_pattern.map_or(1f32, |(_, background)| background.alpha);
+        let fill_colors = self.vertex_colors.iter().chain(self.color_points.iter().map(|(_, color)| color));
+        let stroke_alpha = if self.stroke.is_some() { self.stroke_alpha() } else { 1f32 };
+        // gradients may be given translucent stops at any time
+        self.fill_gradient.is_none() && self.fill_alpha() >= 1f32 && stroke_alpha >= 1f32 && background >= 1f32 && stroke_colors.iter().all(|&(_, color)| color.alpha >= 1f32) &&
+            fill_colors.into_iter().all(|color| color.alpha >= 1f32)
+    }
+
+    /// Create a rectangle path, turned by angle about its center.
+    pub fn rectangle(center: (f32, f32), width: f32, height: f32, angle: impl Into<Angle>) -> Self {
+        let angle = angle.into().to_radians();
         let x2 = width/2f32;
         let x1 = -x2;
         let y2 = height/2f32;
@@ -175,8 +881,9 @@ impl Path {
         Self::new(points[0]).line_to(points[1]).line_to(points[2]).line_to(points[3]).close_path()
     }
 
-    /// Create an ellipse path.
-    pub fn ellipse(center: (f32, f32), x_radius: f32, y_radius:f32, angle: f32) -> Self {
+    /// Create an ellipse path, turned by angle about its center.
+    pub fn ellipse(center: (f32, f32), x_radius: f32, y_radius:f32, angle: impl Into<Angle>) -> Self {
+        let angle = angle.into().to_radians();
         let mut points = [(x_radius, 0f32), (0f32, y_radius)];
         Self::rotate_points(&mut points, angle);
         for p in &mut points {
@@ -186,10 +893,116 @@ impl Path {
             arc_to(x_radius, y_radius, angle, points[0], true, true).close_path()
     }
 
-    // calculate the center point, start angle and sweep angle of the arc.
+    /// Create a closed pie slice of an ellipse: from the center out to the outline at
+    /// start_angle, along the outline turning by sweep (counter clockwise if positive, at most a
+    /// full turn) and back to the center. The ellipse is as in `Path::ellipse`, and the angles
+    /// are measured before it is squashed by y_radius and turned by rotation, so on a circle they
+    /// are plain angles from the x axis.
+    pub fn elliptical_sector(center: (f32, f32), x_radius: f32, y_radius: f32, rotation: impl Into<Angle>,
+                             start_angle: impl Into<Angle>, sweep: impl Into<Angle>) -> Self {
+        let (rotation, start_angle) = (rotation.into().to_radians(), start_angle.into().to_radians());
+        let sweep = sweep.into().to_radians().clamp(-2f32 * f32::consts::PI, 2f32 * f32::consts::PI);
+        let start = Self::ellipse_point(center, x_radius, y_radius, rotation, start_angle);
+        Self::new(center).line_to(start)
+            .sector_arc(center, (x_radius, y_radius), rotation, start_angle, sweep).close_path()
+    }
+
+    /// Create a closed slice of a ring, as for a donut chart: the part of a sector, see
+    /// `elliptical_sector`, outside the ellipse with the inner radii (x, y). Both ellipses share
+    /// the center and rotation.
+    pub fn ring_sector(center: (f32, f32), outer_radii: (f32, f32), inner_radii: (f32, f32),
+                       rotation: impl Into<Angle>, start_angle: impl Into<Angle>, sweep: impl Into<Angle>) -> Self {
+        let (rotation, start_angle) = (rotation.into().to_radians(), start_angle.into().to_radians());
+        let sweep = sweep.into().to_radians().clamp(-2f32 * f32::consts::PI, 2f32 * f32::consts::PI);
+        let end_angle = start_angle + sweep;
+        let outer_start = Self::ellipse_point(center, outer_radii.0, outer_radii.1, rotation, start_angle);
+        let inner_end = Self::ellipse_point(center, inner_radii.0, inner_radii.1, rotation, end_angle);
+        Self::new(outer_start).sector_arc(center, outer_radii, rotation, start_angle, sweep)
+            .line_to(inner_end).sector_arc(center, inner_radii, rotation, end_angle, -sweep).close_path()
+    }
+
+    // the point at angle on an ellipse, measured as for elliptical_sector.
+    fn ellipse_point(center: (f32, f32), x_radius: f32, y_radius: f32, rotation: f32, angle: f32) -> (f32, f32) {
+        let mut point = [(x_radius * angle.cos(), y_radius * angle.sin())];
+        Self::rotate_points(&mut point, rotation);
+        (point[0].0 + center.0, point[0].1 + center.1)
+    }
+
+    // add the outline of a sector from the current point, which must be at start_angle.
+    fn sector_arc(self, center: (f32, f32), radii: (f32, f32), rotation: f32, start_angle: f32,
+                  sweep: f32) -> Self {
+        if radii.0 > 0f32 && radii.1 > 0f32 {
+            self.elliptical_arc(center, radii.0, radii.1, rotation, start_angle, sweep)
+        } else {
+            // the ellipse is flat, go straight to the end
+            let end = Self::ellipse_point(center, radii.0, radii.1, rotation, start_angle + sweep);
+            if self.vertices[self.vertices.len() - 1] == end { self } else { self.line_to(end) }
+        }
+    }
+
+    /// Create a smooth open path through freehand pointer samples (x, y, pressure), e.g. from a
+    /// pen or mouse. Samples closer than half a unit to the previous one are dropped and the rest
+    /// are averaged with their neighbours to take out jitter, then a Catmull-Rom spline is fitted
+    /// through them. The pressure is ignored, see `from_ink_points_with_pressure`.
+    pub fn from_ink_points(points: &[(f32, f32, f32)]) -> Result<Self, TrdlError> {
+        let samples = smooth_ink(points);
+        if samples.is_empty() {
+            return Err(TrdlError::NotEnoughVertices);
+        }
+        let centers: Vec<(f32, f32)> = samples.iter().map(|s| (s.0, s.1)).collect();
+        Ok(Self::new(centers[0]).curves_through(&centers, 0f32))
+    }
+
+    /// Create a closed outline around freehand pointer samples (x, y, pressure) for filling. Its
+    /// width follows the pressure, from min_width at 0 to max_width at 1. Samples are smoothed as
+    /// in `from_ink_points`. Sharp turns can give an outline that crosses itself, adding such a
+    /// path to a drawing fails with NonSimplePolygon, but a `StencilFill` can draw it.
+    pub fn from_ink_points_with_pressure(points: &[(f32, f32, f32)], min_width: f32,
+                                         max_width: f32) -> Result<Self, TrdlError> {
+        let samples = smooth_ink(points);
+        let half_width = |pressure: f32| {
+            (min_width + (max_width - min_width) * pressure.clamp(0f32, 1f32)) / 2f32
+        };
+        let n = samples.len();
+        match n {
+            0 => Err(TrdlError::NotEnoughVertices),
+            1 => {
+                let r = half_width(samples[0].2);
+                Ok(Self::ellipse((samples[0].0, samples[0].1), r, r, 0f32))
+            }
+            _ => {
+                let mut left = Vec::with_capacity(n);
+                let mut right = Vec::with_capacity(n);
+                for i in 0..n {
+                    let before = samples[i.saturating_sub(1)];
+                    let after = samples[(i + 1).min(n - 1)];
+                    let (dx, dy) = (after.0 - before.0, after.1 - before.1);
+                    let length = dx.hypot(dy).max(f32::EPSILON);
+                    let r = half_width(samples[i].2);
+                    let normal = (-dy / length * r, dx / length * r);
+                    left.push((samples[i].0 + normal.0, samples[i].1 + normal.1));
+                    right.push((samples[i].0 - normal.0, samples[i].1 - normal.1));
+                }
+                right.reverse();
+                Ok(Self::new(left[0]).curves_through(&left, 0f32)
+                   .line_to(right[0]).curves_through(&right, 0f32).close_path())
+            }
+        }
+    }
+
+    // add a cardinal spline through points, the current point must be points[0].
+    fn curves_through(mut self, points: &[(f32, f32)], tension: f32) -> Self {
+        let controls = bezier::cardinal_control_points(points, tension);
+        for (i, (cp1, cp2)) in controls.into_iter().enumerate() {
+            self = self.curve_to(cp1, cp2, points[i + 1]);
+        }
+        self
+    }
+
+    // calculate the center point, the radii (scaled up if too small to reach the end point), start
+    // angle and sweep angle of the arc.
     fn get_ellipse_params(&mut self, x_radius: f32, y_radius: f32, angle: f32, end_point: (f32, f32),
-                          is_large_arc: bool, is_positive_sweep: bool) ->
-                          Result<((f32, f32), f32, f32), TrdlError> {
+                          is_large_arc: bool, is_positive_sweep: bool) -> Result<ArcParams, TrdlError> {
         // math taken from https://www.w3.org/TR/SVG/implnote.html#ArcImplementationNotes
         // up to the point where we get the center point.
         let start_point = self.vertices[self.vertices.len() - 1];
@@ -202,15 +1015,17 @@ impl Path {
         let x_sq = x * x;
         let y_sq = y * y;
 
-        let (x_radius, y_radius) = try!(Self::fix_radii(x_radius, y_radius, x, y));
+        let tolerance = length_tolerance([start_point, end_point]);
+        let (x_radius, y_radius) = Self::fix_radii(x_radius, y_radius, x_sq, y_sq, tolerance)?;
 
         let rx_sq = x_radius * x_radius;
         let ry_sq = y_radius * y_radius;
         let xt = x_radius * y / y_radius;
         let yt = -y_radius * x / x_radius;
 
+        // radii that were just big enough round to a tiny negative number here
         let mut radical = ((rx_sq*ry_sq - rx_sq*y_sq - ry_sq*x_sq) /
-                       (rx_sq*y_sq + ry_sq*x_sq)).sqrt();
+                       (rx_sq*y_sq + ry_sq*x_sq)).max(0f32).sqrt();
         if is_large_arc == is_positive_sweep {
             radical = -radical;
         }
@@ -231,17 +1046,18 @@ impl Path {
         let start_angle = Self::get_angle(1f32, 0f32, xt, yt);
         let mut sweep_angle = Self::get_angle(xt, yt, xt2, yt2);
 
-        if is_positive_sweep && sweep_angle < TOL {
+        if is_positive_sweep && sweep_angle < ANGLE_TOL {
             sweep_angle += 2f32*f32::consts::PI;
-        } else if !is_positive_sweep && sweep_angle > -TOL {
+        } else if !is_positive_sweep && sweep_angle > -ANGLE_TOL {
             sweep_angle -= 2f32*f32::consts::PI;
         }
-        Ok(((cx, cy), start_angle, sweep_angle))
+        Ok(((cx, cy), (x_radius, y_radius), start_angle, sweep_angle))
     }
 
     // make sure the radii are big enough to make sense.
-    fn fix_radii(x_radius: f32, y_radius: f32, x_sq: f32, y_sq: f32) -> Result<(f32, f32), TrdlError> {
-        if x_radius < TOL || y_radius == TOL { return Err(TrdlError::ArcToIsLineTo); }
+    fn fix_radii(x_radius: f32, y_radius: f32, x_sq: f32, y_sq: f32, tolerance: f32) ->
+            Result<(f32, f32), TrdlError> {
+        if x_radius.abs() <= tolerance || y_radius.abs() <= tolerance { return Err(TrdlError::ArcToIsLineTo); }
         let x_radius = x_radius.abs();
         let y_radius = y_radius.abs();
         let gamma = x_sq / (x_radius * x_radius) + y_sq / (y_radius * y_radius);
@@ -259,7 +1075,7 @@ impl Path {
         let v_mag = (vx*vx + vy*vy).sqrt();
         let arg = (ux*vx + uy*vy) / (u_mag * v_mag);
         let angle = arg.acos();
-        if ux*vy-uy*vx < -TOL {
+        if ux*vy-uy*vx < -ANGLE_TOL {
             -angle
         } else {
             angle
@@ -270,7 +1086,7 @@ impl Path {
     fn quarter_circle(radius: f32, num_quadrants: usize,
                       is_positive_sweep: bool) -> Vec<(f32, f32)> {
         // math is from http://pomax.github.io/bezierinfo/#circles_cubic
-        let magic =  radius * 0.5522847498308; // 4f32 * (2f32.sqrt() - 1f32) / 3f32;
+        let magic =  radius * 0.552_284_8; // 4f32 * (2f32.sqrt() - 1f32) / 3f32;
 
         let mut result = Vec::with_capacity(4 * num_quadrants);
         if is_positive_sweep {
@@ -282,7 +1098,7 @@ impl Path {
                 result.append(&mut vec![(-radius, -magic), (-magic, -radius), (0f32, -radius)]);
             }
             if num_quadrants > 3 {
-                result.append(&mut vec![(magic, -radius), (radius, -magic), (0f32, -radius)]);
+                result.append(&mut vec![(magic, -radius), (radius, -magic), (radius, 0f32)]);
             }
         } else {
             result.append(&mut vec![(radius, -magic), (magic, -radius), (0f32, -radius)]);
@@ -293,7 +1109,7 @@ impl Path {
                 result.append(&mut vec![(-radius, magic), (-magic, radius), (0f32, radius)]);
             }
             if num_quadrants > 3 {
-                result.append(&mut vec![(magic, radius), (radius, magic), (0f32, radius)]);
+                result.append(&mut vec![(magic, radius), (radius, magic), (radius, 0f32)]);
             }
         }
         result
@@ -342,246 +1158,895 @@ impl Path {
             *p = (cos_angle*x - sin_angle*y, sin_angle*x + cos_angle*y);
         }
     }
-}
 
-/// Manages everything under the hood. Paths are added to the drawing and then drawn.
-pub struct Drawing<'a, W: Window + 'a> {
-    window: &'a W,
-    window_size: [GLfloat; 2],
+    /// A copy of the path moved by (dx, dy).
+    pub fn translated(&self, dx: f32, dy: f32) -> Path {
+        self.transformed(&Transform2D::translation(dx, dy))
+    }
 
-    vertices: Vec<GLfloat>,
-    control_point_1s: Vec<GLfloat>,
-    control_point_2s: Vec<GLfloat>,
-    fill_colors: Vec<GLfloat>,
-    stroke_edges: Vec<GLfloat>,
-    stroke_colors: Vec<GLfloat>,
-    do_fill: Vec<GLint>,
-
-    in_position: GLint,
-    in_control_1: GLint,
-    in_control_2: GLint,
-    in_color: GLint,
-    in_edge: GLint,
-    in_stroke_color: GLint,
-    in_do_fill: GLint,
-
-    position_vbo: GLuint,
-    control_1_vbo: GLuint,
-    control_2_vbo: GLuint,
-    color_vbo: GLuint,
-    edge_vbo: GLuint,
-    stroke_color_vbo: GLuint,
-    do_fill_vbo: GLuint,
+    /// A copy of the path turned counter clockwise by angle about origin.
+    pub fn rotated(&self, angle: impl Into<Angle>, origin: (f32, f32)) -> Path {
+        self.transformed(&Transform2D::translation(origin.0, origin.1).multiply(&Transform2D::rotation(angle))
+            .multiply(&Transform2D::translation(-origin.0, -origin.1)))
+    }
 
-    shader_program: shader::ShaderProgram,
-    vao_handle: GLuint,
+    /// A copy of the path scaled by sx and sy about origin.
+    pub fn scaled(&self, sx: f32, sy: f32, origin: (f32, f32)) -> Path {
+        self.transformed(&Transform2D::translation(origin.0, origin.1).multiply(&Transform2D::scaling(sx, sy))
+            .multiply(&Transform2D::translation(-origin.0, -origin.1)))
+    }
 
-    outer_tess_uniform: GLint,
-    inner_tess_uniform: GLint,
-    projection_uniform: GLint,
-    window_size_uniform: GLint,
+    /// A copy of the path reflected across the line through the two points of axis. A closed path
+    /// is also reversed, so it keeps winding the same way and still fills.
+    pub fn mirrored(&self, axis: ((f32, f32), (f32, f32))) -> Path {
+        self.transformed_keeping_winding(&Transform2D::reflection(axis.0, axis.1))
+    }
 
-    ortho_proj: [GLfloat; 16],
+    // Like transformed, but a closed path turned over by the transform is reversed to wind the
+    // way it did.
+    pub(crate) fn transformed_keeping_winding(&self, transform: &Transform2D) -> Path {
+        let path = self.transformed(transform);
+        if self.is_closed && transform.is_mirroring() { path.reversed() } else { path }
+    }
 
-    background_color: [GLfloat; 3],
+    /// A copy of the path with the transform applied to its vertices and control points, and to
+    /// its color points. Stroke thickness is left alone.
+    pub fn transformed(&self, transform: &Transform2D) -> Path {
+        let apply = |p: &Option<(f32, f32)>| p.map(|p| transform.apply(p));
+        Path {
+            vertices: self.vertices.iter().map(|&p| transform.apply(p)).collect(),
+            control_point_1s: self.control_point_1s.iter().map(apply).collect(),
+            control_point_2s: self.control_point_2s.iter().map(apply).collect(),
+            color_points: self.color_points.iter().map(|&(p, color)| (transform.apply(p), color)).collect(),
+            ..self.clone()
+        }
+    }
 
-    depth_idx: usize,
-    num_tris: usize,
-    remake: bool
-}
+    // Whether every point of the path is finite. Transforms can make NaN of a valid path.
+    pub(crate) fn is_finite(&self) -> bool {
+        let controls = self.control_point_1s.iter().chain(&self.control_point_2s).filter_map(|&p| p);
+        self.vertices.iter().cloned().chain(controls).chain(self.color_points.iter().map(|&(p, _)| p))
+            .all(is_finite_point)
+    }
 
-impl<'a, W: Window> Drawing<'a, W> {
-    /// Constructor, a window, window size and background color.
-    pub fn new(window: &'a W, width: u32, height: u32, bg_red: f32, bg_green: f32, bg_blue: f32) ->
-            Result<Drawing<W>, TrdlError> {
-        window.set_context();
-        gl::load_with(|symbol| window.load_fn(symbol));
+    // The segments of the path collected, for when they are indexed or walked more than once.
+    pub(crate) fn segment_list(&self) -> Vec<bezier::Segment> {
+        self.segments().collect()
+    }
 
-        // load the shaders and compile them into a shader program
-        let vertex_shader_code = try!(read_file("shaders/vertex_shader.glsl"));
-        let tess_control_shader_code = try!(read_file("shaders/tess_control_shader.glsl"));
-        let tess_evaluation_shader_code = try!(read_file("shaders/tess_evaluation_shader.glsl"));
-        let geometry_shader_code = try!(read_file("shaders/geometry_shader.glsl"));
-        let fragment_shader_code = try!(read_file("shaders/fragment_shader.glsl"));
-        let program;
-        {
-            let mut builder = shader::ShaderProgramBuilder::new();
-            builder.set_vertex_shader(&vertex_shader_code);
-            builder.set_tess_control_shader(&tess_control_shader_code);
-            builder.set_tess_evaluation_shader(&tess_evaluation_shader_code);
-            builder.set_geometry_shader(&geometry_shader_code);
-            builder.set_fragment_shader(&fragment_shader_code);
-            program = try!(builder.build_shader_program());
-        }
-
-        // setup the inputs to the vertex shader
-        let program_id = program.get_program_id();
-        unsafe {
-            let c_str = CString::new("in_position").unwrap();
-            let in_position = gl::GetAttribLocation(program_id, c_str.as_ptr());
-            let c_str = CString::new("in_control_1").unwrap();
-            let in_control_1 = gl::GetAttribLocation(program_id, c_str.as_ptr());
-            let c_str = CString::new("in_control_2").unwrap();
-            let in_control_2 = gl::GetAttribLocation(program_id, c_str.as_ptr());
-            let c_str = CString::new("in_color").unwrap();
-            let in_color = gl::GetAttribLocation(program_id, c_str.as_ptr());
-            let c_str = CString::new("in_edge").unwrap();
-            let in_edge = gl::GetAttribLocation(program_id, c_str.as_ptr());
-            let c_str = CString::new("in_stroke_color").unwrap();
-            let in_stroke_color = gl::GetAttribLocation(program_id, c_str.as_ptr());
-            let c_str = CString::new("in_do_fill").unwrap();
-            let in_do_fill = gl::GetAttribLocation(program_id, c_str.as_ptr());
-
-            let vao_handle = 0 as GLuint;
-
-            // Create the buffer objects
-            const NUM_VBO: i32 = 7;
-            let vbo_handles = [0 as GLuint, 0 as GLuint, 0 as GLuint, 0 as GLuint,
-                               0 as GLuint, 0 as GLuint, 0 as GLuint];
-            gl::GenBuffers(NUM_VBO, mem::transmute(&vbo_handles[0]));
-
-            let position_vbo = vbo_handles[0];
-            let control_1_vbo = vbo_handles[1];
-            let control_2_vbo = vbo_handles[2];
-            let color_vbo = vbo_handles[3];
-            let edge_vbo = vbo_handles[4];
-            let stroke_color_vbo = vbo_handles[5];
-            let do_fill_vbo = vbo_handles[6];
-
-            Ok(Drawing {
-                window: window,
-                window_size: [gl!(width), gl!(height)],
-
-                vertices: Vec::new(),
-                control_point_1s: Vec::new(),
-                control_point_2s: Vec::new(),
-                fill_colors: Vec::new(),
-                stroke_colors: Vec::new(),
-                stroke_edges: Vec::new(),
-                do_fill: Vec::new(),
-
-                in_position: in_position,
-                in_control_1: in_control_1,
-                in_control_2: in_control_2,
-                in_color: in_color,
-                in_edge: in_edge,
-                in_stroke_color: in_stroke_color,
-                in_do_fill: in_do_fill,
-
-                position_vbo: position_vbo,
-                control_1_vbo: control_1_vbo,
-                control_2_vbo: control_2_vbo,
-                color_vbo: color_vbo,
-                edge_vbo: edge_vbo,
-                stroke_color_vbo: stroke_color_vbo,
-                do_fill_vbo: do_fill_vbo,
-
-                shader_program: program,
-                vao_handle: vao_handle,
-
-                outer_tess_uniform: -1,
-                inner_tess_uniform: -1,
-                projection_uniform: -1,
-                window_size_uniform: -1,
-
-                ortho_proj: Self::ortho(width, height),
-
-                background_color: [gl!(bg_red), gl!(bg_green), gl!(bg_blue)],
-
-                depth_idx: 0,
-                num_tris: 0,
-                remake: true
-            })
+    // A copy of the path with its curves split into pieces that bend little, with vertex colors
+    // blended along the curves for the new vertices.
+    fn with_flat_curves(&self) -> Path {
+        let n = self.vertices.len();
+        let tolerance = length_tolerance(self.vertices.iter().cloned());
+        let mut path = Path { vertices: Vec::new(), control_point_1s: Vec::new(), control_point_2s: Vec::new(),
+                              vertex_colors: Vec::new(), ..self.clone() };
+        // vertex colors stop at the first vertex without one, like in the original
+        let mut colored = true;
+        for (i, segment) in self.segments().enumerate() {
+            let colors = match (self.vertex_colors.get(i), self.vertex_colors.get((i + 1) % n)) {
+                (Some(&a), Some(&b)) => Some((a, b)),
+                (Some(&a), None) => Some((a, a)),
+                _ => None
+            };
+            let mut pieces = Vec::new();
+            match segment {
+                bezier::Segment::Cubic(p0, p1, p2, p3) =>
+                    bezier::split_until_flat(p0, p1, p2, p3, FILL_CURVE_FLATNESS, &mut pieces),
+                // lines left over from rounding, such as closing a circle, would only get in the
+                // way of the triangulation
+                bezier::Segment::Line(a, b) if (b.0 - a.0).hypot(b.1 - a.1) <= tolerance => (),
+                bezier::Segment::Line(..) => pieces.push((0f32, segment.to_cubic()))
+            }
+            for (t, piece) in pieces {
+                path.vertices.push(piece[0]);
+                let is_curve = self.control_point_1s[i].is_some();
+                path.control_point_1s.push(if is_curve { Some(piece[1]) } else { None });
+                path.control_point_2s.push(if is_curve { Some(piece[2]) } else { None });
+                match colors {
+                    Some((a, b)) if colored =>
+                        path.vertex_colors.push(color::gradient_at(&[(0f32, a), (1f32, b)], t)),
+                    _ => colored = false
+                }
+            }
         }
+        if !self.is_closed {
+            path.vertices.push(self.vertices[n - 1]);
+        }
+        path
     }
 
-    /// Add a path to the drawing.
-    pub fn add_path(&mut self, path: Path) -> Result<(), TrdlError> {
-        self.remake = true;
-        if path.is_closed {
-            self.add_closed_path(path)
-        } else {
-            self.add_open_path(path)
+    // approximate the path by a polyline, curves are split into `steps` straight pieces. For
+    // closed paths the first point is not repeated at the end.
+    fn outline(&self, steps: usize) -> Vec<(f32, f32)> {
+        let mut points = vec![self.vertices[0]];
+        for segment in self.segments() {
+            match segment {
+                bezier::Segment::Cubic(p0, p1, p2, p3) => bezier::flatten_uniform(p0, p1, p2, p3, steps,
+                                                                                  &mut points),
+                bezier::Segment::Line(_, end) => points.push(end)
+            }
+        }
+        if self.is_closed && points.len() > 1 {
+            points.pop();
         }
+        points
     }
 
-    // Triangulate the path.
-    fn add_closed_path(&mut self, path: Path) -> Result<(), TrdlError> {
-        let mut control_point_map = HashMap::new();
-        let last = path.vertices.len() - 1;
-        for i in 0..last {
-            if let Some(cp1) = path.control_point_1s[i] {
-                if let Some(cp2) = path.control_point_2s[i] {
-                    control_point_map.insert((i, i+1), (cp1, cp2));
-                } else {
-                    panic!("inconsistent control points!");
-                }
+    /// Approximate the path by a polyline that stays within `tolerance` of it, in drawing units.
+    /// Lines are kept as they are and curves (including arcs) are split until flat enough. For
+    /// closed paths the first point is not repeated at the end.
+    pub fn flatten(&self, tolerance: f32) -> Vec<(f32, f32)> {
+        let tolerance = tolerance.max(MIN_FLATTEN_TOLERANCE);
+        let mut points = vec![self.vertices[0]];
+        for segment in self.segments() {
+            match segment {
+                bezier::Segment::Cubic(p0, p1, p2, p3) => bezier::flatten_adaptive(p0, p1, p2, p3, tolerance,
+                                                                                   &mut points),
+                bezier::Segment::Line(_, end) => points.push(end)
             }
         }
-        if let Some(cp1) = path.control_point_1s[last] {
-            if let Some(cp2) = path.control_point_2s[last] {
-                control_point_map.insert((last, 0), (cp1, cp2));
-            } else {
-                panic!("inconsistent control points!");
-            }
+        if self.is_closed && points.len() > 1 {
+            points.pop();
         }
+        points
+    }
 
-        let indices = try!(triangulate(&path.vertices));
-
-        self.num_tris = indices.len() / 3;
+    /// Triangulate the area of a closed path, with curves flattened to within `tolerance`. Open
+    /// paths are treated as if closed. Like drawing, this needs a simple polygon wound counter
+    /// clockwise.
+    pub fn fill_mesh(&self, tolerance: f32) -> Result<Mesh, TrdlError> {
+        triangulate(&self.flatten(tolerance))
+    }
 
-        self.vertices.reserve(9 * self.num_tris);
-        self.control_point_1s.reserve(6 * self.num_tris);
-        self.control_point_2s.reserve(6 * self.num_tris);
-        self.fill_colors.reserve(9 * self.num_tris);
-        self.stroke_colors.reserve(9 * self.num_tris);
-        self.stroke_edges.reserve(3 * self.num_tris);
-        self.do_fill.reserve(3 * self.num_tris);
+    /// Copies of stamp placed every spacing along the path by arc length, from its start. The
+    /// stamp's origin goes on the path, and with align_to_tangent its x axis turns to follow the
+    /// path's direction. A closed path gets no stamp on top of the first at its end. A spacing
+    /// that is not positive gives no stamps.
+    pub fn stamps_along(&self, stamp: &Path, spacing: f32, align_to_tangent: bool) -> Vec<Path> {
+        if !(spacing > 0f32 && spacing.is_finite()) {
+            return Vec::new();
+        }
+        let table = self.arc_lengths();
+        let length = table.total_length();
+        let slack = length * RELATIVE_TOL;
+        let mut count = ((length + slack) / spacing).floor() as usize + 1;
+        if self.is_closed && count > 1 && (count - 1) as f32 * spacing >= length - slack {
+            count -= 1;
+        }
+        (0..count).map(|i| {
+            let point = table.point_at_length(i as f32 * spacing);
+            let mut transform = Transform2D::translation(point.position.0, point.position.1);
+            if align_to_tangent {
+                transform = transform.multiply(&Transform2D { a: point.tangent.0, b: point.tangent.1,
+                                                              c: -point.tangent.1, d: point.tangent.0,
+                                                              e: 0f32, f: 0f32 });
+            }
+            stamp.transformed(&transform)
+        }).collect()
+    }
+
+    /// The point, unit tangent and unit normal at t along the path, where t goes from 0 at the
+    /// start to 1 at the end and each segment takes an equal share of it regardless of its
+    /// length. See `point_at_length` for even spacing.
+    pub fn point_at(&self, t: f32) -> PathPoint {
+        let segments = self.segment_list();
+        if segments.is_empty() {
+            return PathPoint::new(self.vertices[0], (1f32, 0f32));
+        }
+        let scaled = t.clamp(0f32, 1f32) * segments.len() as f32;
+        let i = (scaled.floor() as usize).min(segments.len() - 1);
+        let local = scaled - i as f32;
+        PathPoint::new(segments[i].point_at(local), segments[i].direction_at(local))
+    }
+
+    /// The point, unit tangent and unit normal at distance s along the path. Builds an arc length
+    /// table each call, use `arc_lengths` when asking for many points.
+    pub fn point_at_length(&self, s: f32) -> PathPoint {
+        self.arc_lengths().point_at_length(s)
+    }
+
+    /// The length of the path.
+    pub fn length(&self) -> f32 {
+        self.arc_lengths().total_length()
+    }
+
+    /// A table of distances along the path, for fast `point_at_length` queries.
+    pub fn arc_lengths(&self) -> ArcLengthTable {
+        ArcLengthTable::new(self.vertices[0], self.segment_list())
+    }
+
+    /// The vertices of the path in order. A closed path does not repeat its first vertex.
+    pub fn vertices(&self) -> &[(f32, f32)] {
+        &self.vertices
+    }
+
+    /// The segments of the path in order, including the closing one of a closed path. Arcs and
+    /// splines come out as the cubic curves they were built from.
+    pub fn segments(&self) -> impl ExactSizeIterator<Item = bezier::Segment> + '_ {
+        let n = self.vertices.len();
+        (0..self.control_point_1s.len()).map(move |i| {
+            let start = self.vertices[i];
+            let end = self.vertices[(i + 1) % n];
+            match (self.control_point_1s[i], self.control_point_2s[i]) {
+                (Some(cp1), Some(cp2)) => bezier::Segment::Cubic(start, cp1, cp2, end),
+                _ => bezier::Segment::Line(start, end)
+            }
+        })
+    }
+
+    /// Whether the path was closed with `close_path`.
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+
+    /// The fill color, if the path is filled with one.
+    pub fn fill(&self) -> Option<[f32; 3]> {
+        self.fill_color
+    }
+
+    /// The stroke color and thickness, if the path is stroked.
+    pub fn stroke(&self) -> Option<([f32; 3], u32)> {
+        self.stroke
+    }
+
+    // Whether the fill is one flat color, without vertex colors, color points, a pattern or a
+    // gradient.
+    pub(crate) fn has_flat_fill(&self) -> bool {
+        self.vertex_colors.is_empty() && self.color_points.is_empty() && self.fill_pattern.is_none() &&
+            self.fill_gradient.is_none()
+    }
+
+    /// The path's fill, stroke and opacity.
+    pub fn style(&self) -> PathStyle {
+        PathStyle { fill_color: self.fill_color, fill_pattern: self.fill_pattern, fill_gradient: self.fill_gradient,
+                    stroke: self.stroke,
+                    stroke_align: self.stroke_align,
+                    stroke_units: self.stroke_units, line_join: self.line_join, miter_limit: self.miter_limit,
+                    round_sharp_corners: self.round_sharp_corners, opacity: self.opacity,
+                    fill_opacity: self.fill_opacity, stroke_opacity: self.stroke_opacity }
+    }
+
+    /// The smallest axis aligned box containing the path's vertices and control points, as
+    /// (min, max). The path lies inside it, though curves may not reach its edges. The same as
+    /// `fast_bounds`.
+    pub fn bounds(&self) -> ((f32, f32), (f32, f32)) {
+        self.fast_bounds()
+    }
+
+    /// The box around the path's vertices and control points, as (min, max). Cheap, for culling
+    /// and spatial indexes, but loose around curves.
+    pub fn fast_bounds(&self) -> ((f32, f32), (f32, f32)) {
+        let mut min = self.vertices[0];
+        let mut max = self.vertices[0];
+        let controls = self.control_point_1s.iter().chain(&self.control_point_2s).filter_map(|&p| p);
+        for p in self.vertices.iter().cloned().chain(controls) {
+            min = (min.0.min(p.0), min.1.min(p.1));
+            max = (max.0.max(p.0), max.1.max(p.1));
+        }
+        (min, max)
+    }
+
+    /// The smallest axis aligned box containing the path's outline, as (min, max). Curves and
+    /// arcs are bounded where they turn rather than by their control points, so this is exact,
+    /// for layout and alignment, but costs more than `fast_bounds`. Strokes are not included.
+    pub fn tight_bounds(&self) -> ((f32, f32), (f32, f32)) {
+        let start = (self.vertices[0], self.vertices[0]);
+        self.segments().map(|segment| segment.bounds()).fold(start, |(min, max), (s_min, s_max)| {
+            ((min.0.min(s_min.0), min.1.min(s_min.1)), (max.0.max(s_max.0), max.1.max(s_max.1)))
+        })
+    }
+
+    /// The winding number of the path's outline around `point`, worked out on the curves
+    /// themselves rather than a flattened outline: how many times the outline goes around it
+    /// counterclockwise (with y up), less the times it goes clockwise. Open paths are closed
+    /// with a straight line back to the start.
+    pub fn winding_number(&self, point: (f32, f32)) -> i32 {
+        let closing = if self.is_closed {
+            None
+        } else {
+            Some(bezier::Segment::Line(self.vertices[self.vertices.len() - 1], self.vertices[0]))
+        };
+        self.segments().chain(closing).map(|segment| segment.crossings(point)).sum()
+    }
+
+    /// Whether `point` is inside the path's outline, by its winding number, or within
+    /// `tolerance` of the outline. Curves are followed exactly, so points just inside a circle
+    /// count and ones just outside do not.
+    pub fn contains_point(&self, point: (f32, f32), tolerance: f32) -> bool {
+        if self.winding_number(point) != 0 {
+            return true;
+        }
+        if tolerance <= 0f32 {
+            return false;
+        }
+        let nearest = self.nearest_point(point);
+        (nearest.0 - point.0).hypot(nearest.1 - point.1) <= tolerance
+    }
+
+    /// The point of the path's outline closest to `point`.
+    pub fn nearest_point(&self, point: (f32, f32)) -> (f32, f32) {
+        let distance = |p: (f32, f32)| (p.0 - point.0).hypot(p.1 - point.1);
+        self.segments()
+            .map(|segment| segment.nearest_point(point).0)
+            .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(self.vertices[0])
+    }
+
+    /// Points where the outlines of this path and `other` cross, in no particular order.
+    pub fn intersection_points(&self, other: &Path) -> Vec<(f32, f32)> {
+        let mut points = Vec::new();
+        let others = other.segment_list();
+        for a in self.segments() {
+            for b in &others {
+                bezier::intersections(&a, b, &mut points);
+            }
+        }
+        points
+    }
+
+    /// Whether the paths touch: their outlines cross, or one is closed and contains the other.
+    pub fn intersects(&self, other: &Path) -> bool {
+        let contains = |outer: &Path, inner: &Path| outer.is_closed && outer.winding_number(inner.vertices[0]) != 0;
+        contains(self, other) || contains(other, self) || !self.intersection_points(other).is_empty()
+    }
+
+    // Whether a point is on the fill or the stroke of the path. `pixel_size` is the size of a
+    // window pixel in path coordinates.
+    fn hit_test(&self, point: (f32, f32), pixel_size: f32) -> bool {
+        let outline = self.outline(16);
+        if self.is_closed && self.fill_color.is_some() && self.winding_number(point) != 0 {
+            return true;
+        }
+        if let Some((_, thickness)) = self.stroke {
+            let thickness = match self.stroke_units {
+                StrokeUnits::Pixels => thickness as f32 * pixel_size,
+                StrokeUnits::World => thickness as f32
+            };
+            let n = outline.len();
+            let num_edges = if self.is_closed { n } else { n - 1 };
+            let distance = (0..num_edges)
+                .map(|i| bezier::distance_to_segment(point, outline[i], outline[(i + 1) % n]))
+                .fold(f32::INFINITY, f32::min);
+            let align = if self.is_closed { self.stroke_align } else { StrokeAlign::Center };
+            // an aligned stroke reaches its whole thickness to one side and none to the other
+            let reach = match align {
+                StrokeAlign::Center => thickness / 2f32,
+                StrokeAlign::Inside | StrokeAlign::Outside => {
+                    let inside = self.winding_number(point) != 0;
+                    if inside == (align == StrokeAlign::Inside) { thickness } else { 0f32 }
+                }
+            };
+            // and a pixel further for anti-aliasing
+            return distance <= reach + pixel_size;
+        }
+        false
+    }
+}
+
+/// The order paths are sent to the GPU in. Either way paths added later cover the ones added
+/// before them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// Draw every path in the order it was added, with blending on.
+    InsertionOrder,
+    /// Draw opaque paths first, topmost first so the depth test skips what they hide, then
+    /// translucent paths bottom to top with blending. This is the default.
+    OpaqueFirst
+}
+
+/// Which side of a closed path's outline its stroke is drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrokeAlign {
+    /// Half the stroke inside the outline and half outside, the default.
+    Center,
+    /// All of the stroke inside the outline, so it never spills past the fill.
+    Inside,
+    /// All of the stroke outside the outline, leaving the fill uncovered.
+    Outside
+}
+
+impl StrokeAlign {
+    // The alignment as the shaders read it from a vertex's fill flags.
+    fn flags(self) -> GLint {
+        match self {
+            StrokeAlign::Center => 0,
+            StrokeAlign::Inside => 2,
+            StrokeAlign::Outside => 4
+        }
+    }
+}
+
+/// What a stroke's thickness is measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrokeUnits {
+    /// Window pixels, the stroke looks the same at any zoom.
+    Pixels,
+    /// Drawing units, the stroke scales with the camera like the path does.
+    World
+}
+
+/// How the stroke is drawn around a corner of a path, as in SVG's `stroke-linejoin`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    /// The outer edges of the stroke are extended until they meet in a point, up to the miter
+    /// limit.
+    Miter,
+    /// The outer corners of the stroke are joined by a straight line.
+    Bevel,
+    /// The corner is rounded off with a circular arc.
+    Round
+}
+
+impl LineJoin {
+    // The join as the shaders read it from a join patch's fill flags.
+    fn flags(self) -> GLint {
+        match self {
+            LineJoin::Miter => 0,
+            LineJoin::Bevel => 32,
+            LineJoin::Round => 64
+        }
+    }
+}
+
+/// Identifies a path that was added to a drawing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathId(pub(crate) usize);
+
+impl PathId {
+    /// The id as a plain number, for bindings to other languages. It only means something to the
+    /// drawing the path was added to.
+    pub fn to_raw(self) -> u64 {
+        self.0 as u64
+    }
+
+    /// The id `to_raw` returned.
+    pub fn from_raw(raw: u64) -> Self {
+        PathId(raw as usize)
+    }
+}
+
+/// How a path is painted, see `Path::style`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathStyle {
+    pub fill_color: Option<[f32; 3]>,
+    /// Fill pattern and its background color.
+    pub fill_pattern: Option<(FillPattern, Color)>,
+    /// Gradient filling the path in place of the fill color.
+    pub fill_gradient: Option<GradientId>,
+    /// Stroke color and thickness in pixels.
+    pub stroke: Option<([f32; 3], u32)>,
+    pub stroke_align: StrokeAlign,
+    pub stroke_units: StrokeUnits,
+    pub line_join: Option<LineJoin>,
+    pub miter_limit: f32,
+    pub round_sharp_corners: bool,
+    pub opacity: f32,
+    /// Opacity of the fill alone, multiplying `opacity`.
+    pub fill_opacity: f32,
+    /// Opacity of the stroke alone, multiplying `opacity`.
+    pub stroke_opacity: f32
+}
+
+/// What the last frame drew, see `Drawing::frame_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameStats {
+    /// Paths in the drawing.
+    pub paths: usize,
+    /// Patch vertices drawn, including those drawn into layer textures.
+    pub patches: usize,
+    /// Draw calls made for paths. Paths are sorted by GPU state so each run of paths sharing it,
+    /// such as the opaque paths of a layer, goes out in one call.
+    pub batches: usize,
+    /// Layers drawn through their texture.
+    pub layers_composited: usize,
+    /// Layer textures drawn again because they were out of date.
+    pub layers_redrawn: usize
+}
+
+// A path in the drawing along with its geometry, ready to be copied into the vertex buffers.
+struct PathEntry {
+    path: Path,
+    geometry: PathGeometry,
+    layer: LayerId,
+    bounds: ((f32, f32), (f32, f32))
+}
+
+impl PathEntry {
+    // With diagnose on, geometry errors come back as TrdlError::Tessellation with what was wrong.
+    fn new(path: Path, layer: LayerId, diagnose: bool) -> Result<Self, TrdlError> {
+        let geometry = match PathGeometry::new(&path) {
+            Ok(geometry) => geometry,
+            Err(err @ TrdlError::NotEnoughVertices) | Err(err @ TrdlError::NonSimplePolygon) |
+            Err(err @ TrdlError::ZeroAreaPolygon) | Err(err @ TrdlError::PointOutsidePolygon) if diagnose => {
+                // closed paths are triangulated with their curves split
+                let points = if path.is_closed { path.with_flat_curves().vertices } else { path.vertices.clone() };
+                return Err(TrdlError::Tessellation(Box::new(triangulation::diagnose(&points, path.is_closed, err))));
+            }
+            Err(err) => return Err(err)
+        };
+        let bounds = path.bounds();
+        Ok(PathEntry { path, geometry, layer, bounds })
+    }
+}
+
+// A run of patch vertices in the buffers: opaque ones from start to blend_start are drawn without
+// blending, the rest up to end with blending.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct PatchRange {
+    start: usize,
+    blend_start: usize,
+    end: usize
+}
+
+// The vertex attributes for the patches of one path. Every vertex has x, y and depth, the depth is
+// filled in when the buffers are rebuilt since it depends on the draw order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct PathGeometry {
+    vertices: Vec<GLfloat>,
+    control_point_1s: Vec<GLfloat>,
+    control_point_2s: Vec<GLfloat>,
+    fill_colors: Vec<GLfloat>,
+    stroke_edges: Vec<GLfloat>,
+    stroke_colors: Vec<GLfloat>,
+    do_fill: Vec<GLint>
+}
+
+/// The vertex arrays of a drawing exactly as they go to the GPU, in buffer order: every path
+/// layer by layer, then the selection, overlay and debug paths. Every patch is three vertices,
+/// with x, y and depth, two control points and a fill and stroke color each. Get one from
+/// `Drawing::snapshot_geometry` to check what a drawing would render without drawing it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GeometrySnapshot {
+    /// x, y and depth of every vertex.
+    pub vertices: Vec<GLfloat>,
+    /// The first control point of the edge from every vertex.
+    pub control_point_1s: Vec<GLfloat>,
+    /// The second control point of the edge from every vertex.
+    pub control_point_2s: Vec<GLfloat>,
+    /// RGBA fill color of every vertex.
+    pub fill_colors: Vec<GLfloat>,
+    /// Stroke width of the edge from every vertex, zero for no stroke.
+    pub stroke_edges: Vec<GLfloat>,
+    /// RGBA stroke color of every vertex.
+    pub stroke_colors: Vec<GLfloat>,
+    /// The fill, join and pattern flags of every vertex.
+    pub do_fill: Vec<GLint>,
+    /// A record for each path with a fill pattern, the pattern flags index into these.
+    pub fill_patterns: Vec<GLfloat>,
+    /// The vertex range of each path, in buffer order. Overlay paths are not in here.
+    pub paths: Vec<(PathId, usize, usize)>
+}
+
+impl GeometrySnapshot {
+    /// The number of vertices.
+    pub fn num_vertices(&self) -> usize {
+        self.vertices.len() / 3
+    }
+
+    /// The number of patches.
+    pub fn num_patches(&self) -> usize {
+        self.vertices.len() / 9
+    }
+
+    /// The size of the vertex data in bytes, without the fill pattern records.
+    pub fn byte_size(&self) -> usize {
+        let floats = self.vertices.len() + self.control_point_1s.len() + self.control_point_2s.len() +
+                     self.fill_colors.len() + self.stroke_edges.len() + self.stroke_colors.len();
+        floats * mem::size_of::<GLfloat>() + self.do_fill.len() * mem::size_of::<GLint>()
+    }
+
+    fn clear(&mut self) {
+        self.vertices.clear();
+        self.control_point_1s.clear();
+        self.control_point_2s.clear();
+        self.fill_colors.clear();
+        self.stroke_colors.clear();
+        self.stroke_edges.clear();
+        self.do_fill.clear();
+        self.fill_patterns.clear();
+        self.paths.clear();
+    }
+
+    // Append the geometry of one path at the given depth, with its fill pattern record if any.
+    pub(crate) fn push(&mut self, geometry: &PathGeometry, depth: GLfloat, pattern: Option<[GLfloat; fill_pattern::RECORD_FLOATS]>) {
+        let start = self.vertices.len();
+        self.vertices.extend_from_slice(&geometry.vertices);
+        for v in self.vertices[start..].chunks_mut(3) {
+            v[2] = depth;
+        }
+        self.control_point_1s.extend_from_slice(&geometry.control_point_1s);
+        self.control_point_2s.extend_from_slice(&geometry.control_point_2s);
+        self.fill_colors.extend_from_slice(&geometry.fill_colors);
+        self.stroke_colors.extend_from_slice(&geometry.stroke_colors);
+        self.stroke_edges.extend_from_slice(&geometry.stroke_edges);
+        // the vertices of a patterned path point to its record
+        let pattern_flags = match pattern {
+            Some(record) => {
+                self.fill_patterns.extend_from_slice(&record);
+                ((self.fill_patterns.len() / fill_pattern::RECORD_FLOATS) as GLint) << fill_pattern::PATTERN_SHIFT
+            }
+            None => 0
+        };
+        self.do_fill.extend(geometry.do_fill.iter().map(|&flags| flags | pattern_flags));
+    }
+
+    // Give back memory once most of the paths are gone.
+    fn shrink(&mut self) {
+        if self.vertices.capacity() > 2 * self.vertices.len() + SHRINK_SLACK {
+            self.vertices.shrink_to_fit();
+            self.control_point_1s.shrink_to_fit();
+            self.control_point_2s.shrink_to_fit();
+            self.fill_colors.shrink_to_fit();
+            self.stroke_colors.shrink_to_fit();
+            self.stroke_edges.shrink_to_fit();
+            self.do_fill.shrink_to_fit();
+        }
+    }
+}
+
+impl PathGeometry {
+    // The size of the vertex data in bytes.
+    fn byte_size(&self) -> usize {
+        let floats = self.vertices.len() + self.control_point_1s.len() + self.control_point_2s.len() +
+                     self.fill_colors.len() + self.stroke_edges.len() + self.stroke_colors.len();
+        floats * mem::size_of::<GLfloat>() + self.do_fill.len() * mem::size_of::<GLint>()
+    }
+
+    // The patches, three vertices each.
+    fn patches(&self) -> Vec<Patch> {
+        let point = |v: &[GLfloat], k: usize, stride: usize| (v[stride * k], v[stride * k + 1]);
+        (0..self.do_fill.len() / 3).map(|patch| {
+            let k = 3 * patch;
+            let control = |i: usize| (point(&self.control_point_1s, k + i, 2), point(&self.control_point_2s, k + i, 2));
+            Patch {
+                corners: [point(&self.vertices, k, 3), point(&self.vertices, k + 1, 3), point(&self.vertices, k + 2, 3)],
+                controls: [control(0), control(1), control(2)],
+                is_join: self.do_fill[k] & JOIN_FLAG != 0
+            }
+        }).collect()
+    }
+
+    // Change the fill and stroke colors in place, keeping each vertex's alpha. Only right for a
+    // path with a flat fill and a solid stroke, whose vertices all share its colors.
+    fn recolor(&mut self, fill: Option<[f32; 3]>, stroke: Option<[f32; 3]>) {
+        if let Some(rgb) = fill {
+            for (color, &flags) in self.fill_colors.chunks_mut(4).zip(&self.do_fill) {
+                if flags & FILL_FLAG != 0 {
+                    color[..3].copy_from_slice(&rgb);
+                }
+            }
+        }
+        if let Some(rgb) = stroke {
+            for color in self.stroke_colors.chunks_mut(4) {
+                color[..3].copy_from_slice(&rgb);
+            }
+        }
+    }
+
+    // Build the geometry for a path.
+    pub(crate) fn new(path: &Path) -> Result<Self, TrdlError> {
+        span!("tessellate_path");
+        if let Some((call, reason)) = path.problem {
+            return Err(TrdlError::InvalidPath(call, reason));
+        }
+        if !path.is_finite() {
+            return Err(TrdlError::NonFiniteCoordinate);
+        }
+        let styled = path.stroke.is_some() && path.stroke_style != StrokeStyle::Solid;
+        let table = path.arc_lengths();
+        let mut geometry = if styled {
+            // the stroke is drawn in pieces of its own, apart from the fill
+            let mut geometry = if path.is_closed {
+                Self::closed_path(&Path { stroke: None, ..path.clone() })?
+            } else {
+                PathGeometry::default()
+            };
+            let thickness = path.stroke.map_or(0, |(_, thickness)| thickness);
+            geometry.add_stroke_pieces(path, &path.stroke_style.pieces(&table, path.stroke_alpha(), path.gradient_space), thickness);
+            geometry
+        } else if path.is_closed {
+            Self::closed_path(path)?
+        } else {
+            Self::open_path(path)?
+        };
+        if let (Some((color, thickness)), Some(join)) = (path.stroke, path.line_join) {
+            // a join takes the stroke's color at the start of the segment after it
+            let length = table.total_length();
+            let colors: Vec<Option<[f32; 4]>> = (0..table.segments().len()).map(|i| if styled {
+                path.stroke_style.color_at(table.segment_range(i).0, length, path.gradient_space)
+                    .map(|c| [c.red, c.green, c.blue, c.alpha * path.stroke_alpha()])
+            } else {
+                Some([color[0], color[1], color[2], path.stroke_alpha()])
+            }).collect();
+            geometry.add_joins(path, &colors, thickness, join);
+        }
+        let units = match path.stroke_units {
+            StrokeUnits::World => WORLD_STROKE_FLAG,
+            StrokeUnits::Pixels if path.stroke.is_some_and(|(_, thickness)| thickness % 2 == 1) => ODD_STROKE_FLAG,
+            StrokeUnits::Pixels => 0
+        };
+        for flags in &mut geometry.do_fill {
+            *flags |= units;
+        }
+        Ok(geometry)
+    }
+
+    // Add a patch for each corner of the path where its direction changes. A join patch's
+    // vertices are a point before the corner, the corner and a point after it, the shaders draw
+    // the join in window space from them. Its edges hold the thickness and the miter limit.
+    // Corners where the color is None get no join.
+    fn add_joins(&mut self, path: &Path, colors: &[Option<[f32; 4]>], thickness: u32, join: LineJoin) {
+        let segments = path.segment_list();
+        let n = segments.len();
+        let mut flags = JOIN_FLAG | join.flags() | path.stroke_align.flags();
+        if path.round_sharp_corners {
+            flags |= ROUND_SHARP_FLAG;
+        }
+        if path.is_closed && bezier::polygon_area(&path.outline(4)) > 0f32 {
+            flags |= INSIDE_LEFT_FLAG;
+        }
+        let first = if path.is_closed { 0 } else { 1 };
+        for i in first..n {
+            let incoming = &segments[(i + n - 1) % n];
+            let outgoing = &segments[i];
+            let d_in = incoming.direction_at(1f32);
+            let d_out = outgoing.direction_at(0f32);
+            let color = match colors[i] {
+                Some(color) if d_in.0 * d_out.0 + d_in.1 * d_out.1 <= 1f32 - SMOOTH_CORNER => color,
+                _ => continue
+            };
+            let corner = outgoing.point_at(0f32);
+            let chord = |segment: &bezier::Segment| {
+                let (a, b) = (segment.point_at(0f32), segment.point_at(1f32));
+                (b.0 - a.0).hypot(b.1 - a.1).max(ONE)
+            };
+            let (l_in, l_out) = (chord(incoming), chord(outgoing));
+            let points = [(corner.0 - d_in.0 * l_in, corner.1 - d_in.1 * l_in), corner,
+                          (corner.0 + d_out.0 * l_out, corner.1 + d_out.1 * l_out)];
+            for k in 0..3 {
+                let (p, q) = (points[k], points[(k + 1) % 3]);
+                let (cp1, cp2) = bezier_line_control_points(p, q);
+                self.vertices.extend_from_slice(&[p.0, p.1, ZERO]);
+                self.control_point_1s.extend_from_slice(&[cp1.0, cp1.1]);
+                self.control_point_2s.extend_from_slice(&[cp2.0, cp2.1]);
+            }
+            self.fill_colors.extend_from_slice(&[ZERO; 12]);
+            for _ in 0..3 {
+                self.stroke_colors.extend_from_slice(&color);
+            }
+            self.stroke_edges.extend_from_slice(&[gl!(thickness), path.miter_limit, ZERO]);
+            self.do_fill.extend_from_slice(&[flags; 3]);
+        }
+    }
+
+    // Add a patch for each piece of a styled stroke. The piece is the patch's first edge and the
+    // only one stroked, its color blended from one end to the other. The third vertex is put on
+    // the side of the piece the path's inside is on, so aligned strokes go the right way.
+    fn add_stroke_pieces(&mut self, path: &Path, pieces: &[StrokePiece], thickness: u32) {
+        let (flags, side) = if !path.is_closed {
+            (0, ONE)
+        } else if bezier::polygon_area(&path.outline(4)) > 0f32 {
+            (path.stroke_align.flags(), ONE)
+        } else {
+            (path.stroke_align.flags(), -ONE)
+        };
+        for piece in pieces {
+            let segment = &piece.segment;
+            let (a, b) = (segment.point_at(0f32), segment.point_at(1f32));
+            let (middle, direction) = (segment.point_at(0.5f32), segment.direction_at(0.5f32));
+            let reach = side * (b.0 - a.0).hypot(b.1 - a.1).max(ONE) / TWO;
+            let c = (middle.0 - direction.1 * reach, middle.1 + direction.0 * reach);
+            let (ab1, ab2) = match *segment {
+                bezier::Segment::Cubic(_, cp1, cp2, _) => (cp1, cp2),
+                bezier::Segment::Line(..) => bezier_line_control_points(a, b)
+            };
+            let (bc1, bc2) = bezier_line_control_points(b, c);
+            let (ca1, ca2) = bezier_line_control_points(c, a);
+            for p in &[a, b, c] {
+                self.vertices.extend_from_slice(&[p.0, p.1, ZERO]);
+            }
+            self.control_point_1s.extend_from_slice(&[ab1.0, ab1.1, bc1.0, bc1.1, ca1.0, ca1.1]);
+            self.control_point_2s.extend_from_slice(&[ab2.0, ab2.1, bc2.0, bc2.1, ca2.0, ca2.1]);
+            self.fill_colors.extend_from_slice(&[ZERO; 12]);
+            for color in &[piece.start_color, piece.end_color, piece.start_color] {
+                self.stroke_colors.extend_from_slice(color);
+            }
+            self.stroke_edges.extend_from_slice(&[ZERO, ZERO, gl!(thickness)]);
+            self.do_fill.extend_from_slice(&[flags; 3]);
+        }
+    }
+
+    // Triangulate the path. Curves are split first so each triangle's curved edge hugs its chord.
+    fn closed_path(path: &Path) -> Result<Self, TrdlError> {
+        let path = &path.with_flat_curves();
+        let mut control_point_map = HashMap::new();
+        let last = path.vertices.len() - 1;
+        for i in 0..last {
+            if let Some(cp1) = path.control_point_1s[i] {
+                if let Some(cp2) = path.control_point_2s[i] {
+                    control_point_map.insert((i, i+1), (cp1, cp2));
+                } else {
+                    panic!("inconsistent control points!");
+                }
+            }
+        }
+        if let Some(cp1) = path.control_point_1s[last] {
+            if let Some(cp2) = path.control_point_2s[last] {
+                control_point_map.insert((last, 0), (cp1, cp2));
+            } else {
+                panic!("inconsistent control points!");
+            }
+        }
+
+        // points inside the path get vertices of their own after the path's
+        let mut mesh = triangulate_exact(&path.vertices)?;
+        if path.fill_color.is_some() && !path.color_points.is_empty() {
+            let points: Vec<(f32, f32)> = path.color_points.iter().map(|&(p, _)| p).collect();
+            insert_points(&mut mesh, &points)?;
+        }
+        let (points, indices) = (mesh.vertices, mesh.indices);
+
+        let num_tris = indices.len() / 3;
+        let mut geometry = PathGeometry::default();
+
+        geometry.vertices.reserve(9 * num_tris);
+        geometry.control_point_1s.reserve(6 * num_tris);
+        geometry.control_point_2s.reserve(6 * num_tris);
+        geometry.fill_colors.reserve(12 * num_tris);
+        geometry.stroke_colors.reserve(12 * num_tris);
+        geometry.stroke_edges.reserve(3 * num_tris);
+        geometry.do_fill.reserve(3 * num_tris);
 
         let num_verts = path.vertices.len();
-        self.depth_idx += 1;
-        let depth = (MAX_DEPTH - (self.depth_idx as f32)) / MAX_DEPTH;
+        let depth = ZERO;
+        let (alpha, stroke_alpha) = (path.fill_alpha(), path.stroke_alpha());
 
-        for t in 0..self.num_tris {
+        for t in 0..num_tris {
             let ti0 = 3*t;
             let ti1 = ti0+1;
             let ti2 = ti0+2;
-            get_control_points(&path.vertices, indices[ti0], indices[ti1], depth,
-                               &mut control_point_map, &mut self.vertices,
-                               &mut self.control_point_1s, &mut self.control_point_2s);
-            get_control_points(&path.vertices, indices[ti1], indices[ti2], depth,
-                               &mut control_point_map, &mut self.vertices,
-                               &mut self.control_point_1s, &mut self.control_point_2s);
-            get_control_points(&path.vertices, indices[ti2], indices[ti0], depth,
-                               &mut control_point_map, &mut self.vertices,
-                               &mut self.control_point_1s, &mut self.control_point_2s);
+            get_control_points(&points, indices[ti0], indices[ti1], depth,
+                               &mut control_point_map, &mut geometry.vertices,
+                               &mut geometry.control_point_1s, &mut geometry.control_point_2s);
+            get_control_points(&points, indices[ti1], indices[ti2], depth,
+                               &mut control_point_map, &mut geometry.vertices,
+                               &mut geometry.control_point_1s, &mut geometry.control_point_2s);
+            get_control_points(&points, indices[ti2], indices[ti0], depth,
+                               &mut control_point_map, &mut geometry.vertices,
+                               &mut geometry.control_point_1s, &mut geometry.control_point_2s);
             if let Some(stroke) = path.stroke {
-                push3(&mut self.stroke_colors, stroke.0);
+                push_color(&mut geometry.stroke_colors, stroke.0, stroke_alpha);
                 let thickness = gl!(stroke.1);
                 let (e0, e1, e2) = triangle_edges(indices[ti0], indices[ti1], indices[ti2], num_verts-1);
-                self.stroke_edges.push(if e0 {thickness} else {ZERO});
-                self.stroke_edges.push(if e1 {thickness} else {ZERO});
-                self.stroke_edges.push(if e2 {thickness} else {ZERO});
+                geometry.stroke_edges.push(if e0 {thickness} else {ZERO});
+                geometry.stroke_edges.push(if e1 {thickness} else {ZERO});
+                geometry.stroke_edges.push(if e2 {thickness} else {ZERO});
             } else {
-                push3(&mut self.stroke_colors, [ZERO, ZERO, ZERO]);
-                self.stroke_edges.push(ZERO);
-                self.stroke_edges.push(ZERO);
-                self.stroke_edges.push(ZERO);
+                push_color(&mut geometry.stroke_colors, [ZERO, ZERO, ZERO], ZERO);
+                geometry.stroke_edges.push(ZERO);
+                geometry.stroke_edges.push(ZERO);
+                geometry.stroke_edges.push(ZERO);
             }
+            let flags = path.stroke_align.flags();
             if let Some(fill_color) = path.fill_color {
-                push3(&mut self.fill_colors, fill_color);
-                self.do_fill.push(1 as GLint);
-                self.do_fill.push(1 as GLint);
-                self.do_fill.push(1 as GLint);
-
+                for &i in &indices[ti0..ti0 + 3] {
+                    let color = if i < num_verts {
+                        path.vertex_colors.get(i).cloned()
+                    } else {
+                        Some(path.color_points[i - num_verts].1)
+                    };
+                    let color = color.map_or([fill_color[0], fill_color[1], fill_color[2], alpha],
+                                             |c| [c.red, c.green, c.blue, c.alpha * alpha]);
+                    geometry.fill_colors.extend_from_slice(&color);
+                }
+                geometry.do_fill.extend_from_slice(&[flags | FILL_FLAG; 3]);
             } else {
-                push3(&mut self.fill_colors, [ZERO, ZERO, ZERO]);
-                self.do_fill.push(0 as GLint);
-                self.do_fill.push(0 as GLint);
-                self.do_fill.push(0 as GLint);
+                push_color(&mut geometry.fill_colors, [ZERO, ZERO, ZERO], ZERO);
+                geometry.do_fill.extend_from_slice(&[flags; 3]);
             }
         }
-        Ok(())
+        Ok(geometry)
     }
 
     // make a new point such that the 3 points make a triangle, be careful that the order makes a
@@ -629,260 +2094,2360 @@ impl<'a, W: Window> Drawing<'a, W> {
 
     // take each segment between the points of the path and add a point to turn each one into an
     // unfilled triangle.
-    fn add_open_path(&mut self, path: Path) -> Result<(), TrdlError> {
+    fn open_path(path: &Path) -> Result<Self, TrdlError> {
 
-        if path.stroke == None {
+        if path.stroke.is_none() {
             return Err(TrdlError::NoVisibleGeometry);
         }
 
-        self.num_tris = path.vertices.len() - 1;
+        let num_tris = path.vertices.len() - 1;
+        let mut geometry = PathGeometry::default();
 
-        self.vertices.reserve(9 * self.num_tris);
-        self.control_point_1s.reserve(6 * self.num_tris);
-        self.control_point_2s.reserve(6 * self.num_tris);
-        self.fill_colors.append(&mut vec![gl!(0); 9 * self.num_tris]);
-        self.stroke_colors.reserve(9 * self.num_tris);
-        self.stroke_edges.reserve(3 * self.num_tris);
-        self.do_fill.append(&mut vec![0 as GLint; 3 * self.num_tris]);
+        geometry.vertices.reserve(9 * num_tris);
+        geometry.control_point_1s.reserve(6 * num_tris);
+        geometry.control_point_2s.reserve(6 * num_tris);
+        geometry.fill_colors.append(&mut vec![gl!(0); 12 * num_tris]);
+        geometry.stroke_colors.reserve(12 * num_tris);
+        geometry.stroke_edges.reserve(3 * num_tris);
+        geometry.do_fill.append(&mut vec![0 as GLint; 3 * num_tris]);
 
-        self.depth_idx += 1;
-        let depth = (MAX_DEPTH - (self.depth_idx as f32)) / MAX_DEPTH;
+        let depth = ZERO;
 
-        for i in 0..self.num_tris {
+        for i in 0..num_tris {
             let v0 = path.vertices[i];
             let v1 = path.vertices[i + 1];
-            let v2 = try!(Self::make_extra_point(v0, v1));
-            self.vertices.push(v0.0); self.vertices.push(v0.1); self.vertices.push(depth);
-            self.vertices.push(v1.0); self.vertices.push(v1.1); self.vertices.push(depth);
-            self.vertices.push(v2.0); self.vertices.push(v2.1); self.vertices.push(depth);
+            let v2 = Self::make_extra_point(v0, v1)?;
+            geometry.vertices.push(v0.0); geometry.vertices.push(v0.1); geometry.vertices.push(depth);
+            geometry.vertices.push(v1.0); geometry.vertices.push(v1.1); geometry.vertices.push(depth);
+            geometry.vertices.push(v2.0); geometry.vertices.push(v2.1); geometry.vertices.push(depth);
 
             if let Some(cp1) = path.control_point_1s[i] {
-                self.control_point_1s.push(cp1.0); self.control_point_1s.push(cp1.1);
+                geometry.control_point_1s.push(cp1.0); geometry.control_point_1s.push(cp1.1);
                 if let Some(cp2) = path.control_point_2s[i] {
-                    self.control_point_2s.push(cp2.0); self.control_point_2s.push(cp2.1);
+                    geometry.control_point_2s.push(cp2.0); geometry.control_point_2s.push(cp2.1);
                 } else {
                     panic!("Inconsistent control points");
                 }
             } else {
                 let (cp1, cp2) = bezier_line_control_points(v0, v1);
-                self.control_point_1s.push(cp1.0); self.control_point_1s.push(cp1.1);
-                self.control_point_2s.push(cp2.0); self.control_point_2s.push(cp2.1);
+                geometry.control_point_1s.push(cp1.0); geometry.control_point_1s.push(cp1.1);
+                geometry.control_point_2s.push(cp2.0); geometry.control_point_2s.push(cp2.1);
             }
 
             let (cp1, cp2) = bezier_line_control_points(v1, v2);
-            self.control_point_1s.push(cp1.0); self.control_point_1s.push(cp1.1);
-            self.control_point_2s.push(cp2.0); self.control_point_2s.push(cp2.1);
+            geometry.control_point_1s.push(cp1.0); geometry.control_point_1s.push(cp1.1);
+            geometry.control_point_2s.push(cp2.0); geometry.control_point_2s.push(cp2.1);
 
             let (cp1, cp2) = bezier_line_control_points(v2, v0);
-            self.control_point_1s.push(cp1.0); self.control_point_1s.push(cp1.1);
-            self.control_point_2s.push(cp2.0); self.control_point_2s.push(cp2.1);
+            geometry.control_point_1s.push(cp1.0); geometry.control_point_1s.push(cp1.1);
+            geometry.control_point_2s.push(cp2.0); geometry.control_point_2s.push(cp2.1);
 
             if let Some((stroke_color, stroke_thickness)) = path.stroke {
-                push3(&mut self.stroke_colors, stroke_color);
-                self.stroke_edges.push(gl!(0));
-                self.stroke_edges.push(gl!(0));
-                self.stroke_edges.push(gl!(stroke_thickness));
+                push_color(&mut geometry.stroke_colors, stroke_color, path.stroke_alpha());
+                geometry.stroke_edges.push(gl!(0));
+                geometry.stroke_edges.push(gl!(0));
+                geometry.stroke_edges.push(gl!(stroke_thickness));
             } else {
                 unreachable!()
             }
         }
-        Ok(())
+        Ok(geometry)
     }
+}
 
-    /// Make this drawings render context the current one for the window.
-    pub fn make_current(&self) {
-        self.window.set_context();
-    }
+/// Manages everything under the hood. Paths are added to the drawing and then drawn.
+pub struct Drawing<'a, W: Window + 'a> {
+    window: &'a W,
+    window_size: [GLfloat; 2],
 
-    /// Clear all paths in a drawing so the drawing can be reused.
-    pub fn clear_paths(&mut self) {
-        self.vertices.clear();
-        self.control_point_1s.clear();
-        self.control_point_2s.clear();
-        self.fill_colors.clear();
-        self.stroke_colors.clear();
-        self.stroke_edges.clear();
-        self.do_fill.clear();
-        self.depth_idx = 0;
-        self.num_tris = 0;
-        self.remake = true;
-    }
+    paths: BTreeMap<PathId, PathEntry>,
+    next_path_id: usize,
 
-    /// Draw all the paths.
-    pub fn draw(&mut self) {
-        unsafe {
-            if self.remake {
-                // Populate the position buffer
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.position_vbo);
-                gl::BufferData(gl::ARRAY_BUFFER,
-                    (self.vertices.len() * mem::size_of::<GLfloat> ()) as GLsizeiptr,
-                    mem::transmute(&self.vertices[0]),
-                    gl::STATIC_DRAW);
-
-                // Populate the control points buffers
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.control_1_vbo);
-                gl::BufferData(gl::ARRAY_BUFFER,
-                    (self.control_point_1s.len() * mem::size_of::<GLfloat> ()) as GLsizeiptr,
-                    mem::transmute(&self.control_point_1s[0]),
-                    gl::STATIC_DRAW);
-
-                // Populate the control points buffers
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.control_2_vbo);
-                gl::BufferData(gl::ARRAY_BUFFER,
-                    (self.control_point_2s.len() * mem::size_of::<GLfloat> ()) as GLsizeiptr,
-                    mem::transmute(&self.control_point_2s[0]),
-                    gl::STATIC_DRAW);
-
-                // Populate color buffer
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.color_vbo);
-                gl::BufferData(gl::ARRAY_BUFFER,
-                    (self.fill_colors.len() * mem::size_of::<GLfloat> ()) as GLsizeiptr,
-                    mem::transmute(&self.fill_colors[0]),
-                    gl::STATIC_DRAW);
-
-                // Populate the edge buffer
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.edge_vbo);
-                gl::BufferData(gl::ARRAY_BUFFER,
-                    (self.stroke_edges.len() * mem::size_of::<GLfloat> ()) as GLsizeiptr,
-                    mem::transmute(&self.stroke_edges[0]),
-                    gl::STATIC_DRAW);
-
-                // populate the stroke color buffer
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.stroke_color_vbo);
-                gl::BufferData(gl::ARRAY_BUFFER,
-                              (self.stroke_colors.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                               mem::transmute(&self.stroke_colors[0]),
-                               gl::STATIC_DRAW);
-
-                // populate the do fill buffer
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.do_fill_vbo);
-                gl::BufferData(gl::ARRAY_BUFFER,
-                               (self.do_fill.len() * mem::size_of::<GLint>()) as GLsizeiptr,
-                               mem::transmute(&self.do_fill[0]),
-                               gl::STATIC_DRAW);
+    // the vertex data as it was last uploaded, with the vertex range of each path in buffer order
+    arrays: GeometrySnapshot,
 
-                gl::PatchParameteri(gl::PATCH_VERTICES, 3);
+    buffers: VertexBuffers,
+    shader_program: shader::ShaderProgram,
 
-                // Create and set-up the vertex array object
-                gl::GenVertexArrays(1, &mut self.vao_handle);
-                gl::BindVertexArray(self.vao_handle);
-
-                // Enable the vertex attribute arrays
-                gl::EnableVertexAttribArray(0 as GLuint); // position
-                gl::EnableVertexAttribArray(1 as GLuint); // control point 1
-                gl::EnableVertexAttribArray(2 as GLuint); // control point 2
-                gl::EnableVertexAttribArray(3 as GLuint); // color
-                gl::EnableVertexAttribArray(4 as GLuint); // edge
-                gl::EnableVertexAttribArray(5 as GLuint); // stroke color
-                gl::EnableVertexAttribArray(6 as GLuint); // do fill
-
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.position_vbo);
-                gl::VertexAttribPointer(self.in_position as GLuint, 3, gl::FLOAT,
-                                        gl::FALSE as GLboolean, 0, ptr::null());
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.control_1_vbo);
-                gl::VertexAttribPointer(self.in_control_1 as GLuint, 2, gl::FLOAT,
-                                        gl::FALSE as GLboolean, 0, ptr::null());
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.control_2_vbo);
-                gl::VertexAttribPointer(self.in_control_2 as GLuint, 2, gl::FLOAT,
-                                        gl::FALSE as GLboolean, 0, ptr::null());
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.color_vbo);
-                gl::VertexAttribPointer(self.in_color as GLuint, 3, gl::FLOAT,
-                                        gl::FALSE as GLboolean, 0, ptr::null());
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.edge_vbo);
-                gl::VertexAttribPointer(self.in_edge as GLuint, 1, gl::FLOAT,
-                                        gl::FALSE as GLboolean, 0, ptr::null());
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.stroke_color_vbo);
-                gl::VertexAttribPointer(self.in_stroke_color as GLuint, 3, gl::FLOAT,
-                                        gl::FALSE as GLboolean, 0, ptr::null());
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.do_fill_vbo);
-                gl::VertexAttribPointer(self.in_do_fill as GLuint, 1, gl::INT,
-                                        gl::FALSE as GLboolean, 0, ptr::null());
+    outer_tess_uniform: GLint,
+    inner_tess_uniform: GLint,
+    lod_tolerance_uniform: GLint,
+    projection_uniform: GLint,
+    window_size_uniform: GLint,
+    srgb_uniform: GLint,
+    premultiplied_uniform: GLint,
+    stroke_depth_offset_uniform: GLint,
+    crisp_uniform: GLint,
+    world_scale_uniform: GLint,
+    fill_patterns_uniform: GLint,
+    pattern_buffer: Option<PatternBuffer>,
+    // by slot, None once destroyed
+    gradients: Vec<Option<Gradient>>,
+    // the gradients' slots, uploaded again when a gradient changes
+    gradient_buffer: Option<PatternBuffer>,
+    gradients_changed: bool,
+    gradients_uniform: GLint,
 
-                let program_id = self.shader_program.get_program_id();
-                let c_str = CString::new("outer_tess".as_bytes()).unwrap();
-                self.outer_tess_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
-                let c_str = CString::new("inner_tess".as_bytes()).unwrap();
-                self.inner_tess_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
-                let c_str = CString::new("projection".as_bytes()).unwrap();
-                self.projection_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
-                let c_str = CString::new("window_size".as_bytes()).unwrap();
-                self.window_size_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+    camera: Camera,
+    coordinates: CoordinateSystem,
+    // the bars around a view of fixed logical size
+    letterbox_color: Color,
+    projection_mode: Projection,
+    camera_controls: bool,
+    max_tessellation: u32,
+    lod_tolerance: Option<f32>,
+    sort_mode: SortMode,
+    layers: Vec<Layer>,
+    next_layer_id: usize,
+    // where each layer's patches are in the buffers, in the order of `layers`
+    layer_ranges: Vec<PatchRange>,
+    buffer_version: u64,
+    pick_pass: Option<PickPass>,
+    // the selection highlights and overlays, drawn after all layers
+    overlay_range: PatchRange,
+    compositor: Option<LayerCompositor>,
+    tiles: Option<TileIndex>,
+    // path bounds, for finding paths by position
+    rtree: RTree<PathId>,
+    // how many paths have strokes of each thickness in pixels and in drawing units, the thickest
+    // of each is how far outside their bounds paths can be hit
+    stroke_thicknesses: BTreeMap<u32, usize>,
+    world_stroke_thicknesses: BTreeMap<u32, usize>,
+    gpu_budget: Option<usize>,
+    diagnostics: bool,
+    // bytes of vertex data of all paths, and of what was uploaded last
+    geometry_bytes: usize,
+    uploaded_bytes: usize,
+    frame_stats: FrameStats,
+    // the tiles whose paths are in the buffers
+    loaded_tiles: Option<TileRange>,
+    // paths plus selection highlights, each gets its own depth
+    depth_layers: usize,
+    hovered: Option<PathId>,
+    pressed: Option<(PathId, MouseButton)>,
+    dragging: Option<(f32, f32)>,
+    selection: BTreeMap<PathId, SelectionStyle>,
+    // paths left out when drawing, whose geometry stays in the buffers
+    hidden: BTreeSet<PathId>,
+    overlay: Option<Overlay>,
+    debug_view: DebugView,
+    wireframe: bool,
+    show_bounds: bool,
+    user_data: BTreeMap<PathId, Box<dyn Any>>,
+    tags: BTreeMap<PathId, BTreeSet<String>>,
 
-                gl::UseProgram(self.shader_program.get_program_id());
+    background: Background,
+    background_renderer: Option<BackgroundRenderer>,
+    // where draw_embedded keeps the host's depth values while it draws
+    embedded_depth: Option<DepthCopy>,
+    // the opacity the background is drawn over the last frame with when trails are on
+    trail: Option<f32>,
+    clock: AnimationClock,
+    // numbers the handles of the resources the drawing makes
+    namespace: usize,
+    time_uniform: GLint,
+    frame_index_uniform: GLint,
+    baked: BTreeMap<TextureHandle, Baked>,
+    next_baked: usize,
+    // textures being decoded on threads of their own, shown as placeholders until poll_textures
+    #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+    loading: Vec<(TextureHandle, Receiver<Result<ImageData, TrdlError>>)>,
+    baked_renderer: Option<BakedRenderer>,
+    srgb: bool,
+    premultiplied: bool,
+    crisp: bool,
 
-                if self.outer_tess_uniform >= 0 {
-                    gl::Uniform1i(self.outer_tess_uniform, 32);
-                }
+    // the GL context was lost, everything on the GPU is recreated before the next draw
+    gl_lost: bool,
+    remake: bool
+}
 
-                if self.inner_tess_uniform >= 0 {
-                    gl::Uniform1i(self.inner_tess_uniform, 1);
-                }
+impl<'a, W: Window> Drawing<'a, W> {
+    /// Constructor, a window, window size and background color.
+    pub fn new(window: &'a W, width: u32, height: u32, bg_red: f32, bg_green: f32, bg_blue: f32) ->
+            Result<Drawing<'a, W>, TrdlError> {
+        window.set_context();
+        gl::load_with(|symbol| window.load_fn(symbol));
 
-                if self.projection_uniform >= 0 {
-                    gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean,
-                                         mem::transmute(&self.ortho_proj[0]));
-                }
+        let program = load_program()?;
+        let buffers = VertexBuffers::new(&Gl, program.get_program_id());
 
-                if self.window_size_uniform >= 0 {
-                    gl::Uniform2fv(self.window_size_uniform, 1,
-                                  mem::transmute(&self.window_size[0]));
-                }
+        Ok(Drawing {
+            window,
+            window_size: [gl!(width), gl!(height)],
 
-                gl::Enable(gl::DEPTH_TEST);
+            paths: BTreeMap::new(),
+            next_path_id: 0,
 
-                gl::ClearColor(self.background_color[0], self.background_color[1], self.background_color[2], 1.0);
+            arrays: GeometrySnapshot::default(),
 
-                self.remake = false;
-            }
+            buffers,
+            shader_program: program,
 
-            // Clear the screen
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            outer_tess_uniform: -1,
+            inner_tess_uniform: -1,
+            lod_tolerance_uniform: -1,
+            projection_uniform: -1,
+            window_size_uniform: -1,
+            srgb_uniform: -1,
+            premultiplied_uniform: -1,
+            stroke_depth_offset_uniform: -1,
+            crisp_uniform: -1,
+            world_scale_uniform: -1,
+            fill_patterns_uniform: -1,
+            pattern_buffer: None,
+            gradients: Vec::new(),
+            gradient_buffer: None,
+            gradients_changed: false,
+            gradients_uniform: -1,
 
-            gl::BindVertexArray(self.vao_handle);
-            gl::DrawArrays(gl::PATCHES, 0, self.vertices.len() as GLint);
-        }
-    }
+            camera: Camera::new(),
+            coordinates: CoordinateSystem::new(),
+            letterbox_color: Color::rgb(0f32, 0f32, 0f32),
+            projection_mode: Projection::Orthographic2D,
+            camera_controls: false,
+            max_tessellation: DEFAULT_MAX_TESSELLATION,
+            lod_tolerance: Some(DEFAULT_LOD_TOLERANCE),
+            sort_mode: SortMode::OpaqueFirst,
+            selection: BTreeMap::new(),
+            hidden: BTreeSet::new(),
+            overlay: None,
+            debug_view: DebugView::Off,
+            wireframe: false,
+            show_bounds: false,
+            user_data: BTreeMap::new(),
+            tags: BTreeMap::new(),
+            layers: vec![Layer::new(LayerId(0))],
+            next_layer_id: 1,
+            layer_ranges: Vec::new(),
+            buffer_version: 0,
+            pick_pass: None,
+            overlay_range: PatchRange::default(),
+            compositor: None,
+            tiles: None,
+            rtree: RTree::new(),
+            stroke_thicknesses: BTreeMap::new(),
+            world_stroke_thicknesses: BTreeMap::new(),
+            gpu_budget: None,
+            diagnostics: false,
+            geometry_bytes: 0,
+            uploaded_bytes: 0,
+            frame_stats: FrameStats::default(),
+            loaded_tiles: None,
+            depth_layers: 0,
+            hovered: None,
+            pressed: None,
+            dragging: None,
 
-    /// Set new window size.
-    pub fn set_size(&mut self, width: u32, height: u32) {
-        self.ortho_proj = Self::ortho(width, height);
-        self.remake = true;
-        self.window_size = [gl!(width), gl!(height)];
+            background: Background::Solid(Color::rgb(bg_red, bg_green, bg_blue)),
+            background_renderer: None,
+            embedded_depth: None,
+            trail: None,
+            clock: AnimationClock::new(),
+            namespace: resource::new_namespace(),
+            time_uniform: -1,
+            frame_index_uniform: -1,
+            baked: BTreeMap::new(),
+            next_baked: 0,
+            #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+            loading: Vec::new(),
+            baked_renderer: None,
+            srgb: false,
+            premultiplied: true,
+            crisp: false,
+
+            gl_lost: false,
+            remake: true
+        })
     }
 
-    // orthographic projection based on the window size, maps pixels to OpenGL normalized coords.
-    fn ortho(width: u32, height: u32) -> [GLfloat; 16] {
-        [
-            TWO / gl!(width),  ZERO,              ZERO, ZERO,
-            ZERO,              TWO / gl!(height), ZERO, ZERO,
-            ZERO,              ZERO,              ONE,  ZERO,
-            -ONE,             -ONE,               ZERO, ONE
-        ]
+    /// Add a path to the base layer of the drawing, it is drawn on top of the paths added to the
+    /// layer before it. A drawing holds at most 2097152 (2^21) paths, past that this returns
+    /// `TooManyPaths`.
+    pub fn add_path(&mut self, path: Path) -> Result<PathId, TrdlError> {
+        let base = self.base_layer();
+        self.add_path_to_layer(base, path)
     }
-}
 
-impl<'a, W: Window> Drop for Drawing<'a, W> {
-    /// Clean up all OpenGL stuff on drop.
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteBuffers(1, &self.position_vbo);
-            gl::DeleteBuffers(1, &self.control_1_vbo);
-            gl::DeleteBuffers(1, &self.control_2_vbo);
-            gl::DeleteBuffers(1, &self.color_vbo);
-            gl::DeleteBuffers(1, &self.edge_vbo);
-            gl::DeleteBuffers(1, &self.stroke_color_vbo);
-            gl::DeleteBuffers(1, &self.do_fill_vbo);
-            gl::DeleteVertexArrays(1, &self.vao_handle);
+    /// Add a path to a layer, on top of the paths added to the layer before it.
+    pub fn add_path_to_layer(&mut self, layer: LayerId, path: Path) -> Result<PathId, TrdlError> {
+        if self.layer_index(layer).is_none() {
+            return Err(TrdlError::NoSuchLayer);
+        }
+        if self.paths.len() >= MAX_PATHS {
+            return Err(TrdlError::TooManyPaths);
+        }
+        let id = PathId(self.next_path_id);
+        self.insert_entry(id, PathEntry::new(path, layer, self.diagnostics)?)?;
+        self.next_path_id += 1;
+        Ok(id)
+    }
+
+    /// Add copies of stamp every spacing along path to the base layer, see `Path::stamps_along`.
+    /// If one can't be added, none are.
+    pub fn stamp_along(&mut self, path: &Path, stamp: &Path, spacing: f32, align_to_tangent: bool)
+            -> Result<Vec<PathId>, TrdlError> {
+        let mut ids = Vec::new();
+        for copy in path.stamps_along(stamp, spacing, align_to_tangent) {
+            match self.add_path(copy) {
+                Ok(id) => ids.push(id),
+                Err(err) => {
+                    for id in ids {
+                        self.remove_path(id);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Remove a path from the drawing, returning it if it was there.
+    pub fn remove_path(&mut self, id: PathId) -> Option<Path> {
+        self.remove_entry(id).map(|entry| entry.path)
+    }
+
+    /// Replace a path, keeping its id and place in the stacking order. Returns the old path, or
+    /// None without adding anything if the id is not in the drawing.
+    pub fn update_path(&mut self, id: PathId, path: Path) -> Result<Option<Path>, TrdlError> {
+        if !self.paths.contains_key(&id) {
+            return Ok(None);
+        }
+        let layer = self.paths[&id].layer;
+        let old = self.insert_entry(id, PathEntry::new(path, layer, self.diagnostics)?)?;
+        Ok(old.map(|entry| entry.path))
+    }
+
+    // Put a removed path back under its old id, which also puts it back in its old place in the
+    // stacking order. It goes to the base layer if its layer is gone.
+    pub(crate) fn restore_path(&mut self, id: PathId, layer: LayerId, path: Path) -> Result<(), TrdlError> {
+        if self.paths.len() >= MAX_PATHS {
+            return Err(TrdlError::TooManyPaths);
+        }
+        let layer = if self.layer_index(layer).is_some() { layer } else { self.base_layer() };
+        self.insert_entry(id, PathEntry::new(path, layer, self.diagnostics)?)?;
+        Ok(())
+    }
+
+    // Add or replace a path, keeping the tile index and GPU memory count up to date. Fails
+    // without changing anything if the path would take the drawing over its GPU budget.
+    fn insert_entry(&mut self, id: PathId, entry: PathEntry) -> Result<Option<PathEntry>, TrdlError> {
+        let old_bytes = self.paths.get(&id).map_or(0, |old| old.geometry.byte_size());
+        let bytes = self.geometry_bytes - old_bytes + entry.geometry.byte_size();
+        if self.gpu_budget.is_some_and(|budget| bytes > budget) {
+            return Err(TrdlError::OutOfGpuBudget);
+        }
+        self.geometry_bytes = bytes;
+        let layer = entry.layer;
+        self.count_stroke(&entry.path, true);
+        let old = self.paths.insert(id, entry);
+        if let Some(ref old) = old {
+            self.count_stroke(&old.path, false);
+        }
+        self.index_path(id);
+        self.layer_changed(layer);
+        Ok(old)
+    }
+
+    // Remove a path along with everything that refers to it.
+    fn remove_entry(&mut self, id: PathId) -> Option<PathEntry> {
+        let entry = self.paths.remove(&id)?;
+        self.geometry_bytes -= entry.geometry.byte_size();
+        self.count_stroke(&entry.path, false);
+        self.selection.remove(&id);
+        self.hidden.remove(&id);
+        self.user_data.remove(&id);
+        self.tags.remove(&id);
+        self.rtree.remove(id);
+        if let Some(ref mut tiles) = self.tiles {
+            tiles.remove(id);
+        }
+        self.layer_changed(entry.layer);
+        Some(entry)
+    }
+
+    /// Limit the GPU memory the drawing's vertex buffers may take, in bytes, or lift the limit
+    /// with None (the default). Adding or changing a path that would go over the budget fails
+    /// with `OutOfGpuBudget`. Setting a budget below what is already used keeps the paths but
+    /// refuses new ones until enough are removed.
+    pub fn set_gpu_budget(&mut self, bytes: Option<usize>) {
+        self.gpu_budget = bytes;
+    }
+
+    /// Turn diagnostics on or off (the default). With them on, paths that can't be tessellated
+    /// fail with `TessellationDiagnostics` in `TrdlError::Tessellation` rather than a bare
+    /// `NonSimplePolygon` and the like, telling which vertices are at fault and how to fix them.
+    /// Finding out takes time, so leave them off outside of development.
+    pub fn set_diagnostics(&mut self, on: bool) {
+        self.diagnostics = on;
+    }
+
+    /// Whether diagnostics are on.
+    pub fn diagnostics(&self) -> bool {
+        self.diagnostics
+    }
+
+    /// The GPU memory needed for the vertex buffers of all the paths, in bytes. This is what the
+    /// budget is checked against.
+    pub fn gpu_memory_needed(&self) -> usize {
+        self.geometry_bytes
+    }
+
+    /// The GPU memory in the vertex buffers as of the last draw, in bytes. The buffers are
+    /// uploaded whole whenever paths change, so removed paths never take space; with tiling on
+    /// only the paths near the view are uploaded.
+    pub fn gpu_memory_used(&self) -> usize {
+        self.uploaded_bytes
+    }
+
+    /// The vertex arrays the next draw would upload, built the same way but without touching
+    /// OpenGL, so tests can check what a drawing renders. Depths, draw order, overlays and tiling
+    /// are all applied as they would be.
+    pub fn snapshot_geometry(&mut self) -> GeometrySnapshot {
+        self.update_loaded_tiles();
+        if self.remake {
+            // remake stays set, the next draw still uploads them
+            self.rebuild_arrays();
+        }
+        self.arrays.clone()
+    }
+
+    /// What the last `draw` or `draw_embedded` drew, for profiling. All zero before the first.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// The layer a path is in.
+    pub fn layer_of(&self, id: PathId) -> Option<LayerId> {
+        self.paths.get(&id).map(|entry| entry.layer)
+    }
+
+    /// The layer every drawing starts with, lowest of all, which `add_path` adds to.
+    pub fn base_layer(&self) -> LayerId {
+        self.layers[0].id
+    }
+
+    /// Add an empty layer on top of all the others.
+    pub fn add_layer(&mut self) -> LayerId {
+        let id = LayerId(self.next_layer_id);
+        self.next_layer_id += 1;
+        self.layers.push(Layer::new(id));
+        self.remake = true;
+        id
+    }
+
+    /// Remove a layer along with its paths. The base layer cannot be removed, this returns false
+    /// for it and for layers that are not in the drawing.
+    pub fn remove_layer(&mut self, layer: LayerId) -> bool {
+        match self.layer_index(layer) {
+            Some(index) if index > 0 => {
+                self.layers.remove(index);
+                let removed: Vec<PathId> = self.paths.iter()
+                    .filter(|&(_, entry)| entry.layer == layer)
+                    .map(|(&id, _)| id)
+                    .collect();
+                for id in removed {
+                    self.remove_entry(id);
+                }
+                self.remake = true;
+                true
+            }
+            _ => false
+        }
+    }
+
+    /// The layers, bottom to top.
+    pub fn layers(&self) -> Vec<LayerId> {
+        self.layers.iter().map(|layer| layer.id).collect()
+    }
+
+    /// Show or hide a layer. Hidden layers keep their paths but are not drawn or picked.
+    pub fn set_layer_visible(&mut self, layer: LayerId, visible: bool) {
+        if let Some(index) = self.layer_index(layer) {
+            self.layers[index].visible = visible;
+            // selection highlights of paths in hidden layers are left out
+            self.remake = true;
+        }
+    }
+
+    /// Whether a layer is shown, false for layers not in the drawing.
+    pub fn layer_visible(&self, layer: LayerId) -> bool {
+        self.layer_index(layer).is_some_and(|index| self.layers[index].visible)
+    }
+
+    /// Fade a whole layer, from 0 (invisible) to 1 (as drawn, the default). A faded layer is
+    /// drawn into a texture first, so its paths fade as one image.
+    pub fn set_layer_opacity(&mut self, layer: LayerId, opacity: f32) {
+        if let Some(index) = self.layer_index(layer) {
+            self.layers[index].opacity = opacity.clamp(0f32, 1f32);
+        }
+    }
+
+    /// Keep a layer's image in a texture between frames and redraw it only when its paths, the
+    /// camera or the window size change. Worth it for big layers that rarely change under ones
+    /// that change every frame.
+    pub fn set_layer_cached(&mut self, layer: LayerId, cached: bool) {
+        if let Some(index) = self.layer_index(layer) {
+            self.layers[index].cached = cached;
+        }
+    }
+
+    /// Draw a cached or faded layer's texture with this many samples per pixel, so its curved
+    /// edges keep the smoothness of a multisampled window. 0 or 1 (the default) turns
+    /// multisampling off, counts above what the driver supports are lowered to its maximum.
+    pub fn set_layer_samples(&mut self, layer: LayerId, samples: u32) {
+        if let Some(index) = self.layer_index(layer) {
+            self.layers[index].samples = samples;
+        }
+    }
+
+    /// The samples per pixel asked for a layer's texture, 0 for layers not in the drawing.
+    pub fn layer_samples(&self, layer: LayerId) -> u32 {
+        self.layer_index(layer).map_or(0, |index| self.layers[index].samples)
+    }
+
+    /// Split the drawing plane into square tiles of `size` drawing units and only send the paths
+    /// in tiles near the view to the GPU, or stop tiling with None (the default). For very large
+    /// drawings, such as maps, of which only a small part is in view at a time. The paths are
+    /// reloaded when the view moves more than a tile or so, so tiles should be a fair fraction of
+    /// the view.
+    pub fn set_tile_size(&mut self, size: Option<f32>) {
+        self.tiles = match size {
+            Some(size) if size > 0f32 && size.is_finite() => {
+                let mut tiles = TileIndex::new(size);
+                for (&id, entry) in &self.paths {
+                    let (min, max) = entry.bounds;
+                    tiles.insert(id, min, max);
+                }
+                Some(tiles)
+            }
+            _ => None
+        };
+        self.loaded_tiles = None;
+        self.remake = true;
+    }
+
+    // Put a path in the R-tree and tile index after it was added or changed.
+    fn index_path(&mut self, id: PathId) {
+        if let Some(entry) = self.paths.get(&id) {
+            let (min, max) = entry.bounds;
+            self.rtree.insert(id, entry.bounds);
+            if let Some(ref mut tiles) = self.tiles {
+                tiles.insert(id, min, max);
+            }
+        }
+    }
+
+    // Count a path's stroke in or out of the thicknesses of the drawing's strokes.
+    fn count_stroke(&mut self, path: &Path, added: bool) {
+        let thickness = match path.stroke {
+            Some((_, thickness)) => thickness,
+            None => return
+        };
+        let counts = match path.stroke_units {
+            StrokeUnits::Pixels => &mut self.stroke_thicknesses,
+            StrokeUnits::World => &mut self.world_stroke_thicknesses
+        };
+        if added {
+            *counts.entry(thickness).or_insert(0) += 1;
+        } else if let Some(count) = counts.get_mut(&thickness) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&thickness);
+            }
+        }
+    }
+
+    // With tiling on, load the paths around the view if it moved out of the loaded tiles. A tile
+    // of margin is loaded all around so small pans do not reload.
+    fn update_loaded_tiles(&mut self) {
+        if let Some(ref tiles) = self.tiles {
+            // the window's corners, which a projection may turn in the drawing
+            let (w, h) = (self.window_size[0], self.window_size[1]);
+            let corners = [(0f32, 0f32), (w, 0f32), (0f32, h), (w, h)].map(|p| self.window_to_drawing(p));
+            let min = corners.iter().fold(corners[0], |m, p| (m.0.min(p.0), m.1.min(p.1)));
+            let max = corners.iter().fold(corners[0], |m, p| (m.0.max(p.0), m.1.max(p.1)));
+            // strokes can reach a little past the bounds of their paths
+            let needed = TileRange::covering(min, max, tiles.size()).expanded(1);
+            if self.loaded_tiles.is_none_or(|loaded| !loaded.contains(&needed)) {
+                self.loaded_tiles = Some(needed.expanded(1));
+                self.remake = true;
+            }
+        }
+    }
+
+    // Something that changes how every path looks changed, redraw all cached layers.
+    fn invalidate_layer_caches(&mut self) {
+        self.expire_layer_caches();
+        self.remake = true;
+    }
+
+    // Draw the cached layers again on the next draw, leaving the vertex buffers as they are.
+    fn expire_layer_caches(&mut self) {
+        for layer in &mut self.layers {
+            layer.version += 1;
+        }
+    }
+
+    fn layer_index(&self, layer: LayerId) -> Option<usize> {
+        self.layers.iter().position(|l| l.id == layer)
+    }
+
+    // A path in the layer was added, removed or changed.
+    fn layer_changed(&mut self, layer: LayerId) {
+        if let Some(index) = self.layer_index(layer) {
+            self.layers[index].version += 1;
+        }
+        self.remake = true;
+    }
+
+    /// Recolor the paths tagged with `Theme::fill_tag` or `Theme::stroke_tag` of a role with the
+    /// role's color in a variant of the theme. Only the rgb of the color is used, the paths keep
+    /// their opacity, and paths without a fill or stroke are not given one. The colors are changed
+    /// in place, so paths are not tessellated again. Returns the number of paths recolored.
+    pub fn apply_theme(&mut self, theme: &Theme, variant: ThemeVariant) -> usize {
+        let changes: Vec<(PathId, Option<Color>, Option<Color>)> = self.tags.iter()
+            .map(|(&id, tags)| {
+                let (fill, stroke) = theme.colors_for(variant, tags.iter().map(String::as_str));
+                (id, fill, stroke)
+            })
+            .filter(|&(_, fill, stroke)| fill.is_some() || stroke.is_some())
+            .collect();
+        let rgb = |color: Color| [color.red, color.green, color.blue];
+        let mut recolored = 0;
+        for (id, fill, stroke) in changes {
+            let entry = match self.paths.get_mut(&id) {
+                Some(entry) => entry,
+                None => continue
+            };
+            let path = &entry.path;
+            let fill = fill.map(rgb).filter(|&fill| path.fill_color.is_some_and(|old| old != fill));
+            let stroke = stroke.map(rgb).filter(|&stroke| path.stroke.is_some_and(|(old, _)| old != stroke));
+            if fill.is_none() && stroke.is_none() {
+                continue;
+            }
+            let fill_color = fill.or(path.fill_color);
+            let path_stroke = path.stroke.map(|(old, thickness)| (stroke.unwrap_or(old), thickness));
+            if path.has_flat_fill() && path.stroke_style == StrokeStyle::Solid {
+                entry.geometry.recolor(fill, stroke);
+            } else {
+                // vertex colors and stroke styles are mixed in with the colors, so start over, and
+                // leave the path as it is if it can't be tessellated with the new colors
+                let recolored_path = Path { fill_color, stroke: path_stroke, ..path.clone() };
+                match PathGeometry::new(&recolored_path) {
+                    Ok(geometry) => entry.geometry = geometry,
+                    Err(_) => continue
+                }
+            }
+            entry.path.fill_color = fill_color;
+            entry.path.stroke = path_stroke;
+            let layer = entry.layer;
+            self.layer_changed(layer);
+            recolored += 1;
+        }
+        recolored
+    }
+
+    /// Show or hide a path. A hidden path keeps its place in the drawing and its geometry on the
+    /// GPU, it is only skipped when drawing and picking, so toggling it is much cheaper than
+    /// removing and adding it again. Does nothing if the path is not in the drawing.
+    pub fn set_visible(&mut self, id: PathId, visible: bool) {
+        let layer = match self.paths.get(&id) {
+            Some(entry) => entry.layer,
+            None => return
+        };
+        let changed = if visible { self.hidden.remove(&id) } else { self.hidden.insert(id) };
+        if !changed {
+            return;
+        }
+        // a cached layer is drawn again, but the buffers stay as they are
+        if let Some(index) = self.layer_index(layer) {
+            self.layers[index].version += 1;
+        }
+        if self.selection.contains_key(&id) {
+            self.remake = true;
+        }
+    }
+
+    /// Whether a path is shown, false if it is hidden or not in the drawing.
+    pub fn is_visible(&self, id: PathId) -> bool {
+        self.paths.contains_key(&id) && !self.hidden.contains(&id)
+    }
+
+    /// Get a path that was added to the drawing.
+    pub fn get_path(&self, id: PathId) -> Option<&Path> {
+        self.paths.get(&id).map(|entry| &entry.path)
+    }
+
+    /// Attach a value of any type to a path, replacing what was attached before, so it can be
+    /// looked up from a picked id. It stays with the path through `update_path` and goes when the
+    /// path is removed. Returns false if the path is not in the drawing.
+    pub fn set_user_data<T: Any>(&mut self, id: PathId, data: T) -> bool {
+        if !self.paths.contains_key(&id) {
+            return false;
+        }
+        self.user_data.insert(id, Box::new(data));
+        true
+    }
+
+    /// The value attached to a path, None if there is none or it is not a T.
+    pub fn user_data<T: Any>(&self, id: PathId) -> Option<&T> {
+        self.user_data.get(&id).and_then(|data| data.downcast_ref())
+    }
+
+    /// The value attached to a path, to change in place.
+    pub fn user_data_mut<T: Any>(&mut self, id: PathId) -> Option<&mut T> {
+        self.user_data.get_mut(&id).and_then(|data| data.downcast_mut())
+    }
+
+    /// Detach a path's value and return it.
+    pub fn remove_user_data(&mut self, id: PathId) -> Option<Box<dyn Any>> {
+        self.user_data.remove(&id)
+    }
+
+    /// The paths with a T attached, with their values, in the order the paths were added.
+    pub fn paths_with_user_data<T: Any>(&self) -> impl Iterator<Item = (PathId, &T)> {
+        self.user_data.iter().filter_map(|(&id, data)| data.downcast_ref().map(|data| (id, data)))
+    }
+
+    /// Tag a path, e.g. with what kind of thing it draws. Returns false if the path is not in the
+    /// drawing.
+    pub fn add_tag(&mut self, id: PathId, tag: &str) -> bool {
+        if !self.paths.contains_key(&id) {
+            return false;
+        }
+        self.tags.entry(id).or_default().insert(tag.to_string());
+        true
+    }
+
+    /// Take a tag off a path, returns whether it had it.
+    pub fn remove_tag(&mut self, id: PathId, tag: &str) -> bool {
+        let removed = self.tags.get_mut(&id).is_some_and(|tags| tags.remove(tag));
+        if self.tags.get(&id).is_some_and(|tags| tags.is_empty()) {
+            self.tags.remove(&id);
+        }
+        removed
+    }
+
+    /// Whether a path has a tag.
+    pub fn has_tag(&self, id: PathId, tag: &str) -> bool {
+        self.tags.get(&id).is_some_and(|tags| tags.contains(tag))
+    }
+
+    /// The tags of a path, in alphabetical order.
+    pub fn tags(&self, id: PathId) -> Vec<&str> {
+        self.tags.get(&id).map_or(Vec::new(), |tags| tags.iter().map(String::as_str).collect())
+    }
+
+    /// The paths with a tag, in the order they were added.
+    pub fn paths_with_tag(&self, tag: &str) -> Vec<PathId> {
+        self.tags.iter().filter(|&(_, tags)| tags.contains(tag)).map(|(&id, _)| id).collect()
+    }
+
+    /// The paths with their ids, in the order they were added.
+    pub fn paths(&self) -> impl Iterator<Item = (PathId, &Path)> {
+        self.paths.iter().map(|(&id, entry)| (id, &entry.path))
+    }
+
+    /// The paths whose bounds overlap a rectangle given as (min, max) in drawing coordinates, in
+    /// the order they were added. For viewport queries, and for selecting by rectangle along
+    /// with `paths_inside_rect`. Paths in hidden layers are included.
+    pub fn paths_in_rect(&self, rect: ((f32, f32), (f32, f32))) -> Vec<PathId> {
+        self.query_rect(rect, overlaps)
+    }
+
+    /// The paths whose bounds lie wholly inside a rectangle, in the order they were added.
+    pub fn paths_inside_rect(&self, rect: ((f32, f32), (f32, f32))) -> Vec<PathId> {
+        self.query_rect(rect, contains_rect)
+    }
+
+    fn query_rect<F>(&self, rect: ((f32, f32), (f32, f32)), test: F) -> Vec<PathId>
+            where F: Fn(((f32, f32), (f32, f32)), ((f32, f32), (f32, f32))) -> bool {
+        let mut ids: Vec<PathId> = self.rtree.query(rect).into_iter()
+            .filter(|id| self.paths.get(id).is_some_and(|entry| test(rect, entry.bounds)))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// The path whose outline is nearest a point in drawing coordinates, with the distance to
+    /// it, or None for an empty drawing. Paths in hidden layers are included.
+    pub fn nearest_path(&self, point: (f32, f32)) -> Option<(PathId, f32)> {
+        self.rtree.nearest(point, f32::INFINITY, |id| {
+            let nearest = self.paths[&id].path.nearest_point(point);
+            (nearest.0 - point.0).hypot(nearest.1 - point.1)
+        })
+    }
+
+    /// The paths whose style passes a test, in the order they were added, e.g. every path
+    /// stroked in red.
+    pub fn paths_matching<F: FnMut(&PathStyle) -> bool>(&self, mut test: F) -> Vec<PathId> {
+        self.paths.iter().filter(|&(_, entry)| test(&entry.path.style())).map(|(&id, _)| id).collect()
+    }
+
+    /// Make this drawings render context the current one for the window.
+    pub fn make_current(&self) {
+        self.window.set_context();
+    }
+
+    /// Clear all paths in a drawing so the drawing can be reused.
+    pub fn clear_paths(&mut self) {
+        self.paths.clear();
+        self.geometry_bytes = 0;
+        self.selection.clear();
+        self.hidden.clear();
+        self.user_data.clear();
+        self.tags.clear();
+        self.rtree.clear();
+        self.stroke_thicknesses.clear();
+        self.world_stroke_thicknesses.clear();
+        if let Some(ref mut tiles) = self.tiles {
+            tiles.clear();
+        }
+        self.hovered = None;
+        self.pressed = None;
+        self.invalidate_layer_caches();
+    }
+
+    /// The topmost path under a point in drawing coordinates, if any. Fills are hit inside the
+    /// path, strokes where they are drawn (their thickness is in window pixels). Hidden paths and
+    /// paths in hidden layers are skipped.
+    pub fn pick(&self, point: (f32, f32)) -> Option<PathId> {
+        let pixel_size = self.pixel_size();
+        // strokes reach outside their path's bounds by up to their thickness
+        let thickest = |counts: &BTreeMap<u32, usize>| counts.keys().next_back().map_or(0f32, |&thickness| thickness as f32);
+        let margin = thickest(&self.stroke_thicknesses) * pixel_size + thickest(&self.world_stroke_thicknesses) +
+                     pixel_size;
+        let mut candidates = self.rtree.query(((point.0 - margin, point.1 - margin), (point.0 + margin, point.1 + margin)));
+        candidates.sort();
+        self.layers.iter().rev().filter(|layer| layer.visible).filter_map(|layer| {
+            candidates.iter().rev()
+                .filter(|&id| !self.hidden.contains(id))
+                .find(|&id| self.paths.get(id).is_some_and(|entry| entry.layer == layer.id && entry.path.hit_test(point, pixel_size)))
+                .cloned()
+        }).next()
+    }
+
+    /// The topmost path drawn at a point in drawing coordinates, like `pick` but found by drawing
+    /// the paths' ids into a texture and reading the pixel under the point. This matches what is
+    /// drawn exactly, curved edges and thin strokes included, and after the first pick only
+    /// reads a pixel until the paths or the camera change, so it stays fast for thousands of
+    /// paths. Needs the drawing's context to be current. Fully transparent fills and strokes are
+    /// not picked.
+    pub fn pick_gpu(&mut self, point: (f32, f32)) -> Option<PathId> {
+        if !self.update_buffers() {
+            return None;
+        }
+        let saved = SavedGlState::save();
+        if self.pick_pass.is_none() {
+            match PickPass::new() {
+                Ok(pass) => self.pick_pass = Some(pass),
+                Err(err) => {
+                    log_event!(error, "could not create the picking pass: {}", err);
+                    saved.restore();
+                    return None;
+                }
+            }
+        }
+        let ranges = self.layers.iter().zip(&self.layer_ranges)
+            .filter(|&(layer, range)| layer.visible && layer.opacity > 0f32 && range.end > range.start)
+            .flat_map(|(_, range)| visible_runs(&self.arrays.paths, &self.hidden, range.start, range.end))
+            .collect();
+        let key = PickKey {
+            version: self.buffer_version,
+            projection: self.projection(),
+            window_size: self.window_size,
+            max_tessellation: self.max_tessellation,
+            lod_tolerance: self.lod_tolerance.unwrap_or(ZERO),
+            stroke_depth_offset: depth_step(self.depth_layers) / TWO,
+            crisp: self.crisp,
+            world_scale: ONE / self.pixel_size(),
+            ranges
+        };
+        let mut pass = self.pick_pass.take().unwrap();
+        if !pass.is_current(&key) {
+            let paths: Vec<(usize, usize)> = self.arrays.paths.iter().map(|&(_, start, end)| (start, end)).collect();
+            pass.render(key, &self.buffers.vbos, &pick::id_colors(&self.arrays.fill_colors, &paths),
+                        &pick::id_colors(&self.arrays.stroke_colors, &paths));
+        }
+        let pixel = self.drawing_to_window(point);
+        let id = pass.read(pixel.0.floor() as GLint, pixel.1.floor() as GLint);
+        self.pick_pass = Some(pass);
+        saved.restore();
+        match id {
+            0 => None,
+            id => self.arrays.paths.get(id as usize - 1).map(|&(path, _, _)| path)
+        }
+    }
+
+    /// Highlight a path above all other content, replacing any highlight it had. Does nothing if
+    /// the path is not in the drawing.
+    pub fn set_selected(&mut self, id: PathId, style: SelectionStyle) {
+        if self.paths.contains_key(&id) {
+            self.selection.insert(id, style);
+            self.remake = true;
+        }
+    }
+
+    /// Remove the highlight from a path.
+    pub fn clear_selected(&mut self, id: PathId) {
+        if self.selection.remove(&id).is_some() {
+            self.remake = true;
+        }
+    }
+
+    /// Remove the highlight from every path.
+    pub fn clear_selection(&mut self) {
+        if !self.selection.is_empty() {
+            self.selection.clear();
+            self.remake = true;
+        }
+    }
+
+    /// The highlighted paths, in the order they were added.
+    pub fn selected(&self) -> Vec<PathId> {
+        self.selection.keys().cloned().collect()
+    }
+
+    /// Draw a grid, rulers or guides above everything else, replacing any overlay already set.
+    /// The overlay is made again whenever the camera or window changes, so its lines stay a
+    /// pixel thick, and is drawn in the same batch as the selection highlights.
+    pub fn set_overlay(&mut self, overlay: Overlay) {
+        self.overlay = Some(overlay);
+        self.remake = true;
+    }
+
+    /// Remove the overlay.
+    pub fn clear_overlay(&mut self) {
+        if self.overlay.take().is_some() {
+            self.remake = true;
+        }
+    }
+
+    /// The overlay, if one is set.
+    pub fn overlay(&self) -> Option<&Overlay> {
+        self.overlay.as_ref()
+    }
+
+    /// Show how the paths were cut up for the GPU, over everything else, to find out why a path
+    /// looks wrong. Like the overlay it is made again whenever the camera or window changes.
+    /// Slow for big drawings, meant for debugging only.
+    pub fn set_debug_view(&mut self, view: DebugView) {
+        if view != self.debug_view {
+            self.debug_view = view;
+            self.remake = true;
+        }
+    }
+
+    /// The debug view shown.
+    pub fn debug_view(&self) -> DebugView {
+        self.debug_view
+    }
+
+    /// Draw the triangles the GPU tessellated the paths into as lines instead of filling them.
+    /// Overlays and selection highlights are still drawn filled.
+    pub fn show_wireframe(&mut self, on: bool) {
+        if on != self.wireframe {
+            self.wireframe = on;
+            self.invalidate_layer_caches();
+        }
+    }
+
+    /// Outline the bounds of every shown path, labeled with its id as given by
+    /// `PathId::to_raw`, to see what culling and picking work with.
+    pub fn show_bounds(&mut self, on: bool) {
+        if on != self.show_bounds {
+            self.show_bounds = on;
+            self.remake = true;
+        }
+    }
+
+    /// Set the camera, which pans and zooms the whole drawing.
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = camera;
+        self.remake = true;
+    }
+
+    /// The current camera.
+    pub fn camera(&self) -> Camera {
+        self.camera
+    }
+
+    /// Set where the origin of the view is in the window and which way y grows, by default the
+    /// bottom left with y up. With `Origin::TopLeft` and `YAxis::Down` drawing coordinates work
+    /// like those of most interface toolkits. Input event positions stay in window pixels from
+    /// the bottom left and are converted by `window_to_drawing`. Text outlines and the `ui`
+    /// module are laid out with y up.
+    pub fn set_coordinate_system(&mut self, origin: Origin, y_axis: YAxis) {
+        self.coordinates.origin = origin;
+        self.coordinates.y_axis = y_axis;
+        self.loaded_tiles = None;
+        self.invalidate_layer_caches();
+    }
+
+    /// Where the origin of the view is and which way y grows.
+    pub fn coordinate_system(&self) -> (Origin, YAxis) {
+        (self.coordinates.origin, self.coordinates.y_axis)
+    }
+
+    /// Set how many window pixels a logical unit of the view covers, 1 by default. Setting it to
+    /// the display's scale factor lets paths be given in logical pixels on high density displays.
+    /// Stroke thicknesses stay in window pixels. Scales that are not positive are ignored, and a
+    /// logical size overrides the scale while set.
+    pub fn set_logical_scale(&mut self, pixels_per_unit: f32) {
+        if pixels_per_unit > 0f32 {
+            self.coordinates.scale = pixels_per_unit;
+            self.loaded_tiles = None;
+            self.invalidate_layer_caches();
+        }
+    }
+
+    /// Window pixels per logical unit.
+    pub fn logical_scale(&self) -> f32 {
+        self.coordinates.scale
+    }
+
+    /// Give the view a fixed size of width by height logical units, fitted into the window as
+    /// `mode` says whatever the window's size, so a scene keeps its composition on any screen.
+    /// With `ScaleMode::Fit` the parts of the window outside the view are letterbox bars, cleared
+    /// to the letterbox color and never drawn on. The origin and y axis of the coordinate system
+    /// apply within the view. Sizes that are not positive are ignored.
+    pub fn set_logical_size(&mut self, width: f32, height: f32, mode: ScaleMode) {
+        if width > 0f32 && height > 0f32 && width.is_finite() && height.is_finite() {
+            self.coordinates.logical = Some(LogicalSize { width, height, mode });
+            self.loaded_tiles = None;
+            self.invalidate_layer_caches();
+        }
+    }
+
+    /// Let the view follow the window's size again, at the logical scale.
+    pub fn clear_logical_size(&mut self) {
+        if self.coordinates.logical.take().is_some() {
+            self.loaded_tiles = None;
+            self.invalidate_layer_caches();
+        }
+    }
+
+    /// The logical size of the view and how it is fitted into the window, if one is set.
+    pub fn logical_size(&self) -> Option<(f32, f32, ScaleMode)> {
+        self.coordinates.logical.map(|logical| (logical.width, logical.height, logical.mode))
+    }
+
+    /// The part of the window the view is drawn in, (min, max) in window pixels from the bottom
+    /// left. The whole window unless a logical size is set.
+    pub fn view_area(&self) -> ((f32, f32), (f32, f32)) {
+        self.coordinates.area(self.window_pixels())
+    }
+
+    /// Set the color of the letterbox bars around a view of fixed logical size, opaque black by
+    /// default.
+    pub fn set_letterbox_color(&mut self, color: Color) {
+        self.letterbox_color = color;
+    }
+
+    /// Set how the drawing is laid out in the view, flat by default. With an isometric
+    /// projection the same paths render as an isometric scene, and `window_to_drawing` maps input
+    /// back onto the drawing's plane. Projections that cannot be undone, like isometric ones at
+    /// angles of 0 or a quarter turn, are ignored.
+    pub fn set_projection(&mut self, projection: Projection) {
+        if projection.is_valid() {
+            self.projection_mode = projection;
+            self.loaded_tiles = None;
+            self.invalidate_layer_caches();
+        }
+    }
+
+    /// How the drawing is laid out in the view.
+    pub fn projection_mode(&self) -> Projection {
+        self.projection_mode
+    }
+
+    /// Convert a point in window pixels from the bottom left, like input event positions, to
+    /// drawing coordinates.
+    pub fn window_to_drawing(&self, point: (f32, f32)) -> (f32, f32) {
+        self.projection_mode.invert(self.camera.to_drawing(self.coordinates.to_view(point, self.window_pixels())))
+    }
+
+    /// Convert a point in drawing coordinates to window pixels from the bottom left.
+    pub fn drawing_to_window(&self, point: (f32, f32)) -> (f32, f32) {
+        self.coordinates.to_window(self.camera.to_window(self.projection_mode.apply(point)), self.window_pixels())
+    }
+
+    // The window's width and height in pixels.
+    fn window_pixels(&self) -> (f32, f32) {
+        (self.window_size[0], self.window_size[1])
+    }
+
+    // The size of a window pixel in drawing coordinates, along the direction the projection
+    // shrinks most.
+    pub(crate) fn pixel_size(&self) -> f32 {
+        let (x, y) = self.coordinates.scales(self.window_pixels());
+        1f32 / (self.camera.zoom * x.min(y) * self.projection_mode.min_scale())
+    }
+
+    /// Turn mouse camera controls in `handle_event` on or off (off by default). When on, dragging
+    /// with the middle button, or with the left button starting away from any path, pans and the
+    /// scroll wheel zooms around the mouse.
+    pub fn set_camera_controls(&mut self, enabled: bool) {
+        self.camera_controls = enabled;
+        self.dragging = None;
+    }
+
+    /// Feed an input event to the drawing. Returns what happened to the paths: hovering, presses,
+    /// releases and clicks, topmost path first. Moves the camera if camera controls are on.
+    pub fn handle_event(&mut self, event: &InputEvent) -> Vec<PathEvent> {
+        let mut events = Vec::new();
+        match *event {
+            InputEvent::MouseMove { position } => {
+                if let Some(last) = self.dragging {
+                    let (from, to) = (self.coordinates.to_view(last, self.window_pixels()),
+                                      self.coordinates.to_view(position, self.window_pixels()));
+                    self.camera.pan_by(to.0 - from.0, to.1 - from.1);
+                    self.dragging = Some(position);
+                    self.remake = true;
+                }
+                let hit = self.pick(self.window_to_drawing(position));
+                if hit != self.hovered {
+                    if let Some(id) = self.hovered {
+                        events.push(PathEvent::HoverLeave(id));
+                    }
+                    if let Some(id) = hit {
+                        events.push(PathEvent::HoverEnter(id));
+                    }
+                    self.hovered = hit;
+                }
+            }
+            InputEvent::MouseDown { button, position } => {
+                let hit = self.pick(self.window_to_drawing(position));
+                if let Some(id) = hit {
+                    events.push(PathEvent::Press(id, button));
+                }
+                self.pressed = hit.map(|id| (id, button));
+                if self.camera_controls && (button == MouseButton::Middle ||
+                                            (button == MouseButton::Left && hit.is_none())) {
+                    self.dragging = Some(position);
+                }
+            }
+            InputEvent::MouseUp { button, position } => {
+                let hit = self.pick(self.window_to_drawing(position));
+                if let Some(id) = hit {
+                    events.push(PathEvent::Release(id, button));
+                    if self.pressed == Some((id, button)) {
+                        events.push(PathEvent::Click(id, button));
+                    }
+                }
+                self.pressed = None;
+                self.dragging = None;
+            }
+            InputEvent::MouseWheel { delta, position } => {
+                if self.camera_controls {
+                    let anchor = self.coordinates.to_view(position, self.window_pixels());
+                    self.camera.zoom_about(anchor, ZOOM_STEP.powf(delta));
+                    self.remake = true;
+                }
+            }
+            InputEvent::KeyDown { .. } | InputEvent::KeyUp { .. } => ()
+        }
+        events
+    }
+
+    /// Draw all the paths.
+    pub fn draw(&mut self) {
+        match self.trail {
+            Some(opacity) => self.draw_with(ClearMode::Fade(opacity)),
+            None => self.draw_with(ClearMode::Background)
+        }
+    }
+
+    /// Draw all the paths, first clearing the frame as `clear` says.
+    pub fn draw_with(&mut self, clear: ClearMode) {
+        if !self.update_buffers() {
+            return;
+        }
+        // fading draws the background even when it is a plain color
+        if let ClearMode::Fade(_) = clear {
+            if self.background_renderer.is_none() {
+                match BackgroundRenderer::new() {
+                    Ok(renderer) => self.background_renderer = Some(renderer),
+                    Err(err) => log_event!(error, "could not create the background renderer: {}", err)
+                }
+            }
+        }
+        let letterbox = self.letterbox();
+        unsafe {
+            gl::Viewport(0, 0, self.window_size[0] as GLsizei, self.window_size[1] as GLsizei);
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::PatchParameteri(gl::PATCH_VERTICES, 3);
+            gl::Enable(gl::DEPTH_TEST);
+            self.set_srgb_state();
+            self.set_time_uniforms();
+
+            // the bars around a fitted view are cleared to their color, and everything else is
+            // kept inside the view
+            if let Some([x, y, width, height]) = letterbox {
+                if clear != ClearMode::KeepPrevious {
+                    let bars = if self.srgb { self.letterbox_color.to_linear() } else { self.letterbox_color };
+                    gl::ClearColor(bars.red, bars.green, bars.blue, bars.alpha);
+                    gl::Clear(gl::COLOR_BUFFER_BIT);
+                }
+                gl::Enable(gl::SCISSOR_TEST);
+                gl::Scissor(x, y, width, height);
+            }
+
+            let clear_color = match clear {
+                ClearMode::Background => Some(self.background.clear_color()),
+                ClearMode::Color(color) => Some(color),
+                ClearMode::KeepPrevious | ClearMode::DepthOnly | ClearMode::Fade(_) => None
+            };
+            // Clear the screen
+            match clear_color {
+                Some(color) => {
+                    // the clear color bypasses the shaders, so it is linearized here
+                    let bg = if self.srgb { color.to_linear() } else { color };
+                    gl::ClearColor(bg.red, bg.green, bg.blue, bg.alpha);
+                    gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+                }
+                None if clear == ClearMode::KeepPrevious => (),
+                None => gl::Clear(gl::DEPTH_BUFFER_BIT)
+            }
+
+            if let Some(ref mut renderer) = self.background_renderer {
+                renderer.set_time(self.clock.time(), self.clock.frame);
+                match clear {
+                    ClearMode::Background => renderer.draw(&self.background, self.srgb),
+                    ClearMode::Fade(opacity) => renderer.fade(&self.background, opacity, self.srgb),
+                    _ => ()
+                }
+                gl::UseProgram(self.shader_program.get_program_id());
+            }
+
+        }
+        self.draw_baked();
+        self.draw_layers();
+        if letterbox.is_some() {
+            unsafe {
+                gl::Disable(gl::SCISSOR_TEST);
+            }
+        }
+        self.clock.frame += 1;
+    }
+
+    // The view's area in whole window pixels, [x, y, width, height], when a logical size fitted
+    // into the window leaves letterbox bars around it.
+    fn letterbox(&self) -> Option<[GLint; 4]> {
+        let (width, height) = self.window_pixels();
+        let ((x0, y0), (x1, y1)) = self.view_area();
+        let (x0, y0) = (x0.round().max(0f32), y0.round().max(0f32));
+        let (x1, y1) = (x1.round().min(width), y1.round().min(height));
+        if x0 <= 0f32 && y0 <= 0f32 && x1 >= width && y1 >= height {
+            return None;
+        }
+        Some([x0 as GLint, y0 as GLint, (x1 - x0).max(0f32) as GLint, (y1 - y0).max(0f32) as GLint])
+    }
+
+    /// Draw the drawing into an offscreen framebuffer the size of the window instead of the
+    /// window, and read it back. Used for screenshots and for comparing against reference images
+    /// in tests.
+    pub fn render_to_image(&mut self) -> Result<ImageData, TrdlError> {
+        let (width, height) = (self.window_size[0] as GLsizei, self.window_size[1] as GLsizei);
+        if width <= 0 || height <= 0 {
+            return Err(TrdlError::InvalidImageSize);
+        }
+        let mut target = LayerCache::new(width, height, self.srgb, 0);
+        let key = CacheKey { version: 0, projection: self.projection(), srgb: self.srgb, sort_mode: self.sort_mode };
+        target.render(key, || self.draw());
+        ImageData::new(width as u32, height as u32, target.read_pixels())
+    }
+
+    /// Draw the drawing on the CPU instead of the GPU, into an image the size of the window. Only
+    /// a solid background is drawn, and patterned and gradient fills come out in their flat fill
+    /// color. See `SoftwareRasterizer`.
+    #[cfg(feature = "software")]
+    pub fn render_software(&mut self) -> Result<ImageData, TrdlError> {
+        let geometry = self.snapshot_geometry();
+        let view = RasterView {
+            projection: self.projection(),
+            world_scale: ONE / self.pixel_size(),
+            stroke_depth_offset: depth_step(self.depth_layers) / TWO
+        };
+        let mut rasterizer = SoftwareRasterizer::new(self.window_size[0] as u32, self.window_size[1] as u32,
+                                                     self.background.clear_color())?;
+        rasterizer.draw(&geometry, &view);
+        rasterizer.into_image()
+    }
+
+    /// Draw all the paths as an overlay inside another renderer's frame, e.g. a debug HUD in a
+    /// game. All the OpenGL state this touches (program, vertex array, buffer binding, depth,
+    /// blend, scissor and sRGB state) is saved first and restored afterwards. The paths go into
+    /// the bound framebuffer's viewport, which should be the drawing's size, over what is there;
+    /// the background is not drawn. The paths need a cleared depth buffer, so the host's depth
+    /// values in the viewport are copied aside and put back once they are drawn.
+    pub fn draw_embedded(&mut self) {
+        let saved = SavedGlState::save();
+        if !self.update_buffers() {
+            saved.restore();
+            return;
+        }
+        let mut depth = self.embedded_depth.take().unwrap_or_else(DepthCopy::new);
+        let viewport = saved.viewport();
+        depth.save(viewport);
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::PatchParameteri(gl::PATCH_VERTICES, 3);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(gl::LESS);
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+            self.set_srgb_state();
+            self.set_time_uniforms();
+
+            // only the viewport's depth values were copied, so only they are cleared, and the
+            // paths are not cut to a scissor box the host left on
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(viewport[0], viewport[1], viewport[2], viewport[3]);
+            gl::ClearDepth(1.0);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+            gl::Disable(gl::SCISSOR_TEST);
+        }
+        self.draw_baked();
+        self.draw_layers();
+        self.clock.frame += 1;
+        depth.restore();
+        self.embedded_depth = Some(depth);
+        saved.restore();
+    }
+
+    // Draw the visible layers bottom to top, then the selection highlights. Layers that are
+    // cached or faded go through their texture, which is redrawn first if out of date.
+    fn draw_layers(&mut self) {
+        span!("draw_layers");
+        let mut stats = FrameStats { paths: self.paths.len(), ..FrameStats::default() };
+        if self.compositor.is_none() && self.layers.iter().any(|layer| layer.visible && layer.uses_texture()) {
+            // without the compositor every layer is drawn directly
+            self.compositor = LayerCompositor::new().ok();
+        }
+        let (width, height) = (self.window_size[0] as GLsizei, self.window_size[1] as GLsizei);
+        let projection = self.projection();
+        for index in 0..self.layers.len() {
+            let range = self.layer_ranges.get(index).cloned().unwrap_or_default();
+            let layer = &self.layers[index];
+            if !layer.visible || range.start == range.end || layer.opacity <= 0f32 {
+                continue;
+            }
+            if !layer.uses_texture() || self.compositor.is_none() {
+                // a layer that is no longer faded or cached gives its texture back
+                self.layers[index].cache = None;
+                stats.batches += self.draw_layer_range(range);
+                stats.patches += range.end - range.start;
+                continue;
+            }
+            let key = CacheKey { version: layer.version, projection, srgb: self.srgb, sort_mode: self.sort_mode };
+            let samples = layer.samples;
+            let fits = layer.cache.as_ref().is_some_and(|cache| cache.fits(width, height, self.srgb, samples));
+            if !fits {
+                self.layers[index].cache = Some(LayerCache::new(width, height, self.srgb, samples));
+            }
+            let mut cache = self.layers[index].cache.take().unwrap();
+            if cache.key != Some(key) {
+                cache.render(key, || stats.batches += self.draw_layer_range(range));
+                stats.patches += range.end - range.start;
+                stats.layers_redrawn += 1;
+            }
+            if let Some(ref compositor) = self.compositor {
+                compositor.composite(&cache, self.layers[index].opacity);
+                stats.layers_composited += 1;
+            }
+            self.layers[index].cache = Some(cache);
+        }
+        stats.batches += self.draw_range(self.overlay_range);
+        stats.patches += self.overlay_range.end - self.overlay_range.start;
+        ::trace::log_gl_errors("draw");
+        log_event!(debug, "frame: {} paths, {} patches drawn in {} batches, {} of {} layers composited ({} redrawn)",
+                   stats.paths, stats.patches, stats.batches, stats.layers_composited, self.layers.len(),
+                   stats.layers_redrawn);
+        self.frame_stats = stats;
+    }
+
+    // Draw the patches from start to end, leaving out hidden paths, in one call. Returns whether
+    // anything was drawn.
+    fn draw_patches(&self, start: usize, end: usize) -> bool {
+        let runs = visible_runs(&self.arrays.paths, &self.hidden, start, end);
+        if runs.is_empty() {
+            return false;
+        }
+        let firsts: Vec<GLint> = runs.iter().map(|&(start, _)| start as GLint).collect();
+        let counts: Vec<GLsizei> = runs.iter().map(|&(start, end)| (end - start) as GLsizei).collect();
+        unsafe {
+            gl::MultiDrawArrays(gl::PATCHES, firsts.as_ptr(), counts.as_ptr(), runs.len() as GLsizei);
+        }
+        true
+    }
+
+    // Draw a range of a layer's patches, as lines when the wireframe is shown.
+    fn draw_layer_range(&self, range: PatchRange) -> usize {
+        if !self.wireframe {
+            return self.draw_range(range);
+        }
+        unsafe {
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+        }
+        let batches = self.draw_range(range);
+        unsafe {
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+        }
+        batches
+    }
+
+    // Draw the opaque patches of a range without blending, then the rest with blending, one
+    // batch each. Hidden paths are left out. Leaves blending off. Returns the number of draw
+    // calls.
+    fn draw_range(&self, range: PatchRange) -> usize {
+        let mut batches = 0;
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::BindVertexArray(self.buffers.vertex_array);
+            if let Some(ref buffer) = self.pattern_buffer {
+                buffer.bind(PATTERN_UNIT);
+            }
+            if let Some(ref buffer) = self.gradient_buffer {
+                buffer.bind(GRADIENT_UNIT);
+            }
+            gl::Disable(gl::BLEND);
+        }
+        batches += self.draw_patches(range.start, range.blend_start) as usize;
+        unsafe {
+            // alpha accumulates as coverage, so layer textures end up premultiplied
+            gl::Enable(gl::BLEND);
+            let source = if self.premultiplied { gl::ONE } else { gl::SRC_ALPHA };
+            gl::BlendFuncSeparate(source, gl::ONE_MINUS_SRC_ALPHA, gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+        }
+        batches += self.draw_patches(range.blend_start, range.end) as usize;
+        unsafe {
+            gl::Disable(gl::BLEND);
+        }
+        batches
+    }
+
+    /// Draw paths into a texture once and from then on draw the texture instead of the paths, for
+    /// complex artwork that does not change. The longer side of the paths' bounds gets resolution
+    /// texels, so bake at the size the artwork is seen at most closely or it turns blurry. Baked
+    /// textures are drawn in the order they were baked, over the background and under all paths,
+    /// and are not picked or selected.
+    pub fn bake(&mut self, paths: &[Path], resolution: u32) -> Result<TextureHandle, TrdlError> {
+        let mut baked = Baked {
+            paths: paths.to_vec(),
+            resolution,
+            transform: Transform2D::identity(),
+            area: ((0f32, 0f32), (0f32, 0f32)),
+            srgb: self.srgb,
+            image: None,
+            picture: None
+        };
+        self.render_baked(&mut baked)?;
+        Ok(self.insert_baked(baked))
+    }
+
+    /// Make a texture of an image, drawn like a baked texture: one drawing unit per pixel with its
+    /// lower left corner at the origin, until moved with `set_baked_transform`.
+    pub fn add_texture(&mut self, image: &ImageData) -> Result<TextureHandle, TrdlError> {
+        let mut baked = Baked {
+            paths: Vec::new(),
+            resolution: 0,
+            transform: Transform2D::identity(),
+            area: ((0f32, 0f32), (0f32, 0f32)),
+            srgb: self.srgb,
+            image: None,
+            picture: Some(image.clone())
+        };
+        self.render_baked(&mut baked)?;
+        Ok(self.insert_baked(baked))
+    }
+
+    /// Start decoding a PNG or JPEG file on a thread of its own and return its texture right
+    /// away. Until the image is decoded and `poll_textures` swaps it in, the texture is a 64 by 64
+    /// gray checkerboard, drawn like `add_texture` draws images. A file that cannot be read or
+    /// decoded leaves the checkerboard in place.
+    #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+    pub fn load_texture_async<P: AsRef<std::path::Path>>(&mut self, path: P) -> TextureHandle {
+        let placeholder = Baked {
+            paths: Vec::new(),
+            resolution: 0,
+            transform: Transform2D::identity(),
+            area: ((0f32, 0f32), (0f32, 0f32)),
+            srgb: self.srgb,
+            image: None,
+            picture: Some(bake::placeholder())
+        };
+        // uploaded on the next draw, like a texture lost with the OpenGL context
+        let handle = self.insert_baked(placeholder);
+        let (sender, receiver) = mpsc::channel();
+        let path = path.as_ref().to_path_buf();
+        thread::spawn(move || {
+            // the drawing may be gone by the time the image is decoded
+            let _ = sender.send(ImageData::from_file(path));
+        });
+        self.loading.push((handle, receiver));
+        handle
+    }
+
+    /// Swap decoded images into the textures of `load_texture_async`, to call once a frame. Each
+    /// texture that finished loading since the last call is returned with the outcome; the others
+    /// keep their placeholder. Textures destroyed while loading are dropped quietly.
+    #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+    pub fn poll_textures(&mut self) -> Vec<(TextureHandle, Result<(), TrdlError>)> {
+        let mut finished = Vec::new();
+        let mut loading = mem::take(&mut self.loading);
+        loading.retain(|&(handle, ref receiver)| {
+            let result = match receiver.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => Err(TrdlError::InvalidImage("the loading thread stopped".to_string()))
+            };
+            if let Some(baked) = self.baked.get_mut(&handle) {
+                if let Err(ref err) = result {
+                    log_event!(warn, "could not load texture: {}", err);
+                }
+                finished.push((handle, result.map(|image| {
+                    baked.picture = Some(image);
+                    baked.image = None;
+                })));
+            }
+            false
+        });
+        self.loading = loading;
+        finished
+    }
+
+    /// Whether a texture of `load_texture_async` still shows its placeholder while its image is
+    /// decoded.
+    #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+    pub fn is_loading(&self, handle: TextureHandle) -> bool {
+        self.loading.iter().any(|&(loading, _)| loading == handle)
+    }
+
+    // Number a new baked texture and keep it.
+    fn insert_baked(&mut self, baked: Baked) -> TextureHandle {
+        let handle = TextureHandle { namespace: self.namespace, index: self.next_baked };
+        self.next_baked += 1;
+        self.baked.insert(handle, baked);
+        handle
+    }
+
+    /// Remove a baked texture, returns false if there is none with this handle. The same as
+    /// `destroy(handle)`.
+    pub fn remove_baked(&mut self, handle: TextureHandle) -> bool {
+        #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+        self.loading.retain(|&(loading, _)| loading != handle);
+        self.baked.remove(&handle).is_some()
+    }
+
+    /// Free a resource the drawing made: a baked texture or a gradient. Paths filled with a
+    /// destroyed gradient fall back to a flat fill in their fill color. Returns false if the
+    /// drawing has no such resource, because it was destroyed already or the handle came from
+    /// another drawing. Whatever is not destroyed is freed with the drawing, and debug builds log
+    /// it as a leak then.
+    pub fn destroy(&mut self, resource: impl Into<Resource>) -> bool {
+        match resource.into() {
+            Resource::Texture(handle) => self.remove_baked(handle),
+            Resource::Gradient(gradient) => {
+                if self.gradient(gradient).is_none() {
+                    return false;
+                }
+                self.gradients[gradient.slot] = None;
+                self.gradients_changed = true;
+                self.invalidate_layer_caches();
+                true
+            }
+        }
+    }
+
+    /// The resources made by the drawing that were not destroyed yet.
+    pub fn live_resources(&self) -> Vec<Resource> {
+        let textures = self.baked.keys().map(|&handle| Resource::Texture(handle));
+        let gradients = self.gradients.iter().enumerate().filter(|&(_, gradient)| gradient.is_some())
+            .map(|(slot, _)| Resource::Gradient(GradientId { namespace: self.namespace, slot }));
+        textures.chain(gradients).collect()
+    }
+
+    /// Move, scale or rotate a baked texture from where its paths were. Returns false if there is
+    /// none with this handle.
+    pub fn set_baked_transform(&mut self, handle: TextureHandle, transform: Transform2D) -> bool {
+        match self.baked.get_mut(&handle) {
+            Some(baked) => {
+                baked.transform = transform;
+                true
+            }
+            None => false
+        }
+    }
+
+    // Draw a baked group's paths into a new texture, leaving the OpenGL state as it was. Textures
+    // of images upload the image instead.
+    fn render_baked(&mut self, baked: &mut Baked) -> Result<(), TrdlError> {
+        if let Some(ref picture) = baked.picture {
+            let size = (picture.width() as GLint, picture.height() as GLint);
+            let mut max_size = 0 as GLint;
+            unsafe {
+                gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_size);
+            }
+            if size.0 > max_size || size.1 > max_size {
+                return Err(TrdlError::InvalidImageSize);
+            }
+            let saved = SavedGlState::save();
+            let mut image = LayerCache::new(size.0, size.1, self.srgb, 0);
+            image.upload(picture);
+            image.set_smooth();
+            saved.restore();
+            baked.area = ((0f32, 0f32), (size.0 as f32, size.1 as f32));
+            baked.srgb = self.srgb;
+            baked.image = Some(image);
+            return Ok(());
+        }
+        let bounds = baked.paths.iter().map(Path::bounds).reduce(|a, b| {
+            (((a.0).0.min((b.0).0), (a.0).1.min((b.0).1)), ((a.1).0.max((b.1).0), (a.1).1.max((b.1).1)))
+        }).ok_or(TrdlError::NoVisibleGeometry)?;
+        // room for half the widest stroke and a pixel of anti-aliasing
+        let stroke = baked.paths.iter().filter_map(|path| path.stroke.map(|stroke| stroke.1)).max().unwrap_or(0);
+        let (size, camera, area) = bake::bake_frame(bounds, baked.resolution, stroke / 2 + 1);
+        let mut max_size = 0 as GLint;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_size);
+        }
+        if baked.resolution == 0 || size.0 > max_size || size.1 > max_size {
+            return Err(TrdlError::InvalidImageSize);
+        }
+
+        let saved = SavedGlState::save();
+        let result = self.render_paths(&baked.paths, size, camera);
+        saved.restore();
+        baked.area = area;
+        baked.srgb = self.srgb;
+        baked.image = Some(result?);
+        Ok(())
+    }
+
+    // Draw paths seen through a camera into a new texture of a size, with the drawing's own shader
+    // program and buffers. The buffers and view uniforms are the drawing's again afterwards.
+    fn render_paths(&mut self, paths: &[Path], size: (GLsizei, GLsizei), camera: Camera)
+            -> Result<LayerCache, TrdlError> {
+        if self.gl_lost {
+            self.restore_gl_state()?;
+        }
+        self.update_buffers();
+        let geometries = paths.iter().map(PathGeometry::new).collect::<Result<Vec<_>, _>>()?;
+        let opaque: Vec<bool> = paths.iter().map(Path::is_opaque).collect();
+        let (order, num_opaque) = draw_order(&opaque, self.sort_mode);
+        let mut arrays = GeometrySnapshot::default();
+        let mut range = PatchRange::default();
+        for (n, &i) in order.iter().enumerate() {
+            if n == num_opaque {
+                range.blend_start = range.end;
+            }
+            arrays.push(&geometries[i], path_depth(i, paths.len()), self.fill_record(&paths[i]));
+            range.end += geometries[i].vertices.len() / 3;
+        }
+        if num_opaque == order.len() {
+            range.blend_start = range.end;
+        }
+
+        let (projection, pixel_size) = self.bake_view(camera, size);
+        let mut image = LayerCache::new(size.0, size.1, self.srgb, 0);
+        let key = CacheKey { version: 0, projection, srgb: self.srgb, sort_mode: self.sort_mode };
+        // the buffers hold the baked paths until the drawing's own are put back, and baked
+        // textures are premultiplied whatever the drawing blends with
+        mem::swap(&mut self.arrays, &mut arrays);
+        self.upload_arrays();
+        let premultiplied = mem::replace(&mut self.premultiplied, true);
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            self.set_view_uniforms(&projection, [size.0 as GLfloat, size.1 as GLfloat], paths.len(), pixel_size);
+            self.set_time_uniforms();
+            if self.premultiplied_uniform >= 0 {
+                gl::Uniform1i(self.premultiplied_uniform, 1);
+            }
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::ClearDepth(1.0);
+            gl::DepthMask(gl::TRUE);
+        }
+        image.render(key, || {
+            unsafe {
+                gl::PatchParameteri(gl::PATCH_VERTICES, 3);
+                gl::Enable(gl::DEPTH_TEST);
+                gl::DepthFunc(gl::LESS);
+            }
+            self.set_srgb_state();
+            self.draw_range(range);
+        });
+        image.set_smooth();
+        self.premultiplied = premultiplied;
+        mem::swap(&mut self.arrays, &mut arrays);
+        self.upload_arrays();
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            self.set_view_uniforms(&self.projection(), self.window_size, self.depth_layers, self.pixel_size());
+            if self.premultiplied_uniform >= 0 {
+                gl::Uniform1i(self.premultiplied_uniform, self.premultiplied as GLint);
+            }
+        }
+        Ok(image)
+    }
+
+    // The projection and pixel size of a view through a camera into a window of a size, with the
+    // default coordinate system and projection, which baked paths are drawn into textures with.
+    fn bake_view(&mut self, camera: Camera, size: (GLsizei, GLsizei)) -> ([GLfloat; 16], f32) {
+        let camera = mem::replace(&mut self.camera, camera);
+        let window_size = mem::replace(&mut self.window_size, [size.0 as GLfloat, size.1 as GLfloat]);
+        let coordinates = mem::replace(&mut self.coordinates, CoordinateSystem::new());
+        let projection_mode = mem::replace(&mut self.projection_mode, Projection::Orthographic2D);
+        let view = (self.projection(), self.pixel_size());
+        self.camera = camera;
+        self.window_size = window_size;
+        self.coordinates = coordinates;
+        self.projection_mode = projection_mode;
+        view
+    }
+
+    // Draw the baked textures over the background, baking them again first when they were lost
+    // with the OpenGL context or sRGB rendering was turned on or off.
+    fn draw_baked(&mut self) {
+        if self.baked.is_empty() {
+            return;
+        }
+        if self.baked_renderer.is_none() {
+            match BakedRenderer::new() {
+                Ok(renderer) => self.baked_renderer = Some(renderer),
+                Err(err) => {
+                    log_event!(error, "could not draw baked textures: {}", err);
+                    return;
+                }
+            }
+        }
+        let stale: Vec<TextureHandle> = self.baked.iter()
+            .filter(|&(_, baked)| baked.image.is_none() || baked.srgb != self.srgb)
+            .map(|(&handle, _)| handle)
+            .collect();
+        for handle in stale {
+            let mut baked = self.baked.remove(&handle).unwrap();
+            if let Err(err) = self.render_baked(&mut baked) {
+                log_event!(warn, "could not bake texture again: {}", err);
+            }
+            self.baked.insert(handle, baked);
+        }
+        let projection = self.projection();
+        if let Some(ref renderer) = self.baked_renderer {
+            for baked in self.baked.values() {
+                renderer.draw(baked, &projection);
+            }
+        }
+    }
+
+    /// Set the most pieces a curve is split into when drawn, from 1 to 64 (32 by default).
+    pub fn set_max_tessellation(&mut self, level: u32) {
+        self.max_tessellation = level.clamp(1, MAX_TESSELLATION);
+        self.invalidate_layer_caches();
+    }
+
+    /// Choose how finely curves are tessellated by their size on screen. With Some(tolerance)
+    /// (the default is 0.25) each curve is split into just enough pieces to stay within that many
+    /// pixels of the true curve, so small or zoomed out curves and straight edges are cheap. With
+    /// None every curve gets the maximum tessellation.
+    pub fn set_level_of_detail(&mut self, tolerance: Option<f32>) {
+        self.lod_tolerance = tolerance.filter(|&t| t > 0f32 && t.is_finite());
+        self.invalidate_layer_caches();
+    }
+
+    /// Choose the order paths are drawn in, see `SortMode`.
+    pub fn set_sorting(&mut self, sort_mode: SortMode) {
+        self.sort_mode = sort_mode;
+        self.remake = true;
+    }
+
+    // Enable or disable writing to an sRGB framebuffer.
+    fn set_srgb_state(&self) {
+        unsafe {
+            if self.srgb {
+                gl::Enable(gl::FRAMEBUFFER_SRGB);
+            } else {
+                gl::Disable(gl::FRAMEBUFFER_SRGB);
+            }
+        }
+    }
+
+    /// Tell the drawing its OpenGL context was lost (e.g. an Android app going to the background
+    /// or a driver reset) and the window now has a new one. The old shader program, buffers and
+    /// layer textures are abandoned without deleting them, and everything is recreated from the
+    /// paths on the next draw.
+    pub fn invalidate_gl_state(&mut self) {
+        // the GL names belong to the lost context, deleting them could delete new objects
+        mem::forget(self.compositor.take());
+        mem::forget(self.background_renderer.take());
+        mem::forget(self.embedded_depth.take());
+        mem::forget(self.pick_pass.take());
+        mem::forget(self.baked_renderer.take());
+        mem::forget(self.pattern_buffer.take());
+        mem::forget(self.gradient_buffer.take());
+        self.gradients_changed = !self.gradients.is_empty();
+        for baked in self.baked.values_mut() {
+            mem::forget(baked.image.take());
+        }
+        for layer in &mut self.layers {
+            mem::forget(layer.cache.take());
+        }
+        self.gl_lost = true;
+        self.remake = true;
+    }
+
+    // Recreate the shader program, vertex buffers and background renderer in the current
+    // context after `invalidate_gl_state`.
+    fn restore_gl_state(&mut self) -> Result<(), TrdlError> {
+        let window = self.window;
+        window.set_context();
+        gl::load_with(|symbol| window.load_fn(symbol));
+
+        let program = load_program()?;
+        let background_renderer = if self.background.needs_quad() || self.trail.is_some() {
+            let mut renderer = BackgroundRenderer::new()?;
+            match self.background {
+                Background::Image(ref image) => renderer.set_image(image),
+                Background::Shader(ref shader) => renderer.set_shader(shader)?,
+                _ => ()
+            }
+            Some(renderer)
+        } else {
+            None
+        };
+        // the old buffers went with the lost context
+        self.buffers = VertexBuffers::new(&Gl, program.get_program_id());
+        mem::forget(mem::replace(&mut self.shader_program, program));
+        self.background_renderer = background_renderer;
+
+        self.gl_lost = false;
+        self.remake = true;
+        Ok(())
+    }
+
+    // Upload the paths and look up the uniforms if anything changed since the last draw. Returns
+    // false if there is nothing to draw with because the lost GL state could not be recreated.
+    fn update_buffers(&mut self) -> bool {
+        if self.gl_lost {
+            if let Err(err) = self.restore_gl_state() {
+                log_event!(error, "could not recreate the lost OpenGL state: {}", err);
+                return false;
+            }
+            log_event!(info, "recreated the lost OpenGL state");
+        }
+        self.update_loaded_tiles();
+        unsafe {
+            if self.remake {
+                span!("upload_buffers");
+                self.rebuild_arrays();
+                self.upload_arrays();
+
+                let program_id = self.shader_program.get_program_id();
+                let c_str = CString::new("outer_tess".as_bytes()).unwrap();
+                self.outer_tess_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("inner_tess".as_bytes()).unwrap();
+                self.inner_tess_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("lod_tolerance".as_bytes()).unwrap();
+                self.lod_tolerance_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("projection".as_bytes()).unwrap();
+                self.projection_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("window_size".as_bytes()).unwrap();
+                self.window_size_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("srgb".as_bytes()).unwrap();
+                self.srgb_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("premultiplied".as_bytes()).unwrap();
+                self.premultiplied_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("stroke_depth_offset".as_bytes()).unwrap();
+                self.stroke_depth_offset_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("crisp".as_bytes()).unwrap();
+                self.crisp_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("world_scale".as_bytes()).unwrap();
+                self.world_scale_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("fill_patterns".as_bytes()).unwrap();
+                self.fill_patterns_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("gradients".as_bytes()).unwrap();
+                self.gradients_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("time".as_bytes()).unwrap();
+                self.time_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+                let c_str = CString::new("frame_index".as_bytes()).unwrap();
+                self.frame_index_uniform = gl::GetUniformLocation(program_id, c_str.as_ptr());
+
+                gl::UseProgram(self.shader_program.get_program_id());
+
+                if self.outer_tess_uniform >= 0 {
+                    gl::Uniform1i(self.outer_tess_uniform, self.max_tessellation as GLint);
+                }
+
+                if self.lod_tolerance_uniform >= 0 {
+                    gl::Uniform1f(self.lod_tolerance_uniform, self.lod_tolerance.unwrap_or(ZERO));
+                }
+
+                if self.inner_tess_uniform >= 0 {
+                    gl::Uniform1i(self.inner_tess_uniform, 1);
+                }
+
+                self.set_view_uniforms(&self.projection(), self.window_size, self.depth_layers, self.pixel_size());
+
+                if self.srgb_uniform >= 0 {
+                    gl::Uniform1i(self.srgb_uniform, self.srgb as GLint);
+                }
+
+                if self.premultiplied_uniform >= 0 {
+                    gl::Uniform1i(self.premultiplied_uniform, self.premultiplied as GLint);
+                }
+
+                if self.crisp_uniform >= 0 {
+                    gl::Uniform1i(self.crisp_uniform, self.crisp as GLint);
+                }
+
+                if self.fill_patterns_uniform >= 0 {
+                    gl::Uniform1i(self.fill_patterns_uniform, PATTERN_UNIT as GLint);
+                }
+
+                if self.gradients_uniform >= 0 {
+                    gl::Uniform1i(self.gradients_uniform, GRADIENT_UNIT as GLint);
+                }
+
+                ::trace::log_gl_errors("buffer upload");
+                log_event!(debug, "uploaded {} bytes of vertex data for {} patches", self.uploaded_bytes,
+                           self.arrays.num_patches());
+                self.remake = false;
+            }
+        }
+        if self.gradients_changed {
+            let slots: Vec<GLfloat> = self.gradients.iter()
+                .flat_map(|gradient| gradient.as_ref().map_or(gradient::EMPTY_SLOT, Gradient::slot).to_vec())
+                .collect();
+            self.gradient_buffer.get_or_insert_with(PatternBuffer::new).upload(&slots);
+            self.gradients_changed = false;
+        }
+        true
+    }
+
+    // Copy the arrays into the vertex buffers and their fill patterns into the pattern buffer.
+    fn upload_arrays(&mut self) {
+        self.buffers.upload(&Gl, &self.arrays);
+        if !self.arrays.fill_patterns.is_empty() {
+            self.pattern_buffer.get_or_insert_with(PatternBuffer::new).upload(&self.arrays.fill_patterns);
+        }
+    }
+
+    // Hand the path program, which must be in use, the view it draws into: the projection, the
+    // window's size in pixels, how many paths share the depth range and the size of a pixel in
+    // drawing coordinates.
+    unsafe fn set_view_uniforms(&self, projection: &[GLfloat; 16], window_size: [GLfloat; 2], num_layers: usize,
+                                pixel_size: f32) {
+        if self.projection_uniform >= 0 {
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean, projection.as_ptr());
+        }
+        if self.window_size_uniform >= 0 {
+            gl::Uniform2fv(self.window_size_uniform, 1, window_size.as_ptr());
+        }
+        if self.stroke_depth_offset_uniform >= 0 {
+            gl::Uniform1f(self.stroke_depth_offset_uniform, depth_step(num_layers) / TWO);
+        }
+        if self.world_scale_uniform >= 0 {
+            gl::Uniform1f(self.world_scale_uniform, ONE / pixel_size);
+        }
+    }
+
+    /// Turn sRGB-correct rendering on or off (off by default). When on, all colors are taken to be
+    /// sRGB encoded, they are converted to linear RGB in the shaders and written to an sRGB
+    /// framebuffer, so blending and interpolation happen in linear space. The window must have
+    /// been created with an sRGB capable framebuffer.
+    pub fn set_srgb(&mut self, enabled: bool) {
+        self.srgb = enabled;
+        self.remake = true;
+    }
+
+    /// Whether sRGB-correct rendering is on.
+    pub fn srgb(&self) -> bool {
+        self.srgb
+    }
+
+    /// Turn premultiplied alpha compositing on or off, it is on by default. When on the shaders
+    /// multiply colors by their alpha before blending them across patches, and the result is
+    /// blended with `ONE, ONE_MINUS_SRC_ALPHA`, so colors blended towards a transparent one (a
+    /// mesh gradient fading out, say) do not darken on the way. Path colors are still given
+    /// with straight alpha either way.
+    pub fn set_premultiplied_alpha(&mut self, enabled: bool) {
+        self.premultiplied = enabled;
+        self.invalidate_layer_caches();
+    }
+
+    /// Whether premultiplied alpha compositing is on.
+    pub fn premultiplied_alpha(&self) -> bool {
+        self.premultiplied
+    }
+
+    /// Turn crisp mode on or off, it is off by default. In crisp mode the points of paths are
+    /// snapped to the pixel grid as they are drawn: to pixel corners, so axis aligned edges do not
+    /// blur across two pixels, or to pixel centers for paths with strokes an odd number of pixels
+    /// thick, so 1 pixel lines cover exactly one row or column. Meant for interfaces and charts,
+    /// points move by up to half a pixel.
+    pub fn set_crisp(&mut self, enabled: bool) {
+        self.crisp = enabled;
+        self.invalidate_layer_caches();
+    }
+
+    /// Whether crisp mode is on.
+    pub fn crisp(&self) -> bool {
+        self.crisp
+    }
+
+    /// Set what is drawn behind the paths. Gradient, image and shader backgrounds are drawn as a
+    /// fullscreen quad, a transparent background clears to alpha zero so the drawing can be
+    /// composited over other content. The quad is drawn after the depth buffer is cleared and
+    /// leaves it as it is, so paths and baked textures always show in front. Fails if a shader
+    /// background does not compile, keeping the background there was.
+    pub fn set_background(&mut self, background: Background) -> Result<(), TrdlError> {
+        if background.needs_quad() && self.background_renderer.is_none() {
+            self.background_renderer = Some(BackgroundRenderer::new()?);
+        }
+        if let Some(ref mut renderer) = self.background_renderer {
+            match background {
+                Background::Image(ref image) => renderer.set_image(image),
+                Background::Shader(ref shader) => renderer.set_shader(shader)?,
+                _ => ()
+            }
+        }
+        self.background = background;
+        self.remake = true;
+        Ok(())
+    }
+
+    /// Set a uniform of the shader background, for values that change from frame to frame like a
+    /// sun's position. Returns false if the background is not a shader. See
+    /// `BackgroundShader::set_uniform`.
+    pub fn set_background_uniform(&mut self, name: &str, value: impl Into<UniformValue>) -> bool {
+        match self.background {
+            Background::Shader(ref mut shader) => {
+                shader.set_uniform(name, value);
+                true
+            }
+            _ => false
         }
     }
+
+    /// Leave fading trails behind moving paths: instead of clearing, every draw covers the last
+    /// frame with the background at this opacity, from 0 (nothing fades) to 1 (a plain clear).
+    /// The window has to keep its back buffer's contents across swaps for the trails to show.
+    pub fn set_trail(&mut self, opacity: f32) {
+        self.trail = Some(opacity.clamp(ZERO, ONE));
+    }
+
+    /// Turn trails off, draws clear to the background again.
+    pub fn clear_trail(&mut self) {
+        self.trail = None;
+    }
+
+    /// The opacity of trails, None when they are off.
+    pub fn trail(&self) -> Option<f32> {
+        self.trail
+    }
+
+    /// Make a gradient paths can share with `Path::set_fill(Fill::Gradient(..))`, from stops
+    /// (position, color) with positions from 0 to 1 across a path's bounding box. It runs left to
+    /// right until turned with `set_gradient_angle`. Up to 16 stops are kept, blended in sRGB.
+    pub fn create_gradient(&mut self, stops: &[(f32, Color)]) -> GradientId {
+        self.gradients.push(Some(Gradient::new(stops)));
+        self.gradients_changed = true;
+        GradientId { namespace: self.namespace, slot: self.gradients.len() - 1 }
+    }
+
+    // A gradient of this drawing that was not destroyed.
+    fn gradient(&self, gradient: GradientId) -> Option<&Gradient> {
+        if gradient.namespace != self.namespace {
+            return None;
+        }
+        self.gradients.get(gradient.slot).and_then(Option::as_ref)
+    }
+
+    fn gradient_mut(&mut self, gradient: GradientId) -> Option<&mut Gradient> {
+        if gradient.namespace != self.namespace {
+            return None;
+        }
+        self.gradients.get_mut(gradient.slot).and_then(Option::as_mut)
+    }
+
+    /// Change a gradient's stops, which changes every path filled with it without building them
+    /// again.
+    pub fn update_gradient(&mut self, gradient: GradientId, stops: &[(f32, Color)]) {
+        if let Some(entry) = self.gradient_mut(gradient) {
+            entry.set_stops(stops);
+            self.gradients_changed = true;
+            self.expire_layer_caches();
+        }
+    }
+
+    /// Turn the direction a gradient runs in, counter clockwise from left to right.
+    pub fn set_gradient_angle(&mut self, gradient: GradientId, angle: impl Into<Angle>) {
+        if let Some(entry) = self.gradient_mut(gradient) {
+            entry.angle = angle.into();
+            self.gradients_changed = true;
+            self.expire_layer_caches();
+        }
+    }
+
+    /// A gradient's stops, None if the drawing has no such gradient.
+    pub fn gradient_stops(&self, gradient: GradientId) -> Option<&[(f32, Color)]> {
+        self.gradient(gradient).map(|entry| &entry.stops[..])
+    }
+
+    /// Seconds of animation time: since the drawing was made, unless set with `set_time`. Each
+    /// draw hands it to the path shaders as the `time` uniform, next to the `frame_index` uniform,
+    /// so edited shaders can animate without any GL calls of their own.
+    pub fn time(&self) -> f32 {
+        self.clock.time()
+    }
+
+    /// Set the animation time, which then stands still until set again or let go with
+    /// `clear_time`, e.g. to step through an animation or render it at a fixed frame rate.
+    /// Infinite and NaN times are ignored.
+    pub fn set_time(&mut self, seconds: f32) {
+        self.clock.pin(seconds);
+    }
+
+    /// Let the animation time run with the clock again, on from the time that was set.
+    pub fn clear_time(&mut self) {
+        self.clock.release();
+    }
+
+    /// The number of frames drawn so far, which is the index of the next one.
+    pub fn frame_index(&self) -> u64 {
+        self.clock.frame
+    }
+
+    // Hand this frame's time and index to the path program, which must be in use.
+    unsafe fn set_time_uniforms(&self) {
+        if self.time_uniform >= 0 {
+            gl::Uniform1f(self.time_uniform, self.clock.time());
+        }
+        if self.frame_index_uniform >= 0 {
+            gl::Uniform1i(self.frame_index_uniform, self.clock.frame as GLint);
+        }
+    }
+
+    /// Set new window size.
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        self.remake = true;
+        self.window_size = [gl!(width), gl!(height)];
+    }
+
+    // Concatenate the geometry of all paths into the arrays that get uploaded in the sort order.
+    // Depths are spread over the whole depth range for however many paths there are, later paths
+    // get smaller depths so they are drawn on top.
+    fn rebuild_arrays(&mut self) {
+        self.arrays.clear();
+        // selection highlights, the overlay and the debug view sit above every path and are drawn
+        // last, with blending
+        let pixel_size = self.pixel_size();
+        let hidden: Vec<LayerId> = self.layers.iter().filter(|layer| !layer.visible).map(|layer| layer.id).collect();
+        let to_drawing = |p: (f32, f32)| self.window_to_drawing(p);
+        let to_window = |p: (f32, f32)| self.drawing_to_window(p);
+        let view = OverlayView { size: (self.window_size[0], self.window_size[1]), pixel_size,
+                                 to_drawing: &to_drawing, to_window: &to_window };
+        let tessellation = Tessellation { to_window: &to_window, lod_tolerance: self.lod_tolerance,
+                                          max_level: self.max_tessellation };
+        let overlays: Vec<PathGeometry> = self.selection.iter()
+            .filter(|&(id, _)| !self.hidden.contains(id))
+            .filter_map(|(id, style)| self.paths.get(id).map(|entry| (entry, style)))
+            .filter(|&(entry, _)| !hidden.contains(&entry.layer))
+            .flat_map(|(entry, style)| selection::overlay_paths(&entry.path, style, pixel_size))
+            .chain(self.overlay.iter().flat_map(|overlay| overlay::overlay_paths(overlay, &view)))
+            .chain(self.paths.iter()
+                .filter(|&(id, entry)| self.debug_view != DebugView::Off && !self.hidden.contains(id) &&
+                                       !hidden.contains(&entry.layer))
+                .flat_map(|(_, entry)| debug_view::debug_paths(self.debug_view, &entry.geometry.patches(), &tessellation)))
+            .chain(self.paths.iter()
+                .filter(|&(id, entry)| self.show_bounds && !self.hidden.contains(id) && !hidden.contains(&entry.layer))
+                .flat_map(|(id, entry)| debug_view::bounds_paths(id.to_raw(), entry.bounds, &to_window, &to_drawing)))
+            .filter_map(|path| PathGeometry::new(&path).ok())
+            .collect();
+        let num_layers = self.paths.len() + overlays.len();
+        self.depth_layers = num_layers;
+
+        // with tiling on only the paths in the loaded tiles go in the buffers
+        let loaded: Option<BTreeSet<PathId>> = match (&self.tiles, self.loaded_tiles) {
+            (Some(tiles), Some(range)) => Some(tiles.paths_in(&range)),
+            _ => None
+        };
+
+        // the geometry in buffer order with its depth and fill pattern, layer by layer
+        let mut parts: Vec<(&PathGeometry, f32, Option<[GLfloat; fill_pattern::RECORD_FLOATS]>)> =
+            Vec::with_capacity(num_layers);
+        let mut ranges = Vec::with_capacity(self.layers.len());
+        let mut position = 0;
+        for layer in &self.layers {
+            let paths = &self.paths;
+            let entries: Vec<(PathId, &PathEntry)> = match loaded {
+                Some(ref ids) => ids.iter().filter_map(|&id| paths.get(&id).map(|entry| (id, entry)))
+                                    .filter(|&(_, entry)| entry.layer == layer.id).collect(),
+                None => paths.iter().map(|(&id, entry)| (id, entry)).filter(|&(_, entry)| entry.layer == layer.id).collect()
+            };
+            let opaque: Vec<bool> = entries.iter().map(|&(_, entry)| entry.path.is_opaque()).collect();
+            let (order, num_opaque) = draw_order(&opaque, self.sort_mode);
+            let mut range = PatchRange { start: position, blend_start: position, end: position };
+            let base = parts.len();
+            for (n, &i) in order.iter().enumerate() {
+                if n == num_opaque {
+                    range.blend_start = position;
+                }
+                let (id, entry) = entries[i];
+                let geometry = &entry.geometry;
+                parts.push((geometry, path_depth(base + i, num_layers), self.fill_record(&entry.path)));
+                self.arrays.paths.push((id, position, position + geometry.vertices.len() / 3));
+                position += geometry.vertices.len() / 3;
+            }
+            if num_opaque == order.len() {
+                range.blend_start = position;
+            }
+            range.end = position;
+            ranges.push(range);
+        }
+        let overlay_start = position;
+        for geometry in &overlays {
+            parts.push((geometry, path_depth(parts.len(), num_layers), None));
+            position += geometry.vertices.len() / 3;
+        }
+        self.overlay_range = PatchRange { start: overlay_start, blend_start: overlay_start, end: position };
+
+        for (geometry, depth, pattern) in parts {
+            self.arrays.push(geometry, depth, pattern);
+        }
+        self.layer_ranges = ranges;
+        self.buffer_version += 1;
+        self.uploaded_bytes = self.arrays.byte_size();
+        self.arrays.shrink();
+    }
+
+    // The fill pattern or gradient record of a path for the pattern buffer, if it has one.
+    fn fill_record(&self, path: &Path) -> Option<[GLfloat; fill_pattern::RECORD_FLOATS]> {
+        let gradient = path.fill_gradient.filter(|&gradient| self.gradient(gradient).is_some())
+            .map(|gradient| gradient::record(gradient.slot, path.tight_bounds(), path.fill_alpha()));
+        path.fill_pattern.filter(|_| path.fill_color.is_some())
+            .map(|(pattern, background)| pattern.record(background, path.vertices[0], path.fill_alpha()))
+            .or(gradient)
+    }
+
+    // orthographic projection based on the window size, camera and projection mode, maps drawing
+    // coordinates to OpenGL normalized coords.
+    pub(crate) fn projection(&self) -> [GLfloat; 16] {
+        // where the drawing origin is in the window, and window pixels per view unit
+        let origin = self.drawing_to_window((ZERO, ZERO));
+        let (x, y) = self.coordinates.scales(self.window_pixels());
+        let sx = TWO * self.camera.zoom * x / self.window_size[0];
+        let sy = TWO * self.camera.zoom * y * self.coordinates.y_sign() / self.window_size[1];
+        let tx = TWO * origin.0 / self.window_size[0] - ONE;
+        let ty = TWO * origin.1 / self.window_size[1] - ONE;
+        // where the drawing's x and y axes point in the view
+        let ((xx, xy), (yx, yy)) = self.projection_mode.axes();
+        [
+            sx * xx, sy * xy, ZERO, ZERO,
+            sx * yx, sy * yy, ZERO, ZERO,
+            ZERO,    ZERO,    ONE,  ZERO,
+            tx,      ty,      ZERO, ONE
+        ]
+    }
+}
+
+impl<'a, W: Window> Drop for Drawing<'a, W> {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        if cfg!(debug_assertions) {
+            let live = self.live_resources();
+            if !live.is_empty() {
+                log_event!(warn, "drawing dropped with {} resources never destroyed: {:?}", live.len(), live);
+            }
+        }
+        self.buffers.delete(&Gl);
+        // the rest free their OpenGL objects as they drop
+    }
+}
+
+// Load the path shaders and compile them into a shader program.
+fn load_program() -> Result<shader::ShaderProgram, TrdlError> {
+    let vertex_shader_code = shader::read_file("shaders/vertex_shader.glsl")?;
+    let tess_control_shader_code = shader::read_file("shaders/tess_control_shader.glsl")?;
+    let tess_evaluation_shader_code = shader::read_file("shaders/tess_evaluation_shader.glsl")?;
+    let geometry_shader_code = shader::read_file("shaders/geometry_shader.glsl")?;
+    let fragment_shader_code = shader::read_file("shaders/fragment_shader.glsl")?;
+    let mut builder = shader::ShaderProgramBuilder::new();
+    builder.set_vertex_shader(&vertex_shader_code);
+    builder.set_tess_control_shader(&tess_control_shader_code);
+    builder.set_tess_evaluation_shader(&tess_evaluation_shader_code);
+    builder.set_geometry_shader(&geometry_shader_code);
+    builder.set_fragment_shader(&fragment_shader_code);
+    builder.build_shader_program()
+}
+
+// The vertex buffers of the paths and the vertex array object reading them, all in the order
+// positions, both control points, fill colors, edges, stroke colors and fill flags.
+#[derive(Debug, Clone, PartialEq)]
+struct VertexBuffers {
+    vbos: [GLuint; 7],
+    // created on the first upload
+    vertex_array: GLuint,
+    // where the path shader reads each buffer
+    locations: [GLint; 7]
+}
+
+impl VertexBuffers {
+    const ATTRIBUTES: [&'static str; 7] = ["in_position", "in_control_1", "in_control_2", "in_color", "in_edge",
+                                           "in_stroke_color", "in_do_fill"];
+    // values per vertex in each buffer
+    const SIZES: [GLint; 7] = [3, 2, 2, 4, 1, 4, 1];
+
+    fn new(api: &dyn GlApi, program_id: GLuint) -> Self {
+        let mut vbos = [0 as GLuint; 7];
+        api.gen_buffers(&mut vbos);
+        let locations = Self::ATTRIBUTES.map(|name| api.attrib_location(program_id, name));
+        VertexBuffers { vbos, vertex_array: 0, locations }
+    }
+
+    // Fill the buffers with the arrays and point the vertex array at them.
+    fn upload(&mut self, api: &dyn GlApi, arrays: &GeometrySnapshot) {
+        let data = [bytes(&arrays.vertices), bytes(&arrays.control_point_1s), bytes(&arrays.control_point_2s),
+                    bytes(&arrays.fill_colors), bytes(&arrays.stroke_edges), bytes(&arrays.stroke_colors),
+                    bytes(&arrays.do_fill)];
+        for (&vbo, data) in self.vbos.iter().zip(data.iter()) {
+            api.bind_buffer(gl::ARRAY_BUFFER, vbo);
+            api.buffer_data(gl::ARRAY_BUFFER, data, gl::STATIC_DRAW);
+        }
+
+        // Create the vertex array object once and set it up
+        if self.vertex_array == 0 {
+            self.vertex_array = api.gen_vertex_array();
+        }
+        api.bind_vertex_array(self.vertex_array);
+        for index in 0..self.vbos.len() {
+            api.enable_vertex_attrib_array(index as GLuint);
+        }
+        for (index, &vbo) in self.vbos.iter().enumerate() {
+            let (location, size) = (self.locations[index] as GLuint, Self::SIZES[index]);
+            api.bind_buffer(gl::ARRAY_BUFFER, vbo);
+            // the fill flags stay integers
+            if index == DO_FILL_BUFFER {
+                api.vertex_attrib_i_pointer(location, size, gl::INT);
+            } else {
+                api.vertex_attrib_pointer(location, size, gl::FLOAT);
+            }
+        }
+    }
+
+    fn delete(&self, api: &dyn GlApi) {
+        api.delete_buffers(&self.vbos);
+        api.delete_vertex_array(self.vertex_array);
+    }
 }
 
-// read text from a file into a string.
-fn read_file(file_name: &str) -> Result<String, TrdlError> {
-    let mut contents = String::new();
-    let mut f = try!(File::open(file_name));
-    try!(f.read_to_string(&mut contents));
-    Ok(contents)
+// The fill flags are the last buffer.
+const DO_FILL_BUFFER: usize = 6;
+
+// The bytes of a vertex array, as they are uploaded.
+fn bytes<T: Copy>(data: &[T]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data)) }
 }
 
 // Choose control points to represent a straight line as a Bezier curve.
@@ -895,12 +4460,16 @@ fn bezier_line_control_points(first: (GLfloat, GLfloat), last: (GLfloat, GLfloat
     (v1, (v1.0 + dx, v1.1 + dy))
 }
 
+// Control points of the edge between two polygon vertices, keyed by the vertex indices.
+type ControlPointMap = HashMap<(usize, usize), ((GLfloat, GLfloat), (GLfloat, GLfloat))>;
+
 // For straight lines, the control points are calculated from the end points,
 // for curves they have to be specified, this function figures out if control
 // points for a particular pair of end points have been previously specified
 // or calculated and reuses them, or calculates them otherwise.
-fn get_control_points(polygon: &Vec<(GLfloat, GLfloat)>, i0: usize, i1: usize, depth: GLfloat,
-        control_point_map: &mut HashMap<(usize, usize), ((GLfloat, GLfloat), (GLfloat, GLfloat))>,
+#[allow(clippy::too_many_arguments)]
+fn get_control_points(polygon: &[(GLfloat, GLfloat)], i0: usize, i1: usize, depth: GLfloat,
+        control_point_map: &mut ControlPointMap,
         vs: &mut Vec<GLfloat>, cp1s: &mut Vec<GLfloat>, cp2s: &mut Vec<GLfloat>) {
     let v0 = polygon[i0];
     let v1 = polygon[i1];
@@ -927,24 +4496,1011 @@ fn get_control_points(polygon: &Vec<(GLfloat, GLfloat)>, i0: usize, i1: usize, d
     cp2s.push(cp2.1);
 }
 
-fn push3(vec: &mut Vec<GLfloat>, value: [f32; 3]) {
-    vec.push(value[0]);
-    vec.push(value[1]);
-    vec.push(value[2]);
+// Drop ink samples that are too close to the one before, keeping the last sample, then average
+// each interior sample with its neighbours to take out pointer jitter.
+fn smooth_ink(points: &[(f32, f32, f32)]) -> Vec<(f32, f32, f32)> {
+    let mut kept: Vec<(f32, f32, f32)> = Vec::with_capacity(points.len());
+    for &p in points {
+        match kept.last() {
+            Some(&q) if (p.0 - q.0).hypot(p.1 - q.1) < INK_MIN_SPACING => (),
+            _ => kept.push(p)
+        }
+    }
+    if let (Some(&last), true) = (points.last(), kept.len() > 1) {
+        let end = kept.len() - 1;
+        kept[end] = last;
+    }
+    if kept.len() < 3 {
+        return kept;
+    }
+    let mut smoothed = kept.clone();
+    for i in 1..kept.len() - 1 {
+        let (a, b, c) = (kept[i - 1], kept[i], kept[i + 1]);
+        smoothed[i] = ((a.0 + 2f32 * b.0 + c.0) / 4f32,
+                       (a.1 + 2f32 * b.1 + c.1) / 4f32,
+                       (a.2 + 2f32 * b.2 + c.2) / 4f32);
+    }
+    smoothed
+}
+
+// Distance between the depths of neighbouring paths when there are num_paths of them, in
+// normalized device coordinates.
+fn depth_step(num_paths: usize) -> f32 {
+    TWO / (num_paths + 1) as f32
+}
+
+// The time effects are animated by: seconds since the drawing was made, or a time set by hand
+// that stands still until the clock is let go again.
+#[derive(Debug, Clone)]
+struct AnimationClock {
+    origin: Instant,
+    // the time at the origin, where the clock was let go
+    offset: f32,
+    pinned: Option<f32>,
+    // frames drawn so far
+    frame: u64
+}
+
+impl AnimationClock {
+    fn new() -> Self {
+        AnimationClock { origin: Instant::now(), offset: ZERO, pinned: None, frame: 0 }
+    }
+
+    fn time(&self) -> f32 {
+        self.pinned.unwrap_or_else(|| self.offset + self.origin.elapsed().as_secs_f32())
+    }
+
+    // Stand still at a time, non-finite times are ignored.
+    fn pin(&mut self, seconds: f32) {
+        if seconds.is_finite() {
+            self.pinned = Some(seconds);
+        }
+    }
+
+    // Run on from the pinned time without jumping.
+    fn release(&mut self) {
+        if let Some(seconds) = self.pinned.take() {
+            self.origin = Instant::now();
+            self.offset = seconds;
+        }
+    }
+}
+
+// Depth of the i-th of num_paths paths in insertion order, from near 1 for the first path (at the
+// back) to near -1 for the last.
+fn path_depth(i: usize, num_paths: usize) -> f32 {
+    ONE - (i + 1) as f32 * depth_step(num_paths)
+}
+
+// The order to draw paths in, as indices into `opaque` (which says whether each path in insertion
+// order is opaque), and how many of the first ones to draw without blending.
+fn draw_order(opaque: &[bool], sort_mode: SortMode) -> (Vec<usize>, usize) {
+    match sort_mode {
+        SortMode::InsertionOrder => ((0..opaque.len()).collect(), 0),
+        SortMode::OpaqueFirst => {
+            let mut order: Vec<usize> = (0..opaque.len()).rev().filter(|&i| opaque[i]).collect();
+            let num_opaque = order.len();
+            order.extend((0..opaque.len()).filter(|&i| !opaque[i]));
+            (order, num_opaque)
+        }
+    }
+}
+
+// Whether two (min, max) rectangles overlap, touching counts.
+fn overlaps(a: ((f32, f32), (f32, f32)), b: ((f32, f32), (f32, f32))) -> bool {
+    a.0 .0 <= b.1 .0 && b.0 .0 <= a.1 .0 && a.0 .1 <= b.1 .1 && b.0 .1 <= a.1 .1
+}
+
+// Whether rectangle a contains rectangle b.
+fn contains_rect(a: ((f32, f32), (f32, f32)), b: ((f32, f32), (f32, f32))) -> bool {
+    a.0 .0 <= b.0 .0 && a.0 .1 <= b.0 .1 && b.1 .0 <= a.1 .0 && b.1 .1 <= a.1 .1
+}
 
-    vec.push(value[0]);
-    vec.push(value[1]);
-    vec.push(value[2]);
+// push an rgba color for each of the 3 vertices of a triangle.
+fn push_color(vec: &mut Vec<GLfloat>, color: [f32; 3], alpha: f32) {
+    for _ in 0..3 {
+        vec.extend_from_slice(&[color[0], color[1], color[2], alpha]);
+    }
+}
 
-    vec.push(value[0]);
-    vec.push(value[1]);
-    vec.push(value[2]);
+// The runs of vertices from start to end that are not in hidden paths, as (start, end). The
+// paths' vertex ranges are in buffer order.
+fn visible_runs(paths: &[(PathId, usize, usize)], hidden: &BTreeSet<PathId>, start: usize, end: usize)
+    -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut run_start = start;
+    if !hidden.is_empty() {
+        for &(id, path_start, path_end) in paths {
+            if path_end <= start || path_start >= end || !hidden.contains(&id) {
+                continue;
+            }
+            if path_start > run_start {
+                runs.push((run_start, path_start));
+            }
+            run_start = run_start.max(path_end);
+        }
+    }
+    if end > run_start {
+        runs.push((run_start, end));
+    }
+    runs
 }
 
-// determine if the edge of a triangle is also an exterior edge of the polygon.
+// determine if the edge of a triangle is also an exterior edge of the polygon, whose vertices are
+// 0 to max. Vertices after those are inside the polygon.
 fn triangle_edges(i0: usize, i1: usize, i2: usize, max: usize) -> (bool, bool, bool) {
-    let e2 = i1 == 0 && i0 == max || (i1 > i0 && i1 - i0 == 1);
-    let e0 = i2 == 0 && i1 == max || (i2 > i1 && i2 - i1 == 1);
-    let e1 = i0 == 0 && i2 == max || (i0 > i2 && i0 - i2 == 1);
-    (e0, e1, e2)
+    let exterior = |a: usize, b: usize| a <= max && b <= max && (b == 0 && a == max || (b > a && b - a == 1));
+    (exterior(i1, i2), exterior(i2, i0), exterior(i0, i1))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use super::{contains_rect, depth_step, AnimationClock, draw_order, overlaps, path_depth, smooth_ink, visible_runs, GeometrySnapshot, Path,
+                PathEntry, PathGeometry, VertexBuffers, PathId, PathStyle, SortMode, StrokeAlign, LineJoin, MAX_PATHS, JOIN_FLAG,
+                INSIDE_LEFT_FLAG, ODD_STROKE_FLAG, ROUND_SHARP_FLAG, WORLD_STROKE_FLAG, StrokeUnits, shader};
+    use super::super::stroke_style::StrokeStyle;
+    use super::super::fill_pattern::FillPattern;
+    use super::super::gradient::{Fill, GradientId};
+    use super::super::gl_api::{GlCall, RecordingGl};
+    use super::super::layer::LayerId;
+    use super::super::super::angle::Angle;
+    use super::super::super::bezier;
+    use super::super::super::color::Color;
+    use super::super::super::transform::Transform2D;
+    use super::super::super::TrdlError;
+    use gl::types::GLint;
+
+    #[test]
+    fn test_spline_through() {
+        let path = Path::new((0f32, 0f32)).spline_through(&[(10f32, 10f32), (20f32, 0f32)], 0f32);
+        assert_eq!(path.vertices, vec![(0f32, 0f32), (10f32, 10f32), (20f32, 0f32)]);
+        // the curve is horizontal at the middle point
+        let cp_in = path.control_point_2s[0].unwrap();
+        let cp_out = path.control_point_1s[1].unwrap();
+        assert_eq!(cp_in.1, 10f32);
+        assert_eq!(cp_out.1, 10f32);
+        assert!(cp_in.0 < 10f32 && cp_out.0 > 10f32);
+    }
+
+    #[test]
+    fn test_nurbs_to() {
+        let path = Path::new((0f32, 0f32))
+            .nurbs_to(&[(0f32, 0f32), (5f32, 10f32), (10f32, 0f32)], &[0f32, 0f32, 0f32, 1f32, 1f32, 1f32],
+                      &[1f32, 1f32, 1f32]).unwrap();
+        // a quadratic Bezier, raised to a cubic
+        assert_eq!(path.vertices, vec![(0f32, 0f32), (10f32, 0f32)]);
+        let cp1 = path.control_point_1s[0].unwrap();
+        assert!((cp1.0 - 10f32 / 3f32).abs() < 1e-5 && (cp1.1 - 20f32 / 3f32).abs() < 1e-5);
+        assert!(Path::new((0f32, 0f32)).nurbs_to(&[(1f32, 1f32)], &[0f32, 1f32], &[1f32]).is_err());
+    }
+
+    #[test]
+    fn test_flatten() {
+        let path = Path::rectangle((0f32, 0f32), 10f32, 10f32, 0f32);
+        assert_eq!(path.flatten(0.1f32).len(), 4);
+        let circle = Path::ellipse((0f32, 0f32), 10f32, 10f32, 0f32);
+        let coarse = circle.flatten(1f32);
+        let fine = circle.flatten(0.01f32);
+        assert!(fine.len() > coarse.len());
+        for p in fine {
+            assert!((p.0.hypot(p.1) - 10f32).abs() < 0.05f32);
+        }
+    }
+
+    #[test]
+    fn test_fill_mesh() {
+        let square = Path::rectangle((0f32, 0f32), 2f32, 2f32, 0f32);
+        let mesh = square.fill_mesh(0.1f32).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert!((mesh.area() - 4f32).abs() < 1e-5);
+        let circle = Path::ellipse((0f32, 0f32), 1f32, 1f32, 0f32);
+        assert!((circle.fill_mesh(1e-3f32).unwrap().area() - ::std::f32::consts::PI).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_intersections() {
+        let square = Path::rectangle((0f32, 0f32), 2f32, 2f32, 0f32);
+        let circle = Path::ellipse((1f32, 1f32), 1f32, 1f32, 0f32);
+        let mut points = square.intersection_points(&circle);
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(points.len(), 2);
+        assert!((points[0].0).abs() < 1e-3 && (points[0].1 - 1f32).abs() < 1e-3);
+        assert!((points[1].0 - 1f32).abs() < 1e-3 && (points[1].1).abs() < 1e-3);
+        assert!(square.intersects(&circle));
+        let inside = Path::ellipse((0f32, 0f32), 0.5f32, 0.5f32, 0f32);
+        assert!(square.intersection_points(&inside).is_empty());
+        assert!(square.intersects(&inside) && inside.intersects(&square));
+        let far = Path::new((5f32, 5f32)).line_to((6f32, 6f32));
+        assert!(!square.intersects(&far));
+        // just inside the curve, between it and the chords a flattened outline would cut it with
+        let arc = circle.segment_list()[0];
+        let (x, y) = arc.point_at(1f32 / 32f32);
+        let sliver = Path::new((1f32 + (x - 1f32) * 0.9995f32, 1f32 + (y - 1f32) * 0.9995f32)).line_to((1f32, 1f32));
+        assert!(circle.intersection_points(&sliver).is_empty());
+        assert!(circle.intersects(&sliver));
+    }
+
+    #[test]
+    fn test_geometry_byte_size() {
+        let square = Path::rectangle((0f32, 0f32), 2f32, 2f32, 0f32).set_fill_color(1f32, 0f32, 0f32);
+        // two triangles, each patch vertex has 16 floats and an int
+        assert_eq!(PathGeometry::new(&square).unwrap().byte_size(), 6 * 17 * 4);
+    }
+
+    #[test]
+    fn test_rect_queries() {
+        let rect = ((0f32, 0f32), (10f32, 10f32));
+        assert!(overlaps(rect, ((10f32, 5f32), (20f32, 6f32))));
+        assert!(!overlaps(rect, ((11f32, 5f32), (20f32, 6f32))));
+        assert!(contains_rect(rect, ((1f32, 1f32), (10f32, 2f32))));
+        assert!(!contains_rect(rect, ((-1f32, 1f32), (5f32, 2f32))));
+        let style = Path::rectangle((0f32, 0f32), 1f32, 1f32, 0f32).set_stroke(1f32, 0f32, 0f32, 2).set_opacity(0.5f32).style();
+        assert_eq!(style, PathStyle { fill_color: None, fill_pattern: None, fill_gradient: None, stroke: Some(([1f32, 0f32, 0f32], 2)),
+                                       stroke_align: StrokeAlign::Center, stroke_units: StrokeUnits::Pixels,
+                                       line_join: None, miter_limit: 4f32,
+                                       round_sharp_corners: false, opacity: 0.5f32,
+                                       fill_opacity: 1f32, stroke_opacity: 1f32 });
+    }
+
+    #[test]
+    fn test_fillet_and_chamfer_corners() {
+        let square = Path::new((0f32, 0f32)).line_to((10f32, 0f32)).line_to((10f32, 10f32)).line_to((0f32, 10f32))
+            .close_path();
+        let rounded = square.clone().fillet_corners(2f32);
+        assert_eq!(rounded.vertices.len(), 8);
+        assert_eq!(&rounded.vertices[..2], &[(0f32, 2f32), (2f32, 0f32)]);
+        // the arc leaves along the edge with the circular handle length
+        let handle = 4f32 / 3f32 * (std::f32::consts::PI / 8f32).tan() * 2f32;
+        let cp1 = rounded.control_point_1s[0].unwrap();
+        assert!(cp1.0 == 0f32 && (cp1.1 - (2f32 - handle)).abs() < 1e-5);
+        assert_eq!(rounded.control_point_1s[1], None);
+
+        // cuts as big as half the edges meet in the middle of them
+        let diamond = square.chamfer_corners(20f32);
+        assert_eq!(diamond.vertices, vec![(0f32, 5f32), (5f32, 0f32), (10f32, 5f32), (5f32, 10f32)]);
+        assert_eq!(diamond.segments().len(), 4);
+
+        // the ends of an open path stay put
+        let open = Path::new((0f32, 0f32)).line_to((10f32, 0f32)).line_to((10f32, 10f32)).chamfer_corners(1f32);
+        assert_eq!(open.vertices, vec![(0f32, 0f32), (9f32, 0f32), (10f32, 1f32), (10f32, 10f32)]);
+    }
+
+    #[test]
+    fn test_sectors() {
+        let close = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4;
+        // a quarter of a circle of radius 2 from the positive y axis round to the negative x axis
+        let pie = Path::elliptical_sector((1f32, 1f32), 2f32, 2f32, 0f32, std::f32::consts::FRAC_PI_2,
+                                          std::f32::consts::FRAC_PI_2);
+        assert!(pie.is_closed);
+        assert_eq!(pie.vertices.len(), 3);
+        assert_eq!(pie.vertices[0], (1f32, 1f32));
+        assert!(close(pie.vertices[1], (1f32, 3f32)) && close(pie.vertices[2], (-1f32, 1f32)));
+        assert!(pie.control_point_1s[1].is_some());
+        assert_eq!(pie.segments().len(), 3);
+
+        // a third of a ring turning clockwise, on an ellipse turned a quarter turn
+        let ring = Path::ring_sector((0f32, 0f32), (4f32, 2f32), (2f32, 1f32), std::f32::consts::FRAC_PI_2, 0f32,
+                                     -2f32 * std::f32::consts::PI / 3f32);
+        let segments = ring.segment_list();
+        // two arcs, each split into a quarter and the rest, joined by lines
+        assert_eq!(segments.len(), 6);
+        assert!(close(ring.vertices[0], (0f32, 4f32)));
+        assert!(segments[2] == bezier::Segment::Line(ring.vertices[2], ring.vertices[3]));
+        assert!(close(ring.vertices[5], (0f32, 2f32)));
+
+        // a full turn, four quarter circles each way, comes back to where it started
+        for &sweep in &[2f32 * std::f32::consts::PI, -2f32 * std::f32::consts::PI] {
+            let whole = Path::elliptical_sector((0f32, 0f32), 2f32, 1f32, 0f32, 0f32, sweep);
+            assert_eq!(whole.segments().len(), 6);
+            assert!(close(whole.vertices[5], (2f32, 0f32)), "ends at {:?}", whole.vertices[5]);
+        }
+    }
+
+    #[test]
+    fn test_tessellation_diagnostics() {
+        let bow_tie = Path::new((0f32, 0f32)).line_to((2f32, 2f32)).line_to((2f32, 0f32)).line_to((0f32, 2f32))
+            .close_path().set_fill_color(1f32, 0f32, 0f32);
+        match PathEntry::new(bow_tie.clone(), LayerId(0), false) {
+            Err(TrdlError::NonSimplePolygon) => (),
+            other => panic!("expected NonSimplePolygon, got {:?}", other.map(|entry| entry.path))
+        }
+        match PathEntry::new(bow_tie, LayerId(0), true) {
+            Err(TrdlError::Tessellation(diagnostics)) => {
+                assert!(matches!(diagnostics.error, TrdlError::NonSimplePolygon));
+                assert_eq!(diagnostics.vertices, vec![0, 1, 2, 3]);
+                assert_eq!(diagnostics.points.len(), 4);
+            }
+            other => panic!("expected Tessellation, got {:?}", other.map(|entry| entry.path))
+        }
+    }
+
+    #[test]
+    fn test_getters() {
+        let path = Path::new((0f32, 0f32)).line_to((2f32, 0f32)).curve_to((2f32, 1f32), (1f32, 2f32), (0f32, 2f32))
+            .set_fill_color(1f32, 0f32, 0f32).set_stroke(0f32, 0f32, 1f32, 3).close_path();
+        assert_eq!(path.vertices(), &[(0f32, 0f32), (2f32, 0f32), (0f32, 2f32)][..]);
+        assert!(path.is_closed());
+        assert_eq!(path.fill(), Some([1f32, 0f32, 0f32]));
+        assert_eq!(path.stroke(), Some(([0f32, 0f32, 1f32], 3)));
+        let segments: Vec<bezier::Segment> = path.segments().collect();
+        assert_eq!(segments, vec![
+            bezier::Segment::Line((0f32, 0f32), (2f32, 0f32)),
+            bezier::Segment::Cubic((2f32, 0f32), (2f32, 1f32), (1f32, 2f32), (0f32, 2f32)),
+            bezier::Segment::Line((0f32, 2f32), (0f32, 0f32))
+        ]);
+        let open = Path::new((0f32, 0f32)).line_to((1f32, 0f32)).clear_fill_color();
+        assert!(!open.is_closed());
+        assert_eq!(open.fill(), None);
+        assert_eq!(open.segments().len(), 1);
+    }
+
+    #[test]
+    fn test_segment_editing() {
+        let square = Path::new((0f32, 0f32)).line_to((2f32, 0f32)).line_to((2f32, 2f32)).line_to((0f32, 2f32))
+            .close_path();
+        // a bump on the bottom edge, then the corner it ends at taken out again
+        let bump = bezier::Segment::Cubic((0f32, 0f32), (0f32, -1f32), (1f32, -1f32), (1f32, 0f32));
+        let bumped = square.clone().insert_segment(0, bump).build().unwrap();
+        assert_eq!(bumped.vertices(), &[(0f32, 0f32), (1f32, 0f32), (2f32, 0f32), (2f32, 2f32), (0f32, 2f32)][..]);
+        assert_eq!(bumped.segments().next(), Some(bump));
+        let unbumped = bumped.remove_segment(0).build().unwrap();
+        assert_eq!(unbumped.vertices(), square.vertices());
+        assert_eq!(unbumped.segments().next(), Some(bezier::Segment::Line((0f32, 0f32), (2f32, 0f32))));
+
+        // taking out the closing segment closes the path from the vertex before it
+        let triangle = square.clone().remove_segment(3).build().unwrap();
+        assert_eq!(triangle.vertices(), &[(2f32, 0f32), (2f32, 2f32), (0f32, 2f32)][..]);
+        assert_eq!(triangle.segments().last(), Some(bezier::Segment::Line((0f32, 2f32), (2f32, 0f32))));
+
+        // moving a vertex carries the control points next to it along
+        let curve = Path::new((0f32, 0f32)).curve_to((0f32, 1f32), (1f32, 1f32), (1f32, 0f32))
+            .curve_to((1f32, -1f32), (2f32, -1f32), (2f32, 0f32)).set_vertex(1, (1f32, 2f32)).build().unwrap();
+        let segments: Vec<bezier::Segment> = curve.segments().collect();
+        assert_eq!(segments[0], bezier::Segment::Cubic((0f32, 0f32), (0f32, 1f32), (1f32, 3f32), (1f32, 2f32)));
+        assert_eq!(segments[1], bezier::Segment::Cubic((1f32, 2f32), (1f32, 1f32), (2f32, -1f32), (2f32, 0f32)));
+
+        for path in [square.clone().insert_segment(4, bump), square.clone().insert_segment(1, bump),
+                     triangle.remove_segment(0), square.clone().set_vertex(4, (0f32, 0f32))].iter() {
+            assert!(path.clone().build().is_err());
+        }
+    }
+
+    #[test]
+    fn test_split_at() {
+        let square = Path::new((0f32, 0f32)).line_to((2f32, 0f32)).line_to((2f32, 2f32)).line_to((0f32, 2f32))
+            .close_path().set_stroke(0f32, 0f32, 0f32, 2);
+        let (before, after) = square.split_at(0.375f32);
+        assert_eq!(before.vertices(), &[(0f32, 0f32), (2f32, 0f32), (2f32, 1f32)][..]);
+        assert_eq!(after.vertices(), &[(2f32, 1f32), (2f32, 2f32), (0f32, 2f32), (0f32, 0f32)][..]);
+        assert!(!before.is_closed() && !after.is_closed());
+        assert_eq!(after.stroke(), square.stroke());
+        assert!((before.length() + after.length() - square.length()).abs() < 1e-4);
+
+        let curve = Path::new((0f32, 0f32)).curve_to((0f32, 1f32), (1f32, 1f32), (1f32, 0f32));
+        let close = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).hypot(a.1 - b.1) < 1e-5;
+        let (head, tail) = curve.split_at(0.5f32);
+        assert!(close(head.vertices()[1], curve.point_at(0.5f32).position));
+        assert!(close(tail.point_at(0.5f32).position, curve.point_at(0.75f32).position));
+    }
+
+    #[test]
+    fn test_reverse_and_join() {
+        let curve = Path::new((0f32, 0f32)).line_to((1f32, 0f32)).curve_to((1f32, 1f32), (2f32, 1f32), (2f32, 0f32));
+        let reversed = curve.reversed();
+        assert_eq!(reversed.vertices(), &[(2f32, 0f32), (1f32, 0f32), (0f32, 0f32)][..]);
+        assert_eq!(reversed.segments().next(),
+                   Some(bezier::Segment::Cubic((2f32, 0f32), (2f32, 1f32), (1f32, 1f32), (1f32, 0f32))));
+        assert_eq!(reversed.reversed(), curve);
+
+        let triangle = Path::new((0f32, 0f32)).line_to((1f32, 0f32)).line_to((0f32, 1f32)).close_path();
+        let flipped = triangle.reversed();
+        assert_eq!(flipped.vertices(), &[(0f32, 0f32), (0f32, 1f32), (1f32, 0f32)][..]);
+        assert!(bezier::polygon_area(flipped.vertices()) < 0f32);
+
+        let tail = Path::new((2f32, 0f32)).line_to((3f32, 0f32));
+        let appended = curve.clone().append(&tail).build().unwrap();
+        assert_eq!(appended.vertices().len(), 4);
+        assert_eq!(appended.segments().len(), 3);
+        let connected = curve.clone().connect(&Path::new((2f32, 1f32)).line_to((3f32, 1f32))).build().unwrap();
+        assert_eq!(connected.vertices(), &[(0f32, 0f32), (1f32, 0f32), (2f32, 0f32), (2f32, 1f32), (3f32, 1f32)][..]);
+        assert_eq!(curve.clone().connect(&tail), appended);
+
+        assert!(curve.clone().append(&Path::new((5f32, 5f32)).line_to((6f32, 6f32))).build().is_err());
+        assert!(curve.append(&triangle).build().is_err());
+    }
+
+    #[test]
+    fn test_stamps_along() {
+        let close = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).hypot(a.1 - b.1) < 1e-4;
+        let tick = Path::new((0f32, -1f32)).line_to((0f32, 1f32));
+        let line = Path::new((0f32, 0f32)).line_to((10f32, 0f32)).line_to((10f32, 10f32));
+        let stamps = line.stamps_along(&tick, 5f32, true);
+        assert_eq!(stamps.len(), 5);
+        assert!(close(stamps[1].vertices()[0], (5f32, -1f32)));
+        // past the corner the ticks turn with the path
+        assert!(close(stamps[3].vertices()[0], (11f32, 5f32)));
+        assert!(close(line.stamps_along(&tick, 5f32, false)[3].vertices()[0], (10f32, 4f32)));
+
+        let square = Path::rectangle((0f32, 0f32), 4f32, 4f32, 0f32);
+        assert_eq!(square.stamps_along(&tick, 2f32, false).len(), 8);
+        assert!(square.stamps_along(&tick, 0f32, false).is_empty());
+    }
+
+    #[test]
+    fn test_build() {
+        let square = Path::new((0f32, 0f32)).line_to((1f32, 0f32)).line_to((1f32, 1f32)).close_path();
+        assert!(square.clone().build().is_ok());
+        let cases = [
+            (Path::new((0f32, 0f32)).line_to((1f32, 0f32)).close_path(), "close_path"),
+            (Path::new((0f32, 0f32)).arc_to(f32::NAN, 1f32, 0f32, (1f32, 1f32), false, true), "arc_to"),
+            (square.clone().line_to((2f32, 2f32)), "line_to"),
+            (square.close_path(), "close_path")
+        ];
+        for (path, expected) in cases.iter().cloned() {
+            match path.build() {
+                Err(TrdlError::InvalidPath(call, _)) if call == expected => (),
+                other => panic!("expected InvalidPath from {}, got {:?}", expected, other)
+            }
+        }
+        let squashed = Path::rectangle((0f32, 0f32), 1f32, 1f32, 0f32)
+            .transformed(&Transform2D::scaling(f32::INFINITY, 1f32));
+        match PathGeometry::new(&squashed) {
+            Err(TrdlError::NonFiniteCoordinate) => (),
+            other => panic!("expected NonFiniteCoordinate, got {:?}", other)
+        }
+        // only the first problem is kept, and adding the path reports it
+        let path = Path::new((f32::INFINITY, 0f32)).line_to((1f32, 0f32)).close_path();
+        match PathGeometry::new(&path) {
+            Err(TrdlError::InvalidPath("new", _)) => (),
+            other => panic!("expected InvalidPath from new, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_tolerance_scales() {
+        // a tiny arc in normalized coordinates is still an arc
+        let arc = Path::new((0f32, 0f32)).arc_to(1e-6f32, 1e-6f32, 0f32, (2e-6f32, 0f32), false, true);
+        assert!(arc.control_point_1s[0].is_some());
+        // and a corner thousands of units out is still cut
+        let far = Path::new((5000f32, 0f32)).line_to((5000.5f32, 0f32)).line_to((5000.5f32, 0.5f32))
+            .chamfer_corners(0.1f32);
+        assert_eq!(far.vertices.len(), 4);
+        // a radius lost in rounding next to such coordinates makes a line
+        let line = Path::new((5000f32, 0f32)).arc_to(1e-4f32, 1e-4f32, 0f32, (5000f32, 1e-4f32), false, true);
+        assert_eq!(line.control_point_1s, vec![None]);
+    }
+
+    // Flatten arcs for every combination of flags, rotations and sweeps and check them against the
+    // ellipse they come from: on it, in the right direction and the long or short way round.
+    #[test]
+    fn test_arc_to_accuracy() {
+        use std::f32::consts::PI;
+        let (x_radius, y_radius) = (40f32, 20f32);
+        for &rotation in &[0f32, 0.3f32, PI / 2f32, 2f32] {
+            // end points at these angles round the ellipse centered on the origin cover sweeps
+            // in every quadrant, including more than 270 degrees the long way round
+            for &end_angle in &[0.4f32, 1.2f32, 2f32, 3f32, 4f32, 5f32, 5.9f32] {
+                let on_ellipse = |t: f32| {
+                    let (u, v) = (x_radius * t.cos(), y_radius * t.sin());
+                    (u * rotation.cos() - v * rotation.sin(), u * rotation.sin() + v * rotation.cos())
+                };
+                let (start, end) = (on_ellipse(0f32), on_ellipse(end_angle));
+                for &(is_large_arc, is_positive_sweep) in &[(false, false), (false, true), (true, false), (true, true)] {
+                    let path = Path::new(start).arc_to(x_radius, y_radius, rotation, end, is_large_arc,
+                                                       is_positive_sweep);
+                    let last = path.vertices[path.vertices.len() - 1];
+                    assert!((last.0 - end.0).hypot(last.1 - end.1) < 1e-3, "arc ends at {:?}, not {:?}", last, end);
+                    let center = Path::new(start).get_ellipse_params(x_radius, y_radius, rotation, end,
+                                                                     is_large_arc, is_positive_sweep).unwrap().0;
+                    let mut turned = 0f32;
+                    let mut previous = None;
+                    for p in path.flatten(0.01f32) {
+                        // in the ellipse's own frame it is a unit circle
+                        let (dx, dy) = (p.0 - center.0, p.1 - center.1);
+                        let u = (dx * rotation.cos() + dy * rotation.sin()) / x_radius;
+                        let v = (-dx * rotation.sin() + dy * rotation.cos()) / y_radius;
+                        assert!((u.hypot(v) - 1f32).abs() < 1e-3, "{:?} is off the ellipse by {}", p,
+                                u.hypot(v) - 1f32);
+                        let angle = v.atan2(u);
+                        if let Some(before) = previous {
+                            let mut step = angle - before;
+                            if step > PI { step -= 2f32 * PI; }
+                            if step < -PI { step += 2f32 * PI; }
+                            turned += step;
+                        }
+                        previous = Some(angle);
+                    }
+                    let case = (rotation, end_angle, is_large_arc, is_positive_sweep);
+                    assert_eq!(turned > 0f32, is_positive_sweep, "{:?} turned {}", case, turned);
+                    assert_eq!(turned.abs() > PI, is_large_arc, "{:?} turned {}", case, turned);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_angles_in_degrees() {
+        assert_eq!(Path::rectangle((0f32, 0f32), 4f32, 2f32, Angle::degrees(30f32)),
+                   Path::rectangle((0f32, 0f32), 4f32, 2f32, 30f32.to_radians()));
+        let radians = Path::elliptical_sector((0f32, 0f32), 2f32, 1f32, 0f32, 0f32, std::f32::consts::FRAC_PI_2);
+        let degrees = Path::elliptical_sector((0f32, 0f32), 2f32, 1f32, Angle::degrees(0f32), Angle::default(),
+                                              Angle::degrees(90f32));
+        assert_eq!(radians.vertices.len(), degrees.vertices.len());
+        assert!((radians.vertices[2].0 - degrees.vertices[2].0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_tight_bounds() {
+        // a rotated ellipse's control points stick out past the curve
+        let path = Path::ellipse((0f32, 0f32), 10f32, 5f32, Angle::degrees(45f32));
+        let ((min_x, min_y), (max_x, max_y)) = path.tight_bounds();
+        // sqrt(a^2 cos^2 + b^2 sin^2) either way at 45 degrees
+        let reach = (62.5f32).sqrt();
+        assert!((max_x - reach).abs() < 0.05f32 && (min_x + reach).abs() < 0.05f32);
+        assert!((max_y - reach).abs() < 0.05f32 && (min_y + reach).abs() < 0.05f32);
+        let ((fast_min_x, _), (fast_max_x, _)) = path.fast_bounds();
+        assert!(fast_min_x < min_x && fast_max_x > max_x);
+        assert_eq!(Path::new((1f32, 2f32)).tight_bounds(), ((1f32, 2f32), (1f32, 2f32)));
+    }
+
+    #[test]
+    fn test_full_ellipse_arc() {
+        let start = (10f32, 5f32);
+        let path = Path::new(start).arc_to(4f32, 2f32, 0f32, start, false, true).close_path();
+        // four quarters, ending on the start so closing adds nothing
+        assert_eq!(path.segments().len(), 4);
+        assert!(path.control_point_1s.iter().all(|cp| cp.is_some()));
+        let ((min_x, min_y), (max_x, max_y)) = path.bounds();
+        assert!((min_x - 2f32).abs() < 1e-3 && (max_x - 10f32).abs() < 1e-3);
+        assert!((min_y - 3f32).abs() < 1e-2 && (max_y - 7f32).abs() < 1e-2);
+        assert!(path.clone().build().is_ok());
+        // clockwise goes below the start first
+        let clockwise = Path::new(start).ellipse_from_current(4f32, 2f32, 0f32, false);
+        assert!(clockwise.vertices[1].1 < 5f32);
+        assert!(Path::new(start).ellipse_from_current(0f32, 2f32, 0f32, true).build().is_err());
+    }
+
+    #[test]
+    fn test_arc_to_scales_small_radii() {
+        // radii too small to reach are scaled up until the arc is half an ellipse
+        let path = Path::new((0f32, 0f32)).arc_to(1f32, 1f32, 0f32, (10f32, 0f32), false, true);
+        let last = path.vertices[path.vertices.len() - 1];
+        assert!((last.0 - 10f32).abs() < 1e-4 && last.1.abs() < 1e-4);
+        let lowest = path.flatten(0.01f32).iter().map(|p| p.1).fold(0f32, f32::min);
+        assert!((lowest + 5f32).abs() < 1e-2, "lowest point {}", lowest);
+    }
+
+    #[test]
+    fn test_transformed() {
+        let path = Path::new((1f32, 0f32)).curve_to((1f32, 1f32), (2f32, 1f32), (2f32, 0f32));
+        let moved = path.transformed(&Transform2D::translation(1f32, 2f32));
+        assert_eq!(moved.vertices, vec![(2f32, 2f32), (3f32, 2f32)]);
+        assert_eq!(moved.control_point_1s[0], Some((2f32, 3f32)));
+        assert_eq!(moved.control_point_2s[0], Some((3f32, 3f32)));
+        assert_eq!(moved.stroke, path.stroke);
+        assert_eq!(path.translated(1f32, 2f32), moved);
+
+        let close = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).hypot(a.1 - b.1) < 1e-5;
+        let turned = path.rotated(Angle::degrees(90f32), (1f32, 0f32));
+        assert!(close(turned.vertices[0], (1f32, 0f32)));
+        assert!(close(turned.vertices[1], (1f32, 1f32)));
+        assert!(close(turned.control_point_1s[0].unwrap(), (0f32, 0f32)));
+        let stretched = path.scaled(2f32, 3f32, (1f32, 0f32));
+        assert_eq!(stretched.vertices, vec![(1f32, 0f32), (3f32, 0f32)]);
+        assert_eq!(stretched.control_point_2s[0], Some((3f32, 3f32)));
+    }
+
+    #[test]
+    fn test_point_at() {
+        let path = Path::new((0f32, 0f32)).line_to((10f32, 0f32)).line_to((10f32, 30f32));
+        let p = path.point_at(0.25f32);
+        assert_eq!(p.position, (5f32, 0f32));
+        assert_eq!(p.tangent, (1f32, 0f32));
+        assert_eq!(p.normal, (0f32, 1f32));
+        assert_eq!(path.length(), 40f32);
+        let p = path.point_at_length(25f32);
+        assert_eq!(p.position, (10f32, 15f32));
+        assert_eq!(p.tangent, (0f32, 1f32));
+    }
+
+    #[test]
+    fn test_smooth_ink() {
+        let samples = [(0f32, 0f32, 1f32), (0.1f32, 0f32, 1f32), (10f32, 2f32, 0f32),
+                       (20f32, 0f32, 0f32), (20.2f32, 0f32, 0f32)];
+        let smoothed = smooth_ink(&samples);
+        assert_eq!(smoothed.len(), 3);
+        assert_eq!(smoothed[0], (0f32, 0f32, 1f32));
+        // the dropped (20, 0) sample made way for the last one
+        assert_eq!(smoothed[1], (10.05f32, 1f32, 0.25f32));
+        assert_eq!(smoothed[2], (20.2f32, 0f32, 0f32));
+    }
+
+    #[test]
+    fn test_from_ink_points() {
+        let samples: Vec<(f32, f32, f32)> = (0..10).map(|i| (i as f32 * 3f32, 0f32, 0.5f32)).collect();
+        let path = Path::from_ink_points(&samples).unwrap();
+        assert_eq!(path.vertices.len(), 10);
+        assert!(path.control_point_1s.iter().all(|cp| cp.is_some()));
+        assert!(Path::from_ink_points(&[]).is_err());
+
+        let outline = Path::from_ink_points_with_pressure(&samples, 2f32, 6f32).unwrap();
+        assert!(outline.is_closed);
+        assert_eq!(outline.vertices.len(), 20);
+        // half of a width of 4 on either side
+        assert_eq!(outline.vertices[0], (0f32, 2f32));
+        assert_eq!(outline.vertices[10], (27f32, -2f32));
+    }
+
+    #[test]
+    fn test_path_depths_are_ordered() {
+        for &n in &[1, 2, 1000, MAX_PATHS] {
+            assert!(path_depth(0, n) < 1f32);
+            assert!(path_depth(n - 1, n) > -1f32);
+            // the stroke of each path sits between it and the next path, even at the limit
+            for i in [0, n / 2, n.saturating_sub(2)].iter().cloned().filter(|&i| i + 1 < n) {
+                let stroke = path_depth(i, n) - depth_step(n) / 2f32;
+                assert!(stroke < path_depth(i, n));
+                assert!(path_depth(i + 1, n) < stroke);
+            }
+        }
+    }
+
+    #[test]
+    fn test_visible_runs() {
+        let paths = [(PathId(1), 0, 6), (PathId(2), 6, 9), (PathId(3), 9, 15), (PathId(4), 15, 18)];
+        let hidden: BTreeSet<PathId> = [PathId(2), PathId(4)].iter().cloned().collect();
+        assert_eq!(visible_runs(&paths, &hidden, 0, 18), vec![(0, 6), (9, 15)]);
+        assert_eq!(visible_runs(&paths, &hidden, 6, 9), vec![]);
+        assert_eq!(visible_runs(&paths, &BTreeSet::new(), 3, 12), vec![(3, 12)]);
+    }
+
+    #[test]
+    fn test_geometry_snapshot() {
+        let square = PathGeometry::new(&Path::rectangle((0f32, 0f32), 10f32, 10f32, 0f32)
+            .set_fill_color(1f32, 0f32, 0f32)).unwrap();
+        let line = PathGeometry::new(&Path::new((0f32, 0f32)).line_to((10f32, 0f32))
+            .set_stroke(0f32, 0f32, 1f32, 2)).unwrap();
+        let mut snapshot = GeometrySnapshot::default();
+        snapshot.push(&square, 0.5f32, None);
+        snapshot.push(&line, 0.25f32, Some([0f32; super::fill_pattern::RECORD_FLOATS]));
+        let split = square.vertices.len() / 3;
+        assert_eq!(snapshot.num_vertices(), split + line.vertices.len() / 3);
+        assert_eq!(snapshot.num_patches() * 3, snapshot.num_vertices());
+        // depths are filled in, everything else is copied as is
+        assert!(snapshot.vertices.chunks(3).take(split).all(|v| v[2] == 0.5f32));
+        assert!(snapshot.vertices.chunks(3).skip(split).all(|v| v[2] == 0.25f32));
+        assert_eq!(&snapshot.fill_colors[..square.fill_colors.len()], &square.fill_colors[..]);
+        assert_eq!(&snapshot.stroke_edges[split..], &line.stroke_edges[..]);
+        // only the patterned path points to a record
+        assert_eq!(&snapshot.do_fill[..split], &square.do_fill[..]);
+        assert!(snapshot.do_fill[split..].iter().all(|&flags| flags >> super::fill_pattern::PATTERN_SHIFT == 1));
+        snapshot.clear();
+        assert_eq!(snapshot, GeometrySnapshot::default());
+    }
+
+    #[test]
+    fn test_vertex_buffers() {
+        let api = RecordingGl::new();
+        let mut buffers = VertexBuffers::new(&api, 1);
+        assert_eq!(buffers.vbos, [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(buffers.locations, [0, 1, 2, 3, 4, 5, 6]);
+        let mut arrays = GeometrySnapshot::default();
+        let square = PathGeometry::new(&Path::rectangle((0f32, 0f32), 10f32, 10f32, 0f32)
+            .set_fill_color(1f32, 0f32, 0f32)).unwrap();
+        arrays.push(&square, 0.5f32, None);
+        api.reset_calls();
+        buffers.upload(&api, &arrays);
+        let calls = api.calls();
+        // every buffer is filled, then the vertex array is made once
+        assert_eq!(calls[1], GlCall::BufferData(gl::ARRAY_BUFFER, arrays.vertices.len() * 4, gl::STATIC_DRAW));
+        assert_eq!(calls[13], GlCall::BufferData(gl::ARRAY_BUFFER, arrays.do_fill.len() * 4, gl::STATIC_DRAW));
+        assert_eq!(calls[14], GlCall::GenVertexArray(8));
+        assert_eq!(calls.last(), Some(&GlCall::VertexAttribIPointer(6, 1, gl::INT)));
+        api.reset_calls();
+        buffers.upload(&api, &GeometrySnapshot::default());
+        assert!(!api.calls().contains(&GlCall::GenVertexArray(9)));
+        assert!(api.calls().contains(&GlCall::BufferData(gl::ARRAY_BUFFER, 0, gl::STATIC_DRAW)));
+        api.reset_calls();
+        buffers.delete(&api);
+        assert_eq!(api.calls(), vec![GlCall::DeleteBuffers(vec![1, 2, 3, 4, 5, 6, 7]), GlCall::DeleteVertexArray(8)]);
+    }
+
+    #[test]
+    fn test_draw_order() {
+        let opaque = [true, false, true, true, false];
+        assert_eq!(draw_order(&opaque, SortMode::OpaqueFirst), (vec![3, 2, 0, 1, 4], 3));
+        assert_eq!(draw_order(&opaque, SortMode::InsertionOrder), (vec![0, 1, 2, 3, 4], 0));
+    }
+
+    #[test]
+    fn test_contains_point() {
+        let circle = Path::ellipse((0f32, 0f32), 10f32, 10f32, 0f32);
+        // just inside and just outside the curve, where a coarse outline cuts across
+        let angle = std::f32::consts::PI / 8f32;
+        let (c, s) = (angle.cos(), angle.sin());
+        assert!(circle.contains_point((9.95f32 * c, 9.95f32 * s), 0f32));
+        assert!(!circle.contains_point((10.05f32 * c, 10.05f32 * s), 0f32));
+        assert!(circle.contains_point((10.05f32 * c, 10.05f32 * s), 0.1f32));
+        assert_eq!(circle.winding_number((0f32, 0f32)).abs(), 1);
+        // an open path is closed by a straight line
+        let corner = Path::new((0f32, 0f32)).line_to((10f32, 0f32)).line_to((10f32, 10f32));
+        assert!(corner.contains_point((8f32, 2f32), 0f32));
+        assert!(!corner.contains_point((2f32, 8f32), 0f32));
+    }
+
+    #[test]
+    fn test_hit_test_fill() {
+        let path = Path::rectangle((50f32, 50f32), 20f32, 10f32, 0f32).set_fill_color(1f32, 0f32, 0f32);
+        assert!(path.hit_test((50f32, 50f32), 1f32));
+        assert!(path.hit_test((59f32, 54f32), 1f32));
+        assert!(!path.hit_test((61f32, 50f32), 1f32));
+    }
+
+    #[test]
+    fn test_hit_test_stroke() {
+        let path = Path::new((0f32, 0f32)).line_to((100f32, 0f32)).set_stroke(0f32, 0f32, 0f32, 4);
+        assert!(path.hit_test((50f32, 2.5f32), 1f32));
+        assert!(!path.hit_test((50f32, 5f32), 1f32));
+        // zoomed out, a pixel covers more of the drawing
+        assert!(path.hit_test((50f32, 5f32), 2f32));
+    }
+
+    #[test]
+    fn test_stroke_align() {
+        let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_stroke(0f32, 0f32, 0f32, 4);
+        let inside = square.clone().set_stroke_align(StrokeAlign::Inside);
+        assert!(inside.hit_test((7f32, 0f32), 1f32));
+        assert!(!inside.hit_test((12.5f32, 0f32), 1f32));
+        let outside = square.clone().set_stroke_align(StrokeAlign::Outside);
+        assert!(!outside.hit_test((7f32, 0f32), 1f32));
+        assert!(outside.hit_test((14f32, 0f32), 1f32));
+        assert!(square.hit_test((11.5f32, 0f32), 1f32));
+        // the alignment goes to the shaders with the fill flag
+        let geometry = PathGeometry::new(&outside.set_fill_color(1f32, 1f32, 1f32)).unwrap();
+        assert!(geometry.do_fill.iter().all(|&flags| flags == 5));
+    }
+
+    #[test]
+    fn test_line_joins() {
+        let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_stroke(0f32, 0f32, 0f32, 4)
+            .set_line_join(LineJoin::Round).set_miter_limit(0.5f32);
+        assert_eq!(square.style().miter_limit, 1f32);
+        // two triangles then a join patch for each corner, the inside on the left
+        let geometry = PathGeometry::new(&square).unwrap();
+        assert_eq!(geometry.do_fill.len(), 6 + 4 * 3);
+        assert!(geometry.do_fill[6..].iter().all(|&flags| flags == JOIN_FLAG | INSIDE_LEFT_FLAG | 64));
+        assert_eq!(&geometry.vertices[9 * 2..9 * 3], &[-10f32, 10f32, 0f32, -10f32, -10f32, 0f32, 10f32, -10f32, 0f32]);
+        assert_eq!(&geometry.stroke_edges[6..9], &[4f32, 1f32, 0f32]);
+        // no join where a polyline goes straight on
+        let polyline = Path::new((0f32, 0f32)).line_to((10f32, 0f32)).line_to((20f32, 0f32)).line_to((20f32, 10f32))
+            .set_stroke(0f32, 0f32, 0f32, 2).set_line_join(LineJoin::Miter);
+        assert_eq!(PathGeometry::new(&polyline).unwrap().do_fill.len(), 3 * 3 + 3);
+    }
+
+    #[test]
+    fn test_stroke_style() {
+        let gradient = StrokeStyle::Gradient(vec![(0f32, Color::rgb(1f32, 0f32, 0f32)), (1f32, Color::rgb(0f32, 0f32, 1f32))]);
+        let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_stroke(0f32, 0f32, 0f32, 4)
+            .set_stroke_align(StrokeAlign::Inside).set_stroke_style(gradient);
+        assert!(square.is_opaque());
+        // two unstroked fill triangles, then a piece for each side with its third point inside
+        let geometry = PathGeometry::new(&square).unwrap();
+        assert_eq!(geometry.do_fill.len(), 6 + 4 * 3);
+        assert!(geometry.stroke_edges[..6].iter().all(|&edge| edge == 0f32));
+        assert_eq!(&geometry.stroke_edges[6..9], &[0f32, 0f32, 4f32]);
+        assert_eq!(&geometry.vertices[18..27], &[-10f32, -10f32, 0f32, 10f32, -10f32, 0f32, 0f32, 0f32, 0f32]);
+        assert_eq!(&geometry.stroke_colors[24..28], &[1f32, 0f32, 0f32, 1f32]);
+        assert!(geometry.do_fill[6..].iter().all(|&flags| flags == StrokeAlign::Inside.flags()));
+        // gaps in a pattern show what is below
+        let dashes = StrokeStyle::Pattern(vec![(1f32, Color::rgb(0f32, 0f32, 0f32)), (1f32, Color::rgba(0f32, 0f32, 0f32, 0f32))]);
+        assert!(!square.set_stroke_style(dashes).is_opaque());
+    }
+
+    #[test]
+    fn test_recolor() {
+        let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_fill_color(0f32, 1f32, 0f32)
+            .set_stroke(0f32, 0f32, 0f32, 2).set_opacity(0.5f32);
+        let mut geometry = PathGeometry::new(&square).unwrap();
+        geometry.recolor(Some([1f32, 0f32, 0f32]), None);
+        let recolored = square.clone().set_fill_color(1f32, 0f32, 0f32);
+        assert_eq!(geometry.fill_colors, PathGeometry::new(&recolored).unwrap().fill_colors);
+        geometry.recolor(None, Some([0f32, 0f32, 1f32]));
+        let recolored = recolored.set_stroke(0f32, 0f32, 1f32, 2);
+        assert_eq!(geometry.stroke_colors, PathGeometry::new(&recolored).unwrap().stroke_colors);
+    }
+
+    #[test]
+    fn test_fill_and_stroke_opacity() {
+        let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_fill_color(0f32, 1f32, 0f32)
+            .set_stroke(0f32, 0f32, 0f32, 2).set_opacity(0.5f32).set_stroke_opacity(0.5f32);
+        assert!(!square.is_opaque());
+        let geometry = PathGeometry::new(&square).unwrap();
+        assert_eq!(&geometry.fill_colors[..4], &[0f32, 1f32, 0f32, 0.5f32]);
+        assert_eq!(&geometry.stroke_colors[..4], &[0f32, 0f32, 0f32, 0.25f32]);
+        // a faded stroke only keeps the path from hiding what is below if it has one
+        let filled = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_fill_color(0f32, 1f32, 0f32);
+        assert!(filled.clone().set_stroke_opacity(0f32).is_opaque());
+        assert!(!filled.set_fill_opacity(0.9f32).is_opaque());
+    }
+
+    #[test]
+    fn test_fill_gradient() {
+        let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32);
+        let gradient = square.clone().set_fill_pattern(FillPattern::Checker { size: 1f32, angle: 0f32 }, Color::rgb(1f32, 1f32, 1f32))
+            .set_fill(Fill::Gradient(GradientId { namespace: 0, slot: 0 }));
+        assert_eq!(gradient.fill_gradient(), Some(GradientId { namespace: 0, slot: 0 }));
+        assert_eq!(gradient.style().fill_pattern, None);
+        assert!(gradient.fill().is_some() && !gradient.has_flat_fill() && !gradient.is_opaque());
+        // a flat color replaces the gradient, its alpha fading the fill alone
+        let flat = gradient.set_fill(Fill::Color(Color::rgba(1f32, 0f32, 0f32, 0.5f32)));
+        assert_eq!(flat.fill_gradient(), None);
+        assert_eq!(flat, square.set_fill_color(1f32, 0f32, 0f32).set_fill_opacity(0.5f32));
+    }
+
+    #[test]
+    fn test_fill_pattern() {
+        let hatch = FillPattern::Hatch { spacing: 4f32, angle: 0.78f32, width: 1f32 };
+        let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_fill_color(0f32, 0f32, 0f32);
+        let patterned = square.clone().set_fill_pattern(hatch, Color::rgb(1f32, 1f32, 1f32));
+        assert!(patterned.is_opaque());
+        assert_eq!(patterned.style().fill_pattern, Some((hatch, Color::rgb(1f32, 1f32, 1f32))));
+        assert_eq!(patterned.clear_fill_pattern(), square);
+        // the fill shows through between the lines
+        let clear = square.set_fill_pattern(hatch, Color::rgba(0f32, 0f32, 0f32, 0f32));
+        assert!(!clear.is_opaque());
+    }
+
+    #[test]
+    fn test_vertex_colors() {
+        let (red, blue) = (Color::rgb(1f32, 0f32, 0f32), Color::rgb(0f32, 0f32, 1f32));
+        let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_fill_color(0f32, 1f32, 0f32)
+            .set_stroke(0f32, 0f32, 0f32, 2).set_vertex_colors(&[red]).add_color_point((1f32, 1f32), blue);
+        // two triangles, one split in three at the color point
+        let geometry = PathGeometry::new(&square).unwrap();
+        assert_eq!(geometry.do_fill.len(), 4 * 3);
+        let corner_color = |corner: (f32, f32)| {
+            let k = geometry.vertices.chunks(3).position(|v| (v[0], v[1]) == corner).unwrap();
+            geometry.fill_colors[4 * k..4 * k + 4].to_vec()
+        };
+        assert_eq!(corner_color((-10f32, -10f32)), vec![1f32, 0f32, 0f32, 1f32]);
+        assert_eq!(corner_color((10f32, 10f32)), vec![0f32, 1f32, 0f32, 1f32]);
+        assert_eq!(corner_color((1f32, 1f32)), vec![0f32, 0f32, 1f32, 1f32]);
+        // only the outline is stroked
+        assert_eq!(geometry.stroke_edges.iter().filter(|&&edge| edge > 0f32).count(), 4);
+        assert!(PathGeometry::new(&square.add_color_point((30f32, 0f32), blue)).is_err());
+    }
+
+    #[test]
+    fn test_curved_fill_coverage() {
+        // the small triangles a patch is cut into by the tessellation shaders, mapped through
+        // the Bezier triangle of its corners and control points
+        let patch_triangles = |geometry: &PathGeometry, k: usize| {
+            let point = |i: usize| (geometry.vertices[3 * i], geometry.vertices[3 * i + 1]);
+            let cp1 = |i: usize| (geometry.control_point_1s[2 * i], geometry.control_point_1s[2 * i + 1]);
+            let cp2 = |i: usize| (geometry.control_point_2s[2 * i], geometry.control_point_2s[2 * i + 1]);
+            let v = 3 * k;
+            let p = [point(v), cp1(v), cp2(v), point(v + 1), cp1(v + 1), cp2(v + 1), point(v + 2),
+                     cp1(v + 2), cp2(v + 2)];
+            let ce = p.iter().fold((0f32, 0f32), |sum, q| (sum.0 + q.0 / 9f32, sum.1 + q.1 / 9f32));
+            let at = |s: f32, t: f32| {
+                let u = 1f32 - s - t;
+                let w = [s * s * s, 3f32 * s * s * t, 3f32 * s * t * t, t * t * t, 3f32 * t * t * u,
+                         3f32 * t * u * u, u * u * u, 3f32 * u * u * s, 3f32 * u * s * s];
+                let q = (0..9).fold((0f32, 0f32), |sum, i| (sum.0 + w[i] * p[i].0, sum.1 + w[i] * p[i].1));
+                (q.0 + 6f32 * s * t * u * ce.0, q.1 + 6f32 * s * t * u * ce.1)
+            };
+            let n = 16;
+            let step = 1f32 / n as f32;
+            let mut triangles = Vec::new();
+            for i in 0..n {
+                for j in 0..n - i {
+                    let (s, t) = (i as f32 * step, j as f32 * step);
+                    triangles.push(vec![at(s, t), at(s + step, t), at(s, t + step)]);
+                    if i + j + 1 < n {
+                        triangles.push(vec![at(s + step, t), at(s + step, t + step), at(s, t + step)]);
+                    }
+                }
+            }
+            triangles
+        };
+        // the fraction of a grid over the path where the patches and the true outline disagree
+        let miss = |path: &Path| {
+            let geometry = PathGeometry::new(path).unwrap();
+            let triangles: Vec<Vec<(f32, f32)>> = (0..geometry.vertices.len() / 9)
+                .flat_map(|k| patch_triangles(&geometry, k)).collect();
+            let outline = path.outline(256);
+            let ((x0, y0), (x1, y1)) = path.bounds();
+            let n = 50;
+            let mut misses = 0;
+            for i in 0..n {
+                for j in 0..n {
+                    let q = (x0 + (x1 - x0) * (i as f32 + 0.5f32) / n as f32,
+                             y0 + (y1 - y0) * (j as f32 + 0.5f32) / n as f32);
+                    let covered = triangles.iter().any(|triangle| bezier::polygon_contains(triangle, q));
+                    if covered != bezier::polygon_contains(&outline, q) {
+                        misses += 1;
+                    }
+                }
+            }
+            misses as f32 / (n * n) as f32
+        };
+
+        let circle = Path::ellipse((0f32, 0f32), 10f32, 10f32, 0f32).set_fill_color(1f32, 0f32, 0f32);
+        assert!(miss(&circle) < 0.01f32, "circle misses {}", miss(&circle));
+        // a curve bulging into a square far enough to cross the diagonal of its triangulation
+        let bitten = Path::new((0f32, 0f32)).line_to((10f32, 0f32)).line_to((10f32, 10f32))
+            .curve_to((6f32, -2f32), (4f32, -2f32), (0f32, 10f32)).close_path()
+            .set_fill_color(1f32, 0f32, 0f32);
+        assert!(miss(&bitten) < 0.01f32, "bitten square misses {}", miss(&bitten));
+    }
+
+    #[test]
+    fn test_odd_stroke_flag() {
+        let line = Path::new((0f32, 0f32)).line_to((10f32, 0f32));
+        let flags = |path: Path| PathGeometry::new(&path).unwrap().do_fill[0];
+        assert_eq!(flags(line.clone().set_stroke(0f32, 0f32, 0f32, 1)), ODD_STROKE_FLAG);
+        assert_eq!(flags(line.clone().set_stroke(0f32, 0f32, 0f32, 2)), 0);
+        let world = line.set_stroke(0f32, 0f32, 0f32, 3).set_stroke_units(StrokeUnits::World);
+        assert_eq!(flags(world.clone()), WORLD_STROKE_FLAG);
+        // 3 units thick, whatever the pixel size
+        assert!(world.hit_test((5f32, 1.4f32), 0.01f32));
+        assert!(!world.hit_test((5f32, 1.6f32), 0.01f32));
+    }
+
+    #[test]
+    fn test_stroke_align_with_higher_flags() {
+        // the shaders take the alignment from bits 1 and 2 whatever flags are set above them
+        let align = |flags: GLint| (flags >> 1) & 3;
+        for &stroke_align in &[StrokeAlign::Inside, StrokeAlign::Outside] {
+            let square = Path::rectangle((0f32, 0f32), 20f32, 20f32, 0f32).set_stroke(0f32, 0f32, 0f32, 1)
+                .set_stroke_align(stroke_align).set_line_join(LineJoin::Round).set_round_sharp_corners(true);
+            let geometry = PathGeometry::new(&square).unwrap();
+            assert!(geometry.do_fill.iter().all(|&flags| flags & ODD_STROKE_FLAG != 0));
+            assert!(geometry.do_fill[6..].iter().all(|&flags| flags & ROUND_SHARP_FLAG != 0));
+            assert!(geometry.do_fill.iter().all(|&flags| align(flags) == stroke_align.flags() >> 1));
+            let world = PathGeometry::new(&square.set_stroke_units(StrokeUnits::World)).unwrap();
+            assert!(world.do_fill.iter().all(|&flags| flags & WORLD_STROKE_FLAG != 0));
+            assert!(world.do_fill.iter().all(|&flags| align(flags) == stroke_align.flags() >> 1));
+        }
+        let geometry_shader = shader::read_file("shaders/geometry_shader.glsl").unwrap();
+        assert!(geometry_shader.contains("int align = (te_do_fill[0] >> 1) & 3;"));
+    }
+
+    #[test]
+    fn test_outline_of_curve() {
+        let path = Path::new((0f32, 0f32)).curve_to((0f32, 10f32), (10f32, 10f32), (10f32, 0f32));
+        let outline = path.outline(4);
+        assert_eq!(outline.len(), 5);
+        assert_eq!(outline[0], (0f32, 0f32));
+        assert_eq!(outline[4], (10f32, 0f32));
+    }
+
+    #[test]
+    fn test_animation_clock() {
+        let mut clock = AnimationClock::new();
+        assert!(clock.time() >= 0f32);
+        clock.pin(12f32);
+        assert_eq!(clock.time(), 12f32);
+        assert_eq!(clock.time(), 12f32);
+        // letting go runs on from the pinned time
+        clock.release();
+        let time = clock.time();
+        assert!((12f32..13f32).contains(&time));
+        clock.pin(0.5f32);
+        assert_eq!(clock.time(), 0.5f32);
+        // non-finite times are ignored
+        clock.pin(f32::INFINITY);
+        clock.pin(f32::NAN);
+        assert_eq!(clock.time(), 0.5f32);
+        // times far past what an Instant can go back to, and negative ones, run on as well
+        clock.pin(1e20f32);
+        clock.release();
+        assert_eq!(clock.time(), 1e20f32);
+        clock.pin(-3f32);
+        clock.release();
+        let time = clock.time();
+        assert!((-3f32..-2f32).contains(&time));
+    }
 }
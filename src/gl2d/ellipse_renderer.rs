@@ -0,0 +1,196 @@
+//! Ellipses drawn from their implicit equation: each is one instanced quad whose fragment shader
+//! finds the distance to the exact outline, instead of a path of Bezier curves tessellated into
+//! patches. Meant for scenes full of dots and circles.
+
+use std::mem;
+use std::ffi::CString;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::drawing::{Drawing, Window};
+use super::gl_state::SavedGlState;
+use super::super::color::Color;
+use super::super::TrdlError;
+
+// floats per ellipse in the instance buffer: center x, y, radii x, y, angle, stroke thickness,
+// then the fill and stroke rgba
+const INSTANCE_FLOATS: usize = 14;
+
+/// An ellipse for `EllipseRenderer`, in drawing coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipse {
+    pub center: (f32, f32),
+    pub x_radius: f32,
+    pub y_radius: f32,
+    /// Rotation of the x radius from the x axis, radians counter clockwise.
+    pub angle: f32,
+    pub fill: Option<Color>,
+    /// Stroke color and thickness in pixels, centered on the outline.
+    pub stroke: Option<(Color, f32)>
+}
+
+impl Ellipse {
+    /// A circle filled with color.
+    pub fn circle(center: (f32, f32), radius: f32, fill: Color) -> Self {
+        Ellipse { center, x_radius: radius, y_radius: radius, angle: 0f32, fill: Some(fill), stroke: None }
+    }
+
+    // The ellipse's instance data, None if there is nothing to draw.
+    fn instance(&self) -> Option<[GLfloat; INSTANCE_FLOATS]> {
+        let valid = |r: f32| r > 0f32 && r.is_finite();
+        if !valid(self.x_radius) || !valid(self.y_radius) || (self.fill.is_none() && self.stroke.is_none()) {
+            return None;
+        }
+        let none = Color::rgba(0f32, 0f32, 0f32, 0f32);
+        let fill = self.fill.unwrap_or(none);
+        let (stroke, thickness) = self.stroke.unwrap_or((none, 0f32));
+        Some([self.center.0, self.center.1, self.x_radius, self.y_radius, self.angle, thickness.max(0f32),
+              fill.red, fill.green, fill.blue, fill.alpha, stroke.red, stroke.green, stroke.blue, stroke.alpha])
+    }
+}
+
+/// Draws ellipses as instanced quads, on top of the drawing, anti-aliased against their exact
+/// outlines at any zoom.
+pub struct EllipseRenderer {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    corner_vbo: GLuint,
+    instance_vbo: GLuint,
+    instances: Vec<GLfloat>,
+
+    projection_uniform: GLint,
+    pixel_size_uniform: GLint,
+    srgb_uniform: GLint
+}
+
+impl EllipseRenderer {
+    /// Constructor, compiles the ellipse shaders. The drawing's context must be current.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/ellipse_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/ellipse_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        let corners: [GLfloat; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+        unsafe {
+            let attribute = |name: &str| {
+                let c_str = CString::new(name).unwrap();
+                gl::GetAttribLocation(program_id, c_str.as_ptr()) as GLuint
+            };
+            let in_corner = attribute("in_corner");
+
+            let mut vao_handle = 0 as GLuint;
+            gl::GenVertexArrays(1, &mut vao_handle);
+            gl::BindVertexArray(vao_handle);
+
+            let mut corner_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut corner_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, corner_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (corners.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           corners.as_ptr() as *const c_void,
+                           gl::STATIC_DRAW);
+            gl::EnableVertexAttribArray(in_corner);
+            gl::VertexAttribPointer(in_corner, 2, gl::FLOAT, gl::FALSE as GLboolean, 0, ptr::null());
+
+            let mut instance_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut instance_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+            let stride = (INSTANCE_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+            // name, components and offset in floats of each per ellipse attribute
+            let attributes = [("in_center_radii", 4, 0), ("in_angle_thickness", 2, 4), ("in_fill", 4, 6),
+                              ("in_stroke", 4, 10)];
+            for &(name, components, offset) in &attributes {
+                let location = attribute(name);
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribPointer(location, components, gl::FLOAT, gl::FALSE as GLboolean, stride,
+                                        (offset * mem::size_of::<GLfloat>()) as *const c_void);
+                gl::VertexAttribDivisor(location, 1);
+            }
+
+            Ok(EllipseRenderer {
+                projection_uniform: shader::uniform_location(program_id, "projection"),
+                pixel_size_uniform: shader::uniform_location(program_id, "pixel_size"),
+                srgb_uniform: shader::uniform_location(program_id, "srgb"),
+
+                shader_program: program,
+                vao_handle,
+                corner_vbo,
+                instance_vbo,
+                instances: Vec::new()
+            })
+        }
+    }
+
+    /// Draw ellipses with the drawing's camera, in order, call after `Drawing::draw`. They are
+    /// blended over everything else and leave the OpenGL state as they found it. Ellipses
+    /// without a positive radius or without fill and stroke are skipped.
+    pub fn draw<W: Window>(&mut self, drawing: &Drawing<'_, W>, ellipses: &[Ellipse]) {
+        self.instances.clear();
+        for instance in ellipses.iter().filter_map(Ellipse::instance) {
+            self.instances.extend_from_slice(&instance);
+        }
+        let count = self.instances.len() / INSTANCE_FLOATS;
+        if count == 0 {
+            return;
+        }
+
+        let saved = SavedGlState::save();
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean,
+                                 drawing.projection().as_ptr());
+            gl::Uniform1f(self.pixel_size_uniform, drawing.pixel_size());
+            gl::Uniform1i(self.srgb_uniform, drawing.srgb() as GLint);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            // the shader gives premultiplied colors
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::BindVertexArray(self.vao_handle);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (self.instances.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           self.instances.as_ptr() as *const c_void,
+                           gl::STREAM_DRAW);
+            gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, count as GLsizei);
+        }
+        saved.restore();
+    }
+}
+
+impl Drop for EllipseRenderer {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.corner_vbo);
+            gl::DeleteBuffers(1, &self.instance_vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ellipse;
+    use super::super::super::color::Color;
+
+    #[test]
+    fn test_instance() {
+        let red = Color::rgb(1f32, 0f32, 0f32);
+        let mut ellipse = Ellipse::circle((1f32, 2f32), 3f32, red);
+        ellipse.stroke = Some((Color::rgba(0f32, 0f32, 1f32, 0.5f32), 2f32));
+        assert_eq!(ellipse.instance(), Some([1f32, 2f32, 3f32, 3f32, 0f32, 2f32, 1f32, 0f32, 0f32, 1f32,
+                                             0f32, 0f32, 1f32, 0.5f32]));
+        assert_eq!(Ellipse { x_radius: 0f32, ..ellipse }.instance(), None);
+        assert_eq!(Ellipse { fill: None, stroke: None, ..ellipse }.instance(), None);
+    }
+}
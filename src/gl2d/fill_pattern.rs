@@ -0,0 +1,104 @@
+//! Procedural fill patterns: hatching, dots and checkers drawn by the fragment shader in the fill
+//! color over a background color. Each patterned path gets a record in a texture buffer that its
+//! vertices point to from the bits of their fill flags above PATTERN_SHIFT.
+
+use std::mem;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::super::color::Color;
+
+// the fill flags of a patterned path hold the number of its record, counting from 1, from this
+// bit up
+pub(crate) const PATTERN_SHIFT: u32 = 10;
+// floats in a pattern record: kind, spacing, angle and width, the origin, the background color
+pub(crate) const RECORD_FLOATS: usize = 12;
+
+/// A procedural fill, see `Path::set_fill_pattern`. Lengths are in drawing units and angles in
+/// radians counter clockwise from the x axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillPattern {
+    /// Parallel lines `width` thick and `spacing` apart, running at `angle`.
+    Hatch { spacing: f32, angle: f32, width: f32 },
+    /// Two sets of hatch lines at right angles, one running at `angle`.
+    CrossHatch { spacing: f32, angle: f32, width: f32 },
+    /// Dots `diameter` across on a square grid `spacing` apart, turned by `angle`.
+    Dots { spacing: f32, angle: f32, diameter: f32 },
+    /// Squares `size` across alternating with squares of the background, turned by `angle`.
+    Checker { size: f32, angle: f32 }
+}
+
+impl FillPattern {
+    // The pattern's record for a path with its first point at origin, the pattern starting there,
+    // and the background's alpha multiplied by opacity.
+    pub(crate) fn record(&self, background: Color, origin: (f32, f32), opacity: f32) -> [GLfloat; RECORD_FLOATS] {
+        let (kind, spacing, angle, width) = match *self {
+            FillPattern::Hatch { spacing, angle, width } => (1f32, spacing, angle, width),
+            FillPattern::CrossHatch { spacing, angle, width } => (2f32, spacing, angle, width),
+            FillPattern::Dots { spacing, angle, diameter } => (3f32, spacing, angle, diameter),
+            FillPattern::Checker { size, angle } => (4f32, size, angle, 0f32)
+        };
+        [kind, spacing.max(0f32), angle, width.max(0f32), origin.0, origin.1, 0f32, 0f32,
+         background.red, background.green, background.blue, background.alpha * opacity]
+    }
+}
+
+// The texture buffer the pattern records are uploaded to.
+pub(crate) struct PatternBuffer {
+    buffer: GLuint,
+    texture: GLuint
+}
+
+impl PatternBuffer {
+    pub fn new() -> Self {
+        let mut pattern_buffer = PatternBuffer { buffer: 0, texture: 0 };
+        unsafe {
+            gl::GenBuffers(1, &mut pattern_buffer.buffer);
+            gl::GenTextures(1, &mut pattern_buffer.texture);
+        }
+        pattern_buffer
+    }
+
+    // Upload the records of all patterned paths, as RGBA texels.
+    pub fn upload(&self, records: &[GLfloat]) {
+        unsafe {
+            gl::BindBuffer(gl::TEXTURE_BUFFER, self.buffer);
+            gl::BufferData(gl::TEXTURE_BUFFER, mem::size_of_val(records) as GLsizeiptr,
+                           records.as_ptr() as *const c_void, gl::STATIC_DRAW);
+            gl::BindTexture(gl::TEXTURE_BUFFER, self.texture);
+            gl::TexBuffer(gl::TEXTURE_BUFFER, gl::RGBA32F, self.buffer);
+            gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+        }
+    }
+
+    // Bind the records to a texture unit, leaving texture unit 0 active.
+    pub fn bind(&self, unit: GLuint) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_BUFFER, self.texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+}
+
+impl Drop for PatternBuffer {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture);
+            gl::DeleteBuffers(1, &self.buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FillPattern;
+    use super::super::super::color::Color;
+
+    #[test]
+    fn test_record() {
+        let dots = FillPattern::Dots { spacing: 4f32, angle: 0.5f32, diameter: -1f32 };
+        let record = dots.record(Color::rgba(1f32, 1f32, 1f32, 0.5f32), (2f32, 3f32), 0.5f32);
+        assert_eq!(record, [3f32, 4f32, 0.5f32, 0f32, 2f32, 3f32, 0f32, 0f32, 1f32, 1f32, 1f32, 0.25f32]);
+    }
+}
@@ -0,0 +1,373 @@
+//! The OpenGL calls that create shaders and vertex buffers, behind a trait so the code managing
+//! them can run against `RecordingGl` in tests on machines without a GPU. `Gl` passes every call
+//! on to the loaded OpenGL functions.
+
+use gl;
+use gl::types::*;
+use std::cell::{Cell, RefCell};
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::ptr;
+
+/// The OpenGL calls used to build shader programs and fill vertex buffers.
+pub trait GlApi {
+    /// Generate a buffer name for each slot of `buffers`.
+    fn gen_buffers(&self, buffers: &mut [GLuint]);
+    /// Delete buffers, zero names are ignored.
+    fn delete_buffers(&self, buffers: &[GLuint]);
+    /// Bind a buffer to a target.
+    fn bind_buffer(&self, target: GLenum, buffer: GLuint);
+    /// Replace the data of the buffer bound to a target, empty data leaves an empty buffer.
+    fn buffer_data(&self, target: GLenum, data: &[u8], usage: GLenum);
+    /// Generate a vertex array name.
+    fn gen_vertex_array(&self) -> GLuint;
+    /// Bind a vertex array, zero unbinds.
+    fn bind_vertex_array(&self, vertex_array: GLuint);
+    /// Delete a vertex array, zero is ignored.
+    fn delete_vertex_array(&self, vertex_array: GLuint);
+    /// Enable a vertex attribute array.
+    fn enable_vertex_attrib_array(&self, index: GLuint);
+    /// Point a float attribute at the tightly packed start of the bound array buffer.
+    fn vertex_attrib_pointer(&self, index: GLuint, size: GLint, kind: GLenum);
+    /// Point an integer attribute at the tightly packed start of the bound array buffer.
+    fn vertex_attrib_i_pointer(&self, index: GLuint, size: GLint, kind: GLenum);
+    /// Look up a vertex attribute by name, -1 if the program does not use it.
+    fn attrib_location(&self, program: GLuint, name: &str) -> GLint;
+    /// Create a shader object of a kind.
+    fn create_shader(&self, kind: GLenum) -> GLuint;
+    /// Set the source code of a shader.
+    fn shader_source(&self, shader: GLuint, code: &CString);
+    /// Compile a shader.
+    fn compile_shader(&self, shader: GLuint);
+    /// Query a shader parameter, like its compile status.
+    fn shader_parameter(&self, shader: GLuint, name: GLenum) -> GLint;
+    /// The info log of a shader, `length` bytes long.
+    fn shader_info_log(&self, shader: GLuint, length: GLint) -> Vec<u8>;
+    /// Delete a shader, zero is ignored.
+    fn delete_shader(&self, shader: GLuint);
+    /// Create a program object.
+    fn create_program(&self) -> GLuint;
+    /// Attach a shader to a program.
+    fn attach_shader(&self, program: GLuint, shader: GLuint);
+    /// Detach a shader from a program.
+    fn detach_shader(&self, program: GLuint, shader: GLuint);
+    /// Link a program.
+    fn link_program(&self, program: GLuint);
+    /// Query a program parameter, like its link status.
+    fn program_parameter(&self, program: GLuint, name: GLenum) -> GLint;
+    /// The info log of a program, `length` bytes long.
+    fn program_info_log(&self, program: GLuint, length: GLint) -> Vec<u8>;
+    /// Delete a program, zero is ignored.
+    fn delete_program(&self, program: GLuint);
+}
+
+/// The real OpenGL functions, loaded with `gl::load_with`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gl;
+
+impl GlApi for Gl {
+    fn gen_buffers(&self, buffers: &mut [GLuint]) {
+        unsafe { gl::GenBuffers(buffers.len() as GLsizei, buffers.as_mut_ptr()) }
+    }
+
+    fn delete_buffers(&self, buffers: &[GLuint]) {
+        unsafe { gl::DeleteBuffers(buffers.len() as GLsizei, buffers.as_ptr()) }
+    }
+
+    fn bind_buffer(&self, target: GLenum, buffer: GLuint) {
+        unsafe { gl::BindBuffer(target, buffer) }
+    }
+
+    fn buffer_data(&self, target: GLenum, data: &[u8], usage: GLenum) {
+        let pointer = if data.is_empty() { ptr::null() } else { data.as_ptr() as *const c_void };
+        unsafe { gl::BufferData(target, data.len() as GLsizeiptr, pointer, usage) }
+    }
+
+    fn gen_vertex_array(&self) -> GLuint {
+        let mut vertex_array = 0;
+        unsafe { gl::GenVertexArrays(1, &mut vertex_array) };
+        vertex_array
+    }
+
+    fn bind_vertex_array(&self, vertex_array: GLuint) {
+        unsafe { gl::BindVertexArray(vertex_array) }
+    }
+
+    fn delete_vertex_array(&self, vertex_array: GLuint) {
+        unsafe { gl::DeleteVertexArrays(1, &vertex_array) }
+    }
+
+    fn enable_vertex_attrib_array(&self, index: GLuint) {
+        unsafe { gl::EnableVertexAttribArray(index) }
+    }
+
+    fn vertex_attrib_pointer(&self, index: GLuint, size: GLint, kind: GLenum) {
+        unsafe { gl::VertexAttribPointer(index, size, kind, gl::FALSE as GLboolean, 0, ptr::null()) }
+    }
+
+    fn vertex_attrib_i_pointer(&self, index: GLuint, size: GLint, kind: GLenum) {
+        unsafe { gl::VertexAttribIPointer(index, size, kind, 0, ptr::null()) }
+    }
+
+    fn attrib_location(&self, program: GLuint, name: &str) -> GLint {
+        let c_str = CString::new(name).unwrap();
+        unsafe { gl::GetAttribLocation(program, c_str.as_ptr()) }
+    }
+
+    fn create_shader(&self, kind: GLenum) -> GLuint {
+        unsafe { gl::CreateShader(kind) }
+    }
+
+    fn shader_source(&self, shader: GLuint, code: &CString) {
+        unsafe { gl::ShaderSource(shader, 1, &code.as_ptr(), ptr::null()) }
+    }
+
+    fn compile_shader(&self, shader: GLuint) {
+        unsafe { gl::CompileShader(shader) }
+    }
+
+    fn shader_parameter(&self, shader: GLuint, name: GLenum) -> GLint {
+        let mut value = 0;
+        unsafe { gl::GetShaderiv(shader, name, &mut value) };
+        value
+    }
+
+    fn shader_info_log(&self, shader: GLuint, length: GLint) -> Vec<u8> {
+        let mut message = vec![0u8; length.max(0) as usize];
+        unsafe { gl::GetShaderInfoLog(shader, length, ptr::null_mut(), message.as_mut_ptr() as *mut GLchar) };
+        message
+    }
+
+    fn delete_shader(&self, shader: GLuint) {
+        unsafe { gl::DeleteShader(shader) }
+    }
+
+    fn create_program(&self) -> GLuint {
+        unsafe { gl::CreateProgram() }
+    }
+
+    fn attach_shader(&self, program: GLuint, shader: GLuint) {
+        unsafe { gl::AttachShader(program, shader) }
+    }
+
+    fn detach_shader(&self, program: GLuint, shader: GLuint) {
+        unsafe { gl::DetachShader(program, shader) }
+    }
+
+    fn link_program(&self, program: GLuint) {
+        unsafe { gl::LinkProgram(program) }
+    }
+
+    fn program_parameter(&self, program: GLuint, name: GLenum) -> GLint {
+        let mut value = 0;
+        unsafe { gl::GetProgramiv(program, name, &mut value) };
+        value
+    }
+
+    fn program_info_log(&self, program: GLuint, length: GLint) -> Vec<u8> {
+        let mut message = vec![0u8; length.max(0) as usize];
+        unsafe { gl::GetProgramInfoLog(program, length, ptr::null_mut(), message.as_mut_ptr() as *mut GLchar) };
+        message
+    }
+
+    fn delete_program(&self, program: GLuint) {
+        unsafe { gl::DeleteProgram(program) }
+    }
+}
+
+/// One call made through `RecordingGl`, named after the OpenGL function, with its arguments and
+/// any name it handed out. Buffer data is recorded by size only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlCall {
+    GenBuffers(Vec<GLuint>),
+    DeleteBuffers(Vec<GLuint>),
+    BindBuffer(GLenum, GLuint),
+    BufferData(GLenum, usize, GLenum),
+    GenVertexArray(GLuint),
+    BindVertexArray(GLuint),
+    DeleteVertexArray(GLuint),
+    EnableVertexAttribArray(GLuint),
+    VertexAttribPointer(GLuint, GLint, GLenum),
+    VertexAttribIPointer(GLuint, GLint, GLenum),
+    AttribLocation(GLuint, String),
+    CreateShader(GLenum, GLuint),
+    ShaderSource(GLuint),
+    CompileShader(GLuint),
+    DeleteShader(GLuint),
+    CreateProgram(GLuint),
+    AttachShader(GLuint, GLuint),
+    DetachShader(GLuint, GLuint),
+    LinkProgram(GLuint),
+    DeleteProgram(GLuint)
+}
+
+/// A stand-in for OpenGL that records every call and hands out names counting up from 1, and
+/// attribute locations counting up from 0 in the order they are looked up. Shaders compile and
+/// programs link unless set to fail with an info log. Parameter and info log queries are not
+/// recorded.
+#[derive(Debug, Default)]
+pub struct RecordingGl {
+    calls: RefCell<Vec<GlCall>>,
+    next_name: Cell<GLuint>,
+    next_location: Cell<GLint>,
+    compile_error: Option<String>,
+    link_error: Option<String>
+}
+
+impl RecordingGl {
+    /// Constructor, everything succeeds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make every shader fail to compile with this info log.
+    pub fn fail_compile(mut self, log: &str) -> Self {
+        self.compile_error = Some(log.to_string());
+        self
+    }
+
+    /// Make every program fail to link with this info log.
+    pub fn fail_link(mut self, log: &str) -> Self {
+        self.link_error = Some(log.to_string());
+        self
+    }
+
+    /// The calls so far, oldest first.
+    pub fn calls(&self) -> Vec<GlCall> {
+        self.calls.borrow().clone()
+    }
+
+    /// Forget the calls so far.
+    pub fn reset_calls(&self) {
+        self.calls.borrow_mut().clear();
+    }
+
+    fn record(&self, call: GlCall) {
+        self.calls.borrow_mut().push(call);
+    }
+
+    fn name(&self) -> GLuint {
+        self.next_name.set(self.next_name.get() + 1);
+        self.next_name.get()
+    }
+
+    // A status or info log length query on a shader or program that failed with `error`, or succeeded.
+    fn status(error: &Option<String>, name: GLenum) -> GLint {
+        match (name, error.as_ref()) {
+            (gl::COMPILE_STATUS, Some(_)) | (gl::LINK_STATUS, Some(_)) => gl::FALSE as GLint,
+            (gl::COMPILE_STATUS, None) | (gl::LINK_STATUS, None) => gl::TRUE as GLint,
+            (gl::INFO_LOG_LENGTH, Some(log)) => log.len() as GLint,
+            _ => 0
+        }
+    }
+}
+
+impl GlApi for RecordingGl {
+    fn gen_buffers(&self, buffers: &mut [GLuint]) {
+        for buffer in buffers.iter_mut() {
+            *buffer = self.name();
+        }
+        self.record(GlCall::GenBuffers(buffers.to_vec()));
+    }
+
+    fn delete_buffers(&self, buffers: &[GLuint]) {
+        self.record(GlCall::DeleteBuffers(buffers.to_vec()));
+    }
+
+    fn bind_buffer(&self, target: GLenum, buffer: GLuint) {
+        self.record(GlCall::BindBuffer(target, buffer));
+    }
+
+    fn buffer_data(&self, target: GLenum, data: &[u8], usage: GLenum) {
+        self.record(GlCall::BufferData(target, data.len(), usage));
+    }
+
+    fn gen_vertex_array(&self) -> GLuint {
+        let vertex_array = self.name();
+        self.record(GlCall::GenVertexArray(vertex_array));
+        vertex_array
+    }
+
+    fn bind_vertex_array(&self, vertex_array: GLuint) {
+        self.record(GlCall::BindVertexArray(vertex_array));
+    }
+
+    fn delete_vertex_array(&self, vertex_array: GLuint) {
+        self.record(GlCall::DeleteVertexArray(vertex_array));
+    }
+
+    fn enable_vertex_attrib_array(&self, index: GLuint) {
+        self.record(GlCall::EnableVertexAttribArray(index));
+    }
+
+    fn vertex_attrib_pointer(&self, index: GLuint, size: GLint, kind: GLenum) {
+        self.record(GlCall::VertexAttribPointer(index, size, kind));
+    }
+
+    fn vertex_attrib_i_pointer(&self, index: GLuint, size: GLint, kind: GLenum) {
+        self.record(GlCall::VertexAttribIPointer(index, size, kind));
+    }
+
+    fn attrib_location(&self, program: GLuint, name: &str) -> GLint {
+        self.record(GlCall::AttribLocation(program, name.to_string()));
+        let location = self.next_location.get();
+        self.next_location.set(location + 1);
+        location
+    }
+
+    fn create_shader(&self, kind: GLenum) -> GLuint {
+        let shader = self.name();
+        self.record(GlCall::CreateShader(kind, shader));
+        shader
+    }
+
+    fn shader_source(&self, shader: GLuint, _code: &CString) {
+        self.record(GlCall::ShaderSource(shader));
+    }
+
+    fn compile_shader(&self, shader: GLuint) {
+        self.record(GlCall::CompileShader(shader));
+    }
+
+    fn shader_parameter(&self, _shader: GLuint, name: GLenum) -> GLint {
+        Self::status(&self.compile_error, name)
+    }
+
+    fn shader_info_log(&self, _shader: GLuint, _length: GLint) -> Vec<u8> {
+        self.compile_error.clone().unwrap_or_default().into_bytes()
+    }
+
+    fn delete_shader(&self, shader: GLuint) {
+        self.record(GlCall::DeleteShader(shader));
+    }
+
+    fn create_program(&self) -> GLuint {
+        let program = self.name();
+        self.record(GlCall::CreateProgram(program));
+        program
+    }
+
+    fn attach_shader(&self, program: GLuint, shader: GLuint) {
+        self.record(GlCall::AttachShader(program, shader));
+    }
+
+    fn detach_shader(&self, program: GLuint, shader: GLuint) {
+        self.record(GlCall::DetachShader(program, shader));
+    }
+
+    fn link_program(&self, program: GLuint) {
+        self.record(GlCall::LinkProgram(program));
+    }
+
+    fn program_parameter(&self, _program: GLuint, name: GLenum) -> GLint {
+        Self::status(&self.link_error, name)
+    }
+
+    fn program_info_log(&self, _program: GLuint, _length: GLint) -> Vec<u8> {
+        self.link_error.clone().unwrap_or_default().into_bytes()
+    }
+
+    fn delete_program(&self, program: GLuint) {
+        self.record(GlCall::DeleteProgram(program));
+    }
+}
@@ -0,0 +1,250 @@
+use gl;
+use gl::types::*;
+
+/// The pieces of global OpenGL state that drawing touches, so they can be put back afterwards
+/// when TRDL draws inside someone else's render loop.
+#[derive(Debug)]
+pub struct SavedGlState {
+    program: GLint,
+    vertex_array: GLint,
+    array_buffer: GLint,
+    viewport: [GLint; 4],
+    patch_vertices: GLint,
+    depth_test: bool,
+    depth_mask: GLboolean,
+    depth_func: GLint,
+    depth_clear_value: GLfloat,
+    blend: bool,
+    scissor_test: bool,
+    scissor_box: [GLint; 4],
+    stencil_test: bool,
+    // source rgb, destination rgb, source alpha, destination alpha
+    blend_func: [GLint; 4],
+    framebuffer_srgb: bool,
+    clear_color: [GLfloat; 4],
+    active_texture: GLint,
+    // the 2D texture bound to unit 0, which layers are composited from
+    texture_binding: GLint,
+    // the array texture bound to unit 0, which distance fields are drawn from
+    texture_array_binding: GLint
+}
+
+impl SavedGlState {
+    /// Query the current state.
+    pub fn save() -> Self {
+        let mut state = SavedGlState {
+            program: 0,
+            vertex_array: 0,
+            array_buffer: 0,
+            viewport: [0; 4],
+            patch_vertices: 0,
+            depth_test: false,
+            depth_mask: gl::TRUE,
+            depth_func: 0,
+            depth_clear_value: 1f32,
+            blend: false,
+            scissor_test: false,
+            scissor_box: [0; 4],
+            stencil_test: false,
+            blend_func: [0; 4],
+            framebuffer_srgb: false,
+            clear_color: [0f32; 4],
+            active_texture: 0,
+            texture_binding: 0,
+            texture_array_binding: 0
+        };
+        unsafe {
+            gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut state.program);
+            gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, &mut state.vertex_array);
+            gl::GetIntegerv(gl::ARRAY_BUFFER_BINDING, &mut state.array_buffer);
+            gl::GetIntegerv(gl::VIEWPORT, state.viewport.as_mut_ptr());
+            gl::GetIntegerv(gl::PATCH_VERTICES, &mut state.patch_vertices);
+            gl::GetBooleanv(gl::DEPTH_WRITEMASK, &mut state.depth_mask);
+            gl::GetIntegerv(gl::DEPTH_FUNC, &mut state.depth_func);
+            gl::GetFloatv(gl::DEPTH_CLEAR_VALUE, &mut state.depth_clear_value);
+            gl::GetIntegerv(gl::SCISSOR_BOX, state.scissor_box.as_mut_ptr());
+            state.depth_test = gl::IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+            state.blend = gl::IsEnabled(gl::BLEND) == gl::TRUE;
+            state.scissor_test = gl::IsEnabled(gl::SCISSOR_TEST) == gl::TRUE;
+            state.stencil_test = gl::IsEnabled(gl::STENCIL_TEST) == gl::TRUE;
+            state.framebuffer_srgb = gl::IsEnabled(gl::FRAMEBUFFER_SRGB) == gl::TRUE;
+            gl::GetIntegerv(gl::BLEND_SRC_RGB, &mut state.blend_func[0]);
+            gl::GetIntegerv(gl::BLEND_DST_RGB, &mut state.blend_func[1]);
+            gl::GetIntegerv(gl::BLEND_SRC_ALPHA, &mut state.blend_func[2]);
+            gl::GetIntegerv(gl::BLEND_DST_ALPHA, &mut state.blend_func[3]);
+            gl::GetFloatv(gl::COLOR_CLEAR_VALUE, state.clear_color.as_mut_ptr());
+            gl::GetIntegerv(gl::ACTIVE_TEXTURE, &mut state.active_texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::GetIntegerv(gl::TEXTURE_BINDING_2D, &mut state.texture_binding);
+            gl::GetIntegerv(gl::TEXTURE_BINDING_2D_ARRAY, &mut state.texture_array_binding);
+            gl::ActiveTexture(state.active_texture as GLenum);
+        }
+        state
+    }
+
+    /// The viewport that was set, x, y, width and height.
+    pub fn viewport(&self) -> [GLint; 4] {
+        self.viewport
+    }
+
+    /// Put the saved state back.
+    pub fn restore(&self) {
+        unsafe {
+            gl::UseProgram(self.program as GLuint);
+            gl::BindVertexArray(self.vertex_array as GLuint);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.array_buffer as GLuint);
+            gl::Viewport(self.viewport[0], self.viewport[1], self.viewport[2], self.viewport[3]);
+            gl::PatchParameteri(gl::PATCH_VERTICES, self.patch_vertices);
+            gl::DepthMask(self.depth_mask);
+            gl::DepthFunc(self.depth_func as GLenum);
+            gl::ClearDepth(self.depth_clear_value as GLdouble);
+            gl::Scissor(self.scissor_box[0], self.scissor_box[1], self.scissor_box[2], self.scissor_box[3]);
+            set_enabled(gl::DEPTH_TEST, self.depth_test);
+            set_enabled(gl::BLEND, self.blend);
+            set_enabled(gl::SCISSOR_TEST, self.scissor_test);
+            set_enabled(gl::STENCIL_TEST, self.stencil_test);
+            set_enabled(gl::FRAMEBUFFER_SRGB, self.framebuffer_srgb);
+            gl::BlendFuncSeparate(self.blend_func[0] as GLenum, self.blend_func[1] as GLenum,
+                                  self.blend_func[2] as GLenum, self.blend_func[3] as GLenum);
+            gl::ClearColor(self.clear_color[0], self.clear_color[1], self.clear_color[2],
+                           self.clear_color[3]);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture_binding as GLuint);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.texture_array_binding as GLuint);
+            gl::ActiveTexture(self.active_texture as GLenum);
+        }
+    }
+}
+
+/// A copy of the depth buffer of the bound framebuffer, so drawing that needs a cleared depth
+/// buffer can put the host's depth values back afterwards. The stencil values come along when
+/// the framebuffer has a stencil buffer, since the two are usually one buffer.
+#[derive(Debug)]
+pub struct DepthCopy {
+    framebuffer: GLuint,
+    renderbuffer: GLuint,
+    // width, height, internal format and samples of the renderbuffer
+    storage: (GLsizei, GLsizei, GLenum, GLint),
+    // the framebuffers bound when the copy was made, the area copied and which buffers
+    draw_framebuffer: GLuint,
+    read_framebuffer: GLuint,
+    area: [GLint; 4],
+    mask: GLbitfield
+}
+
+impl DepthCopy {
+    /// A copy holding nothing yet.
+    pub fn new() -> Self {
+        let mut framebuffer = 0;
+        let mut renderbuffer = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::GenRenderbuffers(1, &mut renderbuffer);
+        }
+        DepthCopy { framebuffer, renderbuffer, storage: (0, 0, gl::NONE, 0), draw_framebuffer: 0,
+                    read_framebuffer: 0, area: [0; 4], mask: 0 }
+    }
+
+    /// Copy the depth values in `area` (x, y, width and height) of the bound framebuffer. The copy
+    /// is made in the framebuffer's own format and sample count, which blitting back requires.
+    /// Returns false, copying nothing, if the framebuffer has no depth buffer.
+    pub fn save(&mut self, area: [GLint; 4]) -> bool {
+        self.mask = 0;
+        unsafe {
+            let mut draw_framebuffer = 0;
+            let mut read_framebuffer = 0;
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut draw_framebuffer);
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut read_framebuffer);
+            self.draw_framebuffer = draw_framebuffer as GLuint;
+            self.read_framebuffer = read_framebuffer as GLuint;
+
+            // the default framebuffer names its buffers differently
+            let (depth, stencil) = if self.draw_framebuffer == 0 {
+                (gl::DEPTH, gl::STENCIL)
+            } else {
+                (gl::DEPTH_ATTACHMENT, gl::STENCIL_ATTACHMENT)
+            };
+            let attachment = |buffer, parameter| {
+                let mut value = 0;
+                gl::GetFramebufferAttachmentParameteriv(gl::DRAW_FRAMEBUFFER, buffer, parameter, &mut value);
+                value
+            };
+            if attachment(depth, gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE) as GLenum == gl::NONE {
+                return false;
+            }
+            let depth_bits = attachment(depth, gl::FRAMEBUFFER_ATTACHMENT_DEPTH_SIZE);
+            let float = attachment(depth, gl::FRAMEBUFFER_ATTACHMENT_COMPONENT_TYPE) as GLenum == gl::FLOAT;
+            let has_stencil = attachment(stencil, gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE) as GLenum != gl::NONE &&
+                              attachment(stencil, gl::FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE) > 0;
+            let format = match (float, has_stencil, depth_bits) {
+                (true, true, _) => gl::DEPTH32F_STENCIL8,
+                (true, false, _) => gl::DEPTH_COMPONENT32F,
+                (false, true, _) => gl::DEPTH24_STENCIL8,
+                (false, false, 16) => gl::DEPTH_COMPONENT16,
+                (false, false, 32) => gl::DEPTH_COMPONENT32,
+                (false, false, _) => gl::DEPTH_COMPONENT24
+            };
+            let mut samples = 0;
+            gl::GetIntegerv(gl::SAMPLES, &mut samples);
+
+            // multisampled blits need the same rectangle on both sides, so the copy is as large
+            // as the area's far corner
+            let storage = (area[0] + area[2], area[1] + area[3], format, samples);
+            if storage != self.storage {
+                gl::BindRenderbuffer(gl::RENDERBUFFER, self.renderbuffer);
+                gl::RenderbufferStorageMultisample(gl::RENDERBUFFER, samples, format, storage.0, storage.1);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+                let point = if has_stencil { gl::DEPTH_STENCIL_ATTACHMENT } else { gl::DEPTH_ATTACHMENT };
+                gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, point, gl::RENDERBUFFER, self.renderbuffer);
+                self.storage = storage;
+            }
+            self.area = area;
+            self.mask = if has_stencil { gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT } else { gl::DEPTH_BUFFER_BIT };
+            self.blit(self.draw_framebuffer, self.framebuffer);
+        }
+        true
+    }
+
+    /// Put the copied values back where they were copied from. Does nothing if `save` copied
+    /// nothing.
+    pub fn restore(&self) {
+        if self.mask != 0 {
+            self.blit(self.framebuffer, self.draw_framebuffer);
+        }
+    }
+
+    // Copy the area from one framebuffer to the other and bind the saved framebuffers again.
+    // Blits are cut to the scissor box, so the scissor test is turned off, and depth writes on.
+    fn blit(&self, from: GLuint, to: GLuint) {
+        let [x, y, width, height] = self.area;
+        unsafe {
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::DepthMask(gl::TRUE);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, from);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, to);
+            gl::BlitFramebuffer(x, y, x + width, y + height, x, y, x + width, y + height, self.mask, gl::NEAREST);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.read_framebuffer);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.draw_framebuffer);
+        }
+    }
+}
+
+impl Drop for DepthCopy {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+            gl::DeleteRenderbuffers(1, &self.renderbuffer);
+        }
+    }
+}
+
+// enable or disable a capability.
+unsafe fn set_enabled(capability: GLenum, enabled: bool) {
+    if enabled {
+        gl::Enable(capability);
+    } else {
+        gl::Disable(capability);
+    }
+}
@@ -0,0 +1,249 @@
+//! Fills triangulated on the GPU: the points of convex polygons go to a compute shader that fans
+//! them out into triangles in a storage buffer, which is then drawn as a vertex buffer. Nothing is
+//! triangulated on the CPU, so it suits scenes whose shapes change every frame. Needs OpenGL 4.3.
+
+use std::mem;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::drawing::{Drawing, Window};
+use super::gl_state::SavedGlState;
+use super::super::color::Color;
+use super::super::TrdlError;
+
+// 32 bit words in a shape record: first point, point count, first triangle, unused, then rgba
+const SHAPE_WORDS: usize = 8;
+// floats in an output vertex: position xyzw then rgba
+const VERTEX_FLOATS: usize = 8;
+// work groups along one dimension of a dispatch, the least every driver allows
+const MAX_GROUPS: usize = 65535;
+
+// The polygons to fill, as uploaded to the compute shader.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct FanBatch {
+    points: Vec<GLfloat>,
+    shapes: Vec<GLuint>,
+    triangles: usize
+}
+
+impl FanBatch {
+    // Add a polygon that is fanned out from its first point.
+    pub fn push(&mut self, points: &[(f32, f32)], color: Color) -> Result<(), TrdlError> {
+        if points.len() < 3 {
+            return Err(TrdlError::NotEnoughVertices);
+        }
+        let first = self.points.len() / 2;
+        self.points.extend(points.iter().flat_map(|&(x, y)| [x, y]));
+        self.shapes.extend_from_slice(&[first as GLuint, points.len() as GLuint, self.triangles as GLuint, 0,
+                                        color.red.to_bits(), color.green.to_bits(), color.blue.to_bits(),
+                                        color.alpha.to_bits()]);
+        self.triangles += points.len() - 2;
+        Ok(())
+    }
+
+    pub fn shape_count(&self) -> usize {
+        self.shapes.len() / SHAPE_WORDS
+    }
+
+    // Work groups to dispatch along x and y, one per shape.
+    pub fn groups(&self) -> (usize, usize) {
+        let shapes = self.shape_count();
+        let x = shapes.min(MAX_GROUPS);
+        (x, if x == 0 { 0 } else { shapes.div_ceil(x) })
+    }
+}
+
+/// Fills convex polygons with flat colors, triangulated by a compute shader. Add the polygons
+/// with `add_polygon` and draw them over a drawing with `draw`, they are triangulated again on
+/// the GPU only after they change. Fills are not anti-aliased.
+pub struct GpuFill {
+    compute_program: shader::ShaderProgram,
+    draw_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    point_buffer: GLuint,
+    shape_buffer: GLuint,
+    // the triangles, written by the compute shader and read as vertices
+    triangle_buffer: GLuint,
+    triangle_capacity: usize,
+    batch: FanBatch,
+    // whether the triangles need to be made again
+    dirty: bool,
+
+    shape_count_uniform: GLint,
+    projection_uniform: GLint,
+    srgb_uniform: GLint
+}
+
+impl GpuFill {
+    /// Constructor, compiles the shaders. The drawing's context must be current and at least
+    /// OpenGL 4.3, older ones fail with `GlVersionTooOld`.
+    pub fn new() -> Result<Self, TrdlError> {
+        let (mut major, mut minor) = (0 as GLint, 0 as GLint);
+        unsafe {
+            gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+            gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+        }
+        if (major, minor) < (4, 3) {
+            return Err(TrdlError::GlVersionTooOld(4, 3));
+        }
+
+        let compute_shader_code = shader::read_file("shaders/fan_compute_shader.glsl")?;
+        let vertex_shader_code = shader::read_file("shaders/gpu_fill_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/gpu_fill_fragment_shader.glsl")?;
+        let compute_program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_compute_shader(&compute_shader_code);
+            compute_program = builder.build_shader_program()?;
+        }
+        let draw_program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            draw_program = builder.build_shader_program()?;
+        }
+
+        let mut fill = GpuFill {
+            shape_count_uniform: shader::uniform_location(compute_program.get_program_id(), "shape_count"),
+            projection_uniform: shader::uniform_location(draw_program.get_program_id(), "projection"),
+            srgb_uniform: shader::uniform_location(draw_program.get_program_id(), "srgb"),
+            compute_program,
+            draw_program,
+            vao_handle: 0,
+            point_buffer: 0,
+            shape_buffer: 0,
+            triangle_buffer: 0,
+            triangle_capacity: 0,
+            batch: FanBatch::default(),
+            dirty: false
+        };
+        unsafe {
+            gl::GenBuffers(1, &mut fill.point_buffer);
+            gl::GenBuffers(1, &mut fill.shape_buffer);
+            gl::GenBuffers(1, &mut fill.triangle_buffer);
+
+            gl::GenVertexArrays(1, &mut fill.vao_handle);
+            gl::BindVertexArray(fill.vao_handle);
+            gl::BindBuffer(gl::ARRAY_BUFFER, fill.triangle_buffer);
+            let stride = (VERTEX_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(0, 4, gl::FLOAT, gl::FALSE as GLboolean, stride, ptr::null());
+            gl::EnableVertexAttribArray(1);
+            gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE as GLboolean, stride,
+                                    (4 * mem::size_of::<GLfloat>()) as *const c_void);
+        }
+        Ok(fill)
+    }
+
+    /// Add a polygon filled with color. It must be convex, or at least have every point in view
+    /// of the first, since it is fanned out from there; split other shapes into such pieces.
+    /// Fails with `NotEnoughVertices` for fewer than 3 points.
+    pub fn add_polygon(&mut self, points: &[(f32, f32)], color: Color) -> Result<(), TrdlError> {
+        self.batch.push(points, color)?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Remove all the polygons.
+    pub fn clear(&mut self) {
+        self.batch = FanBatch::default();
+        self.dirty = true;
+    }
+
+    // Upload the polygons and run the compute shader over them.
+    fn triangulate(&mut self) {
+        let vertex_bytes = VERTEX_FLOATS * mem::size_of::<GLfloat>();
+        unsafe {
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.point_buffer);
+            gl::BufferData(gl::SHADER_STORAGE_BUFFER, mem::size_of_val(&self.batch.points[..]) as GLsizeiptr,
+                           self.batch.points.as_ptr() as *const c_void, gl::STREAM_DRAW);
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.shape_buffer);
+            gl::BufferData(gl::SHADER_STORAGE_BUFFER, mem::size_of_val(&self.batch.shapes[..]) as GLsizeiptr,
+                           self.batch.shapes.as_ptr() as *const c_void, gl::STREAM_DRAW);
+            if self.batch.triangles > self.triangle_capacity {
+                gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.triangle_buffer);
+                gl::BufferData(gl::SHADER_STORAGE_BUFFER, (3 * self.batch.triangles * vertex_bytes) as GLsizeiptr,
+                               ptr::null(), gl::DYNAMIC_COPY);
+                self.triangle_capacity = self.batch.triangles;
+            }
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, 0);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.point_buffer);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, self.shape_buffer);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 2, self.triangle_buffer);
+
+            gl::UseProgram(self.compute_program.get_program_id());
+            gl::Uniform1ui(self.shape_count_uniform, self.batch.shape_count() as GLuint);
+            let (x, y) = self.batch.groups();
+            gl::DispatchCompute(x as GLuint, y as GLuint, 1);
+            // the triangles are read as vertices next
+            gl::MemoryBarrier(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+        }
+        log_event!(debug, "triangulated {} polygons into {} triangles on the GPU",
+                   self.batch.shape_count(), self.batch.triangles);
+        self.dirty = false;
+    }
+
+    /// Draw the polygons with the drawing's camera, call after `Drawing::draw`. They are blended
+    /// over everything else in the order they were added, and leave the OpenGL state as they
+    /// found it.
+    pub fn draw<W: Window>(&mut self, drawing: &Drawing<'_, W>) {
+        if self.batch.triangles == 0 {
+            return;
+        }
+        let saved = SavedGlState::save();
+        if self.dirty {
+            self.triangulate();
+        }
+        unsafe {
+            gl::UseProgram(self.draw_program.get_program_id());
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean,
+                                 drawing.projection().as_ptr());
+            gl::Uniform1i(self.srgb_uniform, drawing.srgb() as GLint);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::BindVertexArray(self.vao_handle);
+            gl::DrawArrays(gl::TRIANGLES, 0, (3 * self.batch.triangles) as GLsizei);
+        }
+        saved.restore();
+    }
+}
+
+impl Drop for GpuFill {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.point_buffer);
+            gl::DeleteBuffers(1, &self.shape_buffer);
+            gl::DeleteBuffers(1, &self.triangle_buffer);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FanBatch;
+    use super::super::super::color::Color;
+    use super::super::super::TrdlError;
+
+    #[test]
+    fn test_fan_batch() {
+        let mut batch = FanBatch::default();
+        let red = Color::rgb(1f32, 0f32, 0f32);
+        batch.push(&[(0f32, 0f32), (1f32, 0f32), (1f32, 1f32), (0f32, 1f32)], red).unwrap();
+        batch.push(&[(2f32, 0f32), (3f32, 0f32), (2f32, 1f32)], red).unwrap();
+        match batch.push(&[(0f32, 0f32), (1f32, 0f32)], red) {
+            Err(TrdlError::NotEnoughVertices) => (),
+            other => panic!("expected NotEnoughVertices, got {:?}", other)
+        }
+        assert_eq!(batch.shape_count(), 2);
+        assert_eq!(batch.triangles, 3);
+        // the second polygon starts at point 4 and triangle 2
+        assert_eq!(&batch.shapes[8..12], &[4, 3, 2, 0]);
+        assert_eq!(batch.groups(), (2, 1));
+    }
+}
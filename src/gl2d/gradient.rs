@@ -0,0 +1,97 @@
+//! Gradients shared between paths. A drawing keeps the stops of each gradient in a slot of a
+//! texture buffer, and the fill pattern record of each path filled with it points to the slot, so
+//! changing the stops uploads the slot again without touching the paths.
+
+use gl::types::*;
+use super::fill_pattern::RECORD_FLOATS;
+use super::super::angle::Angle;
+use super::super::color::Color;
+
+// stops kept of each gradient, ones past these are dropped
+pub(crate) const MAX_STOPS: usize = 16;
+// floats in a gradient's slot: the number of stops and the angle, then a position and a color for
+// each stop
+pub(crate) const SLOT_FLOATS: usize = 4 + 8 * MAX_STOPS;
+// the slot of a destroyed gradient, without stops
+pub(crate) const EMPTY_SLOT: [GLfloat; SLOT_FLOATS] = [0f32; SLOT_FLOATS];
+// the kind of pattern record of a gradient fill, after the kinds of FillPattern
+const GRADIENT_KIND: f32 = 5f32;
+
+/// Identifies a gradient made with `Drawing::create_gradient`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GradientId {
+    // the namespace of the drawing that made it
+    pub(crate) namespace: usize,
+    // the gradient's slot in the texture buffer
+    pub(crate) slot: usize
+}
+
+/// How a path is filled, see `Path::set_fill`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    /// A flat color, its alpha is the fill's opacity.
+    Color(Color),
+    /// A gradient of the drawing the path is added to, running across the path's bounding box.
+    Gradient(GradientId)
+}
+
+// A gradient's stops (position, color) in order of position, and the direction it runs in.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Gradient {
+    pub stops: Vec<(f32, Color)>,
+    pub angle: Angle
+}
+
+impl Gradient {
+    pub fn new(stops: &[(f32, Color)]) -> Self {
+        let mut gradient = Gradient { stops: Vec::new(), angle: Angle::radians(0f32) };
+        gradient.set_stops(stops);
+        gradient
+    }
+
+    // Keep the first MAX_STOPS stops with finite positions, sorted by position.
+    pub fn set_stops(&mut self, stops: &[(f32, Color)]) {
+        self.stops = stops.iter().cloned().filter(|&(position, _)| position.is_finite()).take(MAX_STOPS).collect();
+        self.stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    // The gradient's slot in the texture buffer, as RGBA texels.
+    pub fn slot(&self) -> [GLfloat; SLOT_FLOATS] {
+        let mut slot = [0f32; SLOT_FLOATS];
+        slot[0] = self.stops.len() as f32;
+        slot[1] = self.angle.to_radians();
+        for (i, &(position, color)) in self.stops.iter().enumerate() {
+            slot[4 + 8 * i] = position;
+            slot[8 + 8 * i..12 + 8 * i].copy_from_slice(&[color.red, color.green, color.blue, color.alpha]);
+        }
+        slot
+    }
+}
+
+// The fill pattern record of a path filled with the gradient in slot, spanning the path's bounds
+// (min, max), with the stops' alpha multiplied by opacity.
+pub(crate) fn record(slot: usize, bounds: ((f32, f32), (f32, f32)), opacity: f32) -> [GLfloat; RECORD_FLOATS] {
+    let ((x0, y0), (x1, y1)) = bounds;
+    [GRADIENT_KIND, slot as f32, 0f32, 0f32, x0, y0, x1, y1, 1f32, 1f32, 1f32, opacity]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{record, Gradient, MAX_STOPS};
+    use super::super::super::color::Color;
+
+    #[test]
+    fn test_slot() {
+        let (red, blue) = (Color::rgb(1f32, 0f32, 0f32), Color::rgba(0f32, 0f32, 1f32, 0.5f32));
+        let gradient = Gradient::new(&[(1f32, blue), (f32::NAN, red), (0f32, red)]);
+        assert_eq!(gradient.stops, vec![(0f32, red), (1f32, blue)]);
+        let slot = gradient.slot();
+        assert_eq!(&slot[..4], &[2f32, 0f32, 0f32, 0f32]);
+        assert_eq!(&slot[4..20], &[0f32, 0f32, 0f32, 0f32, 1f32, 0f32, 0f32, 1f32,
+                                   1f32, 0f32, 0f32, 0f32, 0f32, 0f32, 1f32, 0.5f32]);
+        let many: Vec<(f32, Color)> = (0..20).map(|i| (i as f32, red)).collect();
+        assert_eq!(Gradient::new(&many).stops.len(), MAX_STOPS);
+        assert_eq!(record(3, ((0f32, 1f32), (2f32, 3f32)), 0.5f32)[..8],
+                   [5f32, 3f32, 0f32, 0f32, 0f32, 1f32, 2f32, 3f32]);
+    }
+}
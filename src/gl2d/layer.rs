@@ -0,0 +1,309 @@
+//! Layers group the paths of a drawing. A layer can be hidden, faded, or cached in a texture
+//! that is only redrawn when the layer changes.
+
+use std::mem;
+use std::ffi::CString;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::drawing::SortMode;
+use super::super::image::ImageData;
+use super::super::TrdlError;
+
+/// Identifies a layer of a drawing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerId(pub(crate) usize);
+
+// What a cached layer image depends on besides the layer's own paths.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CacheKey {
+    pub version: u64,
+    pub projection: [GLfloat; 16],
+    pub srgb: bool,
+    pub sort_mode: SortMode
+}
+
+// A layer's settings, along with its cached image when it has one.
+pub(crate) struct Layer {
+    pub id: LayerId,
+    pub visible: bool,
+    pub opacity: f32,
+    pub cached: bool,
+    // samples per pixel of the layer's texture, 0 or 1 for no multisampling
+    pub samples: u32,
+    // bumped whenever a path of the layer changes
+    pub version: u64,
+    pub cache: Option<LayerCache>
+}
+
+impl Layer {
+    pub fn new(id: LayerId) -> Self {
+        Layer { id, visible: true, opacity: 1f32, cached: false, samples: 0, version: 0, cache: None }
+    }
+
+    // Whether the layer is drawn through a texture. Faded layers are, so overlapping paths in
+    // them fade as a whole instead of showing through each other.
+    pub fn uses_texture(&self) -> bool {
+        self.cached || self.opacity < 1f32
+    }
+}
+
+// A framebuffer with a color texture and a depth buffer that a layer is drawn into. A
+// multisampled cache is drawn into a second framebuffer with multisampled renderbuffers, which is
+// resolved into the texture after each redraw.
+pub(crate) struct LayerCache {
+    framebuffer: GLuint,
+    texture: GLuint,
+    depth_buffer: GLuint,
+    // the multisampled framebuffer and its color buffer, 0 without multisampling
+    multisample_framebuffer: GLuint,
+    color_buffer: GLuint,
+    size: (GLsizei, GLsizei),
+    srgb: bool,
+    // the samples asked for, which may be more than the driver supports
+    samples: u32,
+    // what the texture currently shows, None until first drawn
+    pub key: Option<CacheKey>
+}
+
+impl LayerCache {
+    pub fn new(width: GLsizei, height: GLsizei, srgb: bool, samples: u32) -> Self {
+        let mut cache = LayerCache { framebuffer: 0, texture: 0, depth_buffer: 0, multisample_framebuffer: 0,
+                                     color_buffer: 0, size: (width, height), srgb, samples, key: None };
+        unsafe {
+            let mut previous = 0 as GLint;
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous);
+            let mut max_samples = 0 as GLint;
+            gl::GetIntegerv(gl::MAX_SAMPLES, &mut max_samples);
+            let samples = (samples as GLint).min(max_samples);
+
+            gl::GenTextures(1, &mut cache.texture);
+            gl::BindTexture(gl::TEXTURE_2D, cache.texture);
+            // an sRGB texture keeps the colors encoded the same way as an sRGB window
+            let format = if srgb { gl::SRGB8_ALPHA8 } else { gl::RGBA8 };
+            gl::TexImage2D(gl::TEXTURE_2D, 0, format as GLint, width, height, 0,
+                           gl::RGBA, gl::UNSIGNED_BYTE, ptr::null());
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+
+            gl::GenFramebuffers(1, &mut cache.framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, cache.framebuffer);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D,
+                                     cache.texture, 0);
+
+            gl::GenRenderbuffers(1, &mut cache.depth_buffer);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, cache.depth_buffer);
+            if samples > 1 {
+                // the texture only receives resolved colors, the depth buffer goes with the
+                // multisampled color buffer
+                gl::RenderbufferStorageMultisample(gl::RENDERBUFFER, samples, gl::DEPTH_COMPONENT24, width, height);
+                gl::GenRenderbuffers(1, &mut cache.color_buffer);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, cache.color_buffer);
+                gl::RenderbufferStorageMultisample(gl::RENDERBUFFER, samples, format, width, height);
+
+                gl::GenFramebuffers(1, &mut cache.multisample_framebuffer);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, cache.multisample_framebuffer);
+                gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::RENDERBUFFER,
+                                            cache.color_buffer);
+            } else {
+                gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+            }
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::RENDERBUFFER,
+                                        cache.depth_buffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous as GLuint);
+        }
+        cache
+    }
+
+    // Whether the cache can hold a layer drawn at this size, color encoding and sample count.
+    pub fn fits(&self, width: GLsizei, height: GLsizei, srgb: bool, samples: u32) -> bool {
+        self.size == (width, height) && self.srgb == srgb && self.samples.max(1) == samples.max(1)
+    }
+
+    // Draw into the cache with `draw`, which gets it cleared to transparent. A multisampled
+    // cache is resolved into the texture afterwards. The framebuffers and viewport set before are
+    // set again afterwards, which may be a host's when drawing embedded.
+    pub fn render<F: FnOnce()>(&mut self, key: CacheKey, draw: F) {
+        let multisampled = self.multisample_framebuffer != 0;
+        unsafe {
+            let mut draw_framebuffer = 0 as GLint;
+            let mut read_framebuffer = 0 as GLint;
+            let mut viewport = [0 as GLint; 4];
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut draw_framebuffer);
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut read_framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            let target = if multisampled { self.multisample_framebuffer } else { self.framebuffer };
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target);
+            gl::Viewport(0, 0, self.size.0, self.size.1);
+            gl::ClearColor(0f32, 0f32, 0f32, 0f32);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            draw();
+            if multisampled {
+                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.multisample_framebuffer);
+                gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.framebuffer);
+                gl::BlitFramebuffer(0, 0, self.size.0, self.size.1, 0, 0, self.size.0, self.size.1,
+                                    gl::COLOR_BUFFER_BIT, gl::NEAREST);
+            }
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, draw_framebuffer as GLuint);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, read_framebuffer as GLuint);
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+        self.key = Some(key);
+    }
+
+    // Fill the texture with an image the size of the cache, premultiplied like drawn layers. The
+    // image's top row goes at the top, where drawn layers have theirs.
+    pub fn upload(&mut self, image: &ImageData) {
+        let mut image = image.clone();
+        image.premultiply_alpha();
+        let row = 4 * image.width() as usize;
+        let flipped: Vec<u8> = image.pixels().chunks(row).rev().flatten().cloned().collect();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, self.size.0, self.size.1, gl::RGBA, gl::UNSIGNED_BYTE,
+                              flipped.as_ptr() as *const c_void);
+        }
+    }
+
+    // Sample the texture with linear filtering, for images drawn scaled instead of pixel for pixel.
+    pub fn set_smooth(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        }
+    }
+
+    // Bind the texture to the active texture unit.
+    pub fn bind_texture(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+        }
+    }
+
+    // The RGBA values of the cache's texture, rows top to bottom.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let (width, height) = (self.size.0 as usize, self.size.1 as usize);
+        let mut pixels = vec![0u8; 4 * width * height];
+        unsafe {
+            let mut previous = 0 as GLint;
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.framebuffer);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(0, 0, self.size.0, self.size.1, gl::RGBA, gl::UNSIGNED_BYTE,
+                           pixels.as_mut_ptr() as *mut c_void);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous as GLuint);
+        }
+        // OpenGL rows go bottom to top
+        let row = 4 * width;
+        for y in 0..height / 2 {
+            let (top, bottom) = pixels.split_at_mut((height - 1 - y) * row);
+            top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+        }
+        pixels
+    }
+}
+
+impl Drop for LayerCache {
+    /// Delete the framebuffer and its attachments.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+            gl::DeleteRenderbuffers(1, &self.depth_buffer);
+            gl::DeleteTextures(1, &self.texture);
+            // zero names are ignored
+            gl::DeleteFramebuffers(1, &self.multisample_framebuffer);
+            gl::DeleteRenderbuffers(1, &self.color_buffer);
+        }
+    }
+}
+
+/// Draws cached layer images over the window as fullscreen quads.
+pub struct LayerCompositor {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    position_vbo: GLuint,
+    layer_uniform: GLint,
+    opacity_uniform: GLint
+}
+
+impl LayerCompositor {
+    /// Constructor, compiles the layer shaders and creates the quad.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/layer_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/layer_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        let quad: [GLfloat; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+        unsafe {
+            let c_str = CString::new("in_position").unwrap();
+            let in_position = gl::GetAttribLocation(program_id, c_str.as_ptr());
+
+            let mut position_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut position_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, position_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (quad.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           quad.as_ptr() as *const c_void,
+                           gl::STATIC_DRAW);
+
+            let mut vao_handle = 0 as GLuint;
+            gl::GenVertexArrays(1, &mut vao_handle);
+            gl::BindVertexArray(vao_handle);
+            gl::EnableVertexAttribArray(in_position as GLuint);
+            gl::VertexAttribPointer(in_position as GLuint, 2, gl::FLOAT,
+                                    gl::FALSE as GLboolean, 0, ptr::null());
+
+            Ok(LayerCompositor {
+                layer_uniform: shader::uniform_location(program_id, "layer"),
+                opacity_uniform: shader::uniform_location(program_id, "opacity"),
+                shader_program: program,
+                vao_handle,
+                position_vbo
+            })
+        }
+    }
+
+    // Blend a cached layer over the framebuffer, without touching the depth buffer. Leaves
+    // blending off and the compositor program bound.
+    pub(crate) fn composite(&self, cache: &LayerCache, opacity: f32) {
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, cache.texture);
+            gl::Uniform1i(self.layer_uniform, 0);
+            gl::Uniform1f(self.opacity_uniform, opacity);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+            gl::BindVertexArray(self.vao_handle);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl::Disable(gl::BLEND);
+            gl::DepthMask(gl::TRUE);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+impl Drop for LayerCompositor {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.position_vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
@@ -1,2 +1,27 @@
 pub mod shader;
-pub mod drawing;
\ No newline at end of file
+pub mod drawing;
+pub mod texture;
+pub mod background;
+pub mod bake;
+pub mod gl_state;
+pub mod gl_api;
+pub mod particle_renderer;
+pub mod ellipse_renderer;
+pub mod rect_renderer;
+pub mod sdf_renderer;
+pub mod selection;
+pub mod overlay;
+pub mod debug_view;
+pub mod layer;
+pub mod pick;
+pub mod tiles;
+pub mod rtree;
+pub mod stroke_style;
+pub mod fill_pattern;
+pub mod gradient;
+pub mod resource;
+pub mod stencil_fill;
+#[cfg(feature = "software")]
+pub mod software;
+#[cfg(feature = "compute")]
+pub mod gpu_fill;
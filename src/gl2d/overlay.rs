@@ -0,0 +1,195 @@
+//! Editor overlays: a grid, rulers along the window edges and guide lines, drawn as extra paths
+//! above the drawing with strokes a window pixel thick.
+
+use super::super::bezier;
+use super::drawing::Path;
+
+// colors of the grid lines, the lines between them, the rulers and their ticks, and the guides
+const GRID_COLOR: [f32; 3] = [0.7f32, 0.7f32, 0.7f32];
+const GRID_MINOR_COLOR: [f32; 3] = [0.88f32, 0.88f32, 0.88f32];
+const RULER_BACKGROUND: [f32; 3] = [0.95f32, 0.95f32, 0.95f32];
+const RULER_TICK_COLOR: [f32; 3] = [0.35f32, 0.35f32, 0.35f32];
+const GUIDE_COLOR: [f32; 3] = [0f32, 0.7f32, 1f32];
+// width of the rulers, the length of their ticks and the least room between ticks, in pixels
+const RULER_SIZE: f32 = 20f32;
+const TICK_LENGTH: f32 = 10f32;
+const MINOR_TICK_LENGTH: f32 = 4f32;
+const MIN_TICK_SPACING: f32 = 50f32;
+// grid lines closer together than this many pixels are left out
+const MIN_GRID_SPACING: f32 = 4f32;
+// the most ticks along a ruler, in case the projection squeezes one axis far more than the other
+const MAX_TICKS: usize = 1000;
+
+/// Something drawn above the paths to help place them, see `Drawing::set_overlay`. Overlays
+/// follow the camera but keep their lines a pixel thick, and are not picked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Overlay {
+    /// Lines every `spacing` drawing units across the window, with `subdivisions` fainter cells
+    /// between them along each axis. Lines too close together to see are left out.
+    Grid { spacing: f32, subdivisions: u32 },
+    /// Rulers along the top and left edges of the window, ticked in drawing units at round
+    /// intervals. They have no numbers.
+    Rulers,
+    /// Lines across the window at the given drawing coordinates.
+    Guides(Vec<Guide>)
+}
+
+/// A guide line, see `Overlay::Guides`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Guide {
+    /// The line where x is this.
+    Vertical(f32),
+    /// The line where y is this.
+    Horizontal(f32)
+}
+
+// How the window relates to the drawing while the overlays are made.
+pub(crate) struct OverlayView<'v> {
+    // window size in pixels
+    pub size: (f32, f32),
+    // the size of a window pixel in drawing units
+    pub pixel_size: f32,
+    // window pixels from the bottom left to drawing coordinates and back
+    pub to_drawing: &'v dyn Fn((f32, f32)) -> (f32, f32),
+    pub to_window: &'v dyn Fn((f32, f32)) -> (f32, f32)
+}
+
+impl<'v> OverlayView<'v> {
+    // The part of the drawing in the window, as (min, max).
+    fn visible(&self) -> ((f32, f32), (f32, f32)) {
+        let corners = [(0f32, 0f32), (self.size.0, 0f32), (0f32, self.size.1), self.size];
+        corners.iter().map(|&c| (self.to_drawing)(c)).fold(
+            ((f32::INFINITY, f32::INFINITY), (f32::NEG_INFINITY, f32::NEG_INFINITY)),
+            |(min, max), p| ((min.0.min(p.0), min.1.min(p.1)), (max.0.max(p.0), max.1.max(p.1))))
+    }
+
+    // A line between two points in window pixels.
+    fn window_line(&self, a: (f32, f32), b: (f32, f32), color: [f32; 3]) -> Path {
+        line((self.to_drawing)(a), (self.to_drawing)(b), color)
+    }
+}
+
+// A pixel thick line between two points in drawing coordinates.
+fn line(a: (f32, f32), b: (f32, f32), color: [f32; 3]) -> Path {
+    Path::new(a).line_to(b).clear_fill_color().set_stroke(color[0], color[1], color[2], 1)
+}
+
+// The smallest of 1, 2 and 5 times a power of ten that is at least `least`.
+fn round_step(least: f32) -> f32 {
+    let power = 10f32.powf(least.log10().floor());
+    [1f32, 2f32, 5f32, 10f32].iter().map(|&m| m * power).find(|&step| step >= least).unwrap_or(10f32 * power)
+}
+
+// The multiples of step from min to max.
+fn multiples(step: f32, min: f32, max: f32) -> impl Iterator<Item = f32> {
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(move |k| k as f32 * step)
+}
+
+// The paths that draw the overlay.
+pub(crate) fn overlay_paths(overlay: &Overlay, view: &OverlayView) -> Vec<Path> {
+    let (min, max) = view.visible();
+    let mut paths = Vec::new();
+    match *overlay {
+        Overlay::Grid { spacing, subdivisions } => {
+            if !(spacing.is_finite() && spacing / view.pixel_size >= MIN_GRID_SPACING) {
+                return paths;
+            }
+            let minor = spacing / subdivisions.max(1) as f32;
+            let step = if minor / view.pixel_size >= MIN_GRID_SPACING { minor } else { spacing };
+            let is_major = |v: f32| ((v / spacing).round() * spacing - v).abs() < step / 2f32;
+            for x in multiples(step, min.0, max.0) {
+                let color = if is_major(x) { GRID_COLOR } else { GRID_MINOR_COLOR };
+                paths.push(line((x, min.1), (x, max.1), color));
+            }
+            for y in multiples(step, min.1, max.1) {
+                let color = if is_major(y) { GRID_COLOR } else { GRID_MINOR_COLOR };
+                paths.push(line((min.0, y), (max.0, y), color));
+            }
+            // the major lines go over the minor ones
+            paths.sort_by_key(|path| path.stroke() == Some((GRID_COLOR, 1)));
+        }
+        Overlay::Rulers => {
+            let (width, height) = view.size;
+            for &corners in &[[(0f32, height - RULER_SIZE), (width, height - RULER_SIZE), (width, height),
+                               (0f32, height)],
+                              [(0f32, 0f32), (RULER_SIZE, 0f32), (RULER_SIZE, height), (0f32, height)]] {
+                let mut points: Vec<(f32, f32)> = corners.iter().map(|&c| (view.to_drawing)(c)).collect();
+                // filled paths wind counter clockwise in drawing coordinates
+                if bezier::polygon_area(&points) < 0f32 {
+                    points.reverse();
+                }
+                let band = points[1..].iter().fold(Path::new(points[0]), |path, &p| path.line_to(p)).close_path()
+                    .set_fill_color(RULER_BACKGROUND[0], RULER_BACKGROUND[1], RULER_BACKGROUND[2]);
+                paths.push(band);
+            }
+            let step = round_step(MIN_TICK_SPACING * view.pixel_size);
+            let top_left = (view.to_drawing)((0f32, height));
+            for x in multiples(step / 5f32, min.0, max.0).take(MAX_TICKS) {
+                let length = if is_multiple(x, step) { TICK_LENGTH } else { MINOR_TICK_LENGTH };
+                let wx = (view.to_window)((x, top_left.1)).0;
+                if wx >= RULER_SIZE {
+                    paths.push(view.window_line((wx, height), (wx, height - length), RULER_TICK_COLOR));
+                }
+            }
+            for y in multiples(step / 5f32, min.1, max.1).take(MAX_TICKS) {
+                let length = if is_multiple(y, step) { TICK_LENGTH } else { MINOR_TICK_LENGTH };
+                let wy = (view.to_window)((top_left.0, y)).1;
+                if wy <= height - RULER_SIZE {
+                    paths.push(view.window_line((0f32, wy), (length, wy), RULER_TICK_COLOR));
+                }
+            }
+        }
+        Overlay::Guides(ref guides) => {
+            for guide in guides {
+                match *guide {
+                    Guide::Vertical(x) if x >= min.0 && x <= max.0 =>
+                        paths.push(line((x, min.1), (x, max.1), GUIDE_COLOR)),
+                    Guide::Horizontal(y) if y >= min.1 && y <= max.1 =>
+                        paths.push(line((min.0, y), (max.0, y), GUIDE_COLOR)),
+                    _ => ()
+                }
+            }
+        }
+    }
+    paths
+}
+
+// Whether v is a whole number of steps.
+fn is_multiple(v: f32, step: f32) -> bool {
+    ((v / step).round() * step - v).abs() < step / 10f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{overlay_paths, round_step, Guide, Overlay, OverlayView};
+
+    #[test]
+    fn test_overlay_paths() {
+        // 200 by 100 pixels showing the drawing at 2 pixels a unit, y up from the bottom left
+        let to_drawing = |p: (f32, f32)| (p.0 / 2f32, p.1 / 2f32);
+        let to_window = |p: (f32, f32)| (p.0 * 2f32, p.1 * 2f32);
+        let view = OverlayView { size: (200f32, 100f32), pixel_size: 0.5f32, to_drawing: &to_drawing,
+                                 to_window: &to_window };
+        // lines every 10 units from 0 to 100 across and 0 to 50 up, the minor ones 2 pixels apart are left out
+        let grid = overlay_paths(&Overlay::Grid { spacing: 10f32, subdivisions: 10 }, &view);
+        assert_eq!(grid.len(), 11 + 6);
+        let grid = overlay_paths(&Overlay::Grid { spacing: 10f32, subdivisions: 2 }, &view);
+        assert_eq!(grid.len(), 21 + 11);
+        assert!(overlay_paths(&Overlay::Grid { spacing: 1f32, subdivisions: 1 }, &view).is_empty());
+
+        let guides = Overlay::Guides(vec![Guide::Vertical(20f32), Guide::Horizontal(70f32)]);
+        let guides = overlay_paths(&guides, &view);
+        assert_eq!(guides.len(), 1);
+        assert_eq!(guides[0].vertices(), &[(20f32, 0f32), (20f32, 50f32)][..]);
+
+        // two bands, with long ticks every 50 units and short ones every 10 (20 pixels), except
+        // where the rulers cross
+        let rulers = overlay_paths(&Overlay::Rulers, &view);
+        assert!(rulers[0].is_closed() && rulers[1].is_closed());
+        assert_eq!(rulers.len(), 2 + 10 + 5);
+        assert_eq!(round_step(23f32), 50f32);
+        assert_eq!(round_step(0.3f32), 0.5f32);
+    }
+}
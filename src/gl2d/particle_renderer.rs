@@ -0,0 +1,149 @@
+extern crate gl;
+
+use std::mem;
+use std::ffi::CString;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::drawing::{Drawing, Window};
+use super::gl_state::SavedGlState;
+use super::super::particles::{Emitter, ParticleShape};
+use super::super::TrdlError;
+
+// floats per particle in the instance buffer: center x, y, size, then rgba
+const INSTANCE_FLOATS: usize = 7;
+
+/// Draws the particles of emitters as instanced quads, on top of the drawing.
+pub struct ParticleRenderer {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    corner_vbo: GLuint,
+    instance_vbo: GLuint,
+    instances: Vec<GLfloat>,
+
+    projection_uniform: GLint,
+    circle_uniform: GLint,
+    srgb_uniform: GLint
+}
+
+impl ParticleRenderer {
+    /// Constructor, compiles the particle shaders. The drawing's context must be current.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/particle_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/particle_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        let corners: [GLfloat; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+        unsafe {
+            let attribute = |name: &str| {
+                let c_str = CString::new(name).unwrap();
+                gl::GetAttribLocation(program_id, c_str.as_ptr()) as GLuint
+            };
+            let in_corner = attribute("in_corner");
+            let in_center_size = attribute("in_center_size");
+            let in_color = attribute("in_color");
+
+            let mut vao_handle = 0 as GLuint;
+            gl::GenVertexArrays(1, &mut vao_handle);
+            gl::BindVertexArray(vao_handle);
+
+            let mut corner_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut corner_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, corner_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (corners.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           corners.as_ptr() as *const c_void,
+                           gl::STATIC_DRAW);
+            gl::EnableVertexAttribArray(in_corner);
+            gl::VertexAttribPointer(in_corner, 2, gl::FLOAT, gl::FALSE as GLboolean, 0, ptr::null());
+
+            let mut instance_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut instance_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+            let stride = (INSTANCE_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+            gl::EnableVertexAttribArray(in_center_size);
+            gl::VertexAttribPointer(in_center_size, 3, gl::FLOAT, gl::FALSE as GLboolean, stride,
+                                    ptr::null());
+            gl::VertexAttribDivisor(in_center_size, 1);
+            gl::EnableVertexAttribArray(in_color);
+            gl::VertexAttribPointer(in_color, 4, gl::FLOAT, gl::FALSE as GLboolean, stride,
+                                    (3 * mem::size_of::<GLfloat>()) as *const c_void);
+            gl::VertexAttribDivisor(in_color, 1);
+
+            Ok(ParticleRenderer {
+                projection_uniform: shader::uniform_location(program_id, "projection"),
+                circle_uniform: shader::uniform_location(program_id, "circle"),
+                srgb_uniform: shader::uniform_location(program_id, "srgb"),
+
+                shader_program: program,
+                vao_handle,
+                corner_vbo,
+                instance_vbo,
+                instances: Vec::new()
+            })
+        }
+    }
+
+    /// Draw the particles of an emitter with the drawing's camera, call after `Drawing::draw`.
+    /// Particles are blended over everything else and leave the OpenGL state as they found it.
+    pub fn draw<W: Window>(&mut self, drawing: &Drawing<'_, W>, emitter: &Emitter) {
+        let fade_out = emitter.config().fade_out;
+        self.instances.clear();
+        for particle in emitter.particles() {
+            let alpha = if fade_out {
+                particle.color.alpha * (1f32 - particle.life_fraction())
+            } else {
+                particle.color.alpha
+            };
+            self.instances.extend_from_slice(&[particle.position.0, particle.position.1,
+                                               particle.size, particle.color.red,
+                                               particle.color.green, particle.color.blue, alpha]);
+        }
+        let count = emitter.particles().len();
+        if count == 0 {
+            return;
+        }
+
+        let saved = SavedGlState::save();
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean,
+                                 drawing.projection().as_ptr());
+            gl::Uniform1i(self.circle_uniform,
+                          (emitter.config().shape == ParticleShape::Circle) as GLint);
+            gl::Uniform1i(self.srgb_uniform, drawing.srgb() as GLint);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::BindVertexArray(self.vao_handle);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (self.instances.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           self.instances.as_ptr() as *const c_void,
+                           gl::STREAM_DRAW);
+            gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, count as GLsizei);
+        }
+        saved.restore();
+    }
+}
+
+impl Drop for ParticleRenderer {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.corner_vbo);
+            gl::DeleteBuffers(1, &self.instance_vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
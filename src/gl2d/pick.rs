@@ -0,0 +1,244 @@
+//! Picking on the GPU: the paths are drawn with their ids in place of colors into an integer
+//! texture, which is read back at the point being picked. It is exact for curved edges and thin
+//! strokes, and the texture is only drawn again when the paths or the camera change.
+
+use std::mem;
+use std::ptr;
+use gl::types::*;
+use super::shader;
+use super::super::TrdlError;
+
+// What the id texture shows, it is drawn again when any of it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PickKey {
+    // bumped whenever the drawing's buffers are rebuilt
+    pub version: u64,
+    pub projection: [GLfloat; 16],
+    pub window_size: [GLfloat; 2],
+    pub max_tessellation: u32,
+    pub lod_tolerance: GLfloat,
+    pub stroke_depth_offset: GLfloat,
+    pub crisp: bool,
+    pub world_scale: GLfloat,
+    // the vertex ranges of the shown layers
+    pub ranges: Vec<(usize, usize)>
+}
+
+// The picking program, its own color buffers and vertex array, and the id framebuffer.
+pub(crate) struct PickPass {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    color_vbo: GLuint,
+    stroke_color_vbo: GLuint,
+    framebuffer: GLuint,
+    texture: GLuint,
+    depth_buffer: GLuint,
+    size: (GLsizei, GLsizei),
+    key: Option<PickKey>,
+
+    outer_tess_uniform: GLint,
+    inner_tess_uniform: GLint,
+    lod_tolerance_uniform: GLint,
+    projection_uniform: GLint,
+    window_size_uniform: GLint,
+    stroke_depth_offset_uniform: GLint,
+    crisp_uniform: GLint,
+    world_scale_uniform: GLint
+}
+
+impl PickPass {
+    // Compile the picking program, the path shaders with a fragment shader that writes ids.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/vertex_shader.glsl")?;
+        let tess_control_shader_code = shader::read_file("shaders/tess_control_shader.glsl")?;
+        let tess_evaluation_shader_code = shader::read_file("shaders/tess_evaluation_shader.glsl")?;
+        let geometry_shader_code = shader::read_file("shaders/geometry_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/pick_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_tess_control_shader(&tess_control_shader_code);
+            builder.set_tess_evaluation_shader(&tess_evaluation_shader_code);
+            builder.set_geometry_shader(&geometry_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        let mut pass = PickPass {
+            outer_tess_uniform: shader::uniform_location(program_id, "outer_tess"),
+            inner_tess_uniform: shader::uniform_location(program_id, "inner_tess"),
+            lod_tolerance_uniform: shader::uniform_location(program_id, "lod_tolerance"),
+            projection_uniform: shader::uniform_location(program_id, "projection"),
+            window_size_uniform: shader::uniform_location(program_id, "window_size"),
+            stroke_depth_offset_uniform: shader::uniform_location(program_id, "stroke_depth_offset"),
+            crisp_uniform: shader::uniform_location(program_id, "crisp"),
+            world_scale_uniform: shader::uniform_location(program_id, "world_scale"),
+            shader_program: program,
+            vao_handle: 0,
+            color_vbo: 0,
+            stroke_color_vbo: 0,
+            framebuffer: 0,
+            texture: 0,
+            depth_buffer: 0,
+            size: (0, 0),
+            key: None
+        };
+        unsafe {
+            gl::GenVertexArrays(1, &mut pass.vao_handle);
+            gl::GenBuffers(1, &mut pass.color_vbo);
+            gl::GenBuffers(1, &mut pass.stroke_color_vbo);
+            gl::GenFramebuffers(1, &mut pass.framebuffer);
+            gl::GenTextures(1, &mut pass.texture);
+            gl::GenRenderbuffers(1, &mut pass.depth_buffer);
+        }
+        Ok(pass)
+    }
+
+    // Whether the id texture already shows this.
+    pub fn is_current(&self, key: &PickKey) -> bool {
+        self.key.as_ref() == Some(key)
+    }
+
+    // Make the id texture and depth buffer the size of the window.
+    fn resize(&mut self, width: GLsizei, height: GLsizei) {
+        if self.size == (width, height) {
+            return;
+        }
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::R32UI as GLint, width, height, 0,
+                           gl::RED_INTEGER, gl::UNSIGNED_INT, ptr::null());
+            // integer textures cannot be filtered
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth_buffer);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.texture, 0);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::RENDERBUFFER, self.depth_buffer);
+        }
+        self.size = (width, height);
+    }
+
+    // Draw the ranges of the drawing's vertex buffers (positions, control points, colors, edges,
+    // stroke colors and fill flags) into the id texture, with the id colors in place of the
+    // colors. Changes the program, vertex array, viewport, depth and blend state, and binds
+    // the framebuffer bound before again afterwards.
+    pub fn render(&mut self, key: PickKey, vbos: &[GLuint; 7], fill_ids: &[GLfloat], stroke_ids: &[GLfloat]) {
+        let (width, height) = (key.window_size[0] as GLsizei, key.window_size[1] as GLsizei);
+        unsafe {
+            let mut previous = 0 as GLint;
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous);
+            self.resize(width, height);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::Viewport(0, 0, width, height);
+            gl::DepthMask(gl::TRUE);
+            gl::ClearBufferuiv(gl::COLOR, 0, [0u32; 4].as_ptr());
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+
+            for &(vbo, ids) in &[(self.color_vbo, fill_ids), (self.stroke_color_vbo, stroke_ids)] {
+                gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+                // nothing to pick in an empty drawing, so no pointer to upload from
+                let pointer = if ids.is_empty() { ptr::null() } else { ids.as_ptr() as *const _ };
+                gl::BufferData(gl::ARRAY_BUFFER, mem::size_of_val(ids) as GLsizeiptr, pointer, gl::STREAM_DRAW);
+            }
+            // the drawing's buffers apart from the colors, at the vertex shader's fixed locations
+            let buffers = [vbos[0], vbos[1], vbos[2], self.color_vbo, vbos[4], self.stroke_color_vbo, vbos[6]];
+            let components = [3, 2, 2, 4, 1, 4, 1];
+            gl::BindVertexArray(self.vao_handle);
+            for location in 0..buffers.len() {
+                gl::EnableVertexAttribArray(location as GLuint);
+                gl::BindBuffer(gl::ARRAY_BUFFER, buffers[location]);
+                if location == 6 {
+                    gl::VertexAttribIPointer(location as GLuint, 1, gl::INT, 0, ptr::null());
+                } else {
+                    gl::VertexAttribPointer(location as GLuint, components[location], gl::FLOAT,
+                                            gl::FALSE as GLboolean, 0, ptr::null());
+                }
+            }
+
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::Uniform1i(self.outer_tess_uniform, key.max_tessellation as GLint);
+            gl::Uniform1i(self.inner_tess_uniform, 1);
+            gl::Uniform1f(self.lod_tolerance_uniform, key.lod_tolerance);
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean, key.projection.as_ptr());
+            gl::Uniform2fv(self.window_size_uniform, 1, key.window_size.as_ptr());
+            gl::Uniform1f(self.stroke_depth_offset_uniform, key.stroke_depth_offset);
+            gl::Uniform1i(self.crisp_uniform, key.crisp as GLint);
+            gl::Uniform1f(self.world_scale_uniform, key.world_scale);
+
+            gl::PatchParameteri(gl::PATCH_VERTICES, 3);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(gl::LESS);
+            gl::Disable(gl::BLEND);
+            gl::Disable(gl::FRAMEBUFFER_SRGB);
+            for &(start, end) in &key.ranges {
+                gl::DrawArrays(gl::PATCHES, start as GLsizei, (end - start) as GLsizei);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous as GLuint);
+        }
+        self.key = Some(key);
+    }
+
+    // The id at a pixel of the texture, 0 where there is no path or outside it.
+    pub fn read(&self, x: GLint, y: GLint) -> u32 {
+        if x < 0 || y < 0 || x >= self.size.0 || y >= self.size.1 {
+            return 0;
+        }
+        let mut id = 0u32;
+        unsafe {
+            let mut previous = 0 as GLint;
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.framebuffer);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+            gl::ReadPixels(x, y, 1, 1, gl::RED_INTEGER, gl::UNSIGNED_INT, &mut id as *mut u32 as *mut _);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous as GLuint);
+        }
+        id
+    }
+}
+
+impl Drop for PickPass {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+            gl::DeleteRenderbuffers(1, &self.depth_buffer);
+            gl::DeleteTextures(1, &self.texture);
+            gl::DeleteBuffers(1, &self.color_vbo);
+            gl::DeleteBuffers(1, &self.stroke_color_vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
+
+// The fill or stroke colors of the drawing's buffers with the id of the path each vertex belongs
+// to in red, counting paths from 1 in the order of `paths`, each a range of vertices. Alpha is
+// kept so invisible fills and strokes stay unpicked, vertices of no path get no id and alpha 0.
+pub(crate) fn id_colors(colors: &[GLfloat], paths: &[(usize, usize)]) -> Vec<GLfloat> {
+    let mut ids = vec![0 as GLfloat; colors.len()];
+    for (i, &(start, end)) in paths.iter().enumerate() {
+        for vertex in start..end {
+            ids[4 * vertex] = (i + 1) as GLfloat;
+            ids[4 * vertex + 3] = colors[4 * vertex + 3];
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::id_colors;
+
+    #[test]
+    fn test_id_colors() {
+        // two paths of one vertex each and an overlay vertex after them
+        let colors = [0.5f32, 0.5f32, 0.5f32, 1f32, 1f32, 0f32, 0f32, 0.25f32, 1f32, 1f32, 1f32, 1f32];
+        assert_eq!(id_colors(&colors, &[(0, 1), (1, 2)]),
+                   vec![1f32, 0f32, 0f32, 1f32, 2f32, 0f32, 0f32, 0.25f32, 0f32, 0f32, 0f32, 0f32]);
+    }
+}
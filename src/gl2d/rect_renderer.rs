@@ -0,0 +1,259 @@
+//! Rectangles and rounded rectangles drawn from their signed distance: each is one instanced quad,
+//! two triangles, whose fragment shader finds the distance to the outline, instead of a path
+//! tessellated into patches. Meant for interfaces made mostly of boxes.
+
+use std::mem;
+use std::ffi::CString;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::drawing::{Drawing, Path, StrokeAlign, StrokeUnits, Window};
+use super::gl_state::SavedGlState;
+use super::stroke_style::StrokeStyle;
+use super::super::bezier::Segment;
+use super::super::color::Color;
+use super::super::TrdlError;
+
+// floats per rectangle in the instance buffer: center x, y, half width, half height, angle,
+// corner radius, stroke thickness, then the fill and stroke rgba
+const INSTANCE_FLOATS: usize = 15;
+// how far from square the corners of a path can be, relative to its size, and still be a rectangle
+const RELATIVE_TOL: f32 = 1e-4;
+
+/// A rectangle for `RectRenderer`, in drawing coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub center: (f32, f32),
+    pub width: f32,
+    pub height: f32,
+    /// Rotation of the width from the x axis, radians counter clockwise.
+    pub angle: f32,
+    /// Radius of the corners, clamped to half the shorter side. Zero for sharp corners.
+    pub corner_radius: f32,
+    pub fill: Option<Color>,
+    /// Stroke color and thickness in pixels, centered on the outline.
+    pub stroke: Option<(Color, f32)>
+}
+
+impl Rect {
+    /// An axis aligned rectangle filled with color.
+    pub fn new(center: (f32, f32), width: f32, height: f32, fill: Color) -> Self {
+        Rect { center, width, height, angle: 0f32, corner_radius: 0f32, fill: Some(fill), stroke: None }
+    }
+
+    /// An axis aligned rectangle with rounded corners filled with color.
+    pub fn rounded(center: (f32, f32), width: f32, height: f32, corner_radius: f32, fill: Color) -> Self {
+        Rect { corner_radius, ..Rect::new(center, width, height, fill) }
+    }
+
+    /// The rectangle a path draws, if it is a closed path of four straight sides meeting at right
+    /// angles, like those made by `Path::rectangle`, painted with flat colors and a pixel stroke
+    /// centered on the outline. Lets paths already built for a drawing go through the renderer.
+    pub fn from_path(path: &Path) -> Option<Rect> {
+        let style = path.style();
+        let plain_stroke = style.stroke.is_none() || (style.stroke_align == StrokeAlign::Center &&
+            style.stroke_units == StrokeUnits::Pixels && *path.stroke_style() == StrokeStyle::Solid);
+        let v = path.vertices();
+        if !path.is_closed() || v.len() != 4 || !plain_stroke || !path.has_flat_fill() ||
+           path.segments().any(|segment| matches!(segment, Segment::Cubic(..))) {
+            return None;
+        }
+        let side = |a: (f32, f32), b: (f32, f32)| (b.0 - a.0, b.1 - a.1);
+        let (a, b, c, d) = (side(v[0], v[1]), side(v[1], v[2]), side(v[2], v[3]), side(v[3], v[0]));
+        let (width, height) = (a.0.hypot(a.1), b.0.hypot(b.1));
+        let tolerance = width.max(height) * RELATIVE_TOL;
+        // opposite sides equal and opposite, and the corners square
+        let closes = (a.0 + c.0).abs() <= tolerance && (a.1 + c.1).abs() <= tolerance &&
+                     (b.0 + d.0).abs() <= tolerance && (b.1 + d.1).abs() <= tolerance;
+        if !closes || (a.0 * b.0 + a.1 * b.1).abs() > width * height * RELATIVE_TOL {
+            return None;
+        }
+        let center = ((v[0].0 + v[2].0) / 2f32, (v[0].1 + v[2].1) / 2f32);
+        let color = |rgb: [f32; 3], opacity: f32| Color::rgba(rgb[0], rgb[1], rgb[2], style.opacity * opacity);
+        Some(Rect { center, width, height, angle: a.1.atan2(a.0), corner_radius: 0f32,
+                    fill: style.fill_color.map(|rgb| color(rgb, style.fill_opacity)),
+                    stroke: style.stroke.map(|(rgb, thickness)| (color(rgb, style.stroke_opacity), thickness as f32)) })
+    }
+
+    // The rectangle's instance data, None if there is nothing to draw.
+    fn instance(&self) -> Option<[GLfloat; INSTANCE_FLOATS]> {
+        let valid = |size: f32| size > 0f32 && size.is_finite();
+        if !valid(self.width) || !valid(self.height) || (self.fill.is_none() && self.stroke.is_none()) {
+            return None;
+        }
+        let (half_width, half_height) = (self.width / 2f32, self.height / 2f32);
+        let radius = self.corner_radius.max(0f32).min(half_width.min(half_height));
+        let none = Color::rgba(0f32, 0f32, 0f32, 0f32);
+        let fill = self.fill.unwrap_or(none);
+        let (stroke, thickness) = self.stroke.unwrap_or((none, 0f32));
+        Some([self.center.0, self.center.1, half_width, half_height, self.angle, radius, thickness.max(0f32),
+              fill.red, fill.green, fill.blue, fill.alpha, stroke.red, stroke.green, stroke.blue, stroke.alpha])
+    }
+}
+
+/// Draws rectangles as instanced quads, on top of the drawing, anti-aliased against their exact
+/// outlines at any zoom.
+pub struct RectRenderer {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    corner_vbo: GLuint,
+    instance_vbo: GLuint,
+    instances: Vec<GLfloat>,
+
+    projection_uniform: GLint,
+    pixel_size_uniform: GLint,
+    srgb_uniform: GLint
+}
+
+impl RectRenderer {
+    /// Constructor, compiles the rectangle shaders. The drawing's context must be current.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/rect_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/rect_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        let corners: [GLfloat; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+        unsafe {
+            let attribute = |name: &str| {
+                let c_str = CString::new(name).unwrap();
+                gl::GetAttribLocation(program_id, c_str.as_ptr()) as GLuint
+            };
+            let in_corner = attribute("in_corner");
+
+            let mut vao_handle = 0 as GLuint;
+            gl::GenVertexArrays(1, &mut vao_handle);
+            gl::BindVertexArray(vao_handle);
+
+            let mut corner_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut corner_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, corner_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (corners.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           corners.as_ptr() as *const c_void,
+                           gl::STATIC_DRAW);
+            gl::EnableVertexAttribArray(in_corner);
+            gl::VertexAttribPointer(in_corner, 2, gl::FLOAT, gl::FALSE as GLboolean, 0, ptr::null());
+
+            let mut instance_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut instance_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+            let stride = (INSTANCE_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+            // name, components and offset in floats of each per rectangle attribute
+            let attributes = [("in_center_half_size", 4, 0), ("in_angle_radius_thickness", 3, 4), ("in_fill", 4, 7),
+                              ("in_stroke", 4, 11)];
+            for &(name, components, offset) in &attributes {
+                let location = attribute(name);
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribPointer(location, components, gl::FLOAT, gl::FALSE as GLboolean, stride,
+                                        (offset * mem::size_of::<GLfloat>()) as *const c_void);
+                gl::VertexAttribDivisor(location, 1);
+            }
+
+            Ok(RectRenderer {
+                projection_uniform: shader::uniform_location(program_id, "projection"),
+                pixel_size_uniform: shader::uniform_location(program_id, "pixel_size"),
+                srgb_uniform: shader::uniform_location(program_id, "srgb"),
+
+                shader_program: program,
+                vao_handle,
+                corner_vbo,
+                instance_vbo,
+                instances: Vec::new()
+            })
+        }
+    }
+
+    /// Draw rectangles with the drawing's camera, in order and in one draw call, call after
+    /// `Drawing::draw`. They are blended over everything else and leave the OpenGL state as they
+    /// found it. Rectangles without a positive size or without fill and stroke are skipped.
+    pub fn draw<W: Window>(&mut self, drawing: &Drawing<'_, W>, rects: &[Rect]) {
+        self.instances.clear();
+        for instance in rects.iter().filter_map(Rect::instance) {
+            self.instances.extend_from_slice(&instance);
+        }
+        let count = self.instances.len() / INSTANCE_FLOATS;
+        if count == 0 {
+            return;
+        }
+
+        let saved = SavedGlState::save();
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean,
+                                 drawing.projection().as_ptr());
+            gl::Uniform1f(self.pixel_size_uniform, drawing.pixel_size());
+            gl::Uniform1i(self.srgb_uniform, drawing.srgb() as GLint);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            // the shader gives premultiplied colors
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::BindVertexArray(self.vao_handle);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (self.instances.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           self.instances.as_ptr() as *const c_void,
+                           gl::STREAM_DRAW);
+            gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, count as GLsizei);
+        }
+        saved.restore();
+    }
+}
+
+impl Drop for RectRenderer {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.corner_vbo);
+            gl::DeleteBuffers(1, &self.instance_vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rect;
+    use super::super::drawing::Path;
+    use super::super::super::color::Color;
+
+    #[test]
+    fn test_instance() {
+        let red = Color::rgb(1f32, 0f32, 0f32);
+        let mut rect = Rect::rounded((1f32, 2f32), 4f32, 2f32, 3f32, red);
+        rect.stroke = Some((Color::rgba(0f32, 0f32, 1f32, 0.5f32), 2f32));
+        // the radius is clamped to half the height
+        assert_eq!(rect.instance(), Some([1f32, 2f32, 2f32, 1f32, 0f32, 1f32, 2f32, 1f32, 0f32, 0f32, 1f32,
+                                          0f32, 0f32, 1f32, 0.5f32]));
+        assert_eq!(Rect { width: 0f32, ..rect }.instance(), None);
+        assert_eq!(Rect { fill: None, stroke: None, ..rect }.instance(), None);
+    }
+
+    #[test]
+    fn test_from_path() {
+        let path = Path::rectangle((1f32, 2f32), 4f32, 2f32, 0f32).set_fill_color(1f32, 0f32, 0f32)
+            .set_stroke(0f32, 0f32, 1f32, 3).set_opacity(0.5f32);
+        let rect = Rect::from_path(&path).unwrap();
+        assert_eq!((rect.center, rect.width, rect.height, rect.angle), ((1f32, 2f32), 4f32, 2f32, 0f32));
+        assert_eq!(rect.fill, Some(Color::rgba(1f32, 0f32, 0f32, 0.5f32)));
+        assert_eq!(rect.stroke, Some((Color::rgba(0f32, 0f32, 1f32, 0.5f32), 3f32)));
+
+        let turned = Rect::from_path(&Path::rectangle((0f32, 0f32), 4f32, 2f32, 0.5f32)).unwrap();
+        assert!((turned.angle - 0.5f32).abs() < 1e-5 && (turned.width - 4f32).abs() < 1e-5);
+
+        let kite = Path::new((0f32, 0f32)).line_to((2f32, 0f32)).line_to((3f32, 3f32)).line_to((0f32, 2f32))
+            .close_path();
+        assert_eq!(Rect::from_path(&kite), None);
+        assert_eq!(Rect::from_path(&Path::ellipse((0f32, 0f32), 1f32, 1f32, 0f32)), None);
+        assert_eq!(Rect::from_path(&path.set_stroke_units(super::StrokeUnits::World)), None);
+    }
+}
@@ -0,0 +1,54 @@
+//! The GPU resources a drawing makes on request, and the handles to them. Handles belong to the
+//! drawing that made them: every drawing numbers its resources in a namespace of its own, so a
+//! handle given to another drawing is not mistaken for one of its resources.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::bake::TextureHandle;
+use super::gradient::GradientId;
+
+// tells drawings apart, so handles from one are not taken by another
+static NEXT_NAMESPACE: AtomicUsize = AtomicUsize::new(0);
+
+// A new namespace for a drawing's handles.
+pub(crate) fn new_namespace() -> usize {
+    NEXT_NAMESPACE.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A resource to free with `Drawing::destroy`. Handles convert into it, so they can be passed as
+/// they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Resource {
+    /// A baked texture, see `Drawing::bake`.
+    Texture(TextureHandle),
+    /// A gradient, see `Drawing::create_gradient`.
+    Gradient(GradientId)
+}
+
+impl From<TextureHandle> for Resource {
+    fn from(handle: TextureHandle) -> Self {
+        Resource::Texture(handle)
+    }
+}
+
+impl From<GradientId> for Resource {
+    fn from(gradient: GradientId) -> Self {
+        Resource::Gradient(gradient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{new_namespace, Resource};
+    use super::super::bake::TextureHandle;
+    use super::super::gradient::GradientId;
+
+    #[test]
+    fn test_namespaces() {
+        let (a, b) = (new_namespace(), new_namespace());
+        assert_ne!(a, b);
+        // the same number in two drawings is two handles
+        assert_ne!(GradientId { namespace: a, slot: 0 }, GradientId { namespace: b, slot: 0 });
+        assert_eq!(Resource::from(TextureHandle { namespace: a, index: 3 }),
+                   Resource::Texture(TextureHandle { namespace: a, index: 3 }));
+    }
+}
@@ -0,0 +1,338 @@
+//! An R-tree of path bounds, for finding the paths in a rectangle or nearest a point without
+//! looking at every path. Nodes are split by sorting their entries along the longer side of
+//! their bounds, and underfull nodes on removal have their items inserted again.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+// a rectangle, (min, max)
+pub(crate) type Rect = ((f32, f32), (f32, f32));
+
+// most entries in a node before it is split
+const MAX_ENTRIES: usize = 16;
+// fewest entries in a node other than the root before it is dissolved
+const MIN_ENTRIES: usize = 4;
+
+enum Node<T> {
+    Leaf(Vec<(Rect, T)>),
+    Inner(Vec<(Rect, Box<Node<T>>)>)
+}
+
+impl<T: Copy> Node<T> {
+    fn len(&self) -> usize {
+        match *self {
+            Node::Leaf(ref items) => items.len(),
+            Node::Inner(ref children) => children.len()
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        let rects: Vec<Rect> = match *self {
+            Node::Leaf(ref items) => items.iter().map(|&(rect, _)| rect).collect(),
+            Node::Inner(ref children) => children.iter().map(|&(rect, _)| rect).collect()
+        };
+        rects.into_iter().reduce(union).unwrap_or(((0f32, 0f32), (0f32, 0f32)))
+    }
+
+    // Every item in and below the node.
+    fn drain_items(self, items: &mut Vec<(Rect, T)>) {
+        match self {
+            Node::Leaf(mut leaf_items) => items.append(&mut leaf_items),
+            Node::Inner(children) => {
+                for (_, child) in children {
+                    child.drain_items(items);
+                }
+            }
+        }
+    }
+
+    // Insert an item below the node, returning the new sibling if the node had to be split.
+    fn insert(&mut self, rect: Rect, item: T) -> Option<(Rect, Box<Node<T>>)> {
+        match *self {
+            Node::Leaf(ref mut items) => {
+                items.push((rect, item));
+                if items.len() > MAX_ENTRIES {
+                    let other = split(items);
+                    return Some((bounds_of(&other), Box::new(Node::Leaf(other))));
+                }
+            }
+            Node::Inner(ref mut children) => {
+                // the child that grows least, then the smallest
+                let best = (0..children.len()).min_by(|&a, &b| {
+                    let growth = |i: usize| area(union(children[i].0, rect)) - area(children[i].0);
+                    growth(a).total_cmp(&growth(b)).then(area(children[a].0).total_cmp(&area(children[b].0)))
+                }).unwrap();
+                children[best].0 = union(children[best].0, rect);
+                if let Some(sibling) = children[best].1.insert(rect, item) {
+                    children[best].0 = children[best].1.bounds();
+                    children.push(sibling);
+                    if children.len() > MAX_ENTRIES {
+                        let other = split(children);
+                        return Some((bounds_of(&other), Box::new(Node::Inner(other))));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<T: Copy + PartialEq> Node<T> {
+    // Remove an item with the bounds it was inserted with. Items of nodes left underfull are
+    // moved to orphans. Returns whether it was found.
+    fn remove(&mut self, rect: Rect, item: T, orphans: &mut Vec<(Rect, T)>) -> bool {
+        match *self {
+            Node::Leaf(ref mut items) => {
+                match items.iter().position(|&(_, other)| other == item) {
+                    Some(i) => {
+                        items.swap_remove(i);
+                        true
+                    }
+                    None => false
+                }
+            }
+            Node::Inner(ref mut children) => {
+                for i in 0..children.len() {
+                    if !contains(children[i].0, rect) || !children[i].1.remove(rect, item, orphans) {
+                        continue;
+                    }
+                    if children[i].1.len() < MIN_ENTRIES {
+                        let (_, child) = children.swap_remove(i);
+                        child.drain_items(orphans);
+                    } else {
+                        children[i].0 = children[i].1.bounds();
+                    }
+                    return true;
+                }
+                false
+            }
+        }
+    }
+}
+
+// Split entries in two along the longer side of their bounds, returning the upper half.
+fn split<E>(entries: &mut Vec<(Rect, E)>) -> Vec<(Rect, E)> {
+    let bounds = bounds_of(entries);
+    let center = |rect: &Rect, axis: usize| if axis == 0 { rect.0 .0 + rect.1 .0 } else { rect.0 .1 + rect.1 .1 };
+    let axis = if bounds.1 .0 - bounds.0 .0 >= bounds.1 .1 - bounds.0 .1 { 0 } else { 1 };
+    entries.sort_by(|a, b| center(&a.0, axis).total_cmp(&center(&b.0, axis)));
+    let half = entries.len() / 2;
+    entries.split_off(half)
+}
+
+fn bounds_of<E>(entries: &[(Rect, E)]) -> Rect {
+    entries.iter().map(|entry| entry.0).reduce(union).unwrap_or(((0f32, 0f32), (0f32, 0f32)))
+}
+
+fn union(a: Rect, b: Rect) -> Rect {
+    ((a.0 .0.min(b.0 .0), a.0 .1.min(b.0 .1)), (a.1 .0.max(b.1 .0), a.1 .1.max(b.1 .1)))
+}
+
+fn area(rect: Rect) -> f32 {
+    (rect.1 .0 - rect.0 .0) * (rect.1 .1 - rect.0 .1)
+}
+
+fn contains(a: Rect, b: Rect) -> bool {
+    a.0 .0 <= b.0 .0 && a.0 .1 <= b.0 .1 && b.1 .0 <= a.1 .0 && b.1 .1 <= a.1 .1
+}
+
+fn overlaps(a: Rect, b: Rect) -> bool {
+    a.0 .0 <= b.1 .0 && b.0 .0 <= a.1 .0 && a.0 .1 <= b.1 .1 && b.0 .1 <= a.1 .1
+}
+
+// Distance from a point to the nearest point of a rectangle, 0 inside it.
+fn distance_to(rect: Rect, point: (f32, f32)) -> f32 {
+    let dx = (rect.0 .0 - point.0).max(point.0 - rect.1 .0).max(0f32);
+    let dy = (rect.0 .1 - point.1).max(point.1 - rect.1 .1).max(0f32);
+    dx.hypot(dy)
+}
+
+// A node or item waiting in the nearest neighbour search, the closest first.
+struct Candidate<'a, T: 'a> {
+    distance: f32,
+    // an item's distance has been worked out exactly, a node's is a lower bound
+    exact: bool,
+    entry: Entry<'a, T>
+}
+
+enum Entry<'a, T: 'a> {
+    Node(&'a Node<T>),
+    Item(T)
+}
+
+impl<'a, T> PartialEq for Candidate<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, T> Eq for Candidate<'a, T> {}
+
+impl<'a, T> PartialOrd for Candidate<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, T> Ord for Candidate<'a, T> {
+    // reversed, so the binary heap pops the smallest distance. NaN has a place in the total order
+    // like any other distance, so the heap stays consistent.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+// Items with bounds, kept balanced as items come and go.
+pub(crate) struct RTree<T> {
+    root: Node<T>,
+    rects: HashMap<T, Rect>
+}
+
+impl<T: Copy + Eq + Hash> RTree<T> {
+    pub fn new() -> Self {
+        RTree { root: Node::Leaf(Vec::new()), rects: HashMap::new() }
+    }
+
+    // Add an item, or move it if it is already there.
+    pub fn insert(&mut self, item: T, rect: Rect) {
+        self.remove(item);
+        self.rects.insert(item, rect);
+        self.insert_entry(rect, item);
+    }
+
+    fn insert_entry(&mut self, rect: Rect, item: T) {
+        if let Some(sibling) = self.root.insert(rect, item) {
+            let old_root = std::mem::replace(&mut self.root, Node::Leaf(Vec::new()));
+            self.root = Node::Inner(vec![(old_root.bounds(), Box::new(old_root)), sibling]);
+        }
+    }
+
+    // Take an item out, returns whether it was there.
+    pub fn remove(&mut self, item: T) -> bool {
+        let rect = match self.rects.remove(&item) {
+            Some(rect) => rect,
+            None => return false
+        };
+        let mut orphans = Vec::new();
+        self.root.remove(rect, item, &mut orphans);
+        // a root with a single child is replaced by it
+        loop {
+            let only_child = match self.root {
+                Node::Inner(ref mut children) if children.len() == 1 => children.pop().map(|(_, child)| child),
+                Node::Inner(ref children) if children.is_empty() => Some(Box::new(Node::Leaf(Vec::new()))),
+                _ => None
+            };
+            match only_child {
+                Some(child) => self.root = *child,
+                None => break
+            }
+        }
+        for (rect, orphan) in orphans {
+            self.insert_entry(rect, orphan);
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.root = Node::Leaf(Vec::new());
+        self.rects.clear();
+    }
+
+    // The items whose rectangles overlap rect, in no particular order.
+    pub fn query(&self, rect: Rect) -> Vec<T> {
+        let mut found = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match *node {
+                Node::Leaf(ref items) => {
+                    found.extend(items.iter().filter(|&&(item_rect, _)| overlaps(rect, item_rect)).map(|&(_, item)| item));
+                }
+                Node::Inner(ref children) => {
+                    stack.extend(children.iter().filter(|&&(child_rect, _)| overlaps(rect, child_rect)).map(|(_, child)| &**child));
+                }
+            }
+        }
+        found
+    }
+
+    // The item nearest a point by `distance`, which must be no less than the distance to the
+    // item's rectangle, along with that distance. Only items closer than max_distance count.
+    pub fn nearest<F: FnMut(T) -> f32>(&self, point: (f32, f32), max_distance: f32, mut distance: F) -> Option<(T, f32)> {
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate { distance: 0f32, exact: false, entry: Entry::Node(&self.root) });
+        while let Some(candidate) = heap.pop() {
+            if candidate.distance > max_distance {
+                break;
+            }
+            match candidate.entry {
+                Entry::Item(item) if candidate.exact => return Some((item, candidate.distance)),
+                Entry::Item(item) => {
+                    // its exact distance goes back in the queue, nothing else can be closer
+                    // than it until it is popped again
+                    heap.push(Candidate { distance: distance(item), exact: true, entry: Entry::Item(item) });
+                }
+                Entry::Node(Node::Leaf(items)) => {
+                    for &(rect, item) in items {
+                        heap.push(Candidate { distance: distance_to(rect, point), exact: false, entry: Entry::Item(item) });
+                    }
+                }
+                Entry::Node(Node::Inner(children)) => {
+                    for &(rect, ref child) in children {
+                        heap.push(Candidate { distance: distance_to(rect, point), exact: false, entry: Entry::Node(child) });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RTree;
+
+    fn unit_square(x: f32, y: f32) -> ((f32, f32), (f32, f32)) {
+        ((x, y), (x + 1f32, y + 1f32))
+    }
+
+    #[test]
+    fn test_query_and_remove() {
+        let mut tree = RTree::new();
+        // enough squares on a grid for a few levels of nodes
+        for i in 0..400 {
+            tree.insert(i, unit_square((i % 20) as f32 * 2f32, (i / 20) as f32 * 2f32));
+        }
+        assert_eq!(tree.query(((-1f32, -1f32), (40f32, 40f32))).len(), 400);
+        let mut found = tree.query(((0f32, 0f32), (2.5f32, 0.5f32)));
+        found.sort();
+        assert_eq!(found, vec![0, 1]);
+        for i in 0..400 {
+            if i % 3 != 0 {
+                assert!(tree.remove(i));
+            }
+        }
+        assert!(!tree.remove(1));
+        let mut found = tree.query(((0f32, 0f32), (40f32, 40f32)));
+        found.sort();
+        assert_eq!(found, (0..400).filter(|i| i % 3 == 0).collect::<Vec<_>>());
+        // moving an item
+        tree.insert(0, unit_square(100f32, 100f32));
+        assert_eq!(tree.query(unit_square(100f32, 100f32)), vec![0]);
+        assert!(tree.query(((0f32, 0f32), (0.5f32, 0.5f32))).is_empty());
+    }
+
+    #[test]
+    fn test_nearest() {
+        let mut tree = RTree::new();
+        for i in 0..100 {
+            tree.insert(i, unit_square(i as f32 * 3f32, 0f32));
+        }
+        // the distance to each square's center
+        let center_distance = |i: i32| (i as f32 * 3f32 + 0.5f32 - 31f32).hypot(0.5f32 - 5f32);
+        let (nearest, distance) = tree.nearest((31f32, 5f32), f32::MAX, center_distance).unwrap();
+        assert_eq!(nearest, 10);
+        assert!((distance - center_distance(10)).abs() < 1e-6);
+        assert!(tree.nearest((31f32, 5f32), 4f32, center_distance).is_none());
+    }
+}
@@ -0,0 +1,206 @@
+extern crate gl;
+
+use std::mem;
+use std::ffi::CString;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::drawing::{Drawing, Window};
+use super::gl_state::SavedGlState;
+use super::super::sdf::{SdfAtlas, SdfQuad, SdfStyle};
+use super::super::TrdlError;
+
+// floats per quad in the instance buffer: min x, y, max x, y, then the atlas layer
+const INSTANCE_FLOATS: usize = 5;
+
+/// Draws distance field quads from an `SdfAtlas` as instanced quads, on top of the drawing.
+pub struct SdfRenderer {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    corner_vbo: GLuint,
+    instance_vbo: GLuint,
+    texture_id: GLuint,
+    // the atlas version in the texture
+    uploaded: Option<(usize, usize)>,
+    instances: Vec<GLfloat>,
+
+    projection_uniform: GLint,
+    atlas_uniform: GLint,
+    cell_size_uniform: GLint,
+    spread_uniform: GLint,
+    color_uniform: GLint,
+    outline_color_uniform: GLint,
+    outline_width_uniform: GLint,
+    shadow_color_uniform: GLint,
+    shadow_offset_uniform: GLint,
+    shadow_softness_uniform: GLint,
+    srgb_uniform: GLint
+}
+
+impl SdfRenderer {
+    /// Constructor, compiles the distance field shaders. The drawing's context must be current.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/sdf_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/sdf_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        let corners: [GLfloat; 8] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+        unsafe {
+            let attribute = |name: &str| {
+                let c_str = CString::new(name).unwrap();
+                gl::GetAttribLocation(program_id, c_str.as_ptr()) as GLuint
+            };
+            let in_corner = attribute("in_corner");
+            let in_rect = attribute("in_rect");
+            let in_layer = attribute("in_layer");
+
+            let mut vao_handle = 0 as GLuint;
+            gl::GenVertexArrays(1, &mut vao_handle);
+            gl::BindVertexArray(vao_handle);
+
+            let mut corner_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut corner_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, corner_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (corners.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           corners.as_ptr() as *const c_void,
+                           gl::STATIC_DRAW);
+            gl::EnableVertexAttribArray(in_corner);
+            gl::VertexAttribPointer(in_corner, 2, gl::FLOAT, gl::FALSE as GLboolean, 0, ptr::null());
+
+            let mut instance_vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut instance_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+            let stride = (INSTANCE_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+            gl::EnableVertexAttribArray(in_rect);
+            gl::VertexAttribPointer(in_rect, 4, gl::FLOAT, gl::FALSE as GLboolean, stride, ptr::null());
+            gl::VertexAttribDivisor(in_rect, 1);
+            gl::EnableVertexAttribArray(in_layer);
+            gl::VertexAttribPointer(in_layer, 1, gl::FLOAT, gl::FALSE as GLboolean, stride,
+                                    (4 * mem::size_of::<GLfloat>()) as *const c_void);
+            gl::VertexAttribDivisor(in_layer, 1);
+
+            let mut texture_id = 0 as GLuint;
+            gl::GenTextures(1, &mut texture_id);
+
+            Ok(SdfRenderer {
+                projection_uniform: shader::uniform_location(program_id, "projection"),
+                atlas_uniform: shader::uniform_location(program_id, "atlas"),
+                cell_size_uniform: shader::uniform_location(program_id, "cell_size"),
+                spread_uniform: shader::uniform_location(program_id, "spread"),
+                color_uniform: shader::uniform_location(program_id, "color"),
+                outline_color_uniform: shader::uniform_location(program_id, "outline_color"),
+                outline_width_uniform: shader::uniform_location(program_id, "outline_width"),
+                shadow_color_uniform: shader::uniform_location(program_id, "shadow_color"),
+                shadow_offset_uniform: shader::uniform_location(program_id, "shadow_offset"),
+                shadow_softness_uniform: shader::uniform_location(program_id, "shadow_softness"),
+                srgb_uniform: shader::uniform_location(program_id, "srgb"),
+
+                shader_program: program,
+                vao_handle,
+                corner_vbo,
+                instance_vbo,
+                texture_id,
+                uploaded: None,
+                instances: Vec::new()
+            })
+        }
+    }
+
+    // Upload the atlas to the texture array, again whenever fields were added to it.
+    fn upload(&mut self, atlas: &SdfAtlas) {
+        if self.uploaded == Some(atlas.version()) {
+            return;
+        }
+        let size = atlas.cell_size() as GLsizei;
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.texture_id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage3D(gl::TEXTURE_2D_ARRAY, 0, gl::R8 as GLint, size, size, atlas.len() as GLsizei, 0,
+                           gl::RED, gl::UNSIGNED_BYTE, atlas.all_texels().as_ptr() as *const c_void);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+        }
+        self.uploaded = Some(atlas.version());
+    }
+
+    /// Draw quads from an atlas in a style with the drawing's camera, call after `Drawing::draw`.
+    /// Quads are blended over everything else and leave the OpenGL state as they found it. Quads
+    /// with ids from another atlas are skipped.
+    pub fn draw<W: Window>(&mut self, drawing: &Drawing<'_, W>, atlas: &SdfAtlas, quads: &[SdfQuad],
+                           style: &SdfStyle) {
+        self.instances.clear();
+        for quad in quads {
+            if let Some(entry) = atlas.entry(quad.id) {
+                self.instances.extend_from_slice(&[
+                    quad.position.0 + entry.min.0 * quad.scale, quad.position.1 + entry.min.1 * quad.scale,
+                    quad.position.0 + entry.max.0 * quad.scale, quad.position.1 + entry.max.1 * quad.scale,
+                    quad.id.0 as GLfloat
+                ]);
+            }
+        }
+        let count = self.instances.len() / INSTANCE_FLOATS;
+        if count == 0 {
+            return;
+        }
+
+        let saved = SavedGlState::save();
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+        self.upload(atlas);
+        let shadow = style.shadow.map(|shadow| (shadow.color.to_array(), shadow.offset, shadow.softness))
+            .unwrap_or(([0f32; 4], (0f32, 0f32), 0f32));
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean,
+                                 drawing.projection().as_ptr());
+            gl::Uniform1i(self.atlas_uniform, 0);
+            gl::Uniform1f(self.cell_size_uniform, atlas.cell_size() as GLfloat);
+            gl::Uniform1f(self.spread_uniform, atlas.spread());
+            gl::Uniform4fv(self.color_uniform, 1, style.color.to_array().as_ptr());
+            gl::Uniform4fv(self.outline_color_uniform, 1, style.outline_color.to_array().as_ptr());
+            gl::Uniform1f(self.outline_width_uniform, style.outline_width.max(0f32));
+            gl::Uniform4fv(self.shadow_color_uniform, 1, shadow.0.as_ptr());
+            gl::Uniform2f(self.shadow_offset_uniform, (shadow.1).0, (shadow.1).1);
+            gl::Uniform1f(self.shadow_softness_uniform, shadow.2.max(0f32));
+            gl::Uniform1i(self.srgb_uniform, drawing.srgb() as GLint);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            // the shader gives premultiplied colors
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::BindVertexArray(self.vao_handle);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (self.instances.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           self.instances.as_ptr() as *const c_void,
+                           gl::STREAM_DRAW);
+            gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, count as GLsizei);
+        }
+        saved.restore();
+    }
+}
+
+impl Drop for SdfRenderer {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture_id);
+            gl::DeleteBuffers(1, &self.corner_vbo);
+            gl::DeleteBuffers(1, &self.instance_vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
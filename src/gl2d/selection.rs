@@ -0,0 +1,112 @@
+//! Highlighting of selected paths: an outline, bounding box handles and control point markers,
+//! drawn as extra paths above everything else.
+
+use super::super::bezier::Segment;
+use super::super::color::Color;
+use super::drawing::Path;
+
+/// How a selected path is highlighted, see `Drawing::set_selected`. Sizes are in window pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelectionStyle {
+    pub color: Color,
+    /// Thickness of the outline traced over the path, 0 for none.
+    pub outline_width: u32,
+    /// Draw the bounding box with handles on its corners and edge midpoints.
+    pub show_bounds: bool,
+    /// Mark the vertices and control points, with lines joining each control point to its vertex.
+    pub show_control_points: bool,
+    /// Width of the handles and markers.
+    pub handle_size: f32
+}
+
+impl Default for SelectionStyle {
+    fn default() -> Self {
+        SelectionStyle {
+            color: Color::rgb(0.1f32, 0.5f32, 1f32),
+            outline_width: 2,
+            show_bounds: true,
+            show_control_points: false,
+            handle_size: 7f32
+        }
+    }
+}
+
+// The paths highlighting `path`, where a window pixel is `pixel_size` drawing units.
+pub(crate) fn overlay_paths(path: &Path, style: &SelectionStyle, pixel_size: f32) -> Vec<Path> {
+    let [red, green, blue, alpha] = style.color.to_array();
+    let handle = style.handle_size * pixel_size;
+    let marker = |center: (f32, f32)| {
+        Path::rectangle(center, handle, handle, 0f32).set_fill_color(red, green, blue).set_opacity(alpha)
+    };
+    let line = |a: (f32, f32), b: (f32, f32)| {
+        Path::new(a).line_to(b).clear_fill_color().set_stroke(red, green, blue, 1).set_opacity(alpha)
+    };
+    let mut overlays = Vec::new();
+
+    if style.outline_width > 0 {
+        overlays.push(path.clone().clear_fill_color()
+                          .set_stroke(red, green, blue, style.outline_width).set_opacity(alpha));
+    }
+    if style.show_bounds {
+        let (min, max) = path.tight_bounds();
+        let center = ((min.0 + max.0) / 2f32, (min.1 + max.1) / 2f32);
+        let (width, height) = (max.0 - min.0, max.1 - min.1);
+        if width > 0f32 && height > 0f32 {
+            overlays.push(Path::rectangle(center, width, height, 0f32).clear_fill_color()
+                              .set_stroke(red, green, blue, 1).set_opacity(alpha));
+        }
+        for &x in &[min.0, center.0, max.0] {
+            for &y in &[min.1, center.1, max.1] {
+                if (x, y) != center {
+                    overlays.push(marker((x, y)));
+                }
+            }
+        }
+    }
+    if style.show_control_points {
+        let segments = path.segment_list();
+        for &segment in &segments {
+            match segment {
+                Segment::Line(start, _) => overlays.push(marker(start)),
+                Segment::Cubic(p0, p1, p2, p3) => {
+                    overlays.push(marker(p0));
+                    for &(vertex, control) in &[(p0, p1), (p3, p2)] {
+                        if vertex != control {
+                            overlays.push(line(vertex, control));
+                        }
+                        overlays.push(marker(control));
+                    }
+                }
+            }
+        }
+        // an open path's last vertex is not the start of any segment
+        if let (Some(first), Some(last)) = (segments.first(), segments.last()) {
+            let end = last.point_at(1f32);
+            if end != first.point_at(0f32) {
+                overlays.push(marker(end));
+            }
+        }
+    }
+    overlays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{overlay_paths, SelectionStyle};
+    use super::super::drawing::Path;
+
+    #[test]
+    fn test_overlay_paths() {
+        let square = Path::rectangle((0f32, 0f32), 2f32, 2f32, 0f32).set_fill_color(1f32, 0f32, 0f32);
+        let style = SelectionStyle::default();
+        // outline, bounding box and 8 handles
+        assert_eq!(overlay_paths(&square, &style, 1f32).len(), 10);
+        let style = SelectionStyle { outline_width: 0, show_bounds: false, show_control_points: true,
+                                     ..SelectionStyle::default() };
+        // one marker per corner
+        assert_eq!(overlay_paths(&square, &style, 1f32).len(), 4);
+        let curve = Path::new((0f32, 0f32)).curve_to((1f32, 1f32), (2f32, 1f32), (3f32, 0f32));
+        // 2 vertices, 2 control points and the lines joining them
+        assert_eq!(overlay_paths(&curve, &style, 1f32).len(), 6);
+    }
+}
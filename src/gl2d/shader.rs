@@ -1,12 +1,19 @@
 use gl;
 use gl::types::*;
-use std::ptr;
 use std::str;
 use std::ffi::CString;
+use std::io::prelude::*;
+use std::fs::File;
+use std::env;
+use std::path::Path;
+use super::gl_api::{Gl, GlApi};
 use super::super::TrdlError;
 
+// environment variable naming the directory the shaders are read from
+const SHADER_DIR_VAR: &str = "TRDL_SHADER_DIR";
+
 // Default pass through shader if none specified.
-static DEFAULT_VERTEX_SHADER: &'static str =
+static DEFAULT_VERTEX_SHADER: &str =
     r"#version 400
     in vec3 vertex_position;
     in vec3 vertex_color;
@@ -17,7 +24,7 @@ static DEFAULT_VERTEX_SHADER: &'static str =
     }";
 
 // Default shader if none specified.
-static DEFAULT_FRAGMENT_SHADER: &'static str =
+static DEFAULT_FRAGMENT_SHADER: &str =
     r"#version 400
     in vec3 color;
     layout(location = 0) out vec4 frag_color;
@@ -31,7 +38,8 @@ pub struct ShaderProgramBuilder<'a> {
     tess_control_shader_code: Option<&'a str>,
     tess_evaluation_shader_code: Option<&'a str>,
     geometry_shader_code: Option<&'a str>,
-    fragment_shader_code: &'a str
+    fragment_shader_code: &'a str,
+    compute_shader_code: Option<&'a str>
 }
 
 /// Compiled shader program handles. Also cleans up on drop.
@@ -42,6 +50,7 @@ pub struct ShaderProgram {
     tess_evaluation_shader_id: Option<GLuint>,
     geometry_shader_id: Option<GLuint>,
     fragment_shader_id: GLuint,
+    compute_shader_id: Option<GLuint>,
     program_id: GLuint
 }
 
@@ -53,7 +62,8 @@ impl<'a> ShaderProgramBuilder<'a> {
             tess_control_shader_code: None,
             tess_evaluation_shader_code: None,
             geometry_shader_code: None,
-            fragment_shader_code: DEFAULT_FRAGMENT_SHADER
+            fragment_shader_code: DEFAULT_FRAGMENT_SHADER,
+            compute_shader_code: None
         }
     }
 
@@ -87,139 +97,187 @@ impl<'a> ShaderProgramBuilder<'a> {
         self
     }
 
+    /// Set the compute shader code string. A program with a compute shader has no other
+    /// shaders, the rest are ignored. Needs OpenGL 4.3.
+    #[cfg(feature = "compute")]
+    pub fn set_compute_shader<'b>(&'b mut self, code: &'a str) -> &'b mut Self {
+        self.compute_shader_code = Some(code);
+        self
+    }
+
     /// Compile all the shaders and link into a shader program.
     pub fn build_shader_program(&'a mut self) -> Result<ShaderProgram, TrdlError> {
-        let vertex_shader_id = try!(self.compile_shader(self.vertex_shader_code, gl::VERTEX_SHADER));
+        self.build_shader_program_with(&Gl)
+    }
+
+    /// Compile all the shaders and link into a shader program with the calls going through `api`.
+    pub fn build_shader_program_with(&'a mut self, api: &dyn GlApi) -> Result<ShaderProgram, TrdlError> {
+        span!("build_shader_program");
+        if let Some(code) = self.compute_shader_code {
+            let compute_shader_id = self.compile_shader(api, code, gl::COMPUTE_SHADER)?;
+            let program_id = self.link_shaders(api, &[compute_shader_id])?;
+            // deleting shader 0 is ignored
+            return Ok(ShaderProgram {
+                vertex_shader_id: 0,
+                tess_control_shader_id: None,
+                tess_evaluation_shader_id: None,
+                geometry_shader_id: None,
+                fragment_shader_id: 0,
+                compute_shader_id: Some(compute_shader_id),
+                program_id });
+        }
+        let vertex_shader_id = self.compile_shader(api, self.vertex_shader_code, gl::VERTEX_SHADER)?;
         let tess_control_shader_id = match self.tess_control_shader_code {
-            Some(code) => Some(try!(self.compile_shader(code, gl::TESS_CONTROL_SHADER))),
+            Some(code) => Some(self.compile_shader(api, code, gl::TESS_CONTROL_SHADER)?),
             None => None
         };
         let tess_evaluation_shader_id = match self.tess_evaluation_shader_code {
-            Some(code) => Some(try!(self.compile_shader(code, gl::TESS_EVALUATION_SHADER))),
+            Some(code) => Some(self.compile_shader(api, code, gl::TESS_EVALUATION_SHADER)?),
             None => None
         };
         let geometry_shader_id = match self.geometry_shader_code {
-            Some(code) => Some(try!(self.compile_shader(code, gl::GEOMETRY_SHADER))),
+            Some(code) => Some(self.compile_shader(api, code, gl::GEOMETRY_SHADER)?),
             None => None
         };
-        let fragment_shader_id = try!(self.compile_shader(self.fragment_shader_code, gl::FRAGMENT_SHADER));
+        let fragment_shader_id = self.compile_shader(api, self.fragment_shader_code, gl::FRAGMENT_SHADER)?;
 
-        let program_id = try!(self.link_shaders(vertex_shader_id, tess_control_shader_id,
-                              tess_evaluation_shader_id, geometry_shader_id, fragment_shader_id));
+        let mut shader_ids = vec![vertex_shader_id];
+        shader_ids.extend(tess_control_shader_id);
+        shader_ids.extend(tess_evaluation_shader_id);
+        shader_ids.extend(geometry_shader_id);
+        shader_ids.push(fragment_shader_id);
+        let program_id = self.link_shaders(api, &shader_ids)?;
         Ok(ShaderProgram  { 
-            vertex_shader_id: vertex_shader_id,
-            tess_control_shader_id: tess_control_shader_id,
-            tess_evaluation_shader_id: tess_evaluation_shader_id,
-            geometry_shader_id: geometry_shader_id,
-            fragment_shader_id: fragment_shader_id,
-            program_id: program_id })
+            vertex_shader_id,
+            tess_control_shader_id,
+            tess_evaluation_shader_id,
+            geometry_shader_id,
+            fragment_shader_id,
+            compute_shader_id: None,
+            program_id })
     }
 
     // compile a particular shader
-    fn compile_shader(&'a self, code: &str, shader_type: GLuint) -> Result<GLuint, TrdlError> {
-        unsafe {
-            let shader_id = gl::CreateShader(shader_type);
-            let c_str = try!(CString::new(code.as_bytes()).map_err(|_| TrdlError::NullString));
-            gl::ShaderSource(shader_id, 1, &c_str.as_ptr(), ptr::null());
-            gl::CompileShader(shader_id);
-
-            let mut status = gl::FALSE as GLint;
-            gl::GetShaderiv(shader_id, gl::COMPILE_STATUS, &mut status);
-            if status == gl::FALSE as GLint {
-                let mut length = 0 as GLint;
-                gl::GetShaderiv(shader_id, gl::INFO_LOG_LENGTH, &mut length);
-                println!("message length is {}", length);
-                let mut message = vec![0u8; length as usize];
-                gl::GetShaderInfoLog(shader_id, length, ptr::null_mut(), message.as_mut_ptr() as *mut GLchar);
-                let err = match String::from_utf8(message) {
-                    Ok(text) => {
-                        println!("err: '{}'", text);
-                        TrdlError::CompileError(text)
-                    },
-                    Err(_) => TrdlError::InvalidCompileError
-                };
-                Err(err)
-            } else {
-                Ok(shader_id)
-            }
+    fn compile_shader(&'a self, api: &dyn GlApi, code: &str, shader_type: GLuint) -> Result<GLuint, TrdlError> {
+        let shader_id = api.create_shader(shader_type);
+        let c_str = CString::new(code.as_bytes()).map_err(|_| TrdlError::NullString)?;
+        api.shader_source(shader_id, &c_str);
+        api.compile_shader(shader_id);
+
+        if api.shader_parameter(shader_id, gl::COMPILE_STATUS) == gl::FALSE as GLint {
+            let length = api.shader_parameter(shader_id, gl::INFO_LOG_LENGTH);
+            let message = api.shader_info_log(shader_id, length);
+            let err = match String::from_utf8(message) {
+                Ok(text) => {
+                    log_event!(error, "{} failed to compile: {}", shader_name(shader_type), text);
+                    TrdlError::CompileError(text)
+                },
+                Err(_) => TrdlError::InvalidCompileError
+            };
+            Err(err)
+        } else {
+            log_event!(debug, "compiled {}", shader_name(shader_type));
+            Ok(shader_id)
         }
     }
 
-    fn link_shaders(&'a self, vertex_shader_id: GLuint, 
-                        tess_control_shader_id: Option<GLuint>,
-                        tess_evaluation_shader_id: Option<GLuint>,
-                        geometry_shader_id: Option<GLuint>,
-                        fragment_shader_id: GLuint) -> Result<GLuint, TrdlError> {
-        unsafe {
-            let program_id = gl::CreateProgram();
-        
-            gl::AttachShader(program_id, vertex_shader_id);
-            if let Some(id) = tess_control_shader_id {
-                gl::AttachShader(program_id, id);
-            }
-            if let Some(id) = tess_evaluation_shader_id {
-                gl::AttachShader(program_id, id);
-            }
-            if let Some(id) = geometry_shader_id {
-                gl::AttachShader(program_id, id);
-            }
-            gl::AttachShader(program_id, fragment_shader_id);
-
-            gl::LinkProgram(program_id);
-
-            let mut status = gl::FALSE as GLint;
-            gl::GetProgramiv(program_id, gl::LINK_STATUS, &mut status);
-            if status == gl::FALSE as GLint {
-                let mut length = 0 as GLint;
-                gl::GetProgramiv(program_id, gl::INFO_LOG_LENGTH, &mut length);
-                let mut message = Vec::with_capacity(length as usize);
-                gl::GetProgramInfoLog(program_id, length, ptr::null_mut(), message.as_mut_ptr() as *mut GLchar);
-                let err = match String::from_utf8(message) {
-                    Ok(text) => TrdlError::CompileError(text),
-                    Err(_) => TrdlError::InvalidCompileError
-                };
-                return Err(err);
-            }
-
-            gl::DetachShader(program_id, vertex_shader_id);
-            if let Some(id) = tess_control_shader_id {
-                gl::DetachShader(program_id, id);
-            }
-            if let Some(id) = tess_evaluation_shader_id {
-                gl::DetachShader(program_id, id);
-            }
-            if let Some(id) = geometry_shader_id {
-                gl::DetachShader(program_id, id);
-            }
-            gl::DetachShader(program_id, fragment_shader_id);
-
-            Ok(program_id)
+    // link compiled shaders into a program
+    fn link_shaders(&'a self, api: &dyn GlApi, shader_ids: &[GLuint]) -> Result<GLuint, TrdlError> {
+        let program_id = api.create_program();
+        for &id in shader_ids {
+            api.attach_shader(program_id, id);
+        }
+
+        api.link_program(program_id);
+
+        if api.program_parameter(program_id, gl::LINK_STATUS) == gl::FALSE as GLint {
+            let length = api.program_parameter(program_id, gl::INFO_LOG_LENGTH);
+            let message = api.program_info_log(program_id, length);
+            let err = match String::from_utf8(message) {
+                Ok(text) => {
+                    log_event!(error, "shader program failed to link: {}", text);
+                    TrdlError::CompileError(text)
+                },
+                Err(_) => TrdlError::InvalidCompileError
+            };
+            return Err(err);
+        }
+
+        for &id in shader_ids {
+            api.detach_shader(program_id, id);
         }
+
+        Ok(program_id)
+    }
+}
+
+// The name of a kind of shader, for messages.
+fn shader_name(shader_type: GLuint) -> &'static str {
+    match shader_type {
+        gl::VERTEX_SHADER => "vertex shader",
+        gl::TESS_CONTROL_SHADER => "tessellation control shader",
+        gl::TESS_EVALUATION_SHADER => "tessellation evaluation shader",
+        gl::GEOMETRY_SHADER => "geometry shader",
+        gl::FRAGMENT_SHADER => "fragment shader",
+        gl::COMPUTE_SHADER => "compute shader",
+        _ => "shader"
     }
 }
 
+/// Read shader code from a file into a string. Paths under shaders/ are looked up in the
+/// directory named by the TRDL_SHADER_DIR environment variable instead when it is set, for
+/// programs that do not run from the crate's directory.
+pub fn read_file(file_name: &str) -> Result<String, TrdlError> {
+    let mut contents = String::new();
+    let mut f = match (env::var_os(SHADER_DIR_VAR), file_name.strip_prefix("shaders/")) {
+        (Some(dir), Some(name)) => File::open(Path::new(&dir).join(name))?,
+        _ => File::open(file_name)?
+    };
+    f.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Look up a uniform by name, -1 if the program does not use it.
+pub fn uniform_location(program_id: GLuint, name: &str) -> GLint {
+    let c_str = CString::new(name.as_bytes()).unwrap();
+    unsafe { gl::GetUniformLocation(program_id, c_str.as_ptr()) }
+}
+
 impl ShaderProgram {
     /// Get a numeric OpenGL handle to the shader program.
     pub fn get_program_id(&self) -> GLuint { self.program_id }
+
+    // Delete the shader program and all the shaders through `api`, for programs built against a
+    // recording in tests.
+    #[cfg(test)]
+    pub(crate) fn delete_with(self, api: &dyn GlApi) {
+        self.delete_objects(api);
+        ::std::mem::forget(self);
+    }
+
+    fn delete_objects(&self, api: &dyn GlApi) {
+        api.delete_program(self.program_id);
+        api.delete_shader(self.vertex_shader_id);
+        if let Some(id) = self.tess_control_shader_id {
+            api.delete_shader(id);
+        }
+        if let Some(id) = self.tess_evaluation_shader_id {
+            api.delete_shader(id);
+        }
+        if let Some(id) = self.geometry_shader_id {
+            api.delete_shader(id);
+        }
+        api.delete_shader(self.fragment_shader_id);
+        if let Some(id) = self.compute_shader_id {
+            api.delete_shader(id);
+        }
+    }
 }
 
 impl Drop for ShaderProgram {
     /// Delete the shader program and all the shaders.
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteProgram(self.program_id);
-            gl::DeleteShader(self.vertex_shader_id);
-            if let Some(id) = self.tess_control_shader_id {
-                gl::DeleteShader(id);
-            }
-            if let Some(id) = self.tess_evaluation_shader_id {
-                gl::DeleteShader(id);
-            }
-            if let Some(id) = self.geometry_shader_id {
-                gl::DeleteShader(id);
-            }
-            gl::DeleteShader(self.fragment_shader_id);
-        }
+        self.delete_objects(&Gl);
     }
 }
 
@@ -233,27 +291,88 @@ mod tests {
     use gl;
 
     use super::ShaderProgramBuilder;
-    use super::ShaderProgram;
-    use super::ShaderError;
+    use super::super::gl_api::{GlCall, RecordingGl};
+    use super::super::super::TrdlError;
 
     fn read_file(file_name: &str) -> io::Result<String> {
         let mut contents = String::new();
-        let mut f = try!(File::open(file_name));
-        try!(f.read_to_string(&mut contents));
+        let mut f = File::open(file_name)?;
+        f.read_to_string(&mut contents)?;
         Ok(contents)
     }
 
+    // Shader compilation needs a live GL context, these tests are ignored unless a display is
+    // available.
+    fn make_context() -> glutin::Context<glutin::PossiblyCurrent> {
+        let event_loop = glutin::event_loop::EventLoop::new();
+        let context = glutin::ContextBuilder::new()
+            .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (4, 0)))
+            .build_headless(&event_loop, glutin::dpi::PhysicalSize::new(1, 1))
+            .unwrap();
+        let context = unsafe { context.make_current() }.unwrap();
+        gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+        context
+    }
+
     #[test]
+    fn build_with_recording() {
+        let api = RecordingGl::new();
+        let mut builder = ShaderProgramBuilder::new();
+        let program = builder.build_shader_program_with(&api).unwrap();
+        assert_eq!(program.get_program_id(), 3);
+        program.delete_with(&api);
+        assert_eq!(api.calls(), vec![
+            GlCall::CreateShader(gl::VERTEX_SHADER, 1), GlCall::ShaderSource(1), GlCall::CompileShader(1),
+            GlCall::CreateShader(gl::FRAGMENT_SHADER, 2), GlCall::ShaderSource(2), GlCall::CompileShader(2),
+            GlCall::CreateProgram(3), GlCall::AttachShader(3, 1), GlCall::AttachShader(3, 2), GlCall::LinkProgram(3),
+            GlCall::DetachShader(3, 1), GlCall::DetachShader(3, 2),
+            GlCall::DeleteProgram(3), GlCall::DeleteShader(1), GlCall::DeleteShader(2)]);
+    }
+
+    #[test]
+    fn build_errors() {
+        let mut builder = ShaderProgramBuilder::new();
+        let api = RecordingGl::new().fail_compile("syntax error");
+        match builder.build_shader_program_with(&api) {
+            Err(TrdlError::CompileError(log)) => assert_eq!(log, "syntax error"),
+            _ => panic!("expected a compile error")
+        }
+        assert_eq!(api.calls().len(), 3);
+        let api = RecordingGl::new().fail_link("missing main");
+        let mut builder = ShaderProgramBuilder::new();
+        match builder.build_shader_program_with(&api) {
+            Err(TrdlError::CompileError(log)) => assert_eq!(log, "missing main"),
+            _ => panic!("expected a link error")
+        }
+        assert!(!api.calls().iter().any(|call| matches!(call, GlCall::DetachShader(..))));
+    }
+
+    #[test]
+    #[ignore]
     fn compile_defaults() {
+        let _context = make_context();
+
+        let mut builder = ShaderProgramBuilder::new();
+        assert!(builder.build_shader_program().is_ok());
+    }
+
+    #[test]
+    #[ignore]
+    fn compile_trdl_shaders() {
+        let _context = make_context();
 
-        let window = glutin::Window::new().unwrap();
-        unsafe { window.make_current() };
-        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+        let vertex_shader_code = read_file("shaders/vertex_shader.glsl").unwrap();
+        let tess_control_shader_code = read_file("shaders/tess_control_shader.glsl").unwrap();
+        let tess_evaluation_shader_code = read_file("shaders/tess_evaluation_shader.glsl").unwrap();
+        let geometry_shader_code = read_file("shaders/geometry_shader.glsl").unwrap();
+        let fragment_shader_code = read_file("shaders/fragment_shader.glsl").unwrap();
 
         let mut builder = ShaderProgramBuilder::new();
-        match builder.build_shader_program() {
-            Ok(_) => { },
-            Err(_) => assert!(false)
-        };
+        builder.set_vertex_shader(&vertex_shader_code);
+        builder.set_tess_control_shader(&tess_control_shader_code);
+        builder.set_tess_evaluation_shader(&tess_evaluation_shader_code);
+        builder.set_geometry_shader(&geometry_shader_code);
+        builder.set_fragment_shader(&fragment_shader_code);
+        assert!(builder.build_shader_program().is_ok());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,420 @@
+//! A rasterizer on the CPU for the geometry a drawing sends to the GPU: the curved triangles of
+//! the patches are flattened and scanline filled, and strokes and joins are filled as the quads
+//! and triangles the geometry shader would make. Pixels are sampled at their centers with the
+//! same depth test and blending, so the image is what the GPU draws without multisampling, less
+//! fill patterns and crisp mode. Useful where there is no OpenGL and to check the GPU against.
+
+use std::f32::consts::PI;
+use gl::types::*;
+use super::drawing::{GeometrySnapshot, FILL_FLAG, JOIN_FLAG, INSIDE_LEFT_FLAG, ROUND_SHARP_FLAG,
+                     WORLD_STROKE_FLAG};
+use super::super::color::Color;
+use super::super::image::ImageData;
+use super::super::TrdlError;
+
+// stroke alignment in the fill flags, as in the geometry shader
+const ALIGN_INSIDE: GLint = 1;
+const ALIGN_OUTSIDE: GLint = 2;
+// join kinds in bits 5 and 6 of a join patch's fill flags
+const JOIN_MITER: GLint = 0;
+const JOIN_BEVEL: GLint = 1;
+const JOIN_ROUND: GLint = 2;
+// the most triangles in a round join
+const MAX_ROUND_STEPS: usize = 8;
+// curves are cut into pieces about this many pixels long, at most MAX_PIECES of them
+const PIECE_LENGTH: f32 = 2f32;
+const MAX_PIECES: usize = 64;
+
+/// How the drawing maps onto the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RasterView {
+    /// The drawing's projection to OpenGL normalized device coordinates, column major.
+    pub projection: [f32; 16],
+    /// Window pixels per drawing unit, for strokes with thicknesses in drawing units.
+    pub world_scale: f32,
+    /// How far in front of its fill a path's stroke is drawn.
+    pub stroke_depth_offset: f32
+}
+
+/// Draws a `GeometrySnapshot` into RGBA pixels, top row first like `ImageData`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftwareRasterizer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    depth: Vec<f32>
+}
+
+// A point in pixels and an RGBA color from 0 to 1.
+type Point = (f32, f32);
+type Rgba = [f32; 4];
+
+impl SoftwareRasterizer {
+    /// Constructor, an image of a size cleared to a color.
+    pub fn new(width: u32, height: u32, background: Color) -> Result<Self, TrdlError> {
+        if width == 0 || height == 0 {
+            return Err(TrdlError::InvalidImageSize);
+        }
+        let (width, height) = (width as usize, height as usize);
+        let clear = to_bytes(background.to_array());
+        let pixels = clear.iter().cycle().take(4 * width * height).cloned().collect();
+        Ok(SoftwareRasterizer { width, height, pixels, depth: vec![1f32; width * height] })
+    }
+
+    /// The pixels so far, RGBA, top row first.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// The pixels as an image.
+    pub fn into_image(self) -> Result<ImageData, TrdlError> {
+        ImageData::new(self.width as u32, self.height as u32, self.pixels)
+    }
+
+    /// Draw every patch of the geometry, in buffer order.
+    pub fn draw(&mut self, geometry: &GeometrySnapshot, view: &RasterView) {
+        for patch in 0..geometry.num_patches() {
+            self.draw_patch(geometry, view, patch);
+        }
+    }
+
+    // Where a point of the drawing lands in the image, in pixels from the top left corner.
+    fn to_pixel(&self, view: &RasterView, p: Point) -> Point {
+        let m = &view.projection;
+        let x = m[0] * p.0 + m[4] * p.1 + m[12];
+        let y = m[1] * p.0 + m[5] * p.1 + m[13];
+        ((x + 1f32) / 2f32 * self.width as f32, (1f32 - y) / 2f32 * self.height as f32)
+    }
+
+    fn draw_patch(&mut self, geometry: &GeometrySnapshot, view: &RasterView, patch: usize) {
+        let first = 3 * patch;
+        let point = |array: &[GLfloat], i: usize, stride: usize| (array[stride * i], array[stride * i + 1]);
+        let corners = [0, 1, 2].map(|k| self.to_pixel(view, point(&geometry.vertices, first + k, 3)));
+        let controls = [0, 1, 2].map(|k| (self.to_pixel(view, point(&geometry.control_point_1s, first + k, 2)),
+                                          self.to_pixel(view, point(&geometry.control_point_2s, first + k, 2))));
+        let color = |array: &[GLfloat], k: usize| {
+            let i = 4 * (first + k);
+            [array[i], array[i + 1], array[i + 2], array[i + 3]]
+        };
+        let flags = geometry.do_fill[first];
+        let depth = geometry.vertices[3 * first + 2];
+        let stroke_depth = depth - view.stroke_depth_offset;
+        let scale = if flags & WORLD_STROKE_FLAG != 0 { view.world_scale } else { 1f32 };
+
+        if flags & JOIN_FLAG != 0 {
+            let half = geometry.stroke_edges[first] * scale / 2f32;
+            let miter_limit = geometry.stroke_edges[first + 1];
+            let mirrored = determinant(&view.projection) > 0f32;
+            self.draw_join(corners, half, miter_limit, flags, mirrored, stroke_depth, color(&geometry.stroke_colors, 0));
+            return;
+        }
+
+        if flags & FILL_FLAG != 0 {
+            // the outline of the curved triangle, each edge from its corner to the next
+            let mut outline = Vec::new();
+            for k in 0..3 {
+                let edge = [corners[k], controls[k].0, controls[k].1, corners[(k + 1) % 3]];
+                outline.extend(flatten(&edge).into_iter().skip(1));
+            }
+            let colors = [color(&geometry.fill_colors, 0), color(&geometry.fill_colors, 1),
+                          color(&geometry.fill_colors, 2)];
+            self.fill_polygon(&outline, depth, |p| blend_corners(&corners, &colors, p));
+        }
+
+        // the thickness at a corner strokes the edge across from it
+        let align = (flags >> 1) & 3;
+        for k in 0..3 {
+            let thickness = geometry.stroke_edges[first + k];
+            if thickness <= 0f32 {
+                continue;
+            }
+            let (from, to) = ((k + 1) % 3, (k + 2) % 3);
+            let edge = [corners[from], controls[from].0, controls[from].1, corners[to]];
+            let colors = [color(&geometry.stroke_colors, from), color(&geometry.stroke_colors, to)];
+            self.draw_edge(&edge, corners[k], align, thickness * scale / 2f32, stroke_depth, colors);
+        }
+    }
+
+    // Stroke a cubic edge as a run of quads, centered on it or moved to one side for aligned
+    // strokes, colored from one end to the other.
+    fn draw_edge(&mut self, edge: &[Point; 4], inner: Point, align: GLint, half: f32, depth: f32, colors: [Rgba; 2]) {
+        let pieces = pieces(edge);
+        let sides: Vec<(Point, Point)> = (0..=pieces).map(|i| {
+            let t = i as f32 / pieces as f32;
+            let p = cubic_point(edge, t);
+            let tangent = normalize(cubic_tangent(edge, t));
+            let perp = (-tangent.1 * half, tangent.0 * half);
+            let shift = align_shift(align, p, perp, inner);
+            ((p.0 + (shift - 1f32) * perp.0, p.1 + (shift - 1f32) * perp.1),
+             (p.0 + (shift + 1f32) * perp.0, p.1 + (shift + 1f32) * perp.1))
+        }).collect();
+        for i in 0..pieces {
+            let t = (i as f32 + 0.5f32) / pieces as f32;
+            let color = mix(&colors[0], &colors[1], t);
+            let quad = [sides[i].0, sides[i].1, sides[i + 1].1, sides[i + 1].0];
+            self.fill_polygon(&quad, depth, |_| color);
+        }
+    }
+
+    // The join at corner b between the stroke from a and the one on to c, as make_join in the
+    // geometry shader.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_join(&mut self, [a, b, c]: [Point; 3], half: f32, miter_limit: f32, flags: GLint, mirrored: bool,
+                 depth: f32, color: Rgba) {
+        let u_in = normalize((b.0 - a.0, b.1 - a.1));
+        let u_out = normalize((c.0 - b.0, c.1 - b.1));
+        let turn = u_in.0 * u_out.1 - u_in.1 * u_out.0;
+        let outer = if turn > 0f32 { -1f32 } else { 1f32 };
+        let mut reach = 1f32;
+        let align = (flags >> 1) & 3;
+        if align == ALIGN_INSIDE || align == ALIGN_OUTSIDE {
+            let mut inside = if flags & INSIDE_LEFT_FLAG != 0 { 1f32 } else { -1f32 };
+            if mirrored {
+                inside = -inside;
+            }
+            let stroke_side = if align == ALIGN_INSIDE { inside } else { -inside };
+            if stroke_side != outer {
+                return;
+            }
+            reach = 2f32;
+        }
+        let n_in = (-outer * u_in.1, outer * u_in.0);
+        let n_out = (-outer * u_out.1, outer * u_out.0);
+        let at = |n: Point, length: f32| (b.0 + length * n.0 * half, b.1 + length * n.1 * half);
+        let (p_in, p_out) = (at(n_in, reach), at(n_out, reach));
+        let mut join = (flags >> 5) & 3;
+        let dot = n_in.0 * n_out.0 + n_in.1 * n_out.1;
+        let miter_scale = 1f32 / (1f32 + dot).max(0.000001f32);
+        let miter = ((n_in.0 + n_out.0) * miter_scale, (n_in.1 + n_out.1) * miter_scale);
+        if join == JOIN_MITER && miter.0.hypot(miter.1) > miter_limit {
+            join = if flags & ROUND_SHARP_FLAG != 0 { JOIN_ROUND } else { JOIN_BEVEL };
+        }
+        if join == JOIN_ROUND {
+            let angle = dot.clamp(-1f32, 1f32).acos();
+            let direction = if n_in.0 * u_in.1 - n_in.1 * u_in.0 >= 0f32 { 1f32 } else { -1f32 };
+            let steps = ((angle / (PI / 8f32)).ceil() as usize).clamp(1, MAX_ROUND_STEPS);
+            let mut previous = p_in;
+            for i in 1..=steps {
+                let t = direction * angle * i as f32 / steps as f32;
+                let n = (n_in.0 * t.cos() - n_in.1 * t.sin(), n_in.0 * t.sin() + n_in.1 * t.cos());
+                let next = if i == steps { p_out } else { at(n, reach) };
+                self.fill_polygon(&[b, previous, next], depth, |_| color);
+                previous = next;
+            }
+            return;
+        }
+        self.fill_polygon(&[b, p_in, p_out], depth, |_| color);
+        if join == JOIN_MITER {
+            self.fill_polygon(&[p_in, at(miter, reach), p_out], depth, |_| color);
+        }
+    }
+
+    // Fill a polygon by the even-odd rule, shading the pixels whose centers are inside and pass
+    // the depth test, blended over what is there.
+    fn fill_polygon<F: Fn(Point) -> Rgba>(&mut self, points: &[Point], depth: f32, shade: F) {
+        if points.len() < 3 {
+            return;
+        }
+        let (top, bottom) = points.iter().fold((f32::MAX, f32::MIN), |(t, b), p| (t.min(p.1), b.max(p.1)));
+        let first_row = (top - 0.5f32).ceil().max(0f32) as usize;
+        let end_row = ((bottom - 0.5f32).ceil().max(0f32) as usize).min(self.height);
+        let mut crossings = Vec::new();
+        for row in first_row..end_row {
+            let y = row as f32 + 0.5f32;
+            crossings.clear();
+            for (i, p) in points.iter().enumerate() {
+                let q = points[(i + 1) % points.len()];
+                // half open so a vertex on the row is counted once
+                if (p.1 <= y) != (q.1 <= y) {
+                    let x = p.0 + (y - p.1) / (q.1 - p.1) * (q.0 - p.0);
+                    // points that projected to infinity or NaN leave their edges out
+                    if x.is_finite() {
+                        crossings.push(x);
+                    }
+                }
+            }
+            crossings.sort_by(f32::total_cmp);
+            for span in crossings.chunks(2).filter(|span| span.len() == 2) {
+                let start = (span[0] - 0.5f32).ceil().max(0f32) as usize;
+                let end = ((span[1] - 0.5f32).ceil().max(0f32) as usize).min(self.width);
+                for column in start..end {
+                    let i = row * self.width + column;
+                    if depth >= self.depth[i] {
+                        continue;
+                    }
+                    self.depth[i] = depth;
+                    let color = shade((column as f32 + 0.5f32, y));
+                    self.blend(i, color);
+                }
+            }
+        }
+    }
+
+    // Blend a color over a pixel, source over.
+    fn blend(&mut self, i: usize, color: Rgba) {
+        let pixel = &mut self.pixels[4 * i..4 * i + 4];
+        let alpha = color[3].clamp(0f32, 1f32);
+        for c in 0..3 {
+            let under = pixel[c] as f32 / 255f32;
+            pixel[c] = to_byte(color[c] * alpha + under * (1f32 - alpha));
+        }
+        let under = pixel[3] as f32 / 255f32;
+        pixel[3] = to_byte(alpha + under * (1f32 - alpha));
+    }
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0f32, 1f32) * 255f32).round() as u8
+}
+
+fn to_bytes(color: Rgba) -> [u8; 4] {
+    color.map(to_byte)
+}
+
+fn mix(a: &Rgba, b: &Rgba, t: f32) -> Rgba {
+    [0, 1, 2, 3].map(|c| a[c] + (b[c] - a[c]) * t)
+}
+
+// The color at a point from the colors at the corners, by its barycentric coordinates in the
+// straight triangle between them.
+fn blend_corners(corners: &[Point; 3], colors: &[Rgba; 3], p: Point) -> Rgba {
+    let [a, b, c] = *corners;
+    let area = (b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1);
+    if area.abs() < f32::EPSILON {
+        return colors[0];
+    }
+    let s = (((b.0 - p.0) * (c.1 - p.1) - (c.0 - p.0) * (b.1 - p.1)) / area).max(0f32);
+    let t = (((c.0 - p.0) * (a.1 - p.1) - (a.0 - p.0) * (c.1 - p.1)) / area).max(0f32);
+    let u = (1f32 - s - t).max(0f32);
+    let sum = s + t + u;
+    [0, 1, 2, 3].map(|i| (s * colors[0][i] + t * colors[1][i] + u * colors[2][i]) / sum)
+}
+
+// How many straight pieces to cut a cubic into.
+fn pieces(edge: &[Point; 4]) -> usize {
+    let length: f32 = edge.windows(2).map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1)).sum();
+    ((length / PIECE_LENGTH).ceil() as usize).clamp(1, MAX_PIECES)
+}
+
+// The points of a cubic cut into pieces, both ends included.
+fn flatten(edge: &[Point; 4]) -> Vec<Point> {
+    let pieces = pieces(edge);
+    (0..=pieces).map(|i| cubic_point(edge, i as f32 / pieces as f32)).collect()
+}
+
+fn cubic_point(edge: &[Point; 4], t: f32) -> Point {
+    let s = 1f32 - t;
+    let (w0, w1, w2, w3) = (s * s * s, 3f32 * s * s * t, 3f32 * s * t * t, t * t * t);
+    (w0 * edge[0].0 + w1 * edge[1].0 + w2 * edge[2].0 + w3 * edge[3].0,
+     w0 * edge[0].1 + w1 * edge[1].1 + w2 * edge[2].1 + w3 * edge[3].1)
+}
+
+// The derivative of a cubic, falling back on the chord where it vanishes.
+fn cubic_tangent(edge: &[Point; 4], t: f32) -> Point {
+    let s = 1f32 - t;
+    let d = |i: usize| (edge[i + 1].0 - edge[i].0, edge[i + 1].1 - edge[i].1);
+    let (d0, d1, d2) = (d(0), d(1), d(2));
+    let tangent = (3f32 * (s * s * d0.0 + 2f32 * s * t * d1.0 + t * t * d2.0),
+                   3f32 * (s * s * d0.1 + 2f32 * s * t * d1.1 + t * t * d2.1));
+    if tangent.0.hypot(tangent.1) > f32::EPSILON {
+        tangent
+    } else {
+        (edge[3].0 - edge[0].0, edge[3].1 - edge[0].1)
+    }
+}
+
+fn normalize(v: Point) -> Point {
+    let length = v.0.hypot(v.1);
+    if length > 0f32 { (v.0 / length, v.1 / length) } else { (0f32, 0f32) }
+}
+
+// How far to move a stroke off its edge, in half thicknesses along perp, as in the geometry
+// shader.
+fn align_shift(align: GLint, p: Point, perp: Point, inner: Point) -> f32 {
+    if align != ALIGN_INSIDE && align != ALIGN_OUTSIDE {
+        return 0f32;
+    }
+    let side = if perp.0 * (inner.0 - p.0) + perp.1 * (inner.1 - p.1) < 0f32 { -1f32 } else { 1f32 };
+    if align == ALIGN_INSIDE { side } else { -side }
+}
+
+// The determinant of the projection's 2D part. The image's rows go down, so a projection that
+// keeps the drawing's orientation in normalized device coordinates mirrors it in the image.
+fn determinant(projection: &[f32; 16]) -> f32 {
+    projection[0] * projection[5] - projection[4] * projection[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RasterView, SoftwareRasterizer};
+    use super::super::drawing::{GeometrySnapshot, Path, PathGeometry};
+    use super::super::super::color::Color;
+
+    // drawing units are pixels, y up from the bottom of a 40 by 40 image
+    fn view() -> RasterView {
+        let s = 2f32 / 40f32;
+        RasterView {
+            projection: [s, 0f32, 0f32, 0f32, 0f32, s, 0f32, 0f32, 0f32, 0f32, 1f32, 0f32, -1f32, -1f32, 0f32, 1f32],
+            world_scale: 1f32,
+            stroke_depth_offset: 0.01f32
+        }
+    }
+
+    fn pixel(rasterizer: &SoftwareRasterizer, x: usize, y: usize) -> [u8; 4] {
+        let i = 4 * ((39 - y) * 40 + x);
+        let pixels = rasterizer.pixels();
+        [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+    }
+
+    fn rasterize(paths: &[Path]) -> SoftwareRasterizer {
+        let mut snapshot = GeometrySnapshot::default();
+        for (i, path) in paths.iter().enumerate() {
+            snapshot.push(&PathGeometry::new(path).unwrap(), 0.5f32 - i as f32 * 0.1f32, None);
+        }
+        let mut rasterizer = SoftwareRasterizer::new(40, 40, Color::rgb(1f32, 1f32, 1f32)).unwrap();
+        rasterizer.draw(&snapshot, &view());
+        rasterizer
+    }
+
+    #[test]
+    fn test_fill() {
+        let square = Path::rectangle((20f32, 20f32), 20f32, 20f32, 0f32).set_fill_color(1f32, 0f32, 0f32);
+        let rasterizer = rasterize(&[square]);
+        assert_eq!(pixel(&rasterizer, 20, 20), [255, 0, 0, 255]);
+        assert_eq!(pixel(&rasterizer, 10, 29), [255, 0, 0, 255]);
+        assert_eq!(pixel(&rasterizer, 9, 20), [255, 255, 255, 255]);
+        assert_eq!(pixel(&rasterizer, 20, 30), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_non_finite_points() {
+        let mut rasterizer = SoftwareRasterizer::new(40, 40, Color::rgb(1f32, 1f32, 1f32)).unwrap();
+        let red = [1f32, 0f32, 0f32, 1f32];
+        let square = [(5f32, 5f32), (f32::NAN, 10f32), (15f32, 5f32), (15f32, 15f32), (5f32, 15f32)];
+        rasterizer.fill_polygon(&square, 0.5f32, |_| red);
+        rasterizer.fill_polygon(&[(20f32, 5f32), (f32::INFINITY, 10f32), (20f32, 15f32)], 0.5f32, |_| red);
+        // the edges to the NaN point are skipped, the rest of its rows still fill
+        assert_eq!(pixel(&rasterizer, 10, 39 - 7), [255, 0, 0, 255]);
+        assert_eq!(pixel(&rasterizer, 25, 39 - 7), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_stroke_and_depth() {
+        let line = Path::new((5f32, 20f32)).line_to((35f32, 20f32)).set_stroke(0f32, 0f32, 1f32, 4);
+        let square = Path::rectangle((20f32, 20f32), 10f32, 10f32, 0f32).set_fill_color(1f32, 0f32, 0f32);
+        // the line is added first but nearer, so the square only shows around it
+        let rasterizer = rasterize(&[square, line]);
+        assert_eq!(pixel(&rasterizer, 8, 19), [0, 0, 255, 255]);
+        assert_eq!(pixel(&rasterizer, 20, 21), [0, 0, 255, 255]);
+        assert_eq!(pixel(&rasterizer, 8, 22), [255, 255, 255, 255]);
+        assert_eq!(pixel(&rasterizer, 20, 23), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_blending() {
+        let square = Path::rectangle((20f32, 20f32), 20f32, 20f32, 0f32).set_fill_color(0f32, 0f32, 0f32)
+            .set_opacity(0.5f32);
+        let rasterizer = rasterize(&[square]);
+        assert_eq!(pixel(&rasterizer, 20, 20), [128, 128, 128, 255]);
+    }
+}
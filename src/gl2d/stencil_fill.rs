@@ -0,0 +1,305 @@
+//! Fills drawn without triangulating, by stencil-then-cover: a fan of triangles from the first
+//! point of the outline counts how often the outline winds around each pixel in the stencil
+//! buffer, then a quad over the path's bounds colors the pixels the fill rule counts as inside.
+//! Curves are split into quadratic pieces whose triangles are trimmed to the curve in the
+//! fragment shader (Loop-Blinn), so they stay smooth at any zoom. Paths that cross themselves are
+//! fine, unlike in a `Drawing`.
+
+use std::mem;
+use std::ffi::CString;
+use std::ptr;
+use std::os::raw::c_void;
+use gl::types::*;
+use super::shader;
+use super::drawing::{Drawing, Path, Window};
+use super::gl_state::SavedGlState;
+use super::super::bezier::Segment;
+use super::super::TrdlError;
+
+// floats per vertex: x, y, then the curve coordinates u, v
+const VERTEX_FLOATS: usize = 4;
+// the curve coordinates of triangles that are not trimmed
+const SOLID: (f32, f32) = (0f32, 1f32);
+// how far the quadratic pieces may stray from a cubic curve, in pixels
+const TOLERANCE_PIXELS: f32 = 0.25f32;
+// the most quadratic pieces a cubic curve is split into
+const MAX_PIECES: usize = 64;
+
+/// Which points a path that crosses itself or has loops inside it fills, see `StencilFill`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillRule {
+    /// Points the outline winds around any number of times but zero.
+    NonZero,
+    /// Points the outline winds around an odd number of times.
+    EvenOdd
+}
+
+// Where a path's vertices are in the buffer: stencil triangles from the start to the cover quad,
+// the cover quad to the end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PathRange {
+    start: usize,
+    cover: usize,
+    end: usize,
+    rule: FillRule,
+    color: [f32; 4]
+}
+
+// Push a vertex with its curve coordinates.
+fn push_vertex(vertices: &mut Vec<GLfloat>, p: (f32, f32), curve: (f32, f32)) {
+    vertices.extend_from_slice(&[p.0, p.1, curve.0, curve.1]);
+}
+
+// Quadratic curves (start, control point, end) within tolerance of a cubic one.
+fn quadratic_pieces(segment: &Segment, tolerance: f32) -> Vec<[(f32, f32); 3]> {
+    let [p0, p1, p2, p3] = segment.to_cubic();
+    // a piece 1/n of the curve long strays about sqrt(3) / 36 |p3 - 3 p2 + 3 p1 - p0| / n^3
+    let d = (p3.0 - 3f32 * p2.0 + 3f32 * p1.0 - p0.0).hypot(p3.1 - 3f32 * p2.1 + 3f32 * p1.1 - p0.1);
+    let n = (3f32.sqrt() / 36f32 * d / tolerance).cbrt().ceil();
+    let n = if n.is_finite() { (n as usize).clamp(1, MAX_PIECES) } else { 1 };
+    (0..n).map(|i| {
+        let [a, c1, c2, b] = segment.between(i as f32 / n as f32, (i + 1) as f32 / n as f32).to_cubic();
+        let q = ((3f32 * (c1.0 + c2.0) - a.0 - b.0) / 4f32, (3f32 * (c1.1 + c2.1) - a.1 - b.1) / 4f32);
+        [a, q, b]
+    }).collect()
+}
+
+// Push the stencil triangles of the outline made of segments, which is closed from its last point
+// back to its first: a fan over the points the segments start and end at, then a triangle for
+// each quadratic piece of the curves. Returns the outline's bounds.
+pub(crate) fn stencil_triangles(segments: &[Segment], tolerance: f32,
+                                vertices: &mut Vec<GLfloat>) -> ((f32, f32), (f32, f32)) {
+    let mut points = Vec::new();
+    let mut curves = Vec::new();
+    for segment in segments {
+        match *segment {
+            Segment::Line(a, _) => points.push(a),
+            Segment::Cubic(..) => for piece in quadratic_pieces(segment, tolerance) {
+                points.push(piece[0]);
+                curves.push(piece);
+            }
+        }
+    }
+    if let Some(last) = segments.last() {
+        let end = last.point_at(1f32);
+        if points.first() != Some(&end) {
+            points.push(end);
+        }
+    }
+    for i in 1..points.len().saturating_sub(1) {
+        for &p in &[points[0], points[i], points[i + 1]] {
+            push_vertex(vertices, p, SOLID);
+        }
+    }
+    for piece in &curves {
+        push_vertex(vertices, piece[0], (0f32, 0f32));
+        push_vertex(vertices, piece[1], (0.5f32, 0f32));
+        push_vertex(vertices, piece[2], (1f32, 1f32));
+    }
+    // quadratic curves lie within the triangles of their points
+    let all = points.iter().chain(curves.iter().map(|piece| &piece[1]));
+    all.fold(((f32::MAX, f32::MAX), (f32::MIN, f32::MIN)), |(min, max), p| {
+        ((min.0.min(p.0), min.1.min(p.1)), (max.0.max(p.0), max.1.max(p.1)))
+    })
+}
+
+/// Fills paths by stencil-then-cover instead of triangulating them, so paths that cross
+/// themselves, which a `Drawing` refuses with `NonSimplePolygon`, can still be shown. Add the
+/// paths with `add_path` and draw them over a drawing with `draw`. Only the fills are drawn, flat
+/// in the fill color and without anti-aliasing; the window needs a stencil buffer.
+pub struct StencilFill {
+    shader_program: shader::ShaderProgram,
+    vao_handle: GLuint,
+    vbo: GLuint,
+    paths: Vec<(Path, FillRule)>,
+    ranges: Vec<PathRange>,
+    // the tolerance the buffer was filled for, None when it needs filling again
+    built_tolerance: Option<f32>,
+
+    projection_uniform: GLint,
+    cover_uniform: GLint,
+    color_uniform: GLint,
+    srgb_uniform: GLint
+}
+
+impl StencilFill {
+    /// Constructor, compiles the stencil shaders. The drawing's context must be current.
+    pub fn new() -> Result<Self, TrdlError> {
+        let vertex_shader_code = shader::read_file("shaders/stencil_vertex_shader.glsl")?;
+        let fragment_shader_code = shader::read_file("shaders/stencil_fragment_shader.glsl")?;
+        let program;
+        {
+            let mut builder = shader::ShaderProgramBuilder::new();
+            builder.set_vertex_shader(&vertex_shader_code);
+            builder.set_fragment_shader(&fragment_shader_code);
+            program = builder.build_shader_program()?;
+        }
+
+        let program_id = program.get_program_id();
+        unsafe {
+            let attribute = |name: &str| {
+                let c_str = CString::new(name).unwrap();
+                gl::GetAttribLocation(program_id, c_str.as_ptr()) as GLuint
+            };
+            let in_position = attribute("in_position");
+            let in_curve = attribute("in_curve");
+
+            let mut vao_handle = 0 as GLuint;
+            gl::GenVertexArrays(1, &mut vao_handle);
+            gl::BindVertexArray(vao_handle);
+            let mut vbo = 0 as GLuint;
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            let stride = (VERTEX_FLOATS * mem::size_of::<GLfloat>()) as GLsizei;
+            gl::EnableVertexAttribArray(in_position);
+            gl::VertexAttribPointer(in_position, 2, gl::FLOAT, gl::FALSE as GLboolean, stride, ptr::null());
+            gl::EnableVertexAttribArray(in_curve);
+            gl::VertexAttribPointer(in_curve, 2, gl::FLOAT, gl::FALSE as GLboolean, stride,
+                                    (2 * mem::size_of::<GLfloat>()) as *const c_void);
+
+            Ok(StencilFill {
+                projection_uniform: shader::uniform_location(program_id, "projection"),
+                cover_uniform: shader::uniform_location(program_id, "cover"),
+                color_uniform: shader::uniform_location(program_id, "color"),
+                srgb_uniform: shader::uniform_location(program_id, "srgb"),
+                shader_program: program,
+                vao_handle,
+                vbo,
+                paths: Vec::new(),
+                ranges: Vec::new(),
+                built_tolerance: None
+            })
+        }
+    }
+
+    /// Add a path to fill by the rule, in its fill color and opacity. Open paths are filled as
+    /// if closed. Fails with `NoVisibleGeometry` for paths without a fill color.
+    pub fn add_path(&mut self, path: &Path, rule: FillRule) -> Result<(), TrdlError> {
+        if path.style().fill_color.is_none() {
+            return Err(TrdlError::NoVisibleGeometry);
+        }
+        self.paths.push((path.clone(), rule));
+        self.built_tolerance = None;
+        Ok(())
+    }
+
+    /// Remove all the paths.
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.built_tolerance = None;
+    }
+
+    // Fill the buffer with every path's stencil triangles and cover quad.
+    fn build(&mut self, tolerance: f32) {
+        let mut vertices = Vec::new();
+        self.ranges.clear();
+        for &(ref path, rule) in &self.paths {
+            let style = path.style();
+            let fill = style.fill_color.unwrap_or([0f32; 3]);
+            let start = vertices.len() / VERTEX_FLOATS;
+            let (min, max) = stencil_triangles(&path.segment_list(), tolerance, &mut vertices);
+            let cover = vertices.len() / VERTEX_FLOATS;
+            for &p in &[min, (max.0, min.1), max, min, max, (min.0, max.1)] {
+                push_vertex(&mut vertices, p, SOLID);
+            }
+            self.ranges.push(PathRange { start, cover, end: vertices.len() / VERTEX_FLOATS, rule,
+                                         color: [fill[0], fill[1], fill[2], style.opacity * style.fill_opacity] });
+        }
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(gl::ARRAY_BUFFER, mem::size_of_val(&vertices[..]) as GLsizeiptr,
+                           vertices.as_ptr() as *const c_void, gl::STATIC_DRAW);
+        }
+        self.built_tolerance = Some(tolerance);
+    }
+
+    /// Draw the paths with the drawing's camera in the order they were added, call after
+    /// `Drawing::draw`. They are blended over everything else and leave the OpenGL state as they
+    /// found it, apart from the stencil buffer, which is left cleared.
+    pub fn draw<W: Window>(&mut self, drawing: &Drawing<'_, W>) {
+        if self.paths.is_empty() {
+            return;
+        }
+        let saved = SavedGlState::save();
+        // curves are split again when zooming makes the pieces too coarse or needlessly fine
+        let tolerance = drawing.pixel_size() * TOLERANCE_PIXELS;
+        if self.built_tolerance.is_none_or(|built| built > 2f32 * tolerance || built < tolerance / 2f32) {
+            self.build(tolerance);
+        }
+        unsafe {
+            gl::UseProgram(self.shader_program.get_program_id());
+            gl::UniformMatrix4fv(self.projection_uniform, 1, gl::FALSE as GLboolean,
+                                 drawing.projection().as_ptr());
+            gl::Uniform1i(self.srgb_uniform, drawing.srgb() as GLint);
+            gl::BindVertexArray(self.vao_handle);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::Enable(gl::STENCIL_TEST);
+            gl::StencilMask(0xff);
+            gl::ClearStencil(0);
+            gl::Clear(gl::STENCIL_BUFFER_BIT);
+            for range in &self.ranges {
+                // count the windings without touching the colors
+                gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+                gl::Uniform1i(self.cover_uniform, 0);
+                gl::StencilFunc(gl::ALWAYS, 0, 0xff);
+                match range.rule {
+                    FillRule::NonZero => {
+                        gl::StencilOpSeparate(gl::FRONT, gl::KEEP, gl::KEEP, gl::INCR_WRAP);
+                        gl::StencilOpSeparate(gl::BACK, gl::KEEP, gl::KEEP, gl::DECR_WRAP);
+                    }
+                    FillRule::EvenOdd => gl::StencilOp(gl::KEEP, gl::KEEP, gl::INVERT)
+                }
+                gl::DrawArrays(gl::TRIANGLES, range.start as GLsizei, (range.cover - range.start) as GLsizei);
+
+                // color the inside, clearing the stencil for the next path as it goes
+                gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                gl::Uniform1i(self.cover_uniform, 1);
+                gl::Uniform4fv(self.color_uniform, 1, range.color.as_ptr());
+                gl::StencilFunc(gl::NOTEQUAL, 0, 0xff);
+                gl::StencilOp(gl::ZERO, gl::ZERO, gl::ZERO);
+                gl::DrawArrays(gl::TRIANGLES, range.cover as GLsizei, (range.end - range.cover) as GLsizei);
+            }
+            gl::Disable(gl::STENCIL_TEST);
+        }
+        saved.restore();
+    }
+}
+
+impl Drop for StencilFill {
+    /// Clean up all OpenGL stuff on drop.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao_handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stencil_triangles, VERTEX_FLOATS};
+    use super::super::super::bezier::Segment;
+
+    #[test]
+    fn test_stencil_triangles() {
+        // a bow tie crossing itself: a fan of 2 triangles from its first point
+        let bow_tie = [Segment::Line((0f32, 0f32), (2f32, 2f32)), Segment::Line((2f32, 2f32), (2f32, 0f32)),
+                       Segment::Line((2f32, 0f32), (0f32, 2f32)), Segment::Line((0f32, 2f32), (0f32, 0f32))];
+        let mut vertices = Vec::new();
+        let bounds = stencil_triangles(&bow_tie, 0.1f32, &mut vertices);
+        assert_eq!(vertices.len(), 6 * VERTEX_FLOATS);
+        assert_eq!(bounds, ((0f32, 0f32), (2f32, 2f32)));
+
+        // a straight cubic is one quadratic piece, its control point in the middle
+        let curve = [Segment::Cubic((0f32, 0f32), (1f32, 0f32), (2f32, 0f32), (3f32, 0f32)),
+                     Segment::Line((3f32, 0f32), (0f32, 3f32))];
+        let mut vertices = Vec::new();
+        stencil_triangles(&curve, 0.1f32, &mut vertices);
+        assert_eq!(vertices.len(), 2 * 3 * VERTEX_FLOATS);
+        assert_eq!(&vertices[12..24], &[0f32, 0f32, 0f32, 0f32, 1.5f32, 0f32, 0.5f32, 0f32,
+                                        3f32, 0f32, 1f32, 1f32]);
+    }
+}
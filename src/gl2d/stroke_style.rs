@@ -0,0 +1,190 @@
+//! Strokes colored along the path: gradients and repeating patterns. The stroke is cut into
+//! pieces where its color changes, each drawn as its own patch with a color at either end.
+
+use super::super::bezier::Segment;
+use super::super::color::{self, Color, ColorSpace};
+use super::super::measure::ArcLengthTable;
+
+// patterns repeating more often than this along a path are stretched to repeat this often
+const MAX_PATTERN_REPEATS: f32 = 4096f32;
+// pieces between a pair of gradient stops blended in a space other than sRGB, whose colors the
+// shaders blend in sRGB along each piece
+const SPACE_STEPS: usize = 8;
+
+// distances (start, end) along a path and the stroke's colors there, None in a pattern's gaps
+type Interval = (f32, f32, Option<(Color, Color)>);
+
+/// How the stroke of a path is colored along its length, see `Path::set_stroke_style`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrokeStyle {
+    /// The color set with `Path::set_stroke`, the default.
+    Solid,
+    /// Colors blended along the path, with stops (position, color) in order of position. The
+    /// positions are fractions of the path's length, from 0 at its start to 1 at its end.
+    Gradient(Vec<(f32, Color)>),
+    /// Runs (length, color) repeated along the path from its start, with lengths in drawing
+    /// units. Runs with a transparent color leave gaps, as in a dashed line.
+    Pattern(Vec<(f32, Color)>)
+}
+
+// A piece of a styled stroke, with its color at the start and at the end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct StrokePiece {
+    pub segment: Segment,
+    pub start_color: [f32; 4],
+    pub end_color: [f32; 4]
+}
+
+impl StrokeStyle {
+    // The pattern's runs of positive length, scaled so they repeat at most MAX_PATTERN_REPEATS
+    // times along length, and the length of one repeat. None if nothing is left of it.
+    fn pattern_runs(runs: &[(f32, Color)], length: f32) -> Option<(Vec<(f32, Color)>, f32)> {
+        let runs: Vec<(f32, Color)> = runs.iter().cloned().filter(|&(run, _)| run > 0f32).collect();
+        let period: f32 = runs.iter().map(|&(run, _)| run).sum();
+        if !(period > 0f32 && period.is_finite()) {
+            return None;
+        }
+        let scale = (length / period / MAX_PATTERN_REPEATS).max(1f32);
+        Some((runs.iter().map(|&(run, color)| (run * scale, color)).collect(), period * scale))
+    }
+
+    // The intervals along a path of the given length that the color changes smoothly over, with
+    // gradients blended in space.
+    fn intervals(&self, length: f32, space: ColorSpace) -> Vec<Interval> {
+        let mut intervals = Vec::new();
+        if length.is_nan() || length <= 0f32 {
+            return intervals;
+        }
+        match *self {
+            StrokeStyle::Solid => {}
+            StrokeStyle::Gradient(ref stops) => {
+                if stops.is_empty() {
+                    return intervals;
+                }
+                let mut cuts = vec![0f32];
+                cuts.extend(stops.iter().map(|&(position, _)| position * length)
+                            .filter(|&s| s > 0f32 && s < length));
+                cuts.push(length);
+                if space != ColorSpace::Srgb {
+                    let coarse = cuts;
+                    cuts = coarse.windows(2)
+                        .flat_map(|pair| (0..SPACE_STEPS).map(move |k| pair[0] + (pair[1] - pair[0]) * k as f32 /
+                                                                       SPACE_STEPS as f32))
+                        .collect();
+                    cuts.push(length);
+                }
+                for pair in cuts.windows(2) {
+                    let colors = (color::gradient_in(stops, pair[0] / length, space),
+                                  color::gradient_in(stops, pair[1] / length, space));
+                    intervals.push((pair[0], pair[1], Some(colors)));
+                }
+            }
+            StrokeStyle::Pattern(ref runs) => {
+                if let Some((runs, _)) = Self::pattern_runs(runs, length) {
+                    let mut s = 0f32;
+                    'repeats: loop {
+                        for &(run, color) in &runs {
+                            let end = (s + run).min(length);
+                            let colors = if color.alpha > 0f32 { Some((color, color)) } else { None };
+                            intervals.push((s, end, colors));
+                            s = end;
+                            if s >= length {
+                                break 'repeats;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        intervals
+    }
+
+    // The color at distance s along a path of the given length, None in a pattern's gaps and for
+    // solid strokes. Gradients are blended in space.
+    pub(crate) fn color_at(&self, s: f32, length: f32, space: ColorSpace) -> Option<Color> {
+        match *self {
+            StrokeStyle::Solid => None,
+            StrokeStyle::Gradient(ref stops) if !stops.is_empty() && length > 0f32 =>
+                Some(color::gradient_in(stops, s / length, space)),
+            StrokeStyle::Gradient(_) => None,
+            StrokeStyle::Pattern(ref runs) => {
+                let (runs, period) = Self::pattern_runs(runs, length)?;
+                let mut offset = s.max(0f32) % period;
+                for &(run, color) in &runs {
+                    if offset < run {
+                        return if color.alpha > 0f32 { Some(color) } else { None };
+                    }
+                    offset -= run;
+                }
+                None
+            }
+        }
+    }
+
+    // Cut the path measured by the table into the pieces of the stroke, with their colors'
+    // alpha multiplied by opacity and gradients blended in space. Gaps get no pieces.
+    pub(crate) fn pieces(&self, table: &ArcLengthTable, opacity: f32, space: ColorSpace) -> Vec<StrokePiece> {
+        let with_opacity = |c: Color| [c.red, c.green, c.blue, c.alpha * opacity];
+        let mut pieces = Vec::new();
+        for (start, end, colors) in self.intervals(table.total_length(), space) {
+            let (c0, c1) = match colors {
+                Some(colors) => colors,
+                None => continue
+            };
+            let mix = |s: f32| {
+                let f = if end > start { (s - start) / (end - start) } else { 0f32 };
+                Color::rgba(c0.red + (c1.red - c0.red) * f, c0.green + (c1.green - c0.green) * f,
+                            c0.blue + (c1.blue - c0.blue) * f, c0.alpha + (c1.alpha - c0.alpha) * f)
+            };
+            for (i, segment) in table.segments().iter().enumerate() {
+                let (segment_start, segment_end) = table.segment_range(i);
+                let (a, b) = (start.max(segment_start), end.min(segment_end));
+                if b <= a {
+                    continue;
+                }
+                let (t0, t1) = (table.parameter_at(i, a), table.parameter_at(i, b));
+                pieces.push(StrokePiece { segment: segment.between(t0, t1), start_color: with_opacity(mix(a)),
+                                          end_color: with_opacity(mix(b)) });
+            }
+        }
+        pieces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StrokeStyle;
+    use super::super::super::bezier::Segment;
+    use super::super::super::color::{Color, ColorSpace};
+    use super::super::super::measure::ArcLengthTable;
+
+    #[test]
+    fn test_pieces() {
+        // a line 10 long then one 10 long going up
+        let table = ArcLengthTable::new((0f32, 0f32), vec![Segment::Line((0f32, 0f32), (10f32, 0f32)),
+                                                           Segment::Line((10f32, 0f32), (10f32, 10f32))]);
+        let (red, blue) = (Color::rgb(1f32, 0f32, 0f32), Color::rgb(0f32, 0f32, 1f32));
+        let gradient = StrokeStyle::Gradient(vec![(0f32, red), (1f32, blue)]);
+        let pieces = gradient.pieces(&table, 0.5f32, ColorSpace::Srgb);
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].segment, Segment::Line((0f32, 0f32), (10f32, 0f32)));
+        assert_eq!(pieces[0].start_color, [1f32, 0f32, 0f32, 0.5f32]);
+        assert_eq!(pieces[0].end_color, [0.5f32, 0f32, 0.5f32, 0.5f32]);
+        assert_eq!(pieces[1].end_color, [0f32, 0f32, 1f32, 0.5f32]);
+        // blended in another space the gradient is cut finer, into pieces the shaders can blend
+        let pieces = gradient.pieces(&table, 1f32, ColorSpace::Oklab);
+        assert_eq!(pieces.len(), 8);
+        assert_eq!(pieces[3].end_color, red.mix(&blue, 0.5f32, ColorSpace::Oklab).to_array());
+
+        // dashes 4 long with gaps of 2
+        let dashes = StrokeStyle::Pattern(vec![(4f32, red), (2f32, Color::rgba(0f32, 0f32, 0f32, 0f32))]);
+        let pieces = dashes.pieces(&table, 1f32, ColorSpace::Srgb);
+        let segments: Vec<Segment> = pieces.iter().map(|piece| piece.segment).collect();
+        assert_eq!(segments, vec![Segment::Line((0f32, 0f32), (4f32, 0f32)),
+                                  Segment::Line((6f32, 0f32), (10f32, 0f32)),
+                                  Segment::Line((10f32, 2f32), (10f32, 6f32)),
+                                  Segment::Line((10f32, 8f32), (10f32, 10f32))]);
+        assert_eq!(dashes.color_at(5f32, 20f32, ColorSpace::Srgb), None);
+        assert_eq!(dashes.color_at(13f32, 20f32, ColorSpace::Srgb), Some(red));
+    }
+}
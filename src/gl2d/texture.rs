@@ -0,0 +1,47 @@
+use gl;
+use gl::types::*;
+use std::os::raw::c_void;
+use super::super::image::ImageData;
+
+/// An OpenGL texture holding an RGBA image. Also cleans up on drop.
+#[derive(Debug, PartialEq)]
+pub struct Texture {
+    texture_id: GLuint
+}
+
+impl Texture {
+    /// Upload an image to a new texture. Texture coordinate (0, 0) is the top left of the image.
+    pub fn new(image: &ImageData) -> Self {
+        let mut texture_id = 0 as GLuint;
+        unsafe {
+            gl::GenTextures(1, &mut texture_id);
+            gl::BindTexture(gl::TEXTURE_2D, texture_id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA8 as GLint,
+                           image.width() as GLsizei, image.height() as GLsizei, 0,
+                           gl::RGBA, gl::UNSIGNED_BYTE, image.pixels().as_ptr() as *const c_void);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+        }
+        Texture { texture_id }
+    }
+
+    /// Bind the texture to a texture unit (0, 1, 2 ...).
+    pub fn bind(&self, unit: GLuint) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture_id);
+        }
+    }
+}
+
+impl Drop for Texture {
+    /// Delete the texture.
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture_id);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Tiling of large drawings: paths are bucketed into square tiles of the drawing plane by their
+//! bounds, so only paths near the view have to be sent to the GPU.
+
+use std::collections::{BTreeSet, HashMap};
+use super::drawing::PathId;
+
+// paths covering more tiles than this are kept in a list of paths that are always drawn
+const MAX_TILES_PER_PATH: i64 = 1024;
+
+// An inclusive rectangle of tile coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TileRange {
+    pub min: (i32, i32),
+    pub max: (i32, i32)
+}
+
+impl TileRange {
+    // The tiles of size `size` that a rectangle of the drawing plane touches.
+    pub fn covering(min: (f32, f32), max: (f32, f32), size: f32) -> Self {
+        let tile = |v: f32| (v / size).floor().max(i32::MIN as f32).min(i32::MAX as f32) as i32;
+        TileRange { min: (tile(min.0), tile(min.1)), max: (tile(max.0), tile(max.1)) }
+    }
+
+    pub fn contains(&self, other: &TileRange) -> bool {
+        self.min.0 <= other.min.0 && self.min.1 <= other.min.1 &&
+        self.max.0 >= other.max.0 && self.max.1 >= other.max.1
+    }
+
+    // The range grown by n tiles on every side.
+    pub fn expanded(&self, n: i32) -> Self {
+        TileRange { min: (self.min.0.saturating_sub(n), self.min.1.saturating_sub(n)),
+                    max: (self.max.0.saturating_add(n), self.max.1.saturating_add(n)) }
+    }
+
+    fn count(&self) -> i64 {
+        (self.max.0 as i64 - self.min.0 as i64 + 1) * (self.max.1 as i64 - self.min.1 as i64 + 1)
+    }
+}
+
+// Which paths are in which tiles.
+#[derive(Debug, Clone)]
+pub(crate) struct TileIndex {
+    size: f32,
+    tiles: HashMap<(i32, i32), Vec<PathId>>,
+    // where each path was put, None for the always drawn ones
+    path_tiles: HashMap<PathId, Option<TileRange>>
+}
+
+impl TileIndex {
+    pub fn new(size: f32) -> Self {
+        TileIndex { size, tiles: HashMap::new(), path_tiles: HashMap::new() }
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    // Add a path with the given bounds, replacing it if it was already there.
+    pub fn insert(&mut self, id: PathId, min: (f32, f32), max: (f32, f32)) {
+        self.remove(id);
+        let range = TileRange::covering(min, max, self.size);
+        if range.count() > MAX_TILES_PER_PATH {
+            self.path_tiles.insert(id, None);
+            return;
+        }
+        for x in range.min.0..=range.max.0 {
+            for y in range.min.1..=range.max.1 {
+                self.tiles.entry((x, y)).or_default().push(id);
+            }
+        }
+        self.path_tiles.insert(id, Some(range));
+    }
+
+    pub fn remove(&mut self, id: PathId) {
+        if let Some(Some(range)) = self.path_tiles.remove(&id) {
+            for x in range.min.0..=range.max.0 {
+                for y in range.min.1..=range.max.1 {
+                    if let Some(ids) = self.tiles.get_mut(&(x, y)) {
+                        ids.retain(|&other| other != id);
+                        if ids.is_empty() {
+                            self.tiles.remove(&(x, y));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.path_tiles.clear();
+    }
+
+    // The paths in any tile of the range, plus the always drawn ones.
+    pub fn paths_in(&self, range: &TileRange) -> BTreeSet<PathId> {
+        let mut ids: BTreeSet<PathId> = self.path_tiles.iter()
+            .filter(|&(_, tiles)| tiles.is_none())
+            .map(|(&id, _)| id)
+            .collect();
+        if range.count() > self.tiles.len() as i64 {
+            // fewer occupied tiles than tiles in view, e.g. zoomed far out
+            for (tile, tile_ids) in &self.tiles {
+                if tile.0 >= range.min.0 && tile.0 <= range.max.0 && tile.1 >= range.min.1 && tile.1 <= range.max.1 {
+                    ids.extend(tile_ids);
+                }
+            }
+        } else {
+            for x in range.min.0..=range.max.0 {
+                for y in range.min.1..=range.max.1 {
+                    if let Some(tile_ids) = self.tiles.get(&(x, y)) {
+                        ids.extend(tile_ids);
+                    }
+                }
+            }
+        }
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TileIndex, TileRange};
+    use super::super::drawing::PathId;
+
+    #[test]
+    fn test_tile_range() {
+        let range = TileRange::covering((-5f32, 0f32), (25f32, 9.9f32), 10f32);
+        assert_eq!(range, TileRange { min: (-1, 0), max: (2, 0) });
+        assert!(range.expanded(1).contains(&range));
+        assert!(!range.contains(&range.expanded(1)));
+    }
+
+    #[test]
+    fn test_paths_in_view() {
+        let mut index = TileIndex::new(10f32);
+        index.insert(PathId(0), (1f32, 1f32), (2f32, 2f32));
+        index.insert(PathId(1), (105f32, 5f32), (115f32, 8f32));
+        // far too big to bucket, always included
+        index.insert(PathId(2), (-1e6f32, -1e6f32), (1e6f32, 1e6f32));
+        let view = TileRange::covering((0f32, 0f32), (50f32, 50f32), 10f32);
+        assert_eq!(index.paths_in(&view).into_iter().collect::<Vec<_>>(), vec![PathId(0), PathId(2)]);
+        let everything = TileRange::covering((-1e4f32, -1e4f32), (1e4f32, 1e4f32), 10f32);
+        assert_eq!(index.paths_in(&everything).len(), 3);
+        index.remove(PathId(0));
+        index.insert(PathId(1), (0f32, 0f32), (1f32, 1f32));
+        assert_eq!(index.paths_in(&view).into_iter().collect::<Vec<_>>(), vec![PathId(1), PathId(2)]);
+    }
+}
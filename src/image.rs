@@ -0,0 +1,184 @@
+//! Raw image data that can be uploaded to the GPU, and PNG and JPEG decoding behind the
+//! `png-images` and `jpeg-images` features.
+
+#[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+use std::fs;
+#[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+use std::path::Path;
+#[cfg(feature = "png-images")]
+use super::color;
+use super::TrdlError;
+
+// the gamma of PNG files that do not say theirs, close to sRGB's curve
+#[cfg(feature = "png-images")]
+const PNG_DEFAULT_GAMMA: f32 = 1f32 / 2.2f32;
+
+/// An RGBA image with 8 bits per channel. Rows are stored top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageData {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>
+}
+
+impl ImageData {
+    /// Constructor, pixels must hold exactly width * height RGBA values.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, TrdlError> {
+        if width == 0 || height == 0 || pixels.len() != 4 * (width as usize) * (height as usize) {
+            return Err(TrdlError::InvalidImageSize);
+        }
+        Ok(ImageData { width, height, pixels })
+    }
+
+    /// Width of the image in pixels.
+    pub fn width(&self) -> u32 { self.width }
+
+    /// Height of the image in pixels.
+    pub fn height(&self) -> u32 { self.height }
+
+    /// The RGBA pixel values, rows top to bottom.
+    pub fn pixels(&self) -> &[u8] { &self.pixels }
+
+    /// Multiply each pixel's color by its alpha, for textures blended with premultiplied alpha.
+    pub fn premultiply_alpha(&mut self) {
+        for pixel in self.pixels.chunks_mut(4) {
+            let alpha = pixel[3] as u32;
+            for value in &mut pixel[..3] {
+                *value = ((*value as u32 * alpha + 127) / 255) as u8;
+            }
+        }
+    }
+
+    /// Undo `premultiply_alpha`, as near as 8 bits allow. Fully transparent pixels become black.
+    pub fn unpremultiply_alpha(&mut self) {
+        for pixel in self.pixels.chunks_mut(4) {
+            let alpha = pixel[3] as u32;
+            for value in &mut pixel[..3] {
+                *value = (*value as u32 * 255 + alpha / 2).checked_div(alpha).map_or(0, |v| v.min(255) as u8);
+            }
+        }
+    }
+
+    /// Read and decode a PNG or JPEG file, see `from_bytes`.
+    #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, TrdlError> {
+        ImageData::from_bytes(&fs::read(path)?)
+    }
+
+    /// Decode a PNG or JPEG image, told apart by their first bytes, for formats whose feature is
+    /// on. Gray, palette and 16 bit images are turned into 8 bit RGBA, and PNGs stored with a
+    /// gamma other than sRGB's are encoded as sRGB, the colors the drawing takes. Alpha stays
+    /// straight (not premultiplied), as the shaders blend it.
+    #[cfg(any(feature = "png-images", feature = "jpeg-images"))]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TrdlError> {
+        #[cfg(feature = "png-images")]
+        {
+            if bytes.starts_with(b"\x89PNG") {
+                return decode_png(bytes);
+            }
+        }
+        #[cfg(feature = "jpeg-images")]
+        {
+            if bytes.starts_with(&[0xff, 0xd8]) {
+                return decode_jpeg(bytes);
+            }
+        }
+        Err(TrdlError::InvalidImage("not a PNG or JPEG image, or its feature is off".to_string()))
+    }
+}
+
+#[cfg(feature = "png-images")]
+fn decode_png(bytes: &[u8]) -> Result<ImageData, TrdlError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0u8; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer)?;
+    buffer.truncate(frame.buffer_size());
+    let mut pixels = match frame.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::Indexed => return Err(TrdlError::InvalidImage("palette was not expanded".to_string()))
+    };
+    let info = reader.info();
+    let gamma = info.source_gamma.map_or(PNG_DEFAULT_GAMMA, |gamma| gamma.into_value());
+    if info.srgb.is_none() && (gamma - PNG_DEFAULT_GAMMA).abs() > 0.01f32 && gamma > 0f32 {
+        let table: Vec<u8> = (0..256).map(|v| {
+            let linear = (v as f32 / 255f32).powf(1f32 / gamma);
+            (color::linear_to_srgb(linear) * 255f32).round() as u8
+        }).collect();
+        for pixel in pixels.chunks_mut(4) {
+            for value in &mut pixel[..3] {
+                *value = table[*value as usize];
+            }
+        }
+    }
+    ImageData::new(frame.width, frame.height, pixels)
+}
+
+#[cfg(feature = "jpeg-images")]
+fn decode_jpeg(bytes: &[u8]) -> Result<ImageData, TrdlError> {
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let data = decoder.decode()?;
+    let info = decoder.info().ok_or_else(|| TrdlError::InvalidImage("JPEG without a frame".to_string()))?;
+    let pixels = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => data.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        jpeg_decoder::PixelFormat::L8 => data.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        // big endian 16 bit gray, the high byte is enough
+        jpeg_decoder::PixelFormat::L16 => data.chunks(2).flat_map(|p| [p[0], p[0], p[0], 255]).collect(),
+        // Adobe's inverted CMYK
+        jpeg_decoder::PixelFormat::CMYK32 => data.chunks(4).flat_map(|p| {
+            let k = p[3] as u32;
+            let channel = |c: u8| (c as u32 * k / 255) as u8;
+            [channel(p[0]), channel(p[1]), channel(p[2]), 255]
+        }).collect()
+    };
+    ImageData::new(info.width as u32, info.height as u32, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImageData;
+
+    #[test]
+    fn test_new_checks_size() {
+        assert!(ImageData::new(2, 2, vec![0u8; 16]).is_ok());
+        assert!(ImageData::new(2, 2, vec![0u8; 12]).is_err());
+        assert!(ImageData::new(0, 2, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_premultiply_alpha() {
+        let mut image = ImageData::new(2, 1, vec![255, 128, 0, 128, 10, 20, 30, 0]).unwrap();
+        image.premultiply_alpha();
+        assert_eq!(image.pixels(), &[128, 64, 0, 128, 0, 0, 0, 0]);
+        image.unpremultiply_alpha();
+        assert_eq!(image.pixels(), &[255, 128, 0, 128, 0, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "png-images")]
+    #[test]
+    fn test_decode_png() {
+        let encode = |color: png::ColorType, gamma: Option<f32>, data: &[u8]| {
+            let mut bytes = Vec::new();
+            {
+                let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+                encoder.set_color(color);
+                encoder.set_depth(png::BitDepth::Eight);
+                if let Some(gamma) = gamma {
+                    encoder.set_source_gamma(png::ScaledFloat::new(gamma));
+                }
+                encoder.write_header().unwrap().write_image_data(data).unwrap();
+            }
+            bytes
+        };
+        let gray = ImageData::from_bytes(&encode(png::ColorType::GrayscaleAlpha, None, &[10, 20, 30, 40])).unwrap();
+        assert_eq!(gray.pixels(), &[10, 10, 10, 20, 30, 30, 30, 40]);
+        // linear values are encoded as sRGB
+        let linear = ImageData::from_bytes(&encode(png::ColorType::Rgb, Some(1f32), &[0, 255, 128, 0, 0, 0])).unwrap();
+        assert_eq!(&linear.pixels()[..4], &[0, 255, 188, 255]);
+        assert!(ImageData::from_bytes(b"GIF89a").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Windowing library independent input events, see `Drawing::handle_event`. The window adapters
+//! convert their library's events to these.
+
+use super::gl2d::drawing::PathId;
+
+// How many pixels of touchpad scrolling count as one line of scroll wheel movement.
+#[cfg_attr(not(any(feature = "glutin", feature = "winit")), allow(dead_code))]
+pub(crate) const PIXELS_PER_LINE: f32 = 20f32;
+
+/// A mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u16)
+}
+
+/// A keyboard key. Keys that produce text are reported as `Character`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Character(char),
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Shift,
+    Control,
+    Alt,
+    Other
+}
+
+/// State of the modifier keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub logo: bool
+}
+
+/// An input event. Positions are in window pixels with the origin at the bottom left, which are
+/// drawing coordinates as long as the camera has not moved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    MouseMove { position: (f32, f32) },
+    MouseDown { button: MouseButton, position: (f32, f32) },
+    MouseUp { button: MouseButton, position: (f32, f32) },
+    /// Scroll wheel movement in lines, positive is away from the user.
+    MouseWheel { delta: f32, position: (f32, f32) },
+    KeyDown { key: Key, modifiers: Modifiers },
+    KeyUp { key: Key, modifiers: Modifiers }
+}
+
+/// What happened to the paths of a drawing because of an input event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathEvent {
+    /// The mouse moved onto a path.
+    HoverEnter(PathId),
+    /// The mouse moved off a path.
+    HoverLeave(PathId),
+    /// A mouse button was pressed over a path.
+    Press(PathId, MouseButton),
+    /// A mouse button was released over a path.
+    Release(PathId, MouseButton),
+    /// A mouse button was pressed and released over the same path.
+    Click(PathId, MouseButton)
+}
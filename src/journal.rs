@@ -0,0 +1,153 @@
+//! Undo and redo for edits to a drawing's paths.
+
+use super::gl2d::drawing::{Drawing, Path, PathId, Window};
+use super::gl2d::layer::LayerId;
+use super::transform::Transform2D;
+use super::TrdlError;
+
+// A reversible edit, holding everything needed to do it again or undo it.
+#[derive(Debug, Clone)]
+enum Command {
+    Add { id: PathId, layer: LayerId, path: Path },
+    Remove { id: PathId, layer: LayerId, path: Path },
+    // boxed, two paths are much bigger than the other commands
+    Update { id: PathId, before: Box<Path>, after: Box<Path> }
+}
+
+/// A history of edits to a drawing. Make edits through the journal instead of the drawing to be
+/// able to undo them. Edits made directly on the drawing are not recorded, and undoing past them
+/// may not give back the drawing as it was.
+#[derive(Debug, Clone, Default)]
+pub struct DrawingJournal {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    limit: Option<usize>
+}
+
+impl DrawingJournal {
+    /// Constructor for a journal that keeps every edit.
+    pub fn new() -> Self {
+        DrawingJournal::default()
+    }
+
+    /// Constructor for a journal that forgets the oldest edits past `limit`.
+    pub fn with_limit(limit: usize) -> Self {
+        DrawingJournal { limit: Some(limit), ..DrawingJournal::default() }
+    }
+
+    /// Add a path to the drawing, see `Drawing::add_path`.
+    pub fn add_path<W: Window>(&mut self, drawing: &mut Drawing<'_, W>, path: Path) -> Result<PathId, TrdlError> {
+        let layer = drawing.base_layer();
+        self.add_path_to_layer(drawing, layer, path)
+    }
+
+    /// Add a path to a layer, see `Drawing::add_path_to_layer`.
+    pub fn add_path_to_layer<W: Window>(&mut self, drawing: &mut Drawing<'_, W>, layer: LayerId, path: Path)
+            -> Result<PathId, TrdlError> {
+        let id = drawing.add_path_to_layer(layer, path.clone())?;
+        self.record(Command::Add { id, layer, path });
+        Ok(id)
+    }
+
+    /// Remove a path from the drawing, see `Drawing::remove_path`.
+    pub fn remove_path<W: Window>(&mut self, drawing: &mut Drawing<'_, W>, id: PathId) -> Option<Path> {
+        let layer = drawing.layer_of(id)?;
+        let path = drawing.remove_path(id)?;
+        self.record(Command::Remove { id, layer, path: path.clone() });
+        Some(path)
+    }
+
+    /// Replace a path, see `Drawing::update_path`.
+    pub fn update_path<W: Window>(&mut self, drawing: &mut Drawing<'_, W>, id: PathId, path: Path)
+            -> Result<Option<Path>, TrdlError> {
+        let before = drawing.update_path(id, path.clone())?;
+        if let Some(ref before) = before {
+            self.record(Command::Update { id, before: Box::new(before.clone()), after: Box::new(path) });
+        }
+        Ok(before)
+    }
+
+    /// Apply a transform to the vertices and control points of a path. Returns false if the path
+    /// is not in the drawing.
+    pub fn transform_path<W: Window>(&mut self, drawing: &mut Drawing<'_, W>, id: PathId,
+                                     transform: &Transform2D) -> Result<bool, TrdlError> {
+        let path = match drawing.get_path(id) {
+            Some(path) => path.transformed(transform),
+            None => return Ok(false)
+        };
+        self.update_path(drawing, id, path).map(|before| before.is_some())
+    }
+
+    /// Undo the last edit. Returns false if there was nothing to undo.
+    pub fn undo<W: Window>(&mut self, drawing: &mut Drawing<'_, W>) -> Result<bool, TrdlError> {
+        let command = match self.undo.pop() {
+            Some(command) => command,
+            None => return Ok(false)
+        };
+        let result = match command {
+            Command::Add { id, .. } => {
+                drawing.remove_path(id);
+                Ok(())
+            }
+            Command::Remove { id, layer, ref path } => drawing.restore_path(id, layer, path.clone()),
+            Command::Update { id, ref before, .. } => drawing.update_path(id, (**before).clone()).map(|_| ())
+        };
+        // keep a command that could not be undone so it can be tried again
+        if let Err(error) = result {
+            self.undo.push(command);
+            return Err(error);
+        }
+        self.redo.push(command);
+        Ok(true)
+    }
+
+    /// Redo the last undone edit. Returns false if there was nothing to redo.
+    pub fn redo<W: Window>(&mut self, drawing: &mut Drawing<'_, W>) -> Result<bool, TrdlError> {
+        let command = match self.redo.pop() {
+            Some(command) => command,
+            None => return Ok(false)
+        };
+        let result = match command {
+            Command::Add { id, layer, ref path } => drawing.restore_path(id, layer, path.clone()),
+            Command::Remove { id, .. } => {
+                drawing.remove_path(id);
+                Ok(())
+            }
+            Command::Update { id, ref after, .. } => drawing.update_path(id, (**after).clone()).map(|_| ())
+        };
+        if let Err(error) = result {
+            self.redo.push(command);
+            return Err(error);
+        }
+        self.undo.push(command);
+        Ok(true)
+    }
+
+    /// Whether there is an edit to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Whether there is an undone edit to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forget all edits.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    // A new edit was made, which makes the undone ones unreachable.
+    fn record(&mut self, command: Command) {
+        self.redo.clear();
+        self.undo.push(command);
+        if let Some(limit) = self.limit {
+            if self.undo.len() > limit {
+                let excess = self.undo.len() - limit;
+                self.undo.drain(..excess);
+            }
+        }
+    }
+}
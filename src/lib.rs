@@ -1,18 +1,114 @@
 extern crate gl;
+#[cfg(any(test, feature = "glutin"))]
+extern crate glutin;
+#[cfg(feature = "winit")]
+extern crate winit;
+#[cfg(feature = "glfw")]
+extern crate glfw;
+#[cfg(feature = "sdl2")]
+extern crate sdl2;
+#[cfg(feature = "log")]
+extern crate log;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "lottie")]
+extern crate serde_json;
+#[cfg(feature = "font")]
+extern crate ttf_parser;
+#[cfg(feature = "png-images")]
+extern crate png;
+#[cfg(feature = "jpeg-images")]
+extern crate jpeg_decoder;
+
+#[macro_use]
+mod trace;
 
 mod triangulation;
+mod angle;
+mod mesh;
 mod gl2d;
+mod color;
+mod theme;
+mod image;
+mod atlas;
+mod camera;
+mod bezier;
+mod nurbs;
+mod measure;
+mod transform;
+mod symmetry;
+mod canvas;
+mod frame;
+mod journal;
+pub mod input;
+pub mod particles;
+pub mod sdf;
+pub mod snap;
+pub mod adapters;
+pub mod timeline;
+#[cfg(feature = "lottie")]
+pub mod lottie;
+#[cfg(feature = "font")]
+pub mod font;
+#[cfg(feature = "ui")]
+pub mod ui;
 
 pub use gl2d::drawing::Window;
 pub use gl2d::drawing::Drawing;
 pub use gl2d::drawing::Path;
+pub use gl2d::drawing::PathId;
+pub use gl2d::drawing::PathStyle;
+pub use gl2d::drawing::FrameStats;
+pub use gl2d::drawing::GeometrySnapshot;
+pub use gl2d::drawing::SortMode;
+pub use gl2d::drawing::StrokeAlign;
+pub use gl2d::drawing::StrokeUnits;
+pub use gl2d::drawing::LineJoin;
+pub use gl2d::stroke_style::StrokeStyle;
+pub use gl2d::fill_pattern::FillPattern;
+pub use gl2d::gradient::{Fill, GradientId};
+pub use gl2d::stencil_fill::{FillRule, StencilFill};
+pub use gl2d::ellipse_renderer::{Ellipse, EllipseRenderer};
+pub use gl2d::rect_renderer::{Rect, RectRenderer};
+#[cfg(feature = "compute")]
+pub use gl2d::gpu_fill::GpuFill;
+#[cfg(feature = "software")]
+pub use gl2d::software::{RasterView, SoftwareRasterizer};
+pub use gl2d::background::{Background, BackgroundShader, ClearMode, UniformValue};
+pub use gl2d::bake::TextureHandle;
+pub use gl2d::resource::Resource;
+pub use gl2d::gl_api::{Gl, GlApi, GlCall, RecordingGl};
+pub use gl2d::selection::SelectionStyle;
+pub use gl2d::overlay::{Guide, Overlay};
+pub use gl2d::debug_view::DebugView;
+pub use gl2d::layer::LayerId;
+pub use angle::Angle;
+pub use color::{Color, ColorSpace};
+pub use theme::{Palette, Theme, ThemeVariant};
+pub use image::ImageData;
+pub use atlas::{AtlasRegion, TextureAtlas};
+pub use camera::Camera;
+pub use camera::Origin;
+pub use camera::YAxis;
+pub use camera::Projection;
+pub use camera::ScaleMode;
+pub use mesh::Mesh;
+pub use triangulation::TessellationDiagnostics;
+pub use measure::{ArcLengthTable, PathPoint};
+pub use bezier::Segment;
+pub use transform::Transform2D;
+pub use symmetry::Symmetry;
+pub use canvas::Canvas;
+pub use journal::DrawingJournal;
+pub use frame::{run_loop, run_loop_with, FrameContext, FrameTimer, FrameTiming, LoopConfig};
 
 use std::io;
-use std::error::Error;
 use std::fmt;
 
-/// Standard TRDL error
+/// Standard TRDL error. More variants may be added, so matches on it need a catch-all arm; use
+/// `kind` to tell broad categories apart. The messages are stable and fit to show to users.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum TrdlError {
     ShaderIo(io::Error),
     NullString,
@@ -22,55 +118,99 @@ pub enum TrdlError {
     InvalidLinkError,
     NotEnoughVertices,
     NonSimplePolygon,
+    ZeroAreaPolygon,
     NoVisibleGeometry,
     ArcToIsLineTo,
+    InvalidImageSize,
+    TooManyPaths,
+    InvalidNurbs,
+    NoSuchLayer,
+    OutOfGpuBudget,
+    InvalidLottie(String),
+    InvalidFont(String),
+    /// An image could not be decoded.
+    InvalidImage(String),
+    PointOutsidePolygon,
+    GlVersionTooOld(u32, u32),
+    Tessellation(Box<TessellationDiagnostics>),
+    /// The path builder call and what was wrong with it, see `Path::build`.
+    InvalidPath(&'static str, &'static str),
+    NonFiniteCoordinate,
+}
+
+/// The broad category of a `TrdlError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Shaders failed to compile or link, or OpenGL is too old.
+    Gl,
+    /// A file could not be read.
+    Io,
+    /// A path could not be built or turned into triangles.
+    Geometry,
+    /// An argument was wrong, e.g. data that could not be parsed or a layer that is gone.
+    InvalidInput,
+    /// The drawing is full or over its GPU memory budget.
+    Limit
+}
+
+impl TrdlError {
+    /// The category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            TrdlError::ShaderIo(_) => ErrorKind::Io,
+            TrdlError::NullString | TrdlError::CompileError(_) | TrdlError::InvalidCompileError |
+            TrdlError::LinkError(_) | TrdlError::InvalidLinkError | TrdlError::GlVersionTooOld(..) => ErrorKind::Gl,
+            TrdlError::NotEnoughVertices | TrdlError::NonSimplePolygon | TrdlError::ZeroAreaPolygon |
+            TrdlError::NoVisibleGeometry | TrdlError::ArcToIsLineTo | TrdlError::InvalidNurbs |
+            TrdlError::PointOutsidePolygon | TrdlError::Tessellation(_) | TrdlError::InvalidPath(..) |
+            TrdlError::NonFiniteCoordinate =>
+                ErrorKind::Geometry,
+            TrdlError::InvalidImageSize | TrdlError::NoSuchLayer | TrdlError::InvalidLottie(_) |
+            TrdlError::InvalidFont(_) | TrdlError::InvalidImage(_) => ErrorKind::InvalidInput,
+            TrdlError::TooManyPaths | TrdlError::OutOfGpuBudget => ErrorKind::Limit
+        }
+    }
 }
 
 impl fmt::Display for TrdlError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             TrdlError::ShaderIo(ref err) => err.fmt(f),
-            TrdlError::NullString => write!(f, "{}", self.description()),
+            TrdlError::NullString => write!(f, "Shader string was null"),
             TrdlError::CompileError(ref message) => write!(f, "{}", message),
-            TrdlError::InvalidCompileError => write!(f, "{}", self.description()),
+            TrdlError::InvalidCompileError => write!(f, "An error occurred during shader compile"),
             TrdlError::LinkError(ref message) => write!(f, "{}", message),
-            TrdlError::InvalidLinkError => write!(f, "{}", self.description()),
-            TrdlError::NotEnoughVertices => write!(f, "{}", self.description()),
-            TrdlError::NonSimplePolygon => write!(f, "{}", self.description()),
-            TrdlError::NoVisibleGeometry => write!(f, "{}", self.description()),
-            TrdlError::ArcToIsLineTo => write!(f, "{}", self.description()),
+            TrdlError::InvalidLinkError => write!(f, "An error occurred during shader program link"),
+            TrdlError::NotEnoughVertices => write!(f, "A polygon must have 3 or more points"),
+            TrdlError::NonSimplePolygon => write!(f, "Error triangulating polygon, is it non-simple?"),
+            TrdlError::ZeroAreaPolygon => write!(f, "The polygon encloses no area"),
+            TrdlError::NoVisibleGeometry => write!(f, "Either the stroke or fill (or both) must be set"),
+            TrdlError::ArcToIsLineTo => write!(f, "One of the radii is 0, so this is just a line"),
+            TrdlError::InvalidImageSize => write!(f, "Image data does not match its width and height"),
+            TrdlError::TooManyPaths => write!(f, "A drawing can hold at most 2097152 paths"),
+            TrdlError::InvalidNurbs => write!(f, "NURBS control points, knots and weights do not fit together"),
+            TrdlError::NoSuchLayer => write!(f, "Layer is not in the drawing"),
+            TrdlError::OutOfGpuBudget => write!(f, "Path would take the drawing over its GPU memory budget"),
+            TrdlError::InvalidLottie(ref message) => write!(f, "Invalid Lottie animation: {}", message),
+            TrdlError::InvalidFont(ref message) => write!(f, "Invalid font: {}", message),
+            TrdlError::InvalidImage(ref message) => write!(f, "Invalid image: {}", message),
+            TrdlError::PointOutsidePolygon => write!(f, "A color point is outside the path"),
+            TrdlError::GlVersionTooOld(major, minor) => write!(f, "This needs OpenGL {}.{} or later", major, minor),
+            TrdlError::Tessellation(ref diagnostics) if diagnostics.suggestions.is_empty() => diagnostics.error.fmt(f),
+            TrdlError::Tessellation(ref diagnostics) =>
+                write!(f, "{}: {}", diagnostics.error, diagnostics.suggestions.join("; ")),
+            TrdlError::NonFiniteCoordinate => write!(f, "A coordinate is NaN or infinite"),
+            TrdlError::InvalidPath(call, reason) => write!(f, "Invalid path from Path::{}: {}", call, reason),
         }
     }
 }
 
 impl std::error::Error for TrdlError {
-    fn description(&self) -> &str {
-        match *self {
-            TrdlError::ShaderIo(ref err) => err.description(),
-            TrdlError::NullString => "Shader string was null",
-            TrdlError::CompileError(ref message) => message,
-            TrdlError::InvalidCompileError => "An error occurred during shader compile",
-            TrdlError::LinkError(ref message) => message,
-            TrdlError::InvalidLinkError => "An error occurred during shader program link",
-            TrdlError::NotEnoughVertices => "A polygon must have 3 or more points",
-            TrdlError::NonSimplePolygon => "Error triangulating polygon, is it non-simple?",
-            TrdlError::NoVisibleGeometry => "Either the stroke or fill (or both) must be set",
-            TrdlError::ArcToIsLineTo => "One of the radii is 0, so this is just a line"
-        }
-    }
-
-    fn cause(&self) -> Option<&std::error::Error> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match *self {
             TrdlError::ShaderIo(ref err) => Some(err),
-            TrdlError::NullString => None,
-            TrdlError::CompileError(_) => None,
-            TrdlError::InvalidCompileError =>  None,
-            TrdlError::LinkError(_) => None,
-            TrdlError::InvalidLinkError => None,
-            TrdlError::NotEnoughVertices => None,
-            TrdlError::NonSimplePolygon => None,
-            TrdlError::NoVisibleGeometry => None,
-            TrdlError::ArcToIsLineTo => None
+            TrdlError::Tessellation(ref diagnostics) => Some(&diagnostics.error),
+            _ => None
         }
     }
 }
@@ -81,3 +221,47 @@ impl From<io::Error> for TrdlError {
     }
 }
 
+#[cfg(feature = "font")]
+impl From<ttf_parser::FaceParsingError> for TrdlError {
+    fn from(err: ttf_parser::FaceParsingError) -> TrdlError {
+        TrdlError::InvalidFont(err.to_string())
+    }
+}
+
+#[cfg(feature = "png-images")]
+impl From<png::DecodingError> for TrdlError {
+    fn from(err: png::DecodingError) -> TrdlError {
+        TrdlError::InvalidImage(err.to_string())
+    }
+}
+
+#[cfg(feature = "jpeg-images")]
+impl From<jpeg_decoder::Error> for TrdlError {
+    fn from(err: jpeg_decoder::Error) -> TrdlError {
+        TrdlError::InvalidImage(err.to_string())
+    }
+}
+
+#[cfg(feature = "lottie")]
+impl From<serde_json::Error> for TrdlError {
+    fn from(err: serde_json::Error) -> TrdlError {
+        TrdlError::InvalidLottie(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::io;
+    use super::{ErrorKind, TrdlError};
+
+    #[test]
+    fn test_error_kind_and_source() {
+        let err = TrdlError::from(io::Error::new(io::ErrorKind::NotFound, "no shader"));
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert_eq!(err.source().map(|source| source.to_string()), Some("no shader".to_string()));
+        assert_eq!(TrdlError::GlVersionTooOld(4, 3).kind(), ErrorKind::Gl);
+        assert_eq!(TrdlError::TooManyPaths.kind(), ErrorKind::Limit);
+        assert!(TrdlError::NonSimplePolygon.source().is_none());
+    }
+}
@@ -0,0 +1,712 @@
+//! Playback of Lottie (Bodymovin) animations exported from After Effects and other tools.
+//!
+//! A useful subset of the format is read: shape layers with parenting and in and out points,
+//! their transforms and opacity, and groups of paths, rectangles and ellipses with fills and
+//! strokes. Any of their properties may be animated with linear, eased or hold keyframes.
+//! Other layer types, masks, mattes, trims, gradients and expressions are skipped. Each shape
+//! becomes its own path, so shapes meant to cut holes into others are drawn filled.
+
+use std::collections::HashMap;
+use serde_json::Value;
+use super::gl2d::drawing::{Drawing, Path, PathId, Window};
+use super::color::{Color, ColorSpace};
+use super::timeline::ease;
+use super::transform::Transform2D;
+use super::TrdlError;
+
+// how far control points are from a quarter ellipse's ends, as a fraction of its radius
+const KAPPA: f32 = 0.552_284_8;
+
+/// A parsed animation. Frames are numbered as in the file, see `in_point` and `out_point`.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    width: f32,
+    height: f32,
+    frame_rate: f32,
+    in_point: f32,
+    out_point: f32,
+    // top layer first, as in the file
+    layers: Vec<Layer>,
+    color_space: ColorSpace
+}
+
+#[derive(Debug, Clone)]
+struct Layer {
+    index: Option<i64>,
+    parent: Option<i64>,
+    in_point: f32,
+    out_point: f32,
+    transform: LayerTransform,
+    // null layers have a transform for children but nothing to draw
+    shapes: Vec<Shape>
+}
+
+#[derive(Debug, Clone)]
+struct LayerTransform {
+    anchor: Property<Vec<f32>>,
+    position: Property<Vec<f32>>,
+    scale: Property<Vec<f32>>,
+    rotation: Property<f32>,
+    opacity: Property<f32>
+}
+
+#[derive(Debug, Clone)]
+enum Shape {
+    Group(Vec<Shape>),
+    Path(Property<Bezier>),
+    Rectangle { position: Property<Vec<f32>>, size: Property<Vec<f32>>, roundness: Property<f32> },
+    Ellipse { position: Property<Vec<f32>>, size: Property<Vec<f32>> },
+    Fill { color: Property<Vec<f32>>, opacity: Property<f32> },
+    Stroke { color: Property<Vec<f32>>, opacity: Property<f32>, width: Property<f32> },
+    Transform(LayerTransform)
+}
+
+// A cubic Bezier shape: vertices with tangents relative to them.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Bezier {
+    vertices: Vec<(f32, f32)>,
+    in_tangents: Vec<(f32, f32)>,
+    out_tangents: Vec<(f32, f32)>,
+    closed: bool
+}
+
+#[derive(Debug, Clone)]
+enum Property<T> {
+    Static(T),
+    Animated(Vec<Keyframe<T>>)
+}
+
+#[derive(Debug, Clone)]
+struct Keyframe<T> {
+    time: f32,
+    value: T,
+    // the value at the next keyframe, given in files from older exporters
+    end: Option<T>,
+    // control points of the easing curve from (0, 0) to (1, 1) towards the next keyframe
+    ease_out: (f32, f32),
+    ease_in: (f32, f32),
+    hold: bool
+}
+
+// Values keyframes interpolate between.
+trait Interpolate: Clone {
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Vec<f32> {
+    fn interpolate(&self, other: &Vec<f32>, t: f32) -> Vec<f32> {
+        self.iter().zip(other).map(|(a, b)| a.interpolate(b, t)).collect()
+    }
+}
+
+impl Interpolate for Bezier {
+    fn interpolate(&self, other: &Bezier, t: f32) -> Bezier {
+        if self.vertices.len() != other.vertices.len() {
+            return if t < 1f32 { self.clone() } else { other.clone() };
+        }
+        let mix = |a: &[(f32, f32)], b: &[(f32, f32)]| -> Vec<(f32, f32)> {
+            a.iter().zip(b).map(|(p, q)| (p.0.interpolate(&q.0, t), p.1.interpolate(&q.1, t))).collect()
+        };
+        Bezier {
+            vertices: mix(&self.vertices, &other.vertices),
+            in_tangents: mix(&self.in_tangents, &other.in_tangents),
+            out_tangents: mix(&self.out_tangents, &other.out_tangents),
+            closed: self.closed
+        }
+    }
+}
+
+impl<T: Interpolate> Property<T> {
+    fn at(&self, frame: f32) -> T {
+        self.at_with(frame, |a, b, t| a.interpolate(b, t))
+    }
+}
+
+impl<T: Clone> Property<T> {
+    // The value at a frame, with values between keyframes from `interpolate`.
+    fn at_with<F: Fn(&T, &T, f32) -> T>(&self, frame: f32, interpolate: F) -> T {
+        let keyframes = match *self {
+            Property::Static(ref value) => return value.clone(),
+            Property::Animated(ref keyframes) => keyframes
+        };
+        let next = keyframes.iter().position(|k| k.time > frame).unwrap_or(keyframes.len());
+        if next == 0 {
+            return keyframes[0].value.clone();
+        }
+        let current = &keyframes[next - 1];
+        let span = match keyframes.get(next) {
+            Some(next) if !current.hold => next.time - current.time,
+            _ => return current.value.clone()
+        };
+        let end = current.end.as_ref().unwrap_or(&keyframes[next].value);
+        let t = ease(current.ease_out, current.ease_in, (frame - current.time) / span);
+        interpolate(&current.value, end, t)
+    }
+}
+
+fn invalid(message: &str) -> TrdlError {
+    TrdlError::InvalidLottie(message.to_string())
+}
+
+fn number(value: &Value) -> Option<f32> {
+    match *value {
+        Value::Number(ref n) => n.as_f64().map(|n| n as f32),
+        // older exporters wrap keyframe values in arrays
+        Value::Array(ref items) => items.first().and_then(number),
+        _ => None
+    }
+}
+
+fn numbers(value: &Value) -> Option<Vec<f32>> {
+    match *value {
+        Value::Number(_) => number(value).map(|n| vec![n]),
+        Value::Array(ref items) => items.iter().map(number).collect(),
+        _ => None
+    }
+}
+
+fn point(value: &Value) -> Option<(f32, f32)> {
+    numbers(value).filter(|p| p.len() >= 2).map(|p| (p[0], p[1]))
+}
+
+fn points(value: &Value) -> Option<Vec<(f32, f32)>> {
+    value.as_array()?.iter().map(point).collect()
+}
+
+fn bezier(value: &Value) -> Option<Bezier> {
+    // keyframe values hold the shape in an array
+    let value = match *value {
+        Value::Array(ref items) => items.first()?,
+        _ => value
+    };
+    let vertices = points(&value["v"])?;
+    let in_tangents = points(&value["i"])?;
+    let out_tangents = points(&value["o"])?;
+    if in_tangents.len() != vertices.len() || out_tangents.len() != vertices.len() {
+        return None;
+    }
+    Some(Bezier { vertices, in_tangents, out_tangents, closed: value["c"].as_bool().unwrap_or(false) })
+}
+
+fn easing_point(value: &Value) -> Option<(f32, f32)> {
+    Some((number(&value["x"])?, number(&value["y"])?))
+}
+
+// Read an animatable property, or the default if it is missing.
+fn property<T, F>(value: &Value, default: T, parse: F) -> Result<Property<T>, TrdlError>
+        where T: Clone, F: Fn(&Value) -> Option<T> {
+    if value.is_null() {
+        return Ok(Property::Static(default));
+    }
+    let k = &value["k"];
+    let animated = value["a"].as_i64() == Some(1) ||
+        k.as_array().and_then(|items| items.first()).is_some_and(|first| first.get("t").is_some());
+    if !animated {
+        return parse(k).map(Property::Static).ok_or_else(|| invalid("property value has the wrong type"));
+    }
+    let mut keyframes: Vec<Keyframe<T>> = Vec::new();
+    for keyframe in k.as_array().ok_or_else(|| invalid("keyframes are not an array"))? {
+        let time = number(&keyframe["t"]).ok_or_else(|| invalid("keyframe without a time"))?;
+        let value = match keyframe.get("s") {
+            Some(value) => parse(value).ok_or_else(|| invalid("keyframe value has the wrong type"))?,
+            // the last keyframe of older files only has a time
+            None => match keyframes.last() {
+                Some(&Keyframe { end: Some(ref end), .. }) => end.clone(),
+                _ => continue
+            }
+        };
+        keyframes.push(Keyframe {
+            time,
+            value,
+            end: keyframe.get("e").and_then(&parse),
+            ease_out: easing_point(&keyframe["o"]).unwrap_or((0f32, 0f32)),
+            ease_in: easing_point(&keyframe["i"]).unwrap_or((1f32, 1f32)),
+            hold: keyframe["h"].as_i64() == Some(1)
+        });
+    }
+    if keyframes.is_empty() {
+        return Err(invalid("animated property without keyframes"));
+    }
+    Ok(Property::Animated(keyframes))
+}
+
+fn transform(value: &Value) -> Result<LayerTransform, TrdlError> {
+    Ok(LayerTransform {
+        anchor: property(&value["a"], vec![0f32, 0f32], numbers)?,
+        position: property(&value["p"], vec![0f32, 0f32], numbers)?,
+        scale: property(&value["s"], vec![100f32, 100f32], numbers)?,
+        rotation: property(&value["r"], 0f32, number)?,
+        opacity: property(&value["o"], 100f32, number)?
+    })
+}
+
+fn shapes(items: &Value) -> Result<Vec<Shape>, TrdlError> {
+    let items = match items.as_array() {
+        Some(items) => items,
+        None => return Ok(Vec::new())
+    };
+    let mut result = Vec::with_capacity(items.len());
+    for item in items {
+        if item["hd"].as_bool() == Some(true) {
+            continue;
+        }
+        let shape = match item["ty"].as_str() {
+            Some("gr") => Shape::Group(shapes(&item["it"])?),
+            Some("sh") => Shape::Path(property(&item["ks"], Bezier::default(), bezier)?),
+            Some("rc") => Shape::Rectangle {
+                position: property(&item["p"], vec![0f32, 0f32], numbers)?,
+                size: property(&item["s"], vec![0f32, 0f32], numbers)?,
+                roundness: property(&item["r"], 0f32, number)?
+            },
+            Some("el") => Shape::Ellipse {
+                position: property(&item["p"], vec![0f32, 0f32], numbers)?,
+                size: property(&item["s"], vec![0f32, 0f32], numbers)?
+            },
+            Some("fl") => Shape::Fill {
+                color: property(&item["c"], vec![0f32, 0f32, 0f32], numbers)?,
+                opacity: property(&item["o"], 100f32, number)?
+            },
+            Some("st") => Shape::Stroke {
+                color: property(&item["c"], vec![0f32, 0f32, 0f32], numbers)?,
+                opacity: property(&item["o"], 100f32, number)?,
+                width: property(&item["w"], 1f32, number)?
+            },
+            Some("tr") => Shape::Transform(transform(item)?),
+            _ => continue
+        };
+        result.push(shape);
+    }
+    Ok(result)
+}
+
+impl LayerTransform {
+    // The matrix and opacity (0 to 1) at a frame.
+    fn at(&self, frame: f32) -> (Transform2D, f32) {
+        let anchor = self.anchor.at(frame);
+        let position = self.position.at(frame);
+        let scale = self.scale.at(frame);
+        let component = |v: &[f32], i: usize, default: f32| v.get(i).cloned().unwrap_or(default);
+        let matrix = Transform2D::translation(component(&position, 0, 0f32), component(&position, 1, 0f32))
+            * Transform2D::rotation(self.rotation.at(frame).to_radians())
+            * Transform2D::scaling(component(&scale, 0, 100f32) / 100f32, component(&scale, 1, 100f32) / 100f32)
+            * Transform2D::translation(-component(&anchor, 0, 0f32), -component(&anchor, 1, 0f32));
+        (matrix, (self.opacity.at(frame) / 100f32).clamp(0f32, 1f32))
+    }
+}
+
+// Fill and stroke at a frame, opacities from 0 to 1.
+#[derive(Debug, Clone, Copy, Default)]
+struct Style {
+    fill: Option<([f32; 3], f32)>,
+    stroke: Option<([f32; 3], f32, f32)>
+}
+
+// A path and whether a fill or stroke was applied to it yet.
+type StyledPath = (Path, bool);
+
+fn rgb(color: &[f32]) -> [f32; 3] {
+    let component = |i: usize| color.get(i).cloned().unwrap_or(0f32).clamp(0f32, 1f32);
+    [component(0), component(1), component(2)]
+}
+
+// A color property at a frame, blended between keyframes in space.
+fn color_at(color: &Property<Vec<f32>>, frame: f32, space: ColorSpace) -> [f32; 3] {
+    rgb(&color.at_with(frame, |a, b, t| {
+        let (a, b) = (rgb(a), rgb(b));
+        let mixed = Color::rgb(a[0], a[1], a[2]).mix(&Color::rgb(b[0], b[1], b[2]), t, space);
+        vec![mixed.red, mixed.green, mixed.blue]
+    }))
+}
+
+fn bezier_path(shape: &Bezier) -> Option<Path> {
+    let n = shape.vertices.len();
+    if n < 2 {
+        return None;
+    }
+    let add = |p: (f32, f32), d: (f32, f32)| (p.0 + d.0, p.1 + d.1);
+    let segment = |path: Path, from: usize, to: usize| {
+        let (out, into) = (shape.out_tangents[from], shape.in_tangents[to]);
+        if out == (0f32, 0f32) && into == (0f32, 0f32) {
+            path.line_to(shape.vertices[to])
+        } else {
+            path.curve_to(add(shape.vertices[from], out), add(shape.vertices[to], into), shape.vertices[to])
+        }
+    };
+    let mut path = (1..n).fold(Path::new(shape.vertices[0]), |path, i| segment(path, i - 1, i));
+    if shape.closed {
+        path = segment(path, n - 1, 0).close_path();
+    }
+    Some(path)
+}
+
+fn ellipse_path(center: &[f32], size: &[f32]) -> Option<Path> {
+    let (cx, cy) = (*center.first()?, *center.get(1)?);
+    let (w, h) = (size.first()?.abs(), size.get(1)?.abs());
+    if w <= 0f32 || h <= 0f32 {
+        return None;
+    }
+    Some(Path::ellipse((cx, cy), w / 2f32, h / 2f32, 0f32))
+}
+
+fn rectangle_path(center: &[f32], size: &[f32], roundness: f32) -> Option<Path> {
+    let (cx, cy) = (*center.first()?, *center.get(1)?);
+    let (w, h) = (size.first()?.abs(), size.get(1)?.abs());
+    if w <= 0f32 || h <= 0f32 {
+        return None;
+    }
+    let r = roundness.clamp(0f32, w.min(h) / 2f32);
+    if r <= 0f32 {
+        return Some(Path::rectangle((cx, cy), w, h, 0f32));
+    }
+    let (left, right, top, bottom) = (cx - w / 2f32, cx + w / 2f32, cy - h / 2f32, cy + h / 2f32);
+    let k = r * (1f32 - KAPPA);
+    Some(Path::new((left + r, top))
+        .line_to((right - r, top))
+        .curve_to((right - k, top), (right, top + k), (right, top + r))
+        .line_to((right, bottom - r))
+        .curve_to((right, bottom - k), (right - k, bottom), (right - r, bottom))
+        .line_to((left + r, bottom))
+        .curve_to((left + k, bottom), (left, bottom - k), (left, bottom - r))
+        .line_to((left, top + r))
+        .curve_to((left, top + k), (left + k, top), (left + r, top))
+        .close_path())
+}
+
+// The paths of a group at a frame, topmost first. The group's first fill and stroke apply to
+// all its paths, including those of nested groups without styles of their own. Colors are
+// blended between keyframes in space.
+fn group_paths(shapes: &[Shape], frame: f32, parent: &Transform2D, opacity: f32, space: ColorSpace)
+    -> Vec<StyledPath> {
+    let mut transform = *parent;
+    let mut opacity = opacity;
+    for shape in shapes {
+        if let Shape::Transform(ref local) = *shape {
+            let (matrix, local_opacity) = local.at(frame);
+            transform = *parent * matrix;
+            opacity *= local_opacity;
+        }
+    }
+
+    let mut style = Style::default();
+    let mut paths = Vec::new();
+    for shape in shapes {
+        let geometry = match *shape {
+            Shape::Path(ref bezier) => bezier_path(&bezier.at(frame)),
+            Shape::Rectangle { ref position, ref size, ref roundness } =>
+                rectangle_path(&position.at(frame), &size.at(frame), roundness.at(frame)),
+            Shape::Ellipse { ref position, ref size } => ellipse_path(&position.at(frame), &size.at(frame)),
+            Shape::Group(ref items) => {
+                paths.extend(group_paths(items, frame, &transform, opacity, space));
+                None
+            }
+            Shape::Fill { ref color, opacity: ref fill_opacity } => {
+                if style.fill.is_none() {
+                    style.fill = Some((color_at(color, frame, space), fill_opacity.at(frame) / 100f32));
+                }
+                None
+            }
+            Shape::Stroke { ref color, opacity: ref stroke_opacity, ref width } => {
+                if style.stroke.is_none() {
+                    style.stroke = Some((color_at(color, frame, space), stroke_opacity.at(frame) / 100f32,
+                                         width.at(frame)));
+                }
+                None
+            }
+            Shape::Transform(_) => None
+        };
+        if let Some(path) = geometry {
+            paths.push((path.transformed(&transform), false));
+        }
+    }
+    if style.fill.is_none() && style.stroke.is_none() {
+        return paths;
+    }
+    paths.into_iter().map(|(path, styled)| {
+        if styled {
+            (path, true)
+        } else {
+            (apply_style(path, &style, &transform, opacity), true)
+        }
+    }).collect()
+}
+
+fn apply_style(mut path: Path, style: &Style, transform: &Transform2D, opacity: f32) -> Path {
+    if let Some((color, fill_opacity)) = style.fill {
+        path = path.set_fill_color(color[0], color[1], color[2]).set_fill_opacity(fill_opacity);
+    }
+    if let Some((color, stroke_opacity, width)) = style.stroke {
+        let thickness = (width * transform.scale_factor()).round().max(1f32) as u32;
+        path = path.set_stroke(color[0], color[1], color[2], thickness).set_stroke_opacity(stroke_opacity);
+    }
+    path.set_opacity(opacity)
+}
+
+impl Animation {
+    /// Parse an animation from Lottie JSON.
+    pub fn from_json(json: &str) -> Result<Animation, TrdlError> {
+        let root: Value = serde_json::from_str(json)?;
+        let dimension = |key: &str| number(&root[key]).ok_or_else(|| invalid("missing width, height or frame rate"));
+        let (width, height, frame_rate) = (dimension("w")?, dimension("h")?, dimension("fr")?);
+        let (in_point, out_point) = (number(&root["ip"]).unwrap_or(0f32), dimension("op")?);
+        if frame_rate <= 0f32 || out_point <= in_point {
+            return Err(invalid("the animation has no frames"));
+        }
+        let mut layers = Vec::new();
+        for layer in root["layers"].as_array().ok_or_else(|| invalid("missing layers"))? {
+            let kind = layer["ty"].as_i64();
+            // shape layers, and null layers that only parent others
+            if (kind != Some(4) && kind != Some(3)) || layer["hd"].as_bool() == Some(true) {
+                continue;
+            }
+            layers.push(Layer {
+                index: layer["ind"].as_i64(),
+                parent: layer["parent"].as_i64(),
+                in_point: number(&layer["ip"]).unwrap_or(in_point),
+                out_point: number(&layer["op"]).unwrap_or(out_point),
+                transform: transform(&layer["ks"])?,
+                shapes: if kind == Some(4) { shapes(&layer["shapes"])? } else { Vec::new() }
+            });
+        }
+        Ok(Animation { width, height, frame_rate, in_point, out_point, layers, color_space: ColorSpace::Srgb })
+    }
+
+    /// Width of the animation's frame.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// Height of the animation's frame.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Frames per second.
+    pub fn frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    /// The first frame.
+    pub fn in_point(&self) -> f32 {
+        self.in_point
+    }
+
+    /// The frame after the last one.
+    pub fn out_point(&self) -> f32 {
+        self.out_point
+    }
+
+    /// Length in seconds.
+    pub fn duration(&self) -> f32 {
+        (self.out_point - self.in_point) / self.frame_rate
+    }
+
+    /// Set the color space fill and stroke colors are blended in between keyframes, sRGB by
+    /// default as in most Lottie players.
+    pub fn set_color_space(&mut self, space: ColorSpace) {
+        self.color_space = space;
+    }
+
+    /// The color space colors are blended in between keyframes.
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// The frame shown `seconds` after the start, looping.
+    pub fn frame_at(&self, seconds: f32) -> f32 {
+        self.in_point + (seconds * self.frame_rate).rem_euclid(self.out_point - self.in_point)
+    }
+
+    /// The paths of a frame in drawing coordinates, bottom to top. The animation's frame has its
+    /// lower left corner at the origin, y up.
+    pub fn paths_at(&self, frame: f32) -> Vec<Path> {
+        let flip = Transform2D { d: -1f32, f: self.height, ..Transform2D::identity() };
+        let by_index: HashMap<i64, &Layer> = self.layers.iter()
+            .filter_map(|layer| layer.index.map(|index| (index, layer)))
+            .collect();
+        let mut paths = Vec::new();
+        for layer in self.layers.iter().rev() {
+            if frame < layer.in_point || frame >= layer.out_point || layer.shapes.is_empty() {
+                continue;
+            }
+            let (mut matrix, opacity) = layer.transform.at(frame);
+            // parents move their children but do not fade them, stop at cycles
+            let mut parent = layer.parent;
+            let mut depth = 0;
+            while let Some(ancestor) = parent.and_then(|index| by_index.get(&index)) {
+                matrix = ancestor.transform.at(frame).0 * matrix;
+                parent = ancestor.parent;
+                depth += 1;
+                if depth > self.layers.len() {
+                    break;
+                }
+            }
+            let layer_paths = group_paths(&layer.shapes, frame, &(flip * matrix), opacity, self.color_space);
+            // paths with no style anywhere are not drawn by Lottie players either
+            paths.extend(layer_paths.into_iter().rev().filter(|&(_, styled)| styled).map(|(path, _)| path));
+        }
+        paths
+    }
+}
+
+/// Shows an animation in a drawing, keeping the paths of the current frame in it.
+pub struct LottiePlayer {
+    animation: Animation,
+    transform: Transform2D,
+    ids: Vec<PathId>
+}
+
+impl LottiePlayer {
+    /// Constructor, the animation is placed with its lower left corner at the origin.
+    pub fn new(animation: Animation) -> Self {
+        LottiePlayer { animation, transform: Transform2D::identity(), ids: Vec::new() }
+    }
+
+    /// The animation.
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// Place the animation in the drawing, applies from the next update.
+    pub fn set_transform(&mut self, transform: Transform2D) {
+        self.transform = transform;
+    }
+
+    /// The ids of the paths showing the current frame, bottom to top.
+    pub fn path_ids(&self) -> &[PathId] {
+        &self.ids
+    }
+
+    /// Show the frame at `seconds` from the start, looping. Paths are updated in place so the
+    /// animation keeps its place among the drawing's other paths.
+    pub fn update<W: Window>(&mut self, drawing: &mut Drawing<'_, W>, seconds: f32) -> Result<(), TrdlError> {
+        let frame = self.animation.frame_at(seconds);
+        self.show_frame(drawing, frame)
+    }
+
+    /// Show the frame at the drawing's animation time, so the animation follows `Drawing::set_time`.
+    pub fn play<W: Window>(&mut self, drawing: &mut Drawing<'_, W>) -> Result<(), TrdlError> {
+        let seconds = drawing.time();
+        self.update(drawing, seconds)
+    }
+
+    /// Show a frame by number.
+    pub fn show_frame<W: Window>(&mut self, drawing: &mut Drawing<'_, W>, frame: f32) -> Result<(), TrdlError> {
+        let paths: Vec<Path> = self.animation.paths_at(frame).iter()
+            .map(|path| path.transformed(&self.transform))
+            .collect();
+        while self.ids.len() > paths.len() {
+            let id = self.ids.pop().unwrap();
+            drawing.remove_path(id);
+        }
+        for (i, path) in paths.into_iter().enumerate() {
+            if i < self.ids.len() {
+                drawing.update_path(self.ids[i], path)?;
+            } else {
+                self.ids.push(drawing.add_path(path)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the animation's paths out of the drawing.
+    pub fn clear<W: Window>(&mut self, drawing: &mut Drawing<'_, W>) {
+        for id in self.ids.drain(..) {
+            drawing.remove_path(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ease, Animation};
+    use super::super::color::ColorSpace;
+    use super::super::gl2d::drawing::Path;
+    use super::super::transform::Transform2D;
+
+    // A 100x100 animation of a red square moving right over 10 frames, with a stroked circle
+    // in a nested group above it.
+    const SQUARE: &str = r#"{
+        "v": "5.7.0", "w": 100, "h": 100, "fr": 10, "ip": 0, "op": 10,
+        "layers": [{
+            "ty": 4, "ind": 1, "ip": 0, "op": 10,
+            "ks": {"p": {"a": 1, "k": [
+                {"t": 0, "s": [20, 20], "o": {"x": 0, "y": 0}, "i": {"x": 1, "y": 1}},
+                {"t": 10, "s": [80, 20]}
+            ]}},
+            "shapes": [
+                {"ty": "gr", "it": [
+                    {"ty": "el", "p": {"a": 0, "k": [0, 0]}, "s": {"a": 0, "k": [10, 10]}},
+                    {"ty": "st", "c": {"a": 0, "k": [0, 0, 1, 1]}, "o": {"a": 0, "k": 100},
+                     "w": {"a": 0, "k": 2}},
+                    {"ty": "tr", "p": {"a": 0, "k": [0, 30]}}
+                ]},
+                {"ty": "rc", "p": {"a": 0, "k": [0, 0]}, "s": {"a": 0, "k": [10, 10]},
+                 "r": {"a": 0, "k": 0}},
+                {"ty": "fl", "c": {"a": 0, "k": [1, 0, 0, 1]}, "o": {"a": 0, "k": 50}}
+            ]
+        }]
+    }"#;
+
+    #[test]
+    fn test_keyframes_and_layout() {
+        let animation = Animation::from_json(SQUARE).unwrap();
+        assert_eq!(animation.duration(), 1f32);
+        let paths = animation.paths_at(0f32);
+        assert_eq!(paths.len(), 2);
+        // the square is drawn first and the circle over it, moved by the layer and y flipped
+        let layer = Transform2D { d: -1f32, e: 20f32, f: 80f32, ..Transform2D::identity() };
+        assert_eq!(paths[0], Path::rectangle((0f32, 0f32), 10f32, 10f32, 0f32).transformed(&layer)
+            .set_fill_color(1f32, 0f32, 0f32).set_fill_opacity(0.5f32));
+        let circle = layer * Transform2D::translation(0f32, 30f32);
+        assert_eq!(paths[1], Path::ellipse((0f32, 0f32), 5f32, 5f32, 0f32).transformed(&circle)
+            .set_stroke(0f32, 0f32, 1f32, 2));
+
+        let ((x0, y0), (x1, y1)) = animation.paths_at(5f32)[0].bounds();
+        assert!((x0 - 45f32).abs() < 1e-2 && (x1 - 55f32).abs() < 1e-2);
+        assert_eq!((y0, y1), (75f32, 85f32));
+        assert_eq!(animation.frame_at(1.5f32), 5f32);
+    }
+
+    #[test]
+    fn test_color_space() {
+        // a square fading from red to green over 10 frames
+        let json = r#"{
+            "w": 10, "h": 10, "fr": 10, "op": 10,
+            "layers": [{"ty": 4, "ks": {}, "shapes": [
+                {"ty": "rc", "p": {"a": 0, "k": [5, 5]}, "s": {"a": 0, "k": [10, 10]}, "r": {"a": 0, "k": 0}},
+                {"ty": "fl", "o": {"a": 0, "k": 100}, "c": {"a": 1, "k": [
+                    {"t": 0, "s": [1, 0, 0, 1], "o": {"x": 0, "y": 0}, "i": {"x": 1, "y": 1}},
+                    {"t": 10, "s": [0, 1, 0, 1]}
+                ]}}
+            ]}]
+        }"#;
+        let mut animation = Animation::from_json(json).unwrap();
+        let close = |a: [f32; 3], b: [f32; 3]| a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-3);
+        // halfway it is a dull olive blended in sRGB and a full yellow blended in HSL
+        assert!(close(animation.paths_at(5f32)[0].fill().unwrap(), [0.5f32, 0.5f32, 0f32]));
+        animation.set_color_space(ColorSpace::Hsl);
+        assert!(close(animation.paths_at(5f32)[0].fill().unwrap(), [1f32, 1f32, 0f32]));
+    }
+
+    #[test]
+    fn test_easing() {
+        assert!((ease((0f32, 0f32), (1f32, 1f32), 0.5f32) - 0.5f32).abs() < 1e-3);
+        // ease in and out is slow at the ends and symmetric
+        let slow = ease((0.42f32, 0f32), (0.58f32, 1f32), 0.1f32);
+        assert!(slow < 0.1f32);
+        assert!((ease((0.42f32, 0f32), (0.58f32, 1f32), 0.5f32) - 0.5f32).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert!(Animation::from_json("not json").is_err());
+        assert!(Animation::from_json(r#"{"w": 10, "h": 10, "fr": 30, "op": 0, "layers": []}"#).is_err());
+        assert!(Animation::from_json(r#"{"w": 10, "h": 10, "fr": 30, "op": 5}"#).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! Measuring distances along paths.
+
+use super::bezier::Segment;
+
+// samples per segment in an arc length table
+const SAMPLES: usize = 32;
+
+/// A point on a path with the direction of travel there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathPoint {
+    pub position: (f32, f32),
+    /// Unit vector in the direction of travel.
+    pub tangent: (f32, f32),
+    /// Unit vector a quarter turn counter clockwise from the tangent.
+    pub normal: (f32, f32)
+}
+
+impl PathPoint {
+    pub(crate) fn new(position: (f32, f32), tangent: (f32, f32)) -> Self {
+        PathPoint { position, tangent, normal: (-tangent.1, tangent.0) }
+    }
+}
+
+/// Distances along a path sampled at regular parameter steps, made by `Path::arc_lengths`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArcLengthTable {
+    start: (f32, f32),
+    segments: Vec<Segment>,
+    // distance from the start of the path to each sample, SAMPLES + 1 per segment
+    lengths: Vec<f32>
+}
+
+impl ArcLengthTable {
+    pub(crate) fn new(start: (f32, f32), segments: Vec<Segment>) -> Self {
+        let mut lengths = Vec::with_capacity(segments.len() * (SAMPLES + 1));
+        let mut total = 0f32;
+        for segment in &segments {
+            let mut last = segment.point_at(0f32);
+            lengths.push(total);
+            for i in 1..=SAMPLES {
+                let p = segment.point_at(i as f32 / SAMPLES as f32);
+                total += (p.0 - last.0).hypot(p.1 - last.1);
+                lengths.push(total);
+                last = p;
+            }
+        }
+        ArcLengthTable { start, segments, lengths }
+    }
+
+    /// The length of the whole path.
+    pub fn total_length(&self) -> f32 {
+        self.lengths.last().cloned().unwrap_or(0f32)
+    }
+
+    // The segments of the path.
+    pub(crate) fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    // The distance along the path to the start and the end of one of its segments.
+    pub(crate) fn segment_range(&self, segment: usize) -> (f32, f32) {
+        let base = segment * (SAMPLES + 1);
+        (self.lengths[base], self.lengths[base + SAMPLES])
+    }
+
+    // The parameter of the point at distance s along the path on one of its segments, clamped to
+    // the segment.
+    pub(crate) fn parameter_at(&self, segment: usize, s: f32) -> f32 {
+        let base = segment * (SAMPLES + 1);
+        let lengths = &self.lengths[base..base + SAMPLES + 1];
+        let s = s.clamp(lengths[0], lengths[SAMPLES]);
+        let step = lengths.partition_point(|&length| length <= s).saturating_sub(1).min(SAMPLES - 1);
+        let (l0, l1) = (lengths[step], lengths[step + 1]);
+        let fraction = if l1 > l0 { ((s - l0) / (l1 - l0)).clamp(0f32, 1f32) } else { 0f32 };
+        (step as f32 + fraction) / SAMPLES as f32
+    }
+
+    /// The point at distance s along the path, clamped to the ends.
+    pub fn point_at_length(&self, s: f32) -> PathPoint {
+        if self.segments.is_empty() {
+            return PathPoint::new(self.start, (1f32, 0f32));
+        }
+        let s = s.clamp(0f32, self.total_length());
+        // the last sample at or before s
+        let index = self.lengths.partition_point(|&length| length <= s).saturating_sub(1);
+        let segment = (index / (SAMPLES + 1)).min(self.segments.len() - 1);
+        let step = (index % (SAMPLES + 1)).min(SAMPLES - 1);
+        let base = segment * (SAMPLES + 1) + step;
+        let (l0, l1) = (self.lengths[base], self.lengths[base + 1]);
+        let fraction = if l1 > l0 { ((s - l0) / (l1 - l0)).clamp(0f32, 1f32) } else { 0f32 };
+        let t = (step as f32 + fraction) / SAMPLES as f32;
+        let segment = &self.segments[segment];
+        PathPoint::new(segment.point_at(t), segment.direction_at(t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcLengthTable;
+    use super::super::bezier::Segment;
+
+    #[test]
+    fn test_even_spacing_on_curve() {
+        // a curve that moves slowly at the start, parameter steps are not evenly spaced
+        let curve = Segment::Cubic((0f32, 0f32), (0f32, 0f32), (0f32, 0f32), (30f32, 0f32));
+        let table = ArcLengthTable::new((0f32, 0f32), vec![curve]);
+        assert!((table.total_length() - 30f32).abs() < 1e-3);
+        let p = table.point_at_length(15f32);
+        assert!((p.position.0 - 15f32).abs() < 0.2f32);
+        assert_eq!(p.tangent, (1f32, 0f32));
+        assert_eq!(table.point_at_length(100f32).position, (30f32, 0f32));
+    }
+}
@@ -0,0 +1,86 @@
+//! Triangle meshes, the output of triangulation.
+
+use super::transform::Transform2D;
+use super::triangulation::{triangulate, triangulate_with_tolerance};
+use super::TrdlError;
+
+/// Triangles sharing a list of vertices. Every three indices into `vertices` make a triangle,
+/// wound counter clockwise when the mesh comes from a counter clockwise polygon.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<(f32, f32)>,
+    pub indices: Vec<usize>
+}
+
+impl Mesh {
+    /// Constructor.
+    pub fn new(vertices: Vec<(f32, f32)>, indices: Vec<usize>) -> Self {
+        Mesh { vertices, indices }
+    }
+
+    /// Triangulate a simple polygon with counter clockwise winding.
+    pub fn from_polygon(points: &[(f32, f32)]) -> Result<Self, TrdlError> {
+        triangulate(points)
+    }
+
+    /// Triangulate a simple polygon with counter clockwise winding, treating points closer than
+    /// `tolerance` as the same and dropping vertices within it of the line between their
+    /// neighbours. `from_polygon` uses a millionth of the polygon's size, which may be too fine or
+    /// too coarse for polygons whose details are far smaller than they are.
+    pub fn from_polygon_with_tolerance(points: &[(f32, f32)], tolerance: f32) -> Result<Self, TrdlError> {
+        triangulate_with_tolerance(points, tolerance)
+    }
+
+    /// The number of triangles.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// The corners of each triangle.
+    pub fn triangles(&self) -> impl Iterator<Item = [(f32, f32); 3]> + '_ {
+        self.indices.chunks(3).map(move |t| [self.vertices[t[0]], self.vertices[t[1]], self.vertices[t[2]]])
+    }
+
+    /// Add the triangles of another mesh to this one.
+    pub fn merge(&mut self, other: &Mesh) {
+        let offset = self.vertices.len();
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|&i| i + offset));
+    }
+
+    /// Move every vertex by a transform.
+    pub fn transform(&mut self, transform: &Transform2D) {
+        for vertex in &mut self.vertices {
+            *vertex = transform.apply(*vertex);
+        }
+    }
+
+    /// The total area of the triangles.
+    pub fn area(&self) -> f32 {
+        self.triangles().map(|[a, b, c]| {
+            ((b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1)).abs() / 2f32
+        }).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mesh;
+    use super::super::transform::Transform2D;
+
+    #[test]
+    fn test_merge_transform_area() {
+        let square = Mesh::from_polygon(&[(0f32, 0f32), (1f32, 0f32), (1f32, 1f32), (0f32, 1f32)]).unwrap();
+        assert_eq!(square.area(), 1f32);
+        let square = Mesh::new(square.vertices, vec![0, 1, 2, 0, 2, 3]);
+        let mut other = square.clone();
+        other.transform(&(Transform2D::translation(5f32, 0f32) * Transform2D::scaling(2f32, 3f32)));
+        assert_eq!(other.vertices[2], (7f32, 3f32));
+        assert_eq!(other.area(), 6f32);
+        let mut both = square.clone();
+        both.merge(&other);
+        assert_eq!(both.triangle_count(), 4);
+        assert_eq!(&both.indices[6..], &[4, 5, 6, 4, 6, 7]);
+        assert_eq!(both.area(), 7f32);
+    }
+}
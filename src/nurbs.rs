@@ -0,0 +1,183 @@
+//! Conversion of B-splines and NURBS curves to the cubic Bezier curves paths are made of.
+
+use super::TrdlError;
+
+// A point in homogeneous coordinates: x and y multiplied by the weight, and the weight.
+type Homogeneous = [f64; 3];
+
+/// The control points of a cubic Bezier curve, start to end.
+pub type Cubic = [(f32, f32); 4];
+
+// rational segments are split into this many cubics
+const RATIONAL_PIECES: usize = 4;
+
+/// Convert a NURBS curve to cubic Bezier curves. The degree is knots.len() - points.len() - 1 and
+/// must be 1, 2 or 3. Each knot span of the curve's domain becomes one Bezier curve, or several
+/// approximating ones when the weights on it differ.
+pub fn to_cubics(points: &[(f32, f32)], knots: &[f32], weights: &[f32]) -> Result<Vec<Cubic>, TrdlError> {
+    let n = points.len();
+    if knots.len() < n + 2 || weights.len() != n {
+        return Err(TrdlError::InvalidNurbs);
+    }
+    let degree = knots.len() - n - 1;
+    if degree > 3 || n <= degree || knots.windows(2).any(|k| k[1] < k[0]) ||
+       weights.iter().any(|&w| w <= 0f32 || !w.is_finite()) {
+        return Err(TrdlError::InvalidNurbs);
+    }
+    let (start, end) = (knots[degree] as f64, knots[n] as f64);
+    if start >= end {
+        return Err(TrdlError::InvalidNurbs);
+    }
+
+    let mut knots: Vec<f64> = knots.iter().map(|&k| k as f64).collect();
+    let mut control: Vec<Homogeneous> = points.iter().zip(weights)
+        .map(|(p, &w)| [p.0 as f64 * w as f64, p.1 as f64 * w as f64, w as f64])
+        .collect();
+
+    // insert every knot of the domain until it has multiplicity degree, the control points of
+    // each span are then the control points of a Bezier curve
+    let mut values: Vec<f64> = knots.iter().cloned().filter(|&u| u >= start && u <= end).collect();
+    values.dedup();
+    for u in values {
+        while multiplicity(&knots, u) < degree {
+            if !insert_knot(&mut knots, &mut control, degree, u) {
+                break;
+            }
+        }
+    }
+
+    let mut cubics = Vec::new();
+    for k in degree..control.len() {
+        if knots[k] < knots[k + 1] && knots[k] >= start && knots[k + 1] <= end {
+            let segment = elevate(&control[k - degree..k + 1]);
+            push_rational(&segment, &mut cubics);
+        }
+    }
+    Ok(cubics)
+}
+
+fn multiplicity(knots: &[f64], u: f64) -> usize {
+    knots.iter().filter(|&&k| k == u).count()
+}
+
+// Boehm's knot insertion, returns false if u is not inside a knot span.
+fn insert_knot(knots: &mut Vec<f64>, control: &mut Vec<Homogeneous>, degree: usize, u: f64) -> bool {
+    let span = match (0..knots.len() - 1).rev().find(|&k| knots[k] <= u && u < knots[k + 1]) {
+        Some(span) => span,
+        None => return false
+    };
+    let s = multiplicity(knots, u);
+    if span < degree {
+        return false;
+    }
+    let mut new_control = Vec::with_capacity(control.len() + 1);
+    for i in 0..control.len() + 1 {
+        if i + degree <= span {
+            new_control.push(control[i]);
+        } else if i + s <= span {
+            let a = (u - knots[i]) / (knots[i + degree] - knots[i]);
+            let (p, q) = (control[i - 1], control[i]);
+            new_control.push([(1f64 - a) * p[0] + a * q[0],
+                              (1f64 - a) * p[1] + a * q[1],
+                              (1f64 - a) * p[2] + a * q[2]]);
+        } else {
+            new_control.push(control[i - 1]);
+        }
+    }
+    knots.insert(span + 1, u);
+    *control = new_control;
+    true
+}
+
+// Raise a Bezier curve of degree 1 to 3 to degree 3.
+fn elevate(points: &[Homogeneous]) -> [Homogeneous; 4] {
+    let mix = |a: Homogeneous, b: Homogeneous, t: f64| {
+        [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]
+    };
+    match points.len() {
+        2 => [points[0], mix(points[0], points[1], 1f64 / 3f64),
+              mix(points[0], points[1], 2f64 / 3f64), points[1]],
+        3 => [points[0], mix(points[0], points[1], 2f64 / 3f64),
+              mix(points[1], points[2], 1f64 / 3f64), points[2]],
+        _ => [points[0], points[1], points[2], points[3]]
+    }
+}
+
+// Add a rational cubic Bezier curve, exactly if its weights are all the same, otherwise as
+// several cubics matching its points and tangents at their ends.
+fn push_rational(segment: &[Homogeneous; 4], cubics: &mut Vec<Cubic>) {
+    let project = |p: Homogeneous| ((p[0] / p[2]) as f32, (p[1] / p[2]) as f32);
+    if segment.iter().all(|p| p[2] == segment[0][2]) {
+        cubics.push([project(segment[0]), project(segment[1]), project(segment[2]),
+                     project(segment[3])]);
+        return;
+    }
+    let h = 1f64 / RATIONAL_PIECES as f64;
+    for i in 0..RATIONAL_PIECES {
+        let (t0, t1) = (i as f64 * h, (i + 1) as f64 * h);
+        let (p0, d0) = rational_point(segment, t0);
+        let (p1, d1) = rational_point(segment, t1);
+        cubics.push([(p0.0 as f32, p0.1 as f32),
+                     ((p0.0 + d0.0 * h / 3f64) as f32, (p0.1 + d0.1 * h / 3f64) as f32),
+                     ((p1.0 - d1.0 * h / 3f64) as f32, (p1.1 - d1.1 * h / 3f64) as f32),
+                     (p1.0 as f32, p1.1 as f32)]);
+    }
+}
+
+// Point and derivative of a rational cubic Bezier curve at t.
+fn rational_point(p: &[Homogeneous; 4], t: f64) -> ((f64, f64), (f64, f64)) {
+    let mt = 1f64 - t;
+    let basis = [mt * mt * mt, 3f64 * mt * mt * t, 3f64 * mt * t * t, t * t * t];
+    let derivative = [-3f64 * mt * mt, 3f64 * mt * mt - 6f64 * mt * t, 6f64 * mt * t - 3f64 * t * t,
+                      3f64 * t * t];
+    let mut a = [0f64; 3];
+    let mut da = [0f64; 3];
+    for i in 0..4 {
+        for j in 0..3 {
+            a[j] += basis[i] * p[i][j];
+            da[j] += derivative[i] * p[i][j];
+        }
+    }
+    let point = (a[0] / a[2], a[1] / a[2]);
+    let tangent = ((da[0] - da[2] * point.0) / a[2], (da[1] - da[2] * point.1) / a[2]);
+    (point, tangent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_cubics;
+    use super::super::bezier;
+
+    #[test]
+    fn test_quarter_circle() {
+        let w = 0.5f32.sqrt();
+        let cubics = to_cubics(&[(1f32, 0f32), (1f32, 1f32), (0f32, 1f32)],
+                               &[0f32, 0f32, 0f32, 1f32, 1f32, 1f32], &[1f32, w, 1f32]).unwrap();
+        for c in &cubics {
+            for i in 0..=8 {
+                let p = bezier::point_at(c[0], c[1], c[2], c[3], i as f32 / 8f32);
+                assert!((p.0.hypot(p.1) - 1f32).abs() < 1e-3);
+            }
+        }
+        assert_eq!(cubics[0][0], (1f32, 0f32));
+        assert_eq!(cubics[cubics.len() - 1][3], (0f32, 1f32));
+    }
+
+    #[test]
+    fn test_uniform_cubic_b_spline() {
+        let points = [(0f32, 0f32), (1f32, 2f32), (3f32, 2f32), (4f32, 0f32), (6f32, 1f32)];
+        let knots = [0f32, 1f32, 2f32, 3f32, 4f32, 5f32, 6f32, 7f32, 8f32];
+        let cubics = to_cubics(&points, &knots, &[1f32; 5]).unwrap();
+        // the domain [3, 5] has two spans
+        assert_eq!(cubics.len(), 2);
+        // joined end to end, starting at (P0 + 4 P1 + P2) / 6
+        assert_eq!(cubics[0][3], cubics[1][0]);
+        assert!((cubics[0][0].0 - 7f32 / 6f32).abs() < 1e-5 && (cubics[0][0].1 - 10f32 / 6f32).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(to_cubics(&[(0f32, 0f32), (1f32, 1f32)], &[0f32, 1f32, 0f32, 1f32], &[1f32, 1f32]).is_err());
+        assert!(to_cubics(&[(0f32, 0f32), (1f32, 1f32)], &[0f32, 0f32, 1f32, 1f32], &[1f32]).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Particle effects: an `Emitter` spawns and moves particles, a `ParticleRenderer` draws them
+//! with instancing after the drawing.
+
+use std::f32::consts::PI;
+use super::color::Color;
+
+pub use super::gl2d::particle_renderer::ParticleRenderer;
+
+/// The shape each particle is drawn as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleShape {
+    Circle,
+    Square
+}
+
+/// How an emitter spawns particles. Ranges are (min, max), each new particle picks uniformly
+/// from them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmitterConfig {
+    /// Where particles start, in drawing coordinates.
+    pub position: (f32, f32),
+    /// Particles spawned per second.
+    pub spawn_rate: f32,
+    /// Seconds a particle lives.
+    pub lifetime: (f32, f32),
+    /// Direction particles move in, radians counter clockwise from the x axis.
+    pub direction: (f32, f32),
+    /// Starting speed, in drawing units per second.
+    pub speed: (f32, f32),
+    /// Acceleration applied to every particle.
+    pub gravity: (f32, f32),
+    /// Particle diameter.
+    pub size: (f32, f32),
+    /// A particle's color is picked between these two.
+    pub color: (Color, Color),
+    /// Fade particles out over their lifetime.
+    pub fade_out: bool,
+    pub shape: ParticleShape,
+    /// No particles are spawned while this many are alive.
+    pub max_particles: usize
+}
+
+impl Default for EmitterConfig {
+    fn default() -> Self {
+        let white = Color::rgb(1f32, 1f32, 1f32);
+        EmitterConfig {
+            position: (0f32, 0f32),
+            spawn_rate: 50f32,
+            lifetime: (1f32, 2f32),
+            direction: (0f32, 2f32 * PI),
+            speed: (20f32, 60f32),
+            gravity: (0f32, 0f32),
+            size: (4f32, 8f32),
+            color: (white, white),
+            fade_out: true,
+            shape: ParticleShape::Circle,
+            max_particles: 10_000
+        }
+    }
+}
+
+/// A single live particle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    pub size: f32,
+    pub color: Color,
+    /// Seconds since the particle was spawned.
+    pub age: f32,
+    pub lifetime: f32
+}
+
+impl Particle {
+    /// How far through its life the particle is, from 0 to 1.
+    pub fn life_fraction(&self) -> f32 {
+        (self.age / self.lifetime).min(1f32)
+    }
+}
+
+/// Spawns, moves and retires particles.
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    config: EmitterConfig,
+    particles: Vec<Particle>,
+    // fractional particles owed from previous updates
+    spawn_debt: f32,
+    emitting: bool,
+    rng: XorShift
+}
+
+impl Emitter {
+    /// Constructor, the seed makes the randomness repeatable.
+    pub fn new(config: EmitterConfig, seed: u64) -> Self {
+        Emitter { config, particles: Vec::new(), spawn_debt: 0f32, emitting: true,
+                  rng: XorShift::new(seed) }
+    }
+
+    /// The configuration.
+    pub fn config(&self) -> &EmitterConfig {
+        &self.config
+    }
+
+    /// The configuration, mutably, for example to move the emitter.
+    pub fn config_mut(&mut self) -> &mut EmitterConfig {
+        &mut self.config
+    }
+
+    /// The live particles.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Start or stop continuous spawning, live particles carry on either way.
+    pub fn set_emitting(&mut self, emitting: bool) {
+        self.emitting = emitting;
+        self.spawn_debt = 0f32;
+    }
+
+    /// Spawn count particles at once.
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count {
+            self.spawn();
+        }
+    }
+
+    /// Advance the simulation by dt seconds.
+    pub fn update(&mut self, dt: f32) {
+        let gravity = self.config.gravity;
+        for particle in &mut self.particles {
+            particle.velocity.0 += gravity.0 * dt;
+            particle.velocity.1 += gravity.1 * dt;
+            particle.position.0 += particle.velocity.0 * dt;
+            particle.position.1 += particle.velocity.1 * dt;
+            particle.age += dt;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+
+        if self.emitting {
+            self.spawn_debt += self.config.spawn_rate * dt;
+            while self.spawn_debt >= 1f32 {
+                self.spawn_debt -= 1f32;
+                self.spawn();
+            }
+        }
+    }
+
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.config.max_particles {
+            return;
+        }
+        let config = &self.config;
+        let rng = &mut self.rng;
+        let angle = rng.in_range(config.direction);
+        let speed = rng.in_range(config.speed);
+        let t = rng.next_f32();
+        let (c0, c1) = config.color;
+        let color = Color::rgba(c0.red + (c1.red - c0.red) * t,
+                                c0.green + (c1.green - c0.green) * t,
+                                c0.blue + (c1.blue - c0.blue) * t,
+                                c0.alpha + (c1.alpha - c0.alpha) * t);
+        let particle = Particle {
+            position: config.position,
+            velocity: (speed * angle.cos(), speed * angle.sin()),
+            size: rng.in_range(config.size),
+            color,
+            age: 0f32,
+            lifetime: rng.in_range(config.lifetime).max(1e-3f32)
+        };
+        self.particles.push(particle);
+    }
+}
+
+// Small fast random numbers, good enough for effects.
+#[derive(Debug, Clone)]
+struct XorShift {
+    state: u64
+}
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // the state must never be zero
+        XorShift { state: seed ^ 0x9E37_79B9_7F4A_7C15 | 1 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    // uniform in [0, 1)
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn in_range(&mut self, range: (f32, f32)) -> f32 {
+        range.0 + (range.1 - range.0) * self.next_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Emitter, EmitterConfig};
+
+    #[test]
+    fn test_spawn_rate_and_lifetime() {
+        let config = EmitterConfig { spawn_rate: 10f32, lifetime: (1f32, 1f32), ..EmitterConfig::default() };
+        let mut emitter = Emitter::new(config, 1);
+        for _ in 0..5 {
+            emitter.update(0.1f32);
+        }
+        assert_eq!(emitter.particles().len(), 5);
+        // the first particles have lived a full second after 10 more steps
+        for _ in 0..10 {
+            emitter.update(0.1f32);
+        }
+        assert!(emitter.particles().len() <= 10);
+    }
+
+    #[test]
+    fn test_gravity_and_limit() {
+        let config = EmitterConfig { speed: (0f32, 0f32), gravity: (0f32, -10f32), max_particles: 3,
+                                     lifetime: (5f32, 5f32), ..EmitterConfig::default() };
+        let mut emitter = Emitter::new(config, 7);
+        emitter.set_emitting(false);
+        emitter.burst(5);
+        assert_eq!(emitter.particles().len(), 3);
+        emitter.update(1f32);
+        let particle = emitter.particles()[0];
+        assert_eq!(particle.velocity, (0f32, -10f32));
+        assert_eq!(particle.position, (0f32, -10f32));
+    }
+
+    #[test]
+    fn test_same_seed_same_particles() {
+        let mut a = Emitter::new(EmitterConfig::default(), 42);
+        let mut b = Emitter::new(EmitterConfig::default(), 42);
+        a.burst(10);
+        b.burst(10);
+        assert_eq!(a.particles(), b.particles());
+    }
+}
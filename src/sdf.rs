@@ -0,0 +1,292 @@
+//! Signed distance fields, for text and icons that stay crisp at any zoom. An `SdfAtlas` turns
+//! paths into distance fields, one layer of a texture array each, and an `SdfRenderer` draws them
+//! as quads after the drawing, with optional outlines and soft shadows.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "font")]
+use std::collections::HashMap;
+use super::color::Color;
+use super::gl2d::drawing::Path;
+#[cfg(feature = "font")]
+use super::font::Font;
+use super::TrdlError;
+
+pub use super::gl2d::sdf_renderer::SdfRenderer;
+
+// curves are flattened to within this fraction of a texel
+const FLATTEN_TEXELS: f32 = 0.25f32;
+
+// tells atlases apart, so a renderer knows when it is given a different one
+static NEXT_ATLAS: AtomicUsize = AtomicUsize::new(0);
+
+/// Id of a distance field in an `SdfAtlas`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SdfId(pub(crate) usize);
+
+/// The area a distance field covers, in the units of the paths it was made from. It is the
+/// paths' bounds with room around them for the distances outside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfEntry {
+    pub min: (f32, f32),
+    pub max: (f32, f32)
+}
+
+/// Square distance fields of the same size, one per layer of a texture array.
+#[derive(Debug, Clone)]
+pub struct SdfAtlas {
+    id: usize,
+    version: usize,
+    cell_size: u32,
+    spread: f32,
+    texels: Vec<u8>,
+    entries: Vec<SdfEntry>
+}
+
+impl SdfAtlas {
+    /// An empty atlas of cell_size by cell_size fields storing distances up to spread texels
+    /// either side of an edge. 64 with a spread of 8 suits glyphs. Outlines, shadows and zooming
+    /// out far all need distances further from the edge, so they are limited by the spread.
+    pub fn new(cell_size: u32, spread: f32) -> Self {
+        SdfAtlas {
+            id: NEXT_ATLAS.fetch_add(1, Ordering::Relaxed),
+            version: 0,
+            cell_size: cell_size.max(4),
+            spread: spread.clamp(1f32, cell_size.max(4) as f32 / 4f32),
+            texels: Vec::new(),
+            entries: Vec::new()
+        }
+    }
+
+    /// Width and height of each field in texels.
+    pub fn cell_size(&self) -> u32 {
+        self.cell_size
+    }
+
+    /// How far from an edge, in texels, distances are stored.
+    pub fn spread(&self) -> f32 {
+        self.spread
+    }
+
+    /// The number of fields.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no fields.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Where a field is, None for ids from other atlases.
+    pub fn entry(&self, id: SdfId) -> Option<&SdfEntry> {
+        self.entries.get(id.0)
+    }
+
+    /// The texels of a field, rows from bottom to top. 128 is on the edge, more is inside and
+    /// 255 or 0 are spread texels or further in or out.
+    pub fn texels(&self, id: SdfId) -> Option<&[u8]> {
+        let size = (self.cell_size * self.cell_size) as usize;
+        self.entries.get(id.0).map(|_| &self.texels[id.0 * size..(id.0 + 1) * size])
+    }
+
+    /// Make a field of the area inside any of paths, which should be closed. Paths are scaled to
+    /// fill the cell, apart from a border of spread texels.
+    pub fn add_paths(&mut self, paths: &[Path]) -> Result<SdfId, TrdlError> {
+        let (min, max) = match paths.iter().map(Path::bounds).reduce(|a, b| {
+            (((a.0).0.min((b.0).0), (a.0).1.min((b.0).1)), ((a.1).0.max((b.1).0), (a.1).1.max((b.1).1)))
+        }) {
+            Some(bounds) => bounds,
+            None => return Err(TrdlError::NoVisibleGeometry)
+        };
+        let size = (max.0 - min.0).max(max.1 - min.1);
+        if size <= 0f32 || !size.is_finite() {
+            return Err(TrdlError::ZeroAreaPolygon);
+        }
+        let cell = self.cell_size as f32;
+        let units_per_texel = size / (cell - 2f32 * self.spread);
+        let center = ((min.0 + max.0) / 2f32, (min.1 + max.1) / 2f32);
+        let half = cell * units_per_texel / 2f32;
+        let entry = SdfEntry { min: (center.0 - half, center.1 - half), max: (center.0 + half, center.1 + half) };
+
+        let polygons: Vec<Vec<(f32, f32)>> = paths.iter().map(|path| path.flatten(FLATTEN_TEXELS * units_per_texel)).collect();
+        let edges = outline_edges(&polygons);
+        for y in 0..self.cell_size {
+            for x in 0..self.cell_size {
+                let point = (entry.min.0 + (x as f32 + 0.5f32) * units_per_texel,
+                             entry.min.1 + (y as f32 + 0.5f32) * units_per_texel);
+                let distance = edges.iter().map(|&(a, b)| segment_distance(point, a, b)).fold(f32::MAX, f32::min) / units_per_texel;
+                let signed = if polygons.iter().any(|polygon| contains(polygon, point)) { distance } else { -distance };
+                let value = (0.5f32 + signed / (2f32 * self.spread)).clamp(0f32, 1f32);
+                self.texels.push((value * 255f32).round() as u8);
+            }
+        }
+        self.entries.push(entry);
+        self.version += 1;
+        Ok(SdfId(self.entries.len() - 1))
+    }
+
+    // which atlas and which change of it, for renderers to know when to upload it again
+    pub(crate) fn version(&self) -> (usize, usize) {
+        (self.id, self.version)
+    }
+
+    pub(crate) fn all_texels(&self) -> &[u8] {
+        &self.texels
+    }
+}
+
+// The edges of closed polygons apart from the two sides of cuts to holes, which are not edges
+// of the filled area and would leave seams.
+fn outline_edges(polygons: &[Vec<(f32, f32)>]) -> Vec<((f32, f32), (f32, f32))> {
+    let key = |a: (f32, f32), b: (f32, f32)| (a.0.to_bits(), a.1.to_bits(), b.0.to_bits(), b.1.to_bits());
+    let mut edges = Vec::new();
+    for polygon in polygons {
+        let polygon_edges: Vec<_> = (0..polygon.len()).map(|i| (polygon[i], polygon[(i + 1) % polygon.len()])).collect();
+        let all: HashSet<_> = polygon_edges.iter().map(|&(a, b)| key(a, b)).collect();
+        edges.extend(polygon_edges.into_iter().filter(|&(a, b)| !all.contains(&key(b, a))));
+    }
+    edges
+}
+
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0f32 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_squared).clamp(0f32, 1f32)
+    } else {
+        0f32
+    };
+    (p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
+}
+
+// Even-odd test of whether a point is inside a polygon.
+fn contains(polygon: &[(f32, f32)], point: (f32, f32)) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.1 > point.1) != (b.1 > point.1) && point.0 < a.0 + (point.1 - a.1) / (b.1 - a.1) * (b.0 - a.0) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// A distance field to draw, with the field's area scaled by scale and then moved by position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfQuad {
+    pub id: SdfId,
+    pub position: (f32, f32),
+    pub scale: f32
+}
+
+/// A soft shadow behind distance field shapes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfShadow {
+    pub color: Color,
+    /// Offset in pixels, y up.
+    pub offset: (f32, f32),
+    /// Width of the blurred edge in pixels.
+    pub softness: f32
+}
+
+/// How distance field shapes are drawn. Outline widths and shadows are in pixels, so they look the
+/// same at any zoom until they reach past the atlas's spread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfStyle {
+    pub color: Color,
+    pub outline_color: Color,
+    /// Width of the outline around the shape in pixels, 0 for none.
+    pub outline_width: f32,
+    pub shadow: Option<SdfShadow>
+}
+
+impl SdfStyle {
+    /// A plain fill in a color.
+    pub fn new(color: Color) -> Self {
+        SdfStyle { color, outline_color: Color::rgba(0f32, 0f32, 0f32, 0f32), outline_width: 0f32, shadow: None }
+    }
+}
+
+/// Text drawn with distance fields: a font whose glyphs are added to an atlas as they are first
+/// used.
+#[cfg(feature = "font")]
+pub struct SdfText {
+    font: Font,
+    glyphs: HashMap<u16, Option<SdfId>>
+}
+
+#[cfg(feature = "font")]
+impl SdfText {
+    /// Constructor.
+    pub fn new(font: Font) -> Self {
+        SdfText { font, glyphs: HashMap::new() }
+    }
+
+    /// The font.
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// Lay out a line of text like `Font::text_paths`, adding its glyphs to atlas if they are
+    /// not there yet. Use the same atlas every time.
+    pub fn quads(&mut self, atlas: &mut SdfAtlas, text: &str, origin: (f32, f32), size: f32) -> Vec<SdfQuad> {
+        let mut quads = Vec::new();
+        let mut pen = origin;
+        for character in text.chars() {
+            if character == '\n' {
+                pen = (origin.0, pen.1 - self.font.line_height() * size);
+                continue;
+            }
+            let glyph = match self.font.glyph_for(character) {
+                Some(glyph) => glyph,
+                None => continue
+            };
+            // glyphs without outlines, like the space, have no field
+            let id = *self.glyphs.entry(glyph.id()).or_insert_with(|| atlas.add_paths(glyph.paths()).ok());
+            if let Some(id) = id {
+                quads.push(SdfQuad { id, position: pen, scale: size });
+            }
+            pen.0 += glyph.advance() * size;
+        }
+        quads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::gl2d::drawing::Path;
+    use super::SdfAtlas;
+
+    #[test]
+    fn test_square_field() {
+        let mut atlas = SdfAtlas::new(32, 4f32);
+        let id = atlas.add_paths(&[Path::rectangle((0f32, 0f32), 2f32, 2f32, 0f32)]).unwrap();
+        // 24 texels for the square and 4 around it
+        let entry = *atlas.entry(id).unwrap();
+        assert!((entry.min.0 + 32f32 / 24f32).abs() < 1e-5 && (entry.max.1 - 32f32 / 24f32).abs() < 1e-5);
+        let texels = atlas.texels(id).unwrap();
+        let at = |x: usize, y: usize| texels[y * 32 + x];
+        assert_eq!(at(16, 16), 255);
+        assert_eq!(at(0, 0), 0);
+        // half a texel either side of the edge at x = 4
+        assert_eq!((at(3, 16), at(4, 16)), (112, 143));
+    }
+
+    #[test]
+    fn test_cuts_to_holes_are_not_edges() {
+        // a square with a square hole, joined by a cut along y = 5
+        let path = Path::new((0f32, 0f32)).line_to((10f32, 0f32)).line_to((10f32, 5f32)).line_to((7f32, 5f32))
+            .line_to((7f32, 3f32)).line_to((3f32, 3f32)).line_to((3f32, 7f32)).line_to((7f32, 7f32))
+            .line_to((7f32, 5f32)).line_to((10f32, 5f32)).line_to((10f32, 10f32)).line_to((0f32, 10f32))
+            .close_path();
+        let mut atlas = SdfAtlas::new(24, 2f32);
+        let id = atlas.add_paths(&[path]).unwrap();
+        let texels = atlas.texels(id).unwrap();
+        // half a unit per texel, the texel centered on (8.75, 4.75) is next to the cut but more
+        // than the spread from the hole and the side
+        assert_eq!(texels[11 * 24 + 19], 255);
+        assert_eq!(texels[11 * 24 + 11], 0);
+        assert!(atlas.add_paths(&[]).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! Snapping helpers for editors: the nearest point on the paths of a drawing, a snapping grid,
+//! and alignment guides along the edges and centres of paths.
+
+use super::gl2d::drawing::{Drawing, Path, PathId, Window};
+
+/// A point on a path found by `nearest_point_on_paths`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapTarget {
+    pub id: PathId,
+    pub point: (f32, f32),
+    /// Distance from the query point.
+    pub distance: f32
+}
+
+/// The closest point on the outline of any path in the drawing, if one is within `radius` of
+/// `point`. Both are in drawing coordinates.
+pub fn nearest_point_on_paths<W: Window>(drawing: &Drawing<'_, W>, point: (f32, f32), radius: f32)
+        -> Option<SnapTarget> {
+    let nearby = drawing.paths_in_rect(((point.0 - radius, point.1 - radius), (point.0 + radius, point.1 + radius)));
+    nearest_point(nearby.into_iter().filter_map(|id| drawing.get_path(id).map(|path| (id, path))), point, radius)
+}
+
+fn nearest_point<'p, I>(paths: I, point: (f32, f32), radius: f32) -> Option<SnapTarget>
+        where I: Iterator<Item = (PathId, &'p Path)> {
+    let mut best: Option<SnapTarget> = None;
+    for (id, path) in paths {
+        // skip paths whose control points are all too far away, the curves lie inside their hull
+        let (min, max) = path.fast_bounds();
+        let dx = (min.0 - point.0).max(point.0 - max.0).max(0f32);
+        let dy = (min.1 - point.1).max(point.1 - max.1).max(0f32);
+        let limit = best.map_or(radius, |b| b.distance);
+        if dx.hypot(dy) > limit {
+            continue;
+        }
+        let nearest = path.nearest_point(point);
+        let distance = (nearest.0 - point.0).hypot(nearest.1 - point.1);
+        if distance <= limit {
+            best = Some(SnapTarget { id, point: nearest, distance });
+        }
+    }
+    best
+}
+
+/// A rectangular grid of snapping points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    /// A point on the grid.
+    pub origin: (f32, f32),
+    /// Distance between grid lines in x and y.
+    pub spacing: (f32, f32)
+}
+
+impl Grid {
+    /// A square grid through the origin.
+    pub fn new(spacing: f32) -> Self {
+        Grid { origin: (0f32, 0f32), spacing: (spacing, spacing) }
+    }
+
+    /// The grid point closest to `point`.
+    pub fn snap(&self, point: (f32, f32)) -> (f32, f32) {
+        let snap_axis = |value: f32, origin: f32, spacing: f32| {
+            if spacing > 0f32 {
+                origin + ((value - origin) / spacing).round() * spacing
+            } else {
+                value
+            }
+        };
+        (snap_axis(point.0, self.origin.0, self.spacing.0),
+         snap_axis(point.1, self.origin.1, self.spacing.1))
+    }
+}
+
+/// An infinite line to align things to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Guide {
+    /// The line x = value.
+    Vertical(f32),
+    /// The line y = value.
+    Horizontal(f32)
+}
+
+/// Guides along the left, centre and right and the bottom, middle and top of the bounding box of
+/// every path in the drawing, except those in `exclude` (usually the paths being moved).
+pub fn alignment_guides<W: Window>(drawing: &Drawing<'_, W>, exclude: &[PathId]) -> Vec<Guide> {
+    guides_for(drawing.paths().filter(|&(id, _)| !exclude.contains(&id)).map(|(_, path)| path))
+}
+
+fn guides_for<'p, I: Iterator<Item = &'p Path>>(paths: I) -> Vec<Guide> {
+    let mut guides = Vec::new();
+    for path in paths {
+        let (min, max) = path.tight_bounds();
+        for &x in &[min.0, (min.0 + max.0) / 2f32, max.0] {
+            guides.push(Guide::Vertical(x));
+        }
+        for &y in &[min.1, (min.1 + max.1) / 2f32, max.1] {
+            guides.push(Guide::Horizontal(y));
+        }
+    }
+    guides
+}
+
+/// Move `point` onto the nearest vertical and the nearest horizontal guide within `radius`,
+/// each axis on its own.
+pub fn snap_to_guides(point: (f32, f32), guides: &[Guide], radius: f32) -> (f32, f32) {
+    let mut x = (point.0, radius);
+    let mut y = (point.1, radius);
+    for guide in guides {
+        match *guide {
+            Guide::Vertical(value) if (value - point.0).abs() <= x.1 => x = (value, (value - point.0).abs()),
+            Guide::Horizontal(value) if (value - point.1).abs() <= y.1 => y = (value, (value - point.1).abs()),
+            _ => {}
+        }
+    }
+    (x.0, y.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{guides_for, nearest_point, snap_to_guides, Grid, Guide};
+    use super::super::gl2d::drawing::{Path, PathId};
+
+    #[test]
+    fn test_nearest_point() {
+        let square = Path::rectangle((0f32, 0f32), 2f32, 2f32, 0f32);
+        let circle = Path::ellipse((10f32, 0f32), 1f32, 1f32, 0f32);
+        let paths = vec![(PathId(0), &square), (PathId(1), &circle)];
+        let target = nearest_point(paths.clone().into_iter(), (1.5f32, 0.5f32), 1f32).unwrap();
+        assert_eq!(target.id, PathId(0));
+        assert!((target.point.0 - 1f32).abs() < 1e-4 && (target.point.1 - 0.5f32).abs() < 1e-4);
+        let target = nearest_point(paths.clone().into_iter(), (10f32, 1.5f32), 1f32).unwrap();
+        assert_eq!(target.id, PathId(1));
+        assert!((target.point.0 - 10f32).abs() < 1e-3 && (target.point.1 - 1f32).abs() < 1e-3);
+        assert!(nearest_point(paths.into_iter(), (5f32, 5f32), 1f32).is_none());
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid { origin: (1f32, 0f32), spacing: (10f32, 5f32) };
+        assert_eq!(grid.snap((14f32, 8f32)), (11f32, 10f32));
+        assert_eq!(Grid::new(0f32).snap((3f32, 4f32)), (3f32, 4f32));
+    }
+
+    #[test]
+    fn test_guides() {
+        let square = Path::rectangle((0f32, 0f32), 2f32, 4f32, 0f32);
+        let guides = guides_for(vec![&square].into_iter());
+        assert!(guides.contains(&Guide::Vertical(-1f32)) && guides.contains(&Guide::Horizontal(2f32)));
+        assert_eq!(snap_to_guides((0.2f32, 2.2f32), &guides, 0.3f32), (0f32, 2f32));
+        assert_eq!(snap_to_guides((5f32, 5f32), &guides, 0.3f32), (5f32, 5f32));
+    }
+}
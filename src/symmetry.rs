@@ -0,0 +1,91 @@
+//! Symmetric copies of paths.
+
+use super::gl2d::drawing::Path;
+use super::transform::Transform2D;
+
+/// Builds the copies of a path that make up a symmetric figure, such as the petals of a flower
+/// turned about its center or the left and right halves of a face.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symmetry {
+    // one transform per copy, the first is the identity
+    transforms: Vec<Transform2D>
+}
+
+impl Symmetry {
+    /// n copies turned evenly about center, the first one where the path is.
+    pub fn rotational(center: (f32, f32), n: usize) -> Self {
+        let step = 2f32 * std::f32::consts::PI / n.max(1) as f32;
+        let transforms = (0..n.max(1)).map(|i| {
+            Transform2D::translation(center.0, center.1)
+                .multiply(&Transform2D::rotation(step * i as f32))
+                .multiply(&Transform2D::translation(-center.0, -center.1))
+        }).collect();
+        Symmetry { transforms }
+    }
+
+    /// The path and its mirror image across the line through the two points of axis.
+    pub fn reflective(axis: ((f32, f32), (f32, f32))) -> Self {
+        Symmetry { transforms: vec![Transform2D::identity()] }.with_reflection(axis)
+    }
+
+    /// Add the mirror image of every copy across the line through the two points of axis, doubling
+    /// the number of copies. On a rotational symmetry with the axis through its center this gives
+    /// the n-fold symmetry of a kaleidoscope.
+    pub fn with_reflection(mut self, axis: ((f32, f32), (f32, f32))) -> Self {
+        let reflection = Transform2D::reflection(axis.0, axis.1);
+        let mirrored: Vec<Transform2D> = self.transforms.iter().map(|t| t.multiply(&reflection)).collect();
+        self.transforms.extend(mirrored);
+        self
+    }
+
+    /// The number of copies `apply` makes.
+    pub fn len(&self) -> usize {
+        self.transforms.len()
+    }
+
+    /// Whether there are no copies, which never happens.
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// The copies of path, the path itself first. Mirrored copies of closed paths are reversed
+    /// so that they still fill.
+    pub fn apply(&self, path: &Path) -> Vec<Path> {
+        self.transforms.iter().map(|t| path.transformed_keeping_winding(t)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Symmetry;
+    use super::super::gl2d::drawing::Path;
+
+    fn close(p: (f32, f32), q: (f32, f32)) -> bool {
+        (p.0 - q.0).abs() < 1e-5 && (p.1 - q.1).abs() < 1e-5
+    }
+
+    #[test]
+    fn test_copies() {
+        let triangle = Path::new((1f32, 0f32)).line_to((2f32, 0f32)).line_to((2f32, 1f32)).close_path();
+        let rotated = Symmetry::rotational((0f32, 0f32), 4).apply(&triangle);
+        assert_eq!(rotated.len(), 4);
+        assert_eq!(rotated[0], triangle);
+        assert!(close(rotated[1].vertices()[0], (0f32, 1f32)));
+        assert!(close(rotated[2].vertices()[1], (-2f32, 0f32)));
+
+        let kaleidoscope = Symmetry::rotational((0f32, 0f32), 3).with_reflection(((0f32, 0f32), (1f32, 0f32)));
+        assert_eq!(kaleidoscope.len(), 6);
+        let mirrored = Symmetry::reflective(((0f32, 0f32), (0f32, 1f32))).apply(&triangle);
+        assert_eq!(mirrored[1], triangle.mirrored(((0f32, 0f32), (0f32, 1f32))));
+        // the mirror image winds the same way as the original
+        let area = |path: &Path| {
+            let v = path.vertices();
+            (0..v.len()).map(|i| {
+                let (a, b) = (v[i], v[(i + 1) % v.len()]);
+                a.0 * b.1 - b.0 * a.1
+            }).sum::<f32>()
+        };
+        assert!(area(&triangle) > 0f32 && area(&mirrored[1]) > 0f32);
+        assert!(close(mirrored[1].vertices()[0], (-1f32, 0f32)));
+    }
+}
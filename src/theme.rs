@@ -0,0 +1,120 @@
+//! Named color roles with light and dark variants, for recoloring a drawing in bulk.
+
+use std::collections::BTreeMap;
+use super::color::Color;
+
+// the tags that give a path's fill or stroke a role, before the role's name
+const FILL_TAG: &str = "fill:";
+const STROKE_TAG: &str = "stroke:";
+
+/// Colors by role name, such as "background", "accent" or "series1".
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Palette {
+    colors: BTreeMap<String, Color>
+}
+
+impl Palette {
+    /// A palette without any roles.
+    pub fn new() -> Self {
+        Palette::default()
+    }
+
+    /// The palette with a role given a color, replacing any color it had.
+    pub fn with(mut self, role: &str, color: Color) -> Self {
+        self.set(role, color);
+        self
+    }
+
+    /// Give a role a color, replacing any color it had.
+    pub fn set(&mut self, role: &str, color: Color) {
+        self.colors.insert(role.to_string(), color);
+    }
+
+    /// The color of a role, None if the palette does not have it.
+    pub fn get(&self, role: &str) -> Option<Color> {
+        self.colors.get(role).cloned()
+    }
+
+    /// The roles in alphabetical order.
+    pub fn roles(&self) -> Vec<&str> {
+        self.colors.keys().map(String::as_str).collect()
+    }
+}
+
+/// Which of a theme's palettes to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeVariant {
+    Light,
+    Dark
+}
+
+/// A light and a dark palette for the same roles, see `Drawing::apply_theme`. Paths take part by
+/// being tagged with `Theme::fill_tag` or `Theme::stroke_tag` of a role.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Theme {
+    pub light: Palette,
+    pub dark: Palette
+}
+
+impl Theme {
+    /// Constructor, the light and dark palettes.
+    pub fn new(light: Palette, dark: Palette) -> Self {
+        Theme { light, dark }
+    }
+
+    /// The palette of a variant.
+    pub fn palette(&self, variant: ThemeVariant) -> &Palette {
+        match variant {
+            ThemeVariant::Light => &self.light,
+            ThemeVariant::Dark => &self.dark
+        }
+    }
+
+    /// The color of a role in a variant. Roles missing from the dark palette fall back to the
+    /// light one.
+    pub fn color(&self, variant: ThemeVariant, role: &str) -> Option<Color> {
+        self.palette(variant).get(role).or_else(|| self.light.get(role))
+    }
+
+    /// The tag that has a path filled with a role's color.
+    pub fn fill_tag(role: &str) -> String {
+        format!("{}{}", FILL_TAG, role)
+    }
+
+    /// The tag that has a path stroked with a role's color.
+    pub fn stroke_tag(role: &str) -> String {
+        format!("{}{}", STROKE_TAG, role)
+    }
+
+    // The colors for the fill and stroke of a path with these tags, in a variant.
+    pub(crate) fn colors_for<'t, I: IntoIterator<Item = &'t str> + Clone>(&self, variant: ThemeVariant, tags: I)
+        -> (Option<Color>, Option<Color>) {
+        let role_color = |prefix: &str| tags.clone().into_iter().filter_map(|tag| tag.strip_prefix(prefix))
+            .find_map(|role| self.color(variant, role));
+        (role_color(FILL_TAG), role_color(STROKE_TAG))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Palette, Theme, ThemeVariant};
+    use super::super::color::Color;
+
+    #[test]
+    fn test_theme_colors() {
+        let (black, white, blue) = (Color::rgb(0f32, 0f32, 0f32), Color::rgb(1f32, 1f32, 1f32),
+                                    Color::rgb(0f32, 0f32, 1f32));
+        let theme = Theme::new(Palette::new().with("text", black).with("accent", blue),
+                               Palette::new().with("text", white));
+        assert_eq!(theme.light.roles(), vec!["accent", "text"]);
+        assert_eq!(theme.color(ThemeVariant::Dark, "text"), Some(white));
+        // the dark palette has no accent, so the light one is used
+        assert_eq!(theme.color(ThemeVariant::Dark, "accent"), Some(blue));
+        assert_eq!(theme.color(ThemeVariant::Light, "grid"), None);
+
+        let tags = ["label", "fill:text", "stroke:accent"];
+        assert_eq!(theme.colors_for(ThemeVariant::Dark, tags.iter().cloned()), (Some(white), Some(blue)));
+        assert_eq!(Theme::fill_tag("text"), "fill:text");
+        assert_eq!(theme.colors_for(ThemeVariant::Light, ["stroke:grid"].iter().cloned()), (None, None));
+    }
+}
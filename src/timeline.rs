@@ -0,0 +1,626 @@
+//! Keyframe animation of paths, the camera and values of your own on a timeline.
+//!
+//! A timeline has a track for each property it animates, with keyframes at times in seconds.
+//! Each keyframe eases towards the next one in its own way. The playhead plays once, loops or
+//! ping-pongs, can be paused and moved, and reports the markers it passes as events. Applying
+//! the timeline to a drawing sets the animated properties of its paths and camera.
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+use super::gl2d::drawing::{Drawing, Path, PathId, Window};
+use super::gl2d::gradient::Fill;
+use super::camera::Camera;
+use super::color::{Color, ColorSpace};
+use super::transform::Transform2D;
+use super::TrdlError;
+
+// iterations solving an easing curve for its parameter
+const EASING_ITERATIONS: usize = 20;
+
+// The y of the easing curve (0, 0), p1, p2, (1, 1) where its x is `x`.
+pub(crate) fn ease(p1: (f32, f32), p2: (f32, f32), x: f32) -> f32 {
+    let bezier = |a: f32, b: f32, t: f32| {
+        let mt = 1f32 - t;
+        3f32 * mt * mt * t * a + 3f32 * mt * t * t * b + t * t * t
+    };
+    if x <= 0f32 || x >= 1f32 {
+        return x.clamp(0f32, 1f32);
+    }
+    // x grows with t for valid easing curves, so bisect for it
+    let (mut low, mut high) = (0f32, 1f32);
+    for _ in 0..EASING_ITERATIONS {
+        let mid = (low + high) / 2f32;
+        if bezier(p1.0, p2.0, mid) < x {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    bezier(p1.1, p2.1, (low + high) / 2f32)
+}
+
+/// How a keyframe's value changes towards the next keyframe's.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    /// At a steady rate, the default.
+    #[default]
+    Linear,
+    /// Not at all until the next keyframe, then at once.
+    Hold,
+    /// Starting slowly.
+    EaseIn,
+    /// Ending slowly.
+    EaseOut,
+    /// Starting and ending slowly.
+    EaseInOut,
+    /// Along the curve from (0, 0) through the two control points to (1, 1), as in CSS's
+    /// `cubic-bezier`. The control points' x must be from 0 to 1.
+    CubicBezier((f32, f32), (f32, f32))
+}
+
+impl Easing {
+    /// How far the value has changed, from 0 to 1, a fraction t of the way to the next keyframe.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0f32, 1f32);
+        match self {
+            Easing::Linear => t,
+            Easing::Hold => if t < 1f32 { 0f32 } else { 1f32 },
+            Easing::EaseIn => ease((0.42f32, 0f32), (1f32, 1f32), t),
+            Easing::EaseOut => ease((0f32, 0f32), (0.58f32, 1f32), t),
+            Easing::EaseInOut => ease((0.42f32, 0f32), (0.58f32, 1f32), t),
+            Easing::CubicBezier(p1, p2) => ease((p1.0.clamp(0f32, 1f32), p1.1), (p2.0.clamp(0f32, 1f32), p2.1), t)
+        }
+    }
+}
+
+/// A value of a keyframe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Scalar(f32),
+    Point(f32, f32),
+    Color(Color)
+}
+
+impl Value {
+    /// The value if it is a scalar.
+    pub fn scalar(self) -> Option<f32> {
+        match self {
+            Value::Scalar(value) => Some(value),
+            _ => None
+        }
+    }
+
+    /// The value if it is a point, or a scalar taken as the same x and y.
+    pub fn point(self) -> Option<(f32, f32)> {
+        match self {
+            Value::Point(x, y) => Some((x, y)),
+            Value::Scalar(value) => Some((value, value)),
+            Value::Color(_) => None
+        }
+    }
+
+    /// The value if it is a color.
+    pub fn color(self) -> Option<Color> {
+        match self {
+            Value::Color(color) => Some(color),
+            _ => None
+        }
+    }
+
+    // The value t of the way to other, colors blended in space. Values of different kinds do not
+    // blend, this one holds until other is reached.
+    fn interpolate(self, other: Value, t: f32, space: ColorSpace) -> Value {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        match (self, other) {
+            (Value::Scalar(a), Value::Scalar(b)) => Value::Scalar(mix(a, b)),
+            (Value::Point(ax, ay), Value::Point(bx, by)) => Value::Point(mix(ax, bx), mix(ay, by)),
+            (Value::Color(a), Value::Color(b)) => Value::Color(a.mix(&b, t, space)),
+            _ => if t < 1f32 { self } else { other }
+        }
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Scalar(value)
+    }
+}
+
+impl From<(f32, f32)> for Value {
+    fn from(point: (f32, f32)) -> Self {
+        Value::Point(point.0, point.1)
+    }
+}
+
+impl From<Color> for Value {
+    fn from(color: Color) -> Self {
+        Value::Color(color)
+    }
+}
+
+/// What a track animates. Path transforms move, turn and scale the path as it was when the
+/// timeline was first applied to it, about the center of its bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Property {
+    /// The opacity of a whole path, a scalar from 0 to 1.
+    Opacity(PathId),
+    /// The opacity of a path's fill, a scalar from 0 to 1.
+    FillOpacity(PathId),
+    /// The opacity of a path's stroke, a scalar from 0 to 1.
+    StrokeOpacity(PathId),
+    /// The fill color of a path, its alpha is the fill's opacity.
+    FillColor(PathId),
+    /// The stroke color of a stroked path, its alpha is the stroke's opacity.
+    StrokeColor(PathId),
+    /// How far a path is moved, a point.
+    Translation(PathId),
+    /// How far a path is turned counter clockwise, a scalar in radians.
+    Rotation(PathId),
+    /// How much a path is scaled, a scalar or a point for x and y apart.
+    Scale(PathId),
+    /// The camera's pan, a point.
+    CameraPan,
+    /// The camera's zoom, a positive scalar.
+    CameraZoom,
+    /// A value the timeline does not apply, read it with `Timeline::value`.
+    Custom(String)
+}
+
+impl Property {
+    // The path the property belongs to.
+    fn path(&self) -> Option<PathId> {
+        match *self {
+            Property::Opacity(id) | Property::FillOpacity(id) | Property::StrokeOpacity(id) |
+            Property::FillColor(id) | Property::StrokeColor(id) | Property::Translation(id) |
+            Property::Rotation(id) | Property::Scale(id) => Some(id),
+            Property::CameraPan | Property::CameraZoom | Property::Custom(_) => None
+        }
+    }
+}
+
+/// A value at a time in seconds, and how it eases towards the next keyframe's value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    pub time: f32,
+    pub value: Value,
+    pub easing: Easing
+}
+
+// A property's keyframes in order of time.
+#[derive(Debug, Clone, PartialEq)]
+struct Track {
+    property: Property,
+    keyframes: Vec<Keyframe>
+}
+
+impl Track {
+    // The value at a time, the first or last keyframe's before or after them all.
+    fn at(&self, time: f32, space: ColorSpace) -> Value {
+        let next = self.keyframes.iter().position(|key| key.time > time).unwrap_or(self.keyframes.len());
+        if next == 0 {
+            return self.keyframes[0].value;
+        }
+        let current = &self.keyframes[next - 1];
+        match self.keyframes.get(next) {
+            Some(next) => {
+                let t = current.easing.apply((time - current.time) / (next.time - current.time));
+                current.value.interpolate(next.value, t, space)
+            }
+            None => current.value
+        }
+    }
+}
+
+/// What the playhead does when it reaches the end of the timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayMode {
+    /// Stop there, the default.
+    #[default]
+    Once,
+    /// Go on from the start.
+    Loop,
+    /// Play backwards to the start, then forwards again.
+    PingPong
+}
+
+/// What happened while the playhead moved, see `Timeline::advance`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimelineEvent {
+    /// The playhead reached a marker, with its name.
+    Marker(String),
+    /// The playhead went back to the start, or turned around when ping-ponging.
+    Looped,
+    /// The playhead reached the end when playing once, and stopped.
+    Finished
+}
+
+/// Tracks of keyframes and markers, played back by a playhead. See the module documentation.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    tracks: Vec<Track>,
+    // markers in order of time
+    markers: Vec<(f32, String)>,
+    time: f32,
+    playing: bool,
+    mode: PlayMode,
+    speed: f32,
+    // the playhead runs backwards, when ping-ponging
+    reversed: bool,
+    // markers at the playhead fire on the next advance, after a seek or at the start
+    at_start: bool,
+    color_space: ColorSpace,
+    // the animated paths as they were when first applied
+    bases: BTreeMap<PathId, Path>
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Timeline::new()
+    }
+}
+
+impl Timeline {
+    /// Constructor, an empty timeline, paused at the start.
+    pub fn new() -> Self {
+        Timeline {
+            tracks: Vec::new(),
+            markers: Vec::new(),
+            time: 0f32,
+            playing: false,
+            mode: PlayMode::Once,
+            speed: 1f32,
+            reversed: false,
+            at_start: true,
+            color_space: ColorSpace::Srgb,
+            bases: BTreeMap::new()
+        }
+    }
+
+    /// Add a keyframe to the track of a property, making the track if there is none. A keyframe
+    /// at the same time as one already there replaces it. Times that are not finite are ignored.
+    pub fn add_keyframe(&mut self, property: Property, time: f32, value: impl Into<Value>, easing: Easing) {
+        if !time.is_finite() {
+            return;
+        }
+        let keyframe = Keyframe { time, value: value.into(), easing };
+        let track = match self.tracks.iter().position(|track| track.property == property) {
+            Some(i) => &mut self.tracks[i],
+            None => {
+                self.tracks.push(Track { property, keyframes: Vec::new() });
+                self.tracks.last_mut().unwrap()
+            }
+        };
+        match track.keyframes.iter().position(|key| key.time >= time) {
+            Some(i) if track.keyframes[i].time == time => track.keyframes[i] = keyframe,
+            Some(i) => track.keyframes.insert(i, keyframe),
+            None => track.keyframes.push(keyframe)
+        }
+    }
+
+    /// The keyframes of a property in order of time, empty if it has no track.
+    pub fn keyframes(&self, property: &Property) -> &[Keyframe] {
+        self.tracks.iter().find(|track| track.property == *property).map_or(&[], |track| &track.keyframes[..])
+    }
+
+    /// Remove the track of a property, returns false if there is none. A path it animated keeps
+    /// the value last applied.
+    pub fn remove_track(&mut self, property: &Property) -> bool {
+        let count = self.tracks.len();
+        self.tracks.retain(|track| track.property != *property);
+        self.tracks.len() < count
+    }
+
+    /// Put a named marker at a time, reported by `advance` when the playhead reaches it.
+    pub fn add_marker(&mut self, time: f32, name: &str) {
+        if time.is_finite() {
+            let i = self.markers.iter().position(|marker| marker.0 > time).unwrap_or(self.markers.len());
+            self.markers.insert(i, (time, name.to_string()));
+        }
+    }
+
+    /// The time of a marker by name, the first if several have it.
+    pub fn marker(&self, name: &str) -> Option<f32> {
+        self.markers.iter().find(|marker| marker.1 == name).map(|marker| marker.0)
+    }
+
+    /// The length of the timeline in seconds, up to its last keyframe or marker.
+    pub fn duration(&self) -> f32 {
+        self.tracks.iter().flat_map(|track| track.keyframes.iter().map(|key| key.time))
+            .chain(self.markers.iter().map(|marker| marker.0))
+            .fold(0f32, f32::max)
+    }
+
+    /// Start or resume playing. Playing once from the end starts over.
+    pub fn play(&mut self) {
+        if self.mode == PlayMode::Once && self.time >= self.duration() {
+            self.seek(0f32);
+        }
+        self.playing = true;
+    }
+
+    /// Stop the playhead where it is.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Whether the playhead moves on `advance`.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Move the playhead to a time, kept within the timeline, playing forwards from there.
+    /// Markers at that time are reported by the next advance, markers skipped over are not.
+    pub fn seek(&mut self, time: f32) {
+        self.time = if time.is_finite() { time.clamp(0f32, self.duration()) } else { 0f32 };
+        self.reversed = false;
+        self.at_start = true;
+    }
+
+    /// Move the playhead to a marker by name. Returns false if there is none.
+    pub fn seek_to_marker(&mut self, name: &str) -> bool {
+        match self.marker(name) {
+            Some(time) => {
+                self.seek(time);
+                true
+            }
+            None => false
+        }
+    }
+
+    /// The time of the playhead in seconds.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Set what happens at the end of the timeline.
+    pub fn set_play_mode(&mut self, mode: PlayMode) {
+        self.mode = mode;
+        if mode != PlayMode::PingPong {
+            self.reversed = false;
+        }
+    }
+
+    /// What happens at the end of the timeline.
+    pub fn play_mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    /// Set how many seconds of the timeline pass each second, 1 by default. Speeds that are not
+    /// positive are ignored.
+    pub fn set_speed(&mut self, speed: f32) {
+        if speed > 0f32 && speed.is_finite() {
+            self.speed = speed;
+        }
+    }
+
+    /// Seconds of the timeline passing each second.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Set the color space colors are blended in between keyframes, sRGB by default.
+    pub fn set_color_space(&mut self, space: ColorSpace) {
+        self.color_space = space;
+    }
+
+    /// Move the playhead on by dt seconds if playing, returning the markers it reached and
+    /// whether it looped or finished, in the order it did.
+    pub fn advance(&mut self, dt: f32) -> Vec<TimelineEvent> {
+        let mut events = Vec::new();
+        if !self.playing || dt.is_nan() || dt < 0f32 {
+            return events;
+        }
+        let duration = self.duration();
+        let (mut remaining, mut inclusive) = ((dt * self.speed).min(f32::MAX), self.at_start);
+        self.at_start = false;
+        loop {
+            let (from, to_end) = (self.time, if self.reversed { self.time } else { duration - self.time });
+            if remaining < to_end {
+                self.time = if self.reversed { from - remaining } else { from + remaining };
+                self.markers_between(from, self.time, inclusive, &mut events);
+                return events;
+            }
+            self.time = if self.reversed { 0f32 } else { duration };
+            self.markers_between(from, self.time, inclusive, &mut events);
+            remaining -= to_end;
+            inclusive = false;
+            match self.mode {
+                PlayMode::Once => {
+                    self.playing = false;
+                    events.push(TimelineEvent::Finished);
+                    return events;
+                }
+                _ if duration <= 0f32 => return events,
+                PlayMode::Loop => {
+                    self.time = 0f32;
+                    inclusive = true;
+                    // whole loops are skipped, reporting their markers once
+                    if remaining > duration {
+                        remaining %= duration;
+                    }
+                }
+                PlayMode::PingPong => {
+                    self.reversed = !self.reversed;
+                    if remaining > 2f32 * duration {
+                        remaining %= 2f32 * duration;
+                    }
+                }
+            }
+            events.push(TimelineEvent::Looped);
+        }
+    }
+
+    // Report the markers between two playhead times in the order it passed them, the one it
+    // left only if inclusive.
+    fn markers_between(&self, from: f32, to: f32, inclusive: bool, events: &mut Vec<TimelineEvent>) {
+        let passed = |time: f32| if from <= to {
+            (time > from || inclusive && time == from) && time <= to
+        } else {
+            (time < from || inclusive && time == from) && time >= to
+        };
+        let reached = self.markers.iter().filter(|marker| passed(marker.0)).map(|marker| TimelineEvent::Marker(marker.1.clone()));
+        if from <= to {
+            events.extend(reached);
+        } else {
+            events.extend(reached.rev());
+        }
+    }
+
+    /// The value of a property at the playhead, None if it has no track.
+    pub fn value(&self, property: &Property) -> Option<Value> {
+        self.value_at(property, self.time)
+    }
+
+    /// The value of a property at a time, None if it has no track.
+    pub fn value_at(&self, property: &Property, time: f32) -> Option<Value> {
+        self.tracks.iter().find(|track| track.property == *property && !track.keyframes.is_empty())
+            .map(|track| track.at(time, self.color_space))
+    }
+
+    /// Set the animated properties of the drawing's paths and camera to their values at the
+    /// playhead. Paths that are gone from the drawing are skipped.
+    pub fn apply<W: Window>(&mut self, drawing: &mut Drawing<'_, W>) -> Result<(), TrdlError> {
+        let ids: BTreeSet<PathId> = self.tracks.iter().filter_map(|track| track.property.path()).collect();
+        for id in ids {
+            if let Entry::Vacant(entry) = self.bases.entry(id) {
+                match drawing.get_path(id) {
+                    Some(path) => entry.insert(path.clone()),
+                    None => continue
+                };
+            }
+            drawing.update_path(id, self.animated_path(&self.bases[&id], id))?;
+        }
+        let (pan, zoom) = (self.value(&Property::CameraPan).and_then(Value::point),
+                           self.value(&Property::CameraZoom).and_then(Value::scalar));
+        if pan.is_some() || zoom.is_some() {
+            let camera = drawing.camera();
+            drawing.set_camera(Camera {
+                pan: pan.unwrap_or(camera.pan),
+                zoom: zoom.filter(|&zoom| zoom > 0f32).unwrap_or(camera.zoom)
+            });
+        }
+        Ok(())
+    }
+
+    // A path as the tracks of its properties set it at the playhead, from how it was at first.
+    // Colors are set before opacities, which multiply them.
+    fn animated_path(&self, base: &Path, id: PathId) -> Path {
+        let value = |property: Property| self.value(&property);
+        let translation = value(Property::Translation(id)).and_then(Value::point);
+        let rotation = value(Property::Rotation(id)).and_then(Value::scalar);
+        let scale = value(Property::Scale(id)).and_then(Value::point);
+        let mut path = if translation.is_some() || rotation.is_some() || scale.is_some() {
+            let ((x0, y0), (x1, y1)) = base.bounds();
+            let center = ((x0 + x1) / 2f32, (y0 + y1) / 2f32);
+            let (dx, dy) = translation.unwrap_or((0f32, 0f32));
+            let (sx, sy) = scale.unwrap_or((1f32, 1f32));
+            let transform = Transform2D::translation(center.0 + dx, center.1 + dy)
+                .multiply(&Transform2D::rotation(rotation.unwrap_or(0f32)))
+                .multiply(&Transform2D::scaling(sx, sy))
+                .multiply(&Transform2D::translation(-center.0, -center.1));
+            base.transformed_keeping_winding(&transform)
+        } else {
+            base.clone()
+        };
+        if let Some(color) = value(Property::FillColor(id)).and_then(Value::color) {
+            path = path.set_fill(Fill::Color(color));
+        }
+        if let (Some(color), Some((_, thickness))) = (value(Property::StrokeColor(id)).and_then(Value::color), path.stroke()) {
+            path = path.set_stroke(color.red, color.green, color.blue, thickness).set_stroke_opacity(color.alpha);
+        }
+        if let Some(opacity) = value(Property::Opacity(id)).and_then(Value::scalar) {
+            path = path.set_opacity(opacity);
+        }
+        if let Some(opacity) = value(Property::FillOpacity(id)).and_then(Value::scalar) {
+            path = path.set_fill_opacity(opacity);
+        }
+        if let Some(opacity) = value(Property::StrokeOpacity(id)).and_then(Value::scalar) {
+            path = path.set_stroke_opacity(opacity);
+        }
+        path
+    }
+
+    /// Move the playhead on by dt seconds, then apply the timeline to the drawing, see `advance`
+    /// and `apply`.
+    pub fn update<W: Window>(&mut self, drawing: &mut Drawing<'_, W>, dt: f32) -> Result<Vec<TimelineEvent>, TrdlError> {
+        let events = self.advance(dt);
+        self.apply(drawing)?;
+        Ok(events)
+    }
+
+    /// Put the animated paths back in the drawing as they were before the timeline was first
+    /// applied to them, and forget them.
+    pub fn restore<W: Window>(&mut self, drawing: &mut Drawing<'_, W>) -> Result<(), TrdlError> {
+        for (id, path) in mem::take(&mut self.bases) {
+            if drawing.get_path(id).is_some() {
+                drawing.update_path(id, path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Easing, PlayMode, Property, Timeline, TimelineEvent, Value};
+    use super::super::color::Color;
+
+    fn marker(name: &str) -> TimelineEvent {
+        TimelineEvent::Marker(name.to_string())
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let mut timeline = Timeline::new();
+        let x = Property::Custom("x".to_string());
+        timeline.add_keyframe(x.clone(), 2f32, 10f32, Easing::Hold);
+        timeline.add_keyframe(x.clone(), 0f32, 0f32, Easing::Linear);
+        timeline.add_keyframe(x.clone(), 4f32, 20f32, Easing::EaseInOut);
+        assert_eq!(timeline.keyframes(&x).len(), 3);
+        assert_eq!(timeline.duration(), 4f32);
+        assert_eq!(timeline.value_at(&x, -1f32), Some(Value::Scalar(0f32)));
+        assert_eq!(timeline.value_at(&x, 1f32), Some(Value::Scalar(5f32)));
+        // held until the next keyframe
+        assert_eq!(timeline.value_at(&x, 3.9f32), Some(Value::Scalar(10f32)));
+        assert_eq!(timeline.value_at(&x, 4f32), Some(Value::Scalar(20f32)));
+        assert_eq!(timeline.value(&Property::CameraZoom), None);
+        let color = Property::Custom("color".to_string());
+        timeline.add_keyframe(color.clone(), 0f32, Color::rgb(0f32, 0f32, 0f32), Easing::Linear);
+        timeline.add_keyframe(color.clone(), 2f32, Color::rgb(1f32, 1f32, 1f32), Easing::Linear);
+        assert_eq!(timeline.value_at(&color, 1f32), Some(Value::Color(Color::rgb(0.5f32, 0.5f32, 0.5f32))));
+        assert!(Easing::EaseIn.apply(0.25f32) < 0.25f32 && Easing::EaseOut.apply(0.25f32) > 0.25f32);
+    }
+
+    #[test]
+    fn test_playback() {
+        let mut timeline = Timeline::new();
+        timeline.add_keyframe(Property::CameraZoom, 0f32, 1f32, Easing::Linear);
+        timeline.add_keyframe(Property::CameraZoom, 4f32, 2f32, Easing::Linear);
+        timeline.add_marker(0f32, "start");
+        timeline.add_marker(1f32, "one");
+        timeline.add_marker(3f32, "three");
+        // paused timelines stay put
+        assert!(timeline.advance(1f32).is_empty());
+        timeline.play();
+        assert_eq!(timeline.advance(1f32), vec![marker("start"), marker("one")]);
+        assert_eq!(timeline.advance(4f32), vec![marker("three"), TimelineEvent::Finished]);
+        assert!(!timeline.is_playing());
+        assert_eq!(timeline.time(), 4f32);
+
+        timeline.set_play_mode(PlayMode::Loop);
+        timeline.seek(2f32);
+        timeline.play();
+        assert_eq!(timeline.advance(3f32), vec![marker("three"), TimelineEvent::Looped, marker("start"), marker("one")]);
+        assert_eq!(timeline.time(), 1f32);
+
+        timeline.set_play_mode(PlayMode::PingPong);
+        assert!(timeline.seek_to_marker("three"));
+        // the marker the playhead starts on, then again on the way back
+        assert_eq!(timeline.advance(3f32), vec![marker("three"), TimelineEvent::Looped, marker("three")]);
+        assert_eq!(timeline.time(), 2f32);
+        assert_eq!(timeline.value(&Property::CameraZoom), Some(Value::Scalar(1.5f32)));
+        timeline.pause();
+        assert!(timeline.advance(1f32).is_empty());
+    }
+}
@@ -0,0 +1,38 @@
+//! Optional diagnostics. With the `log` or `tracing` feature, shader compilation, tessellation,
+//! buffer uploads and drawing report what they do through that crate, and `tracing` also gets
+//! spans around them. Without either feature the macros compile to nothing.
+
+// Log an event at a level, one of error, warn, info, debug or trace.
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        { ::log::$level!($($arg)+); }
+        #[cfg(feature = "tracing")]
+        { ::tracing::$level!($($arg)+); }
+        // keeps the arguments used, without formatting anything
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        { if false { let _ = format_args!($($arg)+); } }
+    }};
+}
+
+// Enter a debug level span that lasts until the end of the enclosing block.
+macro_rules! span {
+    ($name:expr) => {
+        #[cfg(feature = "tracing")]
+        let _span = ::tracing::debug_span!($name).entered();
+    };
+}
+
+// Report any OpenGL errors raised since the last check. Reading the errors can stall the GPU, so
+// this only happens when logging is on.
+pub(crate) fn log_gl_errors(during: &str) {
+    if cfg!(any(feature = "log", feature = "tracing")) {
+        loop {
+            let error = unsafe { gl::GetError() };
+            if error == gl::NO_ERROR {
+                break;
+            }
+            log_event!(warn, "OpenGL error 0x{:04X} during {}", error, during);
+        }
+    }
+}
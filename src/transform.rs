@@ -0,0 +1,122 @@
+//! 2D affine transforms.
+
+use std::ops::Mul;
+use super::angle::Angle;
+
+/// An affine transform mapping (x, y) to (a*x + c*y + e, b*x + d*y + f), the same layout as the
+/// HTML canvas `setTransform`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2D {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Transform2D::identity()
+    }
+}
+
+impl Transform2D {
+    /// The transform that leaves points where they are.
+    pub fn identity() -> Self {
+        Transform2D { a: 1f32, b: 0f32, c: 0f32, d: 1f32, e: 0f32, f: 0f32 }
+    }
+
+    /// Move points by (dx, dy).
+    pub fn translation(dx: f32, dy: f32) -> Self {
+        Transform2D { e: dx, f: dy, ..Transform2D::identity() }
+    }
+
+    /// Rotate points counter clockwise by angle (radians if a plain f32) about the origin.
+    pub fn rotation(angle: impl Into<Angle>) -> Self {
+        let (sin, cos) = angle.into().to_radians().sin_cos();
+        Transform2D { a: cos, b: sin, c: -sin, d: cos, e: 0f32, f: 0f32 }
+    }
+
+    /// Scale points about the origin.
+    pub fn scaling(sx: f32, sy: f32) -> Self {
+        Transform2D { a: sx, d: sy, ..Transform2D::identity() }
+    }
+
+    /// Reflect points across the line through a and b. The points have to differ.
+    pub fn reflection(a: (f32, f32), b: (f32, f32)) -> Self {
+        let (sin, cos) = (2f32 * (b.1 - a.1).atan2(b.0 - a.0)).sin_cos();
+        Transform2D::translation(a.0, a.1)
+            .multiply(&Transform2D { a: cos, b: sin, c: sin, d: -cos, e: 0f32, f: 0f32 })
+            .multiply(&Transform2D::translation(-a.0, -a.1))
+    }
+
+    /// Whether the transform turns shapes over, so that counter clockwise becomes clockwise.
+    pub fn is_mirroring(&self) -> bool {
+        self.a * self.d - self.b * self.c < 0f32
+    }
+
+    /// The product of this transform and `other`, which applies `other` first and then this one.
+    pub fn multiply(&self, other: &Transform2D) -> Self {
+        Transform2D {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f
+        }
+    }
+
+    /// Transform a point.
+    pub fn apply(&self, point: (f32, f32)) -> (f32, f32) {
+        (self.a * point.0 + self.c * point.1 + self.e, self.b * point.0 + self.d * point.1 + self.f)
+    }
+
+    /// How much the transform scales lengths, on average over all directions.
+    pub fn scale_factor(&self) -> f32 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+}
+
+impl Mul for Transform2D {
+    type Output = Transform2D;
+
+    /// `p * q` applies `q` first, then `p`.
+    fn mul(self, other: Transform2D) -> Transform2D {
+        self.multiply(&other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transform2D;
+    use std::f32::consts::PI;
+
+    fn close(p: (f32, f32), q: (f32, f32)) -> bool {
+        (p.0 - q.0).abs() < 1e-5 && (p.1 - q.1).abs() < 1e-5
+    }
+
+    #[test]
+    fn test_compose_order() {
+        let t = Transform2D::translation(10f32, 0f32) * Transform2D::rotation(PI / 2f32);
+        assert!(close(t.apply((1f32, 0f32)), (10f32, 1f32)));
+        let t = Transform2D::rotation(PI / 2f32) * Transform2D::translation(10f32, 0f32);
+        assert!(close(t.apply((1f32, 0f32)), (0f32, 11f32)));
+    }
+
+    #[test]
+    fn test_reflection() {
+        let t = Transform2D::reflection((1f32, 0f32), (2f32, 1f32));
+        assert!(close(t.apply((1f32, 1f32)), (2f32, 0f32)));
+        assert!(close(t.apply((3f32, 2f32)), (3f32, 2f32)));
+        assert!(t.is_mirroring());
+        assert!(!Transform2D::rotation(PI).is_mirroring());
+    }
+
+    #[test]
+    fn test_scale_factor() {
+        let t = Transform2D::rotation(0.3f32) * Transform2D::scaling(4f32, 4f32);
+        assert!((t.scale_factor() - 4f32).abs() < 1e-5);
+    }
+}
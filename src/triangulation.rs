@@ -1,7 +1,11 @@
 //! Module for triangulating a simple polygon using ear clipping.
 
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use super::TrdlError;
+use super::mesh::Mesh;
+
+// points closer together than this fraction of the polygon's size count as the same point
+const DEGENERATE_TOLERANCE: f32 = 1e-6;
 
 // The vertex class holds the index of a vertex in the list of points. It also has the index of the
 // previousand next vertex as well as a flag indicating if it is convex and if it is an ear.
@@ -18,7 +22,7 @@ struct Vertex {
 impl Vertex {
     // Contructor
     fn new(index: usize, prev_index: usize, next_index: usize) -> Vertex {
-        Vertex { index: index, prev_index: prev_index, next_index: next_index,
+        Vertex { index, prev_index, next_index,
                  is_convex: false, is_ear: false }
     }
 }
@@ -59,9 +63,19 @@ if compare_to_line(v_test, v0, v1) != LineCompare::Left { return false; }
     true
 }
 
-// Determine if a point is an ear tip.
+// Determine if a point is on a line segment, not counting its ends.
+fn is_inside_segment(v_test: &(f32, f32), v0: &(f32, f32), v1: &(f32, f32)) -> bool {
+    if compare_to_line(v_test, v0, v1) != LineCompare::On || v_test == v0 || v_test == v1 {
+        return false;
+    }
+    let along = (v_test.0 - v0.0) * (v1.0 - v0.0) + (v_test.1 - v0.1) * (v1.1 - v0.1);
+    along > 0f32 && along < (v1.0 - v0.0).powi(2) + (v1.1 - v0.1).powi(2)
+}
+
+// Determine if a point is an ear tip. A reflex vertex on the diagonal that would cut the ear off
+// blocks it too, cutting there would leave the rest of the polygon touching itself.
 // note: this function assumes v_test is convex!
-fn is_ear(points: &Vec<(f32, f32)>, reflex_set: &HashSet<usize>, v_test: &Vertex) -> bool {
+fn is_ear(points: &[(f32, f32)], reflex_set: &BTreeSet<usize>, v_test: &Vertex) -> bool {
     for r in reflex_set {
         if *r == v_test.prev_index || *r == v_test.next_index {
             continue;
@@ -69,7 +83,8 @@ fn is_ear(points: &Vec<(f32, f32)>, reflex_set: &HashSet<usize>, v_test: &Vertex
         if is_in_triangle(&points[*r], 
                           &points[v_test.prev_index], 
                           &points[v_test.index], 
-                          &points[v_test.next_index]) {
+                          &points[v_test.next_index]) ||
+           is_inside_segment(&points[*r], &points[v_test.next_index], &points[v_test.prev_index]) {
             return false;
         }
     }
@@ -96,24 +111,26 @@ enum VertexType {
 }
 
 // Classify a vertex as reflex, convex or ear.
-fn classify_vertex(points: &Vec<(f32, f32)>, v_test: &mut Vertex,
-                   reflex_set: &HashSet<usize>) -> VertexType {
+fn classify_vertex(points: &[(f32, f32)], v_test: &mut Vertex,
+                   reflex_set: &BTreeSet<usize>) -> VertexType {
     if is_convex(&points[v_test.index], &points[v_test.prev_index], &points[v_test.next_index]) {
-        if is_ear(&points, reflex_set, &v_test) {
-             return VertexType::Ear;
+        if is_ear(points, reflex_set, v_test) {
+             VertexType::Ear
         } else {
-            return VertexType::Convex;
+            VertexType::Convex
         }
     } else {
-        return VertexType::Reflex;
+        VertexType::Reflex
     }
 }
 
-// Fill the ear set and the reflex set with the indices of the corresponding vertices.
-fn fill_sets(points: &Vec<(f32, f32)>,
-             vertices: &mut Vec<Vertex>) -> (HashSet<usize>, HashSet<usize>) {
-    let mut ear_set = HashSet::new();
-    let mut reflex_set = HashSet::new();
+// Fill the ear set and the reflex set with the indices of the corresponding vertices. The sets
+// are ordered so the lowest ear is always clipped first and the same polygon always gives the
+// same triangles.
+fn fill_sets(points: &[(f32, f32)],
+             vertices: &mut [Vertex]) -> (BTreeSet<usize>, BTreeSet<usize>) {
+    let mut ear_set = BTreeSet::new();
+    let mut reflex_set = BTreeSet::new();
 
     for v in vertices.iter_mut() {
         if classify_vertex(points, v, &reflex_set) == VertexType::Reflex {
@@ -121,7 +138,7 @@ fn fill_sets(points: &Vec<(f32, f32)>,
         }
     }
 
-    for mut v in vertices.iter_mut() {
+    for v in vertices.iter_mut() {
         match classify_vertex(points, v, &reflex_set) {
             VertexType::Reflex => (),
             VertexType::Convex => {
@@ -139,7 +156,7 @@ fn fill_sets(points: &Vec<(f32, f32)>,
 
 // Connect the 2 points on either side of a point, effectivly removing that point from the linked
 // list.
-fn remove_vertex(vertices: &mut Vec<Vertex>, i_test: usize) {
+fn remove_vertex(vertices: &mut [Vertex], i_test: usize) {
     let prev_index = vertices[i_test].prev_index;
     let next_index = vertices[i_test].next_index;
     vertices[prev_index].next_index = next_index;
@@ -153,12 +170,211 @@ fn push_triangle(triangles: &mut Vec<usize>, i_test: usize, i_prev: usize, i_nex
     triangles.push(i_next);
 }
 
+// Whether b adds nothing to the outline between a and c: it is on the line through them, or the
+// tip of a spike that goes out and comes straight back.
+fn is_redundant(a: (f32, f32), b: (f32, f32), c: (f32, f32), tolerance: f32) -> bool {
+    let length = (c.0 - a.0).hypot(c.1 - a.1);
+    if length <= tolerance {
+        return true;
+    }
+    let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+    cross.abs() / length <= tolerance
+}
+
+// The indices of the points that are left after merging points that are within tolerance of the
+// one before and dropping redundant vertices. Flattened curves have lots of both. Without a
+// tolerance it is a small fraction of the polygon's size.
+fn sanitize(points: &[(f32, f32)], tolerance: Option<f32>) -> Result<Vec<usize>, TrdlError> {
+    if points.len() < 3 {
+        return Err(TrdlError::NotEnoughVertices);
+    }
+    check_finite(points)?;
+    let (mut min, mut max) = (points[0], points[0]);
+    for p in points {
+        min = (min.0.min(p.0), min.1.min(p.1));
+        max = (max.0.max(p.0), max.1.max(p.1));
+    }
+    let size = (max.0 - min.0).max(max.1 - min.1);
+    let tolerance = tolerance.unwrap_or(size * DEGENERATE_TOLERANCE).max(0f32);
+    let close = |i: usize, j: usize| {
+        (points[i].0 - points[j].0).hypot(points[i].1 - points[j].1) <= tolerance
+    };
+
+    let mut kept: Vec<usize> = Vec::with_capacity(points.len());
+    'points: for i in 0..points.len() {
+        // dropping a vertex can make the one before it redundant too
+        while let Some(&last) = kept.last() {
+            if close(last, i) {
+                continue 'points;
+            }
+            if kept.len() < 2 || !is_redundant(points[kept[kept.len() - 2]], points[last], points[i], tolerance) {
+                break;
+            }
+            kept.pop();
+        }
+        kept.push(i);
+    }
+    // the same again where the polygon closes
+    loop {
+        let n = kept.len();
+        if n < 3 {
+            break;
+        }
+        if close(kept[n - 1], kept[0]) ||
+           is_redundant(points[kept[n - 2]], points[kept[n - 1]], points[kept[0]], tolerance) {
+            kept.pop();
+        } else if is_redundant(points[kept[n - 1]], points[kept[0]], points[kept[1]], tolerance) {
+            kept.remove(0);
+        } else {
+            break;
+        }
+    }
+
+    let area = (0..kept.len()).map(|i| {
+        let (p, q) = (points[kept[i]], points[kept[(i + 1) % kept.len()]]);
+        p.0 * q.1 - q.0 * p.1
+    }).sum::<f32>() / 2f32;
+    if kept.len() < 3 || area.abs() <= tolerance * size {
+        return Err(TrdlError::ZeroAreaPolygon);
+    }
+    Ok(kept)
+}
+
 /// Accept a vector of points representing vertices of a polygon with counter-clockwise ordering.
-/// Remove ear tips one at a time adding triangles to the triangle list until the last triangle
-/// which is added to the triangle list, creating a triangulation of the polygon.
-/// Return a list of indices into the original passed in list of vertices, every three indices is a
-/// triangle. Or return an error if a problem occurred.
-pub fn triangulate(points: &Vec<(f32, f32)>) -> Result<Vec<usize>, TrdlError> {
+/// Repeated points and vertices on a straight line between their neighbours are dropped first,
+/// then ear tips are removed one at a time adding triangles to the triangle list until the last
+/// triangle which is added to the triangle list, creating a triangulation of the polygon.
+/// Return a mesh of the passed in vertices, every three indices is a triangle, or return an error
+/// if a problem occurred. A polygon that encloses no area gives `TrdlError::ZeroAreaPolygon`, one
+/// with a NaN or infinite coordinate `TrdlError::NonFiniteCoordinate`.
+pub fn triangulate(points: &[(f32, f32)]) -> Result<Mesh, TrdlError> {
+    triangulate_kept(points, sanitize(points, None)?)
+}
+
+/// Like `triangulate`, with points closer than `tolerance` counting as the same point and
+/// vertices within it of the line between their neighbours dropped. The default tolerance is a
+/// millionth of the polygon's size.
+pub fn triangulate_with_tolerance(points: &[(f32, f32)], tolerance: f32) -> Result<Mesh, TrdlError> {
+    triangulate_kept(points, sanitize(points, Some(tolerance))?)
+}
+
+// Triangulate the polygon made of the kept points.
+fn triangulate_kept(points: &[(f32, f32)], kept: Vec<usize>) -> Result<Mesh, TrdlError> {
+    let polygon: Vec<(f32, f32)> = kept.iter().map(|&i| points[i]).collect();
+    let indices = triangulate_indices(&polygon)?.into_iter().map(|i| kept[i]).collect();
+    Ok(Mesh::new(points.to_vec(), indices))
+}
+
+// Triangulate keeping every vertex, for path outlines where the edges between vertices may be
+// curves.
+pub(crate) fn triangulate_exact(points: &[(f32, f32)]) -> Result<Mesh, TrdlError> {
+    triangulate_indices(points).map(|indices| Mesh::new(points.to_vec(), indices))
+}
+
+/// What went wrong tessellating a path, given with `TrdlError::Tessellation` by a drawing with
+/// diagnostics on, see `Drawing::set_diagnostics`.
+#[derive(Debug)]
+pub struct TessellationDiagnostics {
+    /// The error tessellation failed with.
+    pub error: TrdlError,
+    /// The outline that was tessellated, the path's vertices with its curves split.
+    pub points: Vec<(f32, f32)>,
+    /// Indices in points of the vertices at fault.
+    pub vertices: Vec<usize>,
+    /// What could fix it, e.g. "vertices 12 and 13 are coincident, remove one of them".
+    pub suggestions: Vec<String>
+}
+
+// Look for what stops points from being tessellated: points on top of each other, edges that
+// cross and outlines with no area. The closing edge is only checked for closed outlines.
+pub(crate) fn diagnose(points: &[(f32, f32)], is_closed: bool, error: TrdlError) -> TessellationDiagnostics {
+    let n = points.len();
+    let mut vertices = BTreeSet::new();
+    let mut suggestions = Vec::new();
+    if n < 3 && is_closed {
+        suggestions.push(format!("the outline has only {} points, a filled path needs at least 3", n));
+    }
+    if n == 0 {
+        return TessellationDiagnostics { error, points: Vec::new(), vertices: Vec::new(), suggestions };
+    }
+    let (mut min, mut max) = (points[0], points[0]);
+    for p in points {
+        min = (min.0.min(p.0), min.1.min(p.1));
+        max = (max.0.max(p.0), max.1.max(p.1));
+    }
+    let size = (max.0 - min.0).max(max.1 - min.1);
+    let tolerance = size * DEGENERATE_TOLERANCE;
+    let edges = if is_closed { n } else { n - 1 };
+    let mut crossed = false;
+    for i in 0..edges {
+        let j = (i + 1) % n;
+        if (points[i].0 - points[j].0).hypot(points[i].1 - points[j].1) <= tolerance {
+            vertices.extend([i, j]);
+            suggestions.push(format!("vertices {} and {} are coincident, remove one of them", i, j));
+        }
+    }
+    if is_closed && n > 3 {
+        let crosses = |a: (f32, f32), b: (f32, f32), c: (f32, f32), d: (f32, f32)| {
+            let side = |p: &(f32, f32), q: &(f32, f32), r: &(f32, f32)| compare_to_line(p, q, r);
+            side(&c, &a, &b) != side(&d, &a, &b) && side(&a, &c, &d) != side(&b, &c, &d) &&
+                side(&c, &a, &b) != LineCompare::On && side(&d, &a, &b) != LineCompare::On
+        };
+        for i in 0..n {
+            // edges next to each other share a vertex, so start two on
+            for j in i + 2..n {
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+                if crosses(points[i], points[(i + 1) % n], points[j], points[(j + 1) % n]) {
+                    crossed = true;
+                    vertices.extend([i, (i + 1) % n, j, (j + 1) % n]);
+                    suggestions.push(format!("edges {}-{} and {}-{} cross, split the path where they do or \
+                                              draw it with a StencilFill", i, (i + 1) % n, j, (j + 1) % n));
+                }
+            }
+        }
+    }
+    let area = (0..n).map(|i| {
+        let (p, q) = (points[i], points[(i + 1) % n]);
+        p.0 * q.1 - q.0 * p.1
+    }).sum::<f32>() / 2f32;
+    // the loops of a figure eight cancel out, which says nothing new
+    if is_closed && !crossed && area.abs() <= tolerance * size {
+        suggestions.push("the outline encloses no area, its points may all be on one line".to_string());
+    }
+    TessellationDiagnostics { error, points: points.to_vec(), vertices: vertices.into_iter().collect(), suggestions }
+}
+
+// Add points inside a triangulated polygon to its mesh, each splitting the triangle it is in into
+// three that meet at it. The new vertices go after the polygon's, in the order of points, and the
+// first edge of each new triangle is an edge of the one it came from.
+pub(crate) fn insert_points(mesh: &mut Mesh, points: &[(f32, f32)]) -> Result<(), TrdlError> {
+    check_finite(points)?;
+    for &p in points {
+        let inside = |t: &[usize]| (0..3).all(|k| {
+            compare_to_line(&p, &mesh.vertices[t[k]], &mesh.vertices[t[(k + 1) % 3]]) != LineCompare::Right
+        });
+        let t = mesh.indices.chunks(3).position(inside).ok_or(TrdlError::PointOutsidePolygon)?;
+        let (a, b, c) = (mesh.indices[3 * t], mesh.indices[3 * t + 1], mesh.indices[3 * t + 2]);
+        let s = mesh.vertices.len();
+        mesh.vertices.push(p);
+        mesh.indices[3 * t + 2] = s;
+        mesh.indices.extend_from_slice(&[b, c, s, c, a, s]);
+    }
+    Ok(())
+}
+
+// NaN compares as neither left nor right of anything, which would make nonsense of the ear sets.
+fn check_finite(points: &[(f32, f32)]) -> Result<(), TrdlError> {
+    if points.iter().all(|p| p.0.is_finite() && p.1.is_finite()) {
+        Ok(())
+    } else {
+        Err(TrdlError::NonFiniteCoordinate)
+    }
+}
+
+fn triangulate_indices(points: &[(f32, f32)]) -> Result<Vec<usize>, TrdlError> {
+    check_finite(points)?;
     let mut n = points.len();
     if n < 4 {
         if n == 3 {
@@ -193,25 +409,21 @@ pub fn triangulate(points: &Vec<(f32, f32)>) -> Result<Vec<usize>, TrdlError> {
         n -= 1;
 
         if n == 3 {
-            let ear_index = match ear_set.iter().next() {
-                Some(i) => *i,
-                None => return Err(TrdlError::NonSimplePolygon)
-            };
-            let prev_index;
-            let next_index;
-            {
-                let vertex = &vertices[ear_index];
-                prev_index = vertex.prev_index;
-                next_index = vertex.next_index;
+            // the last triangle, or a line when it ends on points in a row (as along either side
+            // of a cut to a hole) and the other triangles already cover everything
+            let vertex = &vertices[prev_index];
+            match compare_to_line(&points[prev_index], &points[vertex.prev_index], &points[vertex.next_index]) {
+                LineCompare::Right => push_triangle(&mut triangles, prev_index, vertex.prev_index, vertex.next_index),
+                LineCompare::On => (),
+                LineCompare::Left => return Err(TrdlError::NonSimplePolygon)
             }
-            push_triangle(&mut triangles, ear_index, prev_index, next_index);
             return Ok(triangles);
         }
 
         {
-            let ref mut v_prev = vertices[prev_index];
+            let v_prev = &mut vertices[prev_index];
             if v_prev.is_ear {
-                if !is_ear(&points, &reflex_set, v_prev) {
+                if !is_ear(points, &reflex_set, v_prev) {
                     v_prev.is_ear = false;
                     ear_set.remove(&prev_index);
                 }
@@ -223,7 +435,8 @@ pub fn triangulate(points: &Vec<(f32, f32)>) -> Result<Vec<usize>, TrdlError> {
                         reflex_set.remove(&prev_index);
                     }
                     
-                    if is_ear(&points, &reflex_set, v_prev) {
+                    if is_ear(points, &reflex_set, v_prev) {
+                        v_prev.is_ear = true;
                         ear_set.insert(prev_index);
                     }
                 }
@@ -231,7 +444,7 @@ pub fn triangulate(points: &Vec<(f32, f32)>) -> Result<Vec<usize>, TrdlError> {
         }
 
         {
-            let ref mut v_next = vertices[next_index];
+            let v_next = &mut vertices[next_index];
             if v_next.is_ear {
                 if !is_ear(points, &reflex_set, v_next) {
                     v_next.is_ear = false;
@@ -246,6 +459,7 @@ pub fn triangulate(points: &Vec<(f32, f32)>) -> Result<Vec<usize>, TrdlError> {
                     }
                     
                     if is_ear(points, &reflex_set, v_next) {
+                        v_next.is_ear = true;
                         ear_set.insert(next_index);
                     }
                 }
@@ -259,10 +473,11 @@ mod tests {
     use super::compare_to_line;
     use super::is_convex;
     use super::is_in_triangle;
-    use super::triangulate;
+    use super::{diagnose, insert_points, triangulate, triangulate_exact, triangulate_with_tolerance};
     use super::LineCompare;
+    use super::super::TrdlError;
 
-    //#[test]
+    #[test]
     fn test_compare_to_line() {
         let v0 = (0.1f32,  0.1f32);
         let v1 = (0.5f32, 0.5f32);
@@ -322,7 +537,7 @@ mod tests {
         false
     }
 
-    fn is_same_triangulation(triangles: &Vec<usize>,
+    fn is_same_triangulation(triangles: &[usize],
                              mut expected: Vec<(usize, usize, usize)>) -> bool {
         let n = triangles.len() / 3;
         for i in 0..n {
@@ -351,7 +566,7 @@ mod tests {
                            (1.0f32, 1.0f32),
                            (0.0f32, 1.0f32) ];
 
-        let triangles = triangulate(&points).unwrap();
+        let triangles = triangulate(&points).unwrap().indices;
 
         for i in 0..(triangles.len() / 3) {
             println!("{}, {}, {}", triangles[i*3], triangles[i*3+1], triangles[i*3+2]);
@@ -371,7 +586,7 @@ mod tests {
                            (5.0f32, 4.0f32),
                            (0.0f32, 4.0f32) ];
 
-        let triangles = triangulate(&points).unwrap();
+        let triangles = triangulate(&points).unwrap().indices;
 
         for i in 0..(triangles.len() / 3) {
             println!("{}, {}, {}", triangles[i*3], triangles[i*3+1], triangles[i*3+2]);
@@ -379,4 +594,115 @@ mod tests {
 
         assert!(is_same_triangulation(&triangles, vec![(0, 1, 2), (0, 2, 4), (4, 2, 3)]));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_triangulate_is_deterministic() {
+        let star: Vec<(f32, f32)> = (0..10).map(|i| {
+            let angle = i as f32 * std::f32::consts::PI / 5f32;
+            let radius = if i % 2 == 0 { 2f32 } else { 1f32 };
+            (radius * angle.cos(), radius * angle.sin())
+        }).collect();
+        let circle: Vec<(f32, f32)> = (0..64).map(|i| {
+            let angle = i as f32 * std::f32::consts::PI / 32f32;
+            (angle.cos(), angle.sin())
+        }).collect();
+        // teeth of different lengths, lots of reflex vertices
+        let mut comb = vec![(0f32, 0f32), (10f32, 0f32)];
+        for i in 0..5 {
+            let (x, y) = (10f32 - 2f32 * i as f32, 1f32 + 0.2f32 * (i * i) as f32);
+            comb.extend_from_slice(&[(x, 5f32), (x - 1f32, 5f32), (x - 1f32, y), (x - 2f32, y + 0.5f32)]);
+        }
+        for points in &[star, circle, comb] {
+            let first = triangulate(points).unwrap().indices;
+            for _ in 0..10 {
+                assert_eq!(triangulate(points).unwrap().indices, first);
+            }
+        }
+    }
+
+    #[test]
+    fn test_triangulate_points_in_a_row() {
+        // an E from a font, the stem's side is in a row with the inner corners
+        let points = vec![(462f32, 613f32), (473f32, 689f32), (100f32, 689f32), (100f32, 0f32), (481f32, 0f32),
+                          (481f32, 76f32), (195f32, 76f32), (195f32, 312f32), (427f32, 312f32), (427f32, 388f32),
+                          (195f32, 388f32), (195f32, 613f32)];
+        let mesh = triangulate(&points).unwrap();
+        assert_eq!(mesh.area(), 125_533f32);
+        for [a, b, c] in mesh.triangles() {
+            assert!((b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1) > 0f32);
+        }
+    }
+
+    #[test]
+    fn test_triangulate_degenerate_points() {
+        // a square with a repeated corner, a point halfway along the top and a spike
+        let points = vec![(0f32, 0f32), (1f32, 0f32), (1f32, 0f32), (1f32, 1f32), (0.5f32, 1f32),
+                          (0f32, 1f32), (0f32, 2f32), (0f32, 1f32)];
+        let mesh = triangulate(&points).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        for &i in &mesh.indices {
+            assert!(i == 0 || i == 1 || i == 3 || i == 5);
+        }
+
+        let line = vec![(0f32, 0f32), (1f32, 1f32), (2f32, 2f32), (3f32, 3f32)];
+        match triangulate(&line) {
+            Err(TrdlError::ZeroAreaPolygon) => (),
+            other => panic!("expected ZeroAreaPolygon, got {:?}", other.map(|mesh| mesh.indices))
+        }
+    }
+
+    #[test]
+    fn test_insert_points() {
+        let mut mesh = triangulate_exact(&[(0f32, 0f32), (4f32, 0f32), (4f32, 4f32), (0f32, 4f32)]).unwrap();
+        insert_points(&mut mesh, &[(1f32, 2f32)]).unwrap();
+        // the triangle holding the point is split in three, keeping the area
+        assert_eq!(mesh.vertices[4], (1f32, 2f32));
+        assert_eq!(mesh.triangle_count(), 4);
+        assert_eq!(mesh.indices.iter().filter(|&&i| i == 4).count(), 3);
+        assert!((mesh.area() - 16f32).abs() < 1e-5);
+        match insert_points(&mut mesh, &[(5f32, 2f32)]) {
+            Err(TrdlError::PointOutsidePolygon) => (),
+            other => panic!("expected PointOutsidePolygon, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_diagnose() {
+        // a bow tie with a repeated point
+        let points = [(0f32, 0f32), (2f32, 2f32), (2f32, 2f32), (2f32, 0f32), (0f32, 2f32)];
+        let diagnostics = diagnose(&points, true, TrdlError::NonSimplePolygon);
+        assert_eq!(diagnostics.vertices, vec![0, 1, 2, 3, 4]);
+        assert_eq!(diagnostics.suggestions.len(), 2);
+        assert!(diagnostics.suggestions[0].starts_with("vertices 1 and 2 are coincident"));
+        assert!(diagnostics.suggestions[1].starts_with("edges 0-1 and 3-4 cross"));
+
+        let line = [(0f32, 0f32), (1f32, 1f32), (2f32, 2f32)];
+        let diagnostics = diagnose(&line, true, TrdlError::ZeroAreaPolygon);
+        assert!(diagnostics.vertices.is_empty());
+        assert_eq!(diagnostics.suggestions.len(), 1);
+        assert_eq!(diagnostics.points, line.to_vec());
+    }
+
+    #[test]
+    fn test_tolerance() {
+        // a square in normalized coordinates with a dent far smaller than the default tolerance
+        // of a large polygon, but a real feature at this size
+        let small = [(0f32, 0f32), (1e-3f32, 0f32), (1e-3f32, 1e-3f32), (0.5e-3f32, 0.99e-3f32), (0f32, 1e-3f32)];
+        assert_eq!(triangulate(&small).unwrap().triangle_count(), 3);
+        // a coarse tolerance flattens the dent away
+        assert_eq!(triangulate_with_tolerance(&small, 1e-4f32).unwrap().triangle_count(), 2);
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let points = [(0f32, 0f32), (1f32, 0f32), (f32::NAN, 1f32), (0f32, 1f32)];
+        match triangulate(&points) {
+            Err(TrdlError::NonFiniteCoordinate) => (),
+            other => panic!("expected NonFiniteCoordinate, got {:?}", other.map(|mesh| mesh.indices))
+        }
+        match triangulate_exact(&points) {
+            Err(TrdlError::NonFiniteCoordinate) => (),
+            other => panic!("expected NonFiniteCoordinate, got {:?}", other.map(|mesh| mesh.indices))
+        }
+    }
+}
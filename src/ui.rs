@@ -0,0 +1,645 @@
+//! A minimal widget toolkit drawn with paths: labels, buttons, sliders and checkboxes laid out in
+//! rows and columns, enough for demo control panels. Widgets are placed in drawing coordinates
+//! and drawn on a layer of their own. Text needs a font, without one labels are left out.
+
+use super::gl2d::drawing::{Drawing, Path, PathId, Window};
+use super::gl2d::layer::LayerId;
+use super::camera::Camera;
+use super::color::Color;
+use super::font::Font;
+use super::input::{InputEvent, MouseButton};
+use super::TrdlError;
+
+// a rectangle in drawing coordinates, (min, max) with y up
+type Rect = ((f32, f32), (f32, f32));
+
+/// Identifies a widget or container of a `Ui`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WidgetId(usize);
+
+/// Which way a container lays out its children: rows left to right, columns top to bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Row,
+    Column
+}
+
+/// Where a container puts its children across its direction, Start being the top of a row and
+/// the left of a column. Stretch (the default) makes them as wide or tall as the container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    Stretch
+}
+
+/// What a widget did because of an input event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UiEvent {
+    /// A button was pressed and released.
+    Clicked(WidgetId),
+    /// A slider was moved to a new value.
+    ValueChanged(WidgetId, f32),
+    /// A checkbox was checked or unchecked.
+    Toggled(WidgetId, bool)
+}
+
+/// Colors and sizes of widgets, sizes in drawing units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiStyle {
+    /// Button faces, checkboxes and slider tracks.
+    pub face: Color,
+    pub hovered: Color,
+    pub pressed: Color,
+    /// Slider fills and knobs and check marks.
+    pub accent: Color,
+    pub border: Color,
+    pub text: Color,
+    pub text_size: f32,
+    /// Room inside buttons and containers.
+    pub padding: f32,
+    /// Room between the children of a container.
+    pub spacing: f32,
+    /// Height of buttons, sliders and checkboxes.
+    pub control_height: f32,
+    pub slider_width: f32
+}
+
+impl Default for UiStyle {
+    fn default() -> Self {
+        UiStyle {
+            face: Color::rgb(0.85f32, 0.85f32, 0.85f32),
+            hovered: Color::rgb(0.93f32, 0.93f32, 0.93f32),
+            pressed: Color::rgb(0.7f32, 0.7f32, 0.7f32),
+            accent: Color::rgb(0.2f32, 0.45f32, 0.85f32),
+            border: Color::rgb(0.35f32, 0.35f32, 0.35f32),
+            text: Color::rgb(0.1f32, 0.1f32, 0.1f32),
+            text_size: 14f32,
+            padding: 6f32,
+            spacing: 6f32,
+            control_height: 24f32,
+            slider_width: 140f32
+        }
+    }
+}
+
+enum Kind {
+    Container { direction: Direction, align: Align, children: Vec<WidgetId> },
+    Label { text: String },
+    Button { text: String },
+    Slider { min: f32, max: f32, value: f32 },
+    Checkbox { text: String, checked: bool }
+}
+
+struct Node {
+    kind: Kind,
+    // share of a container's spare room along its direction
+    grow: f32,
+    rect: Rect
+}
+
+/// A tree of widgets in containers, with a column at its root.
+pub struct Ui {
+    nodes: Vec<Node>,
+    style: UiStyle,
+    font: Option<Font>,
+    top_left: (f32, f32),
+    size: Option<(f32, f32)>,
+    hovered: Option<WidgetId>,
+    pressed: Option<WidgetId>,
+    layer: Option<LayerId>,
+    path_ids: Vec<PathId>,
+    // whether the paths in the drawing are out of date
+    changed: bool
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Ui::new()
+    }
+}
+
+impl Ui {
+    /// An empty ui in the default style, its top left corner at the origin.
+    pub fn new() -> Self {
+        let root = Node {
+            kind: Kind::Container { direction: Direction::Column, align: Align::Stretch, children: Vec::new() },
+            grow: 0f32,
+            rect: ((0f32, 0f32), (0f32, 0f32))
+        };
+        let mut ui = Ui {
+            nodes: vec![root],
+            style: UiStyle::default(),
+            font: None,
+            top_left: (0f32, 0f32),
+            size: None,
+            hovered: None,
+            pressed: None,
+            layer: None,
+            path_ids: Vec::new(),
+            changed: true
+        };
+        ui.layout();
+        ui
+    }
+
+    /// The column everything else goes in.
+    pub fn root(&self) -> WidgetId {
+        WidgetId(0)
+    }
+
+    /// The style.
+    pub fn style(&self) -> &UiStyle {
+        &self.style
+    }
+
+    /// Change the style.
+    pub fn set_style(&mut self, style: UiStyle) {
+        self.style = style;
+        self.layout();
+    }
+
+    /// Set the font text is drawn in.
+    pub fn set_font(&mut self, font: Font) {
+        self.font = Some(font);
+        self.layout();
+    }
+
+    /// Put the top left corner of the root at a point, and give it a size or None to make it
+    /// just big enough for its children.
+    pub fn place(&mut self, top_left: (f32, f32), size: Option<(f32, f32)>) {
+        self.top_left = top_left;
+        self.size = size;
+        self.layout();
+    }
+
+    /// Add a container laying out its children left to right.
+    pub fn add_row(&mut self, parent: WidgetId) -> WidgetId {
+        self.add(parent, Kind::Container { direction: Direction::Row, align: Align::Stretch, children: Vec::new() })
+    }
+
+    /// Add a container laying out its children top to bottom.
+    pub fn add_column(&mut self, parent: WidgetId) -> WidgetId {
+        self.add(parent, Kind::Container { direction: Direction::Column, align: Align::Stretch, children: Vec::new() })
+    }
+
+    /// Add a line of text.
+    pub fn add_label(&mut self, parent: WidgetId, text: &str) -> WidgetId {
+        self.add(parent, Kind::Label { text: text.to_string() })
+    }
+
+    /// Add a button, which sends `Clicked` when pressed and released.
+    pub fn add_button(&mut self, parent: WidgetId, text: &str) -> WidgetId {
+        self.add(parent, Kind::Button { text: text.to_string() })
+    }
+
+    /// Add a horizontal slider from min to max, which sends `ValueChanged` while dragged.
+    pub fn add_slider(&mut self, parent: WidgetId, min: f32, max: f32, value: f32) -> WidgetId {
+        self.add(parent, Kind::Slider { min, max, value: clamp_between(value, min, max) })
+    }
+
+    /// Add a checkbox with text after it, which sends `Toggled` when clicked.
+    pub fn add_checkbox(&mut self, parent: WidgetId, text: &str, checked: bool) -> WidgetId {
+        self.add(parent, Kind::Checkbox { text: text.to_string(), checked })
+    }
+
+    // Add a node to a container, or to the root if parent is not a container.
+    fn add(&mut self, parent: WidgetId, kind: Kind) -> WidgetId {
+        let id = WidgetId(self.nodes.len());
+        self.nodes.push(Node { kind, grow: 0f32, rect: ((0f32, 0f32), (0f32, 0f32)) });
+        let parent = match self.nodes.get(parent.0).map(|node| &node.kind) {
+            Some(Kind::Container { .. }) => parent.0,
+            _ => 0
+        };
+        if let Kind::Container { ref mut children, .. } = self.nodes[parent].kind {
+            children.push(id);
+        }
+        self.layout();
+        id
+    }
+
+    /// Set how a container places its children across its direction.
+    pub fn set_align(&mut self, container: WidgetId, align: Align) {
+        if let Some(Kind::Container { align: ref mut current, .. }) = self.nodes.get_mut(container.0).map(|node| &mut node.kind) {
+            *current = align;
+        }
+        self.layout();
+    }
+
+    /// Let a widget take a share of the room its container has to spare along its direction,
+    /// shared out in proportion to grow. 0 (the default) keeps it at its natural size.
+    pub fn set_grow(&mut self, id: WidgetId, grow: f32) {
+        if let Some(node) = self.nodes.get_mut(id.0) {
+            node.grow = grow.max(0f32);
+        }
+        self.layout();
+    }
+
+    /// Change the text of a label, button or checkbox.
+    pub fn set_text(&mut self, id: WidgetId, text: &str) {
+        match self.nodes.get_mut(id.0).map(|node| &mut node.kind) {
+            Some(Kind::Label { text: ref mut current }) | Some(Kind::Button { text: ref mut current }) |
+            Some(Kind::Checkbox { text: ref mut current, .. }) => *current = text.to_string(),
+            _ => return
+        }
+        self.layout();
+    }
+
+    /// The value of a slider.
+    pub fn value(&self, id: WidgetId) -> Option<f32> {
+        match self.nodes.get(id.0).map(|node| &node.kind) {
+            Some(&Kind::Slider { value, .. }) => Some(value),
+            _ => None
+        }
+    }
+
+    /// Move a slider, the value is kept between its min and max.
+    pub fn set_value(&mut self, id: WidgetId, value: f32) {
+        if let Some(Kind::Slider { min, max, value: ref mut current }) = self.nodes.get_mut(id.0).map(|node| &mut node.kind) {
+            *current = clamp_between(value, *min, *max);
+            self.changed = true;
+        }
+    }
+
+    /// Whether a checkbox is checked.
+    pub fn checked(&self, id: WidgetId) -> Option<bool> {
+        match self.nodes.get(id.0).map(|node| &node.kind) {
+            Some(&Kind::Checkbox { checked, .. }) => Some(checked),
+            _ => None
+        }
+    }
+
+    /// Check or uncheck a checkbox.
+    pub fn set_checked(&mut self, id: WidgetId, checked: bool) {
+        if let Some(Kind::Checkbox { checked: ref mut current, .. }) = self.nodes.get_mut(id.0).map(|node| &mut node.kind) {
+            *current = checked;
+            self.changed = true;
+        }
+    }
+
+    /// Where a widget was laid out, (min, max) in drawing coordinates.
+    pub fn rect(&self, id: WidgetId) -> Option<((f32, f32), (f32, f32))> {
+        self.nodes.get(id.0).map(|node| node.rect)
+    }
+
+    /// Whether the mouse is over a widget or dragging one, so the event should not also pan the
+    /// drawing or pick paths under the ui.
+    pub fn has_pointer(&self) -> bool {
+        self.hovered.is_some() || self.pressed.is_some()
+    }
+
+    /// Update the widgets for an input event, with the camera of the drawing they are in, and
+    /// return what they did. Widgets respond to the left mouse button.
+    pub fn handle_event(&mut self, event: &InputEvent, camera: &Camera) -> Vec<UiEvent> {
+        let mut events = Vec::new();
+        match *event {
+            InputEvent::MouseMove { position } => {
+                let point = camera.to_drawing(position);
+                let hit = self.hit(point);
+                if hit != self.hovered {
+                    self.hovered = hit;
+                    self.changed = true;
+                }
+                if let Some(id) = self.pressed {
+                    events.extend(self.drag_slider(id, point));
+                }
+            }
+            InputEvent::MouseDown { button: MouseButton::Left, position } => {
+                let point = camera.to_drawing(position);
+                self.pressed = self.hit(point);
+                if let Some(id) = self.pressed {
+                    events.extend(self.drag_slider(id, point));
+                    self.changed = true;
+                }
+            }
+            InputEvent::MouseUp { button: MouseButton::Left, position } => {
+                let point = camera.to_drawing(position);
+                if let Some(id) = self.pressed.take() {
+                    if self.hit(point) == Some(id) {
+                        match self.nodes[id.0].kind {
+                            Kind::Button { .. } => events.push(UiEvent::Clicked(id)),
+                            Kind::Checkbox { ref mut checked, .. } => {
+                                *checked = !*checked;
+                                events.push(UiEvent::Toggled(id, *checked));
+                            }
+                            _ => ()
+                        }
+                    }
+                    self.changed = true;
+                }
+            }
+            _ => ()
+        }
+        events
+    }
+
+    // The widget that responds to the mouse at a point.
+    fn hit(&self, point: (f32, f32)) -> Option<WidgetId> {
+        self.nodes.iter().enumerate().rev()
+            .filter(|&(_, node)| matches!(node.kind, Kind::Button { .. } | Kind::Slider { .. } | Kind::Checkbox { .. }))
+            .find(|&(_, node)| {
+                let (min, max) = node.rect;
+                point.0 >= min.0 && point.0 <= max.0 && point.1 >= min.1 && point.1 <= max.1
+            })
+            .map(|(i, _)| WidgetId(i))
+    }
+
+    // Move a slider's knob to a point's x, if the widget is a slider.
+    fn drag_slider(&mut self, id: WidgetId, point: (f32, f32)) -> Option<UiEvent> {
+        let (start, end) = self.track(id);
+        if let Kind::Slider { min, max, ref mut value } = self.nodes[id.0].kind {
+            let t = if end > start { ((point.0 - start) / (end - start)).clamp(0f32, 1f32) } else { 0f32 };
+            let new_value = min + t * (max - min);
+            if new_value != *value {
+                *value = new_value;
+                self.changed = true;
+                return Some(UiEvent::ValueChanged(id, new_value));
+            }
+        }
+        None
+    }
+
+    // The x range a slider's knob moves in, its width less the knob's radius at either end.
+    fn track(&self, id: WidgetId) -> (f32, f32) {
+        let ((x0, _), (x1, _)) = self.nodes[id.0].rect;
+        let radius = self.knob_radius();
+        (x0 + radius, x1 - radius)
+    }
+
+    fn knob_radius(&self) -> f32 {
+        self.style.control_height * 0.35f32
+    }
+
+    /// Put the widgets in a drawing, replacing their paths from the last update if anything
+    /// changed since. They go on a layer added on top of the drawing the first time, call this
+    /// after every `handle_event`.
+    pub fn update<W: Window>(&mut self, drawing: &mut Drawing<'_, W>) -> Result<(), TrdlError> {
+        if !self.changed {
+            return Ok(());
+        }
+        let layer = match self.layer {
+            Some(layer) => layer,
+            None => {
+                let layer = drawing.add_layer();
+                self.layer = Some(layer);
+                layer
+            }
+        };
+        for id in self.path_ids.drain(..) {
+            drawing.remove_path(id);
+        }
+        for path in self.paths() {
+            self.path_ids.push(drawing.add_path_to_layer(layer, path)?);
+        }
+        self.changed = false;
+        Ok(())
+    }
+
+    /// The paths that draw the widgets as they are now, bottom to top.
+    pub fn paths(&self) -> Vec<Path> {
+        let style = &self.style;
+        let mut paths = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let id = Some(WidgetId(i));
+            let ((x0, y0), (x1, y1)) = node.rect;
+            let center = ((x0 + x1) / 2f32, (y0 + y1) / 2f32);
+            match node.kind {
+                Kind::Container { .. } => (),
+                Kind::Label { ref text } => paths.extend(self.text_paths(text, x0, center.1)),
+                Kind::Button { ref text } => {
+                    let face = if self.pressed == id && self.hovered == id {
+                        style.pressed
+                    } else if self.hovered == id {
+                        style.hovered
+                    } else {
+                        style.face
+                    };
+                    paths.push(bordered(Path::rectangle(center, x1 - x0, y1 - y0, 0f32), face, style.border));
+                    let width = self.text_width(text);
+                    paths.extend(self.text_paths(text, center.0 - width / 2f32, center.1));
+                }
+                Kind::Slider { min, max, value } => {
+                    let (start, end) = self.track(WidgetId(i));
+                    let track_height = style.control_height / 6f32;
+                    paths.push(bordered(Path::rectangle(((start + end) / 2f32, center.1), end - start, track_height, 0f32),
+                                        style.face, style.border));
+                    let t = if max > min { (value - min) / (max - min) } else { 0f32 };
+                    let knob = start + t * (end - start);
+                    if knob - start > 0.5f32 {
+                        paths.push(filled(Path::rectangle(((start + knob) / 2f32, center.1), knob - start, track_height, 0f32),
+                                          style.accent));
+                    }
+                    let radius = self.knob_radius();
+                    let fill = if self.hovered == id || self.pressed == id { style.hovered } else { style.face };
+                    paths.push(bordered(Path::ellipse((knob, center.1), radius, radius, 0f32), fill, style.accent));
+                }
+                Kind::Checkbox { ref text, checked } => {
+                    let size = self.box_size();
+                    let fill = if self.hovered == id { style.hovered } else { style.face };
+                    paths.push(bordered(Path::rectangle((x0 + size / 2f32, center.1), size, size, 0f32), fill, style.border));
+                    if checked {
+                        let (bx, by) = (x0, center.1 - size / 2f32);
+                        let mark = Path::new((bx + 0.2f32 * size, by + 0.5f32 * size))
+                            .line_to((bx + 0.42f32 * size, by + 0.25f32 * size))
+                            .line_to((bx + 0.8f32 * size, by + 0.78f32 * size));
+                        paths.push(mark.set_stroke(style.accent.red, style.accent.green, style.accent.blue, 2));
+                    }
+                    paths.extend(self.text_paths(text, x0 + size + style.padding, center.1));
+                }
+            }
+        }
+        paths
+    }
+
+    fn box_size(&self) -> f32 {
+        self.style.control_height * 0.7f32
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        self.font.as_ref().map_or(0f32, |font| font.text_width(text, self.style.text_size))
+    }
+
+    // A line of text starting at x, centered on y.
+    fn text_paths(&self, text: &str, x: f32, y: f32) -> Vec<Path> {
+        let font = match self.font {
+            Some(ref font) => font,
+            None => return Vec::new()
+        };
+        let size = self.style.text_size;
+        let baseline = y - (font.ascender() + font.descender()) / 2f32 * size;
+        font.text_paths(text, (x, baseline), size).into_iter().map(|path| filled(path, self.style.text)).collect()
+    }
+
+    // The smallest size a node fits in.
+    fn natural_size(&self, id: WidgetId) -> (f32, f32) {
+        let style = &self.style;
+        match self.nodes[id.0].kind {
+            Kind::Container { direction, ref children, .. } => {
+                let sizes: Vec<(f32, f32)> = children.iter().map(|&child| self.natural_size(child)).collect();
+                let gaps = style.spacing * children.len().saturating_sub(1) as f32 + 2f32 * style.padding;
+                let main: f32 = sizes.iter().map(|&size| along(direction, size)).sum::<f32>() + gaps;
+                let cross = sizes.iter().map(|&size| across(direction, size)).fold(0f32, f32::max) + 2f32 * style.padding;
+                match direction {
+                    Direction::Row => (main, cross),
+                    Direction::Column => (cross, main)
+                }
+            }
+            Kind::Label { ref text } => {
+                let height = self.font.as_ref().map_or(0f32, |font| font.line_height() * style.text_size);
+                (self.text_width(text), height)
+            }
+            Kind::Button { ref text } => {
+                ((self.text_width(text) + 2f32 * style.padding).max(style.control_height), style.control_height)
+            }
+            Kind::Slider { .. } => (style.slider_width, style.control_height),
+            Kind::Checkbox { ref text, .. } => {
+                let text_width = self.text_width(text);
+                let gap = if text_width > 0f32 { style.padding } else { 0f32 };
+                (self.box_size() + gap + text_width, style.control_height)
+            }
+        }
+    }
+
+    // Lay out the whole tree again, from the root's corner.
+    fn layout(&mut self) {
+        let natural = self.natural_size(self.root());
+        let size = self.size.unwrap_or(natural);
+        let rect = ((self.top_left.0, self.top_left.1 - size.1), (self.top_left.0 + size.0, self.top_left.1));
+        self.layout_node(self.root(), rect);
+        self.changed = true;
+    }
+
+    fn layout_node(&mut self, id: WidgetId, rect: Rect) {
+        self.nodes[id.0].rect = rect;
+        let (direction, align, children) = match self.nodes[id.0].kind {
+            Kind::Container { direction, align, ref children } => (direction, align, children.clone()),
+            _ => return
+        };
+        let padding = self.style.padding;
+        let ((x0, y0), (x1, y1)) = ((rect.0 .0 + padding, rect.0 .1 + padding), (rect.1 .0 - padding, rect.1 .1 - padding));
+        let sizes: Vec<(f32, f32)> = children.iter().map(|&child| self.natural_size(child)).collect();
+        let grows: Vec<f32> = children.iter().map(|&child| self.nodes[child.0].grow).collect();
+        let total_grow: f32 = grows.iter().sum();
+        let inner = match direction {
+            Direction::Row => (x1 - x0, y1 - y0),
+            Direction::Column => (y1 - y0, x1 - x0)
+        };
+        let used = sizes.iter().map(|&size| along(direction, size)).sum::<f32>() +
+                   self.style.spacing * children.len().saturating_sub(1) as f32;
+        let spare = (inner.0 - used).max(0f32);
+
+        let mut position = 0f32;
+        for (i, &child) in children.iter().enumerate() {
+            let main = along(direction, sizes[i]) + if total_grow > 0f32 { spare * grows[i] / total_grow } else { 0f32 };
+            let natural_cross = across(direction, sizes[i]);
+            let (cross, offset) = match align {
+                Align::Start => (natural_cross, 0f32),
+                Align::Center => (natural_cross, (inner.1 - natural_cross) / 2f32),
+                Align::End => (natural_cross, inner.1 - natural_cross),
+                Align::Stretch => (inner.1, 0f32)
+            };
+            // rows go right from the left edge and start at the top, columns go down from the
+            // top and start at the left
+            let child_rect = match direction {
+                Direction::Row => ((x0 + position, y1 - offset - cross), (x0 + position + main, y1 - offset)),
+                Direction::Column => ((x0 + offset, y1 - position - main), (x0 + offset + cross, y1 - position))
+            };
+            self.layout_node(child, child_rect);
+            position += main + self.style.spacing;
+        }
+    }
+}
+
+// A size's extent along a container's direction.
+fn along(direction: Direction, size: (f32, f32)) -> f32 {
+    match direction {
+        Direction::Row => size.0,
+        Direction::Column => size.1
+    }
+}
+
+// A size's extent across a container's direction.
+fn across(direction: Direction, size: (f32, f32)) -> f32 {
+    match direction {
+        Direction::Row => size.1,
+        Direction::Column => size.0
+    }
+}
+
+fn clamp_between(value: f32, a: f32, b: f32) -> f32 {
+    value.max(a.min(b)).min(a.max(b))
+}
+
+fn filled(path: Path, color: Color) -> Path {
+    path.set_fill_color(color.red, color.green, color.blue).set_opacity(color.alpha)
+}
+
+fn bordered(path: Path, fill: Color, border: Color) -> Path {
+    filled(path, fill).set_stroke(border.red, border.green, border.blue, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::camera::Camera;
+    use super::super::input::{InputEvent, MouseButton};
+    use super::{Ui, UiEvent};
+
+    #[test]
+    fn test_layout() {
+        // no font, so buttons are square and the slider sets the width
+        let mut ui = Ui::new();
+        ui.place((10f32, 100f32), None);
+        let root = ui.root();
+        let row = ui.add_row(root);
+        let ok = ui.add_button(row, "OK");
+        let cancel = ui.add_button(row, "Cancel");
+        ui.set_grow(cancel, 1f32);
+        let slider = ui.add_slider(root, 0f32, 1f32, 0.5f32);
+        // 140 wide slider and 6 of padding around the root, rows have padding too
+        assert_eq!(ui.rect(root), Some(((10f32, 100f32 - 78f32), (162f32, 100f32))));
+        assert_eq!(ui.rect(row), Some(((16f32, 58f32), (156f32, 94f32))));
+        assert_eq!(ui.rect(ok), Some(((22f32, 64f32), (46f32, 88f32))));
+        // the cancel button takes the rest of the row
+        assert_eq!(ui.rect(cancel), Some(((52f32, 64f32), (150f32, 88f32))));
+        assert_eq!(ui.rect(slider), Some(((16f32, 28f32), (156f32, 52f32))));
+    }
+
+    #[test]
+    fn test_events() {
+        let mut ui = Ui::new();
+        let root = ui.root();
+        let button = ui.add_button(root, "");
+        let slider = ui.add_slider(root, 0f32, 10f32, 0f32);
+        let checkbox = ui.add_checkbox(root, "", false);
+        let camera = Camera::new();
+        let click = |ui: &mut Ui, position: (f32, f32)| {
+            let mut events = ui.handle_event(&InputEvent::MouseDown { button: MouseButton::Left, position }, &camera);
+            events.extend(ui.handle_event(&InputEvent::MouseUp { button: MouseButton::Left, position }, &camera));
+            events
+        };
+        let center = |ui: &Ui, id| {
+            let ((x0, y0), (x1, y1)) = ui.rect(id).unwrap();
+            ((x0 + x1) / 2f32, (y0 + y1) / 2f32)
+        };
+
+        let at = center(&ui, button);
+        assert_eq!(click(&mut ui, at), vec![UiEvent::Clicked(button)]);
+        let at = center(&ui, checkbox);
+        assert_eq!(click(&mut ui, at), vec![UiEvent::Toggled(checkbox, true)]);
+        assert_eq!(ui.checked(checkbox), Some(true));
+        // clicking the middle of the slider moves it halfway
+        let at = center(&ui, slider);
+        match click(&mut ui, at)[..] {
+            [UiEvent::ValueChanged(id, value)] => assert!(id == slider && (value - 5f32).abs() < 1e-4),
+            ref events => panic!("unexpected events {:?}", events)
+        }
+        // releasing somewhere else is not a click
+        let at = center(&ui, button);
+        ui.handle_event(&InputEvent::MouseDown { button: MouseButton::Left, position: at }, &camera);
+        assert!(ui.has_pointer());
+        assert!(ui.handle_event(&InputEvent::MouseUp { button: MouseButton::Left, position: (500f32, 500f32) },
+                                &camera).is_empty());
+        assert_eq!(ui.paths().len(), 1 + 3 + 2);
+    }
+}
@@ -0,0 +1,273 @@
+//! Golden image tests: canonical scenes are rendered offscreen and compared against the reference
+//! PNGs in tests/golden. Rendering needs an OpenGL 4 context, so the scene test is ignored unless
+//! run with `cargo test -- --ignored` on a machine with a display.
+//!
+//! A missing reference is written from the current rendering. To accept intended changes, run
+//! with TRDL_UPDATE_GOLDEN=1. Failed comparisons write the rendering and a diff image (differing
+//! pixels in red) next to the test binaries, the failure message says where.
+
+extern crate gl;
+extern crate glutin;
+extern crate png;
+extern crate trdl;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::os::raw::c_void;
+use std::path::{Path as FilePath, PathBuf};
+use glutin::{Context, ContextBuilder, GlRequest, Api, PossiblyCurrent};
+use glutin::dpi::PhysicalSize;
+use glutin::event_loop::EventLoop;
+use trdl::{Background, Color, Drawing, ImageData, Path, Window};
+
+const SIZE: u32 = 256;
+// a pixel differs when its perceptual difference from the reference is more than this, from 0
+// for the same color to about 1 for black against white
+const PIXEL_THRESHOLD: f32 = 0.1f32;
+// anti-aliased edges vary a little between drivers, this fraction of pixels may differ
+const MAX_DIFFERENT_FRACTION: f32 = 0.002f32;
+
+struct HeadlessWindow {
+    context: Context<PossiblyCurrent>
+}
+
+impl Window for HeadlessWindow {
+    // the context is made current once and stays current
+    fn set_context(&self) {}
+
+    fn load_fn(&self, addr: &str) -> *const c_void {
+        self.context.get_proc_address(addr) as *const c_void
+    }
+}
+
+#[cfg(unix)]
+fn event_loop() -> EventLoop<()> {
+    use glutin::event_loop::EventLoopBuilder;
+    use glutin::platform::unix::EventLoopBuilderExtUnix;
+    // the test harness runs tests off the main thread
+    EventLoopBuilder::new().with_any_thread(true).build()
+}
+
+#[cfg(not(unix))]
+fn event_loop() -> EventLoop<()> {
+    EventLoop::new()
+}
+
+fn make_window(event_loop: &EventLoop<()>) -> HeadlessWindow {
+    let context = ContextBuilder::new()
+        .with_gl(GlRequest::Specific(Api::OpenGl, (4, 0)))
+        .build_headless(event_loop, PhysicalSize::new(SIZE, SIZE))
+        .unwrap();
+    let context = unsafe { context.make_current() }.unwrap();
+    gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+    HeadlessWindow { context }
+}
+
+// Adds the paths of a scene to a drawing.
+type Scene = fn(&mut Drawing<HeadlessWindow>);
+
+// Filled shapes: polygons, curves and ellipses.
+fn shapes_scene(drawing: &mut Drawing<HeadlessWindow>) {
+    drawing.add_path(Path::rectangle((64f32, 64f32), 80f32, 60f32, 0f32)
+        .set_fill_color(0.9f32, 0.2f32, 0.2f32)).unwrap();
+    drawing.add_path(Path::rectangle((192f32, 64f32), 70f32, 70f32, 0.4f32)
+        .set_fill_color(0.2f32, 0.7f32, 0.3f32)).unwrap();
+    drawing.add_path(Path::ellipse((64f32, 192f32), 50f32, 30f32, 0.3f32)
+        .set_fill_color(0.2f32, 0.3f32, 0.9f32)).unwrap();
+    drawing.add_path(Path::new((150f32, 150f32))
+        .curve_to((170f32, 250f32), (230f32, 250f32), (240f32, 150f32))
+        .line_to((195f32, 170f32))
+        .close_path()
+        .set_fill_color(0.9f32, 0.8f32, 0.1f32)).unwrap();
+}
+
+// Strokes of different widths on lines, curves and arcs, open and closed.
+fn strokes_scene(drawing: &mut Drawing<HeadlessWindow>) {
+    drawing.add_path(Path::new((20f32, 20f32)).line_to((236f32, 60f32))
+        .set_stroke(0f32, 0f32, 0f32, 1)).unwrap();
+    drawing.add_path(Path::new((20f32, 80f32))
+        .curve_to((80f32, 160f32), (160f32, 0f32), (236f32, 90f32))
+        .set_stroke(0.8f32, 0.1f32, 0.1f32, 4)).unwrap();
+    drawing.add_path(Path::new((40f32, 180f32))
+        .arc_to(50f32, 50f32, 0f32, (140f32, 180f32), false, false)
+        .set_stroke(0.1f32, 0.4f32, 0.8f32, 8)).unwrap();
+    drawing.add_path(Path::ellipse((200f32, 190f32), 40f32, 40f32, 0f32)
+        .set_fill_color(1f32, 1f32, 1f32)
+        .set_stroke(0.1f32, 0.6f32, 0.2f32, 3)).unwrap();
+}
+
+// A gradient background with translucent shapes over it.
+fn gradient_scene(drawing: &mut Drawing<HeadlessWindow>) {
+    drawing.set_background(Background::LinearGradient {
+        start: (0f32, 0f32),
+        end: (SIZE as f32, SIZE as f32),
+        start_color: Color::rgb(0.1f32, 0.1f32, 0.4f32),
+        end_color: Color::rgb(0.9f32, 0.6f32, 0.2f32)
+    }).unwrap();
+    drawing.add_path(Path::ellipse((100f32, 128f32), 70f32, 70f32, 0f32)
+        .set_fill_color(1f32, 1f32, 1f32).set_opacity(0.5f32)).unwrap();
+    drawing.add_path(Path::ellipse((156f32, 128f32), 70f32, 70f32, 0f32)
+        .set_fill_color(0f32, 0f32, 0f32).set_opacity(0.5f32)).unwrap();
+}
+
+// How different two RGBA pixels look, from 0 to about 1. Both are blended over white first, then
+// compared in YIQ space where luminance differences count the most.
+fn pixel_delta(a: &[u8], b: &[u8]) -> f32 {
+    let yiq = |p: &[u8]| {
+        let alpha = p[3] as f32 / 255f32;
+        let blend = |c: u8| 255f32 + (c as f32 - 255f32) * alpha;
+        let (r, g, b) = (blend(p[0]), blend(p[1]), blend(p[2]));
+        (0.298_89f32 * r + 0.586_62f32 * g + 0.114_49f32 * b,
+         0.595_98f32 * r - 0.274_17f32 * g - 0.321_81f32 * b,
+         0.211_47f32 * r - 0.522_61f32 * g + 0.311_14f32 * b)
+    };
+    let (ya, ia, qa) = yiq(a);
+    let (yb, ib, qb) = yiq(b);
+    let (dy, di, dq) = (ya - yb, ia - ib, qa - qb);
+    // the largest possible difference, between two saturated colors
+    const MAX_DELTA: f32 = 35_215f32;
+    ((0.5053f32 * dy * dy + 0.299f32 * di * di + 0.1957f32 * dq * dq) / MAX_DELTA).sqrt()
+}
+
+// The number of pixels that differ, and an image of the expected one faded with the differing
+// pixels in red.
+fn compare(expected: &ImageData, actual: &ImageData) -> (usize, Vec<u8>) {
+    let mut different = 0;
+    let mut diff = Vec::with_capacity(expected.pixels().len());
+    for (e, a) in expected.pixels().chunks(4).zip(actual.pixels().chunks(4)) {
+        if pixel_delta(e, a) > PIXEL_THRESHOLD {
+            different += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let gray = 255 - (255 - e[0] / 3 - e[1] / 3 - e[2] / 3) / 4;
+            diff.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+    (different, diff)
+}
+
+fn read_png(path: &FilePath) -> Option<ImageData> {
+    let decoder = png::Decoder::new(File::open(path).ok()?);
+    let mut reader = decoder.read_info().ok()?;
+    let mut pixels = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).ok()?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return None;
+    }
+    pixels.truncate(info.buffer_size());
+    ImageData::new(info.width, info.height, pixels).ok()
+}
+
+fn write_png(path: &FilePath, width: u32, height: u32, pixels: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path).unwrap()), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().unwrap().write_image_data(pixels).unwrap();
+}
+
+// Compare a rendering with its reference, returns a description of the problem if they differ.
+fn check_golden(name: &str, actual: &ImageData) -> Option<String> {
+    let reference = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.png", name));
+    let expected = match read_png(&reference) {
+        Some(expected) if env::var_os("TRDL_UPDATE_GOLDEN").is_none() => expected,
+        _ => {
+            write_png(&reference, actual.width(), actual.height(), actual.pixels());
+            eprintln!("wrote reference image {}", reference.display());
+            return None;
+        }
+    };
+    if (expected.width(), expected.height()) != (actual.width(), actual.height()) {
+        return Some(format!("{}: rendered {}x{}, the reference is {}x{}", name, actual.width(),
+                            actual.height(), expected.width(), expected.height()));
+    }
+    let (different, diff) = compare(&expected, actual);
+    let total = (actual.width() * actual.height()) as usize;
+    if different as f32 <= MAX_DIFFERENT_FRACTION * total as f32 {
+        return None;
+    }
+    let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let actual_path = output.join(format!("{}.actual.png", name));
+    let diff_path = output.join(format!("{}.diff.png", name));
+    write_png(&actual_path, actual.width(), actual.height(), actual.pixels());
+    write_png(&diff_path, actual.width(), actual.height(), &diff);
+    Some(format!("{}: {} of {} pixels differ, see {} and {}", name, different, total,
+                 actual_path.display(), diff_path.display()))
+}
+
+#[test]
+#[ignore]
+fn test_golden_scenes() {
+    let event_loop = event_loop();
+    let window = make_window(&event_loop);
+    let scenes: [(&str, Scene); 3] = [
+        ("shapes", shapes_scene),
+        ("strokes", strokes_scene),
+        ("gradient", gradient_scene)
+    ];
+    let mut failures = Vec::new();
+    for &(name, scene) in &scenes {
+        let mut drawing = Drawing::new(&window, SIZE, SIZE, 1f32, 1f32, 1f32).unwrap();
+        scene(&mut drawing);
+        let image = drawing.render_to_image().unwrap();
+        failures.extend(check_golden(name, &image));
+    }
+    assert!(failures.is_empty(), "golden images differ:\n{}", failures.join("\n"));
+}
+
+#[test]
+#[ignore]
+fn test_pick_gpu() {
+    let event_loop = event_loop();
+    let window = make_window(&event_loop);
+    let mut drawing = Drawing::new(&window, SIZE, SIZE, 1f32, 1f32, 1f32).unwrap();
+    shapes_scene(&mut drawing);
+    // inside each shape and on the background, both ways of picking agree
+    for &point in &[(64f32, 64f32), (192f32, 64f32), (64f32, 192f32), (200f32, 200f32), (5f32, 250f32)] {
+        assert_eq!(drawing.pick_gpu(point), drawing.pick(point), "at {:?}", point);
+    }
+    assert_eq!(drawing.pick_gpu((5f32, 250f32)), None);
+}
+
+#[test]
+#[ignore]
+fn test_empty_drawing_lifecycle() {
+    let event_loop = event_loop();
+    let window = make_window(&event_loop);
+    let mut drawing = Drawing::new(&window, SIZE, SIZE, 0.5f32, 0.5f32, 0.5f32).unwrap();
+    // every pixel the same gray
+    let background = |image: &ImageData| {
+        let first = &image.pixels()[..4];
+        first[0] == first[1] && first[1] == first[2] && image.pixels().chunks(4).all(|pixel| pixel == first)
+    };
+    // nothing added yet, only the background is drawn
+    assert!(background(&drawing.render_to_image().unwrap()));
+    assert_eq!(drawing.pick_gpu((128f32, 128f32)), None);
+    assert_eq!(drawing.frame_stats().patches, 0);
+
+    // a path that was drawn and then removed leaves empty buffers behind
+    let id = drawing.add_path(Path::rectangle((128f32, 128f32), 100f32, 100f32, 0f32)
+        .set_fill_color(1f32, 0f32, 0f32)).unwrap();
+    assert!(!background(&drawing.render_to_image().unwrap()));
+    assert_eq!(drawing.pick_gpu((128f32, 128f32)), Some(id));
+    drawing.remove_path(id);
+    assert_eq!(drawing.pick_gpu((128f32, 128f32)), None);
+    drawing.draw();
+    drawing.draw_embedded();
+    assert!(background(&drawing.render_to_image().unwrap()));
+}
+
+#[test]
+fn test_compare_tolerates_small_differences() {
+    let white = ImageData::new(2, 1, vec![255u8; 8]).unwrap();
+    let off_white = ImageData::new(2, 1, vec![250, 250, 250, 255, 255, 255, 255, 255]).unwrap();
+    let black_pixel = ImageData::new(2, 1, vec![0, 0, 0, 255, 255, 255, 255, 255]).unwrap();
+    assert_eq!(compare(&white, &off_white).0, 0);
+    let (different, diff) = compare(&white, &black_pixel);
+    assert_eq!(different, 1);
+    assert_eq!(&diff[..4], &[255, 0, 0, 255]);
+    assert!(pixel_delta(&[0, 0, 0, 255], &[255, 255, 255, 255]) > 0.9f32);
+    // fully transparent pixels look the same whatever their color
+    assert_eq!(pixel_delta(&[0, 0, 0, 0], &[255, 0, 0, 0]), 0f32);
+}
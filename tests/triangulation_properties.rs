@@ -0,0 +1,123 @@
+//! Property tests for polygon triangulation: random simple polygons are triangulated and the
+//! result is checked against invariants every triangulation must satisfy.
+
+extern crate trdl;
+
+use std::f32::consts::PI;
+use trdl::Mesh;
+
+const CASES: u64 = 500;
+
+// Small repeatable random numbers, so a failing case can be rerun from its seed.
+struct XorShift {
+    state: u64
+}
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        XorShift { state: seed ^ 0x9E37_79B9_7F4A_7C15 | 1 }
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        (x >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn in_range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+// A random star shaped polygon, counter clockwise: a point at a random distance from the origin
+// in each of n equal sectors around it.
+fn random_polygon(rng: &mut XorShift, n: usize) -> Vec<(f32, f32)> {
+    let scale = rng.in_range(0.01f32, 1000f32);
+    let center = (rng.in_range(-100f32, 100f32), rng.in_range(-100f32, 100f32));
+    (0..n).map(|i| {
+        let angle = (i as f32 + rng.in_range(0.1f32, 0.9f32)) * 2f32 * PI / n as f32;
+        let radius = scale * rng.in_range(0.2f32, 1f32);
+        (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+    }).collect()
+}
+
+// Add repeated points and points halfway along edges, which must not change the triangulation.
+fn add_degenerate_points(rng: &mut XorShift, polygon: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut points = Vec::with_capacity(2 * polygon.len());
+    for (i, &p) in polygon.iter().enumerate() {
+        points.push(p);
+        let roll = rng.next_f32();
+        if roll < 0.1f32 {
+            points.push(p);
+        } else if roll < 0.2f32 {
+            let q = polygon[(i + 1) % polygon.len()];
+            points.push(((p.0 + q.0) / 2f32, (p.1 + q.1) / 2f32));
+        }
+    }
+    points
+}
+
+fn signed_area(polygon: &[(f32, f32)]) -> f64 {
+    (0..polygon.len()).map(|i| {
+        let (p, q) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        p.0 as f64 * q.1 as f64 - q.0 as f64 * p.1 as f64
+    }).sum::<f64>() / 2f64
+}
+
+// Even-odd test of whether a point is inside a polygon.
+fn contains(polygon: &[(f32, f32)], point: (f32, f32)) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.1 > point.1) != (b.1 > point.1) &&
+           point.0 < a.0 + (point.1 - a.1) / (b.1 - a.1) * (b.0 - a.0) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn check_triangulation(seed: u64, polygon: &[(f32, f32)], points: &[(f32, f32)], mesh: &Mesh) {
+    assert_eq!(mesh.triangle_count(), polygon.len() - 2, "seed {}: triangle count", seed);
+    assert!(mesh.indices.iter().all(|&i| i < points.len()), "seed {}: index out of range", seed);
+
+    let area = signed_area(polygon);
+    let mut total = 0f64;
+    for triangle in mesh.triangles() {
+        let triangle_area = signed_area(&triangle);
+        assert!(triangle_area >= -1e-6 * area, "seed {}: flipped triangle {:?}", seed, triangle);
+        total += triangle_area;
+        let centroid = ((triangle[0].0 + triangle[1].0 + triangle[2].0) / 3f32,
+                        (triangle[0].1 + triangle[1].1 + triangle[2].1) / 3f32);
+        if triangle_area > 1e-4 * area {
+            assert!(contains(polygon, centroid), "seed {}: triangle {:?} outside", seed, triangle);
+        }
+    }
+    assert!((total - area).abs() <= 1e-3 * area, "seed {}: area {} instead of {}", seed, total, area);
+}
+
+#[test]
+fn test_random_polygons() {
+    for seed in 0..CASES {
+        let mut rng = XorShift::new(seed);
+        let n = 3 + (rng.next_f32() * 60f32) as usize;
+        let polygon = random_polygon(&mut rng, n);
+        let mesh = Mesh::from_polygon(&polygon).unwrap_or_else(|e| panic!("seed {}: {}", seed, e));
+        check_triangulation(seed, &polygon, &polygon, &mesh);
+    }
+}
+
+#[test]
+fn test_random_polygons_with_degenerate_points() {
+    for seed in 0..CASES {
+        let mut rng = XorShift::new(seed);
+        let n = 3 + (rng.next_f32() * 60f32) as usize;
+        let polygon = random_polygon(&mut rng, n);
+        let points = add_degenerate_points(&mut rng, &polygon);
+        let mesh = Mesh::from_polygon(&points).unwrap_or_else(|e| panic!("seed {}: {}", seed, e));
+        check_triangulation(seed, &polygon, &points, &mesh);
+    }
+}